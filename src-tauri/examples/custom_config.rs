@@ -1,4 +1,4 @@
-use agents_rs_lib::llm::{LLMEngine, config::LLMConfig};
+use agents_rs_lib::llm::{ChatMessage, LLMEngine, config::LLMConfig};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -8,18 +8,18 @@ async fn main() -> anyhow::Result<()> {
     let mut config = LLMConfig::default();
     
     // Adjust generation parameters
-    config.temperature = 0.9;      // More creative (0.0 = deterministic, 1.0 = very creative)
-    config.top_p = 0.95;          // Nucleus sampling threshold
-    config.top_k = 50;            // Top-K sampling
-    config.repeat_penalty = 1.2;  // Higher = less repetition
-    config.max_tokens = 150;      // Longer responses
+    config.sampling.temperature = 0.9;      // More creative (0.0 = deterministic, 1.0 = very creative)
+    config.sampling.top_p = 0.95;          // Nucleus sampling threshold
+    config.sampling.top_k = 50;            // Top-K sampling
+    config.sampling.repeat_penalty = 1.2;  // Higher = less repetition
+    config.sampling.max_tokens = 150;      // Longer responses
     
     println!("Configuration:");
-    println!("  Temperature: {}", config.temperature);
-    println!("  Top-P: {}", config.top_p);
-    println!("  Top-K: {}", config.top_k);
-    println!("  Repeat Penalty: {}", config.repeat_penalty);
-    println!("  Max Tokens: {}\n", config.max_tokens);
+    println!("  Temperature: {}", config.sampling.temperature);
+    println!("  Top-P: {}", config.sampling.top_p);
+    println!("  Top-K: {}", config.sampling.top_k);
+    println!("  Repeat Penalty: {}", config.sampling.repeat_penalty);
+    println!("  Max Tokens: {}\n", config.sampling.max_tokens);
     
     // Initialize and load
     let engine = LLMEngine::new(config)?;
@@ -35,7 +35,7 @@ async fn main() -> anyhow::Result<()> {
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         println!("Prompt: {}\n", prompt);
         
-        let response = engine.generate(prompt).await?;
+        let response = engine.generate(&[ChatMessage::user(prompt)]).await?;
         println!("Response: {}\n", response.text);
         println!("Tokens: {}", response.tokens_generated);
         println!();
@@ -1,4 +1,4 @@
-use agents_rs_lib::llm::{LLMEngine, config::LLMConfig};
+use agents_rs_lib::llm::{ChatMessage, LLMEngine, config::{LLMConfig, ModelConfig, SamplingConfig}};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -7,18 +7,30 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     let config = LLMConfig {
-        model_path: "models/Qwen3-1.7B-IQ4_XS.gguf".to_string(),
-        n_ctx: 2048,
-        n_threads: 4,
-        temperature: 0.7,
-        top_p: 0.9,
-        top_k: 40,
-        repeat_penalty: 1.1,
-        max_tokens: 50,
-        context_size: 2048,  // Added missing field
-        use_gpu: false,      // Set to false since GPU features are disabled
-        n_gpu_layers: u32::MAX,  // Use maximum value for all GPU layers
-        main_gpu: 0,
+        model: ModelConfig {
+            model_path: "models/Qwen3-1.7B-IQ4_XS.gguf".to_string(),
+            n_ctx: 2048,
+            n_threads: 4,
+            context_size: 2048,  // Added missing field
+            use_gpu: false,      // Set to false since GPU features are disabled
+            n_gpu_layers: u32::MAX,  // Use maximum value for all GPU layers
+            main_gpu: 0,
+            context_shift: true,
+            prompt_cache_entries: 8,
+            prompt_cache_size_mb: 512,
+            mmproj_path: None,
+            ..Default::default()
+        },
+        sampling: SamplingConfig {
+            temperature: 0.7,
+            top_p: 0.9,
+            top_k: 40,
+            repeat_penalty: 1.1,
+            max_tokens: 50,
+            logprobs: None,
+            stop_conditions: Vec::new(),
+        },
+        ..Default::default()
     };
 
     println!("🚀 Loading model...");
@@ -40,7 +52,7 @@ async fn main() -> anyhow::Result<()> {
         
         let start = std::time::Instant::now();
         
-        match engine.generate(prompt).await {
+        match engine.generate(&[ChatMessage::user(*prompt)]).await {
             Ok(response) => {
                 let duration = start.elapsed();
                 println!("Response: {}", response.text);
@@ -1,4 +1,4 @@
-use agents_rs_lib::llm::{LLMEngine, config::LLMConfig};
+use agents_rs_lib::llm::{ChatMessage, LLMEngine, config::LLMConfig};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -18,7 +18,7 @@ async fn main() -> anyhow::Result<()> {
     let prompt = "What is Rust programming language?";
     println!("Prompt: {}\n", prompt);
     
-    let response = engine.generate(prompt).await?;
+    let response = engine.generate(&[ChatMessage::user(prompt)]).await?;
     
     println!("Response: {}", response.text);
     println!("\nTokens: {}", response.tokens_generated);
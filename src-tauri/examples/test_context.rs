@@ -1,4 +1,4 @@
-use agents_rs_lib::llm::{LLMEngine, config::LLMConfig};
+use agents_rs_lib::llm::{ChatMessage, LLMEngine, config::{LLMConfig, SamplingConfig}};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -6,7 +6,10 @@ async fn main() -> anyhow::Result<()> {
     println!("═════════════════════════════════════\n");
     
     let config = LLMConfig {
-        max_tokens: 100,
+        sampling: SamplingConfig {
+            max_tokens: 100,
+            ..SamplingConfig::default()
+        },
         ..LLMConfig::default()
     };
     
@@ -15,6 +18,9 @@ async fn main() -> anyhow::Result<()> {
     
     println!("✅ Model loaded\n");
     
+    // The engine is stateless: the conversation is kept here and sent on each turn
+    let mut history: Vec<ChatMessage> = Vec::new();
+    
     // Test 1: Set a name
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("Test 1: Setting context");
@@ -22,8 +28,10 @@ async fn main() -> anyhow::Result<()> {
     let prompt1 = "My name is Alice. Remember this.";
     println!("👤 User: {}\n", prompt1);
     
-    let response1 = engine.generate(prompt1).await?;
+    history.push(ChatMessage::user(prompt1));
+    let response1 = engine.generate(&history).await?;
     println!("🤖 Assistant: {}\n", response1.text);
+    history.push(ChatMessage::assistant(response1.text));
     
     // Test 2: Check if model remembers the name
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
@@ -32,8 +40,10 @@ async fn main() -> anyhow::Result<()> {
     let prompt2 = "What is my name?";
     println!("👤 User: {}\n", prompt2);
     
-    let response2 = engine.generate(prompt2).await?;
+    history.push(ChatMessage::user(prompt2));
+    let response2 = engine.generate(&history).await?;
     println!("🤖 Assistant: {}\n", response2.text);
+    history.push(ChatMessage::assistant(response2.text.clone()));
     
     // Check if the response mentions "Alice"
     if response2.text.contains("Alice") {
@@ -46,19 +56,20 @@ async fn main() -> anyhow::Result<()> {
     println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("📜 Full conversation history:");
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    println!("{}", engine.get_conversation_history().await);
+    println!("{}", LLMEngine::format_chat_prompt(&history));
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
     
     // Test 3: Clear and verify
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     println!("Test 3: Clearing context");
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    engine.clear_conversation().await;
+    history.clear();
     
     let prompt3 = "What is my name?";
     println!("👤 User: {}\n", prompt3);
     
-    let response3 = engine.generate(prompt3).await?;
+    history.push(ChatMessage::user(prompt3));
+    let response3 = engine.generate(&history).await?;
     println!("🤖 Assistant: {}\n", response3.text);
     
     if !response3.text.contains("Alice") {
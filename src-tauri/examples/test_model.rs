@@ -1,4 +1,4 @@
-use agents_rs_lib::llm::{LLMEngine, config::LLMConfig};
+use agents_rs_lib::llm::{ChatMessage, LLMEngine, config::LLMConfig};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -12,13 +12,13 @@ async fn main() -> anyhow::Result<()> {
 
     println!("\n🤖 Initializing LLM engine...");
     println!("📋 Configuration:");
-    println!("   - Temperature: {}", config.temperature);
-    println!("   - Top-P: {}", config.top_p);
-    println!("   - Top-K: {}", config.top_k);
-    println!("   - Repeat Penalty: {}", config.repeat_penalty);
-    println!("   - Max Tokens: {}", config.max_tokens);
-    println!("   - Context Size: {}", config.n_ctx);
-    println!("   - Threads: {}\n", config.n_threads);
+    println!("   - Temperature: {}", config.sampling.temperature);
+    println!("   - Top-P: {}", config.sampling.top_p);
+    println!("   - Top-K: {}", config.sampling.top_k);
+    println!("   - Repeat Penalty: {}", config.sampling.repeat_penalty);
+    println!("   - Max Tokens: {}", config.sampling.max_tokens);
+    println!("   - Context Size: {}", config.model.n_ctx);
+    println!("   - Threads: {}\n", config.model.n_threads);
     let engine = LLMEngine::new(config)?;
     
     println!("📦 Loading model...");
@@ -30,6 +30,9 @@ async fn main() -> anyhow::Result<()> {
     // Interactive mode
     use std::io::{self, Write};
     
+    // The engine is stateless: the conversation is kept here and sent on each turn
+    let mut history: Vec<ChatMessage> = Vec::new();
+    
     loop {
         print!("💬 Your prompt (or 'quit'/'clear'/'history'): ");
         io::stdout().flush()?;
@@ -49,19 +52,20 @@ async fn main() -> anyhow::Result<()> {
         }
         
         if prompt.eq_ignore_ascii_case("clear") {
-            engine.clear_conversation().await;
+            history.clear();
             println!("\n🧹 Conversation history cleared!\n");
             continue;
         }
         
         if prompt.eq_ignore_ascii_case("history") {
-            let history = engine.get_conversation_history().await;
             println!("\n📜 Conversation History:");
             println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
             if history.is_empty() {
                 println!("(empty)");
             } else {
-                println!("{}", history);
+                for message in &history {
+                    println!("[{}] {}", message.role.as_str(), message.content);
+                }
             }
             println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
             continue;
@@ -69,19 +73,19 @@ async fn main() -> anyhow::Result<()> {
         
         println!("\n🔄 Generating response...\n");
         
-        match engine.generate(prompt).await {
+        history.push(ChatMessage::user(prompt));
+        match engine.generate(&history).await {
             Ok(response) => {
                 println!("🤖 Response: {}", response.text);
                 println!("\n📊 Tokens generated: {}", response.tokens_generated);
                 
-                // Show conversation history token count
-                let history = engine.get_conversation_history().await;
-                let history_lines = history.lines().count();
-                println!("💬 Conversation turns: {}", history_lines / 3); // Each turn has 3 lines in format
+                history.push(ChatMessage::assistant(response.text));
+                println!("💬 Conversation turns: {}", history.len() / 2);
                 
                 println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
             }
             Err(e) => {
+                history.pop();
                 eprintln!("❌ Error: {}", e);
             }
         }
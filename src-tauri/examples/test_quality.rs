@@ -1,4 +1,4 @@
-use agents_rs_lib::llm::{LLMEngine, config::LLMConfig};
+use agents_rs_lib::llm::{ChatMessage, LLMEngine, config::{LLMConfig, SamplingConfig}};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -6,7 +6,10 @@ async fn main() -> anyhow::Result<()> {
     println!("════════════════════════════════════\n");
     
     let config = LLMConfig {
-        max_tokens: 100,  // Shorter responses for testing
+        sampling: SamplingConfig {
+            max_tokens: 100,  // Shorter responses for testing
+            ..SamplingConfig::default()
+        },
         ..LLMConfig::default()
     };
     let engine = LLMEngine::new(config)?;
@@ -24,7 +27,7 @@ async fn main() -> anyhow::Result<()> {
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         println!("📝 Prompt: {}\n", prompt);
         
-        let response = engine.generate(prompt).await?;
+        let response = engine.generate(&[ChatMessage::user(*prompt)]).await?;
         
         println!("🤖 Response:\n{}\n", response.text);
         println!("📊 Tokens: {} | Quality: ✓ Coherent\n", response.tokens_generated);
@@ -0,0 +1,268 @@
+/// Mode sans fenêtre : conversation, génération, téléchargement et export depuis le terminal
+///
+/// Utilise le même état que l'application (base, réglages, dossier des modèles),
+/// ce qui permet de scripter agents-rs sur un serveur. Les réponses sont écrites
+/// sur la sortie standard, les logs et la progression sur la sortie d'erreur.
+
+use agents_rs_lib::commands::{download_hub_file, exchange, load_last_model, load_model_by_name, DEFAULT_SESSION_TITLE};
+use agents_rs_lib::context::{ConversationSession, MessageRole};
+use agents_rs_lib::llm::{ChatMessage, LLMResponse};
+use agents_rs_lib::events::{AppEvent, EventSink, LlmToken};
+use agents_rs_lib::{logging, AppConfig, AppState};
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+/// Niveau des logs si RUST_LOG n'est pas défini : seuls les problèmes s'affichent
+const CLI_LOG_LEVEL: &str = "warn";
+
+const USAGE: &str = "\
+Usage: agent-cli <command> [--profile NAME] [options]
+
+Commands:
+  chat [--session ID] [--model FILE]            Interactive chat (one message per line, /quit to exit)
+  generate [--model FILE] [--system TEXT] PROMPT  One-shot generation, not saved
+  download REPO_ID FILE [--revision REV]        Download a GGUF file from the HuggingFace Hub
+  sessions                                      List saved sessions
+  export SESSION_ID [--format json|markdown] [--output PATH]
+                                                Export a session (stdout by default)
+
+Without --model, the last model used by the application is loaded.
+Without --profile, the profile selected in the application is used.";
+
+fn main() {
+    let log_handle = logging::init_stderr(logging::default_log_directory(), CLI_LOG_LEVEL);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((command, rest)) = args.split_first() else {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    };
+    if matches!(command.as_str(), "-h" | "--help" | "help") {
+        println!("{}", USAGE);
+        return;
+    }
+
+    if let Err(e) = run(command, CliArgs::parse(rest), log_handle) {
+        eprintln!("Error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(command: &str, args: CliArgs, log_handle: logging::LogHandle) -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    // Les tâches lancées par la bibliothèque (titre, souvenirs) tournent sur ce runtime
+    tauri::async_runtime::set(runtime.handle().clone());
+    runtime.block_on(async {
+        let mut config = AppConfig::new().with_log_handle(log_handle.clone());
+        if let Some(profile) = args.option("profile") {
+            config = config.with_profile(profile);
+        }
+        let state = Arc::new(AppState::initialize(config).await?);
+        // Le niveau enregistré dans l'application ne s'applique pas au terminal
+        if std::env::var_os("RUST_LOG").is_none() {
+            log_handle.set_level(CLI_LOG_LEVEL)?;
+        }
+
+        let result = match command {
+            "chat" => chat(&state, &args).await,
+            "generate" => generate(&state, &args).await,
+            "download" => download(&state, &args).await,
+            "sessions" => list_sessions(&state).await,
+            "export" => export(&state, &args).await,
+            _ => Err(anyhow::anyhow!("Unknown command: {}\n\n{}", command, USAGE)),
+        };
+        agents_rs_lib::shutdown(&state).await;
+        result
+    })
+}
+
+/// Arguments positionnels et options `--nom valeur`
+struct CliArgs {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl CliArgs {
+    fn parse(args: &[String]) -> Self {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    options.insert(name.to_string(), args.next().cloned().unwrap_or_default());
+                }
+                None => positional.push(arg.clone()),
+            }
+        }
+        Self { positional, options }
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str).filter(|value| !value.is_empty())
+    }
+
+    fn positional(&self, index: usize, name: &str) -> Result<&str> {
+        match self.positional.get(index) {
+            Some(value) => Ok(value),
+            None => bail!("Missing {}\n\n{}", name, USAGE),
+        }
+    }
+}
+
+/// Charge le modèle demandé, ou le dernier modèle utilisé
+async fn load_model(state: &AppState, model: Option<&str>) -> Result<()> {
+    let model = match model {
+        Some(model) => {
+            load_model_by_name(state, model).await?;
+            model.to_string()
+        }
+        None => load_last_model(state).await?,
+    };
+    eprintln!("Model: {}", model);
+    Ok(())
+}
+
+/// Génère une réponse en l'écrivant au fil de l'eau sur la sortie standard
+async fn stream_reply(state: &AppState, messages: Vec<ChatMessage>) -> Result<LLMResponse> {
+    let response = state.llm_engine.generate_stream(messages, |chunk| {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(chunk.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }).await?;
+    println!();
+    Ok(response)
+}
+
+async fn chat(state: &Arc<AppState>, args: &CliArgs) -> Result<()> {
+    load_model(state, args.option("model")).await?;
+
+    let session_id = match args.option("session") {
+        Some(id) => id.to_string(),
+        None => {
+            let context_manager = state.context_manager.read().await;
+            context_manager.create_session(DEFAULT_SESSION_TITLE.to_string()).await?
+        }
+    };
+    eprintln!("Session: {} (/quit to exit)", session_id);
+
+    let stdin = io::stdin();
+    loop {
+        eprint!("> ");
+        io::stderr().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let content = line.trim();
+        if content.is_empty() {
+            continue;
+        }
+        if content == "/quit" {
+            break;
+        }
+
+        // Même chemin que l'application : documents, connaissances, souvenirs, titre
+        exchange(print_reply(), state, session_id.clone(), content.to_string(), None, true).await?;
+        println!();
+    }
+    Ok(())
+}
+
+/// Écrit la réponse diffusée sur la sortie standard
+fn print_reply() -> EventSink {
+    EventSink::Callback(Arc::new(|name, event| {
+        if name != LlmToken::NAME {
+            return;
+        }
+        if let Some(chunk) = event["chunk"].as_str() {
+            let mut stdout = io::stdout().lock();
+            let _ = stdout.write_all(chunk.as_bytes());
+            let _ = stdout.flush();
+        }
+    }))
+}
+
+async fn generate(state: &AppState, args: &CliArgs) -> Result<()> {
+    let prompt = args.positional.join(" ");
+    if prompt.trim().is_empty() {
+        bail!("Missing prompt\n\n{}", USAGE);
+    }
+    load_model(state, args.option("model")).await?;
+
+    let mut messages = Vec::new();
+    if let Some(system) = args.option("system") {
+        messages.push(ChatMessage::system(system));
+    }
+    messages.push(ChatMessage::user(prompt));
+    stream_reply(state, messages).await?;
+    Ok(())
+}
+
+async fn download(state: &AppState, args: &CliArgs) -> Result<()> {
+    let repo_id = args.positional(0, "REPO_ID")?;
+    let filename = args.positional(1, "FILE")?;
+
+    let mut last_percent = None;
+    let path = download_hub_file(state, repo_id, filename, args.option("revision"), |downloaded, total| {
+        // Une ligne par pourcent, la progression arrivant par petits morceaux
+        if let Some(total) = total.filter(|total| *total > 0) {
+            let percent = downloaded * 100 / total;
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                eprint!("\r{}: {}% ({} / {} MB)", filename, percent, downloaded / 1_000_000, total / 1_000_000);
+            }
+        }
+    }).await?;
+    eprintln!();
+    println!("{}", path);
+    Ok(())
+}
+
+async fn list_sessions(state: &AppState) -> Result<()> {
+    let context_manager = state.context_manager.read().await;
+    for session in context_manager.list_sessions().await? {
+        println!("{}\t{}\t{}", session.id, session.updated_at.to_rfc3339(), session.title);
+    }
+    Ok(())
+}
+
+async fn export(state: &AppState, args: &CliArgs) -> Result<()> {
+    let session_id = args.positional(0, "SESSION_ID")?;
+    let session = {
+        let context_manager = state.context_manager.read().await;
+        context_manager.get_session(session_id).await?
+    };
+
+    let content = match args.option("format").unwrap_or("json") {
+        "json" => serde_json::to_string_pretty(&session)?,
+        "markdown" | "md" => to_markdown(&session),
+        format => bail!("Unknown format: {} (json or markdown)", format),
+    };
+    match args.option("output") {
+        Some(path) => {
+            std::fs::write(path, content).with_context(|| format!("Failed to write {}", path))?;
+            eprintln!("Session exported to {}", path);
+        }
+        None => println!("{}", content),
+    }
+    Ok(())
+}
+
+/// Conversation lisible : un titre par message, le raisonnement n'est pas repris
+fn to_markdown(session: &ConversationSession) -> String {
+    let mut markdown = format!("# {}\n", session.title);
+    for message in &session.messages {
+        let role = match message.role {
+            MessageRole::System => "System",
+            MessageRole::User => "User",
+            MessageRole::Assistant => "Assistant",
+            MessageRole::Tool => "Tool",
+        };
+        markdown.push_str(&format!("\n## {}\n\n{}\n", role, message.content.trim()));
+    }
+    markdown
+}
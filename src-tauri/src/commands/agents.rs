@@ -0,0 +1,85 @@
+/// Commandes Tauri pour les profils d'agents (persona, outils, paramètres)
+
+use crate::SharedState;
+use crate::context::AgentProfile;
+use crate::error::{AppError, AppResult};
+use crate::llm::{GuardrailPolicy, SamplingParams};
+use tauri::State;
+use tracing::info;
+
+#[tauri::command]
+pub async fn create_agent(
+    state: State<'_, SharedState>,
+    name: String,
+    system_prompt: String,
+    default_model: Option<String>,
+    allowed_tools: Option<Vec<String>>,
+    sampling: Option<SamplingParams>,
+    guardrails: Option<GuardrailPolicy>,
+) -> AppResult<AgentProfile> {
+    let state = state.get();
+    if name.trim().is_empty() {
+        return Err(AppError::invalid_input("Le nom de l'agent est vide"));
+    }
+    validate_guardrails(guardrails.as_ref())?;
+    
+    let mut agent = AgentProfile::new(name, system_prompt);
+    agent.default_model = default_model;
+    agent.allowed_tools = allowed_tools.unwrap_or_default();
+    agent.sampling = sampling.unwrap_or_default();
+    agent.guardrails = guardrails;
+    
+    state.agent_repo.save(&agent).await.map_err(AppError::from)?;
+    info!("Agent créé: {}", agent.name);
+    Ok(agent)
+}
+
+#[tauri::command]
+pub async fn list_agents(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<AgentProfile>> {
+    let state = state.get();
+    state.agent_repo.list().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_agent(
+    state: State<'_, SharedState>,
+    agent_id: String,
+) -> AppResult<Option<AgentProfile>> {
+    let state = state.get();
+    state.agent_repo.get(&agent_id).await.map_err(AppError::from)
+}
+
+/// Met à jour un agent existant (les conversations déjà créées gardent leur prompt)
+#[tauri::command]
+pub async fn update_agent(
+    state: State<'_, SharedState>,
+    agent: AgentProfile,
+) -> AppResult<AgentProfile> {
+    let state = state.get();
+    validate_guardrails(agent.guardrails.as_ref())?;
+    let existing = state.agent_repo.get(&agent.id).await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::invalid_input(format!("Agent introuvable: {}", agent.id)))?;
+    
+    let agent = AgentProfile { created_at: existing.created_at, ..agent };
+    state.agent_repo.save(&agent).await.map_err(AppError::from)?;
+    Ok(agent)
+}
+
+#[tauri::command]
+pub async fn delete_agent(
+    state: State<'_, SharedState>,
+    agent_id: String,
+) -> AppResult<bool> {
+    let state = state.get();
+    state.agent_repo.delete(&agent_id).await.map_err(AppError::from)
+}
+
+fn validate_guardrails(guardrails: Option<&GuardrailPolicy>) -> AppResult<()> {
+    match guardrails {
+        Some(policy) => policy.validate().map_err(AppError::invalid_input),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,55 @@
+/// Commandes Tauri des jetons d'accès au serveur local
+///
+/// Le jeton n'est renvoyé qu'à sa création ; ensuite seuls son nom et sa portée
+/// sont consultables.
+
+use crate::SharedState;
+use crate::context::{ApiScope, ApiToken};
+use crate::error::{AppError, AppResult};
+use serde::Serialize;
+use tauri::State;
+use tracing::info;
+use ts_rs::TS;
+
+/// Jeton émis et sa valeur, à copier immédiatement
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct IssuedApiToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    pub value: String,
+}
+
+/// Émet un jeton ; le serveur exige un jeton dès que le premier existe
+#[tauri::command]
+pub async fn create_api_token(
+    state: State<'_, SharedState>,
+    name: String,
+    scope: ApiScope,
+) -> AppResult<IssuedApiToken> {
+    let state = state.get();
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("Le nom du jeton est vide"));
+    }
+    let (token, value) = state.api_token_repo.create(name, scope).await
+        .map_err(AppError::from)?;
+    info!("Jeton d'API émis: {} ({:?})", token.name, token.scope);
+    Ok(IssuedApiToken { token, value })
+}
+
+#[tauri::command]
+pub async fn list_api_tokens(state: State<'_, SharedState>) -> AppResult<Vec<ApiToken>> {
+    let state = state.get();
+    state.api_token_repo.list().await.map_err(AppError::from)
+}
+
+/// Révoque un jeton (false s'il n'existait pas)
+#[tauri::command]
+pub async fn revoke_api_token(
+    state: State<'_, SharedState>,
+    id: String,
+) -> AppResult<bool> {
+    let state = state.get();
+    state.api_token_repo.revoke(&id).await.map_err(AppError::from)
+}
@@ -0,0 +1,337 @@
+/// Commandes Tauri pour les pièces jointes des messages
+///
+/// Les fichiers sont envoyés par l'interface encodés en base64 et stockés dans la
+/// table `attachments`. Les images sont transmises aux modèles de vision, le texte
+/// des documents (txt, md, pdf, docx) est ajouté au message : en entier pour les
+/// petits documents, sinon les extraits les plus proches de la question.
+
+use crate::{AppState, SharedState};
+use crate::context::documents::{self, Citation, DocumentFormat};
+use crate::context::knowledge::{self, KnowledgeChunk};
+use crate::context::retrieval;
+use crate::context::{Attachment, AttachmentKind, Message};
+use crate::error::{AppError, AppResult};
+use crate::llm::{Capability, ChatMessage, GenerationPriority};
+use crate::mcp::injection;
+use super::memories::embed_one;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Deserialize;
+use tauri::State;
+use tracing::{info, warn};
+use ts_rs::TS;
+
+/// Taille maximale d'une pièce jointe (20 Mo)
+pub const MAX_ATTACHMENT_SIZE: usize = 20 * 1024 * 1024;
+
+/// Fichier joint à un message par l'interface
+#[derive(Debug, Clone, Deserialize, TS)]
+#[ts(export)]
+pub struct AttachmentUpload {
+    pub file_name: String,
+    pub mime_type: String,
+    /// Contenu encodé en base64
+    pub data: String,
+}
+
+/// Décode les fichiers envoyés avec un message et extrait le texte des documents
+///
+/// Les images ne sont acceptées que si le modèle chargé sait les lire.
+pub(crate) async fn decode_uploads(
+    state: &AppState,
+    uploads: Vec<AttachmentUpload>,
+) -> AppResult<Vec<Attachment>> {
+    if uploads.iter().any(|u| u.mime_type.starts_with("image/")) {
+        if let Some(capabilities) = state.llm_engine.capabilities().await {
+            capabilities.require(Capability::Vision).map_err(AppError::from)?;
+        }
+    }
+
+    // Décodage et extraction du texte (PDF, DOCX) hors du runtime asynchrone
+    tokio::task::spawn_blocking(move || uploads.into_iter().map(decode_upload).collect())
+        .await
+        .map_err(|e| AppError::Internal { message: e.to_string() })?
+}
+
+fn decode_upload(upload: AttachmentUpload) -> AppResult<Attachment> {
+    let data = BASE64
+        .decode(upload.data.as_bytes())
+        .map_err(|_| AppError::invalid_input(format!("Contenu base64 invalide: {}", upload.file_name)))?;
+    if data.len() > MAX_ATTACHMENT_SIZE {
+        return Err(AppError::invalid_input(format!(
+            "{} dépasse la taille maximale ({} Mo)",
+            upload.file_name,
+            MAX_ATTACHMENT_SIZE / (1024 * 1024)
+        )));
+    }
+
+    if upload.mime_type.starts_with("image/") {
+        return Ok(Attachment::image(upload.file_name, upload.mime_type, data));
+    }
+
+    let format = DocumentFormat::detect(&upload.file_name, &upload.mime_type).ok_or_else(|| {
+        AppError::invalid_input(format!("Type de fichier non pris en charge: {}", upload.file_name))
+    })?;
+    let text = documents::extract_text(format, &data)
+        .map_err(|e| AppError::invalid_input(format!("{}: {}", upload.file_name, e)))?;
+    Ok(Attachment::document(upload.file_name, upload.mime_type, data, text))
+}
+
+/// Découpe et indexe les documents trop longs pour être inclus en entier
+///
+/// Sans modèle chargé, les extraits sont enregistrés sans embedding (les premiers
+/// extraits seront alors utilisés).
+pub(crate) async fn index_documents(state: &AppState, attachments: &[Attachment]) -> AppResult<()> {
+    for attachment in attachments {
+        let Some(text) = attachment.text.as_deref() else {
+            continue;
+        };
+        if text.chars().count() <= documents::MAX_INLINE_CHARS {
+            continue;
+        }
+        index_document(state, attachment, text).await?;
+    }
+    Ok(())
+}
+
+/// Découpe un document, calcule l'embedding de ses extraits et enregistre l'état de l'index
+pub(crate) async fn index_document(state: &AppState, attachment: &Attachment, text: &str) -> AppResult<()> {
+    let mut chunks = documents::chunk_document(text, documents::CHUNK_CHARS);
+    let contents: Vec<String> = chunks.iter().map(|chunk| chunk.content.clone()).collect();
+    let embedded = state.generation_scheduler
+        .embed(&state.llm_engine, contents.clone(), GenerationPriority::High).await;
+    let embeddings = match embedded {
+        Ok(embeddings) => Some(embeddings),
+        Err(e) => {
+            warn!("Document {} indexé sans embeddings: {:#}", attachment.file_name, e);
+            None
+        }
+    };
+    let tokens = state.llm_engine.count_tokens(contents).await
+        .ok()
+        .map(|counts| counts.iter().sum());
+    let embedding_model = embeddings.as_ref().map(|_| model_file_name(&state.llm_engine.config().model.model_path));
+    if let Some(embeddings) = embeddings {
+        for (chunk, embedding) in chunks.iter_mut().zip(embeddings) {
+            chunk.embedding = Some(embedding);
+        }
+    }
+
+    let context_manager = state.context_manager.read().await;
+    context_manager.save_attachment_chunks(&attachment.id, &chunks).await
+        .map_err(AppError::from)?;
+    context_manager.save_document_index(&attachment.id, tokens, embedding_model.as_deref()).await
+        .map_err(AppError::from)?;
+    info!("Document {} découpé en {} extraits", attachment.file_name, chunks.len());
+    Ok(())
+}
+
+/// Nom du fichier d'un modèle, sans son dossier
+fn model_file_name(model_path: &str) -> String {
+    std::path::Path::new(model_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| model_path.to_string())
+}
+
+/// Ajoute le texte des documents joints à leurs messages
+///
+/// `messages` doit correspondre un à un à `session_messages` (avant l'injection
+/// des souvenirs). Pour les longs documents, seuls les extraits les plus proches
+/// de `query` sont inclus. Chaque document ou extrait est numéroté : les sources
+/// renvoyées permettent de retrouver celles que la réponse cite. Les extraits
+/// signalés sont consignés dans le journal d'audit au nom de `audit_scope`
+/// (None pour un simple aperçu du contexte).
+pub(crate) async fn include_documents(
+    state: &AppState,
+    audit_scope: Option<&str>,
+    session_messages: &[Message],
+    messages: &mut [ChatMessage],
+    query: &str,
+) -> AppResult<Vec<Citation>> {
+    let mut query_embedding: Option<Option<Vec<f32>>> = None;
+    let retrieval = state.settings_repo.get_retrieval_settings().await
+        .map_err(AppError::from)?;
+    let screen = state.tool_registry.read().await.injection_settings().enabled;
+    let mut sources: Vec<Citation> = Vec::new();
+    let mut last_with_documents = None;
+
+    for (position, (message, chat_message)) in session_messages.iter().zip(messages.iter_mut()).enumerate() {
+        for attachment in message.attachments.iter().filter(|a| a.kind == AttachmentKind::Document) {
+            let Some(text) = attachment.text.as_deref() else {
+                continue;
+            };
+            let first_source = sources.len() + 1;
+            let block = if text.chars().count() <= documents::MAX_INLINE_CHARS {
+                sources.push(Citation::document(first_source, &attachment.id, &attachment.file_name, text));
+                documents::document_block(&attachment.file_name, text, first_source)
+            } else {
+                let chunks = {
+                    let context_manager = state.context_manager.read().await;
+                    context_manager.attachment_chunks(&attachment.id).await
+                        .map_err(AppError::from)?
+                };
+                if query_embedding.is_none() {
+                    query_embedding = Some(embed_one(state, query).await);
+                }
+                let embedding = query_embedding.as_ref().and_then(|e| e.as_deref());
+                let selected = documents::select_chunks(chunks, embedding, query, &retrieval, documents::MAX_DOCUMENT_CHUNKS);
+                for (i, chunk) in selected.iter().enumerate() {
+                    sources.push(Citation::chunk(first_source + i, &attachment.id, &attachment.file_name, text, chunk));
+                }
+                documents::excerpts_block(&attachment.file_name, &selected, first_source)
+            };
+            let findings = if screen { injection::detect(&block) } else { Vec::new() };
+            let block = if findings.is_empty() {
+                block
+            } else {
+                warn!(
+                    "Injection de prompt possible dans le document {} ({}): {:?}",
+                    attachment.file_name, injection::rule_names(&findings), findings
+                );
+                let source = format!("document:{}", attachment.file_name);
+                if let Some(scope) = audit_scope {
+                    injection::record(Some(&state.tool_audit_repo), scope, &source, "tagged", &findings).await;
+                }
+                injection::tag(&source, &block, &findings)
+            };
+            chat_message.content = format!("{}\n\n{}", chat_message.content, block);
+            last_with_documents = Some(position);
+        }
+    }
+
+    if let Some(position) = last_with_documents {
+        let chat_message = &mut messages[position];
+        chat_message.content = format!("{}\n\n{}", chat_message.content, documents::CITATION_INSTRUCTION);
+    }
+    Ok(sources)
+}
+
+/// Ajoute au dernier message les extraits du dossier de la base de connaissances
+/// de l'espace de travail les plus proches de la question
+///
+/// Les extraits sont numérotés à la suite des documents joints (`sources`).
+pub(crate) async fn include_knowledge(
+    state: &AppState,
+    audit_scope: Option<&str>,
+    workspace_id: Option<&str>,
+    messages: &mut [ChatMessage],
+    query: &str,
+    mut sources: Vec<Citation>,
+) -> AppResult<Vec<Citation>> {
+    let Some(workspace_id) = workspace_id else {
+        return Ok(sources);
+    };
+    let Some(folder_id) = state.workspace_repo.get(workspace_id).await
+        .map_err(AppError::from)?
+        .and_then(|workspace| workspace.index_id) else {
+        return Ok(sources);
+    };
+    let Some(folder) = state.knowledge_repo.get_folder(&folder_id).await.map_err(AppError::from)? else {
+        warn!("Dossier de la base de connaissances introuvable: {}", folder_id);
+        return Ok(sources);
+    };
+    let chunks = state.knowledge_repo.chunks(&folder.id).await
+        .map_err(AppError::from)?;
+    let Some(chat_message) = messages.last_mut() else {
+        return Ok(sources);
+    };
+    if chunks.is_empty() {
+        return Ok(sources);
+    }
+
+    // Correspondances exactes (FTS5) et proximité des embeddings, fusionnées
+    let retrieval = state.settings_repo.get_retrieval_settings().await
+        .map_err(AppError::from)?;
+    let lexical = state.knowledge_repo.search(&folder.id, query, chunks.len()).await
+        .map_err(AppError::from)?;
+    let embedding = embed_one(state, query).await;
+    let selected = match &folder.reranker_model {
+        Some(reranker) => {
+            let candidates = knowledge::select(chunks, embedding.as_deref(), &lexical, &retrieval, retrieval::RERANK_CANDIDATES);
+            let mut reranked = rerank_chunks(state, reranker, query, candidates).await;
+            reranked.truncate(knowledge::MAX_KNOWLEDGE_CHUNKS);
+            reranked
+        }
+        None => knowledge::select(chunks, embedding.as_deref(), &lexical, &retrieval, knowledge::MAX_KNOWLEDGE_CHUNKS),
+    };
+    let first_source = sources.len() + 1;
+    let block = knowledge::knowledge_block(&folder, &selected, first_source);
+    let screen = state.tool_registry.read().await.injection_settings().enabled;
+    let findings = if screen { injection::detect(&block) } else { Vec::new() };
+    let block = if findings.is_empty() {
+        block
+    } else {
+        warn!(
+            "Injection de prompt possible dans la base de connaissances {} ({}): {:?}",
+            folder.path, injection::rule_names(&findings), findings
+        );
+        let source = format!("knowledge_base:{}", folder.path);
+        if let Some(scope) = audit_scope {
+            injection::record(Some(&state.tool_audit_repo), scope, &source, "tagged", &findings).await;
+        }
+        injection::tag(&source, &block, &findings)
+    };
+    chat_message.content = format!("{}\n\n{}", chat_message.content, block);
+    // Sans document joint, la consigne de citation n'a pas encore été ajoutée
+    if sources.is_empty() {
+        chat_message.content = format!("{}\n\n{}", chat_message.content, documents::CITATION_INSTRUCTION);
+    }
+    sources.extend(selected.iter().enumerate().map(|(i, chunk)| chunk.citation(first_source + i)));
+    Ok(sources)
+}
+
+/// Réordonne les extraits avec le reranker d'un dossier ; en cas d'échec,
+/// l'ordre de la recherche hybride est conservé
+async fn rerank_chunks(
+    state: &AppState,
+    model_name: &str,
+    query: &str,
+    chunks: Vec<KnowledgeChunk>,
+) -> Vec<KnowledgeChunk> {
+    let path = state.model_manager.get_model_path(model_name).to_string_lossy().to_string();
+    let passages: Vec<String> = chunks.iter().map(|chunk| chunk.content.clone()).collect();
+    let scores = match state.llm_engine.load_reranker(path).await {
+        Ok(()) => state.llm_engine.rerank(query.to_string(), passages).await,
+        Err(e) => Err(e),
+    };
+    match scores {
+        Ok(scores) => retrieval::by_score(chunks, &scores),
+        Err(e) => {
+            warn!("Reranker {} indisponible, ordre de la recherche hybride conservé: {:#}", model_name, e);
+            chunks
+        }
+    }
+}
+
+/// Contenu d'une pièce jointe sous forme d'URL `data:` (affichage dans l'interface)
+#[tauri::command]
+pub async fn get_attachment_data(
+    state: State<'_, SharedState>,
+    attachment_id: String,
+) -> AppResult<String> {
+    let state = state.get();
+    let attachment = {
+        let context_manager = state.context_manager.read().await;
+        context_manager.get_attachment(&attachment_id).await
+            .map_err(AppError::from)?
+    };
+    let attachment = attachment
+        .ok_or_else(|| AppError::invalid_input(format!("Pièce jointe introuvable: {}", attachment_id)))?;
+
+    Ok(format!("data:{};base64,{}", attachment.mime_type, BASE64.encode(&attachment.data)))
+}
+
+/// Texte extrait d'un document joint
+#[tauri::command]
+pub async fn get_attachment_text(
+    state: State<'_, SharedState>,
+    attachment_id: String,
+) -> AppResult<Option<String>> {
+    let state = state.get();
+    let context_manager = state.context_manager.read().await;
+    let attachment = context_manager.get_attachment(&attachment_id).await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::invalid_input(format!("Pièce jointe introuvable: {}", attachment_id)))?;
+    Ok(attachment.text)
+}
@@ -0,0 +1,174 @@
+/// Commandes Tauri pour la sélection du backend de génération (local ou distant)
+/// et les informations sur le moteur llama.cpp intégré
+
+use crate::{AppState, SharedState};
+use crate::error::{AppError, AppResult};
+use crate::llm::{
+    AnthropicBackend, BackendInfo, BackendKind, LLMBackend, OpenAICompatibleBackend, RemoteBackendConfig,
+    SamplingParams,
+};
+use crate::llm::remote::{ANTHROPIC_DEFAULT_BASE_URL, OPENAI_DEFAULT_BASE_URL};
+use tauri::State;
+use tracing::info;
+
+/// Build the remote backend configured for a session, with its sampling overrides
+pub(crate) async fn remote_backend(
+    state: &AppState,
+    kind: &BackendKind,
+    sampling: Option<&SamplingParams>,
+) -> AppResult<Box<dyn LLMBackend>> {
+    match kind {
+        BackendKind::Local => Err(AppError::invalid_input("The local engine is not a remote backend")),
+        BackendKind::OpenAI { model } => {
+            let config = remote_config(state, kind, model, OPENAI_DEFAULT_BASE_URL, sampling).await?;
+            let backend = OpenAICompatibleBackend::new(config).map_err(AppError::from)?;
+            Ok(Box::new(backend))
+        }
+        BackendKind::Anthropic { model } => {
+            let config = remote_config(state, kind, model, ANTHROPIC_DEFAULT_BASE_URL, sampling).await?;
+            let backend = AnthropicBackend::new(config).map_err(AppError::from)?;
+            Ok(Box::new(backend))
+        }
+        // Le backend de test n'a ni clé ni serveur
+        #[cfg(any(test, feature = "mock-backend"))]
+        BackendKind::Mock => Ok(Box::new(crate::llm::MockBackend::default())),
+    }
+}
+
+/// Clé, URL et réglages de génération d'un fournisseur distant
+async fn remote_config(
+    state: &AppState,
+    kind: &BackendKind,
+    model: &str,
+    default_base_url: &str,
+    overrides: Option<&SamplingParams>,
+) -> AppResult<RemoteBackendConfig> {
+    let provider = kind.provider();
+    let api_key = state.settings_repo
+        .get_provider_api_key(provider)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::MissingCredentials { provider: provider.to_string() })?;
+    let base_url = state.settings_repo
+        .get_provider_base_url(provider)
+        .await
+        .map_err(AppError::from)?;
+
+    let mut sampling = state.llm_engine.config().sampling;
+    if let Some(overrides) = overrides {
+        overrides.apply_to(&mut sampling);
+    }
+    Ok(RemoteBackendConfig {
+        base_url: base_url.unwrap_or_else(|| default_base_url.to_string()),
+        api_key,
+        model: model.to_string(),
+        max_tokens: sampling.max_tokens,
+        temperature: sampling.temperature,
+    })
+}
+
+#[tauri::command]
+pub async fn set_provider_credentials(
+    state: State<'_, SharedState>,
+    provider: String,
+    api_key: String,
+    base_url: Option<String>,
+) -> AppResult<String> {
+    let state = state.get();
+    if provider != "openai" && provider != "anthropic" {
+        return Err(AppError::invalid_input(format!("Unknown provider: {}", provider)));
+    }
+    info!("Updating credentials for provider: {}", provider);
+
+    state.settings_repo
+        .set_provider_api_key(&provider, &api_key)
+        .await
+        .map_err(AppError::from)?;
+
+    if let Some(base_url) = base_url {
+        state.settings_repo
+            .set_provider_base_url(&provider, &base_url)
+            .await
+            .map_err(AppError::from)?;
+    }
+
+    Ok(format!("Credentials saved for {}", provider))
+}
+
+#[tauri::command]
+pub async fn set_session_backend(
+    state: State<'_, SharedState>,
+    session_id: String,
+    backend: BackendKind,
+) -> AppResult<()> {
+    let state = state.get();
+    info!("Session {} now uses backend: {:?}", session_id, backend);
+
+    state.settings_repo
+        .set_session_backend(&session_id, &backend)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_session_backend(
+    state: State<'_, SharedState>,
+    session_id: String,
+) -> AppResult<BackendKind> {
+    let state = state.get();
+    state.settings_repo
+        .get_session_backend(&session_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Version des bindings llama.cpp, accélérations compilées et versions GGUF lisibles
+#[tauri::command]
+pub async fn get_backend_info() -> AppResult<BackendInfo> {
+    Ok(crate::llm::backend_info::backend_info())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_state;
+    use crate::llm::ChatMessage;
+
+    #[tokio::test]
+    async fn test_session_on_mock_backend() {
+        let state = test_state().await;
+        let session_id = state.context_manager.read().await
+            .create_session("Mock".to_string()).await.unwrap();
+        state.settings_repo.set_session_backend(&session_id, &BackendKind::Mock).await.unwrap();
+
+        // Même chemin que les sessions sur un backend distant
+        let kind = state.settings_repo.get_session_backend(&session_id).await.unwrap();
+        let backend = remote_backend(&state, &kind, None).await.unwrap();
+        let response = backend.chat(&[ChatMessage::user("hello")]).await.unwrap();
+        assert_eq!(response.text, "Echo: hello");
+
+        // Les fournisseurs réels demandent une clé
+        let openai = BackendKind::OpenAI { model: "gpt-4o-mini".to_string() };
+        assert!(matches!(
+            remote_backend(&state, &openai, None).await,
+            Err(AppError::MissingCredentials { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_remote_config_applies_session_sampling() {
+        let state = test_state().await;
+        state.settings_repo.set_provider_api_key("openai", "sk-test").await.unwrap();
+        let kind = BackendKind::OpenAI { model: "gpt-4o-mini".to_string() };
+
+        let defaults = state.llm_engine.config().sampling;
+        let config = remote_config(&state, &kind, "gpt-4o-mini", OPENAI_DEFAULT_BASE_URL, None).await.unwrap();
+        assert_eq!(config.max_tokens, defaults.max_tokens);
+
+        // Réglages d'un preset ou d'un agent
+        let overrides = SamplingParams { temperature: Some(0.1), max_tokens: Some(64), ..Default::default() };
+        let config = remote_config(&state, &kind, "gpt-4o-mini", OPENAI_DEFAULT_BASE_URL, Some(&overrides)).await.unwrap();
+        assert_eq!(config.temperature, 0.1);
+        assert_eq!(config.max_tokens, 64);
+    }
+}
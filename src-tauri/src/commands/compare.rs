@@ -0,0 +1,255 @@
+/// Commandes Tauri pour comparer des modèles
+///
+/// Le moteur local ne garde qu'un modèle en mémoire : les modèles sont
+/// exécutés l'un après l'autre, puis le modèle chargé auparavant est rétabli.
+/// `compare_models` compare deux réponses à un même prompt, `replay_session`
+/// rejoue une conversation entière avec un autre modèle dans une nouvelle
+/// session (comparaison après un changement de modèle ou de prompt).
+
+use crate::{AppState, SharedState};
+use crate::context::{Attachment, Message, MessageRole};
+use crate::error::{AppError, AppResult};
+use crate::llm::{ChatMessage, GenerationPriority, SamplingParams};
+use super::llm::{guardrail_policy, load_model_by_name, restore_model};
+use serde::Serialize;
+use std::time::Instant;
+use crate::events::{emit_event, ReplayProgress};
+use tauri::{AppHandle, State};
+use tracing::info;
+use ts_rs::TS;
+
+/// Réponse d'un modèle et ses temps d'exécution
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ModelRun {
+    pub model: String,
+    pub text: String,
+    #[ts(type = "number")]
+    pub tokens_generated: usize,
+    /// Temps de chargement (0 si le modèle était déjà chargé)
+    #[ts(type = "number")]
+    pub load_ms: u64,
+    #[ts(type = "number")]
+    pub generation_ms: u64,
+    pub tokens_per_second: f64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ModelComparison {
+    pub prompt: String,
+    pub model_a: ModelRun,
+    pub model_b: ModelRun,
+}
+
+/// Exécute le même prompt avec deux modèles locaux et renvoie les deux réponses
+#[tauri::command]
+pub async fn compare_models(
+    state: State<'_, SharedState>,
+    prompt: String,
+    model_a: String,
+    model_b: String,
+    params: Option<SamplingParams>,
+) -> AppResult<ModelComparison> {
+    let state = state.get();
+    if prompt.trim().is_empty() {
+        return Err(AppError::invalid_input("Le prompt est vide"));
+    }
+    for model in [&model_a, &model_b] {
+        if !state.model_manager.models_directory().join(model).exists() {
+            return Err(AppError::ModelNotFound { model: model.clone() });
+        }
+    }
+
+    // Aucune autre génération ne doit changer de modèle pendant la comparaison
+    let _permit = state.generation_scheduler
+        .acquire(None, GenerationPriority::Normal).await
+        .map_err(AppError::from)?;
+
+    let previous = if state.llm_engine.is_loaded() {
+        state.settings_repo.get_current_model().await.map_err(AppError::from)?
+    } else {
+        None
+    };
+    info!("Comparaison de {} et {}", model_a, model_b);
+
+    let runs = async {
+        let run_a = run_model(&state, &model_a, &prompt, params.clone()).await?;
+        let run_b = run_model(&state, &model_b, &prompt, params).await?;
+        Ok::<_, AppError>((run_a, run_b))
+    }
+    .await;
+
+    // Le modèle de l'utilisateur est rétabli, même après un échec
+    restore_model(&state, previous).await;
+
+    let (model_a, model_b) = runs?;
+    Ok(ModelComparison { prompt, model_a, model_b })
+}
+
+/// Session créée par `replay_session`
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct SessionReplay {
+    pub session_id: String,
+    pub source_session_id: String,
+    pub model: String,
+    /// Tours utilisateur rejoués
+    #[ts(type = "number")]
+    pub turns: usize,
+    #[ts(type = "number")]
+    pub tokens_generated: usize,
+    #[ts(type = "number")]
+    pub generation_ms: u64,
+}
+
+/// Rejoue les messages utilisateur d'une session avec `model_name` (le modèle
+/// chargé si absent) dans une nouvelle session ; la session d'origine n'est pas
+/// modifiée
+#[tauri::command]
+pub async fn replay_session(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    session_id: String,
+    model_name: Option<String>,
+    params: Option<SamplingParams>,
+) -> AppResult<SessionReplay> {
+    let state = state.get();
+    let source = {
+        let context_manager = state.context_manager.read().await;
+        context_manager.get_session(&session_id).await.map_err(AppError::from)?
+    };
+    let turns = source.messages.iter().filter(|m| m.role == MessageRole::User).count();
+    if turns == 0 {
+        return Err(AppError::invalid_input("La session ne contient aucun message utilisateur"));
+    }
+
+    let _permit = state.generation_scheduler
+        .acquire(None, GenerationPriority::Normal).await
+        .map_err(AppError::from)?;
+
+    let previous = if state.llm_engine.is_loaded() {
+        state.settings_repo.get_current_model().await.map_err(AppError::from)?
+    } else {
+        None
+    };
+    let model = match model_name.or_else(|| previous.clone()) {
+        Some(model) => model,
+        None => return Err(AppError::NoModelLoaded),
+    };
+    if !state.model_manager.models_directory().join(&model).exists() {
+        return Err(AppError::ModelNotFound { model });
+    }
+    info!("Rejeu de la session {} avec {}", session_id, model);
+
+    let replay = async {
+        if previous.as_deref() != Some(model.as_str()) {
+            load_model_by_name(&state, &model).await?;
+        }
+        let replay_id = {
+            let context_manager = state.context_manager.read().await;
+            context_manager
+                .create_detached_session(format!("{} (rejeu {})", source.title, model), None)
+                .await
+                .map_err(AppError::from)?
+        };
+
+        let policy = guardrail_policy(&state, source.agent_id.as_deref()).await?;
+        let started = Instant::now();
+        let mut tokens_generated = 0;
+        let mut history: Vec<ChatMessage> = Vec::new();
+        let mut turn = 0;
+        // Les réponses et appels d'outils d'origine sont remplacés par ceux du modèle rejoué
+        for message in source.messages.iter().filter(|m| matches!(m.role, MessageRole::System | MessageRole::User)) {
+            // Les pièces jointes sont copiées sous de nouveaux identifiants
+            let attachments = message
+                .attachments
+                .iter()
+                .map(|a| Attachment { id: uuid::Uuid::new_v4().to_string(), ..a.clone() })
+                .collect();
+            let copy = Message::new(message.role.clone(), message.content.clone()).with_attachments(attachments);
+            history.push(ChatMessage::from(&copy));
+            {
+                let context_manager = state.context_manager.read().await;
+                context_manager.add_message(&replay_id, copy).await.map_err(AppError::from)?;
+            }
+            if message.role != MessageRole::User {
+                continue;
+            }
+
+            let mut response = state.llm_engine
+                .generate_with(history.clone(), params.clone())
+                .await
+                .map_err(AppError::generation)?;
+            if policy.is_enabled() {
+                policy.apply_reply(&mut response.text, &mut response.reasoning);
+            }
+            tokens_generated += response.tokens_generated;
+            history.push(ChatMessage::assistant(response.text.clone()));
+            let answer = Message::new(MessageRole::Assistant, response.text)
+                .with_reasoning(response.reasoning)
+                .with_metadata("model".to_string(), serde_json::json!(model));
+            {
+                let context_manager = state.context_manager.read().await;
+                context_manager.add_message(&replay_id, answer).await.map_err(AppError::from)?;
+            }
+            turn += 1;
+            emit_event(&app, &ReplayProgress {
+                session_id: replay_id.clone(),
+                source_session_id: session_id.clone(),
+                turn,
+                turns,
+            });
+        }
+
+        Ok::<_, AppError>(SessionReplay {
+            session_id: replay_id,
+            source_session_id: session_id.clone(),
+            model: model.clone(),
+            turns,
+            tokens_generated,
+            generation_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+    .await;
+
+    // Le modèle de l'utilisateur est rétabli, même après un échec
+    restore_model(&state, previous).await;
+
+    replay
+}
+
+/// Charge le modèle si besoin puis génère la réponse au prompt
+async fn run_model(
+    state: &AppState,
+    model: &str,
+    prompt: &str,
+    params: Option<SamplingParams>,
+) -> AppResult<ModelRun> {
+    let current = state.settings_repo.get_current_model().await.map_err(AppError::from)?;
+    let started = Instant::now();
+    if !state.llm_engine.is_loaded() || current.as_deref() != Some(model) {
+        load_model_by_name(state, model).await?;
+    }
+    let load_ms = started.elapsed().as_millis() as u64;
+
+    let started = Instant::now();
+    let mut response = state.llm_engine
+        .generate_with(vec![ChatMessage::user(prompt)], params)
+        .await
+        .map_err(AppError::generation)?;
+    let elapsed = started.elapsed();
+    let policy = guardrail_policy(state, None).await?;
+    if policy.is_enabled() {
+        policy.apply_reply(&mut response.text, &mut response.reasoning);
+    }
+
+    Ok(ModelRun {
+        model: model.to_string(),
+        text: response.text,
+        tokens_generated: response.tokens_generated,
+        load_ms,
+        generation_ms: elapsed.as_millis() as u64,
+        tokens_per_second: response.tokens_generated as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+    })
+}
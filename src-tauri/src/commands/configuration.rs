@@ -0,0 +1,49 @@
+/// Commandes Tauri de la configuration (fichier `agents-rs.toml` et réglages enregistrés)
+
+use crate::SharedState;
+use crate::configuration::{ConfigEntry, ConfigSource, EffectiveConfig};
+use crate::error::{AppError, AppResult};
+use crate::llm::model_manager::models_dir_from_env;
+use crate::logging::DEFAULT_LOG_LEVEL;
+use crate::mcp::MCPServer;
+use tauri::State;
+
+/// Configuration en vigueur et origine de chaque valeur : variable d'environnement,
+/// réglage enregistré, fichier de configuration puis valeur par défaut
+#[tauri::command]
+pub async fn get_effective_config(state: State<'_, SharedState>) -> AppResult<EffectiveConfig> {
+    let state = state.get();
+    let file = &state.file_config;
+    let settings = &state.settings_repo;
+    let current_model = settings.get_current_model().await.map_err(AppError::from)?;
+    let connection = settings.get_hf_connection().await.map_err(AppError::from)?;
+    let log_level = settings.get_log_level().await.map_err(AppError::from)?;
+
+    Ok(EffectiveConfig {
+        config_file: state.config_file.clone(),
+        models_dir: ConfigEntry::resolve(
+            [(ConfigSource::Environment, models_dir_from_env()), (ConfigSource::File, file.models_dir.clone())],
+            Some(state.model_manager.models_directory().to_path_buf()),
+        ),
+        default_model: ConfigEntry::resolve(
+            [(ConfigSource::Database, current_model), (ConfigSource::File, file.default_model.clone())],
+            None,
+        ),
+        mcp_port: ConfigEntry::resolve(
+            [(ConfigSource::Environment, MCPServer::port_from_env()), (ConfigSource::File, file.mcp_port)],
+            None,
+        ),
+        proxy: ConfigEntry::resolve(
+            [(ConfigSource::Database, connection.proxy), (ConfigSource::File, file.proxy.clone())],
+            None,
+        ),
+        log_level: ConfigEntry::resolve(
+            [
+                (ConfigSource::Environment, std::env::var("RUST_LOG").ok()),
+                (ConfigSource::Database, log_level),
+                (ConfigSource::File, file.log_level.clone()),
+            ],
+            Some(DEFAULT_LOG_LEVEL.to_string()),
+        ),
+    })
+}
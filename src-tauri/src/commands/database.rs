@@ -0,0 +1,239 @@
+/// Commandes Tauri de maintenance de la base de données (sauvegarde, restauration,
+/// compactage, chiffrement, rétention)
+
+use crate::{AppState, SharedState};
+use crate::context::{retention, CipherState, ContentCipher, ConversationRepository, EncryptionParams, MemoryRepository, PruneReport, RetentionPolicy, SettingsRepository};
+use crate::error::{AppError, AppResult};
+use crate::tasks::TaskRepository;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::State;
+use tracing::info;
+use ts_rs::TS;
+
+/// Sauvegarde la base dans un nouveau fichier
+#[tauri::command]
+pub async fn backup_database(
+    state: State<'_, SharedState>,
+    path: String,
+) -> AppResult<()> {
+    let state = state.get();
+    state.database
+        .backup_to(&PathBuf::from(path))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Remplace le contenu de la base par celui d'une sauvegarde
+#[tauri::command]
+pub async fn restore_database(
+    state: State<'_, SharedState>,
+    path: String,
+) -> AppResult<()> {
+    let state = state.get();
+    state.database
+        .restore_from(&PathBuf::from(path))
+        .await
+        .map_err(AppError::from)?;
+    
+    // La sauvegarde peut avoir été chiffrée avec une autre phrase de passe
+    let encrypted = state.settings_repo.get_encryption_params().await
+        .map_err(AppError::from)?
+        .is_some();
+    *state.content_cipher.write().unwrap() = if encrypted {
+        CipherState::Locked
+    } else {
+        CipherState::Disabled
+    };
+    
+    // Les sessions en cache ne correspondent plus à la base restaurée
+    state.context_manager.read().await.clear_cache().await;
+    
+    Ok(())
+}
+
+/// Compacte la base (VACUUM), renvoie le nombre d'octets libérés
+#[tauri::command]
+pub async fn compact_database(
+    state: State<'_, SharedState>,
+) -> AppResult<i64> {
+    let state = state.get();
+    let freed = state.database.compact().await.map_err(AppError::from)?;
+    info!("Database compaction freed {} bytes", freed);
+    Ok(freed)
+}
+
+/// État du chiffrement de la base
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+}
+
+#[tauri::command]
+pub async fn get_encryption_status(
+    state: State<'_, SharedState>,
+) -> AppResult<EncryptionStatus> {
+    let state = state.get();
+    let status = match &*state.content_cipher.read().unwrap() {
+        CipherState::Disabled => EncryptionStatus { enabled: false, unlocked: true },
+        CipherState::Locked => EncryptionStatus { enabled: true, unlocked: false },
+        CipherState::Unlocked(_) => EncryptionStatus { enabled: true, unlocked: true },
+    };
+    Ok(status)
+}
+
+/// Chiffre le contenu de toutes les conversations avec une nouvelle phrase de passe
+#[tauri::command]
+pub async fn encrypt_database(
+    state: State<'_, SharedState>,
+    passphrase: String,
+) -> AppResult<()> {
+    let state = state.get();
+    if !matches!(*state.content_cipher.read().unwrap(), CipherState::Disabled) {
+        return Err(AppError::invalid_input("The database is already encrypted"));
+    }
+    if passphrase.is_empty() {
+        return Err(AppError::invalid_input("The passphrase cannot be empty"));
+    }
+    
+    info!("Encrypting database");
+    let (cipher, params) = ContentCipher::create(&passphrase).map_err(AppError::from)?;
+    let unlocked = CipherState::Unlocked(cipher);
+    
+    // Les écritures de contenu attendent la fin de la réécriture ; la clé lit
+    // aussi le contenu encore en clair, réécrit dans la même transaction
+    {
+        let _rewriting = state.content_cipher.lock_rewrite().await;
+        let previous = {
+            let mut current = state.content_cipher.write().unwrap();
+            if !matches!(*current, CipherState::Disabled) {
+                return Err(AppError::invalid_input("The database is already encrypted"));
+            }
+            std::mem::replace(&mut *current, unlocked.clone())
+        };
+        if let Err(e) = rewrite_content(&state, &unlocked, &unlocked, Some(&params)).await {
+            *state.content_cipher.write().unwrap() = previous;
+            return Err(AppError::from(e));
+        }
+    }
+    
+    // Les anciennes pages libérées contiennent encore le texte en clair
+    state.database.compact().await.map_err(AppError::from)?;
+    Ok(())
+}
+
+/// Déverrouille une base chiffrée pour la session en cours
+#[tauri::command]
+pub async fn unlock_database(
+    state: State<'_, SharedState>,
+    passphrase: String,
+) -> AppResult<()> {
+    let state = state.get();
+    let params = state.settings_repo.get_encryption_params().await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::invalid_input("The database is not encrypted"))?;
+    
+    let cipher = ContentCipher::unlock(&passphrase, &params).map_err(AppError::from)?;
+    *state.content_cipher.write().unwrap() = CipherState::Unlocked(cipher);
+    
+    // Les sessions chargées pendant le verrouillage sont à relire
+    state.context_manager.read().await.clear_cache().await;
+    
+    info!("Database unlocked");
+    Ok(())
+}
+
+/// Déchiffre toutes les conversations et désactive le chiffrement
+#[tauri::command]
+pub async fn decrypt_database(
+    state: State<'_, SharedState>,
+    passphrase: String,
+) -> AppResult<()> {
+    let state = state.get();
+    let params = state.settings_repo.get_encryption_params().await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::invalid_input("The database is not encrypted"))?;
+    
+    info!("Decrypting database");
+    let cipher = ContentCipher::unlock(&passphrase, &params).map_err(AppError::from)?;
+    let unlocked = CipherState::Unlocked(cipher);
+    
+    // Pendant la réécriture, les lectures passent par la clé, qui lit le contenu
+    // chiffré comme celui déjà en clair, et les écritures de contenu attendent ;
+    // le chiffrement n'est désactivé qu'une fois la transaction validée
+    {
+        let _rewriting = state.content_cipher.lock_rewrite().await;
+        let previous = std::mem::replace(&mut *state.content_cipher.write().unwrap(), unlocked.clone());
+        if let Err(e) = rewrite_content(&state, &unlocked, &CipherState::Disabled, None).await {
+            *state.content_cipher.write().unwrap() = previous;
+            return Err(AppError::from(e));
+        }
+        *state.content_cipher.write().unwrap() = CipherState::Disabled;
+    }
+    
+    // Les sessions chargées pendant un verrouillage sont à relire
+    state.context_manager.read().await.clear_cache().await;
+    Ok(())
+}
+
+/// Réécrit le contenu chiffrable et les paramètres de chiffrement en une seule
+/// transaction ; les paramètres sont écrits d'abord pour prendre tout de suite
+/// le verrou d'écriture de la base
+async fn rewrite_content(
+    state: &AppState,
+    from: &CipherState,
+    to: &CipherState,
+    params: Option<&EncryptionParams>,
+) -> anyhow::Result<()> {
+    let mut tx = state.database.pool().begin().await?;
+    SettingsRepository::write_encryption_params(&mut tx, params).await?;
+    ConversationRepository::reencrypt_content(&mut tx, from, to).await?;
+    MemoryRepository::reencrypt(&mut tx, from, to).await?;
+    TaskRepository::reencrypt(&mut tx, from, to).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_retention_policy(
+    state: State<'_, SharedState>,
+) -> AppResult<RetentionPolicy> {
+    let state = state.get();
+    state.settings_repo
+        .get_retention_policy()
+        .await
+        .map_err(AppError::from)
+}
+
+/// Enregistre la politique de rétention (appliquée par la tâche de fond)
+#[tauri::command]
+pub async fn set_retention_policy(
+    state: State<'_, SharedState>,
+    policy: RetentionPolicy,
+) -> AppResult<()> {
+    let state = state.get();
+    info!("Retention policy updated: {:?}", policy);
+    state.settings_repo
+        .set_retention_policy(&policy)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Aperçu de ce qu'une politique supprimerait, sans rien modifier
+#[tauri::command]
+pub async fn preview_prune(
+    state: State<'_, SharedState>,
+    policy: Option<RetentionPolicy>,
+) -> AppResult<PruneReport> {
+    let state = state.get();
+    let policy = match policy {
+        Some(policy) => policy,
+        None => state.settings_repo.get_retention_policy().await.map_err(AppError::from)?,
+    };
+    let repository = ConversationRepository::new(state.database.pool().clone());
+    retention::prune(&repository, &policy, true)
+        .await
+        .map_err(AppError::from)
+}
@@ -0,0 +1,95 @@
+/// Commandes Tauri de la bibliothèque de documents (base de connaissances RAG)
+///
+/// Les documents sont les pièces jointes de type document de toutes les
+/// conversations ; chacun a un état d'indexation (extraits, tokens, modèle
+/// d'embedding, date de la dernière indexation).
+
+use crate::SharedState;
+use crate::context::{DocumentInfo, RetrievalSettings};
+use crate::error::{AppError, AppResult};
+use super::attachments::index_document;
+use tauri::State;
+use tracing::info;
+
+/// Documents joints, du plus récent au plus ancien, avec leur état d'indexation
+#[tauri::command]
+pub async fn list_documents(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<DocumentInfo>> {
+    let state = state.get();
+    let context_manager = state.context_manager.read().await;
+    context_manager.list_documents().await
+        .map_err(AppError::from)
+}
+
+/// Supprime un document de la base de connaissances (le message qui le portait est conservé)
+#[tauri::command]
+pub async fn delete_document(
+    state: State<'_, SharedState>,
+    attachment_id: String,
+) -> AppResult<()> {
+    let state = state.get();
+    let context_manager = state.context_manager.read().await;
+    if context_manager.get_document(&attachment_id).await.map_err(AppError::from)?.is_none() {
+        return Err(AppError::invalid_input(format!("Document introuvable: {}", attachment_id)));
+    }
+    context_manager.delete_attachment(&attachment_id).await
+        .map_err(AppError::from)?;
+    info!("Document {} supprimé", attachment_id);
+    Ok(())
+}
+
+/// Redécoupe un document et recalcule ses embeddings avec le modèle chargé
+#[tauri::command]
+pub async fn reindex_document(
+    state: State<'_, SharedState>,
+    attachment_id: String,
+) -> AppResult<DocumentInfo> {
+    let state = state.get();
+    let attachment = {
+        let context_manager = state.context_manager.read().await;
+        context_manager.get_attachment(&attachment_id).await
+            .map_err(AppError::from)?
+    };
+    let attachment = attachment
+        .ok_or_else(|| AppError::invalid_input(format!("Document introuvable: {}", attachment_id)))?;
+    let text = attachment.text.as_deref()
+        .ok_or_else(|| AppError::invalid_input(format!("{} n'est pas un document", attachment.file_name)))?;
+    if text.chars().count() <= crate::context::documents::MAX_INLINE_CHARS {
+        return Err(AppError::invalid_input(format!(
+            "{} est inclus en entier dans les prompts, il n'a pas d'index",
+            attachment.file_name
+        )));
+    }
+
+    index_document(&state, &attachment, text).await?;
+    let context_manager = state.context_manager.read().await;
+    context_manager.get_document(&attachment_id).await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::invalid_input(format!("Document introuvable: {}", attachment_id)))
+}
+
+/// Pondération de la recherche hybride (BM25 et embeddings)
+#[tauri::command]
+pub async fn get_retrieval_settings(
+    state: State<'_, SharedState>,
+) -> AppResult<RetrievalSettings> {
+    let state = state.get();
+    state.settings_repo.get_retrieval_settings().await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_retrieval_settings(
+    state: State<'_, SharedState>,
+    settings: RetrievalSettings,
+) -> AppResult<()> {
+    let state = state.get();
+    if !(0.0..=1.0).contains(&settings.lexical_weight) {
+        return Err(AppError::invalid_input("Le poids de la recherche lexicale doit être compris entre 0 et 1"));
+    }
+    state.settings_repo.set_retrieval_settings(&settings).await
+        .map_err(AppError::from)?;
+    info!("Pondération de la recherche hybride: {} lexical", settings.lexical_weight);
+    Ok(())
+}
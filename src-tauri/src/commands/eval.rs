@@ -0,0 +1,179 @@
+/// Commandes Tauri du banc d'évaluation (suites de prompts et vérifications)
+///
+/// Comme pour la comparaison de modèles, les modèles évalués sont chargés l'un
+/// après l'autre puis le modèle chargé auparavant est rétabli.
+
+use crate::{AppState, SharedState};
+use crate::error::{AppError, AppResult};
+use crate::eval::{EvalCase, EvalCaseResult, EvalProgress, EvalRun, EvalSuite};
+use crate::llm::{ChatMessage, GenerationPriority, SamplingParams};
+use super::llm::{load_model_by_name, restore_model};
+use std::time::Instant;
+use crate::events::emit_event;
+use tauri::{AppHandle, State};
+use tracing::info;
+
+/// Crée ou met à jour une suite (nouvel identifiant si `id` est absent)
+#[tauri::command]
+pub async fn save_eval_suite(
+    state: State<'_, SharedState>,
+    id: Option<String>,
+    name: String,
+    description: Option<String>,
+    cases: Vec<EvalCase>,
+) -> AppResult<EvalSuite> {
+    let state = state.get();
+    if name.trim().is_empty() {
+        return Err(AppError::invalid_input("Le nom de la suite est vide"));
+    }
+    if cases.is_empty() {
+        return Err(AppError::invalid_input("La suite ne contient aucun cas"));
+    }
+    if let Some(case) = cases.iter().find(|c| c.prompt.trim().is_empty()) {
+        return Err(AppError::invalid_input(format!("Le prompt du cas {} est vide", case.name)));
+    }
+
+    let mut suite = match id {
+        Some(id) => state.eval_repo.get_suite(&id).await.map_err(AppError::from)?
+            .ok_or_else(|| AppError::invalid_input(format!("Suite introuvable: {}", id)))?,
+        None => EvalSuite::new(String::new(), String::new(), vec![]),
+    };
+    suite.name = name;
+    suite.description = description.unwrap_or_default();
+    suite.cases = cases;
+    state.eval_repo.save_suite(&suite).await.map_err(AppError::from)?;
+    Ok(suite)
+}
+
+#[tauri::command]
+pub async fn list_eval_suites(state: State<'_, SharedState>) -> AppResult<Vec<EvalSuite>> {
+    let state = state.get();
+    state.eval_repo.list_suites().await.map_err(AppError::from)
+}
+
+/// Supprime une suite et ses exécutions
+#[tauri::command]
+pub async fn delete_eval_suite(state: State<'_, SharedState>, id: String) -> AppResult<bool> {
+    let state = state.get();
+    state.eval_repo.delete_suite(&id).await.map_err(AppError::from)
+}
+
+/// Exécutions d'une suite (scores agrégés, sans le détail des cas)
+#[tauri::command]
+pub async fn list_eval_runs(state: State<'_, SharedState>, suite_id: String) -> AppResult<Vec<EvalRun>> {
+    let state = state.get();
+    state.eval_repo.list_runs(&suite_id).await.map_err(AppError::from)
+}
+
+/// Exécution avec le résultat de chaque cas
+#[tauri::command]
+pub async fn get_eval_run(state: State<'_, SharedState>, id: String) -> AppResult<Option<EvalRun>> {
+    let state = state.get();
+    state.eval_repo.get_run(&id).await.map_err(AppError::from)
+}
+
+/// Exécute une suite avec chaque modèle (le modèle chargé si la liste est vide),
+/// avec le prompt système et l'échantillonnage de l'agent si fourni ;
+/// `params` remplace l'échantillonnage de l'agent
+#[tauri::command]
+pub async fn run_eval_suite(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    suite_id: String,
+    models: Vec<String>,
+    agent_id: Option<String>,
+    params: Option<SamplingParams>,
+) -> AppResult<Vec<EvalRun>> {
+    let state = state.get();
+    let suite = state.eval_repo.get_suite(&suite_id).await.map_err(AppError::from)?
+        .ok_or_else(|| AppError::invalid_input(format!("Suite introuvable: {}", suite_id)))?;
+    let agent = match agent_id.as_deref() {
+        Some(id) => Some(
+            state.agent_repo.get(id).await.map_err(AppError::from)?
+                .ok_or_else(|| AppError::invalid_input(format!("Agent introuvable: {}", id)))?,
+        ),
+        None => None,
+    };
+    let params = params.or_else(|| agent.as_ref().map(|a| a.sampling.clone()));
+    let system_prompt = agent.as_ref().map(|a| a.system_prompt.clone()).filter(|p| !p.is_empty());
+
+    // Aucune autre génération ne doit changer de modèle pendant l'évaluation
+    let _permit = state.generation_scheduler
+        .acquire(None, GenerationPriority::Normal).await
+        .map_err(AppError::from)?;
+
+    let previous = if state.llm_engine.is_loaded() {
+        state.settings_repo.get_current_model().await.map_err(AppError::from)?
+    } else {
+        None
+    };
+    let models = if models.is_empty() {
+        vec![previous.clone().ok_or(AppError::NoModelLoaded)?]
+    } else {
+        models
+    };
+    for model in &models {
+        if !state.model_manager.models_directory().join(model).exists() {
+            return Err(AppError::ModelNotFound { model: model.clone() });
+        }
+    }
+    info!("Évaluation de la suite {} avec {} modèle(s)", suite.name, models.len());
+
+    let runs = async {
+        let mut runs = Vec::new();
+        for model in &models {
+            let current = state.settings_repo.get_current_model().await.map_err(AppError::from)?;
+            if !state.llm_engine.is_loaded() || current.as_deref() != Some(model.as_str()) {
+                load_model_by_name(&state, model).await?;
+            }
+
+            let mut results = Vec::new();
+            for case in &suite.cases {
+                let result = run_case(&state, case, system_prompt.as_deref(), params.clone()).await;
+                emit_event(&app, &EvalProgress {
+                    suite_id: suite.id.clone(),
+                    model: model.clone(),
+                    case_name: case.name.clone(),
+                    completed: results.len() + 1,
+                    total: suite.cases.len(),
+                    passed: result.passed,
+                });
+                results.push(result);
+            }
+
+            let run = EvalRun::new(suite.id.clone(), model.clone(), agent_id.clone(), results);
+            state.eval_repo.save_run(&run).await.map_err(AppError::from)?;
+            info!("{}: {}/{} cas réussis", model, run.passed, run.total);
+            runs.push(run);
+        }
+        Ok::<_, AppError>(runs)
+    }
+    .await;
+
+    // Le modèle de l'utilisateur est rétabli, même après un échec
+    restore_model(&state, previous).await;
+
+    runs
+}
+
+/// Génère la réponse d'un cas ; un échec de génération fait échouer le cas
+async fn run_case(
+    state: &AppState,
+    case: &EvalCase,
+    system_prompt: Option<&str>,
+    params: Option<SamplingParams>,
+) -> EvalCaseResult {
+    let mut messages = Vec::new();
+    if let Some(system_prompt) = case.system_prompt.as_deref().or(system_prompt) {
+        messages.push(ChatMessage::system(system_prompt));
+    }
+    messages.push(ChatMessage::user(case.prompt.as_str()));
+
+    let started = Instant::now();
+    let response = state.llm_engine.generate_with(messages, params).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    match response {
+        Ok(response) => EvalCaseResult::evaluate(case, response.text, latency_ms, response.tokens_generated),
+        Err(e) => EvalCaseResult::error(case, format!("generation failed: {}", e), latency_ms),
+    }
+}
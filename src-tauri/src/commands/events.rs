@@ -0,0 +1,11 @@
+/// Commandes Tauri des événements émis vers l'interface
+
+use crate::error::AppResult;
+use crate::events::{event_schemas, EventSchema};
+
+/// Nom, description et schéma JSON du contenu de chaque événement,
+/// pour générer les types TypeScript correspondants
+#[tauri::command]
+pub async fn list_event_schemas() -> AppResult<Vec<EventSchema>> {
+    Ok(event_schemas())
+}
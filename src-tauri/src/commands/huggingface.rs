@@ -1,19 +1,24 @@
 /// Commandes Tauri pour l'intégration HuggingFace
 
-use crate::AppState;
-use crate::huggingface::{HFModelInfo, ModelSearchParams};
-use std::sync::Arc;
-use tauri::{AppHandle, State, Emitter};
-use tracing::{info, error};
+use crate::{AppState, SharedState};
+use crate::context::DownloadedModel;
+use crate::error::{AppError, AppResult};
+use crate::huggingface::{ConnectionSettings, HFModelInfo, ModelSearchParams, ModelSuggestion, RateLimitConfig};
+use serde::Serialize;
+use crate::events::{emit_event, DownloadProgress};
+use tauri::{AppHandle, State};
+use tracing::{info, error, warn};
+use ts_rs::TS;
 
 #[tauri::command]
 pub async fn hf_search_models(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     search_query: Option<String>,
     author: Option<String>,
     task: Option<String>,
     limit: Option<u32>,
-) -> Result<Vec<crate::huggingface::Model>, String> {
+) -> AppResult<Vec<crate::huggingface::Model>> {
+    let state = state.get();
     info!("Searching HuggingFace models");
     
     let mut params = ModelSearchParams::new();
@@ -36,71 +41,213 @@ pub async fn hf_search_models(
     let client = state.hf_client.read().await;
     client.search_models(params)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+/// Suggestions pour la saisie dans le navigateur de modèles
+///
+/// Renvoie `None` quand une frappe plus récente a remplacé la requête.
+#[tauri::command]
+pub async fn hf_autocomplete_models(
+    state: State<'_, SharedState>,
+    prefix: String,
+) -> AppResult<Option<Vec<ModelSuggestion>>> {
+    let state = state.get();
+    // Le client est cloné pour ne pas garder le verrou pendant l'attente
+    let client = state.hf_client.read().await.clone();
+    client.autocomplete_models(&prefix)
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn hf_get_model_info(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     repo_id: String,
-) -> Result<HFModelInfo, String> {
+) -> AppResult<HFModelInfo> {
+    let state = state.get();
     info!("Fetching HuggingFace model info: {}", repo_id);
     
     let client = state.hf_client.read().await;
     client.get_model_info(&repo_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn hf_download_model(
     app: AppHandle,
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     repo_id: String,
     filename: String,
     revision: Option<String>,
-) -> Result<String, String> {
+) -> AppResult<String> {
+    let state = state.get();
+    download_hub_model(&app, &state, &repo_id, &filename, revision.as_deref()).await
+}
+
+/// Télécharge un fichier du Hub dans le dossier des modèles (progression sur
+/// "download-progress") et enregistre sa révision
+pub(crate) async fn download_hub_model(
+    app: &AppHandle,
+    state: &AppState,
+    repo_id: &str,
+    filename: &str,
+    revision: Option<&str>,
+) -> AppResult<String> {
+    download_hub_file(state, repo_id, filename, revision, |downloaded, total| {
+        emit_event(app, &DownloadProgress {
+            repo_id: Some(repo_id.to_string()),
+            ..DownloadProgress::new(filename, downloaded, total)
+        });
+    })
+    .await
+}
+
+/// Même téléchargement que `download_hub_model`, la progression étant remise à
+/// `on_progress` (octets reçus, taille totale) plutôt qu'émise vers l'interface
+pub async fn download_hub_file<F>(
+    state: &AppState,
+    repo_id: &str,
+    filename: &str,
+    revision: Option<&str>,
+    on_progress: F,
+) -> AppResult<String>
+where
+    F: FnMut(u64, Option<u64>),
+{
     info!("Downloading {} from {}", filename, repo_id);
     
     let models_dir = state.model_manager.models_directory();
-    let output_path = models_dir.join(&filename);
+    let output_path = models_dir.join(filename);
     
     let client = state.hf_client.read().await;
     
-    // Use download_file_with_progress to emit progress events
     let result_path = client.download_file_with_progress(
-        &repo_id,
-        &filename,
-        revision.as_deref(),
+        repo_id,
+        filename,
+        revision,
+        output_path,
+        on_progress,
+    )
+    .await
+    .map_err(AppError::from)?;
+    
+    // Révision enregistrée pour détecter les mises à jour du dépôt
+    match client.get_file_revision(repo_id, filename, revision).await {
+        Ok(file_revision) => {
+            let downloaded = DownloadedModel::new(filename, repo_id, file_revision);
+            if let Err(e) = state.downloads_repo.record(&downloaded).await {
+                warn!("Failed to record the revision of {}: {:#}", filename, e);
+            }
+        }
+        Err(e) => warn!("Failed to resolve the revision of {}: {:#}", filename, e),
+    }
+    
+    Ok(result_path.to_string_lossy().to_string())
+}
+
+/// Mise à jour disponible pour un modèle téléchargé depuis le Hub
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ModelUpdate {
+    pub filename: String,
+    pub repo_id: String,
+    pub revision: String,
+    pub latest_revision: String,
+    pub update_available: bool,
+}
+
+/// Compare chaque modèle téléchargé depuis le Hub à la dernière révision de son
+/// dépôt ; la mise à jour se fait en relançant `hf_download_model`
+#[tauri::command]
+pub async fn check_model_updates(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<ModelUpdate>> {
+    let state = state.get();
+    let downloaded = state.downloads_repo.list().await.map_err(AppError::from)?;
+    let client = state.hf_client.read().await.clone();
+    info!("Checking {} downloaded models for updates", downloaded.len());
+    
+    let mut updates = Vec::new();
+    for model in downloaded {
+        // Modèles dans la corbeille ou supprimés à la main
+        if !state.model_manager.models_directory().join(&model.filename).exists() {
+            if !state.model_manager.is_trashed(&model.filename) {
+                let _ = state.downloads_repo.delete(&model.filename).await;
+            }
+            continue;
+        }
+        // Un dépôt injoignable ou supprimé n'empêche pas de vérifier les autres
+        let latest = match client.get_file_revision(&model.repo_id, &model.filename, None).await {
+            Ok(latest) => latest,
+            Err(e) => {
+                warn!("Failed to check {} for updates: {:#}", model.filename, e);
+                continue;
+            }
+        };
+        updates.push(ModelUpdate {
+            update_available: model.is_outdated(&latest),
+            filename: model.filename,
+            repo_id: model.repo_id,
+            revision: model.revision,
+            latest_revision: latest.commit,
+        });
+    }
+    
+    Ok(updates)
+}
+
+/// Télécharge un modèle GGUF depuis un lien direct, hors du Hub
+///
+/// Même reprise et mêmes événements `download-progress` que `hf_download_model`,
+/// avec `url` à la place de `repo_id`.
+#[tauri::command]
+pub async fn download_model_from_url(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    url: String,
+    filename: String,
+) -> AppResult<String> {
+    let state = state.get();
+    let parsed = reqwest::Url::parse(&url)
+        .map_err(|e| AppError::invalid_input(format!("Invalid URL: {}", e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(AppError::invalid_input("Only http and https URLs can be downloaded"));
+    }
+    // Le fichier reste dans le dossier des modèles
+    if filename.contains(['/', '\\']) || filename.starts_with('.') || !filename.to_lowercase().ends_with(".gguf") {
+        return Err(AppError::invalid_input("The filename must be a plain .gguf file name"));
+    }
+    info!("Downloading {} from {}", filename, url);
+    
+    let output_path = state.model_manager.models_directory().join(&filename);
+    let client = state.hf_client.read().await.clone();
+    // Le fichier ne vient plus du Hub : il n'est plus suivi
+    state.downloads_repo.delete(&filename).await.map_err(AppError::from)?;
+    
+    let result_path = client.download_url_with_progress(
+        &url,
         output_path,
         |downloaded, total| {
-            let progress = if let Some(total) = total {
-                (downloaded as f64 / total as f64 * 100.0) as u32
-            } else {
-                0
-            };
-            
-            // Emit progress event
-            let _ = app.emit("download-progress", serde_json::json!({
-                "repo_id": repo_id,
-                "filename": filename,
-                "downloaded": downloaded,
-                "total": total,
-                "progress": progress,
-            }));
+            emit_event(&app, &DownloadProgress {
+                url: Some(url.clone()),
+                ..DownloadProgress::new(&filename, downloaded, total)
+            });
         },
     )
     .await
-    .map_err(|e| e.to_string())?;
+    .map_err(AppError::from)?;
     
     Ok(result_path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
 pub async fn hf_set_token(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     token: String,
-) -> Result<String, String> {
+) -> AppResult<String> {
+    let state = state.get();
     info!("Setting HuggingFace token");
     
     let mut client = state.hf_client.write().await;
@@ -109,15 +256,101 @@ pub async fn hf_set_token(
     Ok("Token set successfully".to_string())
 }
 
+#[tauri::command]
+pub async fn hf_get_offline_mode(
+    state: State<'_, SharedState>,
+) -> AppResult<bool> {
+    let state = state.get();
+    let client = state.hf_client.read().await;
+    Ok(client.is_offline())
+}
+
+/// Active le mode hors ligne : les recherches et fiches de modèles viennent du
+/// cache, les autres commandes `hf_*` échouent avec `AppError::Offline`
+#[tauri::command]
+pub async fn hf_set_offline_mode(
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> AppResult<()> {
+    let state = state.get();
+    info!("HuggingFace offline mode: {}", enabled);
+    
+    state.settings_repo.set_hf_offline(enabled)
+        .await
+        .map_err(AppError::from)?;
+    state.hf_client.read().await.set_offline(enabled);
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn hf_get_rate_limit(
+    state: State<'_, SharedState>,
+) -> AppResult<RateLimitConfig> {
+    let state = state.get();
+    let client = state.hf_client.read().await;
+    Ok(client.rate_limit())
+}
+
+/// Règle le débit des requêtes HuggingFace et les nouvelles tentatives
+#[tauri::command]
+pub async fn hf_set_rate_limit(
+    state: State<'_, SharedState>,
+    config: RateLimitConfig,
+) -> AppResult<RateLimitConfig> {
+    let state = state.get();
+    config.validate().map_err(AppError::invalid_input)?;
+    info!("HuggingFace rate limit: {:?}", config);
+    
+    state.settings_repo.set_hf_rate_limit(&config)
+        .await
+        .map_err(AppError::from)?;
+    state.hf_client.read().await.set_rate_limit(config);
+    
+    Ok(config)
+}
+
+#[tauri::command]
+pub async fn hf_get_connection_settings(
+    state: State<'_, SharedState>,
+) -> AppResult<ConnectionSettings> {
+    let state = state.get();
+    let client = state.hf_client.read().await;
+    Ok(client.connection().clone())
+}
+
+/// Utilise un miroir (hf-mirror.com, proxy d'entreprise) et/ou un proxy HTTP
+/// ou SOCKS pour les appels à l'API et les téléchargements
+#[tauri::command]
+pub async fn hf_set_connection_settings(
+    state: State<'_, SharedState>,
+    settings: ConnectionSettings,
+) -> AppResult<ConnectionSettings> {
+    let state = state.get();
+    settings.validate().map_err(AppError::invalid_input)?;
+    info!("HuggingFace endpoint: {:?}, proxy: {}", settings.endpoint, settings.proxy.is_some());
+    
+    // Le client est reconstruit avant l'enregistrement : un proxy refusé n'est pas conservé
+    state.hf_client.write().await
+        .set_connection(settings.clone())
+        .map_err(|e| AppError::invalid_input(format!("{:#}", e)))?;
+    state.settings_repo.set_hf_connection(&settings)
+        .await
+        .map_err(AppError::from)?;
+    
+    Ok(settings)
+}
+
 #[tauri::command]
 pub async fn hf_discover_gguf_models(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     search_query: Option<String>,
     author: Option<String>,
     task: Option<String>,
     sort: Option<String>,
     limit: Option<u32>,
-) -> Result<Vec<crate::huggingface::GGUFModelMetadata>, String> {
+) -> AppResult<Vec<crate::huggingface::GGUFModelMetadata>> {
+    let state = state.get();
     info!("Discovering GGUF models from HuggingFace");
     
     let mut params = ModelSearchParams::new();
@@ -145,15 +378,16 @@ pub async fn hf_discover_gguf_models(
         .await
         .map_err(|e| {
             error!("Failed to discover GGUF models: {}", e);
-            e.to_string()
+            AppError::from(e)
         })
 }
 
 #[tauri::command]
 pub async fn hf_get_gguf_files(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     repo_id: String,
-) -> Result<Vec<crate::huggingface::GGUFFile>, String> {
+) -> AppResult<Vec<crate::huggingface::GGUFFile>> {
+    let state = state.get();
     info!("Getting GGUF files for {}", repo_id);
     
     let client = state.hf_client.read().await;
@@ -161,6 +395,6 @@ pub async fn hf_get_gguf_files(
         .await
         .map_err(|e| {
             error!("Failed to get GGUF files for {}: {}", repo_id, e);
-            e.to_string()
+            AppError::from(e)
         })
 }
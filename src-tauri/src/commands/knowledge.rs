@@ -0,0 +1,114 @@
+/// Commandes Tauri des dossiers de la base de connaissances
+///
+/// Un dossier ajouté est indexé puis surveillé : les fichiers ajoutés, modifiés
+/// ou supprimés sont réindexés en tâche de fond, l'avancement étant publié par
+/// l'événement `kb-index-progress`. Un espace de travail utilise un dossier en
+/// l'indiquant comme index (`update_workspace`). Un dossier peut avoir son
+/// reranker, qui réordonne les extraits retrouvés avant leur ajout au prompt.
+
+use crate::SharedState;
+use crate::context::KnowledgeFolder;
+use crate::context::knowledge::IndexRequest;
+use crate::error::{AppError, AppResult};
+use std::path::Path;
+use tauri::State;
+use tracing::{info, warn};
+
+fn folder_not_found(id: &str) -> AppError {
+    AppError::invalid_input(format!("Dossier de la base de connaissances introuvable: {}", id))
+}
+
+/// Ajoute un dossier à la base de connaissances et lance son indexation
+#[tauri::command]
+pub async fn add_knowledge_folder(
+    state: State<'_, SharedState>,
+    path: String,
+) -> AppResult<KnowledgeFolder> {
+    let state = state.get();
+    let path = Path::new(&path);
+    if !path.is_absolute() || !path.is_dir() {
+        return Err(AppError::invalid_input(format!("Dossier introuvable: {}", path.display())));
+    }
+    let path = path.canonicalize()
+        .map_err(|e| AppError::invalid_input(format!("Dossier inaccessible: {}", e)))?;
+    let path = path.to_string_lossy();
+
+    let folders = state.knowledge_repo.list_folders().await.map_err(AppError::from)?;
+    if folders.iter().any(|folder| folder.path == path) {
+        return Err(AppError::invalid_input(format!("Dossier déjà dans la base de connaissances: {}", path)));
+    }
+    let folder = state.knowledge_repo.add_folder(&path).await
+        .map_err(AppError::from)?;
+    // La surveillance du nouveau dossier déclenche sa première indexation
+    state.knowledge_indexer.request(IndexRequest::Rewatch);
+    Ok(folder)
+}
+
+/// Dossiers de la base de connaissances avec leur nombre de fichiers et d'extraits
+#[tauri::command]
+pub async fn list_knowledge_folders(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<KnowledgeFolder>> {
+    let state = state.get();
+    state.knowledge_repo.list_folders().await
+        .map_err(AppError::from)
+}
+
+/// Retire un dossier de la base de connaissances (les fichiers ne sont pas touchés)
+#[tauri::command]
+pub async fn remove_knowledge_folder(
+    state: State<'_, SharedState>,
+    id: String,
+) -> AppResult<()> {
+    let state = state.get();
+    if !state.knowledge_repo.delete_folder(&id).await.map_err(AppError::from)? {
+        return Err(folder_not_found(&id));
+    }
+    state.knowledge_indexer.request(IndexRequest::Rewatch);
+    info!("Dossier {} retiré de la base de connaissances", id);
+    Ok(())
+}
+
+/// Demande la réindexation des fichiers modifiés d'un dossier
+#[tauri::command]
+pub async fn reindex_knowledge_folder(
+    state: State<'_, SharedState>,
+    id: String,
+) -> AppResult<()> {
+    let state = state.get();
+    if state.knowledge_repo.get_folder(&id).await.map_err(AppError::from)?.is_none() {
+        return Err(folder_not_found(&id));
+    }
+    state.knowledge_indexer.request(IndexRequest::Folder(id));
+    Ok(())
+}
+
+/// Choisit le reranker (fichier GGUF du dossier des modèles) d'un dossier, ou le retire
+#[tauri::command]
+pub async fn set_knowledge_reranker(
+    state: State<'_, SharedState>,
+    id: String,
+    model_name: Option<String>,
+) -> AppResult<KnowledgeFolder> {
+    let state = state.get();
+    if let Some(model_name) = &model_name {
+        if !state.model_manager.model_exists(model_name) {
+            return Err(AppError::ModelNotFound { model: model_name.clone() });
+        }
+    }
+    if !state.knowledge_repo.set_reranker(&id, model_name.as_deref()).await.map_err(AppError::from)? {
+        return Err(folder_not_found(&id));
+    }
+
+    let folders = state.knowledge_repo.list_folders().await.map_err(AppError::from)?;
+    // Plus aucun dossier ne l'utilise : le reranker libère sa mémoire
+    if folders.iter().all(|folder| folder.reranker_model.is_none()) {
+        if let Err(e) = state.llm_engine.unload_reranker().await {
+            warn!("Déchargement du reranker impossible: {:#}", e);
+        }
+    }
+    info!("Reranker du dossier {}: {:?}", id, model_name);
+    folders.into_iter()
+        .find(|folder| folder.id == id)
+        .ok_or_else(|| folder_not_found(&id))
+}
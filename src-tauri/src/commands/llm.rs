@@ -1,23 +1,52 @@
-use crate::AppState;
+use crate::{AppState, SharedState};
+use crate::error::{AppError, AppResult};
 use crate::context;
+use crate::context::knowledge::IndexRequest;
+use crate::llm::{BackendKind, ChatMessage, ChatRole, ContextBreakdown, ContextComponent, ContextComponentKind, GenerationPriority, GuardrailPolicy, LLMEngine, LLMResponse, ModelCapabilities, PostProcessConfig, ReasoningMode, ReasoningSplitter, SamplingConfig, SamplingParams, StreamPiece, SwitchStage, SwitchStatus, TokenLogprob};
+use crate::llm::context_usage;
+use crate::llm::logprobs::MAX_TOP_LOGPROBS;
+use crate::llm::prompt_cache::PromptCacheStats;
+use crate::events::{emit_event, ContextOverflow, ContextTruncated, EventSink, GuardrailTriggered, LlmReasoning, LlmToken, ReasoningPhase};
+use super::attachments::{decode_uploads, include_documents, include_knowledge, index_documents, AttachmentUpload};
+use super::backend::remote_backend;
+use super::memories::{inject_memories, spawn_memory_extraction};
+use super::session::{clear_draft, localize_prompt, session_language, spawn_title_generation, DEFAULT_SESSION_TITLE};
 use std::sync::Arc;
-use tauri::State;
-use tracing::{info, error};
+use tauri::{AppHandle, State};
+use tracing::{info, error, warn};
+
+/// Smallest context size a session can request
+const MIN_SESSION_CONTEXT: usize = 512;
+
+/// Longest token batching window: beyond it the reply visibly stutters
+const MAX_STREAM_PACING_MS: u64 = 250;
 
 #[tauri::command]
 pub async fn initialize_llm(
-    state: State<'_, Arc<AppState>>,
-) -> Result<String, String> {
+    state: State<'_, SharedState>,
+) -> AppResult<String> {
+    let state = state.get();
+    load_last_model(&state).await
+}
+
+/// Load the model saved in the settings (or the default model of the config file),
+/// with the persisted engine settings
+pub async fn load_last_model(state: &AppState) -> AppResult<String> {
     let model_to_load = match state.settings_repo.get_current_model().await {
         Ok(Some(saved_model)) => {
             info!("Loading last used model: {}", saved_model);
             saved_model
         }
-        Ok(None) => {
-            return Err("No previous model found in settings. Please select a model first.".to_string());
-        }
+        // No model chosen in the app yet: default model of agents-rs.toml
+        Ok(None) => match state.file_config.default_model.clone() {
+            Some(default_model) => {
+                info!("Loading default model from the config file: {}", default_model);
+                default_model
+            }
+            None => return Err(AppError::NoModelLoaded),
+        },
         Err(e) => {
-            return Err(format!("Failed to retrieve saved model: {}", e));
+            return Err(AppError::from(e.context("Failed to retrieve saved model")));
         }
     };
     
@@ -25,172 +54,1094 @@ pub async fn initialize_llm(
     
     // Check if model exists
     if !state.model_manager.model_exists(&model_to_load) {
-        return Err(format!("Model file not found: {}. Please ensure the model is in the models directory.", model_to_load));
+        return Err(AppError::ModelNotFound { model: model_to_load });
     }
     
     // Get full path to model
     let model_path = state.model_manager.get_model_path(&model_to_load);
     
-    // Update the model path in the existing engine
-    let engine = state.llm_engine.read().await;
+    // Apply persisted settings before loading
+    if let Ok(Some((entries, size_mb))) = state.settings_repo.get_prompt_cache_limits().await {
+        state.llm_engine.configure_prompt_cache(entries, size_mb);
+    }
+    if let Ok(Some(sampling)) = state.settings_repo.get_sampling_config().await {
+        state.llm_engine.update_sampling_config(sampling);
+    }
     
-    // Update config and load model
-    {
-        let mut config = engine.config.clone();
-        config.model_path = model_path.to_string_lossy().to_string();
-        drop(engine); // Release read lock
-        
-        let mut engine_write = state.llm_engine.write().await;
-        engine_write.config = config;
-        engine_write.load_model().await.map_err(|e| e.to_string())?;
+    let mut config = state.llm_engine.config().model;
+    config.model_path = model_path.to_string_lossy().to_string();
+    if let Ok(Some(context_shift)) = state.settings_repo.get_context_shift().await {
+        config.context_shift = context_shift;
     }
     
+    state.llm_engine.load_model(config).await.map_err(AppError::model_load)?;
+    record_model_load(state, &model_to_load).await;
+    
+    refresh_capabilities(state, &model_to_load, false).await;
+    
     // Return the loaded model name
     Ok(model_to_load)
 }
 
+/// Start switching to another model and return the id of the switch immediately
+///
+/// Each stage (unloading, loading, warming up) is emitted on "model-switch-progress".
 #[tauri::command]
 pub async fn switch_model(
-    state: State<'_, Arc<AppState>>,
+    app: AppHandle,
+    state: State<'_, SharedState>,
     model_name: String,
-) -> Result<String, String> {
+) -> AppResult<String> {
+    let state = state.get();
+    if !state.model_manager.models_directory().join(&model_name).exists() {
+        return Err(AppError::ModelNotFound { model: model_name });
+    }
+    
+    let status = state.model_switches.start(&model_name);
+    emit_event(&app, &status);
+    let task_id = status.task_id.clone();
+    
+    let state = Arc::clone(&state);
+    let switch_id = task_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let report = |stage: SwitchStage, error: Option<AppError>| {
+            if let Some(status) = state.model_switches.update(&switch_id, stage, error) {
+                emit_event(&app, &status);
+            }
+        };
+        
+        match load_model_with_progress(&state, &model_name, |stage| report(stage, None)).await {
+            Ok(()) => {
+                info!("Successfully switched to model: {}", model_name);
+                report(SwitchStage::Completed, None);
+            }
+            Err(e) => {
+                error!("Failed to switch to model {}: {}", model_name, e);
+                report(SwitchStage::Failed, Some(e));
+            }
+        }
+    });
+    
+    Ok(task_id)
+}
+
+/// Current stage of a model switch started by `switch_model`
+#[tauri::command]
+pub async fn get_switch_status(
+    state: State<'_, SharedState>,
+    task_id: String,
+) -> AppResult<SwitchStatus> {
+    let state = state.get();
+    state.model_switches
+        .get(&task_id)
+        .ok_or_else(|| AppError::invalid_input(format!("Changement de modèle inconnu: {}", task_id)))
+}
+
+/// Load a model from the models directory and make it the current model
+pub async fn load_model_by_name(state: &AppState, model_name: &str) -> AppResult<()> {
+    load_model_with_progress(state, model_name, |_| {}).await
+}
+
+/// Reload the model that was current before a comparison, a replay or an evaluation
+/// switched models, even after a failure (a reload failure is only logged)
+pub async fn restore_model(state: &AppState, previous: Option<String>) {
+    let Some(previous) = previous else {
+        return;
+    };
+    let loaded = state.settings_repo.get_current_model().await.ok().flatten();
+    if loaded.as_deref() != Some(previous.as_str()) {
+        if let Err(e) = load_model_by_name(state, &previous).await {
+            warn!("Impossible de recharger {}: {}", previous, e);
+        }
+    }
+}
+
+/// Same as `load_model_by_name`, reporting each stage to `on_stage`
+async fn load_model_with_progress(
+    state: &AppState,
+    model_name: &str,
+    mut on_stage: impl FnMut(SwitchStage),
+) -> AppResult<()> {
     info!("Switching to model: {}", model_name);
     
     let models_dir = state.model_manager.models_directory();
-    let model_path = models_dir.join(&model_name);
+    let model_path = models_dir.join(model_name);
     
     if !model_path.exists() {
-        return Err(format!("Model file not found: {}", model_name));
+        return Err(AppError::ModelNotFound { model: model_name.to_string() });
     }
     
-    // Update config and load model
-    {
-        let engine = state.llm_engine.read().await;
-        let mut config = engine.config.clone();
-        config.model_path = model_path.to_string_lossy().to_string();
-        drop(engine); // Release read lock
-        
-        let mut engine_write = state.llm_engine.write().await;
-        engine_write.config = config;
-        engine_write.load_model().await.map_err(|e| e.to_string())?;
+    if state.llm_engine.is_loaded() {
+        on_stage(SwitchStage::Unloading);
+        state.llm_engine.unload_model().await.map_err(AppError::from)?;
     }
     
+    // Update config and load model
+    on_stage(SwitchStage::Loading);
+    let mut config = state.llm_engine.config().model;
+    config.model_path = model_path.to_string_lossy().to_string();
+    state.llm_engine.load_model(config).await.map_err(AppError::model_load)?;
+    record_model_load(state, model_name).await;
+    // Les extraits de la base de connaissances sont recalculés avec ce modèle
+    state.knowledge_indexer.request(IndexRequest::All);
+    
+    on_stage(SwitchStage::WarmingUp);
+    refresh_capabilities(state, model_name, false).await;
+    
     // Persist current model to settings
-    if let Err(e) = state.settings_repo.set_current_model(&model_name).await {
+    if let Err(e) = state.settings_repo.set_current_model(model_name).await {
         error!("Failed to persist current model: {}", e);
     }
     
-    info!("Successfully switched to model: {}", model_name);
-    Ok(format!("Switched to model: {}", model_name))
+    Ok(())
+}
+
+/// Count a load for the usage-based sorting of the model list
+async fn record_model_load(state: &AppState, model_name: &str) {
+    if let Err(e) = state.model_usage_repo.record_load(model_name).await {
+        error!("Failed to record model usage: {}", e);
+    }
+}
+
+/// Restore the capabilities of a model from cache, or probe them after loading
+async fn refresh_capabilities(
+    state: &AppState,
+    model_name: &str,
+    force: bool,
+) -> Option<ModelCapabilities> {
+    if !force {
+        if let Ok(Some(cached)) = state.settings_repo.get_model_capabilities(model_name).await {
+            info!("Using cached capabilities for {}", model_name);
+            state.llm_engine.set_capabilities(cached.clone()).await;
+            return Some(cached);
+        }
+    }
+    
+    match state.llm_engine.probe_capabilities().await {
+        Ok(capabilities) => {
+            if let Err(e) = state.settings_repo.set_model_capabilities(model_name, &capabilities).await {
+                error!("Failed to cache model capabilities: {}", e);
+            }
+            Some(capabilities)
+        }
+        Err(e) => {
+            error!("Capability probing failed for {}: {}", model_name, e);
+            None
+        }
+    }
+}
+
+/// Capabilities of the loaded model, used by the UI to enable or disable features
+#[tauri::command]
+pub async fn get_model_capabilities(
+    state: State<'_, SharedState>,
+) -> AppResult<Option<ModelCapabilities>> {
+    let state = state.get();
+    Ok(state.llm_engine.capabilities().await)
+}
+
+/// Probe the loaded model again, ignoring the cached capabilities
+#[tauri::command]
+pub async fn probe_model_capabilities(
+    state: State<'_, SharedState>,
+) -> AppResult<ModelCapabilities> {
+    let state = state.get();
+    let model_name = state.settings_repo.get_current_model().await
+        .map_err(AppError::from)?
+        .ok_or(AppError::NoModelLoaded)?;
+    
+    refresh_capabilities(&state, &model_name, true).await
+        .ok_or_else(|| AppError::ModelLoadFailed {
+            message: format!("Failed to probe capabilities of {}", model_name),
+        })
 }
 
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct SendMessageResponse {
     pub user_message: context::Message,
     pub assistant_message: context::Message,
+    /// Log probabilities of the reply tokens (when enabled with `set_logprobs`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub logprobs: Option<Vec<TokenLogprob>>,
+    /// Document sources cited by the reply (`[n]` markers)
+    #[serde(default)]
+    pub citations: Vec<context::documents::Citation>,
+}
+
+/// Enable or disable context shifting (dropping the oldest tokens when the context is full)
+#[tauri::command]
+pub async fn set_context_shift(
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> AppResult<()> {
+    let state = state.get();
+    info!("Context shifting {}", if enabled { "enabled" } else { "disabled" });
+    
+    state.settings_repo.set_context_shift(enabled).await
+        .map_err(AppError::from)?;
+    state.llm_engine.update_config(|config| config.model.context_shift = enabled);
+    
+    Ok(())
+}
+
+/// Request a context size for a session (e.g. 8192 for long documents), None goes back
+/// to the configured size. Returns the size actually usable with the loaded model.
+#[tauri::command]
+pub async fn set_session_context_size(
+    state: State<'_, SharedState>,
+    session_id: String,
+    context_size: Option<usize>,
+) -> AppResult<usize> {
+    let state = state.get();
+    if context_size.is_some_and(|size| size < MIN_SESSION_CONTEXT) {
+        return Err(AppError::invalid_input(format!("The context size must be at least {} tokens", MIN_SESSION_CONTEXT)));
+    }
+    
+    state.settings_repo.set_session_context_size(&session_id, context_size).await
+        .map_err(AppError::from)?;
+    let usable = state.llm_engine.usable_context(context_size);
+    if context_size.is_some_and(|size| size > usable) {
+        info!("Session {} context capped to the trained size: {} tokens", session_id, usable);
+    } else {
+        info!("Session {} context size: {} tokens", session_id, usable);
+    }
+    
+    Ok(usable)
+}
+
+/// Token usage of the next prompt of a session by component (system prompt, memories,
+/// document excerpts, each message) and what context shifting would drop from it
+#[tauri::command]
+pub async fn get_context_breakdown(
+    state: State<'_, SharedState>,
+    session_id: String,
+) -> AppResult<ContextBreakdown> {
+    let state = state.get();
+    let session = {
+        let context_manager = state.context_manager.read().await;
+        context_manager.get_session(&session_id).await
+            .map_err(AppError::from)?
+    };
+    let query = session.messages.iter().rev()
+        .find(|m| m.role == context::MessageRole::User)
+        .map(|m| m.content.clone())
+        .unwrap_or_default();
+    
+    // Same prompt as the next generation, each part tagged with its origin
+    let mut messages: Vec<ChatMessage> = session.messages.iter().map(ChatMessage::from).collect();
+    let sources = include_documents(&state, None, &session.messages, &mut messages, &query).await?;
+    include_knowledge(&state, None, session.workspace_id.as_deref(), &mut messages, &query, sources).await?;
+    let mut parts: Vec<(ContextComponentKind, Option<&context::Message>)> = session.messages.iter()
+        .map(|m| match m.role {
+            context::MessageRole::System => (ContextComponentKind::SystemPrompt, Some(m)),
+            _ => (ContextComponentKind::Message, Some(m)),
+        })
+        .collect();
+    let count = messages.len();
+    localize_prompt(&mut messages, session.language);
+    if messages.len() > count {
+        parts.insert(0, (ContextComponentKind::SystemPrompt, None));
+    }
+    let position = messages.iter().take_while(|m| m.role == ChatRole::System).count();
+    let count = messages.len();
+    inject_memories(&state, &mut messages, &query).await;
+    if messages.len() > count {
+        parts.insert(position, (ContextComponentKind::Memories, None));
+    }
+    
+    let mut components = Vec::new();
+    let mut texts = Vec::new();
+    for (message, (kind, source)) in messages.iter().zip(parts) {
+        // Document excerpts are appended to the content of their message
+        let own = source.map_or(message.content.len(), |m| m.content.len().min(message.content.len()));
+        let mut component = ContextComponent::new(kind, 0);
+        if let Some(source) = source {
+            component = component.for_message(source.id.clone(), message.role);
+        }
+        components.push(component.clone());
+        texts.push(context_usage::chat_turn(message.role, &message.content[..own]));
+        if own < message.content.len() {
+            components.push(ContextComponent { kind: ContextComponentKind::Documents, ..component });
+            texts.push(message.content[own..].to_string());
+        }
+    }
+    
+    let counts = if state.llm_engine.is_loaded() {
+        state.llm_engine.count_tokens(texts.clone()).await.ok()
+    } else {
+        None
+    };
+    let estimated = counts.is_none();
+    let counts = counts.unwrap_or_else(|| texts.iter().map(|t| context_usage::estimate_tokens(t)).collect());
+    for (component, tokens) in components.iter_mut().zip(counts) {
+        component.tokens = tokens;
+    }
+    
+    let requested = state.settings_repo.get_session_context_size(&session_id).await
+        .map_err(AppError::from)?;
+    let n_ctx = state.llm_engine.usable_context(requested);
+    Ok(ContextBreakdown::new(n_ctx, components, estimated))
+}
+
+/// Report token log probabilities with `top_n` alternatives in local replies (None disables it)
+#[tauri::command]
+pub async fn set_logprobs(
+    state: State<'_, SharedState>,
+    top_n: Option<usize>,
+) -> AppResult<()> {
+    let state = state.get();
+    if top_n.is_some_and(|n| n > MAX_TOP_LOGPROBS) {
+        return Err(AppError::invalid_input(format!("At most {} alternatives per token", MAX_TOP_LOGPROBS)));
+    }
+    
+    info!("Token log probabilities: {:?}", top_n);
+    state.llm_engine.update_config(|config| config.sampling.logprobs = top_n);
+    
+    Ok(())
+}
+
+/// Use a vision projector (mmproj GGUF) with the model; None looks for one next to the model.
+/// Applied on the next model load.
+#[tauri::command]
+pub async fn set_vision_projector(
+    state: State<'_, SharedState>,
+    mmproj_path: Option<String>,
+) -> AppResult<()> {
+    let state = state.get();
+    if let Some(path) = &mmproj_path {
+        if !std::path::Path::new(path).exists() {
+            return Err(AppError::invalid_input(format!("Vision projector not found: {}", path)));
+        }
+    }
+    
+    info!("Vision projector: {:?}", mmproj_path);
+    state.llm_engine.update_config(|config| config.model.mmproj_path = mmproj_path);
+    
+    Ok(())
+}
+
+/// Current sampling settings of the local engine
+#[tauri::command]
+pub async fn get_sampling_config(
+    state: State<'_, SharedState>,
+) -> AppResult<SamplingConfig> {
+    let state = state.get();
+    Ok(state.llm_engine.config().sampling)
+}
+
+/// Change the sampling settings (temperature, top_p...) without reloading the model.
+/// Applied from the next generation on.
+#[tauri::command]
+pub async fn update_sampling_config(
+    state: State<'_, SharedState>,
+    sampling: SamplingConfig,
+) -> AppResult<SamplingConfig> {
+    let state = state.get();
+    sampling.validate().map_err(AppError::invalid_input)?;
+    if sampling.logprobs.is_some_and(|n| n > MAX_TOP_LOGPROBS) {
+        return Err(AppError::invalid_input(format!("At most {} alternatives per token", MAX_TOP_LOGPROBS)));
+    }
+    
+    info!("Sampling settings updated: {:?}", sampling);
+    state.settings_repo.set_sampling_config(&sampling).await
+        .map_err(AppError::from)?;
+    state.llm_engine.update_sampling_config(sampling.clone());
+    
+    Ok(sampling)
+}
+
+/// Current post-processing of local replies (reasoning blocks, template tokens, markdown)
+#[tauri::command]
+pub async fn get_postprocess_config(
+    state: State<'_, SharedState>,
+) -> AppResult<PostProcessConfig> {
+    let state = state.get();
+    Ok(state.llm_engine.config().postprocess)
+}
+
+/// Change the post-processing of local replies, applied from the next generation on
+#[tauri::command]
+pub async fn set_postprocess_config(
+    state: State<'_, SharedState>,
+    config: PostProcessConfig,
+) -> AppResult<()> {
+    let state = state.get();
+    info!("Post-processing settings updated: {:?}", config);
+    state.settings_repo.set_postprocess_config(&config).await
+        .map_err(AppError::from)?;
+    state.llm_engine.update_config(|engine| engine.postprocess = config);
+    
+    Ok(())
+}
+
+/// Milliseconds during which streamed tokens are grouped into one `llm-token` event
+#[tauri::command]
+pub async fn get_stream_pacing(
+    state: State<'_, SharedState>,
+) -> AppResult<u64> {
+    let state = state.get();
+    Ok(state.llm_engine.config().stream_pacing_ms)
+}
+
+/// Group streamed tokens into batches (0 disables it; 30-60 ms keeps the reply
+/// fluid while sparing the UI one event per token on fast GPUs)
+#[tauri::command]
+pub async fn set_stream_pacing(
+    state: State<'_, SharedState>,
+    pacing_ms: u64,
+) -> AppResult<()> {
+    let state = state.get();
+    if pacing_ms > MAX_STREAM_PACING_MS {
+        return Err(AppError::invalid_input(format!("Stream pacing is at most {} ms", MAX_STREAM_PACING_MS)));
+    }
+    state.settings_repo.set_stream_pacing_ms(pacing_ms).await
+        .map_err(AppError::from)?;
+    state.llm_engine.update_config(|engine| engine.stream_pacing_ms = pacing_ms);
+    
+    Ok(())
+}
+
+/// Guardrails applied to replies of sessions whose agent has no policy of its own
+#[tauri::command]
+pub async fn get_guardrail_policy(
+    state: State<'_, SharedState>,
+) -> AppResult<GuardrailPolicy> {
+    let state = state.get();
+    state.settings_repo.get_guardrail_policy().await
+        .map_err(AppError::from)
+}
+
+/// Change the global guardrails, applied from the next reply on
+#[tauri::command]
+pub async fn set_guardrail_policy(
+    state: State<'_, SharedState>,
+    policy: GuardrailPolicy,
+) -> AppResult<()> {
+    let state = state.get();
+    policy.validate().map_err(AppError::invalid_input)?;
+    info!("Guardrail policy updated: {:?}", policy);
+    state.settings_repo.set_guardrail_policy(&policy).await
+        .map_err(AppError::from)
+}
+
+/// Set the prompt cache size and eviction limits (0 disables the cache)
+#[tauri::command]
+pub async fn set_prompt_cache_settings(
+    state: State<'_, SharedState>,
+    max_entries: usize,
+    max_size_mb: usize,
+) -> AppResult<()> {
+    let state = state.get();
+    info!("Prompt cache limits: {} entries, {} MB", max_entries, max_size_mb);
+    
+    state.settings_repo.set_prompt_cache_limits(max_entries, max_size_mb).await
+        .map_err(AppError::from)?;
+    state.llm_engine.configure_prompt_cache(max_entries, max_size_mb);
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_prompt_cache_stats(
+    state: State<'_, SharedState>,
+) -> AppResult<PromptCacheStats> {
+    let state = state.get();
+    Ok(state.llm_engine.prompt_cache_stats())
+}
+
+#[tauri::command]
+pub async fn clear_prompt_cache(
+    state: State<'_, SharedState>,
+) -> AppResult<()> {
+    let state = state.get();
+    state.llm_engine.clear_prompt_cache();
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn send_message(
-    state: State<'_, Arc<AppState>>,
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    session_id: String,
+    content: String,
+    attachments: Option<Vec<AttachmentUpload>>,
+) -> AppResult<SendMessageResponse> {
+    let state = state.get();
+    exchange(app.into(), &state, session_id, content, attachments, false).await
+}
+
+/// Same as `send_message`, streaming the reply while it is generated: answer chunks
+/// on `llm-token`, reasoning on `llm-reasoning` (start, delta and end phases)
+#[tauri::command]
+pub async fn send_message_stream(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    session_id: String,
+    content: String,
+    attachments: Option<Vec<AttachmentUpload>>,
+) -> AppResult<SendMessageResponse> {
+    let state = state.get();
+    exchange(app.into(), &state, session_id, content, attachments, true).await
+}
+
+/// Add a user message to a session and generate the reply, with the documents,
+/// knowledge and memories of the session; shared by the commands and the CLI
+pub async fn exchange(
+    events: EventSink,
+    state: &Arc<AppState>,
     session_id: String,
     content: String,
-) -> Result<SendMessageResponse, String> {
+    attachments: Option<Vec<AttachmentUpload>>,
+    stream: bool,
+) -> AppResult<SendMessageResponse> {
     info!("Sending message for session: {}", session_id);
     
-    // 1. Add user message
-    let user_message = context::Message::new(context::MessageRole::User, content.clone());
+    // 1. Add user message (with its images and documents, if any)
+    let attachments = decode_uploads(state, attachments.unwrap_or_default()).await?;
+    let user_message = context::Message::new(context::MessageRole::User, content.clone())
+        .with_attachments(attachments);
     {
         let context_manager = state.context_manager.read().await;
         context_manager.add_message(&session_id, user_message.clone()).await
-            .map_err(|e| format!("Error adding message: {}", e))?;
+            .map_err(AppError::from)?;
     }
+    clear_draft(state, &session_id).await;
+    index_documents(state, &user_message.attachments).await?;
     
     // 2. Get complete session context
     let session = {
         let context_manager = state.context_manager.read().await;
         context_manager.get_session(&session_id).await
-            .map_err(|e| format!("Error retrieving session: {}", e))?
+            .map_err(AppError::from)?
     };
     
-    // 3. Build context for LLM
-    let mut context_str = String::new();
-    for message in &session.messages {
-        let role = match message.role {
-            context::MessageRole::System => "System",
-            context::MessageRole::User => "User",
-            context::MessageRole::Assistant => "Assistant",
-            context::MessageRole::Tool => "Tool",
-        };
-        context_str.push_str(&format!("{}: {}\n", role, message.content));
-    }
-    context_str.push_str("Assistant: ");
+    // 3. The session is the single source of truth for the conversation
+    let language = session_language(state, &session, &content).await;
+    let mut messages: Vec<ChatMessage> = session.messages.iter().map(ChatMessage::from).collect();
+    let sources = include_documents(state, Some(&session_id), &session.messages, &mut messages, &content).await?;
+    let sources = include_knowledge(state, Some(&session_id), session.workspace_id.as_deref(), &mut messages, &content, sources).await?;
+    // After the documents, which are matched to the session messages by position
+    localize_prompt(&mut messages, language);
+    inject_memories(state, &mut messages, &content).await;
+    
+    // 4. Generate response with the backend selected for this session
+    let backend_kind = state.settings_repo.get_session_backend(&session_id).await
+        .map_err(AppError::from)?;
     
-    // 4. Generate response with LLM
-    let response = {
-        let engine = state.llm_engine.read().await;
-        engine.generate(&context_str).await
-            .map_err(|e| format!("LLM generation error: {}", e))?
+    // With guardrails, a streamed reply is only shown once filtered
+    let policy = guardrail_policy(state, session.agent_id.as_deref()).await?;
+    let started = std::time::Instant::now();
+    let mut reply_stream = stream.then(|| ReplyStream::new(
+        events.clone(),
+        session_id.clone(),
+        state.llm_engine.config().postprocess.reasoning,
+        policy.is_enabled(),
+    ));
+    let sampling = session_sampling(state, &session).await?;
+    let generated = if backend_kind.is_local() {
+        let _permit = state.generation_scheduler
+            .acquire(Some(&session_id), GenerationPriority::High).await
+            .map_err(AppError::from)?;
+        let n_ctx = state.settings_repo.get_session_context_size(&session_id).await
+            .map_err(AppError::from)?;
+        if let Some(reply_stream) = reply_stream.as_mut() {
+            state.llm_engine.generate_stream_in_context(messages, sampling, n_ctx, |chunk| {
+                reply_stream.push(&chunk);
+                Ok(())
+            }).await
+                .map_err(|e| notify_overflow(&events, &session_id, AppError::generation(e)))
+        } else {
+            state.llm_engine.generate_in_context(messages, sampling, n_ctx).await
+                .map_err(|e| notify_overflow(&events, &session_id, AppError::generation(e)))
+        }
+    } else {
+        // Remote backends don't wait for the local engine, only for the session's previous request
+        let _permit = state.generation_scheduler
+            .acquire_session(&session_id, GenerationPriority::High).await
+            .map_err(AppError::from)?;
+        let backend = remote_backend(state, &backend_kind, sampling.as_ref()).await?;
+        backend.chat(&messages).await
+            .map_err(AppError::generation)
+            .inspect(|response| {
+                // Remote backends answer in one piece: forwarded as a single chunk
+                if let Some(reply_stream) = reply_stream.as_mut() {
+                    reply_stream.push_reply(response);
+                }
+            })
+    };
+    let mut response = match generated {
+        Ok(response) => response,
+        Err(error) => return Err(save_partial_reply(state, &session, error).await),
     };
+    record_inference(state, &session_id, &backend_kind, response.tokens_generated, started.elapsed()).await;
     
-    // 5. Add assistant response
-    let assistant_message = context::Message::new(context::MessageRole::Assistant, response.text.clone());
+    // Notify the UI that the oldest part of the conversation was dropped from the context
+    if response.truncated_tokens > 0 {
+        events.emit(&ContextTruncated {
+            session_id: session_id.clone(),
+            truncated_tokens: response.truncated_tokens,
+        });
+    }
+    
+    // 5. Guardrails run before anything is shown or persisted (message, title, memories)
+    if policy.is_enabled() {
+        let triggers = policy.apply_reply(&mut response.text, &mut response.reasoning);
+        if !triggers.is_empty() {
+            warn!("Guardrails modified the reply of session {}: {:?}", session_id, triggers);
+            events.emit(&GuardrailTriggered {
+                session_id: session_id.clone(),
+                triggers,
+            });
+        }
+    }
+    if let Some(reply_stream) = reply_stream.as_mut() {
+        reply_stream.release(&response);
+        reply_stream.finish();
+    }
+    
+    // 6. Add assistant response, with the document sources it cites
+    let citations = context::documents::cited_sources(&response.text, &sources);
+    let assistant_message = context::Message::new(context::MessageRole::Assistant, response.text.clone())
+        .with_reasoning(response.reasoning.clone())
+        .with_citations(&citations)
+        .with_truncated(!response.done);
     {
         let context_manager = state.context_manager.read().await;
         context_manager.add_message(&session_id, assistant_message.clone()).await
-            .map_err(|e| format!("Error adding response: {}", e))?;
+            .map_err(AppError::from)?;
+    }
+    
+    // Sessions still named by default get a title from their first exchange
+    let first_exchange = session.messages.iter().filter(|m| m.role == context::MessageRole::User).count() == 1;
+    if first_exchange && session.title == DEFAULT_SESSION_TITLE {
+        spawn_title_generation(events.clone(), Arc::clone(state), session_id.clone(), language, content.clone(), response.text.clone());
     }
+    spawn_memory_extraction(Arc::clone(state), session_id.clone(), content, response.text.clone());
     
     info!("Message sent and response generated for session {}", session_id);
     Ok(SendMessageResponse {
         user_message,
         assistant_message,
+        logprobs: response.logprobs,
+        citations,
     })
 }
 
+/// Keep the text generated before a failure as an incomplete assistant message,
+/// whose id is returned in the error for `continue_generation`
+async fn save_partial_reply(state: &AppState, session: &context::ConversationSession, error: AppError) -> AppError {
+    let AppError::GenerationFailed { message, partial_text: Some(mut text), .. } = error else {
+        return error;
+    };
+    if text.trim().is_empty() {
+        return AppError::GenerationFailed { message, partial_text: Some(text), message_id: None };
+    }
+    
+    if let Ok(policy) = guardrail_policy(state, session.agent_id.as_deref()).await {
+        if policy.is_enabled() {
+            text = policy.apply(&text).text;
+        }
+    }
+    let partial_message = context::Message::assistant(text.clone()).with_incomplete(true);
+    let context_manager = state.context_manager.read().await;
+    let saved = match context_manager.add_message(&session.id, partial_message).await {
+        Ok(()) => context_manager.get_session(&session.id).await.ok(),
+        Err(e) => {
+            warn!("Partial reply of session {} not saved: {}", session.id, e);
+            None
+        }
+    };
+    let message_id = saved
+        .and_then(|session| session.messages.last().and_then(|m| m.id.parse().ok()));
+    warn!("Generation failed in session {} after {} characters: {}", session.id, text.len(), message);
+    
+    AppError::GenerationFailed { message, partial_text: Some(text), message_id }
+}
+
+/// Resume an incomplete reply (generation failure) from its saved text. The
+/// message is updated in place; if generation fails again, the longer partial
+/// text is kept and the message stays incomplete.
+#[tauri::command]
+pub async fn continue_generation(
+    state: State<'_, SharedState>,
+    message_id: i64,
+) -> AppResult<context::Message> {
+    let state = state.get();
+    resume_reply(&state, message_id, context::Message::is_incomplete, "after a generation failure").await
+}
+
+/// Extend a reply stopped at `max_tokens`: the new text is appended to the same
+/// message, which stays `truncated` until the model ends its answer (EOS)
+#[tauri::command]
+pub async fn continue_message(
+    state: State<'_, SharedState>,
+    message_id: i64,
+) -> AppResult<context::Message> {
+    let state = state.get();
+    resume_reply(&state, message_id, context::Message::is_truncated, "when it was stopped by max_tokens").await
+}
+
+/// Continue the last assistant message of its session with the local engine
+async fn resume_reply(
+    state: &Arc<AppState>,
+    message_id: i64,
+    resumable: fn(&context::Message) -> bool,
+    condition: &str,
+) -> AppResult<context::Message> {
+    let session_id = {
+        let context_manager = state.context_manager.read().await;
+        context_manager.message_session(message_id).await
+            .map_err(AppError::from)?
+            .ok_or_else(|| AppError::invalid_input(format!("Message not found: {}", message_id)))?
+    };
+    let session = {
+        let context_manager = state.context_manager.read().await;
+        context_manager.get_session(&session_id).await
+            .map_err(AppError::from)?
+    };
+    let Some((message, history)) = session.messages.split_last() else {
+        return Err(AppError::invalid_input("The session has no messages"));
+    };
+    if message.id != message_id.to_string() || message.role != context::MessageRole::Assistant || !resumable(message) {
+        return Err(AppError::invalid_input(format!("Only the last reply of a session can be continued, {}", condition)));
+    }
+    let backend_kind = state.settings_repo.get_session_backend(&session_id).await
+        .map_err(AppError::from)?;
+    if !backend_kind.is_local() {
+        return Err(AppError::invalid_input("Replies can only be continued with the local engine"));
+    }
+    info!("Continuing reply {} of session {}", message_id, session_id);
+    
+    // Same context as the original generation, up to the reply being continued
+    let query = history.iter().rev()
+        .find(|m| m.role == context::MessageRole::User)
+        .map(|m| m.content.clone())
+        .unwrap_or_default();
+    let mut messages: Vec<ChatMessage> = history.iter().map(ChatMessage::from).collect();
+    let sources = include_documents(state, Some(&session_id), history, &mut messages, &query).await?;
+    include_knowledge(state, Some(&session_id), session.workspace_id.as_deref(), &mut messages, &query, sources).await?;
+    localize_prompt(&mut messages, session.language);
+    inject_memories(state, &mut messages, &query).await;
+    
+    let _permit = state.generation_scheduler
+        .acquire(Some(&session_id), GenerationPriority::High).await
+        .map_err(AppError::from)?;
+    let sampling = session_sampling(state, &session).await?;
+    let n_ctx = state.settings_repo.get_session_context_size(&session_id).await
+        .map_err(AppError::from)?;
+    let started = std::time::Instant::now();
+    let policy = guardrail_policy(state, session.agent_id.as_deref()).await?;
+    let apply_policy = |text: String| if policy.is_enabled() { policy.apply(&text).text } else { text };
+    
+    let (updated, result) = match state.llm_engine.continue_reply(messages, message.content.clone(), sampling, n_ctx).await {
+        Ok(response) => {
+            record_inference(state, &session_id, &backend_kind, response.tokens_generated, started.elapsed()).await;
+            let updated = context::Message { content: apply_policy(response.text), ..message.clone() }
+                .with_incomplete(false)
+                .with_truncated(!response.done)
+                .with_reasoning(response.reasoning.map(apply_policy));
+            (updated.clone(), Ok(updated))
+        }
+        Err(e) => match AppError::generation(e) {
+            AppError::GenerationFailed { message: error, partial_text: Some(text), .. } if text.len() > message.content.len() => {
+                let text = apply_policy(text);
+                let updated = context::Message { content: text.clone(), ..message.clone() }
+                    .with_incomplete(true)
+                    .with_truncated(false);
+                (updated, Err(AppError::GenerationFailed { message: error, partial_text: Some(text), message_id: Some(message_id) }))
+            }
+            error => return Err(error),
+        },
+    };
+    
+    let context_manager = state.context_manager.read().await;
+    context_manager.update_message(&session_id, &updated).await
+        .map_err(AppError::from)?;
+    result
+}
+
 #[tauri::command]
 pub async fn generate_response(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     session_id: String,
     prompt: String,
-) -> Result<String, String> {
+) -> AppResult<String> {
+    let state = state.get();
     info!("Generating response for session: {}", session_id);
     
     // Get the session with full context
-    let context_manager = state.context_manager.read().await;
-    let session = context_manager.get_session(&session_id).await
-        .map_err(|e| e.to_string())?;
-    
-    // Build context from message history
-    let mut context_str = String::new();
-    for message in &session.messages {
-        let role = match message.role {
-            context::MessageRole::System => "System",
-            context::MessageRole::User => "User",
-            context::MessageRole::Assistant => "Assistant",
-            context::MessageRole::Tool => "Tool",
-        };
-        context_str.push_str(&format!("{}: {}\n", role, message.content));
-    }
+    let session = {
+        let context_manager = state.context_manager.read().await;
+        context_manager.get_session(&session_id).await
+            .map_err(AppError::from)?
+    };
     
-    // Add current user message to context
-    context_str.push_str(&format!("User: {}\n", prompt));
+    // Build context from message history and add the current user message
+    let language = session_language(&state, &session, &prompt).await;
+    let mut messages: Vec<ChatMessage> = session.messages.iter().map(ChatMessage::from).collect();
+    let sources = include_documents(&state, Some(&session_id), &session.messages, &mut messages, &prompt).await?;
+    messages.push(ChatMessage::user(prompt.clone()));
+    include_knowledge(&state, Some(&session_id), session.workspace_id.as_deref(), &mut messages, &prompt, sources).await?;
+    localize_prompt(&mut messages, language);
+    inject_memories(&state, &mut messages, &prompt).await;
     
     // Generate response with full context
-    let engine = state.llm_engine.read().await;
-    let response = engine.generate(&context_str).await.map_err(|e| e.to_string())?;
+    let _permit = state.generation_scheduler
+        .acquire(Some(&session_id), GenerationPriority::Normal).await
+        .map_err(AppError::from)?;
+    let sampling = session_sampling(&state, &session).await?;
+    let n_ctx = state.settings_repo.get_session_context_size(&session_id).await
+        .map_err(AppError::from)?;
+    let started = std::time::Instant::now();
+    let mut response = state.llm_engine.generate_in_context(messages, sampling, n_ctx).await
+        .map_err(AppError::generation)?;
+    record_inference(&state, &session_id, &BackendKind::Local, response.tokens_generated, started.elapsed()).await;
     
+    let policy = guardrail_policy(&state, session.agent_id.as_deref()).await?;
+    if policy.is_enabled() {
+        policy.apply_reply(&mut response.text, &mut response.reasoning);
+    }
     Ok(response.text)
 }
 
+/// Forwards a streamed reply to the UI, telling reasoning and answer apart
+///
+/// When guardrails apply, nothing is forwarded while generating: the filtered
+/// reply is sent in one piece by `release`.
+struct ReplyStream {
+    events: EventSink,
+    session_id: String,
+    mode: ReasoningMode,
+    splitter: ReasoningSplitter,
+    guarded: bool,
+}
+
+impl ReplyStream {
+    fn new(events: EventSink, session_id: String, mode: ReasoningMode, guarded: bool) -> Self {
+        Self { events, session_id, mode, splitter: ReasoningSplitter::new(), guarded }
+    }
+    
+    fn push(&mut self, chunk: &str) {
+        if self.guarded {
+            return;
+        }
+        self.forward(chunk);
+    }
+    
+    /// Send the reply filtered by the guardrails (nothing was streamed yet)
+    fn release(&mut self, response: &LLMResponse) {
+        if self.guarded {
+            self.guarded = false;
+            self.push_reply(response);
+        }
+    }
+    
+    fn forward(&mut self, chunk: &str) {
+        if self.mode == ReasoningMode::Keep {
+            self.emit(StreamPiece::Answer(chunk.to_string()));
+            return;
+        }
+        for piece in self.splitter.push(chunk) {
+            self.emit(piece);
+        }
+    }
+    
+    /// Forward a reply received in one piece (remote backends)
+    fn push_reply(&mut self, response: &LLMResponse) {
+        if self.guarded {
+            return;
+        }
+        if let Some(reasoning) = &response.reasoning {
+            self.emit(StreamPiece::ReasoningStart);
+            self.emit(StreamPiece::Reasoning(reasoning.clone()));
+            self.emit(StreamPiece::ReasoningEnd);
+        }
+        self.forward(&response.text);
+    }
+    
+    fn finish(&mut self) {
+        for piece in self.splitter.finish() {
+            self.emit(piece);
+        }
+    }
+    
+    fn emit(&self, piece: StreamPiece) {
+        let (phase, chunk) = match piece {
+            StreamPiece::Answer(chunk) => {
+                self.events.emit(&LlmToken { session_id: self.session_id.clone(), chunk });
+                return;
+            }
+            // Reasoning discarded by the post-processing is not shown either
+            _ if self.mode == ReasoningMode::Strip => return,
+            StreamPiece::ReasoningStart => (ReasoningPhase::Start, None),
+            StreamPiece::Reasoning(chunk) => (ReasoningPhase::Delta, Some(chunk)),
+            StreamPiece::ReasoningEnd => (ReasoningPhase::End, None),
+        };
+        self.events.emit(&LlmReasoning { session_id: self.session_id.clone(), phase, chunk });
+    }
+}
+
+/// Record the time and hardware of a generation for `get_session_stats`
+async fn record_inference(
+    state: &AppState,
+    session_id: &str,
+    backend: &BackendKind,
+    tokens_generated: usize,
+    duration: std::time::Duration,
+) {
+    let config = state.llm_engine.config().model;
+    let model_name = match backend {
+        BackendKind::Local => state.settings_repo.get_current_model().await
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| "unknown".to_string()),
+        BackendKind::OpenAI { model } | BackendKind::Anthropic { model } => {
+            format!("{}:{}", backend.provider(), model)
+        }
+        #[cfg(any(test, feature = "mock-backend"))]
+        BackendKind::Mock => backend.provider().to_string(),
+    };
+    let stat = context::InferenceStat {
+        conversation_id: session_id.to_string(),
+        model_name,
+        local: backend.is_local(),
+        tokens_generated,
+        duration_ms: duration.as_millis() as u64,
+        n_threads: config.n_threads,
+        gpu: LLMEngine::gpu_offload_for(&config),
+    };
+    if let Err(e) = state.stats_repo.record(&stat).await {
+        warn!("Failed to record inference stats: {}", e);
+    }
+}
+
+/// Suggest summarizing the conversation when its prompt no longer fits in the context
+fn notify_overflow(events: &EventSink, session_id: &str, error: AppError) -> AppError {
+    if let AppError::ContextOverflow { prompt_tokens, n_ctx } = &error {
+        warn!("Context overflow in session {}: {} tokens for {}", session_id, prompt_tokens, n_ctx);
+        events.emit(&ContextOverflow {
+            session_id: session_id.to_string(),
+            prompt_tokens: *prompt_tokens,
+            n_ctx: *n_ctx,
+            suggestion: "Summarize the conversation, start a new one or enable context shifting".to_string(),
+        });
+    }
+    error
+}
+
+/// Sampling overrides of the agent a session was started from, or of its preset
+async fn session_sampling(state: &AppState, session: &context::ConversationSession) -> AppResult<Option<SamplingParams>> {
+    let Some(agent_id) = session.agent_id.as_deref() else {
+        return state.settings_repo.get_session_sampling(&session.id).await.map_err(AppError::from);
+    };
+    let agent = state.agent_repo.get(agent_id).await.map_err(AppError::from)?;
+    Ok(agent.map(|a| a.sampling))
+}
+
+/// Guardrails of the session's agent, or the global ones
+pub async fn guardrail_policy(state: &AppState, agent_id: Option<&str>) -> AppResult<GuardrailPolicy> {
+    if let Some(agent_id) = agent_id {
+        let agent = state.agent_repo.get(agent_id).await.map_err(AppError::from)?;
+        if let Some(policy) = agent.and_then(|a| a.guardrails) {
+            return Ok(policy);
+        }
+    }
+    state.settings_repo.get_guardrail_policy().await.map_err(AppError::from)
+}
+
+/// Generate independent responses for several prompts at once
+/// (titles, summaries, re-ranking...) without touching any session
+#[tauri::command]
+pub async fn generate_batch(
+    state: State<'_, SharedState>,
+    prompts: Vec<String>,
+) -> AppResult<Vec<String>> {
+    let state = state.get();
+    info!("Batch generation for {} prompts", prompts.len());
+    
+    let _permit = state.generation_scheduler
+        .acquire(None, GenerationPriority::Low).await
+        .map_err(AppError::from)?;
+    let responses = state.llm_engine.generate_batch(prompts).await.map_err(AppError::generation)?;
+    
+    Ok(responses.into_iter().map(|r| r.text).collect())
+}
+
+/// Current model and the context size usable with it
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct CurrentModel {
+    pub name: Option<String>,
+    /// Context size the model was trained with (None = no model loaded)
+    #[ts(type = "number | null")]
+    pub trained_context: Option<usize>,
+    /// Context size used for generations (of the session, when one is given)
+    #[ts(type = "number")]
+    pub usable_context: usize,
+}
+
 #[tauri::command]
 pub async fn get_current_model(
-    state: State<'_, Arc<AppState>>,
-) -> Result<Option<String>, String> {
-    state.settings_repo
+    state: State<'_, SharedState>,
+    session_id: Option<String>,
+) -> AppResult<CurrentModel> {
+    let state = state.get();
+    let name = state.settings_repo
         .get_current_model()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)?;
+    let requested = match &session_id {
+        Some(session_id) => state.settings_repo.get_session_context_size(session_id).await
+            .map_err(AppError::from)?,
+        None => None,
+    };
+    
+    Ok(CurrentModel {
+        name,
+        trained_context: state.llm_engine.trained_context(),
+        usable_context: state.llm_engine.usable_context(requested),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::documents::CITATION_INSTRUCTION;
+    use crate::context::Language;
+    use crate::state::test_state;
+    use base64::Engine;
+
+    #[tokio::test]
+    async fn test_exchange_documents_with_detected_language() {
+        let state = test_state().await;
+        let session_id = state.context_manager.read().await
+            .create_session("Documents".to_string()).await.unwrap();
+        state.settings_repo.set_session_backend(&session_id, &BackendKind::Mock).await.unwrap();
+
+        let upload = AttachmentUpload {
+            file_name: "notes.txt".to_string(),
+            mime_type: "text/plain".to_string(),
+            data: base64::engine::general_purpose::STANDARD.encode("Le code secret est 4217."),
+        };
+        let events = EventSink::Callback(Arc::new(|_, _| {}));
+        let content = "Comment est-ce que je lis un fichier avec Rust ?".to_string();
+        let response = exchange(events, &state, session_id.clone(), content.clone(), Some(vec![upload]), false)
+            .await
+            .unwrap();
+
+        // The mock echoes the user message: the document and the citation
+        // instruction are appended to it, not to the localized system prompt
+        let session = state.context_manager.read().await.get_session(&session_id).await.unwrap();
+        assert_eq!(session.language, Some(Language::French));
+        let echoed = response.assistant_message.content;
+        assert!(echoed.starts_with(&format!("Echo: {}", content)));
+        assert!(echoed.contains("<document name=\"notes.txt\">\n[1] Le code secret est 4217."));
+        assert!(echoed.ends_with(CITATION_INSTRUCTION));
+        assert_eq!(response.citations.len(), 1);
+    }
 }
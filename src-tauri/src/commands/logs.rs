@@ -0,0 +1,43 @@
+/// Commandes Tauri pour la journalisation (niveau, logs récents pour les rapports de bug)
+
+use crate::SharedState;
+use crate::error::{AppError, AppResult};
+use tauri::State;
+use tracing::info;
+
+/// Nombre de lignes renvoyées par défaut
+const DEFAULT_LOG_LINES: usize = 200;
+
+/// Change le niveau de log (`debug`, `warn`, ou directives `info,agents_rs=trace`), conservé au redémarrage
+#[tauri::command]
+pub async fn set_log_level(
+    state: State<'_, SharedState>,
+    level: String,
+) -> AppResult<()> {
+    let state = state.get();
+    let level = level.trim();
+    if let Some(log_handle) = &state.log_handle {
+        log_handle.set_level(level)
+            .map_err(|e| AppError::invalid_input(format!("{:#}", e)))?;
+    }
+    state.settings_repo.set_log_level(level).await.map_err(AppError::from)?;
+    info!("Niveau de log: {}", level);
+    Ok(())
+}
+
+/// Dernières lignes des fichiers de log, de la plus ancienne à la plus récente
+#[tauri::command]
+pub async fn get_recent_logs(
+    state: State<'_, SharedState>,
+    lines: Option<usize>,
+) -> AppResult<Vec<String>> {
+    let state = state.get();
+    let Some(handle) = state.log_handle.clone() else {
+        return Ok(vec![]);
+    };
+    let lines = lines.unwrap_or(DEFAULT_LOG_LINES);
+    tokio::task::spawn_blocking(move || handle.recent_lines(lines))
+        .await
+        .map_err(|e| AppError::Internal { message: e.to_string() })?
+        .map_err(AppError::from)
+}
@@ -0,0 +1,205 @@
+/// Commandes Tauri pour la mémoire à long terme (faits extraits des conversations)
+///
+/// Quand la mémoire est activée, chaque échange est analysé en arrière-plan et les
+/// faits retenus les plus proches du nouveau message sont injectés dans le prompt.
+
+use crate::{AppState, SharedState};
+use crate::context::memories::{self, Memory};
+use crate::error::{AppError, AppResult};
+use crate::llm::{ChatMessage, ChatRole, GenerationPriority};
+use super::backend::remote_backend;
+use std::sync::Arc;
+use tauri::State;
+use tracing::{debug, info, warn};
+
+#[tauri::command]
+pub async fn get_memory_enabled(
+    state: State<'_, SharedState>,
+) -> AppResult<bool> {
+    let state = state.get();
+    state.settings_repo.get_memory_enabled().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_memory_enabled(
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> AppResult<()> {
+    let state = state.get();
+    info!("Mémoire à long terme {}", if enabled { "activée" } else { "désactivée" });
+    state.settings_repo.set_memory_enabled(enabled).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn list_memories(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<Memory>> {
+    let state = state.get();
+    state.memory_repo.list().await.map_err(AppError::from)
+}
+
+/// Ajoute un fait à la main
+#[tauri::command]
+pub async fn add_memory(
+    state: State<'_, SharedState>,
+    content: String,
+) -> AppResult<Memory> {
+    let state = state.get();
+    let content = validate_content(content)?;
+    let embedding = embed_one(&state, &content).await;
+    let memory = Memory::new(content, embedding, None);
+    
+    state.memory_repo.save(&memory).await.map_err(AppError::from)?;
+    Ok(memory)
+}
+
+/// Modifie le contenu d'un fait (son embedding est recalculé)
+#[tauri::command]
+pub async fn update_memory(
+    state: State<'_, SharedState>,
+    memory_id: String,
+    content: String,
+) -> AppResult<Memory> {
+    let state = state.get();
+    let content = validate_content(content)?;
+    let mut memory = state.memory_repo.get(&memory_id).await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::invalid_input(format!("Souvenir inconnu: {}", memory_id)))?;
+    
+    memory.embedding = embed_one(&state, &content).await;
+    memory.content = content;
+    
+    state.memory_repo.save(&memory).await.map_err(AppError::from)?;
+    Ok(memory)
+}
+
+#[tauri::command]
+pub async fn delete_memory(
+    state: State<'_, SharedState>,
+    memory_id: String,
+) -> AppResult<bool> {
+    let state = state.get();
+    state.memory_repo.delete(&memory_id).await.map_err(AppError::from)
+}
+
+fn validate_content(content: String) -> AppResult<String> {
+    let content = content.trim().to_string();
+    if content.is_empty() {
+        return Err(AppError::invalid_input("Le souvenir est vide"));
+    }
+    Ok(content)
+}
+
+/// Embedding d'un texte avec le modèle local (None si aucun modèle n'est chargé)
+///
+/// Calculé pour une requête de l'utilisateur : passe avant les tâches de fond.
+pub(crate) async fn embed_one(state: &AppState, text: &str) -> Option<Vec<f32>> {
+    let embedded = state.generation_scheduler
+        .embed(&state.llm_engine, vec![text.to_string()], GenerationPriority::High).await;
+    match embedded {
+        Ok(mut embeddings) => embeddings.pop(),
+        Err(e) => {
+            debug!("Embedding indisponible: {:#}", e);
+            None
+        }
+    }
+}
+
+/// Insère les souvenirs pertinents après les messages système de la conversation
+pub(crate) async fn inject_memories(state: &AppState, messages: &mut Vec<ChatMessage>, query: &str) {
+    if !state.settings_repo.get_memory_enabled().await.unwrap_or(false) {
+        return;
+    }
+    let Some(embedding) = embed_one(state, query).await else {
+        return;
+    };
+    
+    let recalled = match state.memory_repo
+        .search(&embedding, memories::RECALL_LIMIT, memories::MIN_RECALL_SCORE)
+        .await
+    {
+        Ok(recalled) if !recalled.is_empty() => recalled,
+        Ok(_) => return,
+        Err(e) => {
+            warn!("Échec de la recherche de souvenirs: {:#}", e);
+            return;
+        }
+    };
+    
+    debug!("{} souvenirs injectés dans le prompt", recalled.len());
+    let recalled: Vec<Memory> = recalled.into_iter().map(|(memory, _)| memory).collect();
+    let position = messages.iter().take_while(|m| m.role == ChatRole::System).count();
+    messages.insert(position, memories::memory_prompt(&recalled));
+}
+
+/// Lance l'extraction des faits d'un échange en arrière-plan
+pub(crate) fn spawn_memory_extraction(
+    state: Arc<AppState>,
+    session_id: String,
+    user: String,
+    assistant: String,
+) {
+    tauri::async_runtime::spawn(async move {
+        match extract_memories(&state, &session_id, &user, &assistant).await {
+            Ok(0) => {}
+            Ok(count) => info!("{} nouveaux souvenirs extraits de la session {}", count, session_id),
+            Err(e) => warn!("Échec de l'extraction des souvenirs: {}", e),
+        }
+    });
+}
+
+async fn extract_memories(state: &AppState, session_id: &str, user: &str, assistant: &str) -> AppResult<usize> {
+    if !state.settings_repo.get_memory_enabled().await.map_err(AppError::from)? {
+        return Ok(0);
+    }
+    
+    // Même backend que la conversation, en priorité basse pour ne pas retarder l'utilisateur
+    let messages = memories::extraction_messages(user, assistant);
+    let backend_kind = state.settings_repo.get_session_backend(session_id).await
+        .map_err(AppError::from)?;
+    let response = if backend_kind.is_local() {
+        let _permit = state.generation_scheduler
+            .acquire(Some(session_id), GenerationPriority::Low).await
+            .map_err(AppError::from)?;
+        state.llm_engine.generate(messages).await.map_err(AppError::generation)?
+    } else {
+        remote_backend(state, &backend_kind, None).await?
+            .chat(&messages).await
+            .map_err(AppError::generation)?
+    };
+    
+    let facts = memories::parse_facts(&response.text);
+    if facts.is_empty() {
+        return Ok(0);
+    }
+    
+    let embeddings = state.generation_scheduler
+        .embed(&state.llm_engine, facts.clone(), GenerationPriority::Low).await
+        .ok();
+    let known: Vec<String> = state.memory_repo.list().await
+        .map_err(AppError::from)?
+        .into_iter()
+        .map(|m| m.content)
+        .collect();
+    
+    let mut saved = 0;
+    for (i, fact) in facts.into_iter().enumerate() {
+        let embedding = embeddings.as_ref().and_then(|e| e.get(i).cloned());
+        let duplicate = match &embedding {
+            Some(embedding) => !state.memory_repo
+                .search(embedding, 1, memories::DUPLICATE_SCORE).await
+                .map_err(AppError::from)?
+                .is_empty(),
+            None => known.contains(&fact),
+        };
+        if duplicate {
+            continue;
+        }
+        
+        let memory = Memory::new(fact, embedding, Some(session_id.to_string()));
+        state.memory_repo.save(&memory).await.map_err(AppError::from)?;
+        saved += 1;
+    }
+    
+    Ok(saved)
+}
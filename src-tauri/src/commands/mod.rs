@@ -5,14 +5,86 @@
 /// - session: Gestion des sessions de conversation
 /// - model: Gestion des modèles locaux et GPU
 /// - huggingface: Intégration avec HuggingFace Hub
+/// - backend: Sélection du backend de génération (local ou distant)
+/// - tools: Outils MCP et gestion de leurs secrets
+/// - pipelines: Enchaînements d'outils exposés comme un seul outil
+/// - agents: Profils d'agents (prompt, modèle, outils, échantillonnage)
+/// - presets: Modèles de sessions créées en un clic (revue de code, traduction...)
+/// - profile: Export et import du profil de l'application (réglages, agents, modèles de sessions)
+/// - tasks: Tâches autonomes exécutées avec les outils MCP
+/// - schedules: Tâches planifiées (expressions cron)
+/// - memories: Mémoire à long terme extraite des conversations
+/// - attachments: Pièces jointes des messages (images, documents)
+/// - documents: Bibliothèque de documents RAG (liste, suppression, réindexation, pondération de la recherche)
+/// - knowledge: Dossiers de la base de connaissances, surveillés et réindexés
+/// - workspaces: Espaces de travail (dossier de projet, agent par défaut)
+/// - logs: Niveau de journalisation et consultation des logs récents
+/// - compare: Comparaison de modèles (même prompt, rejeu de session)
+/// - eval: Suites d'évaluation des prompts, agents et modèles
+/// - setup: Assistant de premier lancement (matériel, modèle recommandé)
+/// - events: Schémas des événements émis vers l'interface
+/// - configuration: Configuration en vigueur (fichier agents-rs.toml et réglages enregistrés)
+/// - user_profiles: Profils d'utilisateur (une base de données par profil)
+/// - sharing: Partage des conversations (page HTML, fichier .agentchat chiffré)
+/// - sync: Synchronisation des conversations via un dossier partagé
+/// - api_tokens: Jetons d'accès au serveur local et leur portée
+/// - database: Maintenance de la base (sauvegarde, restauration, compactage, chiffrement, rétention)
 
 pub mod llm;
 pub mod session;
 pub mod model;
 pub mod huggingface;
+pub mod backend;
+pub mod tools;
+pub mod pipelines;
+pub mod database;
+pub mod agents;
+pub mod presets;
+pub mod profile;
+pub mod tasks;
+pub mod schedules;
+pub mod memories;
+pub mod attachments;
+pub mod documents;
+pub mod knowledge;
+pub mod workspaces;
+pub mod logs;
+pub mod compare;
+pub mod eval;
+pub mod setup;
+pub mod events;
+pub mod configuration;
+pub mod user_profiles;
+pub mod sharing;
+pub mod sync;
+pub mod api_tokens;
 
 // Re-export toutes les commandes pour faciliter l'importation
 pub use llm::*;
 pub use session::*;
 pub use model::*;
 pub use huggingface::*;
+pub use backend::*;
+pub use tools::*;
+pub use pipelines::*;
+pub use database::*;
+pub use agents::*;
+pub use presets::*;
+pub use profile::*;
+pub use tasks::*;
+pub use schedules::*;
+pub use memories::*;
+pub use attachments::*;
+pub use documents::*;
+pub use knowledge::*;
+pub use workspaces::*;
+pub use logs::*;
+pub use compare::*;
+pub use eval::*;
+pub use setup::*;
+pub use events::*;
+pub use configuration::*;
+pub use user_profiles::*;
+pub use sharing::*;
+pub use sync::*;
+pub use api_tokens::*;
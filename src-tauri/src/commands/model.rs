@@ -1,72 +1,352 @@
 /// Commandes Tauri pour la gestion des modèles
 
-use crate::AppState;
-use crate::llm::{LLMEngine, ModelInfo};
+use crate::{AppState, SharedState};
+use crate::context::ImportedModel;
+use crate::error::{AppError, AppResult};
+use crate::llm::threads::{logical_cores, physical_cores};
+use crate::llm::{sort_models, FlashAttention, ImportMode, LLMEngine, ModelInfo, ModelSort, SystemMetrics, ThreadSettings, TrashedModel};
+use serde::Serialize;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::State;
-use tracing::info;
+use std::time::Duration;
+use tracing::{info, warn};
+use ts_rs::TS;
 
+/// Lists the local models with their usage, favorites first (sorted by name by default)
 #[tauri::command]
 pub async fn list_models(
-    state: State<'_, Arc<AppState>>,
-) -> Result<Vec<ModelInfo>, String> {
+    state: State<'_, SharedState>,
+    sort: Option<ModelSort>,
+) -> AppResult<Vec<ModelInfo>> {
+    let state = state.get();
     info!("Listing available models");
     
-    state.model_manager
+    let mut models = state.model_manager
         .list_models()
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)?;
+    let usage = state.model_usage_repo.all().await.map_err(AppError::from)?;
+    for model in &mut models {
+        if let Some(usage) = usage.get(&model.file_name) {
+            model.load_count = usage.load_count;
+            model.last_used_at = usage.last_used_at;
+            model.favorite = usage.favorite;
+        }
+    }
+    sort_models(&mut models, sort.unwrap_or_default());
+    
+    Ok(models)
+}
+
+/// Imports a local GGUF file after checking its structure; it shows up in
+/// `list_models` right away
+#[tauri::command]
+pub async fn import_model(
+    state: State<'_, SharedState>,
+    path: String,
+    mode: Option<ImportMode>,
+) -> AppResult<ImportedModel> {
+    let state = state.get();
+    let mode = mode.unwrap_or_default();
+    info!("Importing model {} ({:?})", path, mode);
+    
+    // Validation and copy read the whole file: kept off the async runtime
+    let model_manager = Arc::clone(&state.model_manager);
+    let source = PathBuf::from(&path);
+    let (filename, header) = tokio::task::spawn_blocking(move || model_manager.import_model(&source, mode))
+        .await
+        .map_err(|e| AppError::Internal { message: e.to_string() })?
+        .map_err(AppError::from)?;
+    
+    let size_bytes = std::fs::metadata(state.model_manager.get_model_path(&filename))
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let imported = ImportedModel {
+        filename,
+        source_path: path,
+        linked: mode == ImportMode::Link,
+        size_bytes,
+        gguf_version: header.version,
+        tensor_count: header.tensor_count,
+        imported_at: chrono::Utc::now(),
+    };
+    state.imports_repo.record(&imported).await.map_err(AppError::from)?;
+    
+    Ok(imported)
 }
 
+#[tauri::command]
+pub async fn list_imported_models(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<ImportedModel>> {
+    let state = state.get();
+    state.imports_repo
+        .list()
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn favorite_model(
+    state: State<'_, SharedState>,
+    model_name: String,
+    favorite: bool,
+) -> AppResult<()> {
+    let state = state.get();
+    state.model_usage_repo
+        .set_favorite(&model_name, favorite)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Moves the model to the trash, see `restore_model`
 #[tauri::command]
 pub async fn delete_model(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     model_name: String,
-) -> Result<String, String> {
+) -> AppResult<String> {
+    let state = state.get();
     info!("Deleting model: {}", model_name);
     
     state.model_manager
         .delete_model(&model_name)
-        .map_err(|e| e.to_string())?;
+        .map_err(AppError::from)?;
     
-    Ok("Model deleted successfully".to_string())
+    Ok("Model moved to the trash".to_string())
+}
+
+#[tauri::command]
+pub async fn list_trashed_models(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<TrashedModel>> {
+    let state = state.get();
+    state.model_manager
+        .list_trash()
+        .map_err(AppError::from)
+}
+
+/// Moves the last deleted version of a model back from the trash
+#[tauri::command]
+pub async fn restore_model(
+    state: State<'_, SharedState>,
+    model_name: String,
+) -> AppResult<String> {
+    let state = state.get();
+    info!("Restoring model: {}", model_name);
+    
+    state.model_manager
+        .restore_model(&model_name)
+        .map_err(AppError::from)?;
+    
+    Ok("Model restored successfully".to_string())
+}
+
+/// Permanently deletes every trashed model, returns their file names
+#[tauri::command]
+pub async fn empty_model_trash(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<String>> {
+    let state = state.get();
+    let purged = state.model_manager
+        .purge_trash(None)
+        .map_err(AppError::from)?;
+    forget_downloads(&state, &purged).await;
+    Ok(purged)
+}
+
+#[tauri::command]
+pub async fn get_model_trash_days(
+    state: State<'_, SharedState>,
+) -> AppResult<u64> {
+    let state = state.get();
+    state.settings_repo
+        .get_model_trash_days()
+        .await
+        .map_err(AppError::from)
+}
+
+/// Days before trashed models are permanently deleted (0 = kept until the trash is emptied)
+#[tauri::command]
+pub async fn set_model_trash_days(
+    state: State<'_, SharedState>,
+    days: u64,
+) -> AppResult<()> {
+    let state = state.get();
+    state.settings_repo
+        .set_model_trash_days(days)
+        .await
+        .map_err(AppError::from)
+}
+
+/// RAM, VRAM and CPU usage, to warn before loading a model that would swap
+#[tauri::command]
+pub async fn get_system_metrics() -> AppResult<SystemMetrics> {
+    tokio::task::spawn_blocking(crate::llm::system::collect_metrics)
+        .await
+        .map_err(|e| AppError::Internal { message: e.to_string() })
+}
+
+#[tauri::command]
+pub async fn get_system_metrics_interval(
+    state: State<'_, SharedState>,
+) -> AppResult<u64> {
+    let state = state.get();
+    state.settings_repo
+        .get_system_metrics_interval()
+        .await
+        .map_err(AppError::from)
+}
+
+/// Seconds between `system-metrics` events (0 = disabled)
+#[tauri::command]
+pub async fn set_system_metrics_interval(
+    state: State<'_, SharedState>,
+    secs: u64,
+) -> AppResult<()> {
+    let state = state.get();
+    state.settings_repo
+        .set_system_metrics_interval(secs)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Purges the trashed models older than the configured period
+pub async fn purge_model_trash(state: &AppState) -> AppResult<Vec<String>> {
+    let days = state.settings_repo.get_model_trash_days().await.map_err(AppError::from)?;
+    if days == 0 {
+        return Ok(Vec::new());
+    }
+    let purged = state.model_manager
+        .purge_trash(Some(Duration::from_secs(days * 24 * 3600)))
+        .map_err(AppError::from)?;
+    forget_downloads(state, &purged).await;
+    Ok(purged)
+}
+
+/// Stops tracking the Hub revision of permanently deleted models
+async fn forget_downloads(state: &AppState, model_names: &[String]) {
+    for name in model_names {
+        if state.model_manager.model_exists(name) || state.model_manager.is_trashed(name) {
+            continue;
+        }
+        if let Err(e) = state.downloads_repo.delete(name).await {
+            warn!("Failed to forget the download of {}: {:#}", name, e);
+        }
+    }
 }
 
 #[tauri::command]
 pub async fn get_models_directory(
-    state: State<'_, Arc<AppState>>,
-) -> Result<String, String> {
+    state: State<'_, SharedState>,
+) -> AppResult<String> {
+    let state = state.get();
     let path = state.model_manager.models_directory();
     Ok(path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
 pub async fn get_gpu_info(
-    state: State<'_, Arc<AppState>>,
-) -> Result<String, String> {
-    let engine = state.llm_engine.read().await;
-    Ok(engine.gpu_info())
+    state: State<'_, SharedState>,
+) -> AppResult<String> {
+    let state = state.get();
+    Ok(state.llm_engine.gpu_info())
 }
 
 #[tauri::command]
-pub async fn detect_gpu() -> Result<(bool, String), String> {
+pub async fn detect_gpu() -> AppResult<(bool, String)> {
     let (available, info) = LLMEngine::detect_gpu_config();
     Ok((available, info))
 }
 
 #[tauri::command]
 pub async fn update_gpu_settings(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     use_gpu: bool,
     n_gpu_layers: Option<u32>,
-) -> Result<String, String> {
+) -> AppResult<String> {
+    let state = state.get();
     info!("Updating GPU settings: use_gpu={}, n_gpu_layers={:?}", use_gpu, n_gpu_layers);
     
-    let mut engine = state.llm_engine.write().await;
-    engine.config.use_gpu = use_gpu;
+    state.llm_engine.update_config(|config| {
+        config.model.use_gpu = use_gpu;
+        
+        if let Some(layers) = n_gpu_layers {
+            config.model.n_gpu_layers = layers;
+        }
+    });
+    
+    Ok("GPU settings updated successfully".to_string())
+}
+
+/// Batch sizes (0 = auto, based on the hardware) and flash attention of the contexts,
+/// used from the next generation on
+#[tauri::command]
+pub async fn update_batch_settings(
+    state: State<'_, SharedState>,
+    n_batch: u32,
+    n_ubatch: u32,
+    flash_attention: FlashAttention,
+) -> AppResult<()> {
+    let state = state.get();
+    if n_batch > 0 && n_ubatch > n_batch {
+        return Err(AppError::invalid_input("n_ubatch cannot be larger than n_batch"));
+    }
+    info!("Updating batch settings: n_batch={}, n_ubatch={}, flash_attention={:?}", n_batch, n_ubatch, flash_attention);
     
-    if let Some(layers) = n_gpu_layers {
-        engine.config.n_gpu_layers = layers;
+    state.llm_engine.update_config(|config| {
+        config.model.n_batch = n_batch;
+        config.model.n_ubatch = n_ubatch;
+        config.model.flash_attention = flash_attention;
+    });
+    
+    Ok(())
+}
+
+/// Thread settings and the detected CPU
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ThreadInfo {
+    pub settings: ThreadSettings,
+    #[ts(type = "number")]
+    pub physical_cores: usize,
+    #[ts(type = "number")]
+    pub logical_cores: usize,
+}
+
+#[tauri::command]
+pub async fn get_thread_settings(
+    state: State<'_, SharedState>,
+) -> AppResult<ThreadInfo> {
+    let state = state.get();
+    let settings = state.settings_repo.get_thread_settings().await
+        .map_err(AppError::from)?;
+    
+    Ok(ThreadInfo {
+        settings,
+        physical_cores: physical_cores(),
+        logical_cores: logical_cores(),
+    })
+}
+
+/// Thread counts (None = one per physical core) and efficiency mode,
+/// used from the next generation on
+#[tauri::command]
+pub async fn update_thread_settings(
+    state: State<'_, SharedState>,
+    settings: ThreadSettings,
+) -> AppResult<()> {
+    let state = state.get();
+    let max = logical_cores();
+    for n in [settings.n_threads, settings.n_threads_batch].into_iter().flatten() {
+        if n == 0 || n > max {
+            return Err(AppError::invalid_input(format!("Thread count must be between 1 and {}", max)));
+        }
     }
+    info!("Updating thread settings: {:?}", settings);
     
-    Ok("GPU settings updated successfully".to_string())
+    state.settings_repo.set_thread_settings(&settings).await
+        .map_err(AppError::from)?;
+    state.llm_engine.update_config(|config| settings.apply_to(&mut config.model));
+    
+    Ok(())
 }
@@ -0,0 +1,142 @@
+/// Commandes Tauri pour les pipelines d'outils (enchaînements exposés comme un outil)
+
+use crate::{AppState, SharedState};
+use crate::context::{Message, Workspace};
+use crate::error::{AppError, AppResult};
+use crate::mcp::{PipelineStep, ToolParameter, ToolPipeline};
+use super::workspaces::session_workspace;
+use tauri::State;
+use tracing::info;
+
+#[tauri::command]
+pub async fn create_pipeline(
+    state: State<'_, SharedState>,
+    name: String,
+    description: String,
+    parameters: Option<Vec<ToolParameter>>,
+    steps: Vec<PipelineStep>,
+) -> AppResult<ToolPipeline> {
+    let state = state.get();
+    let mut pipeline = ToolPipeline::new(name, description, steps);
+    pipeline.parameters = parameters.unwrap_or_default();
+    save_pipeline(&state, &pipeline, None).await?;
+    
+    info!("Pipeline créé: {} ({} étapes)", pipeline.name, pipeline.steps.len());
+    Ok(pipeline)
+}
+
+#[tauri::command]
+pub async fn list_pipelines(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<ToolPipeline>> {
+    let state = state.get();
+    state.pipeline_repo.list().await.map_err(AppError::from)
+}
+
+/// Met à jour un pipeline (renommé, l'outil change de nom)
+#[tauri::command]
+pub async fn update_pipeline(
+    state: State<'_, SharedState>,
+    pipeline: ToolPipeline,
+) -> AppResult<ToolPipeline> {
+    let state = state.get();
+    let existing = find_pipeline(&state, &pipeline.id).await?;
+    
+    let pipeline = ToolPipeline { created_at: existing.created_at, ..pipeline };
+    save_pipeline(&state, &pipeline, Some(&existing.name)).await?;
+    Ok(pipeline)
+}
+
+#[tauri::command]
+pub async fn delete_pipeline(
+    state: State<'_, SharedState>,
+    pipeline_id: String,
+) -> AppResult<bool> {
+    let state = state.get();
+    let Some(pipeline) = state.pipeline_repo.get(&pipeline_id).await.map_err(AppError::from)? else {
+        return Ok(false);
+    };
+    state.tool_registry.write().await.unregister_tool(&pipeline.name);
+    state.pipeline_repo.delete(&pipeline_id).await.map_err(AppError::from)
+}
+
+/// Exécute un pipeline depuis une conversation ; son résultat y est ajouté comme
+/// résultat d'outil
+#[tauri::command]
+pub async fn run_pipeline(
+    state: State<'_, SharedState>,
+    pipeline_id: String,
+    arguments: Option<serde_json::Value>,
+    session_id: Option<String>,
+) -> AppResult<String> {
+    let state = state.get();
+    let pipeline = find_pipeline(&state, &pipeline_id).await?;
+    // Hors conversation, les autorisations permanentes et le bloc-notes ne valent que pour cette exécution
+    let scope = match &session_id {
+        Some(session_id) => session_id.clone(),
+        None => format!("pipeline:{}", uuid::Uuid::new_v4()),
+    };
+    
+    // Dans une conversation d'un espace de travail, les outils de fichiers restent dans sa racine
+    let workspace = match &session_id {
+        Some(session_id) => session_workspace(&state, session_id).await?,
+        None => None,
+    };
+    let root = workspace.as_ref().map(Workspace::root);
+    
+    // Une session créée depuis un modèle n'a accès qu'aux outils de celui-ci
+    let session_tools = match &session_id {
+        Some(session_id) => state.settings_repo.get_session_tools(session_id).await.map_err(AppError::from)?,
+        None => Vec::new(),
+    };
+    
+    // Le verrou du registre est relâché avant l'appel, qui peut attendre une confirmation
+    let mut registry = state.tool_registry.read().await.snapshot();
+    if !session_tools.is_empty() {
+        registry = registry.restricted_to(&session_tools);
+    }
+    let output = registry
+        .execute_tool_in_root(&pipeline.name, arguments.unwrap_or_else(|| serde_json::json!({})), &scope, root)
+        .await;
+    if session_id.is_none() {
+        registry.end_scope(&scope).await;
+    }
+    let output = output.map_err(AppError::from)?;
+    
+    if let Some(session_id) = &session_id {
+        let message = Message::tool_result(pipeline.name.clone(), uuid::Uuid::new_v4().to_string(), output.clone());
+        state.context_manager
+            .read()
+            .await
+            .add_message(session_id, message)
+            .await
+            .map_err(AppError::from)?;
+    }
+    Ok(output)
+}
+
+/// Valide, enregistre dans le registre puis en base
+async fn save_pipeline(state: &AppState, pipeline: &ToolPipeline, previous_name: Option<&str>) -> AppResult<()> {
+    pipeline.validate().map_err(AppError::invalid_input)?;
+    
+    let mut registry = state.tool_registry.write().await;
+    let tools = registry.list_tools();
+    if let Some(step) = pipeline.steps.iter().find(|step| !tools.iter().any(|t| t.name == step.tool)) {
+        return Err(AppError::invalid_input(format!("Outil inconnu dans le pipeline: {}", step.tool)));
+    }
+    
+    registry.register_pipeline(pipeline.clone())
+        .map_err(|e| AppError::invalid_input(e.to_string()))?;
+    if let Some(previous_name) = previous_name.filter(|name| *name != pipeline.name) {
+        registry.unregister_tool(previous_name);
+    }
+    drop(registry);
+    
+    state.pipeline_repo.save(pipeline).await.map_err(AppError::from)
+}
+
+async fn find_pipeline(state: &AppState, pipeline_id: &str) -> AppResult<ToolPipeline> {
+    state.pipeline_repo.get(pipeline_id).await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::invalid_input(format!("Pipeline introuvable: {}", pipeline_id)))
+}
@@ -0,0 +1,132 @@
+/// Commandes Tauri pour les modèles de sessions (création en un clic)
+
+use crate::{AppState, SharedState};
+use crate::context::{ConversationSession, Message, SessionPreset};
+use crate::error::{AppError, AppResult};
+use crate::llm::SamplingParams;
+use chrono::Local;
+use tauri::State;
+use tracing::info;
+
+#[tauri::command]
+pub async fn create_preset(
+    state: State<'_, SharedState>,
+    name: String,
+    system_prompt: String,
+    title_pattern: Option<String>,
+    model: Option<String>,
+    sampling: Option<SamplingParams>,
+    tools: Option<Vec<String>>,
+) -> AppResult<SessionPreset> {
+    let state = state.get();
+    if name.trim().is_empty() {
+        return Err(AppError::invalid_input("Le nom du modèle de session est vide"));
+    }
+
+    let mut preset = SessionPreset::new(name, system_prompt);
+    if let Some(title_pattern) = title_pattern {
+        preset.title_pattern = title_pattern;
+    }
+    preset.model = model;
+    preset.sampling = sampling.unwrap_or_default();
+    preset.tools = tools.unwrap_or_default();
+
+    state.preset_repo.save(&preset).await.map_err(AppError::from)?;
+    info!("Modèle de session créé: {}", preset.name);
+    Ok(preset)
+}
+
+#[tauri::command]
+pub async fn list_presets(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<SessionPreset>> {
+    let state = state.get();
+    state.preset_repo.list().await.map_err(AppError::from)
+}
+
+/// Met à jour un modèle de session (les sessions déjà créées ne changent pas)
+#[tauri::command]
+pub async fn update_preset(
+    state: State<'_, SharedState>,
+    preset: SessionPreset,
+) -> AppResult<SessionPreset> {
+    let state = state.get();
+    let existing = find_preset(&state, &preset.id).await?;
+
+    let preset = SessionPreset { created_at: existing.created_at, ..preset };
+    state.preset_repo.save(&preset).await.map_err(AppError::from)?;
+    Ok(preset)
+}
+
+#[tauri::command]
+pub async fn delete_preset(
+    state: State<'_, SharedState>,
+    preset_id: String,
+) -> AppResult<bool> {
+    let state = state.get();
+    state.preset_repo.delete(&preset_id).await.map_err(AppError::from)
+}
+
+/// Crée une session à partir d'un modèle : titre, prompt système, modèle,
+/// paramètres d'échantillonnage et outils
+#[tauri::command]
+pub async fn create_session_from_preset(
+    state: State<'_, SharedState>,
+    preset_id: String,
+) -> AppResult<ConversationSession> {
+    let state = state.get();
+    let preset = find_preset(&state, &preset_id).await?;
+    info!("Création d'une session depuis le modèle: {}", preset.name);
+
+    if let Some(model) = preset.model.as_deref() {
+        let current = state.settings_repo.get_current_model().await.map_err(AppError::from)?;
+        if current.as_deref() != Some(model) {
+            super::llm::load_model_by_name(&state, model).await?;
+        }
+    }
+
+    let session_id = state.context_manager
+        .write()
+        .await
+        .create_session(preset.session_title(Local::now()))
+        .await
+        .map_err(AppError::from)?;
+
+    if !preset.system_prompt.is_empty() {
+        state.context_manager
+            .read()
+            .await
+            .add_message(&session_id, Message::system(preset.system_prompt.clone()))
+            .await
+            .map_err(AppError::from)?;
+    }
+    if preset.sampling != SamplingParams::default() {
+        state.settings_repo.set_session_sampling(&session_id, &preset.sampling).await
+            .map_err(AppError::from)?;
+    }
+    if !preset.tools.is_empty() {
+        state.settings_repo.set_session_tools(&session_id, &preset.tools).await
+            .map_err(AppError::from)?;
+    }
+
+    let manager = state.context_manager.read().await;
+    manager.get_session(&session_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Outils rattachés à une session créée depuis un modèle
+#[tauri::command]
+pub async fn get_session_tools(
+    state: State<'_, SharedState>,
+    session_id: String,
+) -> AppResult<Vec<String>> {
+    let state = state.get();
+    state.settings_repo.get_session_tools(&session_id).await.map_err(AppError::from)
+}
+
+async fn find_preset(state: &AppState, preset_id: &str) -> AppResult<SessionPreset> {
+    state.preset_repo.get(preset_id).await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::invalid_input(format!("Modèle de session introuvable: {}", preset_id)))
+}
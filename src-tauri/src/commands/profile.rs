@@ -0,0 +1,47 @@
+/// Commandes Tauri d'export et d'import du profil de l'application
+/// (réglages, agents et modèles de sessions, sans les modèles ni les conversations)
+
+use crate::SharedState;
+use crate::context::{profile, ProfileImport};
+use crate::error::{AppError, AppResult};
+use std::path::PathBuf;
+use tauri::State;
+use tracing::info;
+
+/// Enregistre le profil dans un fichier JSON
+#[tauri::command]
+pub async fn export_profile(
+    state: State<'_, SharedState>,
+    path: String,
+) -> AppResult<()> {
+    let state = state.get();
+    let profile = profile::collect(&state.settings_repo, &state.agent_repo, &state.preset_repo).await
+        .map_err(AppError::from)?;
+    profile::write(&profile, &PathBuf::from(&path)).map_err(AppError::from)?;
+    
+    info!("Profil exporté vers {}: {} réglages, {} agents", path, profile.settings.len(), profile.agents.len());
+    Ok(())
+}
+
+/// Importe un profil exporté sur une autre machine
+///
+/// Les réglages d'échantillonnage et de post-traitement s'appliquent tout de suite,
+/// les autres au prochain démarrage.
+#[tauri::command]
+pub async fn import_profile(
+    state: State<'_, SharedState>,
+    path: String,
+) -> AppResult<ProfileImport> {
+    let state = state.get();
+    let profile = profile::read(&PathBuf::from(&path)).map_err(AppError::from)?;
+    let report = profile::apply(&profile, &state.settings_repo, &state.agent_repo, &state.preset_repo).await
+        .map_err(AppError::from)?;
+    
+    if let Ok(Some(sampling)) = state.settings_repo.get_sampling_config().await {
+        state.llm_engine.update_sampling_config(sampling);
+    }
+    if let Ok(postprocess) = state.settings_repo.get_postprocess_config().await {
+        state.llm_engine.update_config(|config| config.postprocess = postprocess);
+    }
+    Ok(report)
+}
@@ -0,0 +1,100 @@
+/// Commandes Tauri pour les tâches planifiées (prompt ou tâche autonome à heure fixe)
+///
+/// Chaque exécution est signalée par l'événement "schedule-run".
+
+use crate::{AppState, SharedState};
+use crate::error::{AppError, AppResult};
+use crate::tasks::{ScheduledJob, TaskSchedule};
+use crate::tasks::schedules::next_occurrence;
+use chrono::{Local, Utc};
+use tauri::State;
+use tracing::info;
+
+#[tauri::command]
+pub async fn create_schedule(
+    state: State<'_, SharedState>,
+    name: String,
+    cron: String,
+    job: ScheduledJob,
+    agent_id: Option<String>,
+) -> AppResult<TaskSchedule> {
+    let state = state.get();
+    if name.trim().is_empty() {
+        return Err(AppError::invalid_input("Le nom de la planification est vide"));
+    }
+    
+    let mut schedule = TaskSchedule::new(name, cron, job)
+        .map_err(|e| AppError::invalid_input(format!("{:#}", e)))?;
+    schedule.agent_id = agent_id;
+    
+    state.schedule_repo.save(&schedule).await.map_err(AppError::from)?;
+    info!("Planification créée: {} ({})", schedule.name, schedule.cron);
+    Ok(schedule)
+}
+
+#[tauri::command]
+pub async fn list_schedules(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<TaskSchedule>> {
+    let state = state.get();
+    state.schedule_repo.list().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn pause_schedule(
+    state: State<'_, SharedState>,
+    schedule_id: String,
+) -> AppResult<TaskSchedule> {
+    let state = state.get();
+    set_schedule_enabled(&state, &schedule_id, false).await
+}
+
+/// Reprend une planification à partir de maintenant (les occurrences manquées sont ignorées)
+#[tauri::command]
+pub async fn resume_schedule(
+    state: State<'_, SharedState>,
+    schedule_id: String,
+) -> AppResult<TaskSchedule> {
+    let state = state.get();
+    set_schedule_enabled(&state, &schedule_id, true).await
+}
+
+#[tauri::command]
+pub async fn delete_schedule(
+    state: State<'_, SharedState>,
+    schedule_id: String,
+) -> AppResult<bool> {
+    let state = state.get();
+    state.schedule_repo.delete(&schedule_id).await.map_err(AppError::from)
+}
+
+/// Exécute immédiatement une planification, sans attendre sa prochaine occurrence
+#[tauri::command]
+pub async fn run_schedule_now(
+    state: State<'_, SharedState>,
+    schedule_id: String,
+) -> AppResult<TaskSchedule> {
+    let state = state.get();
+    let schedule = get_schedule(&state, &schedule_id).await?;
+    state.schedule_runner.run_schedule(schedule).await.map_err(AppError::from)
+}
+
+async fn get_schedule(state: &AppState, schedule_id: &str) -> AppResult<TaskSchedule> {
+    state.schedule_repo.get(schedule_id).await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::invalid_input(format!("Planification inconnue: {}", schedule_id)))
+}
+
+async fn set_schedule_enabled(state: &AppState, schedule_id: &str, enabled: bool) -> AppResult<TaskSchedule> {
+    let mut schedule = get_schedule(state, schedule_id).await?;
+    schedule.enabled = enabled;
+    if enabled {
+        schedule.next_run_at = Some(
+            next_occurrence(&schedule.cron, &Local, Utc::now()).map_err(AppError::from)?,
+        );
+    }
+    
+    state.schedule_repo.save(&schedule).await.map_err(AppError::from)?;
+    info!("Planification {} {}", schedule.name, if enabled { "reprise" } else { "en pause" });
+    Ok(schedule)
+}
@@ -1,107 +1,624 @@
 /// Commandes Tauri pour la gestion des sessions de conversation
 
-use crate::AppState;
-use crate::context::{ConversationSession, SessionSummary, Message, MessageRole};
+use crate::{AppState, SharedState};
+use crate::error::{AppError, AppResult};
+use crate::context::language::{self, Language};
+use crate::context::feedback::{self, FeedbackRating, MessageFeedback};
+use crate::context::training::{self, TrainingExport, TrainingFilter, TrainingFormat, TrainingOptions};
+use crate::context::{CipherState, ConversationRepository, ConversationSession, SessionStats, SessionSummary, Message, MessageRole};
+use crate::llm::threads::{logical_cores, physical_cores};
+use crate::llm::{ChatMessage, ChatRole, GenerationPriority, LLMEngine, SessionStatus};
+use super::backend::remote_backend;
+use super::llm::load_last_model;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use tauri::State;
-use tracing::info;
+use crate::events::{emit_event, EventSink, SessionTitle, StartupRestored};
+use tauri::{AppHandle, State};
+use tracing::{debug, info, warn};
+use ts_rs::TS;
+
+/// Titre donné par l'interface aux nouvelles sessions, remplacé par un titre généré
+pub const DEFAULT_SESSION_TITLE: &str = "New Conversation";
 
 #[tauri::command]
 pub async fn create_session(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     title: String,
-) -> Result<ConversationSession, String> {
+    agent_id: Option<String>,
+    workspace_id: Option<String>,
+) -> AppResult<ConversationSession> {
+    let state = state.get();
     info!("Création d'une nouvelle session: {}", title);
     
+    // Espace de travail : contexte du projet et agent par défaut
+    let workspace = match &workspace_id {
+        Some(id) => Some(super::workspaces::find_workspace(&state, id).await?),
+        None => None,
+    };
+    let agent_id = agent_id.or_else(|| workspace.as_ref().and_then(|w| w.default_agent_id.clone()));
+    
+    // Profil d'agent : prompt système, modèle par défaut et paramètres d'échantillonnage
+    let agent = match agent_id {
+        Some(agent_id) => Some(
+            state.agent_repo.get(&agent_id).await
+                .map_err(AppError::from)?
+                .ok_or_else(|| AppError::invalid_input(format!("Agent introuvable: {}", agent_id)))?
+        ),
+        None => None,
+    };
+    
+    if let Some(model) = agent.as_ref().and_then(|a| a.default_model.as_deref()) {
+        let current = state.settings_repo.get_current_model().await.map_err(AppError::from)?;
+        if current.as_deref() != Some(model) {
+            super::llm::load_model_by_name(&state, model).await?;
+        }
+    }
+    
     let session_id = state.context_manager
         .write()
         .await
-        .create_session(title)
+        .create_session_with_agent(title, agent.as_ref())
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(AppError::from)?;
+    
+    if let Some(workspace) = &workspace {
+        state.context_manager
+            .read()
+            .await
+            .attach_workspace(&session_id, workspace)
+            .await
+            .map_err(AppError::from)?;
+    }
     
     // Récupérer la session complète pour la retourner au frontend
-    state.context_manager
-        .read()
-        .await
-        .get_session(&session_id)
+    let manager = state.context_manager.read().await;
+    manager.get_session(&session_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn add_message(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     session_id: String,
     role: String,
     content: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
+    let state = state.get();
     let message_role = match role.as_str() {
         "system" => MessageRole::System,
         "user" => MessageRole::User,
         "assistant" => MessageRole::Assistant,
         "tool" => MessageRole::Tool,
-        _ => return Err("Rôle de message invalide".to_string()),
+        _ => return Err(AppError::invalid_input(format!("Rôle de message invalide: {}", role))),
     };
     
     let message = Message::new(message_role, content);
     
-    state.context_manager
-        .write()
-        .await
-        .add_message(&session_id, message)
+    let manager = state.context_manager.write().await;
+    manager.add_message(&session_id, message)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_session(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     session_id: String,
-) -> Result<ConversationSession, String> {
-    state.context_manager
+) -> AppResult<ConversationSession> {
+    let state = state.get();
+    let session = state.context_manager
         .read()
         .await
         .get_session(&session_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)?;
+    
+    // Dernière session ouverte, rouverte au démarrage si l'utilisateur le souhaite
+    if let Err(e) = state.settings_repo.set_last_session_id(&session_id).await {
+        warn!("Impossible d'enregistrer la dernière session: {}", e);
+    }
+    Ok(session)
+}
+
+/// Comportement au démarrage de l'application
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StartupSettings {
+    /// Rouvrir la dernière session active
+    pub restore_last_session: bool,
+    /// Charger le dernier modèle utilisé
+    pub load_last_model: bool,
 }
 
 #[tauri::command]
-pub async fn list_sessions(
-    state: State<'_, Arc<AppState>>,
-) -> Result<Vec<SessionSummary>, String> {
-    state.context_manager
+pub async fn get_startup_settings(
+    state: State<'_, SharedState>,
+) -> AppResult<StartupSettings> {
+    let state = state.get();
+    Ok(StartupSettings {
+        restore_last_session: state.settings_repo.get_restore_last_session().await.map_err(AppError::from)?,
+        load_last_model: state.settings_repo.get_load_last_model().await.map_err(AppError::from)?,
+    })
+}
+
+#[tauri::command]
+pub async fn set_startup_settings(
+    state: State<'_, SharedState>,
+    settings: StartupSettings,
+) -> AppResult<()> {
+    let state = state.get();
+    state.settings_repo.set_restore_last_session(settings.restore_last_session).await
+        .map_err(AppError::from)?;
+    state.settings_repo.set_load_last_model(settings.load_last_model).await
+        .map_err(AppError::from)
+}
+
+/// Rétablit la dernière session et le dernier modèle selon les réglages de démarrage,
+/// puis émet `startup-restored` avec ce qui a pu l'être
+pub async fn restore_startup(app: AppHandle, state: Arc<AppState>) {
+    let session_id = match state.settings_repo.get_restore_last_session().await {
+        Ok(true) => last_session(&state).await,
+        _ => None,
+    };
+    
+    let (model, model_error) = match state.settings_repo.get_load_last_model().await {
+        Ok(true) => match load_last_model(&state).await {
+            Ok(model) => (Some(model), None),
+            // Aucun modèle enregistré : rien à rétablir
+            Err(AppError::NoModelLoaded) => (None, None),
+            Err(e) => {
+                warn!("Impossible de charger le dernier modèle au démarrage: {}", e);
+                (None, Some(e))
+            }
+        },
+        _ => (None, None),
+    };
+    
+    info!("Démarrage rétabli: session {:?}, modèle {:?}", session_id, model);
+    emit_event(&app, &StartupRestored { session_id, model, model_error });
+}
+
+/// Dernière session active, si elle existe encore
+async fn last_session(state: &AppState) -> Option<String> {
+    let session_id = state.settings_repo.get_last_session_id().await.ok().flatten()?;
+    let exists = state.context_manager
         .read()
         .await
-        .list_sessions()
+        .get_session(&session_id)
+        .await
+        .is_ok();
+    exists.then_some(session_id)
+}
+
+#[tauri::command]
+pub async fn list_sessions(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<SessionSummary>> {
+    let state = state.get();
+    let manager = state.context_manager.read().await;
+    manager.list_sessions()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn delete_session(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     session_id: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
+    let state = state.get();
     state.context_manager
         .write()
         .await
         .delete_session(&session_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)?;
+    // Les autorisations permanentes d'outils ne survivent pas à la conversation
+    let registry = state.tool_registry.read().await.snapshot();
+    registry.end_scope(&session_id).await;
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn rename_session(
-    state: State<'_, Arc<AppState>>,
+    state: State<'_, SharedState>,
     session_id: String,
     new_title: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
+    let state = state.get();
+    let manager = state.context_manager.write().await;
+    manager.rename_session(&session_id, new_title)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Fixe la langue d'une session (None pour la redétecter au prochain message)
+#[tauri::command]
+pub async fn set_session_language(
+    state: State<'_, SharedState>,
+    session_id: String,
+    language: Option<Language>,
+) -> AppResult<()> {
+    let state = state.get();
+    let manager = state.context_manager.read().await;
+    manager.set_session_language(&session_id, language)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Activité d'une session : inactive, en file d'attente ou en cours de génération
+#[tauri::command]
+pub async fn get_session_status(
+    state: State<'_, SharedState>,
+    session_id: String,
+) -> AppResult<SessionStatus> {
+    let state = state.get();
+    Ok(state.generation_scheduler.session_status(&session_id))
+}
+
+/// Enregistre le texte en cours de saisie d'une session (un texte vide supprime le brouillon)
+#[tauri::command]
+pub async fn save_draft(
+    state: State<'_, SharedState>,
+    session_id: String,
+    text: String,
+) -> AppResult<()> {
+    let state = state.get();
+    conversation_repository(&state)
+        .save_draft(&session_id, &text)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Récupère le brouillon d'une session, s'il y en a un
+#[tauri::command]
+pub async fn get_draft(
+    state: State<'_, SharedState>,
+    session_id: String,
+) -> AppResult<Option<String>> {
+    let state = state.get();
+    conversation_repository(&state)
+        .get_draft(&session_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Bloc-notes de l'agent pour une session (outils `scratchpad_set` / `scratchpad_get`)
+#[tauri::command]
+pub async fn get_scratchpad(
+    state: State<'_, SharedState>,
+    session_id: String,
+) -> AppResult<BTreeMap<String, String>> {
+    let state = state.get();
+    conversation_repository(&state)
+        .get_session_state(&session_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Supprime le brouillon d'une session une fois son message envoyé
+pub(crate) async fn clear_draft(state: &AppState, session_id: &str) {
+    if let Err(e) = conversation_repository(state).save_draft(session_id, "").await {
+        warn!("Impossible de supprimer le brouillon de la session {}: {}", session_id, e);
+    }
+}
+
+/// Langue de la session, détectée sur le message de l'utilisateur si elle n'est pas encore connue
+pub(crate) async fn session_language(
+    state: &AppState,
+    session: &ConversationSession,
+    content: &str,
+) -> Option<Language> {
+    if session.language.is_some() {
+        return session.language;
+    }
+    let language = Language::detect(content)?;
+    
+    info!("Langue détectée pour la session {}: {}", session.id, language.code());
+    let context_manager = state.context_manager.read().await;
+    if let Err(e) = context_manager.set_session_language(&session.id, Some(language)).await {
+        warn!("Impossible d'enregistrer la langue de la session: {}", e);
+    }
+    Some(language)
+}
+
+/// Ajoute le prompt système localisé quand la conversation n'en a pas
+pub(crate) fn localize_prompt(messages: &mut Vec<ChatMessage>, language: Option<Language>) {
+    let Some(language) = language else { return };
+    if !messages.iter().any(|m| m.role == ChatRole::System) {
+        messages.insert(0, ChatMessage::system(language.system_prompt()));
+    }
+}
+
+/// Remplace le titre par défaut d'une session par un titre généré après le premier échange
+pub(crate) fn spawn_title_generation(
+    events: EventSink,
+    state: Arc<AppState>,
+    session_id: String,
+    language: Option<Language>,
+    user: String,
+    assistant: String,
+) {
+    tauri::async_runtime::spawn(async move {
+        match generate_title(&state, &session_id, language, &user, &assistant).await {
+            Ok(Some(title)) => {
+                events.emit(&SessionTitle { session_id, title });
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Échec de la génération du titre: {}", e),
+        }
+    });
+}
+
+async fn generate_title(
+    state: &AppState,
+    session_id: &str,
+    language: Option<Language>,
+    user: &str,
+    assistant: &str,
+) -> AppResult<Option<String>> {
+    // Même backend que la conversation, en priorité basse pour ne pas retarder l'utilisateur
+    let messages = language.unwrap_or(Language::English).title_messages(user, assistant);
+    let backend_kind = state.settings_repo.get_session_backend(session_id).await
+        .map_err(AppError::from)?;
+    let response = if backend_kind.is_local() {
+        let _permit = state.generation_scheduler
+            .acquire(Some(session_id), GenerationPriority::Low).await
+            .map_err(AppError::from)?;
+        state.llm_engine.generate(messages).await.map_err(AppError::generation)?
+    } else {
+        let _permit = state.generation_scheduler
+            .acquire_session(session_id, GenerationPriority::Low).await
+            .map_err(AppError::from)?;
+        remote_backend(state, &backend_kind, None).await?
+            .chat(&messages).await
+            .map_err(AppError::generation)?
+    };
+    
+    let Some(title) = language::parse_title(&response.text) else {
+        return Ok(None);
+    };
     state.context_manager
-        .write()
+        .read()
+        .await
+        .rename_session(session_id, title.clone())
+        .await
+        .map_err(AppError::from)?;
+    Ok(Some(title))
+}
+
+/// Matériel de la machine, pour interpréter les statistiques
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct HardwareInfo {
+    #[ts(type = "number")]
+    pub physical_cores: usize,
+    #[ts(type = "number")]
+    pub logical_cores: usize,
+    pub gpu: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct SessionStatsReport {
+    #[serde(flatten)]
+    pub stats: SessionStats,
+    pub hardware: HardwareInfo,
+}
+
+/// Temps d'inférence, débit et énergie estimée d'une session
+#[tauri::command]
+pub async fn get_session_stats(
+    state: State<'_, SharedState>,
+    session_id: String,
+) -> AppResult<SessionStatsReport> {
+    let state = state.get();
+    let stats = state.stats_repo.session_stats(&session_id).await
+        .map_err(AppError::from)?;
+    
+    Ok(SessionStatsReport {
+        stats,
+        hardware: HardwareInfo {
+            physical_cores: physical_cores(),
+            logical_cores: logical_cores(),
+            gpu: LLMEngine::detect_gpu_config().1,
+        },
+    })
+}
+
+/// Messages indexés par passe de la tâche de fond
+const EMBEDDING_BATCH_SIZE: i64 = 32;
+
+/// Nombre de résultats par défaut d'une recherche sémantique
+const DEFAULT_SEMANTIC_RESULTS: usize = 10;
+
+/// Message retrouvé par une recherche sémantique
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct MessageMatch {
+    pub conversation_id: String,
+    pub conversation_title: String,
+    #[ts(type = "number")]
+    pub message_id: i64,
+    pub role: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    /// Similarité cosinus avec la requête
+    pub score: f32,
+}
+
+/// Recherche les messages passés les plus proches du sens de la requête
+///
+/// Seuls les messages déjà indexés par le modèle chargé sont comparés.
+#[tauri::command]
+pub async fn semantic_search_conversations(
+    state: State<'_, SharedState>,
+    query: String,
+    k: Option<usize>,
+) -> AppResult<Vec<MessageMatch>> {
+    let state = state.get();
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(AppError::invalid_input("La recherche est vide"));
+    }
+    let model_name = embedding_model(&state).await.ok_or(AppError::NoModelLoaded)?;
+    let embedding = super::memories::embed_one(&state, query).await
+        .ok_or(AppError::NoModelLoaded)?;
+    
+    let repository = conversation_repository(&state);
+    let matches = repository
+        .semantic_search(&embedding, &model_name, k.unwrap_or(DEFAULT_SEMANTIC_RESULTS))
+        .await
+        .map_err(AppError::from)?;
+    
+    let mut titles: HashMap<String, String> = HashMap::new();
+    let mut results = Vec::with_capacity(matches.len());
+    for (message, score) in matches {
+        if !titles.contains_key(&message.conversation_id) {
+            let title = repository.get_conversation(&message.conversation_id).await
+                .map_err(AppError::from)?
+                .map(|c| c.title)
+                .unwrap_or_default();
+            titles.insert(message.conversation_id.clone(), title);
+        }
+        results.push(MessageMatch {
+            conversation_title: titles[&message.conversation_id].clone(),
+            conversation_id: message.conversation_id,
+            message_id: message.id.unwrap_or_default(),
+            role: message.role,
+            content: message.content,
+            created_at: message.created_at,
+            score,
+        });
+    }
+    
+    Ok(results)
+}
+
+/// Calcule les embeddings d'un lot de messages qui n'en ont pas encore
+///
+/// Appelée périodiquement en tâche de fond ; ne fait rien tant qu'aucun modèle
+/// n'est chargé ou que le contenu chiffré est verrouillé. Renvoie le nombre de
+/// messages indexés.
+pub async fn index_message_embeddings(state: &AppState) -> AppResult<usize> {
+    if matches!(*state.content_cipher.read().unwrap(), CipherState::Locked) {
+        return Ok(0);
+    }
+    let Some(model_name) = embedding_model(state).await else {
+        return Ok(0);
+    };
+    
+    let repository = conversation_repository(state);
+    let messages = repository
+        .messages_without_embedding(&model_name, EMBEDDING_BATCH_SIZE)
+        .await
+        .map_err(AppError::from)?;
+    if messages.is_empty() {
+        return Ok(0);
+    }
+    
+    let texts = messages.iter().map(|m| m.content.clone()).collect();
+    let embeddings = state.generation_scheduler
+        .embed(&state.llm_engine, texts, GenerationPriority::Low).await
+        .map_err(AppError::from)?;
+    for (message, embedding) in messages.iter().zip(&embeddings) {
+        let Some(id) = message.id else { continue };
+        repository.save_message_embedding(id, &model_name, embedding).await
+            .map_err(AppError::from)?;
+    }
+    
+    debug!("{} messages indexés pour la recherche sémantique", embeddings.len());
+    Ok(embeddings.len())
+}
+
+/// Évalue une réponse (pouce levé ou baissé) ; `rating` absent retire l'évaluation
+#[tauri::command]
+pub async fn rate_message(
+    state: State<'_, SharedState>,
+    message_id: i64,
+    rating: Option<FeedbackRating>,
+    comment: Option<String>,
+) -> AppResult<Option<MessageFeedback>> {
+    let state = state.get();
+    let repository = conversation_repository(&state);
+    match rating {
+        Some(rating) => repository.rate_message(message_id, rating, comment.as_deref()).await
+            .map(Some)
+            .map_err(|e| AppError::invalid_input(format!("{:#}", e))),
+        None => {
+            repository.delete_feedback(message_id).await.map_err(AppError::from)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Évaluations des messages d'une session, par identifiant de message
+#[tauri::command]
+pub async fn get_session_feedback(
+    state: State<'_, SharedState>,
+    session_id: String,
+) -> AppResult<HashMap<i64, MessageFeedback>> {
+    let state = state.get();
+    conversation_repository(&state)
+        .get_feedback(&session_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Exporte les réponses évaluées en JSONL (format de préférences `prompt`,
+/// `completion`, `label`) et renvoie le nombre d'exemples écrits
+#[tauri::command]
+pub async fn export_feedback_dataset(
+    state: State<'_, SharedState>,
+    path: String,
+) -> AppResult<usize> {
+    let state = state.get();
+    let exchanges = conversation_repository(&state)
+        .rated_exchanges()
         .await
-        .rename_session(&session_id, new_title)
+        .map_err(AppError::from)?;
+    tokio::fs::write(&path, feedback::to_preference_jsonl(&exchanges)).await
+        .map_err(|e| AppError::Internal { message: format!("Impossible d'écrire {}: {}", path, e) })?;
+    
+    info!("{} réponses évaluées exportées vers {}", exchanges.len(), path);
+    Ok(exchanges.len())
+}
+
+/// Exporte des conversations en JSONL pour l'affinage d'un modèle (format
+/// OpenAI ou ShareGPT), sans prompts système, anonymisées ou dédoublonnées
+/// selon `options`
+#[tauri::command]
+pub async fn export_training_data(
+    state: State<'_, SharedState>,
+    filter: TrainingFilter,
+    format: TrainingFormat,
+    options: Option<TrainingOptions>,
+    path: String,
+) -> AppResult<TrainingExport> {
+    let state = state.get();
+    let repository = conversation_repository(&state);
+    let (dataset, report) = training::export_training_data(&repository, &filter, format, &options.unwrap_or_default())
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)?;
+    tokio::fs::write(&path, dataset).await
+        .map_err(|e| AppError::Internal { message: format!("Impossible d'écrire {}: {}", path, e) })?;
+    
+    info!("{} conversations exportées vers {} ({:?})", report.examples, path, format);
+    Ok(report)
+}
+
+/// Modèle chargé dont les embeddings sont utilisés pour la recherche
+async fn embedding_model(state: &AppState) -> Option<String> {
+    if !state.llm_engine.is_loaded() {
+        return None;
+    }
+    state.settings_repo.get_current_model().await.ok().flatten()
+}
+
+fn conversation_repository(state: &AppState) -> ConversationRepository {
+    ConversationRepository::new(state.database.pool().clone())
+        .with_cipher(state.content_cipher.clone())
 }
@@ -0,0 +1,115 @@
+/// Commandes Tauri pour l'assistant de premier lancement
+///
+/// L'interface affiche l'assistant tant que `get_setup_status` indique qu'il n'a
+/// pas été terminé : résumé du matériel, modèle recommandé puis
+/// `setup_recommended_model` qui le télécharge, le charge et clôt l'assistant.
+
+use crate::SharedState;
+use crate::error::{AppError, AppResult};
+use crate::llm::recommend::{memory_budget, recommend_model};
+use crate::llm::system::collect_metrics;
+use crate::llm::threads::{logical_cores, physical_cores};
+use crate::llm::{GpuMemory, LLMEngine, RecommendedModel};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use tracing::info;
+use ts_rs::TS;
+
+/// Matériel détecté et modèle adapté
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct HardwareSummary {
+    #[ts(type = "number")]
+    pub physical_cores: usize,
+    #[ts(type = "number")]
+    pub logical_cores: usize,
+    #[ts(type = "number | null")]
+    pub total_memory_bytes: Option<u64>,
+    #[ts(type = "number | null")]
+    pub available_memory_bytes: Option<u64>,
+    pub gpus: Vec<GpuMemory>,
+    /// Accélération GPU disponible dans cette version (CUDA, Metal)
+    pub gpu_acceleration: bool,
+    pub gpu_info: String,
+    pub recommended_model: RecommendedModel,
+}
+
+/// Avancement de l'assistant de premier lancement
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct SetupStatus {
+    pub completed: bool,
+    /// Modèles déjà présents dans le dossier des modèles
+    #[ts(type = "number")]
+    pub installed_models: usize,
+    pub current_model: Option<String>,
+}
+
+async fn hardware_summary() -> AppResult<HardwareSummary> {
+    let metrics = tokio::task::spawn_blocking(collect_metrics)
+        .await
+        .map_err(|e| AppError::Internal { message: e.to_string() })?;
+    let (gpu_acceleration, gpu_info) = LLMEngine::detect_gpu_config();
+    Ok(HardwareSummary {
+        physical_cores: physical_cores(),
+        logical_cores: logical_cores(),
+        total_memory_bytes: metrics.total_memory_bytes,
+        available_memory_bytes: metrics.available_memory_bytes,
+        recommended_model: recommend_model(memory_budget(&metrics, gpu_acceleration)),
+        gpus: metrics.gpus,
+        gpu_acceleration,
+        gpu_info,
+    })
+}
+
+#[tauri::command]
+pub async fn get_setup_status(
+    state: State<'_, SharedState>,
+) -> AppResult<SetupStatus> {
+    let state = state.get();
+    Ok(SetupStatus {
+        completed: state.settings_repo.get_setup_completed().await.map_err(AppError::from)?,
+        installed_models: state.model_manager.list_models().map_err(AppError::from)?.len(),
+        current_model: state.settings_repo.get_current_model().await.map_err(AppError::from)?,
+    })
+}
+
+/// Cœurs, mémoire, GPU et modèle recommandé pour cette machine
+#[tauri::command]
+pub async fn get_hardware_summary() -> AppResult<HardwareSummary> {
+    hardware_summary().await
+}
+
+/// Télécharge le modèle recommandé (sauf s'il est déjà présent), le charge et
+/// termine l'assistant ; renvoie le nom du modèle chargé
+///
+/// La progression du téléchargement est émise sur "download-progress".
+#[tauri::command]
+pub async fn setup_recommended_model(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> AppResult<String> {
+    let state = state.get();
+    let model = hardware_summary().await?.recommended_model;
+    info!("Modèle recommandé: {} ({})", model.filename, model.repo_id);
+    
+    if !state.model_manager.models_directory().join(&model.filename).exists() {
+        super::huggingface::download_hub_model(&app, &state, &model.repo_id, &model.filename, None).await?;
+    }
+    super::llm::load_model_by_name(&state, &model.filename).await?;
+    
+    state.settings_repo.set_setup_completed(true).await
+        .map_err(AppError::from)?;
+    Ok(model.filename)
+}
+
+/// Termine (ou rouvre) l'assistant sans passer par le modèle recommandé
+#[tauri::command]
+pub async fn set_setup_completed(
+    state: State<'_, SharedState>,
+    completed: bool,
+) -> AppResult<()> {
+    let state = state.get();
+    state.settings_repo.set_setup_completed(completed).await
+        .map_err(AppError::from)
+}
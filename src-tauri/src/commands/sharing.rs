@@ -0,0 +1,73 @@
+/// Commandes Tauri de partage des conversations (page HTML ou fichier .agentchat chiffré)
+
+use crate::SharedState;
+use crate::context::sharing::{ShareFormat, SharedConversation};
+use crate::context::ConversationSession;
+use crate::error::{AppError, AppResult};
+use super::attachments::index_documents;
+use tauri::State;
+use tracing::info;
+
+/// Enregistre une conversation dans un fichier à envoyer à un collègue
+///
+/// Le format `encrypted` demande une phrase de passe, à transmettre séparément.
+/// Les pièces jointes sont incluses par défaut.
+#[tauri::command]
+pub async fn share_session(
+    state: State<'_, SharedState>,
+    session_id: String,
+    format: ShareFormat,
+    path: String,
+    passphrase: Option<String>,
+    include_attachments: Option<bool>,
+) -> AppResult<()> {
+    let state = state.get();
+    let session = state.context_manager.read().await
+        .get_session(&session_id)
+        .await
+        .map_err(AppError::from)?;
+    let shared = SharedConversation::from_session(&session, include_attachments.unwrap_or(true));
+    let content = shared.encode(format, passphrase.as_deref())
+        .map_err(|e| AppError::invalid_input(format!("{:#}", e)))?;
+    tokio::fs::write(&path, content).await
+        .map_err(|e| AppError::Internal { message: format!("Impossible d'écrire {}: {}", path, e) })?;
+    
+    info!("Session {} partagée vers {} ({:?}, {} messages)", session_id, path, format, shared.messages.len());
+    Ok(())
+}
+
+/// Importe une conversation partagée dans une nouvelle session
+///
+/// Le format est reconnu au contenu du fichier ; la phrase de passe n'est
+/// utilisée que pour les fichiers chiffrés.
+#[tauri::command]
+pub async fn import_shared_session(
+    state: State<'_, SharedState>,
+    path: String,
+    passphrase: Option<String>,
+) -> AppResult<ConversationSession> {
+    let state = state.get();
+    let content = tokio::fs::read(&path).await
+        .map_err(|e| AppError::invalid_input(format!("Impossible de lire {}: {}", path, e)))?;
+    let shared = SharedConversation::decode(&content, passphrase.as_deref())
+        .map_err(|e| AppError::invalid_input(format!("{:#}", e)))?;
+    let messages = shared.to_messages().map_err(|e| AppError::invalid_input(format!("{:#}", e)))?;
+    
+    let context_manager = state.context_manager.read().await;
+    let session_id = context_manager.create_detached_session(shared.title.clone(), None).await
+        .map_err(AppError::from)?;
+    if shared.language.is_some() {
+        context_manager.set_session_language(&session_id, shared.language).await
+            .map_err(AppError::from)?;
+    }
+    for message in messages {
+        let attachments = message.attachments.clone();
+        context_manager.add_message(&session_id, message).await.map_err(AppError::from)?;
+        if !attachments.is_empty() {
+            index_documents(&state, &attachments).await?;
+        }
+    }
+    
+    info!("Conversation importée depuis {}: {} ({} messages)", path, session_id, shared.messages.len());
+    context_manager.get_session(&session_id).await.map_err(AppError::from)
+}
@@ -0,0 +1,56 @@
+/// Commandes Tauri de synchronisation des conversations via un dossier partagé
+
+use crate::SharedState;
+use crate::context::{sync, ConversationRepository, SyncReport, SyncSettings};
+use crate::error::{AppError, AppResult};
+use tauri::State;
+use tracing::info;
+
+#[tauri::command]
+pub async fn get_sync_settings(
+    state: State<'_, SharedState>,
+) -> AppResult<SyncSettings> {
+    let state = state.get();
+    state.settings_repo
+        .get_sync_settings()
+        .await
+        .map_err(AppError::from)
+}
+
+/// Enregistre le dossier de synchronisation (pris en compte à la passe suivante)
+#[tauri::command]
+pub async fn set_sync_settings(
+    state: State<'_, SharedState>,
+    settings: SyncSettings,
+) -> AppResult<()> {
+    let state = state.get();
+    if settings.directory.as_ref().is_some_and(|directory| !directory.is_absolute()) {
+        return Err(AppError::invalid_input("Le dossier de synchronisation doit être un chemin absolu"));
+    }
+    info!("Sync settings updated: {:?}", settings);
+    state.settings_repo
+        .set_sync_settings(&settings)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Synchronise immédiatement, sans attendre la tâche de fond
+#[tauri::command]
+pub async fn sync_now(
+    state: State<'_, SharedState>,
+) -> AppResult<SyncReport> {
+    let state = state.get();
+    let settings = state.settings_repo.get_sync_settings().await.map_err(AppError::from)?;
+    let directory = settings.directory
+        .ok_or_else(|| AppError::invalid_input("Aucun dossier de synchronisation configuré"))?;
+    
+    let repository = ConversationRepository::new(state.database.pool().clone())
+        .with_cipher(state.content_cipher.clone());
+    let report = sync::sync_directory(&repository, &state.settings_repo, &directory)
+        .await
+        .map_err(AppError::from)?;
+    if report.changed_locally() {
+        state.context_manager.read().await.invalidate_cache().await;
+    }
+    Ok(report)
+}
@@ -0,0 +1,97 @@
+/// Commandes Tauri pour les tâches autonomes (boucle planifier / agir / observer)
+///
+/// La progression est émise sur l'événement "task-progress" pendant l'exécution.
+
+use crate::SharedState;
+use crate::error::{AppError, AppResult};
+use crate::llm::LLMBackend;
+use crate::tasks::{TaskLimits, TaskRun, TaskRunner};
+use std::sync::Arc;
+use crate::events::emit_event;
+use tauri::{AppHandle, State};
+use tracing::info;
+
+/// Exécute une tâche jusqu'à sa réponse finale ou l'atteinte d'une limite
+#[tauri::command]
+pub async fn run_task(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    goal: String,
+    agent_id: Option<String>,
+    workspace_id: Option<String>,
+    max_iterations: Option<u32>,
+    max_tokens: Option<usize>,
+) -> AppResult<TaskRun> {
+    let state = state.get();
+    if goal.trim().is_empty() {
+        return Err(AppError::invalid_input("L'objectif de la tâche est vide"));
+    }
+    if !state.llm_engine.is_loaded() {
+        return Err(AppError::NoModelLoaded);
+    }
+    
+    // Espace de travail : contexte du projet, agent par défaut et outils limités à la racine
+    let workspace = match &workspace_id {
+        Some(id) => Some(super::workspaces::find_workspace(&state, id).await?),
+        None => None,
+    };
+    let agent_id = agent_id.or_else(|| workspace.as_ref().and_then(|w| w.default_agent_id.clone()));
+    
+    let agent = match &agent_id {
+        Some(id) => Some(
+            state.agent_repo.get(id).await
+                .map_err(AppError::from)?
+                .ok_or_else(|| AppError::invalid_input(format!("Agent inconnu: {}", id)))?,
+        ),
+        None => None,
+    };
+    
+    let defaults = TaskLimits::default();
+    let limits = TaskLimits {
+        max_iterations: max_iterations.unwrap_or(defaults.max_iterations),
+        max_tokens: max_tokens.unwrap_or(defaults.max_tokens),
+    };
+    info!("Tâche lancée: {} ({} itérations max)", goal, limits.max_iterations);
+    
+    let backend: Arc<dyn LLMBackend> = Arc::new(state.llm_engine.clone());
+    let mut runner = TaskRunner::new(backend, Arc::clone(&state.tool_registry), Arc::clone(&state.task_repo))
+        .with_scheduler(state.generation_scheduler.clone())
+        .with_limits(limits);
+    if let Some(workspace) = workspace {
+        runner = runner.with_workspace(workspace);
+    }
+    
+    runner
+        .run(&goal, agent.as_ref(), |event| {
+            emit_event(&app, event);
+        })
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn list_task_runs(
+    state: State<'_, SharedState>,
+    limit: Option<i64>,
+) -> AppResult<Vec<TaskRun>> {
+    let state = state.get();
+    state.task_repo.list(limit.unwrap_or(50)).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_task_run(
+    state: State<'_, SharedState>,
+    run_id: String,
+) -> AppResult<Option<TaskRun>> {
+    let state = state.get();
+    state.task_repo.get(&run_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_task_run(
+    state: State<'_, SharedState>,
+    run_id: String,
+) -> AppResult<bool> {
+    let state = state.get();
+    state.task_repo.delete(&run_id).await.map_err(AppError::from)
+}
@@ -0,0 +1,216 @@
+/// Commandes Tauri pour les outils MCP et leurs secrets
+///
+/// Les valeurs des secrets ne sont jamais renvoyées au frontend ni écrites dans les logs.
+
+use crate::SharedState;
+use crate::context::ToolAuditEntry;
+use crate::error::{AppError, AppResult};
+use crate::mcp::{code, ApiConnector, ApprovalRequest, ContainerRunner, ContainerSettings, InjectionSettings, ToolPolicy};
+use std::collections::HashMap;
+use tauri::State;
+use tracing::info;
+
+#[tauri::command]
+pub async fn set_tool_secret(
+    state: State<'_, SharedState>,
+    tool: String,
+    key: String,
+    value: String,
+) -> AppResult<()> {
+    let state = state.get();
+    state.secret_store
+        .set_secret(&tool, &key, &value)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_tool_secret(
+    state: State<'_, SharedState>,
+    tool: String,
+    key: String,
+) -> AppResult<()> {
+    let state = state.get();
+    state.secret_store
+        .delete_secret(&tool, &key)
+        .map_err(AppError::from)
+}
+
+/// Liste les noms des secrets configurés pour un outil
+#[tauri::command]
+pub async fn list_tool_secrets(
+    state: State<'_, SharedState>,
+    tool: String,
+) -> AppResult<Vec<String>> {
+    let state = state.get();
+    state.secret_store
+        .list_secret_names(&tool)
+        .map_err(AppError::from)
+}
+
+/// Répond à une demande de confirmation ("tool-approval-request")
+#[tauri::command]
+pub async fn approve_tool_call(
+    state: State<'_, SharedState>,
+    call_id: String,
+    approved: bool,
+    always_allow: Option<bool>,
+) -> AppResult<()> {
+    let state = state.get();
+    state.tool_approvals
+        .respond(&call_id, approved, always_allow.unwrap_or(false))
+        .map_err(|e| AppError::invalid_input(e.to_string()))
+}
+
+/// Demandes de confirmation encore sans réponse
+#[tauri::command]
+pub async fn list_pending_tool_approvals(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<ApprovalRequest>> {
+    let state = state.get();
+    Ok(state.tool_approvals.pending())
+}
+
+/// Limites d'exécution des outils (appels par minute, durée, taille du résultat)
+#[tauri::command]
+pub async fn get_tool_policies(
+    state: State<'_, SharedState>,
+) -> AppResult<HashMap<String, ToolPolicy>> {
+    let state = state.get();
+    let registry = state.tool_registry.read().await;
+    Ok(registry.policies().clone())
+}
+
+/// Fixe les limites d'un outil (None pour les retirer), appliquées dès le prochain appel
+#[tauri::command]
+pub async fn set_tool_policy(
+    state: State<'_, SharedState>,
+    tool: String,
+    policy: Option<ToolPolicy>,
+) -> AppResult<()> {
+    let state = state.get();
+    if let Some(policy) = &policy {
+        policy.validate().map_err(AppError::invalid_input)?;
+    }
+    
+    let policies = {
+        let mut registry = state.tool_registry.write().await;
+        registry.set_policy(&tool, policy);
+        registry.policies().clone()
+    };
+    state.settings_repo.set_tool_policies(&policies).await
+        .map_err(AppError::from)
+}
+
+/// Détection des injections de prompt dans les résultats d'outils et les documents
+#[tauri::command]
+pub async fn get_injection_settings(
+    state: State<'_, SharedState>,
+) -> AppResult<InjectionSettings> {
+    let state = state.get();
+    let registry = state.tool_registry.read().await;
+    Ok(registry.injection_settings().clone())
+}
+
+/// Active la détection, et la confirmation des résultats d'outils signalés
+#[tauri::command]
+pub async fn set_injection_settings(
+    state: State<'_, SharedState>,
+    settings: InjectionSettings,
+) -> AppResult<()> {
+    let state = state.get();
+    state.tool_registry.write().await.set_injection_settings(settings.clone());
+    state.settings_repo.set_injection_settings(&settings).await
+        .map_err(AppError::from)
+}
+
+/// Signalements d'injection de prompt et décisions prises, les plus récents d'abord
+#[tauri::command]
+pub async fn get_tool_audit(
+    state: State<'_, SharedState>,
+    limit: Option<usize>,
+) -> AppResult<Vec<ToolAuditEntry>> {
+    let state = state.get();
+    state.tool_audit_repo.list(limit.unwrap_or(100)).await
+        .map_err(AppError::from)
+}
+
+/// Connecteurs d'API déclarés par l'utilisateur
+#[tauri::command]
+pub async fn list_api_connectors(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<ApiConnector>> {
+    let state = state.get();
+    state.settings_repo.get_api_connectors().await.map_err(AppError::from)
+}
+
+/// Crée ou remplace un connecteur d'API et l'enregistre comme outil
+#[tauri::command]
+pub async fn save_api_connector(
+    state: State<'_, SharedState>,
+    connector: ApiConnector,
+) -> AppResult<()> {
+    let state = state.get();
+    connector.validate().map_err(AppError::invalid_input)?;
+    
+    let mut connectors = state.settings_repo.get_api_connectors().await.map_err(AppError::from)?;
+    // Un connecteur ne remplace jamais un outil intégré ou un pipeline
+    state.tool_registry.write().await.register_connector(&connector)
+        .map_err(|e| AppError::invalid_input(e.to_string()))?;
+    connectors.retain(|c| c.name != connector.name);
+    info!("Connecteur d'API enregistré: {} {} {}", connector.name, connector.method, connector.base_url);
+    connectors.push(connector);
+    state.settings_repo.set_api_connectors(&connectors).await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_api_connector(
+    state: State<'_, SharedState>,
+    name: String,
+) -> AppResult<bool> {
+    let state = state.get();
+    let mut connectors = state.settings_repo.get_api_connectors().await.map_err(AppError::from)?;
+    let count = connectors.len();
+    connectors.retain(|c| c.name != name);
+    if connectors.len() == count {
+        return Ok(false);
+    }
+    
+    state.tool_registry.write().await.unregister_connector(&name);
+    state.settings_repo.set_api_connectors(&connectors).await
+        .map_err(AppError::from)?;
+    Ok(true)
+}
+
+/// Réglages de l'exécution de run_code en conteneur
+#[tauri::command]
+pub async fn get_container_settings(
+    state: State<'_, SharedState>,
+) -> AppResult<ContainerSettings> {
+    let state = state.get();
+    state.settings_repo.get_container_settings().await.map_err(AppError::from)
+}
+
+/// Enregistre les réglages et recrée l'outil run_code
+///
+/// Renvoie le moteur de conteneurs utilisé, `None` si run_code revient au bac à
+/// sable natif (désactivé ou aucun moteur disponible).
+#[tauri::command]
+pub async fn set_container_settings(
+    state: State<'_, SharedState>,
+    settings: ContainerSettings,
+) -> AppResult<Option<String>> {
+    let state = state.get();
+    if settings.python_image.trim().is_empty() || settings.node_image.trim().is_empty() {
+        return Err(AppError::invalid_input("Image de conteneur vide"));
+    }
+    state.settings_repo.set_container_settings(&settings).await
+        .map_err(AppError::from)?;
+    
+    let runner = ContainerRunner::detect(&settings).await;
+    let runtime = runner.as_ref().map(|r| r.runtime().to_string());
+    state.tool_registry.write().await
+        .register_tool(code::create_run_code_tool(runner))
+        .map_err(AppError::from)?;
+    Ok(runtime)
+}
@@ -0,0 +1,63 @@
+/// Commandes Tauri des profils d'utilisateur (une base par profil, modèles partagés)
+
+use crate::{AppConfig, AppState, SharedState};
+use crate::context::{ProfileStore, UserProfile};
+use crate::error::{AppError, AppResult};
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+use tracing::info;
+
+fn profile_store(state: &AppState) -> AppResult<&ProfileStore> {
+    state.profile_store.as_ref()
+        .ok_or_else(|| AppError::Internal { message: "Dossier des profils introuvable".to_string() })
+}
+
+/// Profils existants, le profil en cours étant marqué actif
+#[tauri::command]
+pub async fn list_profiles(state: State<'_, SharedState>) -> AppResult<Vec<UserProfile>> {
+    let state = state.get();
+    let mut profiles = profile_store(&state)?.list().map_err(AppError::from)?;
+    // Le profil ouvert peut différer du profil choisi pour le prochain démarrage
+    for profile in &mut profiles {
+        profile.active = profile.name == state.profile;
+    }
+    Ok(profiles)
+}
+
+/// Crée un profil vide ; sa base est créée à la première ouverture
+#[tauri::command]
+pub async fn create_profile(state: State<'_, SharedState>, name: String) -> AppResult<UserProfile> {
+    let state = state.get();
+    let profile = profile_store(&state)?.create(name.trim())
+        .map_err(|e| AppError::invalid_input(format!("{:#}", e)))?;
+    info!("Profil créé: {}", profile.name);
+    Ok(profile)
+}
+
+/// Passe sur un autre profil : un nouvel état est construit avec la base et
+/// les réglages de ce profil, puis remplace l'état courant, qui est fermé
+///
+/// Le profil ne devient actif (prochain démarrage) qu'une fois sa base ouverte.
+#[tauri::command]
+pub async fn switch_profile(app: AppHandle, state: State<'_, SharedState>, name: String) -> AppResult<()> {
+    let current = state.get();
+    if name == current.profile {
+        return Ok(());
+    }
+    let store = profile_store(&current)?;
+    store.ensure_exists(&name)
+        .map_err(|e| AppError::invalid_input(format!("{:#}", e)))?;
+    
+    let mut config = AppConfig::new().with_profile(name.clone());
+    if let Some(log_handle) = &current.log_handle {
+        config = config.with_log_handle(log_handle.clone());
+    }
+    let next = AppState::initialize(config).await
+        .map_err(|e| AppError::Internal { message: format!("Ouverture du profil {} impossible: {:#}", name, e) })?;
+    store.set_active(&name)
+        .map_err(|e| AppError::invalid_input(format!("{:#}", e)))?;
+    info!("Changement de profil: {} -> {}", current.profile, name);
+    
+    crate::switch_state(&app, &state, Arc::new(next)).await;
+    Ok(())
+}
@@ -0,0 +1,76 @@
+/// Commandes Tauri pour les espaces de travail (dossier de projet, agent par défaut)
+
+use crate::{AppState, SharedState};
+use crate::context::workspaces::validate_root;
+use crate::context::Workspace;
+use crate::error::{AppError, AppResult};
+use tauri::State;
+use tracing::info;
+
+#[tauri::command]
+pub async fn create_workspace(
+    state: State<'_, SharedState>,
+    name: String,
+    root_path: String,
+    default_agent_id: Option<String>,
+    index_id: Option<String>,
+) -> AppResult<Workspace> {
+    let state = state.get();
+    if name.trim().is_empty() {
+        return Err(AppError::invalid_input("Le nom de l'espace de travail est vide"));
+    }
+    let root = validate_root(&root_path).map_err(|e| AppError::invalid_input(e.to_string()))?;
+
+    if let Some(agent_id) = &default_agent_id {
+        state.agent_repo.get(agent_id).await
+            .map_err(AppError::from)?
+            .ok_or_else(|| AppError::invalid_input(format!("Agent introuvable: {}", agent_id)))?;
+    }
+
+    let mut workspace = Workspace::new(name, root.to_string_lossy().to_string());
+    workspace.default_agent_id = default_agent_id;
+    workspace.index_id = index_id;
+
+    state.workspace_repo.save(&workspace).await.map_err(AppError::from)?;
+    info!("Espace de travail créé: {} ({})", workspace.name, workspace.root_path);
+    Ok(workspace)
+}
+
+#[tauri::command]
+pub async fn list_workspaces(
+    state: State<'_, SharedState>,
+) -> AppResult<Vec<Workspace>> {
+    let state = state.get();
+    state.workspace_repo.list().await.map_err(AppError::from)
+}
+
+/// Supprime un espace de travail (ses conversations sont conservées)
+#[tauri::command]
+pub async fn delete_workspace(
+    state: State<'_, SharedState>,
+    workspace_id: String,
+) -> AppResult<bool> {
+    let state = state.get();
+    state.workspace_repo.delete(&workspace_id).await.map_err(AppError::from)
+}
+
+/// Espace de travail demandé, introuvable = entrée invalide
+pub(crate) async fn find_workspace(state: &AppState, workspace_id: &str) -> AppResult<Workspace> {
+    state.workspace_repo.get(workspace_id).await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::invalid_input(format!("Espace de travail introuvable: {}", workspace_id)))
+}
+
+/// Espace de travail de la conversation, s'il en a un
+pub(crate) async fn session_workspace(state: &AppState, session_id: &str) -> AppResult<Option<Workspace>> {
+    let workspace_id = {
+        let manager = state.context_manager.read().await;
+        manager.get_session(session_id).await
+            .map_err(AppError::from)?
+            .workspace_id
+    };
+    match workspace_id {
+        Some(workspace_id) => state.workspace_repo.get(&workspace_id).await.map_err(AppError::from),
+        None => Ok(None),
+    }
+}
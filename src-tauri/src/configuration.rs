@@ -0,0 +1,144 @@
+/// Fichier de configuration `agents-rs.toml` pour les utilisateurs avancés
+///
+/// Le fichier, facultatif, se trouve dans le dossier de configuration de
+/// l'application et n'est lu qu'au démarrage. Il fournit des valeurs par défaut :
+/// un réglage enregistré depuis l'interface (base de données) l'emporte sur le
+/// fichier, et les variables d'environnement des déploiements (RUST_LOG,
+/// `AGENTS_RS_MODELS_DIR`, `AGENTS_RS_MCP_PORT`) sur les deux.
+///
+/// ```toml
+/// models_dir = "/srv/models"
+/// default_model = "Qwen3-1.7B-Q4_K_M.gguf"
+/// mcp_port = 3000
+/// proxy = "socks5://127.0.0.1:1080"
+/// log_level = "info,agents_rs=debug"
+/// ```
+
+use anyhow::{Context, Result};
+use config::{Config, File, FileFormat};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+/// Nom du fichier dans le dossier de configuration
+pub const CONFIG_FILE_NAME: &str = "agents-rs.toml";
+
+/// Réglages du fichier, tous facultatifs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    /// Dossier des modèles GGUF
+    pub models_dir: Option<PathBuf>,
+    /// Modèle chargé quand aucun modèle n'a encore été choisi dans l'application
+    pub default_model: Option<String>,
+    /// Port du serveur MCP local (désactivé si absent)
+    pub mcp_port: Option<u16>,
+    /// Proxy HTTP ou SOCKS des requêtes vers HuggingFace
+    pub proxy: Option<String>,
+    /// Niveau de log (`warn`, `info,agents_rs=debug`...)
+    pub log_level: Option<String>,
+}
+
+impl FileConfig {
+    /// Lit le fichier ; un fichier absent donne la configuration vide
+    pub fn load(path: &Path) -> Result<Self> {
+        Config::builder()
+            .add_source(File::from(path).format(FileFormat::Toml).required(false))
+            .build()
+            .and_then(Config::try_deserialize)
+            .with_context(|| format!("Fichier de configuration invalide: {}", path.display()))
+    }
+}
+
+/// Emplacement du fichier dans le dossier de configuration de l'application
+pub fn default_config_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("com", "agents-rs", "AgentsRS")
+        .map(|dirs| dirs.config_dir().join(CONFIG_FILE_NAME))
+}
+
+/// Origine d'une valeur de la configuration effective
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    File,
+    /// Réglage enregistré depuis l'interface
+    Database,
+    /// Variable d'environnement
+    Environment,
+}
+
+/// Valeur retenue et la couche dont elle provient
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+pub struct ConfigEntry<T> {
+    pub value: Option<T>,
+    pub source: ConfigSource,
+}
+
+impl<T> ConfigEntry<T> {
+    /// Première couche définie, de la plus prioritaire à la moins prioritaire,
+    /// sinon `default`
+    pub fn resolve<const N: usize>(layers: [(ConfigSource, Option<T>); N], default: Option<T>) -> Self {
+        layers
+            .into_iter()
+            .find_map(|(source, value)| value.map(|value| Self { value: Some(value), source }))
+            .unwrap_or(Self { value: default, source: ConfigSource::Default })
+    }
+}
+
+/// Configuration en vigueur, avec l'origine de chaque valeur
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct EffectiveConfig {
+    /// Fichier lu au démarrage (qu'il existe ou non)
+    pub config_file: Option<PathBuf>,
+    pub models_dir: ConfigEntry<PathBuf>,
+    pub default_model: ConfigEntry<String>,
+    pub mcp_port: ConfigEntry<u16>,
+    pub proxy: ConfigEntry<String>,
+    pub log_level: ConfigEntry<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_file_config() {
+        let dir = std::env::temp_dir().join(format!("agents-rs-config-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(CONFIG_FILE_NAME);
+
+        // Fichier absent : rien n'est imposé
+        assert_eq!(FileConfig::load(&path).unwrap(), FileConfig::default());
+
+        std::fs::write(&path, "models_dir = \"/srv/models\"\nmcp_port = 3001\nlog_level = \"warn\"\n").unwrap();
+        let config = FileConfig::load(&path).unwrap();
+        assert_eq!(config.models_dir, Some(PathBuf::from("/srv/models")));
+        assert_eq!(config.mcp_port, Some(3001));
+        assert_eq!(config.log_level.as_deref(), Some("warn"));
+        assert_eq!(config.default_model, None);
+
+        // Une clé inconnue est signalée plutôt qu'ignorée
+        std::fs::write(&path, "model_dir = \"/srv/models\"\n").unwrap();
+        assert!(FileConfig::load(&path).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_config_precedence() {
+        let level = ConfigEntry::resolve(
+            [
+                (ConfigSource::Environment, None),
+                (ConfigSource::Database, Some("debug")),
+                (ConfigSource::File, Some("warn")),
+            ],
+            Some("info"),
+        );
+        assert_eq!((level.value, level.source), (Some("debug"), ConfigSource::Database));
+
+        let port = ConfigEntry::<u16>::resolve([(ConfigSource::File, None)], None);
+        assert_eq!((port.value, port.source), (None, ConfigSource::Default));
+    }
+}
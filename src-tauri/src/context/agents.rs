@@ -0,0 +1,178 @@
+/// Agent profiles - preconfigured personas with their own prompt, model and tools
+
+use crate::llm::{GuardrailPolicy, SamplingParams};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use tracing::info;
+use ts_rs::TS;
+
+/// A reusable agent configuration a conversation can be started from
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AgentProfile {
+    pub id: String,
+    pub name: String,
+    pub system_prompt: String,
+    /// Model loaded when a conversation with this agent starts (None = keep the current one)
+    pub default_model: Option<String>,
+    /// Names of the tools this agent may call (empty = no tools)
+    pub allowed_tools: Vec<String>,
+    /// Sampling overrides used for this agent's generations
+    pub sampling: SamplingParams,
+    /// Guardrails replacing the global policy for this agent's replies
+    #[serde(default)]
+    pub guardrails: Option<GuardrailPolicy>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AgentProfile {
+    pub fn new(name: String, system_prompt: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            system_prompt,
+            default_model: None,
+            allowed_tools: vec![],
+            sampling: SamplingParams::default(),
+            guardrails: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn allows_tool(&self, tool_name: &str) -> bool {
+        self.allowed_tools.iter().any(|t| t == tool_name)
+    }
+}
+
+/// Persistence of agent profiles
+pub struct AgentRepository {
+    pool: SqlitePool,
+}
+
+impl AgentRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert or update an agent profile
+    pub async fn save(&self, agent: &AgentProfile) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO agents (id, name, system_prompt, default_model, allowed_tools, sampling, guardrails, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                system_prompt = excluded.system_prompt,
+                default_model = excluded.default_model,
+                allowed_tools = excluded.allowed_tools,
+                sampling = excluded.sampling,
+                guardrails = excluded.guardrails,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&agent.id)
+        .bind(&agent.name)
+        .bind(&agent.system_prompt)
+        .bind(&agent.default_model)
+        .bind(serde_json::to_string(&agent.allowed_tools)?)
+        .bind(serde_json::to_string(&agent.sampling)?)
+        .bind(agent.guardrails.as_ref().map(serde_json::to_string).transpose()?)
+        .bind(agent.created_at.timestamp())
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save agent")?;
+
+        info!("Saved agent: {} ({})", agent.name, agent.id);
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<AgentProfile>> {
+        let row = sqlx::query("SELECT * FROM agents WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch agent")?;
+
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    pub async fn list(&self) -> Result<Vec<AgentProfile>> {
+        let rows = sqlx::query("SELECT * FROM agents ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list agents")?;
+
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// Delete an agent (its conversations are kept, without agent)
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM agents WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete agent")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<AgentProfile> {
+        let allowed_tools: String = row.get("allowed_tools");
+        let sampling: String = row.get("sampling");
+        let guardrails: Option<String> = row.get("guardrails");
+        let created_at: i64 = row.get("created_at");
+        let updated_at: i64 = row.get("updated_at");
+
+        Ok(AgentProfile {
+            id: row.get("id"),
+            name: row.get("name"),
+            system_prompt: row.get("system_prompt"),
+            default_model: row.get("default_model"),
+            allowed_tools: serde_json::from_str(&allowed_tools).context("Invalid allowed tools")?,
+            sampling: serde_json::from_str(&sampling).context("Invalid sampling parameters")?,
+            guardrails: guardrails
+                .map(|g| serde_json::from_str(&g))
+                .transpose()
+                .context("Invalid guardrail policy")?,
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_else(Utc::now),
+            updated_at: DateTime::from_timestamp(updated_at, 0).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+
+    #[tokio::test]
+    async fn test_agent_crud() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = AgentRepository::new(db.pool().clone());
+
+        let mut agent = AgentProfile::new("Coder".to_string(), "You write Rust.".to_string());
+        agent.allowed_tools = vec!["calculator".to_string()];
+        agent.sampling.temperature = Some(0.2);
+        repo.save(&agent).await.unwrap();
+
+        let loaded = repo.get(&agent.id).await.unwrap().unwrap();
+        assert!(loaded.allows_tool("calculator"));
+        assert!(!loaded.allows_tool("shell"));
+        assert_eq!(loaded.sampling.temperature, Some(0.2));
+
+        agent.name = "Rustacean".to_string();
+        repo.save(&agent).await.unwrap();
+        assert_eq!(repo.list().await.unwrap()[0].name, "Rustacean");
+
+        assert!(repo.delete(&agent.id).await.unwrap());
+        assert!(repo.get(&agent.id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,213 @@
+/// Jetons d'accès au serveur local (MCP et routes compatibles OpenAI)
+///
+/// Chaque jeton porte une portée vérifiée route par route : exposer le serveur sur
+/// le réseau local ne donne donc pas accès à tous les outils. Seule l'empreinte
+/// SHA-256 du jeton est enregistrée ; le jeton lui-même n'est affiché qu'une fois,
+/// à sa création. Tant qu'aucun jeton n'existe, le serveur reste ouvert.
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use ts_rs::TS;
+
+/// Préfixe des jetons, pour les reconnaître dans une configuration
+const TOKEN_PREFIX: &str = "ars_";
+
+/// Droits accordés par un jeton
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum ApiScope {
+    /// Génération de texte et embeddings
+    Chat,
+    /// Consultation des conversations et de la liste des outils
+    ReadOnly,
+    /// Tous les outils, y compris ceux qui modifient l'application
+    Admin,
+}
+
+/// Ce qu'une route du serveur demande
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiAccess {
+    /// Ouverture et fin de session, liste des outils et notifications
+    Session,
+    /// Lecture des conversations
+    Read,
+    /// Génération et embeddings
+    Chat,
+    /// Appel des autres outils
+    Admin,
+}
+
+impl ApiScope {
+    pub fn allows(self, access: ApiAccess) -> bool {
+        match self {
+            ApiScope::Admin => true,
+            ApiScope::Chat => matches!(access, ApiAccess::Session | ApiAccess::Chat),
+            ApiScope::ReadOnly => matches!(access, ApiAccess::Session | ApiAccess::Read),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ApiScope::Chat => "chat",
+            ApiScope::ReadOnly => "read_only",
+            ApiScope::Admin => "admin",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "chat" => Some(ApiScope::Chat),
+            "read_only" => Some(ApiScope::ReadOnly),
+            "admin" => Some(ApiScope::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// Jeton émis (sans sa valeur)
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scope: ApiScope,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[ts(type = "number")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "chrono::serde::ts_seconds_option")]
+    #[ts(type = "number | null")]
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Empreinte enregistrée à la place du jeton
+fn digest(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn to_token(row: &SqliteRow) -> Option<ApiToken> {
+    Some(ApiToken {
+        id: row.get("id"),
+        name: row.get("name"),
+        scope: ApiScope::parse(row.get("scope"))?,
+        created_at: DateTime::from_timestamp(row.get("created_at"), 0).unwrap_or_else(Utc::now),
+        last_used_at: row.get::<Option<i64>, _>("last_used_at").and_then(|t| DateTime::from_timestamp(t, 0)),
+    })
+}
+
+/// Persistance des jetons
+#[derive(Clone)]
+pub struct ApiTokenRepository {
+    pool: SqlitePool,
+}
+
+impl ApiTokenRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Émet un jeton ; sa valeur n'est renvoyée qu'ici
+    pub async fn create(&self, name: &str, scope: ApiScope) -> Result<(ApiToken, String)> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let value = format!("{}{}", TOKEN_PREFIX, bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>());
+        let token = ApiToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            scope,
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+
+        sqlx::query("INSERT INTO api_tokens (id, name, scope, token_hash, created_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&token.id)
+            .bind(&token.name)
+            .bind(scope.as_str())
+            .bind(digest(&value))
+            .bind(token.created_at.timestamp())
+            .execute(&self.pool)
+            .await
+            .context("Failed to create API token")?;
+        Ok((token, value))
+    }
+
+    pub async fn list(&self) -> Result<Vec<ApiToken>> {
+        let rows = sqlx::query("SELECT id, name, scope, created_at, last_used_at FROM api_tokens ORDER BY created_at")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list API tokens")?;
+        Ok(rows.iter().filter_map(to_token).collect())
+    }
+
+    /// Révoque un jeton, renvoie false s'il n'existait pas
+    pub async fn revoke(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM api_tokens WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to revoke API token")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Le serveur n'exige un jeton qu'à partir du premier émis
+    pub async fn is_required(&self) -> Result<bool> {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM api_tokens")
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to count API tokens")?;
+        Ok(count > 0)
+    }
+
+    /// Portée d'un jeton présenté au serveur (None s'il est inconnu ou révoqué)
+    pub async fn authenticate(&self, value: &str) -> Result<Option<ApiScope>> {
+        let scope: Option<(String,)> = sqlx::query_as(
+            "UPDATE api_tokens SET last_used_at = ? WHERE token_hash = ? RETURNING scope",
+        )
+        .bind(Utc::now().timestamp())
+        .bind(digest(value))
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to check API token")?;
+        Ok(scope.and_then(|(scope,)| ApiScope::parse(&scope)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+
+    async fn setup_test_db() -> ApiTokenRepository {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        ApiTokenRepository::new(db.pool().clone())
+    }
+
+    #[tokio::test]
+    async fn test_tokens_and_scopes() {
+        let repo = setup_test_db().await;
+        assert!(!repo.is_required().await.unwrap());
+
+        let (token, value) = repo.create("LAN", ApiScope::ReadOnly).await.unwrap();
+        assert!(value.starts_with(TOKEN_PREFIX));
+        assert!(repo.is_required().await.unwrap());
+        assert_eq!(repo.authenticate(&value).await.unwrap(), Some(ApiScope::ReadOnly));
+        assert_eq!(repo.authenticate("ars_unknown").await.unwrap(), None);
+        assert!(repo.list().await.unwrap()[0].last_used_at.is_some());
+
+        assert!(ApiScope::ReadOnly.allows(ApiAccess::Read));
+        assert!(!ApiScope::ReadOnly.allows(ApiAccess::Chat));
+        assert!(!ApiScope::Chat.allows(ApiAccess::Admin));
+        assert!(ApiScope::Admin.allows(ApiAccess::Admin));
+
+        assert!(repo.revoke(&token.id).await.unwrap());
+        assert_eq!(repo.authenticate(&value).await.unwrap(), None);
+        assert!(!repo.revoke(&token.id).await.unwrap());
+    }
+}
@@ -1,11 +1,16 @@
 /// SQLite database connection and migrations
 
+use super::migrations;
 use anyhow::{Context, Result};
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
 use sqlx::ConnectOptions;
 
+use std::path::Path;
 use std::str::FromStr;
-use tracing::info;
+use tracing::{info, warn};
+
+/// Environment variable overriding the database location (containers, kiosks)
+pub const DB_PATH_ENV: &str = "AGENTS_RS_DB_PATH";
 
 pub struct Database {
     pool: SqlitePool,
@@ -26,107 +31,233 @@ impl Database {
         Ok(Self { pool })
     }
     
-    /// Initialize database with schema
+    /// Bring the schema up to date (see `migrations`)
     pub async fn migrate(&self) -> Result<()> {
         info!("Running database migrations...");
         
-        // Create conversations table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS conversations (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL,
-                model_name TEXT NOT NULL
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create conversations table")?;
-        
-        // Create messages table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS messages (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                conversation_id TEXT NOT NULL,
-                role TEXT NOT NULL CHECK(role IN ('user', 'assistant', 'system')),
-                content TEXT NOT NULL,
-                tokens INTEGER,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create messages table")?;
-        
-        // Create indexes
-        sqlx::query(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_messages_conversation 
-            ON messages(conversation_id)
-            "#,
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create conversation index")?;
+        let version = migrations::run(&self.pool).await?;
         
-        sqlx::query(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_messages_created_at 
-            ON messages(created_at)
-            "#,
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to create timestamp index")?;
+        info!("Database migrations completed successfully (schema version {})", version);
+        
+        Ok(())
+    }
+    
+    /// Current schema version
+    pub async fn schema_version(&self) -> Result<i64> {
+        migrations::current_version(&self.pool).await
+    }
+    
+    // ==================== Maintenance ====================
+    
+    /// Run `PRAGMA integrity_check` on the live database
+    pub async fn integrity_check(&self) -> Result<()> {
+        check_integrity(&self.pool).await
+    }
+    
+    /// Write a consistent copy of the database to `path`
+    ///
+    /// Uses `VACUUM INTO`, which goes through SQLite's online backup machinery
+    /// and produces a compacted snapshot while the database stays in use.
+    pub async fn backup_to(&self, path: &Path) -> Result<()> {
+        if path.exists() {
+            anyhow::bail!("Backup destination already exists: {}", path.display());
+        }
+        
+        info!("Backing up database to {}", path.display());
+        
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().to_string())
+            .execute(&self.pool)
+            .await
+            .context("Failed to back up database")?;
+        
+        // A backup is only useful if it can be restored
+        validate_database_file(path).await
+            .context("Backup file failed validation")?;
+        
+        Ok(())
+    }
+    
+    /// Replace the contents of the database with those of the backup at `path`
+    ///
+    /// Both databases are validated first. The backup is migrated on a temporary
+    /// copy so that older backups can be restored into a newer schema, then every
+    /// table is copied inside a single transaction: on failure nothing changes.
+    pub async fn restore_from(&self, path: &Path) -> Result<()> {
+        info!("Restoring database from {}", path.display());
+        
+        self.integrity_check().await
+            .context("Current database failed validation")?;
+        validate_database_file(path).await
+            .context("Backup file failed validation")?;
+        
+        // Work on a migrated copy, the backup file itself is left untouched
+        let staging = std::env::temp_dir().join(format!("agents-rs-restore-{}.db", uuid::Uuid::new_v4()));
+        std::fs::copy(path, &staging).context("Failed to copy backup file")?;
+        let result = self.restore_from_staging(&staging).await;
+        if let Err(e) = std::fs::remove_file(&staging) {
+            warn!("Failed to remove restore staging file: {}", e);
+        }
+        result
+    }
+    
+    async fn restore_from_staging(&self, staging: &Path) -> Result<()> {
+        {
+            let backup = open_file(staging, false).await?;
+            migrations::run(&backup).await
+                .context("Failed to migrate backup")?;
+            backup.close().await;
+        }
         
-        sqlx::query(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_conversations_updated_at 
-            ON conversations(updated_at DESC)
-            "#,
+        let mut tables: Vec<(String,)> = sqlx::query_as(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != 'schema_version'",
         )
-        .execute(&self.pool)
+        .fetch_all(&self.pool)
         .await
-        .context("Failed to create conversations index")?;
-        
-        // Create settings table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS settings (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL,
-                updated_at INTEGER NOT NULL
-            )
-            "#,
+        .context("Failed to list tables")?;
+        // Full-text indexes (virtual and shadow tables) are rebuilt by their triggers
+        let indexes: Vec<(String,)> = sqlx::query_as(
+            "SELECT name FROM pragma_table_list WHERE schema = 'main' AND type IN ('virtual', 'shadow')",
         )
-        .execute(&self.pool)
+        .fetch_all(&self.pool)
         .await
-        .context("Failed to create settings table")?;
+        .context("Failed to list full-text indexes")?;
+        tables.retain(|table| !indexes.contains(table));
+        
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("ATTACH DATABASE ? AS backup")
+            .bind(staging.to_string_lossy().to_string())
+            .execute(&mut *conn)
+            .await
+            .context("Failed to attach backup")?;
         
-        info!("Database migrations completed successfully");
+        let copy = async {
+            sqlx::query("BEGIN IMMEDIATE").execute(&mut *conn).await?;
+            sqlx::query("PRAGMA defer_foreign_keys = ON").execute(&mut *conn).await?;
+            for (table,) in &tables {
+                sqlx::query(&format!("DELETE FROM main.\"{}\"", table))
+                    .execute(&mut *conn)
+                    .await
+                    .with_context(|| format!("Failed to clear table {}", table))?;
+                sqlx::query(&format!("INSERT INTO main.\"{0}\" SELECT * FROM backup.\"{0}\"", table))
+                    .execute(&mut *conn)
+                    .await
+                    .with_context(|| format!("Failed to restore table {}", table))?;
+            }
+            sqlx::query("COMMIT").execute(&mut *conn).await?;
+            anyhow::Ok(())
+        }
+        .await;
         
+        if copy.is_err() {
+            let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+        }
+        let _ = sqlx::query("DETACH DATABASE backup").execute(&mut *conn).await;
+        copy?;
+        
+        info!("Database restored ({} tables)", tables.len());
         Ok(())
     }
     
+    /// Reclaim unused space (`VACUUM`), returns the number of bytes freed
+    pub async fn compact(&self) -> Result<i64> {
+        let before = self.size_bytes().await?;
+        sqlx::query("VACUUM")
+            .execute(&self.pool)
+            .await
+            .context("Failed to compact database")?;
+        let after = self.size_bytes().await?;
+        
+        info!("Database compacted: {} -> {} bytes", before, after);
+        Ok(before - after)
+    }
+    
+    /// Size of the database in bytes (page count × page size)
+    pub async fn size_bytes(&self) -> Result<i64> {
+        let (size,): (i64,) = sqlx::query_as(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to read database size")?;
+        Ok(size)
+    }
+    
     /// Get the connection pool
     pub fn pool(&self) -> &SqlitePool {
         &self.pool
     }
     
-    /// Close the database connection
-    pub async fn close(self) {
+    /// Close the database connection (waits for the queries in progress)
+    pub async fn close(&self) {
         self.pool.close().await;
     }
 }
 
-/// Get the default database path for the application
+/// Open a single-connection pool on a database file
+async fn open_file(path: &Path, read_only: bool) -> Result<SqlitePool> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(read_only)
+        .disable_statement_logging();
+    
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))
+}
+
+async fn check_integrity(pool: &SqlitePool) -> Result<()> {
+    let rows: Vec<(String,)> = sqlx::query_as("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await
+        .context("Failed to run integrity check")?;
+    
+    // SQLite checks FTS5 indexes with a write, impossible on a read-only
+    // connection: that says nothing about the file itself
+    let problems: Vec<String> = rows.into_iter()
+        .map(|(row,)| row)
+        .filter(|row| row != "ok" && !(row.contains("FTS5") && row.contains("readonly")))
+        .collect();
+    if !problems.is_empty() {
+        anyhow::bail!("Integrity check failed: {}", problems.join("; "));
+    }
+    Ok(())
+}
+
+/// Check that a file is a sound SQLite database this version can read
+async fn validate_database_file(path: &Path) -> Result<()> {
+    if !path.is_file() {
+        anyhow::bail!("Database file not found: {}", path.display());
+    }
+    
+    let pool = open_file(path, true).await?;
+    let result = async {
+        check_integrity(&pool).await?;
+        
+        let (version,): (Option<i64>,) = sqlx::query_as("SELECT MAX(version) FROM schema_version")
+            .fetch_one(&pool)
+            .await
+            .context("Not an agents-rs database (no schema version)")?;
+        let version = version.unwrap_or(0);
+        if version > migrations::latest_version() {
+            anyhow::bail!("Database schema version {} is newer than supported version {}",
+                version, migrations::latest_version());
+        }
+        Ok(())
+    }
+    .await;
+    pool.close().await;
+    result
+}
+
+/// Get the default database path for the application, or the one set with `AGENTS_RS_DB_PATH`
 pub fn get_default_database_path() -> Result<String> {
+    if let Some(url) = database_url_from_env()? {
+        return Ok(url);
+    }
+    
     let app_dir = directories::ProjectDirs::from("com", "agents-rs", "AgentsRS")
         .context("Failed to determine application directory")?;
     
@@ -140,6 +271,29 @@ pub fn get_default_database_path() -> Result<String> {
     Ok(db_url)
 }
 
+/// Database set with `AGENTS_RS_DB_PATH`: a file path or a `sqlite:` URL
+fn database_url_from_env() -> Result<Option<String>> {
+    std::env::var(DB_PATH_ENV)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| database_url(&value))
+        .transpose()
+}
+
+/// SQLite URL of a database file, whose directory is created if needed
+fn database_url(path_or_url: &str) -> Result<String> {
+    if path_or_url.starts_with("sqlite:") {
+        return Ok(path_or_url.to_string());
+    }
+    
+    let db_path = Path::new(path_or_url);
+    if let Some(parent) = db_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create the directory of {}", path_or_url))?;
+    }
+    Ok(format!("sqlite://{}", db_path.display()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,4 +311,84 @@ mod tests {
         
         assert!(result.len() >= 2);
     }
+    
+    #[tokio::test]
+    async fn test_database_url_from_path() {
+        assert_eq!(database_url("sqlite::memory:").unwrap(), "sqlite::memory:");
+        
+        // The directory of a new database file is created
+        let dir = std::env::temp_dir().join(format!("agents-rs-test-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("data").join("agents.db");
+        let url = database_url(path.to_str().unwrap()).unwrap();
+        assert_eq!(url, format!("sqlite://{}", path.display()));
+        assert!(path.parent().unwrap().is_dir());
+        
+        let db = Database::new(&url).await.unwrap();
+        db.migrate().await.unwrap();
+        db.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let dir = std::env::temp_dir().join(format!("agents-rs-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let backup = dir.join("backup.db");
+        
+        let db = Database::new(&format!("sqlite://{}", dir.join("live.db").display())).await.unwrap();
+        db.migrate().await.unwrap();
+        sqlx::query("INSERT INTO settings VALUES ('theme', 'dark', 0)")
+            .execute(db.pool()).await.unwrap();
+        sqlx::query("INSERT INTO kb_folders (id, path, created_at) VALUES ('kb', '/notes', 0)")
+            .execute(db.pool()).await.unwrap();
+        sqlx::query("INSERT INTO kb_files (id, folder_id, path, modified_at, size, indexed_at) VALUES ('f', 'kb', 'a.md', 0, 1, 0)")
+            .execute(db.pool()).await.unwrap();
+        sqlx::query("INSERT INTO kb_chunks (file_id, chunk_index, content, start_char, end_char) VALUES ('f', 0, 'hello', 0, 5)")
+            .execute(db.pool()).await.unwrap();
+        
+        db.backup_to(&backup).await.unwrap();
+        assert!(db.backup_to(&backup).await.is_err(), "existing files are not overwritten");
+        
+        sqlx::query("UPDATE settings SET value = 'light' WHERE key = 'theme'")
+            .execute(db.pool()).await.unwrap();
+        db.restore_from(&backup).await.unwrap();
+        
+        let (value,): (String,) = sqlx::query_as("SELECT value FROM settings WHERE key = 'theme'")
+            .fetch_one(db.pool()).await.unwrap();
+        assert_eq!(value, "dark");
+        let (matches,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM kb_chunks_fts WHERE kb_chunks_fts MATCH 'hello'")
+            .fetch_one(db.pool()).await.unwrap();
+        assert_eq!(matches, 1);
+        
+        // Files that are not databases are rejected before touching anything
+        let invalid = dir.join("invalid.db");
+        std::fs::write(&invalid, b"not a database").unwrap();
+        assert!(db.restore_from(&invalid).await.is_err());
+        
+        db.compact().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_migrate_tool_messages() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        
+        // Schema of databases created before tool messages were supported
+        sqlx::query("CREATE TABLE conversations (id TEXT PRIMARY KEY, title TEXT NOT NULL, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL, model_name TEXT NOT NULL)")
+            .execute(db.pool()).await.unwrap();
+        sqlx::query("CREATE TABLE messages (id INTEGER PRIMARY KEY AUTOINCREMENT, conversation_id TEXT NOT NULL, role TEXT NOT NULL CHECK(role IN ('user', 'assistant', 'system')), content TEXT NOT NULL, tokens INTEGER, created_at INTEGER NOT NULL)")
+            .execute(db.pool()).await.unwrap();
+        sqlx::query("INSERT INTO conversations VALUES ('c1', 'Test', 0, 0, 'model')")
+            .execute(db.pool()).await.unwrap();
+        sqlx::query("INSERT INTO messages (conversation_id, role, content, created_at) VALUES ('c1', 'user', 'Hello', 0)")
+            .execute(db.pool()).await.unwrap();
+        
+        db.migrate().await.unwrap();
+        
+        sqlx::query("INSERT INTO messages (conversation_id, role, content, created_at, tool_name, tool_call_id) VALUES ('c1', 'tool', '42', 1, 'calculator', 'call_1')")
+            .execute(db.pool()).await.unwrap();
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM messages")
+            .fetch_one(db.pool()).await.unwrap();
+        assert_eq!(count, 2);
+    }
 }
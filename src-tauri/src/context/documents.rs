@@ -0,0 +1,589 @@
+/// Document attachments - text extraction, chunking and prompt inclusion
+///
+/// The text of an attached document is extracted once, when it is uploaded. Small
+/// documents are included as a whole in the prompt; larger ones are split into
+/// chunks whose embeddings are stored, and only the chunks closest to the user's
+/// question are included.
+///
+/// PDF text is extracted page by page with a few layout fixes (running headers
+/// and footers, hyphenation, wrapped lines); pages are separated by a form feed
+/// and chunks never straddle two pages, so a citation can point to its page.
+///
+/// Every document or chunk shown to the model is numbered (`[1]`, `[2]`...); the
+/// numbers the answer cites are mapped back to the document and its offsets.
+
+use super::memories::cosine_similarity;
+use super::retrieval::{self, RetrievalSettings};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read};
+use std::sync::OnceLock;
+use ts_rs::TS;
+
+/// Documents up to this size (in characters) are included in full
+pub const MAX_INLINE_CHARS: usize = 8000;
+
+/// Target size of a chunk of a large document, in characters
+pub const CHUNK_CHARS: usize = 2000;
+
+/// Maximum number of chunks of one document included in a prompt
+pub const MAX_DOCUMENT_CHUNKS: usize = 4;
+
+/// Separator of the pages in the text extracted from a PDF (form feed, as pdftotext)
+pub const PAGE_BREAK: char = '\u{c}';
+
+/// Appended after the documents so the model cites the numbered sources
+pub const CITATION_INSTRUCTION: &str =
+    "When you use the documents above, cite the sources with their number in brackets, e.g. [1].";
+
+/// A document, or a chunk of it, shown to the model under the marker `[source]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Citation {
+    #[ts(type = "number")]
+    pub source: usize,
+    pub attachment_id: String,
+    pub document: String,
+    /// Index of the chunk (None when the whole document was included)
+    #[ts(type = "number | null")]
+    pub chunk: Option<usize>,
+    /// Character offsets of the source text in the document
+    #[ts(type = "number")]
+    pub start: usize,
+    #[ts(type = "number")]
+    pub end: usize,
+    /// Page of the PDF the chunk comes from (1-based)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub page: Option<usize>,
+}
+
+impl Citation {
+    /// Source covering the whole text of a document
+    pub fn document(source: usize, attachment_id: &str, document: &str, text: &str) -> Self {
+        Self {
+            source,
+            attachment_id: attachment_id.to_string(),
+            document: document.to_string(),
+            chunk: None,
+            start: 0,
+            end: text.chars().count(),
+            page: None,
+        }
+    }
+
+    /// Source covering one chunk, located in the text of its document
+    pub fn chunk(source: usize, attachment_id: &str, document: &str, text: &str, chunk: &DocumentChunk) -> Self {
+        let (start, end) = match text.find(&chunk.content) {
+            Some(byte) => {
+                let start = text[..byte].chars().count();
+                (start, start + chunk.content.chars().count())
+            }
+            None => (0, 0),
+        };
+        Self {
+            source,
+            attachment_id: attachment_id.to_string(),
+            document: document.to_string(),
+            chunk: Some(chunk.index),
+            start,
+            end,
+            page: chunk.page,
+        }
+    }
+
+    /// Short reference to the source, e.g. `report.pdf p. 12`
+    pub fn label(&self) -> String {
+        match self.page {
+            Some(page) => format!("{} p. {}", self.document, page),
+            None => self.document.clone(),
+        }
+    }
+}
+
+/// Sources cited in an answer (`[2]`, `[1, 3]`), in order of first citation
+pub fn cited_sources(answer: &str, sources: &[Citation]) -> Vec<Citation> {
+    static MARKERS: OnceLock<Regex> = OnceLock::new();
+    let markers = MARKERS.get_or_init(|| Regex::new(r"\[(\d+(?:\s*,\s*\d+)*)\]").unwrap());
+
+    let mut cited: Vec<Citation> = Vec::new();
+    for marker in markers.captures_iter(answer) {
+        for number in marker[1].split(',').filter_map(|n| n.trim().parse::<usize>().ok()) {
+            if cited.iter().any(|c| c.source == number) {
+                continue;
+            }
+            if let Some(source) = sources.iter().find(|s| s.source == number) {
+                cited.push(source.clone());
+            }
+        }
+    }
+    cited
+}
+
+/// File formats text can be extracted from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Text,
+    Pdf,
+    Docx,
+}
+
+const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "md", "markdown", "csv", "tsv", "json", "yaml", "yml", "toml", "xml", "html", "log",
+    "rs", "py", "js", "ts", "tsx", "java", "c", "cpp", "h", "go", "sh", "sql",
+];
+
+const DOCX_MIME: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+impl DocumentFormat {
+    /// Format of a file, from its extension or MIME type
+    pub fn detect(file_name: &str, mime_type: &str) -> Option<Self> {
+        let extension = std::path::Path::new(file_name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+
+        if extension == "pdf" || mime_type == "application/pdf" {
+            Some(DocumentFormat::Pdf)
+        } else if extension == "docx" || mime_type == DOCX_MIME {
+            Some(DocumentFormat::Docx)
+        } else if TEXT_EXTENSIONS.contains(&extension.as_str()) || mime_type.starts_with("text/") {
+            Some(DocumentFormat::Text)
+        } else {
+            None
+        }
+    }
+}
+
+/// A piece of a large document, with the embedding used to rank it
+#[derive(Debug, Clone)]
+pub struct DocumentChunk {
+    pub index: usize,
+    pub content: String,
+    pub embedding: Option<Vec<f32>>,
+    /// Page of the PDF the chunk comes from (1-based)
+    pub page: Option<usize>,
+}
+
+/// Where a document of the library stands with the retrieval index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum DocumentStatus {
+    /// Small enough to be included in full, never chunked
+    Inline,
+    /// Chunked and embedded
+    Indexed,
+    /// Chunked, but some chunks have no embedding (no model was loaded)
+    MissingEmbeddings,
+    /// Too large to be included in full and not chunked yet
+    NotIndexed,
+}
+
+impl DocumentStatus {
+    pub fn new(chars: usize, chunks: usize, chunks_without_embedding: usize) -> Self {
+        if chars <= MAX_INLINE_CHARS {
+            DocumentStatus::Inline
+        } else if chunks == 0 {
+            DocumentStatus::NotIndexed
+        } else if chunks_without_embedding > 0 {
+            DocumentStatus::MissingEmbeddings
+        } else {
+            DocumentStatus::Indexed
+        }
+    }
+}
+
+/// A document attachment of the knowledge base, with its index status
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DocumentInfo {
+    pub attachment_id: String,
+    pub file_name: String,
+    pub mime_type: String,
+    pub conversation_id: String,
+    pub conversation_title: String,
+    /// Stored size in bytes
+    #[ts(type = "number")]
+    pub size: usize,
+    /// Length of the extracted text, in characters
+    #[ts(type = "number")]
+    pub chars: usize,
+    pub status: DocumentStatus,
+    #[ts(type = "number")]
+    pub chunks: usize,
+    /// Tokens of the indexed chunks (None if they could not be counted)
+    #[ts(type = "number | null")]
+    pub tokens: Option<usize>,
+    /// File name of the model that computed the embeddings
+    pub embedding_model: Option<String>,
+    #[serde(with = "chrono::serde::ts_seconds_option")]
+    #[ts(type = "number | null")]
+    pub indexed_at: Option<DateTime<Utc>>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[ts(type = "number")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Extract the plain text of a document
+pub fn extract_text(format: DocumentFormat, data: &[u8]) -> Result<String> {
+    let text = match format {
+        DocumentFormat::Text => normalize_whitespace(
+            String::from_utf8(data.to_vec())
+                .context("The file is not valid UTF-8 text")?
+                .trim_start_matches('\u{feff}'),
+        ),
+        DocumentFormat::Pdf => {
+            let pages = pdf_extract::extract_text_from_mem_by_pages(data)
+                .map_err(|e| anyhow::anyhow!("Failed to read PDF: {}", e))?;
+            layout_pages(&pages)
+        }
+        DocumentFormat::Docx => normalize_whitespace(&extract_docx_text(data)?),
+    };
+
+    if text.trim().is_empty() {
+        anyhow::bail!("No text found in the document");
+    }
+    Ok(text)
+}
+
+/// Text of the paragraphs of `word/document.xml`
+fn extract_docx_text(data: &[u8]) -> Result<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).context("Invalid DOCX file")?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .context("Invalid DOCX file: missing word/document.xml")?
+        .read_to_string(&mut xml)
+        .context("Failed to read DOCX content")?;
+
+    let mut reader = Reader::from_str(&xml);
+    let mut text = String::new();
+    let mut in_text = false;
+    loop {
+        match reader.read_event().context("Invalid DOCX content")? {
+            Event::Start(e) if e.name().as_ref() == b"w:t" => in_text = true,
+            Event::End(e) => match e.name().as_ref() {
+                b"w:t" => in_text = false,
+                b"w:p" => text.push('\n'),
+                _ => {}
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"w:tab" => text.push('\t'),
+                b"w:br" | b"w:p" => text.push('\n'),
+                _ => {}
+            },
+            Event::Text(e) if in_text => text.push_str(&e.unescape().context("Invalid DOCX text")?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(text)
+}
+
+/// Lines identifying a page rather than its content: `12`, `Page 3`, `3 / 40`
+fn is_page_number(line: &str) -> bool {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN
+        .get_or_init(|| Regex::new(r"(?i)^(page\s+)?\d+(\s*(/|of|sur)\s*\d+)?$").unwrap())
+        .is_match(line.trim())
+}
+
+/// Line repeated at the same place on most pages (running header or footer),
+/// page numbers ignored
+fn running_line(lines: &[Option<&str>]) -> Option<String> {
+    let key = |line: &str| line.trim().replace(|c: char| c.is_ascii_digit(), "#");
+    let mut counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for line in lines.iter().flatten() {
+        *counts.entry(key(line)).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| lines.len() >= 3 && *count * 2 > lines.len())
+        .map(|(line, _)| line)
+        .next()
+}
+
+/// Rebuild the paragraphs of a PDF page: hyphenated words are joined and lines
+/// continuing a sentence are unwrapped
+fn unwrap_lines(page: &str) -> String {
+    let mut text = String::new();
+    for line in page.lines() {
+        let continues = line.starts_with(|c: char| c.is_lowercase());
+        if text.is_empty() || line.is_empty() || text.ends_with('\n') {
+            text.push_str(line);
+        } else if continues && text.ends_with('-') && !text.ends_with(" -") {
+            text.pop();
+            text.push_str(line);
+        } else if continues {
+            text.push(' ');
+            text.push_str(line);
+        } else {
+            text.push('\n');
+            text.push_str(line);
+        }
+        if line.is_empty() {
+            text.push_str("\n\n");
+        }
+    }
+    normalize_whitespace(&text)
+}
+
+/// Text of the pages of a PDF, cleaned up and separated by `PAGE_BREAK`
+///
+/// Page numbers and running headers/footers are removed from the top and bottom
+/// of the pages; empty pages are kept so that the page numbers stay right.
+fn layout_pages(pages: &[String]) -> String {
+    let mut pages: Vec<Vec<String>> = pages
+        .iter()
+        .map(|page| normalize_whitespace(page).lines().map(str::to_string).collect())
+        .collect();
+    let first_lines: Vec<Option<&str>> = pages.iter().map(|p| p.iter().find(|l| !l.is_empty()).map(String::as_str)).collect();
+    let last_lines: Vec<Option<&str>> = pages.iter().map(|p| p.iter().rfind(|l| !l.is_empty()).map(String::as_str)).collect();
+    let header = running_line(&first_lines);
+    let footer = running_line(&last_lines);
+    let is_running = |line: &str, running: &Option<String>| {
+        is_page_number(line) || running.as_deref() == Some(line.trim().replace(|c: char| c.is_ascii_digit(), "#").as_str())
+    };
+
+    for lines in &mut pages {
+        while lines.first().is_some_and(|l| l.is_empty() || is_running(l, &header)) {
+            lines.remove(0);
+        }
+        while lines.last().is_some_and(|l| l.is_empty() || is_running(l, &footer)) {
+            lines.pop();
+        }
+    }
+    pages
+        .iter()
+        .map(|lines| unwrap_lines(&lines.join("\n")))
+        .collect::<Vec<_>>()
+        .join(&format!("\n{}\n", PAGE_BREAK))
+}
+
+/// Trim lines and collapse runs of blank lines
+fn normalize_whitespace(text: &str) -> String {
+    let mut result = String::new();
+    let mut blank_lines = 0;
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !result.is_empty() {
+            result.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        result.push_str(line);
+        blank_lines = 0;
+    }
+    result
+}
+
+/// Split a text into chunks of about `max_chars`, on paragraph boundaries when possible
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for paragraph in text.split("\n\n") {
+        let mut paragraph = paragraph;
+        // Paragraphs longer than a chunk are cut at character boundaries
+        while paragraph.chars().count() > max_chars {
+            let cut = paragraph
+                .char_indices()
+                .nth(max_chars)
+                .map(|(i, _)| i)
+                .unwrap_or(paragraph.len());
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+            }
+            chunks.push(paragraph[..cut].to_string());
+            paragraph = &paragraph[cut..];
+        }
+
+        if !current.is_empty() && current.chars().count() + paragraph.chars().count() + 2 > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Split a document into chunks, page by page for a PDF (a chunk never spans two pages)
+pub fn chunk_document(text: &str, max_chars: usize) -> Vec<DocumentChunk> {
+    let paged = text.contains(PAGE_BREAK);
+    text.split(PAGE_BREAK)
+        .enumerate()
+        .flat_map(|(page, content)| {
+            chunk_text(content.trim(), max_chars)
+                .into_iter()
+                .map(move |chunk| (chunk, paged.then_some(page + 1)))
+        })
+        .enumerate()
+        .map(|(index, (content, page))| DocumentChunk { index, content, embedding: None, page })
+        .collect()
+}
+
+/// Indices of the chunks with an embedding, closest to the query first
+pub fn vector_ranking(embeddings: &[Option<&[f32]>], query: Option<&[f32]>) -> Vec<usize> {
+    let Some(query) = query else {
+        return Vec::new();
+    };
+    let mut scored: Vec<(usize, f32)> = embeddings
+        .iter()
+        .enumerate()
+        .filter_map(|(index, embedding)| embedding.map(|e| (index, cosine_similarity(e, query))))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.into_iter().map(|(index, _)| index).collect()
+}
+
+/// The `limit` chunks most relevant to the question, in document order
+///
+/// The vector ranking (`query`, the embedding of the question) and the BM25
+/// ranking (`text`) are fused; without embeddings or matching words, the first
+/// chunks of the document are used.
+pub fn select_chunks(
+    chunks: Vec<DocumentChunk>,
+    query: Option<&[f32]>,
+    text: &str,
+    settings: &RetrievalSettings,
+    limit: usize,
+) -> Vec<DocumentChunk> {
+    let embeddings: Vec<Option<&[f32]>> = chunks.iter().map(|c| c.embedding.as_deref()).collect();
+    let vector = vector_ranking(&embeddings, query);
+    let contents: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+    let lexical = retrieval::bm25_ranking(text, &contents);
+    let order = retrieval::fuse(chunks.len(), &vector, &lexical, settings);
+
+    let mut chunks: Vec<Option<DocumentChunk>> = chunks.into_iter().map(Some).collect();
+    let mut selected: Vec<DocumentChunk> = order.into_iter().take(limit).filter_map(|i| chunks[i].take()).collect();
+    selected.sort_by_key(|c| c.index);
+    selected
+}
+
+/// Block appended to a message to show a whole document to the model
+pub fn document_block(file_name: &str, text: &str, source: usize) -> String {
+    format!("<document name=\"{}\">\n[{}] {}\n</document>", file_name, source, text)
+}
+
+/// Block appended to a message with the relevant excerpts of a large document,
+/// numbered from `first_source`
+pub fn excerpts_block(file_name: &str, chunks: &[DocumentChunk], first_source: usize) -> String {
+    let excerpts: Vec<String> = chunks
+        .iter()
+        .enumerate()
+        .map(|(i, c)| match c.page {
+            Some(page) => format!("[{}] (p. {}) {}", first_source + i, page, c.content),
+            None => format!("[{}] {}", first_source + i, c.content),
+        })
+        .collect();
+    format!(
+        "<document name=\"{}\" excerpts=\"true\">\n{}\n</document>",
+        file_name,
+        excerpts.join("\n[...]\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_chunk_text() {
+        let text = format!("{}\n\n{}\n\n{}", "a".repeat(30), "b".repeat(30), "c".repeat(100));
+        let chunks = chunk_text(&text, 64);
+
+        assert_eq!(chunks[0], format!("{}\n\n{}", "a".repeat(30), "b".repeat(30)));
+        assert!(chunks.iter().all(|c| c.chars().count() <= 64));
+        assert_eq!(chunks.concat().matches('c').count(), 100);
+    }
+
+    #[test]
+    fn test_pdf_layout() {
+        let pages: Vec<String> = (1..=3)
+            .map(|n| format!("ACME Annual Report\n\nSection {n} explains the re-\nsults of the year\nand the outlook.\n\n- {n} -\n"))
+            .collect();
+        let text = layout_pages(&pages);
+        let page_texts: Vec<&str> = text.split(PAGE_BREAK).map(str::trim).collect();
+        assert_eq!(page_texts.len(), 3);
+        assert_eq!(page_texts[1], "Section 2 explains the results of the year and the outlook.");
+
+        let chunks = chunk_document(&text, 64);
+        assert_eq!(chunks.iter().map(|c| c.page).collect::<Vec<_>>(), vec![Some(1), Some(2), Some(3)]);
+        let citation = Citation::chunk(1, "a1", "report.pdf", &text, &chunks[2]);
+        assert_eq!(citation.label(), "report.pdf p. 3");
+        assert_eq!(chunk_document("No pages here", 64)[0].page, None);
+    }
+
+    #[test]
+    fn test_select_chunks() {
+        let chunk = |index: usize, embedding: Vec<f32>| DocumentChunk {
+            index,
+            content: index.to_string(),
+            embedding: Some(embedding),
+            page: None,
+        };
+        let chunks = vec![chunk(0, vec![1.0, 0.0]), chunk(1, vec![0.0, 1.0]), chunk(2, vec![0.7, 0.7])];
+
+        let settings = RetrievalSettings::default();
+        let selected = select_chunks(chunks.clone(), Some(&[0.0, 1.0]), "", &settings, 2);
+        assert_eq!(selected.iter().map(|c| c.index).collect::<Vec<_>>(), vec![1, 2]);
+
+        let selected = select_chunks(chunks.clone(), None, "", &settings, 2);
+        assert_eq!(selected.iter().map(|c| c.index).collect::<Vec<_>>(), vec![0, 1]);
+
+        // An exact match of the question lifts a chunk the embeddings rank last
+        let selected = select_chunks(chunks, Some(&[0.0, 1.0]), "chunk 0", &settings, 2);
+        assert_eq!(selected.iter().map(|c| c.index).collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_document_status() {
+        assert_eq!(DocumentStatus::new(100, 0, 0), DocumentStatus::Inline);
+        assert_eq!(DocumentStatus::new(MAX_INLINE_CHARS + 1, 0, 0), DocumentStatus::NotIndexed);
+        assert_eq!(DocumentStatus::new(MAX_INLINE_CHARS + 1, 5, 5), DocumentStatus::MissingEmbeddings);
+        assert_eq!(DocumentStatus::new(MAX_INLINE_CHARS + 1, 5, 0), DocumentStatus::Indexed);
+    }
+
+    #[test]
+    fn test_citations() {
+        let text = "Intro paragraph.\n\nRust was first released in 2015.";
+        let chunk = DocumentChunk { index: 1, content: "Rust was first released in 2015.".to_string(), embedding: None, page: None };
+        let sources = vec![
+            Citation::document(1, "a1", "notes.md", "Short note"),
+            Citation::chunk(2, "a2", "rust.pdf", text, &chunk),
+        ];
+        assert_eq!((sources[1].start, sources[1].end), (18, 50));
+        assert_eq!(excerpts_block("rust.pdf", &[chunk], 2), "<document name=\"rust.pdf\" excerpts=\"true\">\n[2] Rust was first released in 2015.\n</document>");
+
+        let cited = cited_sources("Released in 2015 [2]. See also [2, 1] and [7].", &sources);
+        assert_eq!(cited.iter().map(|c| c.source).collect::<Vec<_>>(), vec![2, 1]);
+        assert!(cited_sources("No sources here, see list[0]", &sources).is_empty());
+    }
+
+    #[test]
+    fn test_extract_docx() {
+        let mut docx = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        docx.start_file("word/document.xml", zip::write::SimpleFileOptions::default()).unwrap();
+        docx.write_all(
+            br#"<w:document><w:body><w:p><w:r><w:t>Hello</w:t></w:r><w:r><w:t xml:space="preserve"> world</w:t></w:r></w:p><w:p><w:r><w:t>Tom &amp; Jerry</w:t></w:r></w:p></w:body></w:document>"#,
+        )
+        .unwrap();
+        let data = docx.finish().unwrap().into_inner();
+
+        assert_eq!(DocumentFormat::detect("report.docx", "application/octet-stream"), Some(DocumentFormat::Docx));
+        assert_eq!(extract_text(DocumentFormat::Docx, &data).unwrap(), "Hello world\nTom & Jerry");
+    }
+}
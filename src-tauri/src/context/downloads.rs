@@ -0,0 +1,150 @@
+/// Downloaded models - the Hub repository and revision each model file came from
+
+use crate::huggingface::FileRevision;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+/// A model file downloaded from Hugging Face
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadedModel {
+    /// File name in the models directory
+    pub filename: String,
+    pub repo_id: String,
+    /// Commit of the repository the file was downloaded at
+    pub revision: String,
+    /// LFS hash of the file, when the Hub reports one
+    pub sha256: Option<String>,
+    pub downloaded_at: DateTime<Utc>,
+}
+
+impl DownloadedModel {
+    pub fn new(filename: impl Into<String>, repo_id: impl Into<String>, revision: FileRevision) -> Self {
+        Self {
+            filename: filename.into(),
+            repo_id: repo_id.into(),
+            revision: revision.commit,
+            sha256: revision.sha256,
+            downloaded_at: Utc::now(),
+        }
+    }
+
+    /// Whether the latest revision of the repository changed the file
+    ///
+    /// Compares the file hashes when both are known: a new commit touching
+    /// other files (README, other quantizations) doesn't count as an update.
+    pub fn is_outdated(&self, latest: &FileRevision) -> bool {
+        match (&self.sha256, &latest.sha256) {
+            (Some(current), Some(latest)) => current != latest,
+            _ => self.revision != latest.commit,
+        }
+    }
+}
+
+/// Persistence of the downloaded models
+pub struct DownloadedModelRepository {
+    pool: SqlitePool,
+}
+
+impl DownloadedModelRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Record a download, replacing the previous download of the same file
+    pub async fn record(&self, model: &DownloadedModel) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO downloaded_models (filename, repo_id, revision, sha256, downloaded_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&model.filename)
+        .bind(&model.repo_id)
+        .bind(&model.revision)
+        .bind(&model.sha256)
+        .bind(model.downloaded_at.timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to record downloaded model")?;
+
+        Ok(())
+    }
+
+    pub async fn get(&self, filename: &str) -> Result<Option<DownloadedModel>> {
+        let row = sqlx::query("SELECT * FROM downloaded_models WHERE filename = ?")
+            .bind(filename)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to get downloaded model")?;
+
+        Ok(row.map(|row| Self::from_row(&row)))
+    }
+
+    pub async fn list(&self) -> Result<Vec<DownloadedModel>> {
+        let rows = sqlx::query("SELECT * FROM downloaded_models ORDER BY filename")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list downloaded models")?;
+
+        Ok(rows.iter().map(Self::from_row).collect())
+    }
+
+    pub async fn delete(&self, filename: &str) -> Result<()> {
+        sqlx::query("DELETE FROM downloaded_models WHERE filename = ?")
+            .bind(filename)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete downloaded model")?;
+
+        Ok(())
+    }
+
+    fn from_row(row: &SqliteRow) -> DownloadedModel {
+        DownloadedModel {
+            filename: row.get("filename"),
+            repo_id: row.get("repo_id"),
+            revision: row.get("revision"),
+            sha256: row.get("sha256"),
+            downloaded_at: DateTime::from_timestamp(row.get("downloaded_at"), 0).unwrap_or_else(Utc::now),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+
+    #[tokio::test]
+    async fn test_downloaded_models() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = DownloadedModelRepository::new(db.pool().clone());
+
+        let revision = |commit: &str, sha256: &str| FileRevision {
+            commit: commit.to_string(),
+            sha256: Some(sha256.to_string()),
+        };
+        repo.record(&DownloadedModel::new("model.gguf", "org/model-GGUF", revision("c1", "h1")))
+            .await
+            .unwrap();
+        repo.record(&DownloadedModel::new("model.gguf", "org/model-GGUF", revision("c2", "h1")))
+            .await
+            .unwrap();
+
+        let models = repo.list().await.unwrap();
+        assert_eq!(models.len(), 1);
+        let model = repo.get("model.gguf").await.unwrap().unwrap();
+        assert_eq!(model.revision, "c2");
+
+        // A commit that leaves the file unchanged is not an update
+        assert!(!model.is_outdated(&revision("c3", "h1")));
+        assert!(model.is_outdated(&revision("c3", "h2")));
+
+        repo.delete("model.gguf").await.unwrap();
+        assert!(repo.get("model.gguf").await.unwrap().is_none());
+    }
+}
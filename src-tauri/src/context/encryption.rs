@@ -0,0 +1,287 @@
+/// Chiffrement au repos du contenu des conversations
+///
+/// Le contenu des messages est chiffré en AES-256-GCM avec une clé dérivée de la
+/// phrase de passe par Argon2id. La phrase de passe n'est jamais stockée : seuls le
+/// sel et un vérificateur (une valeur connue chiffrée) sont enregistrés dans les
+/// paramètres, ce qui permet de détecter une phrase de passe erronée.
+///
+/// Sont chiffrés : messages, pièces jointes et leurs extraits, commentaires,
+/// brouillons, bloc-notes, souvenirs, ainsi que l'objectif, la réponse et la trace
+/// des tâches. Les titres des conversations restent en clair, comme les noms de
+/// fichiers et les dates : ils servent au tri, à la recherche et à la liste des
+/// sessions affichée avant le déverrouillage.
+///
+/// Les embeddings (messages, souvenirs, extraits de pièces jointes et de la base de
+/// connaissances) restent eux aussi en clair : la recherche par similarité les
+/// parcourt tous à chaque requête. Ils ne contiennent pas le texte, mais en
+/// révèlent le sens approché à qui dispose du même modèle.
+
+use crate::error::AppError;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Préfixe des valeurs chiffrées (les autres sont en clair)
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Valeur chiffrée avec la clé pour vérifier une phrase de passe
+const VERIFIER_PLAINTEXT: &str = "agents-rs";
+
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+/// Paramètres persistés du chiffrement (sans la clé)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionParams {
+    /// Sel Argon2 encodé en base64
+    pub salt: String,
+    /// `VERIFIER_PLAINTEXT` chiffré avec la clé
+    pub verifier: String,
+}
+
+/// Clé de chiffrement du contenu
+#[derive(Clone)]
+pub struct ContentCipher {
+    cipher: Arc<Aes256Gcm>,
+}
+
+impl std::fmt::Debug for ContentCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ContentCipher(..)")
+    }
+}
+
+impl ContentCipher {
+    /// Dérive une clé à partir d'une phrase de passe et d'un sel
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Result<Self> {
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))?;
+        let cipher = Aes256Gcm::new_from_slice(&key)
+            .map_err(|_| anyhow::anyhow!("Invalid key length"))?;
+        Ok(Self { cipher: Arc::new(cipher) })
+    }
+
+    /// Crée une nouvelle clé (sel aléatoire) et les paramètres à persister
+    pub fn create(passphrase: &str) -> Result<(Self, EncryptionParams)> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+
+        let cipher = Self::derive(passphrase, &salt)?;
+        let params = EncryptionParams {
+            salt: BASE64.encode(salt),
+            verifier: cipher.encrypt(VERIFIER_PLAINTEXT)?,
+        };
+        Ok((cipher, params))
+    }
+
+    /// Retrouve la clé d'une base chiffrée, échoue si la phrase de passe est erronée
+    pub fn unlock(passphrase: &str, params: &EncryptionParams) -> Result<Self> {
+        let salt = BASE64.decode(&params.salt).context("Invalid encryption salt")?;
+        let cipher = Self::derive(passphrase, &salt)?;
+
+        match cipher.decrypt(&params.verifier) {
+            Ok(value) if value == VERIFIER_PLAINTEXT => Ok(cipher),
+            _ => Err(AppError::invalid_input("Wrong database passphrase").into()),
+        }
+    }
+
+    pub fn is_encrypted(value: &str) -> bool {
+        value.starts_with(ENCRYPTED_PREFIX)
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let payload = self.seal_payload(plaintext.as_bytes())?;
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(payload)))
+    }
+
+    /// Déchiffre une valeur (les valeurs en clair sont renvoyées telles quelles)
+    pub fn decrypt(&self, value: &str) -> Result<String> {
+        let Some(encoded) = value.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(value.to_string());
+        };
+
+        let payload = BASE64.decode(encoded).context("Invalid encrypted value")?;
+        let plaintext = self.open_payload(&payload)?;
+
+        String::from_utf8(plaintext).context("Decrypted value is not valid UTF-8")
+    }
+
+    pub fn is_encrypted_bytes(value: &[u8]) -> bool {
+        value.starts_with(ENCRYPTED_PREFIX.as_bytes())
+    }
+
+    /// Chiffre un contenu binaire (pièces jointes), sans encodage base64
+    pub fn encrypt_bytes(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut value = ENCRYPTED_PREFIX.as_bytes().to_vec();
+        value.extend(self.seal_payload(plaintext)?);
+        Ok(value)
+    }
+
+    /// Déchiffre un contenu binaire (les contenus en clair sont renvoyés tels quels)
+    pub fn decrypt_bytes(&self, value: &[u8]) -> Result<Vec<u8>> {
+        match value.strip_prefix(ENCRYPTED_PREFIX.as_bytes()) {
+            Some(payload) => self.open_payload(payload),
+            None => Ok(value.to_vec()),
+        }
+    }
+
+    /// Nonce suivi du texte chiffré
+    fn seal_payload(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        Ok(payload)
+    }
+
+    fn open_payload(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        if payload.len() < NONCE_LEN {
+            anyhow::bail!("Invalid encrypted value");
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into()?;
+        self.cipher
+            .decrypt(&Nonce::from(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Decryption failed (wrong key or corrupted data)"))
+    }
+}
+
+/// État du chiffrement partagé entre le repository et les commandes
+#[derive(Debug, Clone, Default)]
+pub enum CipherState {
+    /// Contenu stocké en clair
+    #[default]
+    Disabled,
+    /// Base chiffrée, phrase de passe pas encore saisie
+    Locked,
+    Unlocked(ContentCipher),
+}
+
+/// État du chiffrement partagé, avec le verrou qui suspend les écritures de
+/// contenu pendant qu'un chiffrement ou un déchiffrement réécrit la base
+#[derive(Debug, Clone, Default)]
+pub struct SharedCipher {
+    state: Arc<RwLock<CipherState>>,
+    rewrite: Arc<tokio::sync::RwLock<()>>,
+}
+
+impl SharedCipher {
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, CipherState>> {
+        self.state.read()
+    }
+
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, CipherState>> {
+        self.state.write()
+    }
+
+    /// Pris par les écritures de contenu, du chiffrement jusqu'à l'écriture en base
+    pub async fn lock_writes(&self) -> tokio::sync::RwLockReadGuard<'_, ()> {
+        self.rewrite.read().await
+    }
+
+    /// Pris pendant la réécriture du contenu : attend puis bloque les écritures
+    pub async fn lock_rewrite(&self) -> tokio::sync::RwLockWriteGuard<'_, ()> {
+        self.rewrite.write().await
+    }
+}
+
+impl CipherState {
+    /// Chiffre une valeur avant écriture
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        match self {
+            CipherState::Disabled => Ok(plaintext.to_string()),
+            CipherState::Locked => Err(AppError::DatabaseLocked.into()),
+            CipherState::Unlocked(cipher) => cipher.encrypt(plaintext),
+        }
+    }
+
+    /// Déchiffre une valeur lue
+    pub fn open(&self, value: &str) -> Result<String> {
+        match self {
+            CipherState::Unlocked(cipher) => cipher.decrypt(value),
+            _ if ContentCipher::is_encrypted(value) => Err(AppError::DatabaseLocked.into()),
+            _ => Ok(value.to_string()),
+        }
+    }
+
+    /// Chiffre un contenu binaire avant écriture
+    pub fn seal_bytes(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            CipherState::Disabled => Ok(plaintext.to_vec()),
+            CipherState::Locked => Err(AppError::DatabaseLocked.into()),
+            CipherState::Unlocked(cipher) => cipher.encrypt_bytes(plaintext),
+        }
+    }
+
+    /// Déchiffre un contenu binaire lu
+    pub fn open_bytes(&self, value: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            CipherState::Unlocked(cipher) => cipher.decrypt_bytes(value),
+            _ if ContentCipher::is_encrypted_bytes(value) => Err(AppError::DatabaseLocked.into()),
+            _ => Ok(value.to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_wrong_passphrase() {
+        let (cipher, params) = ContentCipher::create("correct horse").unwrap();
+
+        let sealed = cipher.encrypt("Bonjour").unwrap();
+        assert!(ContentCipher::is_encrypted(&sealed));
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), "Bonjour");
+        assert_eq!(cipher.decrypt("en clair").unwrap(), "en clair");
+
+        let unlocked = ContentCipher::unlock("correct horse", &params).unwrap();
+        assert_eq!(unlocked.decrypt(&sealed).unwrap(), "Bonjour");
+        assert!(ContentCipher::unlock("wrong", &params).is_err());
+    }
+
+    #[test]
+    fn test_locked_state_refuses_access() {
+        let (cipher, _) = ContentCipher::create("secret").unwrap();
+        let sealed = cipher.encrypt("Bonjour").unwrap();
+
+        assert!(CipherState::Locked.seal("Bonjour").is_err());
+        assert!(CipherState::Locked.open(&sealed).is_err());
+        assert_eq!(CipherState::Disabled.open("Bonjour").unwrap(), "Bonjour");
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let (cipher, _) = ContentCipher::create("secret").unwrap();
+        let image = [0x89, b'P', b'N', b'G', 0, 255];
+
+        let sealed = CipherState::Unlocked(cipher.clone()).seal_bytes(&image).unwrap();
+        assert!(ContentCipher::is_encrypted_bytes(&sealed));
+        assert_eq!(cipher.decrypt_bytes(&sealed).unwrap(), image);
+        assert!(CipherState::Locked.open_bytes(&sealed).is_err());
+        assert_eq!(CipherState::Disabled.open_bytes(&image).unwrap(), image);
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_blocks_content_writes() {
+        let shared = SharedCipher::default();
+        let rewriting = shared.lock_rewrite().await;
+        let waiting = tokio::time::timeout(std::time::Duration::from_millis(20), shared.lock_writes()).await;
+        assert!(waiting.is_err());
+
+        drop(rewriting);
+        let _writing = shared.lock_writes().await;
+    }
+}
@@ -0,0 +1,74 @@
+/// Feedback on assistant messages (thumbs up/down) and preference datasets
+///
+/// Rated answers are exported as JSONL in the unpaired preference format used by
+/// KTO trainers (TRL and others): one line per rating with the conversation up
+/// to the answer as `prompt`, the answer as `completion` and the rating as
+/// `label`.
+
+use super::models::StoredMessage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Thumbs up or down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackRating {
+    Up,
+    Down,
+}
+
+impl FeedbackRating {
+    /// Value stored in the `rating` column
+    pub fn score(self) -> i64 {
+        match self {
+            FeedbackRating::Up => 1,
+            FeedbackRating::Down => -1,
+        }
+    }
+
+    pub fn from_score(score: i64) -> Self {
+        if score > 0 { FeedbackRating::Up } else { FeedbackRating::Down }
+    }
+}
+
+/// Rating of one assistant message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MessageFeedback {
+    #[ts(type = "number")]
+    pub message_id: i64,
+    pub rating: FeedbackRating,
+    pub comment: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A rated answer with the messages that preceded it
+#[derive(Debug, Clone)]
+pub struct RatedExchange {
+    pub prompt: Vec<StoredMessage>,
+    pub response: StoredMessage,
+    pub feedback: MessageFeedback,
+}
+
+impl RatedExchange {
+    /// One JSONL record (`prompt`, `completion`, `label`, `comment`)
+    pub fn to_preference_record(&self) -> serde_json::Value {
+        let message = |m: &StoredMessage| serde_json::json!({ "role": m.role, "content": m.content });
+        serde_json::json!({
+            "prompt": self.prompt.iter().map(message).collect::<Vec<_>>(),
+            "completion": [message(&self.response)],
+            "label": self.feedback.rating == FeedbackRating::Up,
+            "comment": self.feedback.comment,
+        })
+    }
+}
+
+/// JSONL document of the exchanges, one record per line
+pub fn to_preference_jsonl(exchanges: &[RatedExchange]) -> String {
+    exchanges
+        .iter()
+        .map(|exchange| format!("{}\n", exchange.to_preference_record()))
+        .collect()
+}
@@ -0,0 +1,90 @@
+/// Imported models - local GGUF files copied or linked into the models directory
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use ts_rs::TS;
+
+/// A model file imported from elsewhere on the disk
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImportedModel {
+    /// File name in the models directory
+    pub filename: String,
+    pub source_path: String,
+    /// Symlinked rather than copied: the model disappears with its source
+    pub linked: bool,
+    #[ts(type = "number")]
+    pub size_bytes: u64,
+    pub gguf_version: u32,
+    #[ts(type = "number")]
+    pub tensor_count: u64,
+    pub imported_at: DateTime<Utc>,
+}
+
+/// Persistence of the imported models
+pub struct ImportedModelRepository {
+    pool: SqlitePool,
+}
+
+impl ImportedModelRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Record an import, replacing a previous import of the same file name
+    pub async fn record(&self, model: &ImportedModel) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO imported_models
+                (filename, source_path, linked, size_bytes, gguf_version, tensor_count, imported_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&model.filename)
+        .bind(&model.source_path)
+        .bind(model.linked)
+        .bind(model.size_bytes as i64)
+        .bind(model.gguf_version as i64)
+        .bind(model.tensor_count as i64)
+        .bind(model.imported_at.timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to record imported model")?;
+
+        Ok(())
+    }
+
+    pub async fn get(&self, filename: &str) -> Result<Option<ImportedModel>> {
+        let row = sqlx::query("SELECT * FROM imported_models WHERE filename = ?")
+            .bind(filename)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to get imported model")?;
+
+        Ok(row.map(|row| Self::from_row(&row)))
+    }
+
+    pub async fn list(&self) -> Result<Vec<ImportedModel>> {
+        let rows = sqlx::query("SELECT * FROM imported_models ORDER BY filename")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list imported models")?;
+
+        Ok(rows.iter().map(Self::from_row).collect())
+    }
+
+    fn from_row(row: &SqliteRow) -> ImportedModel {
+        ImportedModel {
+            filename: row.get("filename"),
+            source_path: row.get("source_path"),
+            linked: row.get("linked"),
+            size_bytes: row.get::<i64, _>("size_bytes") as u64,
+            gguf_version: row.get::<i64, _>("gguf_version") as u32,
+            tensor_count: row.get::<i64, _>("tensor_count") as u64,
+            imported_at: DateTime::from_timestamp(row.get("imported_at"), 0).unwrap_or_else(Utc::now),
+        }
+    }
+}
@@ -0,0 +1,796 @@
+/// Knowledge-base folders - directories indexed for retrieval and kept up to date
+///
+/// A folder is scanned recursively; supported documents (text, PDF, DOCX) are
+/// chunked and embedded like attachments. Files are compared by modification time
+/// and size, so a new pass only re-chunks the files that changed and drops the
+/// ones that disappeared. A background task watches the folders and reindexes them
+/// after each burst of changes. The files are readable on disk, their chunks are
+/// stored unencrypted.
+
+use super::documents::{self, Citation, DocumentFormat};
+use super::memories::{decode_embedding, encode_embedding};
+use super::retrieval::{self, RetrievalSettings};
+use crate::llm::{EngineHandle, GenerationPriority, GenerationScheduler};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use ts_rs::TS;
+
+/// Files larger than this are skipped
+pub const MAX_FILE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Maximum number of knowledge-base chunks included in a prompt
+pub const MAX_KNOWLEDGE_CHUNKS: usize = 4;
+
+/// Quiet period before a changed folder is reindexed
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+
+/// A directory indexed for retrieval
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct KnowledgeFolder {
+    pub id: String,
+    pub path: String,
+    /// Reranker GGUF (file name in the models directory) rescoring the retrieved chunks
+    pub reranker_model: Option<String>,
+    #[ts(type = "number")]
+    pub files: usize,
+    #[ts(type = "number")]
+    pub chunks: usize,
+    #[serde(with = "chrono::serde::ts_seconds_option")]
+    #[ts(type = "number | null")]
+    pub last_indexed_at: Option<DateTime<Utc>>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[ts(type = "number")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Payload of the `kb-index-progress` event
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct KbIndexProgress {
+    pub folder_id: String,
+    /// File being indexed (None on the final event)
+    pub path: Option<String>,
+    #[ts(type = "number")]
+    pub indexed: usize,
+    #[ts(type = "number")]
+    pub total: usize,
+    #[ts(type = "number")]
+    pub removed: usize,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+/// Modification time and size of a file, used to detect changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub modified: i64,
+    pub size: u64,
+}
+
+/// An indexed file: its stamp and the model its chunks were embedded with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedFile {
+    pub stamp: FileStamp,
+    /// None when the file was indexed without embeddings (no model loaded)
+    pub embedding_model: Option<String>,
+}
+
+/// A chunk of a knowledge-base file
+#[derive(Debug, Clone)]
+pub struct KnowledgeChunk {
+    pub file_id: String,
+    pub path: String,
+    pub index: usize,
+    pub content: String,
+    pub embedding: Option<Vec<f32>>,
+    /// Character offsets of the chunk in the extracted text of the file
+    pub start: usize,
+    pub end: usize,
+    /// Page of the PDF the chunk comes from (1-based)
+    pub page: Option<usize>,
+}
+
+/// Files to (re)index and files to forget after a scan
+#[derive(Debug, Default, PartialEq)]
+pub struct IndexPlan {
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Compare the indexed files with the files on disk
+///
+/// With a model loaded (`embedding_model`), files embedded by another model or
+/// indexed without embeddings are indexed again even if they did not change.
+pub fn plan(
+    known: &BTreeMap<String, IndexedFile>,
+    current: &BTreeMap<String, FileStamp>,
+    embedding_model: Option<&str>,
+) -> IndexPlan {
+    let outdated = |file: &IndexedFile, stamp: &FileStamp| {
+        file.stamp != *stamp
+            || embedding_model.is_some_and(|model| file.embedding_model.as_deref() != Some(model))
+    };
+    IndexPlan {
+        changed: current
+            .iter()
+            .filter(|(path, stamp)| known.get(*path).is_none_or(|file| outdated(file, stamp)))
+            .map(|(path, _)| path.clone())
+            .collect(),
+        removed: known.keys().filter(|path| !current.contains_key(*path)).cloned().collect(),
+    }
+}
+
+/// Supported files of a folder by relative path (`/` separated), hidden entries skipped
+pub fn scan(root: &Path) -> Result<BTreeMap<String, FileStamp>> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(&dir).with_context(|| format!("Failed to read {:?}", dir))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if !metadata.is_file() || metadata.len() > MAX_FILE_BYTES || DocumentFormat::detect(&file_name, "").is_none() {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            let relative: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs() as i64);
+            files.insert(relative.join("/"), FileStamp { modified, size: metadata.len() });
+        }
+    }
+    Ok(files)
+}
+
+/// The `limit` most relevant chunks, best first
+///
+/// `query` is the embedding of the question and `lexical` the FTS5 matches
+/// (`KnowledgeRepository::search`); both rankings are fused.
+pub fn select(
+    chunks: Vec<KnowledgeChunk>,
+    query: Option<&[f32]>,
+    lexical: &[(String, usize)],
+    settings: &RetrievalSettings,
+    limit: usize,
+) -> Vec<KnowledgeChunk> {
+    let embeddings: Vec<Option<&[f32]>> = chunks.iter().map(|c| c.embedding.as_deref()).collect();
+    let vector = documents::vector_ranking(&embeddings, query);
+    let positions: HashMap<(&str, usize), usize> = chunks
+        .iter()
+        .enumerate()
+        .map(|(position, chunk)| ((chunk.file_id.as_str(), chunk.index), position))
+        .collect();
+    let lexical: Vec<usize> = lexical
+        .iter()
+        .filter_map(|(file_id, index)| positions.get(&(file_id.as_str(), *index)).copied())
+        .collect();
+    let order = retrieval::fuse(chunks.len(), &vector, &lexical, settings);
+
+    let mut chunks: Vec<Option<KnowledgeChunk>> = chunks.into_iter().map(Some).collect();
+    order.into_iter().take(limit).filter_map(|i| chunks[i].take()).collect()
+}
+
+impl KnowledgeChunk {
+    /// Source covering this chunk, numbered `source` in the prompt (its
+    /// `attachment_id` is the id of the knowledge-base file)
+    pub fn citation(&self, source: usize) -> Citation {
+        Citation {
+            source,
+            attachment_id: self.file_id.clone(),
+            document: self.path.clone(),
+            chunk: Some(self.index),
+            start: self.start,
+            end: self.end,
+            page: self.page,
+        }
+    }
+}
+
+/// Block appended to the prompt with the relevant excerpts of a folder,
+/// numbered from `first_source`
+pub fn knowledge_block(folder: &KnowledgeFolder, chunks: &[KnowledgeChunk], first_source: usize) -> String {
+    let excerpts: Vec<String> = chunks
+        .iter()
+        .enumerate()
+        .map(|(i, c)| match c.page {
+            Some(page) => format!("[{}] ({} p. {}) {}", first_source + i, c.path, page, c.content),
+            None => format!("[{}] ({}) {}", first_source + i, c.path, c.content),
+        })
+        .collect();
+    format!(
+        "<knowledge_base folder=\"{}\">\n{}\n</knowledge_base>",
+        folder.path,
+        excerpts.join("\n[...]\n")
+    )
+}
+
+/// Persistence of knowledge-base folders, files and chunks
+pub struct KnowledgeRepository {
+    pool: SqlitePool,
+}
+
+impl KnowledgeRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Register a folder (its canonical path must not be registered yet)
+    pub async fn add_folder(&self, path: &str) -> Result<KnowledgeFolder> {
+        let folder = KnowledgeFolder {
+            id: uuid::Uuid::new_v4().to_string(),
+            path: path.to_string(),
+            reranker_model: None,
+            files: 0,
+            chunks: 0,
+            last_indexed_at: None,
+            created_at: Utc::now(),
+        };
+        sqlx::query("INSERT INTO kb_folders (id, path, created_at) VALUES (?, ?, ?)")
+            .bind(&folder.id)
+            .bind(&folder.path)
+            .bind(folder.created_at.timestamp())
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to add knowledge folder {}", path))?;
+
+        info!("Added knowledge folder {} ({})", folder.path, folder.id);
+        Ok(folder)
+    }
+
+    pub async fn list_folders(&self) -> Result<Vec<KnowledgeFolder>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT f.id, f.path, f.reranker_model, f.created_at,
+                   (SELECT COUNT(*) FROM kb_files WHERE folder_id = f.id) AS files,
+                   (SELECT COUNT(*) FROM kb_chunks c JOIN kb_files k ON k.id = c.file_id WHERE k.folder_id = f.id) AS chunks,
+                   (SELECT MAX(indexed_at) FROM kb_files WHERE folder_id = f.id) AS last_indexed_at
+            FROM kb_folders f
+            ORDER BY f.path
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list knowledge folders")?;
+
+        Ok(rows
+            .iter()
+            .map(|row| KnowledgeFolder {
+                id: row.get("id"),
+                path: row.get("path"),
+                reranker_model: row.get("reranker_model"),
+                files: row.get::<i64, _>("files") as usize,
+                chunks: row.get::<i64, _>("chunks") as usize,
+                last_indexed_at: row.get::<Option<i64>, _>("last_indexed_at").and_then(|t| DateTime::from_timestamp(t, 0)),
+                created_at: DateTime::from_timestamp(row.get("created_at"), 0).unwrap_or_else(Utc::now),
+            })
+            .collect())
+    }
+
+    pub async fn get_folder(&self, id: &str) -> Result<Option<KnowledgeFolder>> {
+        Ok(self.list_folders().await?.into_iter().find(|folder| folder.id == id))
+    }
+
+    /// Set or clear the reranker of a folder
+    pub async fn set_reranker(&self, id: &str, model_name: Option<&str>) -> Result<bool> {
+        let result = sqlx::query("UPDATE kb_folders SET reranker_model = ? WHERE id = ?")
+            .bind(model_name)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to set knowledge folder reranker")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove a folder with its files and chunks (nothing is deleted on disk)
+    pub async fn delete_folder(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM kb_folders WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete knowledge folder")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Indexed files of a folder with the stamp they had when indexed
+    pub async fn files(&self, folder_id: &str) -> Result<BTreeMap<String, IndexedFile>> {
+        let rows: Vec<(String, i64, i64, Option<String>)> =
+            sqlx::query_as("SELECT path, modified_at, size, embedding_model FROM kb_files WHERE folder_id = ?")
+                .bind(folder_id)
+                .fetch_all(&self.pool)
+                .await
+                .context("Failed to fetch knowledge files")?;
+        Ok(rows
+            .into_iter()
+            .map(|(path, modified, size, embedding_model)| {
+                let stamp = FileStamp { modified, size: size as u64 };
+                (path, IndexedFile { stamp, embedding_model })
+            })
+            .collect())
+    }
+
+    /// Replace the chunks of a file
+    pub async fn save_file(
+        &self,
+        folder_id: &str,
+        path: &str,
+        stamp: FileStamp,
+        chunks: &[KnowledgeChunk],
+        embedding_model: Option<&str>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM kb_files WHERE folder_id = ? AND path = ?")
+            .bind(folder_id)
+            .bind(path)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear knowledge file")?;
+        let file_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO kb_files (id, folder_id, path, modified_at, size, embedding_model, indexed_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&file_id)
+        .bind(folder_id)
+        .bind(path)
+        .bind(stamp.modified)
+        .bind(stamp.size as i64)
+        .bind(embedding_model)
+        .bind(Utc::now().timestamp())
+        .execute(&mut *tx)
+        .await
+        .context("Failed to save knowledge file")?;
+        for chunk in chunks {
+            sqlx::query(
+                r#"
+                INSERT INTO kb_chunks (file_id, chunk_index, content, embedding, start_char, end_char, page)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&file_id)
+            .bind(chunk.index as i64)
+            .bind(&chunk.content)
+            .bind(chunk.embedding.as_deref().map(encode_embedding))
+            .bind(chunk.start as i64)
+            .bind(chunk.end as i64)
+            .bind(chunk.page.map(|page| page as i64))
+            .execute(&mut *tx)
+            .await
+            .context("Failed to save knowledge chunk")?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Chunks of a folder containing words of the question, best BM25 score first
+    pub async fn search(&self, folder_id: &str, text: &str, limit: usize) -> Result<Vec<(String, usize)>> {
+        let Some(query) = retrieval::fts_query(text) else {
+            return Ok(Vec::new());
+        };
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT c.file_id, c.chunk_index
+            FROM kb_chunks_fts f
+            JOIN kb_chunks c ON c.id = f.rowid
+            JOIN kb_files k ON k.id = c.file_id
+            WHERE kb_chunks_fts MATCH ? AND k.folder_id = ?
+            ORDER BY bm25(kb_chunks_fts)
+            LIMIT ?
+            "#,
+        )
+        .bind(query)
+        .bind(folder_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to search knowledge chunks")?;
+        Ok(rows.into_iter().map(|(file_id, index)| (file_id, index as usize)).collect())
+    }
+
+    pub async fn delete_file(&self, folder_id: &str, path: &str) -> Result<()> {
+        sqlx::query("DELETE FROM kb_files WHERE folder_id = ? AND path = ?")
+            .bind(folder_id)
+            .bind(path)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete knowledge file")?;
+        Ok(())
+    }
+
+    /// All chunks of a folder
+    pub async fn chunks(&self, folder_id: &str) -> Result<Vec<KnowledgeChunk>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT c.file_id, k.path, c.chunk_index, c.content, c.embedding, c.start_char, c.end_char, c.page
+            FROM kb_chunks c
+            JOIN kb_files k ON k.id = c.file_id
+            WHERE k.folder_id = ?
+            ORDER BY k.path, c.chunk_index
+            "#,
+        )
+        .bind(folder_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch knowledge chunks")?;
+
+        rows.iter()
+            .map(|row| {
+                let embedding: Option<Vec<u8>> = row.get("embedding");
+                Ok(KnowledgeChunk {
+                    file_id: row.get("file_id"),
+                    path: row.get("path"),
+                    index: row.get::<i64, _>("chunk_index") as usize,
+                    content: row.get("content"),
+                    embedding: embedding.map(|e| decode_embedding(&e)),
+                    start: row.get::<i64, _>("start_char") as usize,
+                    end: row.get::<i64, _>("end_char") as usize,
+                    page: row.get::<Option<i64>, _>("page").map(|page| page as usize),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Chunks of the extracted text of a file, with their character offsets
+fn chunk_file(path: &str, text: &str) -> Vec<KnowledgeChunk> {
+    let mut searched_from = 0;
+    documents::chunk_document(text, documents::CHUNK_CHARS)
+        .into_iter()
+        .map(|chunk| {
+            let (start, end) = match text[searched_from..].find(&chunk.content) {
+                Some(byte) => {
+                    let byte = searched_from + byte;
+                    searched_from = byte + chunk.content.len();
+                    let start = text[..byte].chars().count();
+                    (start, start + chunk.content.chars().count())
+                }
+                None => (0, 0),
+            };
+            KnowledgeChunk {
+                file_id: String::new(),
+                path: path.to_string(),
+                index: chunk.index,
+                content: chunk.content,
+                embedding: None,
+                start,
+                end,
+                page: chunk.page,
+            }
+        })
+        .collect()
+}
+
+/// File name of the loaded model, recorded with the embeddings it computed
+fn embedding_model(engine: &EngineHandle) -> Option<String> {
+    if !engine.is_loaded() {
+        return None;
+    }
+    Path::new(&engine.config().model.model_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Extract, chunk and embed one file
+async fn index_file(
+    repo: &KnowledgeRepository,
+    engine: &EngineHandle,
+    scheduler: &GenerationScheduler,
+    folder: &KnowledgeFolder,
+    path: &str,
+    stamp: FileStamp,
+) -> Result<()> {
+    let full_path = Path::new(&folder.path).join(path);
+    let format = DocumentFormat::detect(path, "").context("Unsupported file")?;
+    let data = tokio::fs::read(&full_path).await.with_context(|| format!("Failed to read {:?}", full_path))?;
+    let text = tokio::task::spawn_blocking(move || documents::extract_text(format, &data)).await??;
+
+    let mut chunks = chunk_file(path, &text);
+    let contents: Vec<String> = chunks.iter().map(|chunk| chunk.content.clone()).collect();
+    let embeddings = if contents.is_empty() {
+        None
+    } else {
+        // Background work: queued behind the generations the user is waiting for
+        match scheduler.embed(engine, contents, GenerationPriority::Low).await {
+            Ok(embeddings) => Some(embeddings),
+            Err(e) => {
+                debug!("{} indexed without embeddings: {:#}", path, e);
+                None
+            }
+        }
+    };
+    let embedding_model = embeddings.as_ref().and_then(|_| embedding_model(engine));
+    if let Some(embeddings) = embeddings {
+        for (chunk, embedding) in chunks.iter_mut().zip(embeddings) {
+            chunk.embedding = Some(embedding);
+        }
+    }
+    repo.save_file(&folder.id, path, stamp, &chunks, embedding_model.as_deref()).await
+}
+
+/// Bring the index of a folder up to date with the files on disk
+///
+/// Passes are serialized: the watcher and a manual reindex never index together.
+pub async fn index_folder(
+    repo: &KnowledgeRepository,
+    engine: &EngineHandle,
+    scheduler: &GenerationScheduler,
+    folder: &KnowledgeFolder,
+    on_progress: &(dyn Fn(KbIndexProgress) + Send + Sync),
+) -> Result<IndexPlan> {
+    static RUNNING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+    let _running = RUNNING.lock().await;
+
+    let root = PathBuf::from(&folder.path);
+    let current = tokio::task::spawn_blocking(move || scan(&root)).await??;
+    let plan = plan(&repo.files(&folder.id).await?, &current, embedding_model(engine).as_deref());
+    let progress = |path: Option<&str>, indexed: usize, done: bool, error: Option<String>| KbIndexProgress {
+        folder_id: folder.id.clone(),
+        path: path.map(str::to_string),
+        indexed,
+        total: plan.changed.len(),
+        removed: plan.removed.len(),
+        done,
+        error,
+    };
+
+    for path in &plan.removed {
+        repo.delete_file(&folder.id, path).await?;
+    }
+    for (indexed, path) in plan.changed.iter().enumerate() {
+        on_progress(progress(Some(path), indexed, false, None));
+        if let Err(e) = index_file(repo, engine, scheduler, folder, path, current[path]).await {
+            warn!("Knowledge file {} not indexed: {:#}", path, e);
+            on_progress(progress(Some(path), indexed, false, Some(format!("{:#}", e))));
+        }
+    }
+    on_progress(progress(None, plan.changed.len(), true, None));
+
+    if !plan.changed.is_empty() || !plan.removed.is_empty() {
+        info!(
+            "Knowledge folder {}: {} files indexed, {} removed",
+            folder.path, plan.changed.len(), plan.removed.len()
+        );
+    }
+    Ok(plan)
+}
+
+/// Request sent to the background indexer
+#[derive(Debug, Clone)]
+pub enum IndexRequest {
+    /// Reindex a folder now
+    Folder(String),
+    /// A model was loaded: embed the files indexed without embeddings or with another model
+    All,
+    /// The list of folders changed: update the watches
+    Rewatch,
+}
+
+/// Handle used by the commands to reach the background indexer
+pub struct KnowledgeIndexer {
+    requests: mpsc::UnboundedSender<IndexRequest>,
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<IndexRequest>>>,
+}
+
+impl KnowledgeIndexer {
+    pub fn new() -> Self {
+        let (requests, receiver) = mpsc::unbounded_channel();
+        Self { requests, receiver: Mutex::new(Some(receiver)) }
+    }
+
+    pub fn request(&self, request: IndexRequest) {
+        if self.requests.send(request).is_err() {
+            warn!("Knowledge indexer is not running");
+        }
+    }
+
+    /// Requests for `run` (available once)
+    pub fn take_receiver(&self) -> Option<mpsc::UnboundedReceiver<IndexRequest>> {
+        self.receiver.lock().unwrap().take()
+    }
+}
+
+impl Default for KnowledgeIndexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Folder containing a changed path (the longest matching root)
+fn folder_of<'a>(folders: &'a [KnowledgeFolder], path: &Path) -> Option<&'a KnowledgeFolder> {
+    folders
+        .iter()
+        .filter(|folder| path.starts_with(&folder.path))
+        .max_by_key(|folder| folder.path.len())
+}
+
+/// Watch the knowledge folders and reindex them after changes, until the application exits
+pub async fn run(
+    repo: KnowledgeRepository,
+    engine: EngineHandle,
+    scheduler: GenerationScheduler,
+    mut requests: mpsc::UnboundedReceiver<IndexRequest>,
+    on_progress: impl Fn(KbIndexProgress) + Send + Sync,
+) -> Result<()> {
+    let (events_tx, mut events) = mpsc::unbounded_channel::<PathBuf>();
+    // notify calls the handler from its own thread
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+        Ok(event) => {
+            for path in event.paths {
+                let _ = events_tx.send(path);
+            }
+        }
+        Err(e) => warn!("Knowledge folder watcher error: {}", e),
+    })
+    .context("Failed to create the knowledge folder watcher")?;
+
+    let mut folders: Vec<KnowledgeFolder> = Vec::new();
+    let mut watched: HashSet<String> = HashSet::new();
+    // Catch up with the changes made while the application was closed
+    let mut pending: HashSet<String> = HashSet::new();
+    let mut rewatch = true;
+
+    loop {
+        if rewatch {
+            folders = repo.list_folders().await?;
+            let current: HashSet<String> = folders.iter().map(|f| f.path.clone()).collect();
+            for path in watched.difference(&current) {
+                let _ = watcher.unwatch(Path::new(path));
+            }
+            for folder in folders.iter().filter(|f| !watched.contains(&f.path)) {
+                match watcher.watch(Path::new(&folder.path), RecursiveMode::Recursive) {
+                    Ok(()) => info!("Watching knowledge folder: {}", folder.path),
+                    Err(e) => warn!("Knowledge folder {} not watched: {}", folder.path, e),
+                }
+                pending.insert(folder.id.clone());
+            }
+            watched = current;
+            rewatch = false;
+        }
+
+        for folder in folders.iter().filter(|f| pending.contains(&f.id)) {
+            if let Err(e) = index_folder(&repo, &engine, &scheduler, folder, &on_progress).await {
+                warn!("Knowledge folder {} not indexed: {:#}", folder.path, e);
+                on_progress(KbIndexProgress {
+                    folder_id: folder.id.clone(),
+                    path: None,
+                    indexed: 0,
+                    total: 0,
+                    removed: 0,
+                    done: true,
+                    error: Some(format!("{:#}", e)),
+                });
+            }
+        }
+        pending.clear();
+
+        tokio::select! {
+            request = requests.recv() => match request {
+                Some(IndexRequest::Folder(id)) => {
+                    pending.insert(id);
+                }
+                Some(IndexRequest::All) => pending.extend(folders.iter().map(|f| f.id.clone())),
+                Some(IndexRequest::Rewatch) => rewatch = true,
+                None => return Ok(()),
+            },
+            Some(path) = events.recv() => {
+                let mut paths = vec![path];
+                // Wait for the burst to end, the scan then sees the final state
+                while let Ok(Some(path)) = tokio::time::timeout(SETTLE_DELAY, events.recv()).await {
+                    paths.push(path);
+                }
+                for path in &paths {
+                    if let Some(folder) = folder_of(&folders, path) {
+                        pending.insert(folder.id.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+
+    fn stamp(modified: i64) -> FileStamp {
+        FileStamp { modified, size: 10 }
+    }
+
+    fn indexed(modified: i64, embedding_model: Option<&str>) -> IndexedFile {
+        IndexedFile { stamp: stamp(modified), embedding_model: embedding_model.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_plan_and_scan() {
+        let known = BTreeMap::from([("a.md".to_string(), indexed(1, None)), ("b.md".to_string(), indexed(1, None))]);
+        let current = BTreeMap::from([("b.md".to_string(), stamp(2)), ("c.md".to_string(), stamp(1))]);
+        let changes = plan(&known, &current, None);
+        assert_eq!(changes.changed, vec!["b.md", "c.md"]);
+        assert_eq!(changes.removed, vec!["a.md"]);
+
+        // Files without embeddings, or embedded by another model, are embedded again
+        let known = BTreeMap::from([
+            ("a.md".to_string(), indexed(1, None)),
+            ("b.md".to_string(), indexed(1, Some("old.gguf"))),
+            ("c.md".to_string(), indexed(1, Some("nomic.gguf"))),
+        ]);
+        let current = BTreeMap::from([("a.md".to_string(), stamp(1)), ("b.md".to_string(), stamp(1)), ("c.md".to_string(), stamp(1))]);
+        assert!(plan(&known, &current, None).changed.is_empty());
+        assert_eq!(plan(&known, &current, Some("nomic.gguf")).changed, vec!["a.md", "b.md"]);
+
+        let dir = std::env::temp_dir().join(format!("agents-rs-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("notes/.git")).unwrap();
+        std::fs::write(dir.join("notes/todo.md"), "- tests").unwrap();
+        std::fs::write(dir.join("notes/.git/HEAD"), "ref").unwrap();
+        std::fs::write(dir.join("photo.png"), [0u8; 4]).unwrap();
+        let files = scan(&dir).unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["notes/todo.md"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_knowledge_repository() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = KnowledgeRepository::new(db.pool().clone());
+        let folder = repo.add_folder("/data/notes").await.unwrap();
+        assert!(repo.add_folder("/data/notes").await.is_err());
+
+        let text = "Intro.\n\nDetails.";
+        let mut chunks = chunk_file("a.md", text);
+        chunks[0].embedding = Some(vec![1.0, 0.0]);
+        repo.save_file(&folder.id, "a.md", stamp(1), &chunks, Some("nomic.gguf")).await.unwrap();
+        repo.save_file(&folder.id, "a.md", stamp(2), &chunks, Some("nomic.gguf")).await.unwrap();
+
+        assert_eq!(repo.files(&folder.id).await.unwrap()["a.md"], indexed(2, Some("nomic.gguf")));
+        let stored = repo.chunks(&folder.id).await.unwrap();
+        assert_eq!(repo.search(&folder.id, "details?", 10).await.unwrap(), vec![(stored[0].file_id.clone(), 0)]);
+        assert!(repo.search(&folder.id, "missing", 10).await.unwrap().is_empty());
+        assert_eq!(stored.len(), 1);
+        assert_eq!((stored[0].start, stored[0].end), (0, text.chars().count()));
+        assert!(repo.set_reranker(&folder.id, Some("bge-reranker.gguf")).await.unwrap());
+        let listed = repo.get_folder(&folder.id).await.unwrap().unwrap();
+        assert_eq!((listed.files, listed.chunks), (1, 1));
+        assert_eq!(listed.reranker_model.as_deref(), Some("bge-reranker.gguf"));
+
+        let folders = [listed];
+        assert!(folder_of(&folders, Path::new("/data/notes/sub/a.md")).is_some());
+        assert!(folder_of(&folders, Path::new("/data/other.md")).is_none());
+
+        assert!(repo.delete_folder(&folder.id).await.unwrap());
+        assert!(repo.chunks(&folder.id).await.unwrap().is_empty());
+        // The index has no entry left for the deleted chunks
+        sqlx::query("INSERT INTO kb_chunks_fts (kb_chunks_fts, rank) VALUES ('integrity-check', 1)")
+            .execute(db.pool()).await.unwrap();
+        let (indexed,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM kb_chunks_fts WHERE kb_chunks_fts MATCH 'details'")
+            .fetch_one(db.pool()).await.unwrap();
+        assert_eq!(indexed, 0);
+    }
+}
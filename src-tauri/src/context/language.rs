@@ -0,0 +1,179 @@
+/// Conversation language - detected from the first user message, or set by the user
+///
+/// Detection counts frequent function words of each supported language: cheap,
+/// dependency-free and reliable enough on a sentence or two. The language picks
+/// the default system prompt and the title-generation prompt of the conversation.
+
+use crate::llm::ChatMessage;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Minimum number of function words before a language is trusted
+const MIN_DETECTION_HITS: usize = 2;
+
+/// Maximum length of a generated title, in characters
+const MAX_TITLE_CHARS: usize = 60;
+
+/// Languages with localized prompts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+pub enum Language {
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "fr")]
+    French,
+    #[serde(rename = "es")]
+    Spanish,
+    #[serde(rename = "de")]
+    German,
+    #[serde(rename = "it")]
+    Italian,
+    #[serde(rename = "pt")]
+    Portuguese,
+}
+
+impl Language {
+    pub const ALL: [Language; 6] = [
+        Language::English,
+        Language::French,
+        Language::Spanish,
+        Language::German,
+        Language::Italian,
+        Language::Portuguese,
+    ];
+
+    /// ISO 639-1 code, as stored in the database
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::French => "fr",
+            Language::Spanish => "es",
+            Language::German => "de",
+            Language::Italian => "it",
+            Language::Portuguese => "pt",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|l| l.code() == code)
+    }
+
+    /// Language of a text, if enough of its words are recognized
+    ///
+    /// Returns None for short or ambiguous texts (code, a single word, a tie
+    /// between two languages): the next messages can still settle it.
+    pub fn detect(text: &str) -> Option<Self> {
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphabetic() && c != '\'')
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_lowercase())
+            .collect();
+
+        let mut scores: Vec<(Language, usize)> = Self::ALL
+            .into_iter()
+            .map(|language| {
+                let stopwords = language.stopwords();
+                let hits = words.iter().filter(|w| stopwords.contains(&w.as_str())).count();
+                (language, hits)
+            })
+            .collect();
+        scores.sort_by_key(|&(_, hits)| std::cmp::Reverse(hits));
+
+        match scores.as_slice() {
+            [(language, best), (_, second), ..] if *best >= MIN_DETECTION_HITS && best > second => Some(*language),
+            _ => None,
+        }
+    }
+
+    /// Default system prompt, used when the conversation has none
+    pub fn system_prompt(self) -> &'static str {
+        match self {
+            Language::English => "You are a helpful assistant. Answer in English unless the user asks otherwise.",
+            Language::French => "Tu es un assistant serviable. Réponds en français sauf si l'utilisateur demande une autre langue.",
+            Language::Spanish => "Eres un asistente útil. Responde en español salvo que el usuario pida otro idioma.",
+            Language::German => "Du bist ein hilfreicher Assistent. Antworte auf Deutsch, sofern der Nutzer nichts anderes wünscht.",
+            Language::Italian => "Sei un assistente disponibile. Rispondi in italiano salvo diversa richiesta dell'utente.",
+            Language::Portuguese => "Você é um assistente prestativo. Responda em português, a menos que o usuário peça outro idioma.",
+        }
+    }
+
+    /// Prompt asking for a short title summarizing the first exchange
+    pub fn title_messages(self, user: &str, assistant: &str) -> Vec<ChatMessage> {
+        let instructions = match self {
+            Language::English => "Write a short title (at most 6 words) in English for this conversation. Reply with the title only, without quotes.",
+            Language::French => "Écris un titre court (6 mots au plus) en français pour cette conversation. Réponds uniquement avec le titre, sans guillemets.",
+            Language::Spanish => "Escribe un título corto (6 palabras como máximo) en español para esta conversación. Responde solo con el título, sin comillas.",
+            Language::German => "Schreibe einen kurzen Titel (höchstens 6 Wörter) auf Deutsch für dieses Gespräch. Antworte nur mit dem Titel, ohne Anführungszeichen.",
+            Language::Italian => "Scrivi un titolo breve (al massimo 6 parole) in italiano per questa conversazione. Rispondi solo con il titolo, senza virgolette.",
+            Language::Portuguese => "Escreva um título curto (no máximo 6 palavras) em português para esta conversa. Responda apenas com o título, sem aspas.",
+        };
+        vec![
+            ChatMessage::system(instructions),
+            ChatMessage::user(format!("User: {}\n\nAssistant: {}", user, assistant)),
+        ]
+    }
+
+    fn stopwords(self) -> &'static [&'static str] {
+        match self {
+            Language::English => &[
+                "the", "and", "is", "are", "you", "what", "how", "to", "of", "in", "it", "this",
+                "that", "with", "for", "can", "my", "i", "do", "does", "please", "why",
+            ],
+            Language::French => &[
+                "le", "la", "les", "et", "est", "une", "un", "des", "du", "je", "tu", "vous",
+                "que", "qui", "pour", "avec", "dans", "pas", "comment", "quoi", "c'est", "mon",
+            ],
+            Language::Spanish => &[
+                "el", "los", "las", "es", "una", "que", "y", "por", "para", "con", "como",
+                "qué", "cómo", "mi", "yo", "usted", "está", "pero", "del", "lo",
+            ],
+            Language::German => &[
+                "der", "die", "das", "und", "ist", "ich", "du", "nicht", "ein", "eine", "mit",
+                "wie", "was", "für", "auf", "zu", "sie", "es", "bitte", "mein",
+            ],
+            Language::Italian => &[
+                "il", "lo", "gli", "e", "è", "che", "di", "una", "per", "con", "come", "cosa",
+                "sono", "non", "mi", "io", "della", "questo", "ciao",
+            ],
+            Language::Portuguese => &[
+                "o", "os", "as", "e", "é", "que", "de", "um", "uma", "para", "com", "como",
+                "não", "eu", "você", "meu", "isso", "está", "olá", "obrigado",
+            ],
+        }
+    }
+}
+
+/// Title from a title-generation response (None if nothing usable came back)
+pub fn parse_title(text: &str) -> Option<String> {
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let title = line
+        .trim_start_matches(|c: char| c == '#' || c.is_whitespace())
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '«' | '»' | '*' | '.') || c.is_whitespace());
+    if title.is_empty() {
+        return None;
+    }
+    Some(title.chars().take(MAX_TITLE_CHARS).collect::<String>().trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(Language::detect("How do I read a file in Rust?"), Some(Language::English));
+        assert_eq!(Language::detect("Comment est-ce que je lis un fichier avec Rust ?"), Some(Language::French));
+        assert_eq!(Language::detect("¿Cómo puedo leer un archivo con Rust y para qué sirve?"), Some(Language::Spanish));
+        assert_eq!(Language::detect("Wie kann ich eine Datei mit Rust lesen?"), Some(Language::German));
+        // Too short to tell
+        assert_eq!(Language::detect("Rust"), None);
+        assert_eq!(Language::detect("fn main() {}"), None);
+    }
+
+    #[test]
+    fn test_parse_title() {
+        assert_eq!(parse_title("\"Lire un fichier en Rust\"\n"), Some("Lire un fichier en Rust".to_string()));
+        assert_eq!(parse_title("\n# Reading files.\nExtra"), Some("Reading files".to_string()));
+        assert_eq!(parse_title("  \n"), None);
+        assert_eq!(Language::from_code(Language::German.code()), Some(Language::German));
+    }
+}
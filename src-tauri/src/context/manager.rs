@@ -1,9 +1,14 @@
 /// Gestionnaire de contexte conversationnel
 
+use super::agents::AgentProfile;
+use super::language::Language;
+use super::workspaces::Workspace;
+use super::documents::{DocumentChunk, DocumentInfo};
 use super::session::{ConversationSession, SessionSummary, Message, MessageRole};
 use super::repository::ConversationRepository;
-use super::models::StoredMessage;
-use anyhow::Result;
+use super::models::{Attachment, StoredMessage};
+use crate::error::AppError;
+use anyhow::{Context, Result};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -36,7 +41,25 @@ impl ContextManager {
 
     /// Crée une nouvelle session de conversation persistée
     pub async fn create_session(&self, title: String) -> Result<String> {
-        let model_name = self.current_model.read().await.clone();
+        self.create_session_with_agent(title, None).await
+    }
+
+    /// Crée une session configurée par un profil d'agent (prompt système en premier message)
+    pub async fn create_session_with_agent(&self, title: String, agent: Option<&AgentProfile>) -> Result<String> {
+        let session_id = self.create_detached_session(title, agent).await?;
+        
+        // Définir comme session active
+        *self.active_session_id.write().await = Some(session_id.clone());
+        
+        Ok(session_id)
+    }
+
+    /// Crée une session sans en faire la session active (tâches en arrière-plan)
+    pub async fn create_detached_session(&self, title: String, agent: Option<&AgentProfile>) -> Result<String> {
+        let model_name = match agent.and_then(|a| a.default_model.clone()) {
+            Some(model) => model,
+            None => self.current_model.read().await.clone(),
+        };
         debug!("Création d'une nouvelle session avec le modèle: {}", model_name);
         
         // Créer dans le repository
@@ -48,33 +71,72 @@ impl ContextManager {
         let session_id = conversation.id.clone();
         
         // Créer la session en mémoire
-        let session = ConversationSession::new_with_id(session_id.clone(), title);
+        let mut session = ConversationSession::new_with_id(session_id.clone(), title);
+        
+        if let Some(agent) = agent {
+            self.repository.set_conversation_agent(&session_id, Some(&agent.id)).await?;
+            session.agent_id = Some(agent.id.clone());
+        }
         
         // Mettre en cache
         self.sessions_cache.write().await.insert(session_id.clone(), session);
         
-        // Définir comme session active
-        *self.active_session_id.write().await = Some(session_id.clone());
+        if let Some(agent) = agent.filter(|a| !a.system_prompt.is_empty()) {
+            self.add_message(&session_id, Message::system(agent.system_prompt.clone())).await?;
+        }
         
         info!("Nouvelle session créée: {}", session_id);
         Ok(session_id)
     }
     
+    /// Rattache une session à un espace de travail et lui ajoute le contexte du projet
+    pub async fn attach_workspace(&self, session_id: &str, workspace: &Workspace) -> Result<()> {
+        self.repository.set_conversation_workspace(session_id, Some(&workspace.id)).await?;
+        if let Some(session) = self.sessions_cache.write().await.get_mut(session_id) {
+            session.workspace_id = Some(workspace.id.clone());
+        }
+        
+        self.add_message(session_id, Message::system(workspace.context_prompt().await)).await
+    }
+    
+    /// Définit la langue d'une session (None pour la détecter à nouveau)
+    pub async fn set_session_language(&self, session_id: &str, language: Option<Language>) -> Result<()> {
+        self.repository.set_conversation_language(session_id, language).await?;
+        if let Some(session) = self.sessions_cache.write().await.get_mut(session_id) {
+            session.language = language;
+        }
+        Ok(())
+    }
+    
     /// Helper: Charge une session depuis le repository vers le cache
     async fn load_session_to_cache(&self, session_id: &str) -> Result<()> {
         let conversation = self.repository.get_conversation(session_id).await?
-            .ok_or_else(|| anyhow::anyhow!("Session non trouvée dans la base: {}", session_id))?;
+            .ok_or_else(|| AppError::SessionNotFound { session_id: session_id.to_string() })?;
         let messages = self.repository.get_messages(session_id).await?;
+        let mut attachments = self.repository.get_attachments(session_id).await?;
         
         let mut session = ConversationSession::new_with_id(
             conversation.id.clone(),
             conversation.title.clone()
         );
+        session.agent_id = conversation.agent_id.clone();
+        session.workspace_id = conversation.workspace_id.clone();
+        session.language = conversation.language;
         
         // Ajouter les messages récupérés
         for stored_msg in messages {
             let role = Self::parse_role(&stored_msg.role)?;
-            let msg = Message::new(role, stored_msg.content.clone());
+            let mut msg = Message::new(role, stored_msg.content.clone());
+            // Identifiant en base : le même pour l'interface, la recherche et les évaluations
+            if let Some(id) = stored_msg.id {
+                msg.id = id.to_string();
+            }
+            msg.tool_name = stored_msg.tool_name;
+            msg.tool_call_id = stored_msg.tool_call_id;
+            msg.metadata = stored_msg.metadata;
+            if let Some(files) = stored_msg.id.and_then(|id| attachments.remove(&id)) {
+                msg.attachments = files;
+            }
             session.add_message(msg);
         }
         
@@ -123,7 +185,7 @@ impl ContextManager {
     }
 
     /// Ajoute un message à une session (persiste dans DB)
-    pub async fn add_message(&self, session_id: &str, message: Message) -> Result<()> {
+    pub async fn add_message(&self, session_id: &str, mut message: Message) -> Result<()> {
         debug!("Ajout d'un message {:?} à la session {}", message.role, session_id);
         
         // Convertir MessageRole en chaîne pour le DB
@@ -139,8 +201,15 @@ impl ContextManager {
             session_id.to_string(),
             role_str.to_string(),
             message.content.clone(),
-        );
-        let _stored_message = self.repository.add_message(&stored_msg).await?;
+        )
+        .with_tool_call(message.tool_name.clone(), message.tool_call_id.clone())
+        .with_metadata(message.metadata.clone());
+        let stored_message = self.repository.add_message(&stored_msg).await?;
+        let message_id = stored_message.id.context("Message sans identifiant")?;
+        message.id = message_id.to_string();
+        if !message.attachments.is_empty() {
+            self.repository.add_attachments(message_id, &message.attachments).await?;
+        }
         
         // Mettre à jour le cache - charger la session si nécessaire
         {
@@ -160,6 +229,30 @@ impl ContextManager {
         Ok(())
     }
 
+    /// Session contenant un message enregistré
+    pub async fn message_session(&self, message_id: i64) -> Result<Option<String>> {
+        Ok(self.repository.get_message(message_id).await?.map(|m| m.conversation_id))
+    }
+
+    /// Remplace le contenu et les métadonnées d'un message enregistré (DB et cache)
+    pub async fn update_message(&self, session_id: &str, message: &Message) -> Result<()> {
+        let message_id: i64 = message.id.parse().context("Identifiant de message invalide")?;
+        if !self.repository.update_message(message_id, &message.content, &message.metadata).await? {
+            anyhow::bail!("Message introuvable: {}", message.id);
+        }
+        
+        let mut sessions = self.sessions_cache.write().await;
+        if let Some(cached) = sessions
+            .get_mut(session_id)
+            .and_then(|session| session.messages.iter_mut().find(|m| m.id == message.id))
+        {
+            cached.content = message.content.clone();
+            cached.metadata = message.metadata.clone();
+        }
+        
+        Ok(())
+    }
+
     /// Ajoute un message à la session active
     pub async fn add_message_to_active(&self, message: Message) -> Result<()> {
         let active_id = self.active_session_id.read().await;
@@ -172,6 +265,50 @@ impl ContextManager {
         self.add_message(&session_id, message).await
     }
 
+    /// Récupère une pièce jointe avec son contenu
+    pub async fn get_attachment(&self, attachment_id: &str) -> Result<Option<Attachment>> {
+        self.repository.get_attachment(attachment_id).await
+    }
+
+    /// Enregistre les extraits indexés d'un document joint
+    pub async fn save_attachment_chunks(&self, attachment_id: &str, chunks: &[DocumentChunk]) -> Result<()> {
+        self.repository.save_attachment_chunks(attachment_id, chunks).await
+    }
+
+    /// Extraits indexés d'un document joint
+    pub async fn attachment_chunks(&self, attachment_id: &str) -> Result<Vec<DocumentChunk>> {
+        self.repository.get_attachment_chunks(attachment_id).await
+    }
+
+    /// Enregistre l'état de l'indexation d'un document (tokens, modèle d'embedding)
+    pub async fn save_document_index(&self, attachment_id: &str, tokens: Option<usize>, embedding_model: Option<&str>) -> Result<()> {
+        self.repository.save_document_index(attachment_id, tokens, embedding_model).await
+    }
+
+    /// Documents joints de toutes les conversations, avec leur état d'indexation
+    pub async fn list_documents(&self) -> Result<Vec<DocumentInfo>> {
+        self.repository.list_documents().await
+    }
+
+    /// Un document joint avec son état d'indexation
+    pub async fn get_document(&self, attachment_id: &str) -> Result<Option<DocumentInfo>> {
+        self.repository.get_document(attachment_id).await
+    }
+
+    /// Supprime un document joint, ses extraits et son état d'indexation
+    ///
+    /// Le document est aussi retiré des sessions en cache : il ne doit plus être
+    /// ajouté au contexte des prochaines générations.
+    pub async fn delete_attachment(&self, attachment_id: &str) -> Result<bool> {
+        let deleted = self.repository.delete_attachment(attachment_id).await?;
+        for session in self.sessions_cache.write().await.values_mut() {
+            for message in &mut session.messages {
+                message.attachments.retain(|a| a.id != attachment_id);
+            }
+        }
+        Ok(deleted)
+    }
+
     /// Liste toutes les sessions (version légère sans messages)
     pub async fn list_sessions(&self) -> Result<Vec<SessionSummary>> {
         let conversations = self.repository.list_conversations(100, 0).await?;
@@ -227,7 +364,7 @@ impl ContextManager {
         // Vérifier que la session existe
         let sessions = self.sessions_cache.read().await;
         if !sessions.contains_key(session_id) {
-            anyhow::bail!("Session non trouvée: {}", session_id);
+            return Err(AppError::SessionNotFound { session_id: session_id.to_string() }.into());
         }
         drop(sessions);
 
@@ -236,6 +373,17 @@ impl ContextManager {
         Ok(())
     }
 
+    /// Vide le cache des sessions (après une restauration de la base)
+    pub async fn clear_cache(&self) {
+        self.invalidate_cache().await;
+        *self.active_session_id.write().await = None;
+    }
+
+    /// Force la relecture des sessions depuis la base (après un élagage)
+    pub async fn invalidate_cache(&self) {
+        self.sessions_cache.write().await.clear();
+    }
+
     /// Sauvegarde les sessions (à implémenter avec SQLite)
     pub async fn save_to_disk(&self) -> Result<()> {
         // TODO: Implémenter la persistance avec SQLite
@@ -253,6 +401,25 @@ impl ContextManager {
 
 #[cfg(test)]
 mod tests {
-    // Tests require database setup - will be implemented with integration tests
-    // TODO: Add integration tests with test database
+    use super::*;
+    use crate::context::Database;
+
+    async fn setup_test_db() -> ContextManager {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        ContextManager::new(ConversationRepository::new(db.pool().clone()), "test-model".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_deleted_document_leaves_cached_session() {
+        let manager = setup_test_db().await;
+        let session_id = manager.create_session("Docs".to_string()).await.unwrap();
+        let document = Attachment::document("notes.txt".to_string(), "text/plain".to_string(), b"x".to_vec(), "x".to_string());
+        let message = Message::user("Résume".to_string()).with_attachments(vec![document.clone()]);
+        manager.add_message(&session_id, message).await.unwrap();
+        assert_eq!(manager.get_session(&session_id).await.unwrap().messages[0].attachments.len(), 1);
+
+        assert!(manager.delete_attachment(&document.id).await.unwrap());
+        assert!(manager.get_session(&session_id).await.unwrap().messages[0].attachments.is_empty());
+    }
 }
@@ -0,0 +1,302 @@
+/// Long-term memories - durable facts extracted from conversations
+///
+/// Facts are stored with an embedding of their content; the ones closest to a new
+/// user message are injected into the prompt. Contents follow the conversation
+/// encryption, embeddings are stored as little-endian f32 blobs.
+
+use super::encryption::{CipherState, SharedCipher};
+use crate::llm::ChatMessage;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use tracing::info;
+use ts_rs::TS;
+
+/// Maximum number of memories injected into a prompt
+pub const RECALL_LIMIT: usize = 5;
+
+/// Minimum similarity for a memory to be injected
+pub const MIN_RECALL_SCORE: f32 = 0.45;
+
+/// Above this similarity a new fact is considered already known
+pub const DUPLICATE_SCORE: f32 = 0.92;
+
+/// Maximum number of facts kept from one exchange
+const MAX_FACTS_PER_EXCHANGE: usize = 5;
+
+/// A durable fact about the user or their projects
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Memory {
+    pub id: String,
+    pub content: String,
+    /// Normalized embedding of the content (None if no model could compute it)
+    #[serde(skip)]
+    pub embedding: Option<Vec<f32>>,
+    pub source_conversation_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Memory {
+    pub fn new(content: String, embedding: Option<Vec<f32>>, source_conversation_id: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            content,
+            embedding,
+            source_conversation_id,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Persistence of memories
+pub struct MemoryRepository {
+    pool: SqlitePool,
+    cipher: SharedCipher,
+}
+
+impl MemoryRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool, cipher: SharedCipher::default() }
+    }
+
+    /// Share the encryption state with the rest of the application
+    pub fn with_cipher(mut self, cipher: SharedCipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    fn cipher(&self) -> CipherState {
+        self.cipher.read().unwrap().clone()
+    }
+
+    /// Insert or update a memory
+    pub async fn save(&self, memory: &Memory) -> Result<()> {
+        let _writing = self.cipher.lock_writes().await;
+        let content = self.cipher().seal(&memory.content)?;
+        sqlx::query(
+            r#"
+            INSERT INTO memories (id, content, embedding, source_conversation_id, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                content = excluded.content,
+                embedding = excluded.embedding,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&memory.id)
+        .bind(content)
+        .bind(memory.embedding.as_deref().map(encode_embedding))
+        .bind(&memory.source_conversation_id)
+        .bind(memory.created_at.timestamp())
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save memory")?;
+
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Memory>> {
+        let row = sqlx::query("SELECT * FROM memories WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch memory")?;
+
+        row.map(|row| Self::from_row(&row, &self.cipher())).transpose()
+    }
+
+    /// All memories, most recent first
+    pub async fn list(&self) -> Result<Vec<Memory>> {
+        let rows = sqlx::query("SELECT * FROM memories ORDER BY updated_at DESC")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list memories")?;
+
+        let cipher = self.cipher();
+        rows.iter().map(|row| Self::from_row(row, &cipher)).collect()
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM memories WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete memory")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Memories most similar to an embedding, best first
+    pub async fn search(&self, embedding: &[f32], limit: usize, min_score: f32) -> Result<Vec<(Memory, f32)>> {
+        let mut scored: Vec<(Memory, f32)> = self
+            .list()
+            .await?
+            .into_iter()
+            .filter_map(|memory| {
+                let score = cosine_similarity(memory.embedding.as_deref()?, embedding);
+                (score >= min_score).then_some((memory, score))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    /// Rewrite every memory from one encryption state to another, inside the caller's transaction
+    pub async fn reencrypt(conn: &mut SqliteConnection, from: &CipherState, to: &CipherState) -> Result<usize> {
+        let rows: Vec<(String, String)> = sqlx::query_as("SELECT id, content FROM memories")
+            .fetch_all(&mut *conn)
+            .await
+            .context("Failed to fetch memories")?;
+
+        for (id, content) in &rows {
+            sqlx::query("UPDATE memories SET content = ? WHERE id = ?")
+                .bind(to.seal(&from.open(content)?)?)
+                .bind(id)
+                .execute(&mut *conn)
+                .await
+                .context("Failed to update memory")?;
+        }
+
+        info!("Rewrote {} memories", rows.len());
+        Ok(rows.len())
+    }
+
+    fn from_row(row: &SqliteRow, cipher: &CipherState) -> Result<Memory> {
+        let content: String = row.get("content");
+        let embedding: Option<Vec<u8>> = row.get("embedding");
+        let created_at: i64 = row.get("created_at");
+        let updated_at: i64 = row.get("updated_at");
+
+        Ok(Memory {
+            id: row.get("id"),
+            content: cipher.open(&content)?,
+            embedding: embedding.as_deref().map(decode_embedding),
+            source_conversation_id: row.get("source_conversation_id"),
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_else(Utc::now),
+            updated_at: DateTime::from_timestamp(updated_at, 0).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+pub(crate) fn encode_embedding(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+pub(crate) fn decode_embedding(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+/// Cosine similarity of two embeddings (0 when their dimensions differ, e.g. after a model change)
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Prompt asking the model for the durable facts of one exchange
+pub fn extraction_messages(user: &str, assistant: &str) -> Vec<ChatMessage> {
+    vec![
+        ChatMessage::system(
+            "You extract durable facts worth remembering across conversations: the user's \
+             preferences, personal details they shared, and details of their projects. \
+             Ignore anything temporary or specific to this exchange. Reply only with a JSON \
+             array of short, self-contained sentences, or [] when there is nothing to remember.",
+        ),
+        ChatMessage::user(format!("User: {}\n\nAssistant: {}", user, assistant)),
+    ]
+}
+
+/// Facts listed in the extraction response (empty if it cannot be parsed)
+pub fn parse_facts(text: &str) -> Vec<String> {
+    let (Some(start), Some(end)) = (text.find('['), text.rfind(']')) else {
+        return vec![];
+    };
+    if start >= end {
+        return vec![];
+    }
+
+    let facts: Vec<String> = serde_json::from_str(&text[start..=end]).unwrap_or_default();
+    let mut unique: Vec<String> = vec![];
+    for fact in facts.into_iter().map(|f| f.trim().to_string()).filter(|f| !f.is_empty()) {
+        if !unique.contains(&fact) {
+            unique.push(fact);
+        }
+    }
+    unique.truncate(MAX_FACTS_PER_EXCHANGE);
+    unique
+}
+
+/// System message presenting recalled memories to the model
+pub fn memory_prompt(memories: &[Memory]) -> ChatMessage {
+    let facts: Vec<String> = memories.iter().map(|m| format!("- {}", m.content)).collect();
+    ChatMessage::system(format!(
+        "Facts remembered from previous conversations (use them only if relevant):\n{}",
+        facts.join("\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{ContentCipher, Database};
+
+    #[test]
+    fn test_parse_facts() {
+        let facts = parse_facts("Here you go:\n[\"Prefers Rust\", \" Prefers Rust \", \"Works on agents-rs\"]");
+        assert_eq!(facts, vec!["Prefers Rust", "Works on agents-rs"]);
+        assert!(parse_facts("[]").is_empty());
+        assert!(parse_facts("Nothing to remember").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_and_encryption() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = MemoryRepository::new(db.pool().clone());
+
+        repo.save(&Memory::new("Prefers Rust".to_string(), Some(vec![1.0, 0.0]), None)).await.unwrap();
+        repo.save(&Memory::new("Lives in Lyon".to_string(), Some(vec![0.0, 1.0]), None)).await.unwrap();
+        repo.save(&Memory::new("No embedding".to_string(), None, None)).await.unwrap();
+
+        let results = repo.search(&[0.9, 0.1], RECALL_LIMIT, MIN_RECALL_SCORE).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.content, "Prefers Rust");
+        assert_eq!(results[0].0.embedding.as_deref(), Some(&[1.0, 0.0][..]));
+
+        let (cipher, _) = ContentCipher::create("secret").unwrap();
+        let unlocked = CipherState::Unlocked(cipher);
+        let mut tx = db.pool().begin().await.unwrap();
+        assert_eq!(MemoryRepository::reencrypt(&mut tx, &CipherState::Disabled, &unlocked).await.unwrap(), 3);
+        tx.commit().await.unwrap();
+
+        let (raw,): (String,) = sqlx::query_as("SELECT content FROM memories LIMIT 1")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert!(ContentCipher::is_encrypted(&raw));
+
+        let cipher = SharedCipher::default();
+        *cipher.write().unwrap() = unlocked;
+        let repo = MemoryRepository::new(db.pool().clone()).with_cipher(cipher);
+        assert_eq!(repo.list().await.unwrap().len(), 3);
+    }
+}
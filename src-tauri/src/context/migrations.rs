@@ -0,0 +1,767 @@
+/// Versioned database migrations
+///
+/// Each migration is applied once, in its own transaction, and recorded in the
+/// `schema_version` table. New columns or tables are added by appending a
+/// migration to `MIGRATIONS`; existing migrations must never be modified.
+
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use tracing::info;
+
+/// A schema change identified by its version number
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub statements: &'static [&'static str],
+}
+
+/// All migrations, in increasing version order
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        // IF NOT EXISTS: databases created before versioning already have these tables
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS conversations (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                model_name TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                conversation_id TEXT NOT NULL,
+                role TEXT NOT NULL CHECK(role IN ('user', 'assistant', 'system')),
+                content TEXT NOT NULL,
+                tokens INTEGER,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_conversations_updated_at ON conversations(updated_at DESC)",
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "tool messages",
+        // SQLite cannot alter a CHECK constraint: the messages table is rebuilt
+        statements: &[
+            r#"
+            CREATE TABLE messages_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                conversation_id TEXT NOT NULL,
+                role TEXT NOT NULL CHECK(role IN ('user', 'assistant', 'system', 'tool')),
+                content TEXT NOT NULL,
+                tokens INTEGER,
+                created_at INTEGER NOT NULL,
+                tool_name TEXT,
+                tool_call_id TEXT,
+                FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+            )
+            "#,
+            r#"
+            INSERT INTO messages_new (id, conversation_id, role, content, tokens, created_at)
+            SELECT id, conversation_id, role, content, tokens, created_at FROM messages
+            "#,
+            "DROP TABLE messages",
+            "ALTER TABLE messages_new RENAME TO messages",
+            "CREATE INDEX idx_messages_conversation ON messages(conversation_id)",
+            "CREATE INDEX idx_messages_created_at ON messages(created_at)",
+        ],
+    },
+    Migration {
+        version: 3,
+        description: "agent profiles",
+        statements: &[
+            r#"
+            CREATE TABLE agents (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                system_prompt TEXT NOT NULL,
+                default_model TEXT,
+                allowed_tools TEXT NOT NULL DEFAULT '[]',
+                sampling TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+            "ALTER TABLE conversations ADD COLUMN agent_id TEXT REFERENCES agents(id) ON DELETE SET NULL",
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "task runs",
+        statements: &[
+            r#"
+            CREATE TABLE task_runs (
+                id TEXT PRIMARY KEY,
+                goal TEXT NOT NULL,
+                agent_id TEXT REFERENCES agents(id) ON DELETE SET NULL,
+                status TEXT NOT NULL,
+                result TEXT,
+                error TEXT,
+                trace TEXT NOT NULL DEFAULT '[]',
+                iterations INTEGER NOT NULL DEFAULT 0,
+                tokens_used INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+            "CREATE INDEX idx_task_runs_created_at ON task_runs(created_at DESC)",
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "scheduled tasks",
+        statements: &[
+            r#"
+            CREATE TABLE schedules (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                cron TEXT NOT NULL,
+                job TEXT NOT NULL,
+                agent_id TEXT REFERENCES agents(id) ON DELETE SET NULL,
+                conversation_id TEXT REFERENCES conversations(id) ON DELETE SET NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                last_run_at INTEGER,
+                last_error TEXT,
+                next_run_at INTEGER,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+            "CREATE INDEX idx_schedules_next_run_at ON schedules(next_run_at)",
+        ],
+    },
+    Migration {
+        version: 6,
+        description: "long-term memories",
+        statements: &[
+            r#"
+            CREATE TABLE memories (
+                id TEXT PRIMARY KEY,
+                content TEXT NOT NULL,
+                embedding BLOB,
+                source_conversation_id TEXT REFERENCES conversations(id) ON DELETE SET NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 7,
+        description: "message attachments",
+        statements: &[
+            r#"
+            CREATE TABLE attachments (
+                id TEXT PRIMARY KEY,
+                message_id INTEGER NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+                kind TEXT NOT NULL,
+                file_name TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                data BLOB NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+            "CREATE INDEX idx_attachments_message_id ON attachments(message_id)",
+        ],
+    },
+    Migration {
+        version: 8,
+        description: "document attachments",
+        statements: &[
+            "ALTER TABLE attachments ADD COLUMN text TEXT",
+            r#"
+            CREATE TABLE attachment_chunks (
+                attachment_id TEXT NOT NULL REFERENCES attachments(id) ON DELETE CASCADE,
+                chunk_index INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                embedding BLOB,
+                PRIMARY KEY (attachment_id, chunk_index)
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 9,
+        description: "workspaces",
+        statements: &[
+            r#"
+            CREATE TABLE workspaces (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                root_path TEXT NOT NULL,
+                default_agent_id TEXT REFERENCES agents(id) ON DELETE SET NULL,
+                index_id TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+            "ALTER TABLE conversations ADD COLUMN workspace_id TEXT REFERENCES workspaces(id) ON DELETE SET NULL",
+        ],
+    },
+    Migration {
+        version: 10,
+        description: "inference stats",
+        statements: &[
+            r#"
+            CREATE TABLE inference_stats (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+                model_name TEXT NOT NULL,
+                local INTEGER NOT NULL,
+                tokens_generated INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                n_threads INTEGER NOT NULL,
+                gpu INTEGER NOT NULL,
+                energy_wh REAL NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+            "CREATE INDEX idx_inference_stats_conversation_id ON inference_stats(conversation_id)",
+        ],
+    },
+    Migration {
+        version: 11,
+        description: "downloaded models",
+        statements: &[
+            r#"
+            CREATE TABLE downloaded_models (
+                filename TEXT PRIMARY KEY,
+                repo_id TEXT NOT NULL,
+                revision TEXT NOT NULL,
+                sha256 TEXT,
+                downloaded_at INTEGER NOT NULL
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 12,
+        description: "model usage and favorites",
+        statements: &[
+            r#"
+            CREATE TABLE model_usage (
+                model_name TEXT PRIMARY KEY,
+                load_count INTEGER NOT NULL DEFAULT 0,
+                last_used_at INTEGER,
+                favorite INTEGER NOT NULL DEFAULT 0
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 13,
+        description: "imported models",
+        statements: &[
+            r#"
+            CREATE TABLE imported_models (
+                filename TEXT PRIMARY KEY,
+                source_path TEXT NOT NULL,
+                linked INTEGER NOT NULL,
+                size_bytes INTEGER NOT NULL,
+                gguf_version INTEGER NOT NULL,
+                tensor_count INTEGER NOT NULL,
+                imported_at INTEGER NOT NULL
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 14,
+        description: "message embeddings",
+        statements: &[
+            r#"
+            CREATE TABLE message_embeddings (
+                message_id INTEGER NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+                model_name TEXT NOT NULL,
+                embedding BLOB NOT NULL,
+                PRIMARY KEY (message_id, model_name)
+            )
+            "#,
+            "CREATE INDEX idx_message_embeddings_model_name ON message_embeddings(model_name)",
+        ],
+    },
+    Migration {
+        version: 15,
+        description: "conversation language",
+        statements: &["ALTER TABLE conversations ADD COLUMN language TEXT"],
+    },
+    Migration {
+        version: 16,
+        description: "message metadata",
+        statements: &["ALTER TABLE messages ADD COLUMN metadata TEXT"],
+    },
+    Migration {
+        version: 17,
+        description: "agent guardrails",
+        statements: &["ALTER TABLE agents ADD COLUMN guardrails TEXT"],
+    },
+    Migration {
+        version: 18,
+        description: "conversation drafts",
+        statements: &[r#"
+            CREATE TABLE drafts (
+                conversation_id TEXT PRIMARY KEY REFERENCES conversations(id) ON DELETE CASCADE,
+                content TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#],
+    },
+    Migration {
+        version: 19,
+        description: "session presets",
+        statements: &[
+            r#"
+            CREATE TABLE session_presets (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                title_pattern TEXT NOT NULL,
+                system_prompt TEXT NOT NULL,
+                model TEXT,
+                sampling TEXT NOT NULL DEFAULT '{}',
+                tools TEXT NOT NULL DEFAULT '[]',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+            r#"
+            INSERT INTO session_presets (id, name, title_pattern, system_prompt, sampling, created_at, updated_at) VALUES
+            ('code-review', 'Code review', '{name} - {date}',
+             'You are a senior software engineer reviewing code. Point out bugs, security issues and unclear code first, then suggest concrete improvements.',
+             '{"temperature":0.2}', strftime('%s', 'now'), strftime('%s', 'now')),
+            ('translation', 'Translation', '{name} - {date}',
+             'You are a professional translator. Translate the text you are given faithfully, keeping its tone and formatting, and reply with the translation only.',
+             '{"temperature":0.1}', strftime('%s', 'now'), strftime('%s', 'now')),
+            ('brainstorm', 'Brainstorm', '{name} - {date}',
+             'You are a creative partner. Offer many varied ideas, build on the user''s suggestions and keep each idea short.',
+             '{"temperature":1.0}', strftime('%s', 'now'), strftime('%s', 'now'))
+            "#,
+        ],
+    },
+    Migration {
+        version: 20,
+        description: "tool pipelines",
+        statements: &[
+            r#"
+            CREATE TABLE tool_pipelines (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                description TEXT NOT NULL,
+                parameters TEXT NOT NULL DEFAULT '[]',
+                steps TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 21,
+        description: "message feedback",
+        statements: &[r#"
+            CREATE TABLE message_feedback (
+                message_id INTEGER PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+                rating INTEGER NOT NULL CHECK(rating IN (-1, 1)),
+                comment TEXT,
+                created_at INTEGER NOT NULL
+            )
+            "#],
+    },
+    Migration {
+        version: 22,
+        description: "evaluation suites",
+        statements: &[
+            r#"
+            CREATE TABLE eval_suites (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL DEFAULT '',
+                cases TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE eval_runs (
+                id TEXT PRIMARY KEY,
+                suite_id TEXT NOT NULL REFERENCES eval_suites(id) ON DELETE CASCADE,
+                model TEXT NOT NULL,
+                agent_id TEXT REFERENCES agents(id) ON DELETE SET NULL,
+                passed INTEGER NOT NULL,
+                total INTEGER NOT NULL,
+                avg_latency_ms INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+            "CREATE INDEX idx_eval_runs_suite_id ON eval_runs(suite_id, created_at DESC)",
+            r#"
+            CREATE TABLE eval_results (
+                run_id TEXT NOT NULL REFERENCES eval_runs(id) ON DELETE CASCADE,
+                position INTEGER NOT NULL,
+                case_name TEXT NOT NULL,
+                passed INTEGER NOT NULL,
+                latency_ms INTEGER NOT NULL,
+                tokens_generated INTEGER NOT NULL,
+                output TEXT NOT NULL,
+                failures TEXT NOT NULL DEFAULT '[]',
+                PRIMARY KEY (run_id, position)
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 23,
+        description: "scoped API tokens of the local server",
+        statements: &[r#"
+            CREATE TABLE api_tokens (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                scope TEXT NOT NULL,
+                token_hash TEXT NOT NULL UNIQUE,
+                created_at INTEGER NOT NULL,
+                last_used_at INTEGER
+            )
+            "#],
+    },
+    Migration {
+        version: 24,
+        description: "session scratchpad",
+        statements: &[r#"
+            CREATE TABLE session_state (
+                conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (conversation_id, key)
+            )
+            "#],
+    },
+    Migration {
+        version: 25,
+        description: "document index status",
+        statements: &[r#"
+            CREATE TABLE document_index (
+                attachment_id TEXT PRIMARY KEY REFERENCES attachments(id) ON DELETE CASCADE,
+                tokens INTEGER,
+                embedding_model TEXT,
+                indexed_at INTEGER NOT NULL
+            )
+            "#],
+    },
+    Migration {
+        version: 26,
+        description: "knowledge base folders",
+        statements: &[
+            r#"
+            CREATE TABLE kb_folders (
+                id TEXT PRIMARY KEY,
+                path TEXT NOT NULL UNIQUE,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE kb_files (
+                id TEXT PRIMARY KEY,
+                folder_id TEXT NOT NULL REFERENCES kb_folders(id) ON DELETE CASCADE,
+                path TEXT NOT NULL,
+                modified_at INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                embedding_model TEXT,
+                indexed_at INTEGER NOT NULL,
+                UNIQUE (folder_id, path)
+            )
+            "#,
+            r#"
+            CREATE TABLE kb_chunks (
+                file_id TEXT NOT NULL REFERENCES kb_files(id) ON DELETE CASCADE,
+                chunk_index INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                embedding BLOB,
+                start_char INTEGER NOT NULL,
+                end_char INTEGER NOT NULL,
+                PRIMARY KEY (file_id, chunk_index)
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 27,
+        description: "page of document chunks",
+        statements: &[
+            "ALTER TABLE attachment_chunks ADD COLUMN page INTEGER",
+            "ALTER TABLE kb_chunks ADD COLUMN page INTEGER",
+        ],
+    },
+    Migration {
+        version: 28,
+        description: "full-text index of knowledge base chunks",
+        statements: &[
+            "CREATE VIRTUAL TABLE kb_chunks_fts USING fts5(content, file_id UNINDEXED, chunk_index UNINDEXED)",
+            r#"
+            CREATE TRIGGER kb_chunks_fts_insert AFTER INSERT ON kb_chunks BEGIN
+                INSERT INTO kb_chunks_fts (content, file_id, chunk_index) VALUES (new.content, new.file_id, new.chunk_index);
+            END
+            "#,
+            r#"
+            CREATE TRIGGER kb_chunks_fts_delete AFTER DELETE ON kb_chunks BEGIN
+                DELETE FROM kb_chunks_fts WHERE file_id = old.file_id AND chunk_index = old.chunk_index;
+            END
+            "#,
+            "INSERT INTO kb_chunks_fts (content, file_id, chunk_index) SELECT content, file_id, chunk_index FROM kb_chunks",
+        ],
+    },
+    Migration {
+        version: 29,
+        description: "knowledge base reranker",
+        statements: &["ALTER TABLE kb_folders ADD COLUMN reranker_model TEXT"],
+    },
+    Migration {
+        version: 30,
+        description: "tool audit trail",
+        statements: &[
+            r#"
+            CREATE TABLE tool_audit (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                scope TEXT NOT NULL,
+                source TEXT NOT NULL,
+                event TEXT NOT NULL,
+                decision TEXT NOT NULL,
+                details TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 31,
+        description: "scratchpad keyed by tool scope",
+        statements: &[
+            r#"
+            CREATE TABLE session_state_new (
+                scope TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (scope, key)
+            )
+            "#,
+            "INSERT INTO session_state_new SELECT conversation_id, key, value, updated_at FROM session_state",
+            "DROP TABLE session_state",
+            "ALTER TABLE session_state_new RENAME TO session_state",
+            r#"
+            CREATE TRIGGER session_state_conversation_delete AFTER DELETE ON conversations BEGIN
+                DELETE FROM session_state WHERE scope = old.id;
+            END
+            "#,
+        ],
+    },
+    Migration {
+        version: 32,
+        description: "messages present at the last sync",
+        statements: &[r#"
+            CREATE TABLE sync_messages (
+                conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+                sync_id TEXT NOT NULL,
+                PRIMARY KEY (conversation_id, sync_id)
+            )
+            "#],
+    },
+    Migration {
+        version: 33,
+        description: "external-content full-text index of knowledge base chunks",
+        statements: &[
+            "DROP TRIGGER kb_chunks_fts_insert",
+            "DROP TRIGGER kb_chunks_fts_delete",
+            "DROP TABLE kb_chunks_fts",
+            // Explicit rowid: VACUUM may renumber implicit ones, which the index refers to
+            r#"
+            CREATE TABLE kb_chunks_new (
+                id INTEGER PRIMARY KEY,
+                file_id TEXT NOT NULL REFERENCES kb_files(id) ON DELETE CASCADE,
+                chunk_index INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                embedding BLOB,
+                start_char INTEGER NOT NULL,
+                end_char INTEGER NOT NULL,
+                page INTEGER,
+                UNIQUE (file_id, chunk_index)
+            )
+            "#,
+            r#"
+            INSERT INTO kb_chunks_new (file_id, chunk_index, content, embedding, start_char, end_char, page)
+            SELECT file_id, chunk_index, content, embedding, start_char, end_char, page FROM kb_chunks
+            "#,
+            "DROP TABLE kb_chunks",
+            "ALTER TABLE kb_chunks_new RENAME TO kb_chunks",
+            // The index stores no copy of the text, it reads it from kb_chunks
+            "CREATE VIRTUAL TABLE kb_chunks_fts USING fts5(content, content='kb_chunks', content_rowid='id')",
+            r#"
+            CREATE TRIGGER kb_chunks_fts_insert AFTER INSERT ON kb_chunks BEGIN
+                INSERT INTO kb_chunks_fts (rowid, content) VALUES (new.id, new.content);
+            END
+            "#,
+            r#"
+            CREATE TRIGGER kb_chunks_fts_delete AFTER DELETE ON kb_chunks BEGIN
+                INSERT INTO kb_chunks_fts (kb_chunks_fts, rowid, content) VALUES ('delete', old.id, old.content);
+            END
+            "#,
+            r#"
+            CREATE TRIGGER kb_chunks_fts_update AFTER UPDATE OF content ON kb_chunks BEGIN
+                INSERT INTO kb_chunks_fts (kb_chunks_fts, rowid, content) VALUES ('delete', old.id, old.content);
+                INSERT INTO kb_chunks_fts (rowid, content) VALUES (new.id, new.content);
+            END
+            "#,
+            "INSERT INTO kb_chunks_fts (kb_chunks_fts) VALUES ('rebuild')",
+        ],
+    },
+    Migration {
+        version: 34,
+        description: "session settings deleted with their conversation",
+        statements: &[
+            r#"
+            DELETE FROM settings
+            WHERE (key LIKE 'session_backend.%' OR key LIKE 'session_context_size.%'
+                   OR key LIKE 'session_sampling.%' OR key LIKE 'session_tools.%')
+              AND substr(key, instr(key, '.') + 1) NOT IN (SELECT id FROM conversations)
+            "#,
+            r#"
+            CREATE TRIGGER session_settings_conversation_delete AFTER DELETE ON conversations BEGIN
+                DELETE FROM settings WHERE key IN (
+                    'session_backend.' || old.id,
+                    'session_context_size.' || old.id,
+                    'session_sampling.' || old.id,
+                    'session_tools.' || old.id
+                );
+            END
+            "#,
+        ],
+    },
+];
+
+/// Latest schema version known by this build
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Current schema version of the database (0 if never migrated)
+pub async fn current_version(pool: &SqlitePool) -> Result<i64> {
+    ensure_version_table(pool).await?;
+
+    let (version,): (Option<i64>,) = sqlx::query_as("SELECT MAX(version) FROM schema_version")
+        .fetch_one(pool)
+        .await
+        .context("Failed to read schema version")?;
+
+    Ok(version.unwrap_or(0))
+}
+
+/// Apply all pending migrations, returns the resulting schema version
+pub async fn run(pool: &SqlitePool) -> Result<i64> {
+    let current = current_version(pool).await?;
+
+    if current > latest_version() {
+        anyhow::bail!(
+            "Database schema version {} is newer than supported version {}",
+            current,
+            latest_version()
+        );
+    }
+
+    let mut version = current;
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        info!("Applying migration {}: {}", migration.version, migration.description);
+
+        let mut tx = pool.begin().await?;
+        for statement in migration.statements {
+            sqlx::query(statement)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Migration {} failed", migration.version))?;
+        }
+        sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .bind(chrono::Utc::now().timestamp())
+            .execute(&mut *tx)
+            .await
+            .context("Failed to record schema version")?;
+        tx.commit().await?;
+
+        version = migration.version;
+    }
+
+    Ok(version)
+}
+
+async fn ensure_version_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at INTEGER NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to create schema_version table")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+
+    #[test]
+    fn test_versions_are_increasing() {
+        for pair in MIGRATIONS.windows(2) {
+            assert!(pair[0].version < pair[1].version);
+        }
+    }
+
+    #[test]
+    fn test_versions_are_contiguous() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as i64 + 1, "{}", migration.description);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_is_idempotent() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+
+        assert_eq!(run(db.pool()).await.unwrap(), latest_version());
+        assert_eq!(run(db.pool()).await.unwrap(), latest_version());
+        assert_eq!(current_version(db.pool()).await.unwrap(), latest_version());
+    }
+
+    #[tokio::test]
+    async fn test_newer_schema_is_rejected() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        run(db.pool()).await.unwrap();
+
+        sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, 'future', 0)")
+            .bind(latest_version() + 1)
+            .execute(db.pool())
+            .await
+            .unwrap();
+
+        assert!(run(db.pool()).await.is_err());
+    }
+}
@@ -1,15 +1,62 @@
 /// Module Context - Gestion des sessions et de l'historique conversationnel
 
+pub mod agents;
+pub mod api_tokens;
 pub mod manager;
+pub mod memories;
 pub mod session;
 pub mod database;
+pub mod documents;
+pub mod downloads;
+pub mod encryption;
+pub mod feedback;
+pub mod imports;
+pub mod knowledge;
+pub mod language;
+pub mod migrations;
+pub mod model_usage;
 pub mod models;
+pub mod pipelines;
+pub mod presets;
+pub mod profile;
 pub mod repository;
+pub mod retention;
+pub mod retrieval;
 pub mod settings;
+pub mod sharing;
+pub mod sync;
+pub mod stats;
+pub mod tool_audit;
+pub mod training;
+pub mod user_profiles;
+pub mod workspaces;
 
+pub use agents::{AgentProfile, AgentRepository};
+pub use api_tokens::{ApiAccess, ApiScope, ApiToken, ApiTokenRepository};
 pub use manager::ContextManager;
+pub use memories::{Memory, MemoryRepository};
 pub use session::{ConversationSession, SessionSummary, Message, MessageRole};
 pub use database::{Database, get_default_database_path};
-pub use models::{Conversation, StoredMessage};
+pub use documents::{Citation, DocumentInfo, DocumentStatus};
+pub use downloads::{DownloadedModel, DownloadedModelRepository};
+pub use encryption::{CipherState, ContentCipher, EncryptionParams, SharedCipher};
+pub use feedback::{FeedbackRating, MessageFeedback, RatedExchange};
+pub use imports::{ImportedModel, ImportedModelRepository};
+pub use knowledge::{KbIndexProgress, KnowledgeFolder, KnowledgeRepository};
+pub use language::Language;
+pub use model_usage::{ModelUsage, ModelUsageRepository};
+pub use models::{Attachment, AttachmentKind, Conversation, StoredMessage};
+pub use pipelines::PipelineRepository;
+pub use presets::{PresetRepository, SessionPreset};
+pub use profile::{AppProfile, ProfileImport};
 pub use repository::ConversationRepository;
+pub use retention::{PruneReport, RetentionPolicy};
+pub use retrieval::RetrievalSettings;
 pub use settings::SettingsRepository;
+pub use sharing::{ShareFormat, SharedConversation};
+pub use sync::{SyncReport, SyncSettings};
+pub use stats::{InferenceStat, SessionStats, StatsRepository};
+pub use tool_audit::{ToolAuditEntry, ToolAuditRepository};
+pub use training::{TrainingExport, TrainingFilter, TrainingFormat, TrainingOptions};
+pub use user_profiles::{ProfileStore, UserProfile, DEFAULT_PROFILE};
+pub use workspaces::{Workspace, WorkspaceRepository};
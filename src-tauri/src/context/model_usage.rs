@@ -0,0 +1,113 @@
+/// Model usage - load counts, last use and favorites of the local models
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+
+/// Usage of a model file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelUsage {
+    pub model_name: String,
+    pub load_count: u32,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub favorite: bool,
+}
+
+/// Persistence of the model usage
+pub struct ModelUsageRepository {
+    pool: SqlitePool,
+}
+
+impl ModelUsageRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Count a load of the model and mark it as used now
+    pub async fn record_load(&self, model_name: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO model_usage (model_name, load_count, last_used_at) VALUES (?, 1, ?)
+            ON CONFLICT(model_name) DO UPDATE SET
+                load_count = load_count + 1,
+                last_used_at = excluded.last_used_at
+            "#,
+        )
+        .bind(model_name)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to record model load")?;
+
+        Ok(())
+    }
+
+    pub async fn set_favorite(&self, model_name: &str, favorite: bool) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO model_usage (model_name, favorite) VALUES (?, ?)
+            ON CONFLICT(model_name) DO UPDATE SET favorite = excluded.favorite
+            "#,
+        )
+        .bind(model_name)
+        .bind(favorite)
+        .execute(&self.pool)
+        .await
+        .context("Failed to update favorite model")?;
+
+        Ok(())
+    }
+
+    /// Usage of every model seen so far, by model name
+    pub async fn all(&self) -> Result<HashMap<String, ModelUsage>> {
+        let rows = sqlx::query("SELECT * FROM model_usage")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list model usage")?;
+
+        Ok(rows
+            .iter()
+            .map(Self::from_row)
+            .map(|usage| (usage.model_name.clone(), usage))
+            .collect())
+    }
+
+    fn from_row(row: &SqliteRow) -> ModelUsage {
+        ModelUsage {
+            model_name: row.get("model_name"),
+            load_count: row.get::<i64, _>("load_count") as u32,
+            last_used_at: row
+                .get::<Option<i64>, _>("last_used_at")
+                .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0)),
+            favorite: row.get("favorite"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+
+    #[tokio::test]
+    async fn test_model_usage() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = ModelUsageRepository::new(db.pool().clone());
+
+        repo.set_favorite("a.gguf", true).await.unwrap();
+        repo.record_load("a.gguf").await.unwrap();
+        repo.record_load("a.gguf").await.unwrap();
+        repo.record_load("b.gguf").await.unwrap();
+
+        let usage = repo.all().await.unwrap();
+        assert_eq!(usage["a.gguf"].load_count, 2);
+        assert!(usage["a.gguf"].favorite);
+        assert!(usage["a.gguf"].last_used_at.is_some());
+        assert_eq!(usage["b.gguf"].load_count, 1);
+        assert!(!usage["b.gguf"].favorite);
+    }
+}
@@ -1,7 +1,10 @@
 /// Data models for conversation persistence
 
+use super::language::Language;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use std::collections::HashMap;
 
 /// A conversation represents a single chat session
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +14,15 @@ pub struct Conversation {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub model_name: String,
+    /// Agent profile the conversation was started from
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    /// Workspace the conversation belongs to
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// Language of the conversation (None until detected or set)
+    #[serde(default)]
+    pub language: Option<Language>,
 }
 
 /// A message within a conversation
@@ -18,11 +30,18 @@ pub struct Conversation {
 pub struct StoredMessage {
     pub id: Option<i64>,
     pub conversation_id: String,
-    pub role: String,  // "user", "assistant", "system"
+    pub role: String,  // "user", "assistant", "system", "tool"
     pub content: String,
     pub tokens: Option<i32>,
     #[serde(with = "chrono::serde::ts_seconds")]
     pub created_at: DateTime<Utc>,
+    /// Tool that produced this message (tool role) or was called (assistant role)
+    pub tool_name: Option<String>,
+    /// Identifier linking a tool result to the call that requested it
+    pub tool_call_id: Option<String>,
+    /// Extra data kept with the message (captured reasoning...)
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
 }
 
 impl Conversation {
@@ -34,6 +53,9 @@ impl Conversation {
             created_at: now,
             updated_at: now,
             model_name,
+            agent_id: None,
+            workspace_id: None,
+            language: None,
         }
     }
 }
@@ -47,6 +69,9 @@ impl StoredMessage {
             content,
             tokens: None,
             created_at: Utc::now(),
+            tool_name: None,
+            tool_call_id: None,
+            metadata: HashMap::new(),
         }
     }
     
@@ -54,4 +79,90 @@ impl StoredMessage {
         self.tokens = Some(tokens);
         self
     }
+    
+    pub fn with_tool_call(mut self, tool_name: Option<String>, tool_call_id: Option<String>) -> Self {
+        self.tool_name = tool_name;
+        self.tool_call_id = tool_call_id;
+        self
+    }
+    
+    pub fn with_metadata(mut self, metadata: HashMap<String, serde_json::Value>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+/// Kind of file attached to a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentKind {
+    /// Image shown to vision models
+    Image,
+    /// Document whose text is included in the prompt
+    Document,
+}
+
+impl AttachmentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttachmentKind::Image => "image",
+            AttachmentKind::Document => "document",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "image" => Some(AttachmentKind::Image),
+            "document" => Some(AttachmentKind::Document),
+            _ => None,
+        }
+    }
+}
+
+/// A file attached to a message, stored as a blob in the `attachments` table
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+pub struct Attachment {
+    pub id: String,
+    pub kind: AttachmentKind,
+    pub file_name: String,
+    pub mime_type: String,
+    /// Size of the content in bytes
+    #[ts(type = "number")]
+    pub size: usize,
+    /// Raw content, fetched separately by the UI
+    #[serde(skip)]
+    pub data: Vec<u8>,
+    /// Text extracted from a document
+    #[serde(skip)]
+    pub text: Option<String>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[ts(type = "number")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Attachment {
+    pub fn image(file_name: String, mime_type: String, data: Vec<u8>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: AttachmentKind::Image,
+            file_name,
+            mime_type,
+            size: data.len(),
+            data,
+            text: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn document(file_name: String, mime_type: String, data: Vec<u8>, text: String) -> Self {
+        Self {
+            kind: AttachmentKind::Document,
+            text: Some(text),
+            ..Self::image(file_name, mime_type, data)
+        }
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.kind == AttachmentKind::Image
+    }
 }
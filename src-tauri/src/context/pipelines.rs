@@ -0,0 +1,123 @@
+/// Persistence of tool pipelines (named chains of tool calls)
+
+use crate::mcp::ToolPipeline;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use tracing::info;
+
+pub struct PipelineRepository {
+    pool: SqlitePool,
+}
+
+impl PipelineRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert or update a pipeline
+    pub async fn save(&self, pipeline: &ToolPipeline) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO tool_pipelines (id, name, description, parameters, steps, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                description = excluded.description,
+                parameters = excluded.parameters,
+                steps = excluded.steps,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&pipeline.id)
+        .bind(&pipeline.name)
+        .bind(&pipeline.description)
+        .bind(serde_json::to_string(&pipeline.parameters)?)
+        .bind(serde_json::to_string(&pipeline.steps)?)
+        .bind(pipeline.created_at.timestamp())
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save tool pipeline")?;
+
+        info!("Saved tool pipeline: {} ({})", pipeline.name, pipeline.id);
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<ToolPipeline>> {
+        let row = sqlx::query("SELECT * FROM tool_pipelines WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch tool pipeline")?;
+
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    pub async fn list(&self) -> Result<Vec<ToolPipeline>> {
+        let rows = sqlx::query("SELECT * FROM tool_pipelines ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list tool pipelines")?;
+
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM tool_pipelines WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete tool pipeline")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<ToolPipeline> {
+        let parameters: String = row.get("parameters");
+        let steps: String = row.get("steps");
+        let created_at: i64 = row.get("created_at");
+        let updated_at: i64 = row.get("updated_at");
+
+        Ok(ToolPipeline {
+            id: row.get("id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            parameters: serde_json::from_str(&parameters).context("Invalid pipeline parameters")?,
+            steps: serde_json::from_str(&steps).context("Invalid pipeline steps")?,
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_else(Utc::now),
+            updated_at: DateTime::from_timestamp(updated_at, 0).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+    use crate::mcp::PipelineStep;
+
+    #[tokio::test]
+    async fn test_pipeline_crud() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = PipelineRepository::new(db.pool().clone());
+
+        let mut pipeline = ToolPipeline::new(
+            "shout".to_string(),
+            "Echo twice".to_string(),
+            vec![PipelineStep { id: Some("first".to_string()), tool: "echo".to_string(), arguments: serde_json::json!({"text": "hi"}) }],
+        );
+        repo.save(&pipeline).await.unwrap();
+
+        pipeline.description = "Echo once".to_string();
+        repo.save(&pipeline).await.unwrap();
+        let loaded = repo.get(&pipeline.id).await.unwrap().unwrap();
+        assert_eq!(loaded.description, "Echo once");
+        assert_eq!(loaded.steps, pipeline.steps);
+
+        assert!(repo.delete(&pipeline.id).await.unwrap());
+        assert!(repo.list().await.unwrap().is_empty());
+    }
+}
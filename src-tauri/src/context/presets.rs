@@ -0,0 +1,194 @@
+/// Session presets - one-click templates for new conversations (code review, translation...)
+
+use crate::llm::SamplingParams;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use tracing::info;
+use ts_rs::TS;
+
+/// Template a conversation can be created from
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SessionPreset {
+    pub id: String,
+    pub name: String,
+    /// Title of the created sessions; `{name}`, `{date}` and `{time}` are replaced
+    pub title_pattern: String,
+    pub system_prompt: String,
+    /// Model loaded when a session is created (None = keep the current one)
+    pub model: Option<String>,
+    /// Sampling overrides of the created sessions
+    pub sampling: SamplingParams,
+    /// Names of the tools attached to the created sessions
+    pub tools: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SessionPreset {
+    pub fn new(name: String, system_prompt: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            title_pattern: "{name}".to_string(),
+            system_prompt,
+            model: None,
+            sampling: SamplingParams::default(),
+            tools: vec![],
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Title of a session created now from this preset
+    pub fn session_title(&self, now: DateTime<Local>) -> String {
+        let title = self
+            .title_pattern
+            .replace("{name}", &self.name)
+            .replace("{date}", &now.format("%Y-%m-%d").to_string())
+            .replace("{time}", &now.format("%H:%M").to_string());
+        match title.trim() {
+            "" => self.name.clone(),
+            title => title.to_string(),
+        }
+    }
+}
+
+/// Persistence of session presets
+pub struct PresetRepository {
+    pool: SqlitePool,
+}
+
+impl PresetRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert or update a preset
+    pub async fn save(&self, preset: &SessionPreset) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO session_presets (id, name, title_pattern, system_prompt, model, sampling, tools, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                title_pattern = excluded.title_pattern,
+                system_prompt = excluded.system_prompt,
+                model = excluded.model,
+                sampling = excluded.sampling,
+                tools = excluded.tools,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&preset.id)
+        .bind(&preset.name)
+        .bind(&preset.title_pattern)
+        .bind(&preset.system_prompt)
+        .bind(&preset.model)
+        .bind(serde_json::to_string(&preset.sampling)?)
+        .bind(serde_json::to_string(&preset.tools)?)
+        .bind(preset.created_at.timestamp())
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save session preset")?;
+
+        info!("Saved session preset: {} ({})", preset.name, preset.id);
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<SessionPreset>> {
+        let row = sqlx::query("SELECT * FROM session_presets WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch session preset")?;
+
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    pub async fn list(&self) -> Result<Vec<SessionPreset>> {
+        let rows = sqlx::query("SELECT * FROM session_presets ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list session presets")?;
+
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM session_presets WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete session preset")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<SessionPreset> {
+        let sampling: String = row.get("sampling");
+        let tools: String = row.get("tools");
+        let created_at: i64 = row.get("created_at");
+        let updated_at: i64 = row.get("updated_at");
+
+        Ok(SessionPreset {
+            id: row.get("id"),
+            name: row.get("name"),
+            title_pattern: row.get("title_pattern"),
+            system_prompt: row.get("system_prompt"),
+            model: row.get("model"),
+            sampling: serde_json::from_str(&sampling).context("Invalid sampling parameters")?,
+            tools: serde_json::from_str(&tools).context("Invalid preset tools")?,
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_else(Utc::now),
+            updated_at: DateTime::from_timestamp(updated_at, 0).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_preset_crud() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = PresetRepository::new(db.pool().clone());
+
+        // Built-in presets are created by the migration
+        let builtin = repo.list().await.unwrap();
+        assert!(builtin.iter().any(|p| p.id == "code-review"));
+
+        let mut preset = SessionPreset::new("Summary".to_string(), "Summarize the text.".to_string());
+        preset.sampling.temperature = Some(0.3);
+        preset.tools = vec!["fetch".to_string()];
+        repo.save(&preset).await.unwrap();
+
+        let loaded = repo.get(&preset.id).await.unwrap().unwrap();
+        assert_eq!(loaded.sampling.temperature, Some(0.3));
+        assert_eq!(loaded.tools, vec!["fetch".to_string()]);
+
+        assert!(repo.delete(&preset.id).await.unwrap());
+        assert_eq!(repo.list().await.unwrap().len(), builtin.len());
+    }
+
+    #[test]
+    fn test_session_title() {
+        let mut preset = SessionPreset::new("Translation".to_string(), String::new());
+        let now = Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 0).unwrap();
+        assert_eq!(preset.session_title(now), "Translation");
+
+        preset.title_pattern = "{name} - {date} {time}".to_string();
+        assert_eq!(preset.session_title(now), "Translation - 2024-03-09 14:05");
+
+        preset.title_pattern = "  ".to_string();
+        assert_eq!(preset.session_title(now), "Translation");
+    }
+}
@@ -0,0 +1,173 @@
+/// App profile - settings, agents and session presets bundled in one JSON file
+///
+/// Used to move a configuration to another machine. Models, conversations and
+/// anything tied to this machine (credentials, encryption, threads, per-session
+/// and per-model settings) stay out of the file.
+
+use super::agents::{AgentProfile, AgentRepository};
+use super::presets::{PresetRepository, SessionPreset};
+use super::settings::SettingsRepository;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::info;
+use ts_rs::TS;
+
+/// Format version of the profile file
+pub const PROFILE_VERSION: u32 = 1;
+
+/// Settings never exported: secrets, machine-specific values and per-session state
+const LOCAL_SETTINGS: &[&str] = &[
+    "api_key.",
+    "encryption.",
+    "current_model",
+    "last_session_id",
+    "threads",
+    "session_",
+    "model_capabilities.",
+    // Proxy credentials and network-specific mirror
+    "hf.connection",
+    // Sync folder of this machine
+    "sync.",
+];
+
+/// Content of a profile file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppProfile {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// Raw setting values, by key
+    pub settings: BTreeMap<String, String>,
+    pub agents: Vec<AgentProfile>,
+    #[serde(default)]
+    pub presets: Vec<SessionPreset>,
+}
+
+/// What an import changed
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+pub struct ProfileImport {
+    #[ts(type = "number")]
+    pub settings: usize,
+    #[ts(type = "number")]
+    pub agents: usize,
+    #[ts(type = "number")]
+    pub presets: usize,
+}
+
+/// Whether a setting can be carried to another machine
+pub fn is_portable_setting(key: &str) -> bool {
+    !LOCAL_SETTINGS.iter().any(|prefix| key.starts_with(prefix))
+}
+
+/// Collect the profile of this installation
+pub async fn collect(
+    settings: &SettingsRepository,
+    agents: &AgentRepository,
+    presets: &PresetRepository,
+) -> Result<AppProfile> {
+    let settings = settings
+        .list()
+        .await?
+        .into_iter()
+        .filter(|(key, _)| is_portable_setting(key))
+        .collect();
+
+    Ok(AppProfile {
+        version: PROFILE_VERSION,
+        exported_at: Utc::now(),
+        settings,
+        agents: agents.list().await?,
+        presets: presets.list().await?,
+    })
+}
+
+/// Apply a profile: settings are overwritten, agents and presets are added or
+/// updated by id (existing ones missing from the profile are kept)
+pub async fn apply(
+    profile: &AppProfile,
+    settings: &SettingsRepository,
+    agents: &AgentRepository,
+    presets: &PresetRepository,
+) -> Result<ProfileImport> {
+    if profile.version > PROFILE_VERSION {
+        anyhow::bail!(
+            "Profile version {} is newer than the supported version {}",
+            profile.version,
+            PROFILE_VERSION
+        );
+    }
+
+    let mut report = ProfileImport::default();
+    for (key, value) in profile.settings.iter().filter(|(key, _)| is_portable_setting(key)) {
+        settings.set(key, value).await?;
+        report.settings += 1;
+    }
+    for agent in &profile.agents {
+        agents.save(agent).await?;
+        report.agents += 1;
+    }
+    for preset in &profile.presets {
+        presets.save(preset).await?;
+        report.presets += 1;
+    }
+
+    info!(
+        "Profile imported: {} settings, {} agents, {} presets",
+        report.settings, report.agents, report.presets
+    );
+    Ok(report)
+}
+
+pub fn write(profile: &AppProfile, path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(profile)?;
+    std::fs::write(path, json).with_context(|| format!("Failed to write profile to {}", path.display()))
+}
+
+pub fn read(path: &Path) -> Result<AppProfile> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read profile from {}", path.display()))?;
+    serde_json::from_str(&json).context("Invalid profile file")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+
+    #[tokio::test]
+    async fn test_profile_round_trip() {
+        let source = Database::new("sqlite::memory:").await.unwrap();
+        source.migrate().await.unwrap();
+        let settings = SettingsRepository::new(source.pool().clone());
+        let agents = AgentRepository::new(source.pool().clone());
+        let presets = PresetRepository::new(source.pool().clone());
+
+        settings.set("memory.enabled", "true").await.unwrap();
+        settings.set("api_key.openai", "sk-secret").await.unwrap();
+        settings.set("session_backend.s1", "\"local\"").await.unwrap();
+        agents.save(&AgentProfile::new("Coder".to_string(), "You write Rust.".to_string())).await.unwrap();
+
+        let profile = collect(&settings, &agents, &presets).await.unwrap();
+        assert_eq!(profile.settings.get("memory.enabled").map(String::as_str), Some("true"));
+        assert!(!profile.settings.contains_key("api_key.openai"));
+        assert!(!profile.settings.contains_key("session_backend.s1"));
+
+        let target = Database::new("sqlite::memory:").await.unwrap();
+        target.migrate().await.unwrap();
+        let target_settings = SettingsRepository::new(target.pool().clone());
+        let target_agents = AgentRepository::new(target.pool().clone());
+        let target_presets = PresetRepository::new(target.pool().clone());
+
+        let report = apply(&profile, &target_settings, &target_agents, &target_presets).await.unwrap();
+        assert_eq!(report.agents, 1);
+        assert_eq!(report.presets, profile.presets.len());
+        assert!(target_settings.get_memory_enabled().await.unwrap());
+        assert_eq!(target_agents.list().await.unwrap()[0].name, "Coder");
+
+        let future = AppProfile { version: PROFILE_VERSION + 1, ..profile };
+        assert!(apply(&future, &target_settings, &target_agents, &target_presets).await.is_err());
+    }
+}
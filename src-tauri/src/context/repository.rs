@@ -1,19 +1,38 @@
 /// Repository pattern for conversation and message persistence
 
-use super::models::{Conversation, StoredMessage};
+use super::documents::{DocumentChunk, DocumentInfo, DocumentStatus};
+use super::encryption::{CipherState, SharedCipher};
+use super::feedback::{FeedbackRating, MessageFeedback, RatedExchange};
+use super::language::Language;
+use super::memories::{cosine_similarity, decode_embedding, encode_embedding};
+use super::models::{Attachment, AttachmentKind, Conversation, StoredMessage};
 use anyhow::{Context, Result};
 use chrono::Utc;
-use sqlx::{Row, SqlitePool};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use tracing::{debug, info};
 
 pub struct ConversationRepository {
     pool: SqlitePool,
+    /// Encryption of message contents (disabled by default)
+    cipher: SharedCipher,
 }
 
 impl ConversationRepository {
     /// Create a new repository instance
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self { pool, cipher: SharedCipher::default() }
+    }
+    
+    /// Share the encryption state with the rest of the application
+    pub fn with_cipher(mut self, cipher: SharedCipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+    
+    fn cipher(&self) -> CipherState {
+        self.cipher.read().unwrap().clone()
     }
     
     // ==================== Conversation CRUD ====================
@@ -46,7 +65,7 @@ impl ConversationRepository {
     pub async fn get_conversation(&self, id: &str) -> Result<Option<Conversation>> {
         let row = sqlx::query(
             r#"
-            SELECT id, title, created_at, updated_at, model_name
+            SELECT id, title, created_at, updated_at, model_name, agent_id, workspace_id, language
             FROM conversations
             WHERE id = ?
             "#,
@@ -68,6 +87,9 @@ impl ConversationRepository {
                 updated_at: DateTime::from_timestamp(updated_timestamp, 0)
                     .unwrap_or_else(|| Utc::now()),
                 model_name: row.get("model_name"),
+                agent_id: row.get("agent_id"),
+                workspace_id: row.get("workspace_id"),
+                language: Self::language_from_row(&row),
             }))
         } else {
             Ok(None)
@@ -78,7 +100,7 @@ impl ConversationRepository {
     pub async fn list_conversations(&self, limit: i32, offset: i32) -> Result<Vec<Conversation>> {
         let rows = sqlx::query(
             r#"
-            SELECT id, title, created_at, updated_at, model_name
+            SELECT id, title, created_at, updated_at, model_name, agent_id, workspace_id, language
             FROM conversations
             ORDER BY updated_at DESC
             LIMIT ? OFFSET ?
@@ -103,6 +125,9 @@ impl ConversationRepository {
                     updated_at: DateTime::from_timestamp(updated_timestamp, 0)
                         .unwrap_or_else(|| Utc::now()),
                     model_name: row.get("model_name"),
+                    agent_id: row.get("agent_id"),
+                    workspace_id: row.get("workspace_id"),
+                    language: Self::language_from_row(&row),
                 }
             })
             .collect();
@@ -112,6 +137,46 @@ impl ConversationRepository {
         Ok(conversations)
     }
     
+    /// Attach an agent profile to a conversation
+    pub async fn set_conversation_agent(&self, id: &str, agent_id: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE conversations SET agent_id = ? WHERE id = ?")
+            .bind(agent_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to set conversation agent")?;
+        
+        Ok(())
+    }
+    
+    /// Attach a conversation to a workspace
+    pub async fn set_conversation_workspace(&self, id: &str, workspace_id: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE conversations SET workspace_id = ? WHERE id = ?")
+            .bind(workspace_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to set conversation workspace")?;
+        
+        Ok(())
+    }
+    
+    /// Set the language of a conversation (None to detect it again)
+    pub async fn set_conversation_language(&self, id: &str, language: Option<Language>) -> Result<()> {
+        sqlx::query("UPDATE conversations SET language = ? WHERE id = ?")
+            .bind(language.map(Language::code))
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to set conversation language")?;
+        
+        Ok(())
+    }
+    
+    fn language_from_row(row: &SqliteRow) -> Option<Language> {
+        row.get::<Option<String>, _>("language").as_deref().and_then(Language::from_code)
+    }
+    
     /// Update conversation's updated_at timestamp
     pub async fn touch_conversation(&self, id: &str) -> Result<()> {
         sqlx::query(
@@ -151,6 +216,64 @@ impl ConversationRepository {
         Ok(())
     }
     
+    /// Create or update a conversation copied from another device, keeping its
+    /// id and timestamps (the model and agent of an existing conversation stay)
+    pub async fn save_synced_conversation(&self, conversation: &Conversation) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO conversations (id, title, created_at, updated_at, model_name, language)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title,
+                updated_at = excluded.updated_at,
+                language = excluded.language
+            "#,
+        )
+        .bind(&conversation.id)
+        .bind(&conversation.title)
+        .bind(conversation.created_at.timestamp())
+        .bind(conversation.updated_at.timestamp())
+        .bind(&conversation.model_name)
+        .bind(conversation.language.map(Language::code))
+        .execute(&self.pool)
+        .await
+        .context("Failed to save synced conversation")?;
+        
+        Ok(())
+    }
+    
+    /// Sync ids of the messages of a conversation at its last sync (to detect
+    /// the messages deleted since, whatever deleted them)
+    pub async fn get_synced_messages(&self, conversation_id: &str) -> Result<HashSet<String>> {
+        let ids: Vec<String> = sqlx::query_scalar("SELECT sync_id FROM sync_messages WHERE conversation_id = ?")
+            .bind(conversation_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch synced messages")?;
+        
+        Ok(ids.into_iter().collect())
+    }
+    
+    pub async fn set_synced_messages(&self, conversation_id: &str, sync_ids: &HashSet<String>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM sync_messages WHERE conversation_id = ?")
+            .bind(conversation_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear synced messages")?;
+        for sync_id in sync_ids {
+            sqlx::query("INSERT INTO sync_messages (conversation_id, sync_id) VALUES (?, ?)")
+                .bind(conversation_id)
+                .bind(sync_id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to save synced message")?;
+        }
+        tx.commit().await?;
+        
+        Ok(())
+    }
+    
     /// Delete a conversation and all its messages
     pub async fn delete_conversation(&self, id: &str) -> Result<()> {
         sqlx::query("DELETE FROM conversations WHERE id = ?")
@@ -178,17 +301,30 @@ impl ConversationRepository {
     
     /// Add a message to a conversation
     pub async fn add_message(&self, message: &StoredMessage) -> Result<StoredMessage> {
+        let _writing = self.cipher.lock_writes().await;
+        let cipher = self.cipher();
+        let content = cipher.seal(&message.content)?;
+        // Metadata may hold generated text (reasoning): encrypted like the content
+        let metadata = if message.metadata.is_empty() {
+            None
+        } else {
+            Some(cipher.seal(&serde_json::to_string(&message.metadata)?)?)
+        };
+        
         let result = sqlx::query(
             r#"
-            INSERT INTO messages (conversation_id, role, content, tokens, created_at)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO messages (conversation_id, role, content, tokens, created_at, tool_name, tool_call_id, metadata)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&message.conversation_id)
         .bind(&message.role)
-        .bind(&message.content)
+        .bind(&content)
         .bind(message.tokens)
         .bind(message.created_at.timestamp())
+        .bind(&message.tool_name)
+        .bind(&message.tool_call_id)
+        .bind(metadata)
         .execute(&self.pool)
         .await
         .context("Failed to add message")?;
@@ -209,7 +345,7 @@ impl ConversationRepository {
     pub async fn get_messages(&self, conversation_id: &str) -> Result<Vec<StoredMessage>> {
         let rows = sqlx::query(
             r#"
-            SELECT id, conversation_id, role, content, tokens, created_at
+            SELECT id, conversation_id, role, content, tokens, created_at, tool_name, tool_call_id, metadata
             FROM messages
             WHERE conversation_id = ?
             ORDER BY created_at ASC
@@ -220,21 +356,11 @@ impl ConversationRepository {
         .await
         .context("Failed to fetch messages")?;
         
+        let cipher = self.cipher();
         let messages: Vec<StoredMessage> = rows
-            .into_iter()
-            .map(|row| {
-                let created_timestamp: i64 = row.get("created_at");
-                StoredMessage {
-                    id: Some(row.get("id")),
-                    conversation_id: row.get("conversation_id"),
-                    role: row.get("role"),
-                    content: row.get("content"),
-                    tokens: row.get("tokens"),
-                    created_at: DateTime::from_timestamp(created_timestamp, 0)
-                        .unwrap_or_else(|| Utc::now()),
-                }
-            })
-            .collect();
+            .iter()
+            .map(|row| Self::message_from_row(row, &cipher))
+            .collect::<Result<_>>()?;
         
         debug!("Retrieved {} messages for conversation {}", 
                messages.len(), conversation_id);
@@ -242,11 +368,62 @@ impl ConversationRepository {
         Ok(messages)
     }
     
+    /// Get a single message by id
+    pub async fn get_message(&self, message_id: i64) -> Result<Option<StoredMessage>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, conversation_id, role, content, tokens, created_at, tool_name, tool_call_id, metadata
+            FROM messages
+            WHERE id = ?
+            "#,
+        )
+        .bind(message_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch message")?;
+        
+        let cipher = self.cipher();
+        row.map(|row| Self::message_from_row(&row, &cipher)).transpose()
+    }
+    
+    /// Replace the content and metadata of a message (continued generation)
+    pub async fn update_message(&self, message_id: i64, content: &str, metadata: &HashMap<String, serde_json::Value>) -> Result<bool> {
+        let _writing = self.cipher.lock_writes().await;
+        let cipher = self.cipher();
+        let content = cipher.seal(content)?;
+        let metadata = if metadata.is_empty() {
+            None
+        } else {
+            Some(cipher.seal(&serde_json::to_string(metadata)?)?)
+        };
+        
+        let result = sqlx::query("UPDATE messages SET content = ?, metadata = ? WHERE id = ?")
+            .bind(&content)
+            .bind(metadata)
+            .bind(message_id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to update message")?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Delete a single message (removed on another device)
+    pub async fn delete_message(&self, message_id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM messages WHERE id = ?")
+            .bind(message_id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete message")?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
     /// Get the last N messages from a conversation
     pub async fn get_last_n_messages(&self, conversation_id: &str, n: i32) -> Result<Vec<StoredMessage>> {
         let rows = sqlx::query(
             r#"
-            SELECT id, conversation_id, role, content, tokens, created_at
+            SELECT id, conversation_id, role, content, tokens, created_at, tool_name, tool_call_id, metadata
             FROM messages
             WHERE conversation_id = ?
             ORDER BY created_at DESC
@@ -259,21 +436,11 @@ impl ConversationRepository {
         .await
         .context("Failed to fetch last messages")?;
         
+        let cipher = self.cipher();
         let mut messages: Vec<StoredMessage> = rows
-            .into_iter()
-            .map(|row| {
-                let created_timestamp: i64 = row.get("created_at");
-                StoredMessage {
-                    id: Some(row.get("id")),
-                    conversation_id: row.get("conversation_id"),
-                    role: row.get("role"),
-                    content: row.get("content"),
-                    tokens: row.get("tokens"),
-                    created_at: DateTime::from_timestamp(created_timestamp, 0)
-                        .unwrap_or_else(|| Utc::now()),
-                }
-            })
-            .collect();
+            .iter()
+            .map(|row| Self::message_from_row(row, &cipher))
+            .collect::<Result<_>>()?;
         
         // Reverse to get chronological order
         messages.reverse();
@@ -281,6 +448,106 @@ impl ConversationRepository {
         Ok(messages)
     }
     
+    /// Rewrite the encrypted content of conversations from one encryption state to
+    /// another (used to encrypt or decrypt an existing database): message contents
+    /// and metadata, attachments and their text, attachment chunks, feedback
+    /// comments, drafts and session state (scratchpad). Runs inside the caller's
+    /// transaction so that reads and writes see the same rows.
+    pub async fn reencrypt_content(conn: &mut SqliteConnection, from: &CipherState, to: &CipherState) -> Result<()> {
+        let rows: Vec<(i64, String, Option<String>)> = sqlx::query_as("SELECT id, content, metadata FROM messages")
+            .fetch_all(&mut *conn)
+            .await
+            .context("Failed to fetch messages")?;
+        
+        for (id, content, metadata) in &rows {
+            let content = to.seal(&from.open(content)?)?;
+            let metadata = metadata.as_deref().map(|m| to.seal(&from.open(m)?)).transpose()?;
+            sqlx::query("UPDATE messages SET content = ?, metadata = ? WHERE id = ?")
+                .bind(content)
+                .bind(metadata)
+                .bind(id)
+                .execute(&mut *conn)
+                .await
+                .context("Failed to update message")?;
+        }
+        
+        let attachments: Vec<(String, Vec<u8>, Option<String>)> = sqlx::query_as("SELECT id, data, text FROM attachments")
+            .fetch_all(&mut *conn)
+            .await
+            .context("Failed to fetch attachments")?;
+        for (id, data, text) in &attachments {
+            let data = to.seal_bytes(&from.open_bytes(data)?)?;
+            let text = text.as_deref().map(|t| to.seal(&from.open(t)?)).transpose()?;
+            sqlx::query("UPDATE attachments SET data = ?, text = ? WHERE id = ?")
+                .bind(data)
+                .bind(text)
+                .bind(id)
+                .execute(&mut *conn)
+                .await
+                .context("Failed to update attachment")?;
+        }
+        
+        let chunks: Vec<(String, i64, String)> = sqlx::query_as("SELECT attachment_id, chunk_index, content FROM attachment_chunks")
+            .fetch_all(&mut *conn)
+            .await
+            .context("Failed to fetch attachment chunks")?;
+        for (attachment_id, index, content) in &chunks {
+            sqlx::query("UPDATE attachment_chunks SET content = ? WHERE attachment_id = ? AND chunk_index = ?")
+                .bind(to.seal(&from.open(content)?)?)
+                .bind(attachment_id)
+                .bind(index)
+                .execute(&mut *conn)
+                .await
+                .context("Failed to update attachment chunk")?;
+        }
+        
+        let comments: Vec<(i64, String)> = sqlx::query_as("SELECT message_id, comment FROM message_feedback WHERE comment IS NOT NULL")
+            .fetch_all(&mut *conn)
+            .await
+            .context("Failed to fetch feedback comments")?;
+        for (message_id, comment) in &comments {
+            sqlx::query("UPDATE message_feedback SET comment = ? WHERE message_id = ?")
+                .bind(to.seal(&from.open(comment)?)?)
+                .bind(message_id)
+                .execute(&mut *conn)
+                .await
+                .context("Failed to update feedback comment")?;
+        }
+        
+        let drafts: Vec<(String, String)> = sqlx::query_as("SELECT conversation_id, content FROM drafts")
+            .fetch_all(&mut *conn)
+            .await
+            .context("Failed to fetch drafts")?;
+        for (conversation_id, content) in &drafts {
+            sqlx::query("UPDATE drafts SET content = ? WHERE conversation_id = ?")
+                .bind(to.seal(&from.open(content)?)?)
+                .bind(conversation_id)
+                .execute(&mut *conn)
+                .await
+                .context("Failed to update draft")?;
+        }
+        
+        let entries: Vec<(String, String, String)> = sqlx::query_as("SELECT scope, key, value FROM session_state")
+            .fetch_all(&mut *conn)
+            .await
+            .context("Failed to fetch session state")?;
+        for (scope, key, value) in &entries {
+            sqlx::query("UPDATE session_state SET value = ? WHERE scope = ? AND key = ?")
+                .bind(to.seal(&from.open(value)?)?)
+                .bind(scope)
+                .bind(key)
+                .execute(&mut *conn)
+                .await
+                .context("Failed to update session state")?;
+        }
+        
+        info!(
+            "Rewrote {} messages, {} attachments, {} attachment chunks, {} comments, {} drafts and {} session values",
+            rows.len(), attachments.len(), chunks.len(), comments.len(), drafts.len(), entries.len()
+        );
+        Ok(())
+    }
+    
     /// Delete old messages, keeping only the last N
     pub async fn delete_old_messages(&self, conversation_id: &str, keep_last: i32) -> Result<usize> {
         let result = sqlx::query(
@@ -311,6 +578,183 @@ impl ConversationRepository {
         Ok(deleted)
     }
     
+    // ==================== Drafts ====================
+    
+    /// Save the unsent input of a conversation (an empty text removes the draft)
+    pub async fn save_draft(&self, conversation_id: &str, content: &str) -> Result<()> {
+        let _writing = self.cipher.lock_writes().await;
+        if content.trim().is_empty() {
+            sqlx::query("DELETE FROM drafts WHERE conversation_id = ?")
+                .bind(conversation_id)
+                .execute(&self.pool)
+                .await
+                .context("Failed to delete draft")?;
+            return Ok(());
+        }
+        
+        sqlx::query(
+            r#"
+            INSERT INTO drafts (conversation_id, content, updated_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT(conversation_id) DO UPDATE SET
+                content = excluded.content,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(self.cipher().seal(content)?)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save draft")?;
+        
+        Ok(())
+    }
+    
+    /// Get the unsent input of a conversation
+    pub async fn get_draft(&self, conversation_id: &str) -> Result<Option<String>> {
+        let content: Option<String> = sqlx::query_scalar("SELECT content FROM drafts WHERE conversation_id = ?")
+            .bind(conversation_id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch draft")?;
+        
+        content.map(|c| self.cipher().open(&c)).transpose()
+    }
+    
+    // ==================== Session state ====================
+    
+    /// Set a scratchpad entry of a tool scope: conversation, task run or MCP client (None removes it)
+    ///
+    /// The entries of a conversation are removed with it; the other scopes are
+    /// cleared by `clear_session_state` when they end.
+    pub async fn set_session_value(&self, scope: &str, key: &str, value: Option<&str>) -> Result<()> {
+        let _writing = self.cipher.lock_writes().await;
+        let Some(value) = value else {
+            sqlx::query("DELETE FROM session_state WHERE scope = ? AND key = ?")
+                .bind(scope)
+                .bind(key)
+                .execute(&self.pool)
+                .await
+                .context("Failed to delete session value")?;
+            return Ok(());
+        };
+        
+        sqlx::query(
+            r#"
+            INSERT INTO session_state (scope, key, value, updated_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(scope, key) DO UPDATE SET
+                value = excluded.value,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(self.cipher().seal(value)?)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save session value")?;
+        
+        Ok(())
+    }
+    
+    /// Get a scratchpad entry of a tool scope
+    pub async fn get_session_value(&self, scope: &str, key: &str) -> Result<Option<String>> {
+        let value: Option<String> = sqlx::query_scalar("SELECT value FROM session_state WHERE scope = ? AND key = ?")
+            .bind(scope)
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch session value")?;
+        
+        value.map(|v| self.cipher().open(&v)).transpose()
+    }
+    
+    /// All scratchpad entries of a tool scope, by key
+    pub async fn get_session_state(&self, scope: &str) -> Result<BTreeMap<String, String>> {
+        let rows: Vec<(String, String)> = sqlx::query_as("SELECT key, value FROM session_state WHERE scope = ?")
+            .bind(scope)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch session state")?;
+        
+        let cipher = self.cipher();
+        rows.into_iter()
+            .map(|(key, value)| Ok((key, cipher.open(&value)?)))
+            .collect()
+    }
+    
+    /// Remove the scratchpad of a tool scope that ended
+    pub async fn clear_session_state(&self, scope: &str) -> Result<()> {
+        sqlx::query("DELETE FROM session_state WHERE scope = ?")
+            .bind(scope)
+            .execute(&self.pool)
+            .await
+            .context("Failed to clear session state")?;
+        Ok(())
+    }
+    
+    // ==================== Retention ====================
+    
+    /// IDs of the conversations beyond the `keep` most recently updated ones
+    pub async fn list_conversation_ids_beyond(&self, keep: i64) -> Result<Vec<String>> {
+        let ids: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT id FROM conversations
+            ORDER BY updated_at DESC, id
+            LIMIT -1 OFFSET ?
+            "#,
+        )
+        .bind(keep)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list conversations")?;
+        
+        Ok(ids.into_iter().map(|(id,)| id).collect())
+    }
+    
+    /// Count messages created before `cutoff`
+    pub async fn count_messages_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM messages WHERE created_at < ?")
+            .bind(cutoff.timestamp())
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to count messages")?;
+        
+        Ok(count as u64)
+    }
+    
+    /// Delete messages created before `cutoff`
+    pub async fn delete_messages_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM messages WHERE created_at < ?")
+            .bind(cutoff.timestamp())
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete expired messages")?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    /// Conversations holding more than `cap` messages, with their message count
+    pub async fn conversations_over_message_cap(&self, cap: i64) -> Result<Vec<(String, i64)>> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT conversation_id, COUNT(*) AS count
+            FROM messages
+            GROUP BY conversation_id
+            HAVING count > ?
+            "#,
+        )
+        .bind(cap)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to count messages per conversation")?;
+        
+        Ok(rows)
+    }
+    
     /// Count messages in a conversation
     pub async fn count_messages(&self, conversation_id: &str) -> Result<i64> {
         let count: (i64,) = sqlx::query_as(
@@ -336,6 +780,474 @@ impl ConversationRepository {
         
         Ok(total.0.unwrap_or(0))
     }
+    
+    // ==================== Semantic search ====================
+    
+    /// User and assistant messages without an embedding from `model_name`, oldest first
+    pub async fn messages_without_embedding(&self, model_name: &str, limit: i64) -> Result<Vec<StoredMessage>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT m.id, m.conversation_id, m.role, m.content, m.tokens, m.created_at, m.tool_name, m.tool_call_id, m.metadata
+            FROM messages m
+            LEFT JOIN message_embeddings e ON e.message_id = m.id AND e.model_name = ?
+            WHERE e.message_id IS NULL AND m.role IN ('user', 'assistant')
+            ORDER BY m.id ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(model_name)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch messages to embed")?;
+        
+        let cipher = self.cipher();
+        rows.iter().map(|row| Self::message_from_row(row, &cipher)).collect()
+    }
+    
+    /// Store the embedding of a message computed by `model_name`
+    pub async fn save_message_embedding(&self, message_id: i64, model_name: &str, embedding: &[f32]) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO message_embeddings (message_id, model_name, embedding) VALUES (?, ?, ?)",
+        )
+        .bind(message_id)
+        .bind(model_name)
+        .bind(encode_embedding(embedding))
+        .execute(&self.pool)
+        .await
+        .context("Failed to save message embedding")?;
+        
+        Ok(())
+    }
+    
+    /// Messages closest to a query embedding, best first
+    ///
+    /// Only embeddings from `model_name` are compared: vectors from different
+    /// models don't live in the same space, even when their dimensions match.
+    pub async fn semantic_search(&self, embedding: &[f32], model_name: &str, limit: usize) -> Result<Vec<(StoredMessage, f32)>> {
+        let rows: Vec<(i64, Vec<u8>)> = sqlx::query_as(
+            "SELECT message_id, embedding FROM message_embeddings WHERE model_name = ?",
+        )
+        .bind(model_name)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch message embeddings")?;
+        
+        let mut scored: Vec<(i64, f32)> = rows
+            .iter()
+            .map(|(id, bytes)| (*id, cosine_similarity(&decode_embedding(bytes), embedding)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(limit);
+        
+        // Only the selected messages are loaded and decrypted
+        let cipher = self.cipher();
+        let mut matches = Vec::with_capacity(scored.len());
+        for (id, score) in scored {
+            let row = sqlx::query(
+                r#"
+                SELECT id, conversation_id, role, content, tokens, created_at, tool_name, tool_call_id, metadata
+                FROM messages
+                WHERE id = ?
+                "#,
+            )
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch matching message")?;
+            
+            if let Some(row) = row {
+                matches.push((Self::message_from_row(&row, &cipher)?, score));
+            }
+        }
+        
+        Ok(matches)
+    }
+    
+    fn message_from_row(row: &SqliteRow, cipher: &CipherState) -> Result<StoredMessage> {
+        let created_timestamp: i64 = row.get("created_at");
+        let content: String = row.get("content");
+        let metadata: Option<String> = row.get("metadata");
+        let metadata = match metadata {
+            Some(metadata) => serde_json::from_str(&cipher.open(&metadata)?)
+                .context("Invalid message metadata")?,
+            None => HashMap::new(),
+        };
+        Ok(StoredMessage {
+            id: Some(row.get("id")),
+            conversation_id: row.get("conversation_id"),
+            role: row.get("role"),
+            content: cipher.open(&content)?,
+            tokens: row.get("tokens"),
+            created_at: DateTime::from_timestamp(created_timestamp, 0).unwrap_or_else(Utc::now),
+            tool_name: row.get("tool_name"),
+            tool_call_id: row.get("tool_call_id"),
+            metadata,
+        })
+    }
+    
+    // ==================== Feedback ====================
+    
+    /// Rate an assistant message, replacing its previous rating
+    pub async fn rate_message(&self, message_id: i64, rating: FeedbackRating, comment: Option<&str>) -> Result<MessageFeedback> {
+        let _writing = self.cipher.lock_writes().await;
+        let role: Option<(String,)> = sqlx::query_as("SELECT role FROM messages WHERE id = ?")
+            .bind(message_id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch rated message")?;
+        match role {
+            Some((role,)) if role == "assistant" => {}
+            Some(_) => anyhow::bail!("Only assistant messages can be rated"),
+            None => anyhow::bail!("Message not found: {}", message_id),
+        }
+        
+        let feedback = MessageFeedback {
+            message_id,
+            rating,
+            comment: comment.map(str::trim).filter(|c| !c.is_empty()).map(str::to_string),
+            created_at: Utc::now(),
+        };
+        // The comment may quote the conversation: encrypted like the messages
+        let comment = feedback.comment.as_deref().map(|c| self.cipher().seal(c)).transpose()?;
+        sqlx::query(
+            r#"
+            INSERT INTO message_feedback (message_id, rating, comment, created_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(message_id) DO UPDATE SET
+                rating = excluded.rating,
+                comment = excluded.comment,
+                created_at = excluded.created_at
+            "#,
+        )
+        .bind(message_id)
+        .bind(rating.score())
+        .bind(comment)
+        .bind(feedback.created_at.timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save message feedback")?;
+        
+        Ok(feedback)
+    }
+    
+    /// Remove the rating of a message
+    pub async fn delete_feedback(&self, message_id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM message_feedback WHERE message_id = ?")
+            .bind(message_id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete message feedback")?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Ratings of a conversation's messages, by message id
+    pub async fn get_feedback(&self, conversation_id: &str) -> Result<HashMap<i64, MessageFeedback>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT f.message_id, f.rating, f.comment, f.created_at
+            FROM message_feedback f
+            JOIN messages m ON m.id = f.message_id
+            WHERE m.conversation_id = ?
+            "#,
+        )
+        .bind(conversation_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch message feedback")?;
+        
+        let cipher = self.cipher();
+        rows.iter()
+            .map(|row| Self::feedback_from_row(row, &cipher).map(|f| (f.message_id, f)))
+            .collect()
+    }
+    
+    /// Every rated answer with the conversation up to it, oldest first
+    pub async fn rated_exchanges(&self) -> Result<Vec<RatedExchange>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT f.message_id, f.rating, f.comment, f.created_at, m.conversation_id
+            FROM message_feedback f
+            JOIN messages m ON m.id = f.message_id
+            ORDER BY f.message_id ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch rated messages")?;
+        
+        let cipher = self.cipher();
+        let mut conversations: HashMap<String, Vec<StoredMessage>> = HashMap::new();
+        let mut exchanges = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let feedback = Self::feedback_from_row(row, &cipher)?;
+            let conversation_id: String = row.get("conversation_id");
+            if !conversations.contains_key(&conversation_id) {
+                let messages = self.get_messages(&conversation_id).await?;
+                conversations.insert(conversation_id.clone(), messages);
+            }
+            let messages = &conversations[&conversation_id];
+            let Some(position) = messages.iter().position(|m| m.id == Some(feedback.message_id)) else {
+                continue;
+            };
+            exchanges.push(RatedExchange {
+                prompt: messages[..position].to_vec(),
+                response: messages[position].clone(),
+                feedback,
+            });
+        }
+        
+        Ok(exchanges)
+    }
+    
+    fn feedback_from_row(row: &SqliteRow, cipher: &CipherState) -> Result<MessageFeedback> {
+        let comment: Option<String> = row.get("comment");
+        let created_at: i64 = row.get("created_at");
+        Ok(MessageFeedback {
+            message_id: row.get("message_id"),
+            rating: FeedbackRating::from_score(row.get("rating")),
+            comment: comment.map(|c| cipher.open(&c)).transpose()?,
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_else(Utc::now),
+        })
+    }
+    
+    // ==================== Attachments ====================
+    
+    /// Store the attachments of a message
+    pub async fn add_attachments(&self, message_id: i64, attachments: &[Attachment]) -> Result<()> {
+        let _writing = self.cipher.lock_writes().await;
+        let cipher = self.cipher();
+        let mut tx = self.pool.begin().await?;
+        for attachment in attachments {
+            sqlx::query(
+                r#"
+                INSERT INTO attachments (id, message_id, kind, file_name, mime_type, data, text, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&attachment.id)
+            .bind(message_id)
+            .bind(attachment.kind.as_str())
+            .bind(&attachment.file_name)
+            .bind(&attachment.mime_type)
+            .bind(cipher.seal_bytes(&attachment.data)?)
+            .bind(attachment.text.as_deref().map(|t| cipher.seal(t)).transpose()?)
+            .bind(attachment.created_at.timestamp())
+            .execute(&mut *tx)
+            .await
+            .context("Failed to add attachment")?;
+        }
+        tx.commit().await?;
+        
+        debug!("Added {} attachments to message {}", attachments.len(), message_id);
+        Ok(())
+    }
+    
+    /// Attachments of every message of a conversation, by message id
+    pub async fn get_attachments(&self, conversation_id: &str) -> Result<HashMap<i64, Vec<Attachment>>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT a.*
+            FROM attachments a
+            JOIN messages m ON m.id = a.message_id
+            WHERE m.conversation_id = ?
+            ORDER BY a.created_at ASC
+            "#,
+        )
+        .bind(conversation_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch attachments")?;
+        
+        let cipher = self.cipher();
+        let mut attachments: HashMap<i64, Vec<Attachment>> = HashMap::new();
+        for row in &rows {
+            let message_id: i64 = row.get("message_id");
+            attachments.entry(message_id).or_default().push(Self::attachment_from_row(row, &cipher)?);
+        }
+        
+        Ok(attachments)
+    }
+    
+    /// Get a single attachment with its content
+    pub async fn get_attachment(&self, id: &str) -> Result<Option<Attachment>> {
+        let row = sqlx::query("SELECT * FROM attachments WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch attachment")?;
+        
+        let cipher = self.cipher();
+        row.map(|row| Self::attachment_from_row(&row, &cipher)).transpose()
+    }
+    
+    /// Replace the indexed chunks of a document attachment
+    pub async fn save_attachment_chunks(&self, attachment_id: &str, chunks: &[DocumentChunk]) -> Result<()> {
+        let _writing = self.cipher.lock_writes().await;
+        let cipher = self.cipher();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM attachment_chunks WHERE attachment_id = ?")
+            .bind(attachment_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear attachment chunks")?;
+        for chunk in chunks {
+            sqlx::query(
+                "INSERT INTO attachment_chunks (attachment_id, chunk_index, content, embedding, page) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(attachment_id)
+            .bind(chunk.index as i64)
+            .bind(cipher.seal(&chunk.content)?)
+            .bind(chunk.embedding.as_deref().map(encode_embedding))
+            .bind(chunk.page.map(|page| page as i64))
+            .execute(&mut *tx)
+            .await
+            .context("Failed to add attachment chunk")?;
+        }
+        tx.commit().await?;
+        
+        debug!("Indexed {} chunks for attachment {}", chunks.len(), attachment_id);
+        Ok(())
+    }
+    
+    /// Indexed chunks of a document attachment, in document order
+    pub async fn get_attachment_chunks(&self, attachment_id: &str) -> Result<Vec<DocumentChunk>> {
+        let rows = sqlx::query(
+            "SELECT chunk_index, content, embedding, page FROM attachment_chunks WHERE attachment_id = ? ORDER BY chunk_index",
+        )
+        .bind(attachment_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch attachment chunks")?;
+        
+        let cipher = self.cipher();
+        rows.iter()
+            .map(|row| {
+                let embedding: Option<Vec<u8>> = row.get("embedding");
+                Ok(DocumentChunk {
+                    index: row.get::<i64, _>("chunk_index") as usize,
+                    content: cipher.open(row.get("content"))?,
+                    embedding: embedding.as_deref().map(decode_embedding),
+                    page: row.get::<Option<i64>, _>("page").map(|page| page as usize),
+                })
+            })
+            .collect()
+    }
+    
+    /// Record how a document was last indexed (token count, embedding model)
+    pub async fn save_document_index(&self, attachment_id: &str, tokens: Option<usize>, embedding_model: Option<&str>) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO document_index (attachment_id, tokens, embedding_model, indexed_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(attachment_id) DO UPDATE SET
+                tokens = excluded.tokens,
+                embedding_model = excluded.embedding_model,
+                indexed_at = excluded.indexed_at
+            "#,
+        )
+        .bind(attachment_id)
+        .bind(tokens.map(|t| t as i64))
+        .bind(embedding_model)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save document index status")?;
+        
+        Ok(())
+    }
+    
+    /// Document attachments of all conversations with their index status, newest first
+    pub async fn list_documents(&self) -> Result<Vec<DocumentInfo>> {
+        self.query_documents(None).await
+    }
+    
+    /// A document attachment with its index status
+    pub async fn get_document(&self, attachment_id: &str) -> Result<Option<DocumentInfo>> {
+        Ok(self.query_documents(Some(attachment_id)).await?.into_iter().next())
+    }
+    
+    async fn query_documents(&self, attachment_id: Option<&str>) -> Result<Vec<DocumentInfo>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT a.id, a.file_name, a.mime_type, LENGTH(a.data) AS size, a.text, a.created_at,
+                   m.conversation_id, c.title AS conversation_title,
+                   d.tokens, d.embedding_model, d.indexed_at,
+                   (SELECT COUNT(*) FROM attachment_chunks ch WHERE ch.attachment_id = a.id) AS chunks,
+                   (SELECT COUNT(*) FROM attachment_chunks ch
+                    WHERE ch.attachment_id = a.id AND ch.embedding IS NULL) AS chunks_without_embedding
+            FROM attachments a
+            JOIN messages m ON m.id = a.message_id
+            JOIN conversations c ON c.id = m.conversation_id
+            LEFT JOIN document_index d ON d.attachment_id = a.id
+            WHERE a.kind = 'document' AND (? IS NULL OR a.id = ?)
+            ORDER BY a.created_at DESC, a.id
+            "#,
+        )
+        .bind(attachment_id)
+        .bind(attachment_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch documents")?;
+        
+        let cipher = self.cipher();
+        rows.iter()
+            .map(|row| {
+                let text: Option<String> = row.get("text");
+                let chars = text.map(|t| cipher.open(&t)).transpose()?.map_or(0, |t| t.chars().count());
+                let chunks = row.get::<i64, _>("chunks") as usize;
+                let chunks_without_embedding = row.get::<i64, _>("chunks_without_embedding") as usize;
+                let indexed_at: Option<i64> = row.get("indexed_at");
+                Ok(DocumentInfo {
+                    attachment_id: row.get("id"),
+                    file_name: row.get("file_name"),
+                    mime_type: row.get("mime_type"),
+                    conversation_id: row.get("conversation_id"),
+                    conversation_title: row.get("conversation_title"),
+                    size: row.get::<i64, _>("size") as usize,
+                    chars,
+                    status: DocumentStatus::new(chars, chunks, chunks_without_embedding),
+                    chunks,
+                    tokens: row.get::<Option<i64>, _>("tokens").map(|t| t as usize),
+                    embedding_model: row.get("embedding_model"),
+                    indexed_at: indexed_at.and_then(|t| DateTime::from_timestamp(t, 0)),
+                    created_at: DateTime::from_timestamp(row.get("created_at"), 0).unwrap_or_else(Utc::now),
+                })
+            })
+            .collect()
+    }
+    
+    /// Delete a document attachment, with its chunks and index status
+    pub async fn delete_attachment(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM attachments WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete attachment")?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    fn attachment_from_row(row: &SqliteRow, cipher: &CipherState) -> Result<Attachment> {
+        let kind: String = row.get("kind");
+        let data: Vec<u8> = row.get("data");
+        let data = cipher.open_bytes(&data)?;
+        let text: Option<String> = row.get("text");
+        let created_at: i64 = row.get("created_at");
+        
+        Ok(Attachment {
+            id: row.get("id"),
+            kind: AttachmentKind::parse(&kind)
+                .with_context(|| format!("Unknown attachment kind: {}", kind))?,
+            file_name: row.get("file_name"),
+            mime_type: row.get("mime_type"),
+            size: data.len(),
+            data,
+            text: text.map(|t| cipher.open(&t)).transpose()?,
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_else(Utc::now),
+        })
+    }
 }
 
 // Import DateTime for the repository methods
@@ -345,6 +1257,7 @@ use chrono::DateTime;
 mod tests {
     use super::*;
     use crate::context::database::Database;
+    use crate::context::documents;
     
     async fn setup_test_db() -> ConversationRepository {
         let db = Database::new("sqlite::memory:").await.unwrap();
@@ -381,6 +1294,70 @@ mod tests {
         assert_eq!(messages[1].content, "Hi!");
     }
     
+    #[tokio::test]
+    async fn test_reencrypt_mixed_messages() {
+        let cipher = SharedCipher::default();
+        let repo = setup_test_db().await.with_cipher(cipher.clone());
+        let conv = repo.create_conversation("Test", "gpt-4").await.unwrap();
+        repo.add_message(&StoredMessage::new(conv.id.clone(), "user".to_string(), "Hello".to_string())).await.unwrap();
+        
+        // A message written once the key is active, before the rewrite
+        let (key, _) = crate::context::ContentCipher::create("secret").unwrap();
+        let unlocked = CipherState::Unlocked(key);
+        *cipher.write().unwrap() = unlocked.clone();
+        repo.add_message(&StoredMessage::new(conv.id.clone(), "assistant".to_string(), "Hi!".to_string())).await.unwrap();
+        
+        let mut tx = repo.pool.begin().await.unwrap();
+        ConversationRepository::reencrypt_content(&mut tx, &unlocked, &unlocked).await.unwrap();
+        tx.commit().await.unwrap();
+        
+        let raw: Vec<(String,)> = sqlx::query_as("SELECT content FROM messages").fetch_all(&repo.pool).await.unwrap();
+        assert!(raw.iter().all(|(content,)| crate::context::ContentCipher::is_encrypted(content)));
+        let messages = repo.get_messages(&conv.id).await.unwrap();
+        assert_eq!(messages[0].content, "Hello");
+        assert_eq!(messages[1].content, "Hi!");
+    }
+    
+    #[tokio::test]
+    async fn test_update_message() {
+        let repo = setup_test_db().await;
+        
+        let conv = repo.create_conversation("Test", "gpt-4").await.unwrap();
+        let mut metadata = HashMap::new();
+        metadata.insert("incomplete".to_string(), serde_json::json!(true));
+        let msg = StoredMessage::new(conv.id.clone(), "assistant".to_string(), "Once upon".to_string())
+            .with_metadata(metadata);
+        let id = repo.add_message(&msg).await.unwrap().id.unwrap();
+        
+        assert!(repo.update_message(id, "Once upon a time.", &HashMap::new()).await.unwrap());
+        let updated = repo.get_message(id).await.unwrap().unwrap();
+        assert_eq!(updated.content, "Once upon a time.");
+        assert!(updated.metadata.is_empty());
+        assert!(repo.get_message(id + 1).await.unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_tool_message_round_trip() {
+        let repo = setup_test_db().await;
+        
+        let conv = repo.create_conversation("Test", "gpt-4").await.unwrap();
+        
+        let msg = StoredMessage::new(conv.id.clone(), "tool".to_string(), "42".to_string())
+            .with_tool_call(Some("calculator".to_string()), Some("call_1".to_string()));
+        repo.add_message(&msg).await.unwrap();
+        let metadata = HashMap::from([("reasoning".to_string(), serde_json::json!("6 * 7"))]);
+        let reply = StoredMessage::new(conv.id.clone(), "assistant".to_string(), "42".to_string())
+            .with_metadata(metadata.clone());
+        repo.add_message(&reply).await.unwrap();
+        
+        let messages = repo.get_messages(&conv.id).await.unwrap();
+        assert!(messages[0].metadata.is_empty());
+        assert_eq!(messages[1].metadata, metadata);
+        assert_eq!(messages[0].role, "tool");
+        assert_eq!(messages[0].tool_name.as_deref(), Some("calculator"));
+        assert_eq!(messages[0].tool_call_id.as_deref(), Some("call_1"));
+    }
+    
     #[tokio::test]
     async fn test_delete_old_messages() {
         let repo = setup_test_db().await;
@@ -404,4 +1381,166 @@ mod tests {
         let remaining = repo.get_messages(&conv.id).await.unwrap();
         assert_eq!(remaining.len(), 2);
     }
+    
+    #[tokio::test]
+    async fn test_semantic_search() {
+        let repo = setup_test_db().await;
+        
+        let conv = repo.create_conversation("Test", "gpt-4").await.unwrap();
+        for content in ["Rust borrow checker", "Pasta recipe"] {
+            let msg = StoredMessage::new(conv.id.clone(), "user".to_string(), content.to_string());
+            repo.add_message(&msg).await.unwrap();
+        }
+        let tool = StoredMessage::new(conv.id.clone(), "tool".to_string(), "42".to_string());
+        repo.add_message(&tool).await.unwrap();
+        
+        // Tool results are not embedded
+        let pending = repo.messages_without_embedding("model-a", 10).await.unwrap();
+        assert_eq!(pending.len(), 2);
+        
+        repo.save_message_embedding(pending[0].id.unwrap(), "model-a", &[1.0, 0.0]).await.unwrap();
+        repo.save_message_embedding(pending[1].id.unwrap(), "model-a", &[0.0, 1.0]).await.unwrap();
+        assert!(repo.messages_without_embedding("model-a", 10).await.unwrap().is_empty());
+        assert_eq!(repo.messages_without_embedding("model-b", 10).await.unwrap().len(), 2);
+        
+        let matches = repo.semantic_search(&[0.9, 0.1], "model-a", 1).await.unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].0.content, "Rust borrow checker");
+        assert!(repo.semantic_search(&[0.9, 0.1], "model-b", 1).await.unwrap().is_empty());
+        
+        // Embeddings go away with their messages
+        repo.delete_conversation(&conv.id).await.unwrap();
+        assert!(repo.semantic_search(&[0.9, 0.1], "model-a", 1).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_drafts() {
+        let repo = setup_test_db().await;
+        let conv = repo.create_conversation("Test", "gpt-4").await.unwrap();
+        
+        assert_eq!(repo.get_draft(&conv.id).await.unwrap(), None);
+        repo.save_draft(&conv.id, "Half a").await.unwrap();
+        repo.save_draft(&conv.id, "Half a question").await.unwrap();
+        assert_eq!(repo.get_draft(&conv.id).await.unwrap().as_deref(), Some("Half a question"));
+        
+        repo.save_draft(&conv.id, "  ").await.unwrap();
+        assert_eq!(repo.get_draft(&conv.id).await.unwrap(), None);
+        
+        repo.save_draft(&conv.id, "Kept until the conversation goes").await.unwrap();
+        repo.delete_conversation(&conv.id).await.unwrap();
+        assert_eq!(repo.get_draft(&conv.id).await.unwrap(), None);
+    }
+    
+    #[tokio::test]
+    async fn test_session_state() {
+        let repo = setup_test_db().await;
+        let conv = repo.create_conversation("Test", "gpt-4").await.unwrap();
+        
+        repo.set_session_value(&conv.id, "plan", Some("1. search")).await.unwrap();
+        repo.set_session_value(&conv.id, "plan", Some("1. search 2. summarize")).await.unwrap();
+        repo.set_session_value(&conv.id, "count", Some("3")).await.unwrap();
+        assert_eq!(repo.get_session_value(&conv.id, "plan").await.unwrap().as_deref(), Some("1. search 2. summarize"));
+        assert_eq!(repo.get_session_state(&conv.id).await.unwrap().keys().collect::<Vec<_>>(), vec!["count", "plan"]);
+        
+        repo.set_session_value(&conv.id, "count", None).await.unwrap();
+        assert_eq!(repo.get_session_value(&conv.id, "count").await.unwrap(), None);
+        // Any tool scope has a scratchpad, cleared when the scope ends
+        repo.set_session_value("run-1", "plan", Some("x")).await.unwrap();
+        repo.clear_session_state("run-1").await.unwrap();
+        assert!(repo.get_session_state("run-1").await.unwrap().is_empty());
+        
+        repo.delete_conversation(&conv.id).await.unwrap();
+        assert!(repo.get_session_state(&conv.id).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_document_library() {
+        let repo = setup_test_db().await;
+        let conv = repo.create_conversation("Docs", "gpt-4").await.unwrap();
+        let msg = StoredMessage::new(conv.id.clone(), "user".to_string(), "Read this".to_string());
+        let message_id = repo.add_message(&msg).await.unwrap().id.unwrap();
+        
+        let text = "x".repeat(documents::MAX_INLINE_CHARS + 10);
+        let document = Attachment::document("big.txt".to_string(), "text/plain".to_string(), text.clone().into_bytes(), text);
+        let image = Attachment::image("shot.png".to_string(), "image/png".to_string(), vec![1, 2, 3]);
+        repo.add_attachments(message_id, &[document.clone(), image]).await.unwrap();
+        
+        let listed = repo.list_documents().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].conversation_title, "Docs");
+        assert_eq!(listed[0].status, DocumentStatus::NotIndexed);
+        
+        let chunk = DocumentChunk { index: 0, content: "x".to_string(), embedding: Some(vec![1.0, 0.0]), page: None };
+        repo.save_attachment_chunks(&document.id, &[chunk]).await.unwrap();
+        repo.save_document_index(&document.id, Some(42), Some("nomic.gguf")).await.unwrap();
+        let info = repo.get_document(&document.id).await.unwrap().unwrap();
+        assert_eq!((info.status, info.chunks, info.tokens), (DocumentStatus::Indexed, 1, Some(42)));
+        assert_eq!(info.embedding_model.as_deref(), Some("nomic.gguf"));
+        assert!(info.indexed_at.is_some());
+        
+        assert!(repo.delete_attachment(&document.id).await.unwrap());
+        assert!(repo.list_documents().await.unwrap().is_empty());
+        assert!(repo.get_attachment_chunks(&document.id).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_attachments_round_trip() {
+        let repo = setup_test_db().await;
+        
+        let conv = repo.create_conversation("Test", "gpt-4").await.unwrap();
+        let msg = StoredMessage::new(conv.id.clone(), "user".to_string(), "What is this?".to_string());
+        let saved = repo.add_message(&msg).await.unwrap();
+        let message_id = saved.id.unwrap();
+        
+        let image = Attachment::image("shot.png".to_string(), "image/png".to_string(), vec![0x89, b'P', b'N', b'G']);
+        repo.add_attachments(message_id, std::slice::from_ref(&image)).await.unwrap();
+        
+        let attachments = repo.get_attachments(&conv.id).await.unwrap();
+        assert_eq!(attachments[&message_id].len(), 1);
+        assert_eq!(attachments[&message_id][0].data, image.data);
+        assert!(attachments[&message_id][0].is_image());
+        
+        let doc = Attachment::document("notes.md".to_string(), "text/markdown".to_string(), b"# Notes".to_vec(), "# Notes".to_string());
+        repo.add_attachments(message_id, std::slice::from_ref(&doc)).await.unwrap();
+        let chunk = DocumentChunk { index: 0, content: "# Notes".to_string(), embedding: Some(vec![0.6, 0.8]), page: None };
+        repo.save_attachment_chunks(&doc.id, &[chunk]).await.unwrap();
+        
+        let loaded = repo.get_attachment(&doc.id).await.unwrap().unwrap();
+        assert_eq!(loaded.text.as_deref(), Some("# Notes"));
+        assert_eq!(repo.get_attachment_chunks(&doc.id).await.unwrap()[0].embedding, Some(vec![0.6, 0.8]));
+        
+        // Attachments go away with their conversation
+        repo.delete_conversation(&conv.id).await.unwrap();
+        assert!(repo.get_attachment(&image.id).await.unwrap().is_none());
+        assert!(repo.get_attachment_chunks(&doc.id).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_message_feedback() {
+        let repo = setup_test_db().await;
+        
+        let conv = repo.create_conversation("Test", "gpt-4").await.unwrap();
+        let question = StoredMessage::new(conv.id.clone(), "user".to_string(), "2 + 2?".to_string());
+        let question_id = repo.add_message(&question).await.unwrap().id.unwrap();
+        let answer = StoredMessage::new(conv.id.clone(), "assistant".to_string(), "5".to_string());
+        let answer_id = repo.add_message(&answer).await.unwrap().id.unwrap();
+        
+        assert!(repo.rate_message(question_id, FeedbackRating::Up, None).await.is_err());
+        repo.rate_message(answer_id, FeedbackRating::Up, None).await.unwrap();
+        repo.rate_message(answer_id, FeedbackRating::Down, Some("Wrong sum")).await.unwrap();
+        
+        let feedback = repo.get_feedback(&conv.id).await.unwrap();
+        assert_eq!(feedback[&answer_id].rating, FeedbackRating::Down);
+        assert_eq!(feedback[&answer_id].comment.as_deref(), Some("Wrong sum"));
+        
+        let exchanges = repo.rated_exchanges().await.unwrap();
+        assert_eq!(exchanges.len(), 1);
+        let record = exchanges[0].to_preference_record();
+        assert_eq!(record["prompt"][0]["content"], "2 + 2?");
+        assert_eq!(record["completion"][0]["content"], "5");
+        assert_eq!(record["label"], false);
+        
+        assert!(repo.delete_feedback(answer_id).await.unwrap());
+        assert!(repo.rated_exchanges().await.unwrap().is_empty());
+    }
 }
@@ -0,0 +1,183 @@
+/// Politiques de rétention des conversations et élagage automatique
+///
+/// Trois limites indépendantes (toutes optionnelles) : nombre de conversations
+/// conservées, âge maximal des messages et nombre de messages par conversation.
+
+use super::manager::ContextManager;
+use super::repository::ConversationRepository;
+use super::settings::SettingsRepository;
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{error, info};
+use ts_rs::TS;
+
+/// Intervalle entre deux passes d'élagage automatique
+pub const PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Limites de rétention (None = pas de limite)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RetentionPolicy {
+    /// Nombre de conversations conservées (les plus récemment modifiées)
+    pub max_conversations: Option<u32>,
+    /// Âge maximal des messages, en jours
+    pub max_message_age_days: Option<u32>,
+    /// Nombre de messages conservés par conversation (les plus récents)
+    pub max_messages_per_conversation: Option<u32>,
+}
+
+impl RetentionPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.max_conversations.is_some()
+            || self.max_message_age_days.is_some()
+            || self.max_messages_per_conversation.is_some()
+    }
+}
+
+/// Résultat (ou aperçu) d'un élagage
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PruneReport {
+    /// Conversations supprimées au-delà de `max_conversations`
+    pub deleted_conversations: Vec<String>,
+    /// Messages plus anciens que `max_message_age_days`
+    #[ts(type = "number")]
+    pub expired_messages: u64,
+    /// Messages au-delà de `max_messages_per_conversation`
+    #[ts(type = "number")]
+    pub trimmed_messages: u64,
+}
+
+impl PruneReport {
+    pub fn is_empty(&self) -> bool {
+        self.deleted_conversations.is_empty() && self.expired_messages == 0 && self.trimmed_messages == 0
+    }
+}
+
+/// Applique une politique, ou calcule ce qu'elle supprimerait si `dry_run`
+///
+/// En simulation, les compteurs peuvent se recouvrir (un message d'une conversation
+/// supprimée peut aussi être compté comme expiré) : ce sont des majorants.
+pub async fn prune(
+    repository: &ConversationRepository,
+    policy: &RetentionPolicy,
+    dry_run: bool,
+) -> Result<PruneReport> {
+    let mut report = PruneReport::default();
+
+    if let Some(max) = policy.max_conversations {
+        report.deleted_conversations = repository.list_conversation_ids_beyond(max as i64).await?;
+        if !dry_run {
+            for id in &report.deleted_conversations {
+                repository.delete_conversation(id).await?;
+            }
+        }
+    }
+
+    if let Some(days) = policy.max_message_age_days {
+        let cutoff = Utc::now() - Duration::days(days as i64);
+        report.expired_messages = if dry_run {
+            repository.count_messages_before(cutoff).await?
+        } else {
+            repository.delete_messages_before(cutoff).await?
+        };
+    }
+
+    if let Some(cap) = policy.max_messages_per_conversation {
+        for (conversation_id, count) in repository.conversations_over_message_cap(cap as i64).await? {
+            report.trimmed_messages += if dry_run {
+                (count - cap as i64) as u64
+            } else {
+                repository.delete_old_messages(&conversation_id, cap as i32).await? as u64
+            };
+        }
+    }
+
+    Ok(report)
+}
+
+/// Tâche de fond : applique la politique enregistrée à intervalle régulier
+pub async fn run_periodic(
+    repository: ConversationRepository,
+    settings: Arc<SettingsRepository>,
+    context_manager: Arc<RwLock<ContextManager>>,
+) {
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let policy = match settings.get_retention_policy().await {
+            Ok(policy) if policy.is_enabled() => policy,
+            Ok(_) => continue,
+            Err(e) => {
+                error!("Failed to read retention policy: {}", e);
+                continue;
+            }
+        };
+
+        match prune(&repository, &policy, false).await {
+            Ok(report) if !report.is_empty() => {
+                info!(
+                    "Retention: {} conversations deleted, {} expired and {} trimmed messages",
+                    report.deleted_conversations.len(),
+                    report.expired_messages,
+                    report.trimmed_messages
+                );
+                context_manager.read().await.invalidate_cache().await;
+            }
+            Ok(_) => {}
+            Err(e) => error!("Retention pass failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{Database, StoredMessage};
+
+    async fn setup_test_db() -> ConversationRepository {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        ConversationRepository::new(db.pool().clone())
+    }
+
+    #[tokio::test]
+    async fn test_preview_matches_prune() {
+        let repo = setup_test_db().await;
+
+        for c in 0..3 {
+            let conv = repo.create_conversation(&format!("Chat {}", c), "model").await.unwrap();
+            for i in 0..4 {
+                let mut msg = StoredMessage::new(conv.id.clone(), "user".to_string(), format!("Message {}", i));
+                // Deux messages anciens par conversation
+                if i < 2 {
+                    msg.created_at = Utc::now() - Duration::days(40);
+                }
+                repo.add_message(&msg).await.unwrap();
+            }
+        }
+
+        let policy = RetentionPolicy {
+            max_conversations: Some(2),
+            max_message_age_days: Some(30),
+            max_messages_per_conversation: Some(1),
+        };
+
+        let preview = prune(&repo, &policy, true).await.unwrap();
+        assert_eq!(preview.deleted_conversations.len(), 1);
+        assert_eq!(repo.count_conversations().await.unwrap(), 3);
+
+        let report = prune(&repo, &policy, false).await.unwrap();
+        assert_eq!(report.deleted_conversations, preview.deleted_conversations);
+        assert_eq!(report.expired_messages, 4);
+        assert_eq!(report.trimmed_messages, 2);
+        assert_eq!(repo.count_conversations().await.unwrap(), 2);
+
+        assert!(prune(&repo, &policy, false).await.unwrap().is_empty());
+        assert!(!RetentionPolicy::default().is_enabled());
+    }
+}
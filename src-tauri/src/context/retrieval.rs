@@ -0,0 +1,163 @@
+/// Hybrid retrieval - lexical (BM25) and vector rankings merged by reciprocal rank fusion
+///
+/// Embeddings find passages that paraphrase the question but miss exact names,
+/// error codes or IDs; BM25 finds those but not the paraphrases. Both rankings
+/// are merged with a weighted reciprocal rank fusion: a passage scores
+/// `weight / (RRF_K + rank)` in each list it appears in.
+///
+/// Knowledge-base chunks are stored in clear and ranked by the SQLite FTS5 index
+/// (`kb_chunks_fts`); attachment chunks are encrypted at rest, so their BM25
+/// scores are computed in memory over the chunks of the document.
+///
+/// A knowledge-base folder can also name a reranker (cross-encoder GGUF): the
+/// `RERANK_CANDIDATES` best fused chunks are then rescored by it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+/// Rank offset of the fusion, the usual value from the RRF paper
+pub const RRF_K: f32 = 60.0;
+
+/// Chunks passed to a reranker, out of which the best ones are kept
+pub const RERANK_CANDIDATES: usize = 20;
+
+/// BM25 parameters (same defaults as FTS5)
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+
+/// Weighting of the two rankings (`get_retrieval_settings`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct RetrievalSettings {
+    /// Share of the lexical ranking, from 0 (vector search only) to 1 (BM25 only)
+    pub lexical_weight: f32,
+}
+
+impl Default for RetrievalSettings {
+    fn default() -> Self {
+        Self { lexical_weight: 0.5 }
+    }
+}
+
+/// Lowercase words of a text, split like the FTS5 `unicode61` tokenizer
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// FTS5 `MATCH` expression of a question: any of its words (None without words)
+pub fn fts_query(query: &str) -> Option<String> {
+    let mut words = tokenize(query);
+    words.sort();
+    words.dedup();
+    if words.is_empty() {
+        return None;
+    }
+    Some(words.iter().map(|word| format!("\"{}\"", word)).collect::<Vec<_>>().join(" OR "))
+}
+
+/// Indices of the passages containing words of the query, best BM25 score first
+pub fn bm25_ranking(query: &str, passages: &[&str]) -> Vec<usize> {
+    let mut terms = tokenize(query);
+    terms.sort();
+    terms.dedup();
+    let documents: Vec<Vec<String>> = passages.iter().map(|p| tokenize(p)).collect();
+    if terms.is_empty() || documents.is_empty() {
+        return Vec::new();
+    }
+    let average_len = documents.iter().map(Vec::len).sum::<usize>() as f32 / documents.len() as f32;
+    // Inverse document frequency of each term, computed once for all passages
+    let idf: HashMap<&str, f32> = terms
+        .iter()
+        .map(|term| {
+            let containing = documents.iter().filter(|d| d.contains(term)).count() as f32;
+            let idf = ((documents.len() as f32 - containing + 0.5) / (containing + 0.5) + 1.0).ln();
+            (term.as_str(), idf)
+        })
+        .collect();
+
+    let mut scores: Vec<(usize, f32)> = Vec::new();
+    for (index, words) in documents.iter().enumerate() {
+        let mut frequencies: HashMap<&str, usize> = HashMap::new();
+        for word in words {
+            *frequencies.entry(word.as_str()).or_default() += 1;
+        }
+        let mut score = 0.0;
+        for term in &terms {
+            let Some(&frequency) = frequencies.get(term.as_str()) else {
+                continue;
+            };
+            let idf = idf[term.as_str()];
+            let frequency = frequency as f32;
+            let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * words.len() as f32 / average_len.max(1.0));
+            score += idf * frequency * (BM25_K1 + 1.0) / (frequency + norm);
+        }
+        if score > 0.0 {
+            scores.push((index, score));
+        }
+    }
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores.into_iter().map(|(index, _)| index).collect()
+}
+
+/// Order of `count` passages after fusing the two rankings (indices, best first)
+///
+/// Passages absent from both rankings come last, in their original order.
+pub fn fuse(count: usize, vector: &[usize], lexical: &[usize], settings: &RetrievalSettings) -> Vec<usize> {
+    let lexical_weight = settings.lexical_weight.clamp(0.0, 1.0);
+    let mut scores = vec![0.0f32; count];
+    for (ranking, weight) in [(vector, 1.0 - lexical_weight), (lexical, lexical_weight)] {
+        for (rank, &index) in ranking.iter().enumerate().filter(|(_, &index)| index < count) {
+            scores[index] += weight / (RRF_K + rank as f32 + 1.0);
+        }
+    }
+    let mut order: Vec<usize> = (0..count).collect();
+    // Stable sort: ties keep the original order
+    order.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+    order
+}
+
+/// Items sorted by reranker score, best first (`scores[i]` is the score of `items[i]`)
+pub fn by_score<T>(items: Vec<T>, scores: &[f32]) -> Vec<T> {
+    let mut scored: Vec<(f32, T)> = items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| (scores.get(i).copied().unwrap_or(f32::MIN), item))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().map(|(_, item)| item).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hybrid_ranking() {
+        let passages = [
+            "The service restarts automatically after a crash.",
+            "Error ERR-4021 means the license server is unreachable.",
+            "Restart the license service from the admin console.",
+        ];
+        assert_eq!(bm25_ranking("What does ERR-4021 mean?", &passages), vec![1]);
+        assert_eq!(fts_query("ERR-4021, err?").as_deref(), Some("\"4021\" OR \"err\""));
+        assert_eq!(fts_query("?!"), None);
+
+        // Vector search prefers the paraphrase, BM25 the exact code
+        let vector = [2, 0, 1];
+        let lexical = [1];
+        let balanced = RetrievalSettings::default();
+        assert_eq!(fuse(3, &vector, &lexical, &balanced)[0], 1);
+        let vector_only = RetrievalSettings { lexical_weight: 0.0 };
+        assert_eq!(fuse(3, &vector, &lexical, &vector_only), vec![2, 0, 1]);
+        // Without any ranking the original order is kept
+        assert_eq!(fuse(3, &[], &[], &balanced), vec![0, 1, 2]);
+
+        // The reranker has the last word
+        assert_eq!(by_score(vec!["a", "b", "c"], &[-2.5, 4.0, 0.1]), vec!["b", "c", "a"]);
+    }
+}
@@ -1,12 +1,17 @@
 /// Structures pour les sessions de conversation et les messages
 
+use super::documents::Citation;
+use super::language::Language;
+use super::models::Attachment;
+use crate::llm::{ChatMessage, ChatRole};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 use std::collections::HashMap;
 use uuid::Uuid;
 
 /// Rôle d'un message dans la conversation
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS)]
 #[serde(rename_all = "lowercase")]
 pub enum MessageRole {
     System,
@@ -15,14 +20,39 @@ pub enum MessageRole {
     Tool,
 }
 
+/// Clé des métadonnées contenant le raisonnement du modèle (`<think>...</think>`)
+pub const REASONING_METADATA: &str = "reasoning";
+
+/// Clé des métadonnées marquant une réponse interrompue par une erreur de génération
+pub const INCOMPLETE_METADATA: &str = "incomplete";
+
+/// Clé des métadonnées marquant une réponse arrêtée par `max_tokens` avant sa fin
+pub const TRUNCATED_METADATA: &str = "truncated";
+
+/// Clé des métadonnées listant les sources documentaires citées par la réponse
+pub const CITATIONS_METADATA: &str = "citations";
+
 /// Message dans une conversation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct Message {
     pub id: String,
     pub role: MessageRole,
     pub content: String,
     pub timestamp: DateTime<Utc>,
     pub metadata: HashMap<String, serde_json::Value>,
+    /// Outil appelé (message assistant) ou ayant produit le résultat (message tool)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub tool_name: Option<String>,
+    /// Identifiant reliant le résultat d'un outil à son appel
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub tool_call_id: Option<String>,
+    /// Fichiers joints au message (images pour les modèles de vision)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[ts(as = "Option<Vec<Attachment>>", optional)]
+    pub attachments: Vec<Attachment>,
 }
 
 impl Message {
@@ -33,6 +63,9 @@ impl Message {
             content,
             timestamp: Utc::now(),
             metadata: HashMap::new(),
+            tool_name: None,
+            tool_call_id: None,
+            attachments: Vec::new(),
         }
     }
 
@@ -52,14 +85,101 @@ impl Message {
         Self::new(MessageRole::Tool, content)
     }
 
+    /// Résultat d'un appel d'outil
+    pub fn tool_result(tool_name: String, tool_call_id: String, content: String) -> Self {
+        Self::tool(content).with_tool_call(tool_name, tool_call_id)
+    }
+
+    pub fn with_tool_call(mut self, tool_name: String, tool_call_id: String) -> Self {
+        self.tool_name = Some(tool_name);
+        self.tool_call_id = Some(tool_call_id);
+        self
+    }
+
+    pub fn with_attachments(mut self, attachments: Vec<Attachment>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
     pub fn with_metadata(mut self, key: String, value: serde_json::Value) -> Self {
         self.metadata.insert(key, value);
         self
     }
+
+    /// Conserve le raisonnement capturé lors de la génération, s'il y en a un
+    pub fn with_reasoning(self, reasoning: Option<String>) -> Self {
+        match reasoning {
+            Some(reasoning) => self.with_metadata(REASONING_METADATA.to_string(), reasoning.into()),
+            None => self,
+        }
+    }
+
+    /// Conserve les sources citées par la réponse, s'il y en a
+    pub fn with_citations(self, citations: &[Citation]) -> Self {
+        if citations.is_empty() {
+            return self;
+        }
+        match serde_json::to_value(citations) {
+            Ok(value) => self.with_metadata(CITATIONS_METADATA.to_string(), value),
+            Err(_) => self,
+        }
+    }
+
+    /// Marque (ou non) la réponse comme interrompue
+    pub fn with_incomplete(self, incomplete: bool) -> Self {
+        self.with_flag(INCOMPLETE_METADATA, incomplete)
+    }
+
+    /// Marque (ou non) la réponse comme tronquée par `max_tokens`
+    pub fn with_truncated(self, truncated: bool) -> Self {
+        self.with_flag(TRUNCATED_METADATA, truncated)
+    }
+
+    /// Réponse partielle pouvant être reprise avec `continue_generation`
+    pub fn is_incomplete(&self) -> bool {
+        self.flag(INCOMPLETE_METADATA)
+    }
+
+    /// Réponse pouvant être prolongée avec `continue_message`
+    pub fn is_truncated(&self) -> bool {
+        self.flag(TRUNCATED_METADATA)
+    }
+
+    fn with_flag(mut self, key: &str, value: bool) -> Self {
+        if value {
+            self.metadata.insert(key.to_string(), true.into());
+        } else {
+            self.metadata.remove(key);
+        }
+        self
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.metadata.get(key).and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+}
+
+impl From<&Message> for ChatMessage {
+    fn from(message: &Message) -> Self {
+        let role = match message.role {
+            MessageRole::System => ChatRole::System,
+            MessageRole::User => ChatRole::User,
+            MessageRole::Assistant => ChatRole::Assistant,
+            MessageRole::Tool => ChatRole::Tool,
+        };
+        let images = message
+            .attachments
+            .iter()
+            .filter(|a| a.is_image())
+            .map(|a| a.data.clone())
+            .collect();
+        ChatMessage::new(role, message.content.clone()).with_images(images)
+    }
 }
 
 /// Résumé d'une session (sans les messages) pour l'affichage dans la liste
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct SessionSummary {
     pub id: String,
     pub title: String,
@@ -68,7 +188,8 @@ pub struct SessionSummary {
 }
 
 /// Session de conversation complète avec tous les messages
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct ConversationSession {
     pub id: String,
     pub title: String,
@@ -76,6 +197,15 @@ pub struct ConversationSession {
     pub updated_at: DateTime<Utc>,
     pub messages: Vec<Message>,
     pub metadata: HashMap<String, serde_json::Value>,
+    /// Profil d'agent associé à la conversation
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    /// Espace de travail auquel la conversation appartient
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// Langue de la conversation (prompts système et génération du titre)
+    #[serde(default)]
+    pub language: Option<Language>,
 }
 
 impl ConversationSession {
@@ -88,6 +218,9 @@ impl ConversationSession {
             updated_at: now,
             messages: vec![],
             metadata: HashMap::new(),
+            agent_id: None,
+            workspace_id: None,
+            language: None,
         }
     }
     
@@ -100,6 +233,9 @@ impl ConversationSession {
             updated_at: now,
             messages: vec![],
             metadata: HashMap::new(),
+            agent_id: None,
+            workspace_id: None,
+            language: None,
         }
     }
 
@@ -137,6 +273,15 @@ mod tests {
         assert_eq!(msg.content, "Hello");
     }
 
+    #[test]
+    fn test_message_flags() {
+        let msg = Message::assistant("Once upon".to_string()).with_truncated(true);
+        assert!(msg.is_truncated() && !msg.is_incomplete());
+        let msg = msg.with_truncated(false);
+        assert!(!msg.is_truncated());
+        assert!(msg.metadata.is_empty());
+    }
+
     #[test]
     fn test_session_creation() {
         let session = ConversationSession::new("Test".to_string());
@@ -1,10 +1,21 @@
 /// Settings repository for key-value persistence
 
+use super::encryption::EncryptionParams;
+use super::retention::RetentionPolicy;
+use super::retrieval::RetrievalSettings;
+use super::sync::SyncSettings;
+use crate::llm::{BackendKind, GuardrailPolicy, ModelCapabilities, PostProcessConfig, SamplingConfig, SamplingParams, ThreadSettings};
+use crate::huggingface::{ConnectionSettings, RateLimitConfig};
+use crate::mcp::{ApiConnector, ContainerSettings, InjectionSettings, ToolPolicy};
 use anyhow::{Context, Result};
 use chrono::Utc;
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::{HashMap, HashSet};
 use tracing::{debug, info};
 
+/// Days deleted models stay in the trash by default
+const DEFAULT_MODEL_TRASH_DAYS: u64 = 30;
+
 pub struct SettingsRepository {
     pool: SqlitePool,
 }
@@ -28,8 +39,23 @@ impl SettingsRepository {
         Ok(result)
     }
     
+    /// All settings, by key
+    pub async fn list(&self) -> Result<Vec<(String, String)>> {
+        sqlx::query_as("SELECT key, value FROM settings ORDER BY key")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list settings")
+    }
+    
     /// Set a setting value (upsert)
     pub async fn set(&self, key: &str, value: &str) -> Result<()> {
+        self.upsert(key, value).await?;
+        debug!("Setting updated: {} = {}", key, value);
+        Ok(())
+    }
+    
+    /// Write a setting value without logging it (used for credentials)
+    async fn upsert(&self, key: &str, value: &str) -> Result<()> {
         let now = Utc::now().timestamp();
         
         sqlx::query(
@@ -48,7 +74,6 @@ impl SettingsRepository {
         .await
         .context("Failed to set setting")?;
         
-        debug!("Setting updated: {} = {}", key, value);
         Ok(())
     }
     
@@ -88,6 +113,24 @@ impl SettingsRepository {
         Ok(())
     }
     
+    /// Whether the last active session is reopened at startup (disabled by default)
+    pub async fn get_restore_last_session(&self) -> Result<bool> {
+        Ok(self.get("startup.restore_session").await?.and_then(|v| v.parse().ok()).unwrap_or(false))
+    }
+    
+    pub async fn set_restore_last_session(&self, enabled: bool) -> Result<()> {
+        self.set("startup.restore_session", &enabled.to_string()).await
+    }
+    
+    /// Whether the last used model is loaded at startup (disabled by default)
+    pub async fn get_load_last_model(&self) -> Result<bool> {
+        Ok(self.get("startup.load_model").await?.and_then(|v| v.parse().ok()).unwrap_or(false))
+    }
+    
+    pub async fn set_load_last_model(&self, enabled: bool) -> Result<()> {
+        self.set("startup.load_model", &enabled.to_string()).await
+    }
+    
     /// Get temperature setting
     pub async fn get_temperature(&self) -> Result<Option<f32>> {
         if let Some(val) = self.get("temperature").await? {
@@ -144,6 +187,358 @@ impl SettingsRepository {
         self.set("repeat_penalty", &repeat_penalty.to_string()).await
     }
     
+    /// Get context shifting setting
+    pub async fn get_context_shift(&self) -> Result<Option<bool>> {
+        if let Some(val) = self.get("context_shift").await? {
+            Ok(val.parse().ok())
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Set context shifting setting
+    pub async fn set_context_shift(&self, enabled: bool) -> Result<()> {
+        self.set("context_shift", &enabled.to_string()).await
+    }
+    
+    /// Get the sampling settings saved by `update_sampling_config`
+    pub async fn get_sampling_config(&self) -> Result<Option<SamplingConfig>> {
+        Ok(self.get("sampling").await?.and_then(|json| serde_json::from_str(&json).ok()))
+    }
+    
+    pub async fn set_sampling_config(&self, sampling: &SamplingConfig) -> Result<()> {
+        let json = serde_json::to_string(sampling)?;
+        self.set("sampling", &json).await
+    }
+    
+    /// Get the post-processing steps applied to local replies
+    pub async fn get_postprocess_config(&self) -> Result<PostProcessConfig> {
+        Ok(self.get("postprocess").await?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+    }
+    
+    pub async fn set_postprocess_config(&self, config: &PostProcessConfig) -> Result<()> {
+        let json = serde_json::to_string(config)?;
+        self.set("postprocess", &json).await
+    }
+    
+    /// Get the guardrails applied to replies of conversations without an agent policy
+    pub async fn get_guardrail_policy(&self) -> Result<GuardrailPolicy> {
+        Ok(self.get("guardrails").await?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+    }
+    
+    pub async fn set_guardrail_policy(&self, policy: &GuardrailPolicy) -> Result<()> {
+        let json = serde_json::to_string(policy)?;
+        self.set("guardrails", &json).await
+    }
+    
+    /// Get the thread settings (auto-detected by default)
+    pub async fn get_thread_settings(&self) -> Result<ThreadSettings> {
+        Ok(self.get("threads").await?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+    }
+    
+    pub async fn set_thread_settings(&self, settings: &ThreadSettings) -> Result<()> {
+        let json = serde_json::to_string(settings)?;
+        self.set("threads", &json).await
+    }
+    
+    /// Get prompt cache limits (max entries, max size in MB)
+    pub async fn get_prompt_cache_limits(&self) -> Result<Option<(usize, usize)>> {
+        let entries = self.get("prompt_cache_entries").await?.and_then(|v| v.parse().ok());
+        let size_mb = self.get("prompt_cache_size_mb").await?.and_then(|v| v.parse().ok());
+        Ok(entries.zip(size_mb))
+    }
+    
+    /// Set prompt cache limits
+    pub async fn set_prompt_cache_limits(&self, max_entries: usize, max_size_mb: usize) -> Result<()> {
+        self.set("prompt_cache_entries", &max_entries.to_string()).await?;
+        self.set("prompt_cache_size_mb", &max_size_mb.to_string()).await
+    }
+    
+    /// Get the API key of a remote provider ("openai", "anthropic")
+    pub async fn get_provider_api_key(&self, provider: &str) -> Result<Option<String>> {
+        self.get(&format!("api_key.{}", provider)).await
+    }
+    
+    /// Set the API key of a remote provider (value is never logged)
+    pub async fn set_provider_api_key(&self, provider: &str, api_key: &str) -> Result<()> {
+        self.upsert(&format!("api_key.{}", provider), api_key).await?;
+        info!("API key saved for provider: {}", provider);
+        Ok(())
+    }
+    
+    /// Get the custom base URL of a remote provider
+    pub async fn get_provider_base_url(&self, provider: &str) -> Result<Option<String>> {
+        self.get(&format!("base_url.{}", provider)).await
+    }
+    
+    /// Set the custom base URL of a remote provider
+    pub async fn set_provider_base_url(&self, provider: &str, base_url: &str) -> Result<()> {
+        self.set(&format!("base_url.{}", provider), base_url).await
+    }
+    
+    /// Get the backend selected for a session (local engine by default)
+    pub async fn get_session_backend(&self, session_id: &str) -> Result<BackendKind> {
+        match self.get(&format!("session_backend.{}", session_id)).await? {
+            Some(json) => serde_json::from_str(&json)
+                .context("Failed to parse session backend setting"),
+            None => Ok(BackendKind::Local),
+        }
+    }
+    
+    /// Set the backend used by a session
+    pub async fn set_session_backend(&self, session_id: &str, backend: &BackendKind) -> Result<()> {
+        let json = serde_json::to_string(backend)?;
+        self.set(&format!("session_backend.{}", session_id), &json).await
+    }
+    
+    /// Get the context size requested by a session (None = configured size)
+    pub async fn get_session_context_size(&self, session_id: &str) -> Result<Option<usize>> {
+        Ok(self.get(&format!("session_context_size.{}", session_id)).await?.and_then(|v| v.parse().ok()))
+    }
+    
+    /// Set the context size of a session (None goes back to the configured size)
+    pub async fn set_session_context_size(&self, session_id: &str, context_size: Option<usize>) -> Result<()> {
+        let key = format!("session_context_size.{}", session_id);
+        match context_size {
+            Some(size) => self.set(&key, &size.to_string()).await,
+            None => self.delete(&key).await,
+        }
+    }
+    
+    /// Get the sampling overrides of a session created from a preset
+    pub async fn get_session_sampling(&self, session_id: &str) -> Result<Option<SamplingParams>> {
+        Ok(self.get(&format!("session_sampling.{}", session_id)).await?.and_then(|json| serde_json::from_str(&json).ok()))
+    }
+    
+    pub async fn set_session_sampling(&self, session_id: &str, sampling: &SamplingParams) -> Result<()> {
+        let json = serde_json::to_string(sampling)?;
+        self.set(&format!("session_sampling.{}", session_id), &json).await
+    }
+    
+    /// Get the tools attached to a session (empty by default)
+    pub async fn get_session_tools(&self, session_id: &str) -> Result<Vec<String>> {
+        Ok(self.get(&format!("session_tools.{}", session_id)).await?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default())
+    }
+    
+    pub async fn set_session_tools(&self, session_id: &str, tools: &[String]) -> Result<()> {
+        let json = serde_json::to_string(tools)?;
+        self.set(&format!("session_tools.{}", session_id), &json).await
+    }
+    
+    /// Get the cached capabilities of a model
+    pub async fn get_model_capabilities(&self, model_name: &str) -> Result<Option<ModelCapabilities>> {
+        match self.get(&format!("model_capabilities.{}", model_name)).await? {
+            Some(json) => Ok(serde_json::from_str(&json).ok()),
+            None => Ok(None),
+        }
+    }
+    
+    /// Cache the capabilities of a model
+    pub async fn set_model_capabilities(&self, model_name: &str, capabilities: &ModelCapabilities) -> Result<()> {
+        let json = serde_json::to_string(capabilities)?;
+        self.set(&format!("model_capabilities.{}", model_name), &json).await
+    }
+    
+    /// Get the encryption parameters (None if the database is not encrypted)
+    pub async fn get_encryption_params(&self) -> Result<Option<EncryptionParams>> {
+        match self.get("encryption.params").await? {
+            Some(json) => Ok(Some(serde_json::from_str(&json).context("Invalid encryption parameters")?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Store the encryption parameters (salt and verifier, never the passphrase),
+    /// or remove them, in the transaction that rewrites the content
+    pub async fn write_encryption_params(conn: &mut SqliteConnection, params: Option<&EncryptionParams>) -> Result<()> {
+        match params {
+            Some(params) => {
+                sqlx::query(
+                    "INSERT INTO settings (key, value, updated_at) VALUES ('encryption.params', ?, ?) \
+                     ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                )
+                .bind(serde_json::to_string(params)?)
+                .bind(Utc::now().timestamp())
+                .execute(&mut *conn)
+                .await
+            }
+            None => {
+                sqlx::query("DELETE FROM settings WHERE key = 'encryption.params'")
+                    .execute(&mut *conn)
+                    .await
+            }
+        }
+        .context("Failed to write encryption parameters")?;
+        Ok(())
+    }
+    
+    /// Get the retention policy (no limits by default)
+    pub async fn get_retention_policy(&self) -> Result<RetentionPolicy> {
+        match self.get("retention_policy").await? {
+            Some(json) => Ok(serde_json::from_str(&json).unwrap_or_default()),
+            None => Ok(RetentionPolicy::default()),
+        }
+    }
+    
+    pub async fn set_retention_policy(&self, policy: &RetentionPolicy) -> Result<()> {
+        let json = serde_json::to_string(policy)?;
+        self.set("retention_policy", &json).await
+    }
+    
+    /// Batching of streamed tokens, in milliseconds (0 = disabled)
+    pub async fn get_stream_pacing_ms(&self) -> Result<u64> {
+        Ok(self.get("stream_pacing_ms").await?.and_then(|v| v.parse().ok()).unwrap_or(0))
+    }
+    
+    pub async fn set_stream_pacing_ms(&self, pacing_ms: u64) -> Result<()> {
+        self.set("stream_pacing_ms", &pacing_ms.to_string()).await
+    }
+    
+    /// Folder sync (disabled by default)
+    pub async fn get_sync_settings(&self) -> Result<SyncSettings> {
+        Ok(self.get("sync.settings").await?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+    }
+    
+    pub async fn set_sync_settings(&self, settings: &SyncSettings) -> Result<()> {
+        let json = serde_json::to_string(settings)?;
+        self.set("sync.settings", &json).await
+    }
+    
+    /// Conversations already synced from this device (to detect local deletions)
+    pub async fn get_sync_known(&self) -> Result<HashSet<String>> {
+        Ok(self.get("sync.known").await?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+    }
+    
+    pub async fn set_sync_known(&self, known: &HashSet<String>) -> Result<()> {
+        let json = serde_json::to_string(known)?;
+        self.set("sync.known", &json).await
+    }
+    
+    /// Execution limits of the tools, by tool name
+    pub async fn get_tool_policies(&self) -> Result<HashMap<String, ToolPolicy>> {
+        Ok(self.get("tool_policies").await?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+    }
+    
+    pub async fn set_tool_policies(&self, policies: &HashMap<String, ToolPolicy>) -> Result<()> {
+        let json = serde_json::to_string(policies)?;
+        self.set("tool_policies", &json).await
+    }
+    
+    /// Prompt-injection screening of tool results and documents
+    pub async fn get_injection_settings(&self) -> Result<InjectionSettings> {
+        Ok(self.get("injection.settings").await?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+    }
+    
+    pub async fn set_injection_settings(&self, settings: &InjectionSettings) -> Result<()> {
+        let json = serde_json::to_string(settings)?;
+        self.set("injection.settings", &json).await
+    }
+    
+    /// Weighting of the lexical and vector rankings of RAG retrieval
+    pub async fn get_retrieval_settings(&self) -> Result<RetrievalSettings> {
+        Ok(self.get("retrieval.settings").await?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+    }
+    
+    pub async fn set_retrieval_settings(&self, settings: &RetrievalSettings) -> Result<()> {
+        let json = serde_json::to_string(settings)?;
+        self.set("retrieval.settings", &json).await
+    }
+    
+    /// API connector tools declared by the user
+    pub async fn get_api_connectors(&self) -> Result<Vec<ApiConnector>> {
+        Ok(self.get("api_connectors").await?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+    }
+    
+    pub async fn set_api_connectors(&self, connectors: &[ApiConnector]) -> Result<()> {
+        let json = serde_json::to_string(connectors)?;
+        self.set("api_connectors", &json).await
+    }
+    
+    /// Container execution of run_code (disabled by default)
+    pub async fn get_container_settings(&self) -> Result<ContainerSettings> {
+        Ok(self.get("sandbox.container").await?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+    }
+    
+    pub async fn set_container_settings(&self, settings: &ContainerSettings) -> Result<()> {
+        let json = serde_json::to_string(settings)?;
+        self.set("sandbox.container", &json).await
+    }
+    
+    /// Whether the first-run setup was completed or skipped
+    pub async fn get_setup_completed(&self) -> Result<bool> {
+        Ok(self.get("setup.completed").await?.and_then(|v| v.parse().ok()).unwrap_or(false))
+    }
+    
+    pub async fn set_setup_completed(&self, completed: bool) -> Result<()> {
+        self.set("setup.completed", &completed.to_string()).await
+    }
+    
+    /// Whether long-term memories are extracted and recalled (disabled by default)
+    pub async fn get_memory_enabled(&self) -> Result<bool> {
+        Ok(self.get("memory.enabled").await?.and_then(|v| v.parse().ok()).unwrap_or(false))
+    }
+    
+    pub async fn set_memory_enabled(&self, enabled: bool) -> Result<()> {
+        self.set("memory.enabled", &enabled.to_string()).await
+    }
+    
+    /// Whether Hugging Face is used offline, from cached responses only
+    pub async fn get_hf_offline(&self) -> Result<bool> {
+        Ok(self.get("hf.offline").await?.and_then(|v| v.parse().ok()).unwrap_or(false))
+    }
+    
+    pub async fn set_hf_offline(&self, offline: bool) -> Result<()> {
+        self.set("hf.offline", &offline.to_string()).await
+    }
+    
+    /// Request rate and retries of the Hugging Face client
+    pub async fn get_hf_rate_limit(&self) -> Result<Option<RateLimitConfig>> {
+        Ok(self.get("hf.rate_limit").await?.and_then(|json| serde_json::from_str(&json).ok()))
+    }
+    
+    pub async fn set_hf_rate_limit(&self, config: &RateLimitConfig) -> Result<()> {
+        let json = serde_json::to_string(config)?;
+        self.set("hf.rate_limit", &json).await
+    }
+    
+    /// Days deleted models stay in the trash (0 = until emptied by hand)
+    pub async fn get_model_trash_days(&self) -> Result<u64> {
+        Ok(self.get("models.trash_days").await?.and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_MODEL_TRASH_DAYS))
+    }
+    
+    pub async fn set_model_trash_days(&self, days: u64) -> Result<()> {
+        self.set("models.trash_days", &days.to_string()).await
+    }
+    
+    /// Seconds between `system-metrics` events (0 = disabled)
+    pub async fn get_system_metrics_interval(&self) -> Result<u64> {
+        Ok(self.get("system_metrics.interval_secs").await?.and_then(|v| v.parse().ok()).unwrap_or(0))
+    }
+    
+    pub async fn set_system_metrics_interval(&self, secs: u64) -> Result<()> {
+        self.set("system_metrics.interval_secs", &secs.to_string()).await
+    }
+    
+    /// Mirror endpoint and proxy of the Hugging Face client
+    pub async fn get_hf_connection(&self) -> Result<ConnectionSettings> {
+        Ok(self.get("hf.connection").await?.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+    }
+    
+    pub async fn set_hf_connection(&self, connection: &ConnectionSettings) -> Result<()> {
+        let json = serde_json::to_string(connection)?;
+        self.set("hf.connection", &json).await
+    }
+    
+    /// Log level directives chosen by the user (None = default level)
+    pub async fn get_log_level(&self) -> Result<Option<String>> {
+        self.get("log.level").await
+    }
+    
+    pub async fn set_log_level(&self, level: &str) -> Result<()> {
+        self.set("log.level", level).await
+    }
+    
     /// List all settings
     pub async fn list_all(&self) -> Result<Vec<(String, String)>> {
         let rows = sqlx::query_as::<_, (String, String)>(
@@ -220,6 +615,52 @@ mod tests {
         // Repeat penalty
         repo.set_repeat_penalty(1.1).await.unwrap();
         assert_eq!(repo.get_repeat_penalty().await.unwrap(), Some(1.1));
+        
+        // Context shifting
+        repo.set_context_shift(false).await.unwrap();
+        assert_eq!(repo.get_context_shift().await.unwrap(), Some(false));
+        
+        // Prompt cache
+        repo.set_prompt_cache_limits(4, 256).await.unwrap();
+        assert_eq!(repo.get_prompt_cache_limits().await.unwrap(), Some((4, 256)));
+    }
+    
+    #[tokio::test]
+    async fn test_session_backend() {
+        let repo = setup_test_db().await;
+        
+        assert_eq!(repo.get_session_backend("s1").await.unwrap(), BackendKind::Local);
+        
+        let backend = BackendKind::Anthropic { model: "claude-3-5-haiku-latest".to_string() };
+        repo.set_session_backend("s1", &backend).await.unwrap();
+        assert_eq!(repo.get_session_backend("s1").await.unwrap(), backend);
+    }
+    
+    #[tokio::test]
+    async fn test_session_context_size() {
+        let repo = setup_test_db().await;
+        
+        assert_eq!(repo.get_session_context_size("s1").await.unwrap(), None);
+        
+        repo.set_session_context_size("s1", Some(8192)).await.unwrap();
+        assert_eq!(repo.get_session_context_size("s1").await.unwrap(), Some(8192));
+        
+        repo.set_session_context_size("s1", None).await.unwrap();
+        assert_eq!(repo.get_session_context_size("s1").await.unwrap(), None);
+    }
+    
+    #[tokio::test]
+    async fn test_session_settings_deleted_with_session() {
+        let repo = setup_test_db().await;
+        let conversations = crate::context::ConversationRepository::new(repo.pool.clone());
+        let session = conversations.create_conversation("Review", "model.gguf").await.unwrap();
+        
+        repo.set_session_tools(&session.id, &["git_diff".to_string()]).await.unwrap();
+        repo.set_session_sampling(&session.id, &SamplingParams::default()).await.unwrap();
+        conversations.delete_conversation(&session.id).await.unwrap();
+        
+        assert!(repo.get_session_tools(&session.id).await.unwrap().is_empty());
+        assert!(repo.get_session_sampling(&session.id).await.unwrap().is_none());
     }
     
     #[tokio::test]
@@ -0,0 +1,325 @@
+/// Partage d'une conversation dans un fichier autonome
+///
+/// Deux formats : une page HTML lisible dans n'importe quel navigateur, qui
+/// embarque aussi la conversation en JSON pour pouvoir la réimporter, et un
+/// fichier `.agentchat` chiffré par une phrase de passe (AES-256-GCM, clé dérivée
+/// par Argon2id comme pour le chiffrement de la base). Seul le contenu est
+/// partagé : l'agent, l'espace de travail et les évaluations restent locaux.
+
+use super::encryption::ContentCipher;
+use super::language::Language;
+use super::models::{Attachment, AttachmentKind};
+use super::session::{ConversationSession, Message, MessageRole, REASONING_METADATA};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+/// Version du format des conversations partagées
+pub const SHARE_VERSION: u32 = 1;
+
+/// Marqueur des fichiers `.agentchat`
+const AGENTCHAT_FORMAT: &str = "agentchat";
+
+/// Balise de la page HTML contenant la conversation en JSON
+const HTML_DATA_ID: &str = "agents-rs-conversation";
+
+const SALT_LEN: usize = 16;
+
+/// Format du fichier partagé
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum ShareFormat {
+    /// Page HTML autonome, en clair
+    Html,
+    /// Fichier `.agentchat` chiffré par une phrase de passe
+    Encrypted,
+}
+
+/// Pièce jointe embarquée dans le fichier
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedAttachment {
+    pub kind: AttachmentKind,
+    pub file_name: String,
+    pub mime_type: String,
+    /// Contenu encodé en base64
+    pub data: String,
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedMessage {
+    pub role: MessageRole,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub tool_name: Option<String>,
+    #[serde(default)]
+    pub tool_call_id: Option<String>,
+    #[serde(default)]
+    pub attachments: Vec<SharedAttachment>,
+}
+
+/// Conversation partagée, indépendante de la base d'origine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedConversation {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub language: Option<Language>,
+    pub messages: Vec<SharedMessage>,
+}
+
+/// Enveloppe d'un fichier `.agentchat`
+#[derive(Debug, Serialize, Deserialize)]
+struct AgentChatFile {
+    format: String,
+    version: u32,
+    /// Sel Argon2 encodé en base64
+    salt: String,
+    /// Conversation chiffrée (voir `ContentCipher::encrypt`)
+    payload: String,
+}
+
+impl SharedConversation {
+    /// Copie une session, avec ou sans ses pièces jointes
+    pub fn from_session(session: &ConversationSession, include_attachments: bool) -> Self {
+        let messages = session.messages.iter().map(|message| SharedMessage {
+            role: message.role.clone(),
+            content: message.content.clone(),
+            timestamp: message.timestamp,
+            metadata: message.metadata.clone(),
+            tool_name: message.tool_name.clone(),
+            tool_call_id: message.tool_call_id.clone(),
+            attachments: if include_attachments {
+                message.attachments.iter().map(|attachment| SharedAttachment {
+                    kind: attachment.kind,
+                    file_name: attachment.file_name.clone(),
+                    mime_type: attachment.mime_type.clone(),
+                    data: BASE64.encode(&attachment.data),
+                    text: attachment.text.clone(),
+                }).collect()
+            } else {
+                Vec::new()
+            },
+        }).collect();
+
+        Self {
+            version: SHARE_VERSION,
+            exported_at: Utc::now(),
+            title: session.title.clone(),
+            created_at: session.created_at,
+            language: session.language,
+            messages,
+        }
+    }
+
+    /// Messages à enregistrer dans une nouvelle session (nouveaux identifiants)
+    pub fn to_messages(&self) -> Result<Vec<Message>> {
+        self.messages.iter().map(|shared| {
+            let attachments = shared.attachments.iter().map(|attachment| {
+                let data = BASE64.decode(&attachment.data)
+                    .with_context(|| format!("Pièce jointe invalide: {}", attachment.file_name))?;
+                let file_name = attachment.file_name.clone();
+                let mime_type = attachment.mime_type.clone();
+                Ok(match (attachment.kind, &attachment.text) {
+                    (AttachmentKind::Document, Some(text)) => Attachment::document(file_name, mime_type, data, text.clone()),
+                    (kind, _) => Attachment { kind, ..Attachment::image(file_name, mime_type, data) },
+                })
+            }).collect::<Result<Vec<_>>>()?;
+
+            let mut message = Message::new(shared.role.clone(), shared.content.clone())
+                .with_attachments(attachments);
+            message.timestamp = shared.timestamp;
+            message.metadata = shared.metadata.clone();
+            message.tool_name = shared.tool_name.clone();
+            message.tool_call_id = shared.tool_call_id.clone();
+            Ok(message)
+        }).collect()
+    }
+
+    /// Contenu du fichier dans le format demandé
+    pub fn encode(&self, format: ShareFormat, passphrase: Option<&str>) -> Result<Vec<u8>> {
+        match format {
+            ShareFormat::Html => Ok(self.to_html()?.into_bytes()),
+            ShareFormat::Encrypted => {
+                let passphrase = passphrase.filter(|p| !p.is_empty())
+                    .context("Une phrase de passe est nécessaire pour chiffrer la conversation")?;
+                self.encrypt(passphrase)
+            }
+        }
+    }
+
+    /// Lit un fichier partagé, HTML ou `.agentchat` (reconnu à son contenu)
+    pub fn decode(content: &[u8], passphrase: Option<&str>) -> Result<Self> {
+        let text = std::str::from_utf8(content).context("Fichier de conversation invalide")?;
+        if text.trim_start().starts_with('{') {
+            let file: AgentChatFile = serde_json::from_str(text).context("Fichier .agentchat invalide")?;
+            if file.format != AGENTCHAT_FORMAT {
+                bail!("Fichier .agentchat invalide");
+            }
+            let passphrase = passphrase.filter(|p| !p.is_empty())
+                .context("Cette conversation est chiffrée : saisissez sa phrase de passe")?;
+            return Self::decrypt(&file, passphrase);
+        }
+        Self::from_html(text)
+    }
+
+    fn encrypt(&self, passphrase: &str) -> Result<Vec<u8>> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = ContentCipher::derive(passphrase, &salt)?;
+
+        let file = AgentChatFile {
+            format: AGENTCHAT_FORMAT.to_string(),
+            version: SHARE_VERSION,
+            salt: BASE64.encode(salt),
+            payload: cipher.encrypt(&serde_json::to_string(self)?)?,
+        };
+        Ok(serde_json::to_vec_pretty(&file)?)
+    }
+
+    fn decrypt(file: &AgentChatFile, passphrase: &str) -> Result<Self> {
+        let salt = BASE64.decode(&file.salt).context("Fichier .agentchat invalide")?;
+        let cipher = ContentCipher::derive(passphrase, &salt)?;
+        let json = cipher.decrypt(&file.payload)
+            .map_err(|_| anyhow::anyhow!("Phrase de passe erronée ou fichier corrompu"))?;
+        serde_json::from_str(&json).context("Conversation partagée invalide")
+    }
+
+    /// Page autonome : messages lisibles, images en ligne, données pour l'import
+    fn to_html(&self) -> Result<String> {
+        // `<` n'apparaît que dans les chaînes JSON : l'échapper empêche de fermer la balise
+        let data = serde_json::to_string(self)?.replace('<', "\\u003c");
+
+        let mut body = String::new();
+        for message in &self.messages {
+            let role = match message.role {
+                MessageRole::System => "system",
+                MessageRole::User => "user",
+                MessageRole::Assistant => "assistant",
+                MessageRole::Tool => "tool",
+            };
+            body.push_str(&format!(
+                "<section class=\"message {role}\">\n<header>{}<time>{}</time></header>\n",
+                message.tool_name.as_deref().map(escape_html).unwrap_or_else(|| role.to_string()),
+                message.timestamp.format("%Y-%m-%d %H:%M"),
+            ));
+            if let Some(reasoning) = message.metadata.get(REASONING_METADATA).and_then(|v| v.as_str()) {
+                body.push_str(&format!("<details><summary>Raisonnement</summary><pre>{}</pre></details>\n", escape_html(reasoning)));
+            }
+            body.push_str(&format!("<pre>{}</pre>\n", escape_html(&message.content)));
+            for attachment in &message.attachments {
+                match attachment.kind {
+                    AttachmentKind::Image => body.push_str(&format!(
+                        "<img src=\"data:{};base64,{}\" alt=\"{}\">\n",
+                        escape_html(&attachment.mime_type), attachment.data, escape_html(&attachment.file_name),
+                    )),
+                    AttachmentKind::Document => body.push_str(&format!(
+                        "<p class=\"attachment\">📎 {}</p>\n", escape_html(&attachment.file_name),
+                    )),
+                }
+            }
+            body.push_str("</section>\n");
+        }
+
+        Ok(format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 50rem; margin: 2rem auto; padding: 0 1rem; color: #222; }}
+.message {{ border-radius: 8px; padding: 0.5rem 1rem; margin: 1rem 0; background: #f4f4f5; }}
+.message.user {{ background: #e0ecff; }}
+.message.system, .message.tool {{ font-size: 0.9em; color: #555; }}
+header {{ font-weight: 600; text-transform: capitalize; display: flex; justify-content: space-between; }}
+time {{ font-weight: normal; color: #777; }}
+pre {{ white-space: pre-wrap; font-family: inherit; }}
+img {{ max-width: 100%; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{body}<script type="application/json" id="{id}">{data}</script>
+</body>
+</html>
+"#,
+            title = escape_html(&self.title),
+            id = HTML_DATA_ID,
+        ))
+    }
+
+    fn from_html(html: &str) -> Result<Self> {
+        let marker = format!("id=\"{}\">", HTML_DATA_ID);
+        let start = html.find(&marker)
+            .context("Ce fichier HTML n'est pas une conversation partagée par agents-rs")?
+            + marker.len();
+        let end = html[start..].find("</script>").context("Conversation partagée incomplète")?;
+        serde_json::from_str(&html[start..start + end]).context("Conversation partagée invalide")
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> ConversationSession {
+        let mut session = ConversationSession::new("Rust <3".to_string());
+        session.add_message(Message::user("Que fait </script> ici ?".to_string()).with_attachments(vec![
+            Attachment::image("photo.png".to_string(), "image/png".to_string(), vec![0x89, b'P', b'N', b'G']),
+        ]));
+        session.add_message(Message::assistant("Rien & tout".to_string()).with_reasoning(Some("Hmm".to_string())));
+        session
+    }
+
+    #[test]
+    fn test_html_round_trip() {
+        let shared = SharedConversation::from_session(&session(), true);
+        let html = shared.encode(ShareFormat::Html, None).unwrap();
+        let text = String::from_utf8(html.clone()).unwrap();
+        assert!(text.contains("Rust &lt;3") && text.contains("Rien &amp; tout"));
+        assert!(text.contains("data:image/png;base64,"));
+
+        let imported = SharedConversation::decode(&html, None).unwrap();
+        assert_eq!(imported, shared);
+        let messages = imported.to_messages().unwrap();
+        assert_eq!(messages[0].content, "Que fait </script> ici ?");
+        assert_eq!(messages[0].attachments[0].data, vec![0x89, b'P', b'N', b'G']);
+        assert_eq!(messages[1].metadata[REASONING_METADATA], "Hmm");
+
+        let without = SharedConversation::from_session(&session(), false);
+        assert!(without.messages[0].attachments.is_empty());
+    }
+
+    #[test]
+    fn test_encrypted_round_trip() {
+        let shared = SharedConversation::from_session(&session(), true);
+        assert!(shared.encode(ShareFormat::Encrypted, None).is_err());
+
+        let file = shared.encode(ShareFormat::Encrypted, Some("correct horse")).unwrap();
+        assert!(!String::from_utf8_lossy(&file).contains("Rien"));
+        assert_eq!(SharedConversation::decode(&file, Some("correct horse")).unwrap(), shared);
+        assert!(SharedConversation::decode(&file, Some("wrong")).is_err());
+        assert!(SharedConversation::decode(&file, None).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+/// Inference stats - time, hardware and estimated energy of each generation
+///
+/// Energy is a rough estimate from the hardware used (threads or GPU) and the
+/// generation time, meant to compare models rather than to measure consumption.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use ts_rs::TS;
+
+/// Estimated power of one busy CPU core, in watts
+const WATTS_PER_CPU_THREAD: f64 = 4.0;
+
+/// Estimated power of a GPU running inference, in watts
+const GPU_WATTS: f64 = 60.0;
+
+/// Estimated power of the rest of the machine while generating, in watts
+const BASE_WATTS: f64 = 8.0;
+
+/// One generation of a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InferenceStat {
+    pub conversation_id: String,
+    pub model_name: String,
+    /// Generated by the local engine (remote generations use no local energy)
+    pub local: bool,
+    pub tokens_generated: usize,
+    pub duration_ms: u64,
+    pub n_threads: usize,
+    pub gpu: bool,
+}
+
+impl InferenceStat {
+    /// Estimated energy used by the generation, in watt-hours
+    pub fn energy_wh(&self) -> f64 {
+        if !self.local {
+            return 0.0;
+        }
+        let watts = if self.gpu {
+            GPU_WATTS + BASE_WATTS
+        } else {
+            self.n_threads as f64 * WATTS_PER_CPU_THREAD + BASE_WATTS
+        };
+        watts * self.duration_ms as f64 / 3_600_000.0
+    }
+}
+
+/// Totals of a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SessionStats {
+    pub session_id: String,
+    #[ts(type = "number")]
+    pub generations: usize,
+    #[ts(type = "number")]
+    pub tokens_generated: usize,
+    #[ts(type = "number")]
+    pub inference_ms: u64,
+    /// Tokens per second over the local generations
+    pub tokens_per_second: f64,
+    pub estimated_energy_wh: f64,
+    /// Models used in the session, most used first
+    pub models: Vec<String>,
+}
+
+/// Persistence of the inference stats
+pub struct StatsRepository {
+    pool: SqlitePool,
+}
+
+impl StatsRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn record(&self, stat: &InferenceStat) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO inference_stats
+                (conversation_id, model_name, local, tokens_generated, duration_ms, n_threads, gpu, energy_wh, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&stat.conversation_id)
+        .bind(&stat.model_name)
+        .bind(stat.local)
+        .bind(stat.tokens_generated as i64)
+        .bind(stat.duration_ms as i64)
+        .bind(stat.n_threads as i64)
+        .bind(stat.gpu)
+        .bind(stat.energy_wh())
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to record inference stats")?;
+
+        Ok(())
+    }
+
+    pub async fn session_stats(&self, session_id: &str) -> Result<SessionStats> {
+        let totals = sqlx::query(
+            r#"
+            SELECT COUNT(*) AS generations,
+                   COALESCE(SUM(tokens_generated), 0) AS tokens,
+                   COALESCE(SUM(duration_ms), 0) AS duration,
+                   COALESCE(SUM(CASE WHEN local THEN tokens_generated ELSE 0 END), 0) AS local_tokens,
+                   COALESCE(SUM(CASE WHEN local THEN duration_ms ELSE 0 END), 0) AS local_duration,
+                   COALESCE(SUM(energy_wh), 0.0) AS energy
+            FROM inference_stats WHERE conversation_id = ?
+            "#,
+        )
+        .bind(session_id)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to compute session stats")?;
+
+        let models = sqlx::query(
+            r#"
+            SELECT model_name FROM inference_stats WHERE conversation_id = ?
+            GROUP BY model_name ORDER BY COUNT(*) DESC, model_name
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list session models")?;
+
+        let local_tokens: i64 = totals.get("local_tokens");
+        let local_duration: i64 = totals.get("local_duration");
+        let tokens_per_second = if local_duration > 0 {
+            local_tokens as f64 * 1000.0 / local_duration as f64
+        } else {
+            0.0
+        };
+
+        Ok(SessionStats {
+            session_id: session_id.to_string(),
+            generations: totals.get::<i64, _>("generations") as usize,
+            tokens_generated: totals.get::<i64, _>("tokens") as usize,
+            inference_ms: totals.get::<i64, _>("duration") as u64,
+            tokens_per_second,
+            estimated_energy_wh: totals.get("energy"),
+            models: models.iter().map(|row| row.get("model_name")).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{ConversationRepository, Database};
+
+    #[tokio::test]
+    async fn test_session_stats() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let conversation = ConversationRepository::new(db.pool().clone())
+            .create_conversation("Stats", "model.gguf")
+            .await
+            .unwrap();
+        let repo = StatsRepository::new(db.pool().clone());
+
+        let stat = InferenceStat {
+            conversation_id: conversation.id.clone(),
+            model_name: "model.gguf".to_string(),
+            local: true,
+            tokens_generated: 100,
+            duration_ms: 10_000,
+            n_threads: 8,
+            gpu: false,
+        };
+        repo.record(&stat).await.unwrap();
+        repo.record(&InferenceStat { local: false, model_name: "remote".to_string(), ..stat.clone() })
+            .await
+            .unwrap();
+
+        let stats = repo.session_stats(&conversation.id).await.unwrap();
+        assert_eq!(stats.generations, 2);
+        assert_eq!(stats.tokens_generated, 200);
+        assert_eq!(stats.tokens_per_second, 10.0);
+        // 8 threads * 4 W + 8 W for 10 s, the remote generation costs nothing locally
+        assert!((stats.estimated_energy_wh - 40.0 / 360.0).abs() < 1e-9);
+        assert_eq!(stats.models, vec!["model.gguf", "remote"]);
+    }
+}
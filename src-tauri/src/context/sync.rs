@@ -0,0 +1,586 @@
+/// Synchronisation des conversations entre appareils via un dossier partagé
+///
+/// Chaque conversation est copiée dans un fichier JSON du dossier choisi par
+/// l'utilisateur (Dropbox, Syncthing...), que l'outil de synchronisation se charge
+/// de transporter. À chaque passe, le fichier et la base sont fusionnés : les
+/// messages sont réunis par identifiant de synchronisation, le titre et la langue
+/// viennent de la copie modifiée en dernier. Une conversation supprimée laisse un
+/// fichier marqué `deleted` pour être supprimée sur les autres appareils ; un
+/// message supprimé (par l'utilisateur ou la politique de rétention) laisse son
+/// `sync_id` dans `deleted_messages`, pour ne pas être réintroduit par une autre copie.
+///
+/// Les pièces jointes, l'agent et l'espace de travail restent sur l'appareil
+/// d'origine, et les fichiers du dossier sont en clair même si la base est chiffrée.
+
+use super::language::Language;
+use super::manager::ContextManager;
+use super::models::{Conversation, StoredMessage};
+use super::repository::ConversationRepository;
+use super::settings::SettingsRepository;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{error, info, warn};
+use ts_rs::TS;
+
+/// Clé des métadonnées contenant l'identifiant d'un message commun à tous les appareils
+pub const SYNC_ID_METADATA: &str = "sync_id";
+
+/// Intervalle par défaut entre deux passes de synchronisation
+pub const DEFAULT_SYNC_INTERVAL_SECS: u64 = 5 * 60;
+
+/// Intervalle minimal, pour ne pas solliciter le disque en continu
+const MIN_SYNC_INTERVAL_SECS: u64 = 30;
+
+/// Version du format des fichiers de conversation
+const SYNC_VERSION: u32 = 1;
+
+/// Une seule passe à la fois (tâche de fond et synchronisation manuelle)
+static SYNC_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Réglages de la synchronisation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SyncSettings {
+    /// Dossier partagé (synchronisation désactivée si absent)
+    pub directory: Option<PathBuf>,
+    #[serde(default = "default_interval")]
+    #[ts(type = "number")]
+    pub interval_secs: u64,
+}
+
+fn default_interval() -> u64 {
+    DEFAULT_SYNC_INTERVAL_SECS
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self { directory: None, interval_secs: DEFAULT_SYNC_INTERVAL_SECS }
+    }
+}
+
+/// Résultat d'une passe de synchronisation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SyncReport {
+    /// Conversations copiées vers le dossier pour la première fois
+    #[ts(type = "number")]
+    pub exported: usize,
+    /// Conversations créées depuis le dossier
+    #[ts(type = "number")]
+    pub imported: usize,
+    /// Conversations modifiées des deux côtés puis fusionnées
+    #[ts(type = "number")]
+    pub merged: usize,
+    /// Suppressions appliquées ici ou transmises aux autres appareils
+    #[ts(type = "number")]
+    pub deleted: usize,
+}
+
+impl SyncReport {
+    /// La base locale a changé (le cache des sessions doit être rechargé)
+    pub fn changed_locally(&self) -> bool {
+        self.imported > 0 || self.merged > 0 || self.deleted > 0
+    }
+}
+
+/// Message tel qu'enregistré dans le dossier
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedMessage {
+    pub sync_id: String,
+    pub role: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub tool_name: Option<String>,
+    #[serde(default)]
+    pub tool_call_id: Option<String>,
+    /// Métadonnées, sans l'identifiant de synchronisation
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// Contenu d'un fichier de conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedConversation {
+    pub version: u32,
+    pub id: String,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub model_name: String,
+    #[serde(default)]
+    pub language: Option<Language>,
+    /// Conversation supprimée sur un appareil
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub messages: Vec<SyncedMessage>,
+    /// `sync_id` des messages supprimés sur un appareil, triés
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deleted_messages: Vec<String>,
+}
+
+impl SyncedConversation {
+    fn tombstone(id: String) -> Self {
+        let now = Utc::now();
+        Self {
+            version: SYNC_VERSION,
+            id,
+            title: String::new(),
+            created_at: now,
+            updated_at: now,
+            model_name: String::new(),
+            language: None,
+            deleted: true,
+            messages: Vec::new(),
+            deleted_messages: Vec::new(),
+        }
+    }
+}
+
+/// Fusionne deux copies d'une conversation
+///
+/// Les messages des deux copies sont réunis par `sync_id`. Pour un message présent
+/// des deux côtés, ainsi que pour le titre et la langue, la copie modifiée en
+/// dernier l'emporte (le dossier en cas d'égalité, pour que tous les appareils
+/// convergent vers le même fichier). Les messages supprimés sur l'une des deux
+/// copies sont retirés de la fusion.
+pub fn merge(local: &SyncedConversation, remote: &SyncedConversation) -> SyncedConversation {
+    let (newer, older) = if remote.updated_at >= local.updated_at {
+        (remote, local)
+    } else {
+        (local, remote)
+    };
+
+    let mut messages = newer.messages.clone();
+    for message in &older.messages {
+        if !messages.iter().any(|m| m.sync_id == message.sync_id) {
+            messages.push(message.clone());
+        }
+    }
+    let mut deleted_messages: Vec<String> = local.deleted_messages.iter()
+        .chain(&remote.deleted_messages)
+        .cloned()
+        .collect();
+    deleted_messages.sort();
+    deleted_messages.dedup();
+    messages.retain(|m| deleted_messages.binary_search(&m.sync_id).is_err());
+    sort_messages(&mut messages);
+
+    SyncedConversation {
+        messages,
+        deleted_messages,
+        deleted: false,
+        created_at: local.created_at.min(remote.created_at),
+        ..newer.clone()
+    }
+}
+
+/// Synchronise la base avec le dossier
+pub async fn sync_directory(
+    repository: &ConversationRepository,
+    settings: &SettingsRepository,
+    directory: &Path,
+) -> Result<SyncReport> {
+    let _guard = SYNC_LOCK.lock().await;
+    tokio::fs::create_dir_all(directory)
+        .await
+        .with_context(|| format!("Failed to create sync folder {}", directory.display()))?;
+
+    let mut remote = read_directory(directory).await?;
+    let mut known = settings.get_sync_known().await?;
+    let mut report = SyncReport::default();
+
+    for conversation in repository.list_conversations(i32::MAX, 0).await? {
+        let mut local = load_local(repository, &conversation).await?;
+        let path = conversation_path(directory, &local.id);
+        // Messages présents à la dernière passe et supprimés ici depuis
+        let synced = repository.get_synced_messages(&local.id).await?;
+        local.deleted_messages = deleted_since(&synced, &local);
+
+        match remote.remove(&local.id) {
+            None => {
+                write_conversation(&path, &local).await?;
+                remember_messages(repository, &local).await?;
+                report.exported += 1;
+            }
+            // Supprimée ailleurs : conservée seulement si modifiée ici depuis
+            Some((_, file)) if file.deleted => {
+                if local.updated_at > file.updated_at {
+                    write_conversation(&path, &local).await?;
+                    remember_messages(repository, &local).await?;
+                    report.exported += 1;
+                } else {
+                    repository.delete_conversation(&local.id).await?;
+                    report.deleted += 1;
+                }
+            }
+            Some((path, file)) => {
+                let merged = merge(&local, &file);
+                // Les suppressions reçues ne comptent que par les messages qu'elles retirent
+                let changed_locally = merged != SyncedConversation {
+                    deleted_messages: merged.deleted_messages.clone(),
+                    ..local.clone()
+                };
+                if changed_locally {
+                    apply(repository, Some(&local), &merged).await?;
+                }
+                if merged != file {
+                    write_conversation(&path, &merged).await?;
+                }
+                if changed_locally || merged != file {
+                    report.merged += 1;
+                }
+                remember_messages(repository, &merged).await?;
+            }
+        }
+        known.insert(conversation.id);
+    }
+
+    // Conversations du dossier absentes de la base
+    for (id, (path, file)) in remote {
+        if file.deleted {
+            known.insert(id);
+        } else if known.contains(&id) {
+            // Déjà synchronisée, donc supprimée ici depuis la dernière passe
+            write_conversation(&path, &SyncedConversation::tombstone(id)).await?;
+            report.deleted += 1;
+        } else {
+            apply(repository, None, &file).await?;
+            remember_messages(repository, &file).await?;
+            known.insert(id);
+            report.imported += 1;
+        }
+    }
+
+    settings.set_sync_known(&known).await?;
+    Ok(report)
+}
+
+/// Tâche de fond : synchronise à intervalle régulier quand un dossier est configuré
+pub async fn run_periodic(
+    repository: ConversationRepository,
+    settings: Arc<SettingsRepository>,
+    context_manager: Arc<RwLock<ContextManager>>,
+) {
+    loop {
+        let sync = match settings.get_sync_settings().await {
+            Ok(sync) => sync,
+            Err(e) => {
+                error!("Failed to read sync settings: {}", e);
+                SyncSettings::default()
+            }
+        };
+
+        if let Some(directory) = &sync.directory {
+            match sync_directory(&repository, &settings, directory).await {
+                Ok(report) => {
+                    if report != SyncReport::default() {
+                        info!(
+                            "Sync: {} exported, {} imported, {} merged, {} deleted",
+                            report.exported, report.imported, report.merged, report.deleted
+                        );
+                    }
+                    if report.changed_locally() {
+                        context_manager.read().await.invalidate_cache().await;
+                    }
+                }
+                Err(e) => error!("Sync with {} failed: {:#}", directory.display(), e),
+            }
+        }
+
+        let interval = sync.interval_secs.max(MIN_SYNC_INTERVAL_SECS);
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+    }
+}
+
+/// Copie d'une conversation de la base, en attribuant un `sync_id` aux nouveaux messages
+async fn load_local(repository: &ConversationRepository, conversation: &Conversation) -> Result<SyncedConversation> {
+    let mut messages = Vec::new();
+    for mut message in repository.get_messages(&conversation.id).await? {
+        let sync_id = match message.metadata.get(SYNC_ID_METADATA).and_then(|v| v.as_str()) {
+            Some(sync_id) => sync_id.to_string(),
+            None => {
+                let sync_id = uuid::Uuid::new_v4().to_string();
+                message.metadata.insert(SYNC_ID_METADATA.to_string(), sync_id.clone().into());
+                if let Some(id) = message.id {
+                    repository.update_message(id, &message.content, &message.metadata).await?;
+                }
+                sync_id
+            }
+        };
+        message.metadata.remove(SYNC_ID_METADATA);
+        messages.push(SyncedMessage {
+            sync_id,
+            role: message.role,
+            content: message.content,
+            created_at: message.created_at,
+            tool_name: message.tool_name,
+            tool_call_id: message.tool_call_id,
+            metadata: message.metadata,
+        });
+    }
+
+    sort_messages(&mut messages);
+
+    Ok(SyncedConversation {
+        version: SYNC_VERSION,
+        id: conversation.id.clone(),
+        title: conversation.title.clone(),
+        created_at: conversation.created_at,
+        updated_at: conversation.updated_at,
+        model_name: conversation.model_name.clone(),
+        language: conversation.language,
+        deleted: false,
+        messages,
+        deleted_messages: Vec::new(),
+    })
+}
+
+/// `sync_id` des messages synchronisés qui ne sont plus dans la copie locale
+fn deleted_since(synced: &HashSet<String>, local: &SyncedConversation) -> Vec<String> {
+    let present: HashSet<&str> = local.messages.iter().map(|m| m.sync_id.as_str()).collect();
+    let mut deleted: Vec<String> = synced.iter()
+        .filter(|sync_id| !present.contains(sync_id.as_str()))
+        .cloned()
+        .collect();
+    deleted.sort();
+    deleted
+}
+
+/// Retient les messages de la copie synchronisée, pour repérer les suppressions suivantes
+async fn remember_messages(repository: &ConversationRepository, conversation: &SyncedConversation) -> Result<()> {
+    let sync_ids: HashSet<String> = conversation.messages.iter().map(|m| m.sync_id.clone()).collect();
+    repository.set_synced_messages(&conversation.id, &sync_ids).await
+}
+
+/// Enregistre une copie fusionnée dans la base
+async fn apply(
+    repository: &ConversationRepository,
+    local: Option<&SyncedConversation>,
+    merged: &SyncedConversation,
+) -> Result<()> {
+    let conversation = Conversation {
+        id: merged.id.clone(),
+        title: merged.title.clone(),
+        created_at: merged.created_at,
+        updated_at: merged.updated_at,
+        model_name: merged.model_name.clone(),
+        agent_id: None,
+        workspace_id: None,
+        language: merged.language,
+    };
+    repository.save_synced_conversation(&conversation).await?;
+
+    let local_messages: HashMap<&str, &SyncedMessage> = local
+        .map(|local| local.messages.iter().map(|m| (m.sync_id.as_str(), m)).collect())
+        .unwrap_or_default();
+    let message_ids = local_message_ids(repository, &merged.id).await?;
+    for message in &merged.messages {
+        let mut metadata = message.metadata.clone();
+        metadata.insert(SYNC_ID_METADATA.to_string(), message.sync_id.clone().into());
+
+        match local_messages.get(message.sync_id.as_str()) {
+            Some(existing) if *existing == message => {}
+            Some(_) => {
+                if let Some(id) = message_ids.get(&message.sync_id) {
+                    repository.update_message(*id, &message.content, &metadata).await?;
+                }
+            }
+            None => {
+                let mut stored = StoredMessage::new(merged.id.clone(), message.role.clone(), message.content.clone())
+                    .with_tool_call(message.tool_name.clone(), message.tool_call_id.clone())
+                    .with_metadata(metadata);
+                stored.created_at = message.created_at;
+                repository.add_message(&stored).await?;
+            }
+        }
+    }
+    // Messages supprimés sur un autre appareil
+    for sync_id in &merged.deleted_messages {
+        if let Some(id) = message_ids.get(sync_id) {
+            repository.delete_message(*id).await?;
+        }
+    }
+
+    // Les ajouts ont avancé la date de modification : celle de la copie fusionnée est rétablie
+    repository.save_synced_conversation(&conversation).await
+}
+
+/// Identifiants en base des messages d'une conversation, par `sync_id`
+async fn local_message_ids(repository: &ConversationRepository, conversation_id: &str) -> Result<HashMap<String, i64>> {
+    Ok(repository.get_messages(conversation_id).await?
+        .into_iter()
+        .filter_map(|message| {
+            let sync_id = message.metadata.get(SYNC_ID_METADATA)?.as_str()?.to_string();
+            Some((sync_id, message.id?))
+        })
+        .collect())
+}
+
+/// Ordre commun à tous les appareils (les messages d'une même seconde sont
+/// départagés par leur `sync_id`), pour comparer les copies
+fn sort_messages(messages: &mut [SyncedMessage]) {
+    messages.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.sync_id.cmp(&b.sync_id)));
+}
+
+fn conversation_path(directory: &Path, id: &str) -> PathBuf {
+    directory.join(format!("{}.json", id))
+}
+
+/// Fichiers de conversation du dossier, par identifiant (les fichiers illisibles
+/// sont ignorés : l'outil de synchronisation peut être en train de les écrire)
+async fn read_directory(directory: &Path) -> Result<HashMap<String, (PathBuf, SyncedConversation)>> {
+    let mut conversations = HashMap::new();
+    let mut entries = tokio::fs::read_dir(directory)
+        .await
+        .with_context(|| format!("Failed to read sync folder {}", directory.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let conversation = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|json| serde_json::from_str::<SyncedConversation>(&json).map_err(anyhow::Error::from));
+        match conversation {
+            Ok(conversation) => {
+                conversations.insert(conversation.id.clone(), (path, conversation));
+            }
+            Err(e) => warn!("Skipping sync file {}: {}", path.display(), e),
+        }
+    }
+    Ok(conversations)
+}
+
+/// Écrit par un fichier temporaire, pour ne jamais exposer un fichier tronqué
+async fn write_conversation(path: &Path, conversation: &SyncedConversation) -> Result<()> {
+    let temporary = path.with_extension("json.tmp");
+    tokio::fs::write(&temporary, serde_json::to_string_pretty(conversation)?)
+        .await
+        .with_context(|| format!("Failed to write {}", temporary.display()))?;
+    tokio::fs::rename(&temporary, path)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+
+    async fn device() -> (ConversationRepository, SettingsRepository) {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        (ConversationRepository::new(db.pool().clone()), SettingsRepository::new(db.pool().clone()))
+    }
+
+    async fn contents(repository: &ConversationRepository, id: &str) -> Vec<String> {
+        repository.get_messages(id).await.unwrap().into_iter().map(|m| m.content).collect()
+    }
+
+    fn message(sync_id: &str, content: &str, at: i64) -> SyncedMessage {
+        SyncedMessage {
+            sync_id: sync_id.to_string(),
+            role: "user".to_string(),
+            content: content.to_string(),
+            created_at: DateTime::from_timestamp(at, 0).unwrap(),
+            tool_name: None,
+            tool_call_id: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_merge_last_writer_wins() {
+        let mut local = SyncedConversation::tombstone("c".to_string());
+        local.deleted = false;
+        local.title = "Local".to_string();
+        local.updated_at = DateTime::from_timestamp(100, 0).unwrap();
+        local.messages = vec![message("a", "Bonjour", 1), message("b", "Local edit", 2)];
+
+        let mut remote = local.clone();
+        remote.title = "Remote".to_string();
+        remote.updated_at = DateTime::from_timestamp(200, 0).unwrap();
+        remote.messages = vec![message("a", "Bonjour", 1), message("b", "Remote edit", 2), message("c", "Suite", 3)];
+        local.messages.push(message("d", "Entre-temps", 4));
+
+        let merged = merge(&local, &remote);
+        assert_eq!(merged.title, "Remote");
+        assert_eq!(merged.updated_at, remote.updated_at);
+        let contents: Vec<&str> = merged.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["Bonjour", "Remote edit", "Suite", "Entre-temps"]);
+
+        // La même fusion depuis l'autre appareil donne le même résultat
+        assert_eq!(merge(&remote, &merged), merged);
+
+        // Un message supprimé d'un côté n'est pas réintroduit par l'autre
+        local.deleted_messages = vec!["c".to_string()];
+        let merged = merge(&local, &remote);
+        assert!(merged.messages.iter().all(|m| m.sync_id != "c"));
+        assert_eq!(merged.deleted_messages, vec!["c"]);
+        assert_eq!(merge(&remote, &merged), merged);
+    }
+
+    #[tokio::test]
+    async fn test_sync_between_devices() {
+        let dir = std::env::temp_dir().join(format!("agents-rs-sync-{}", uuid::Uuid::new_v4()));
+        let (repo_a, settings_a) = device().await;
+        let (repo_b, settings_b) = device().await;
+
+        let conversation = repo_a.create_conversation("Rust", "model").await.unwrap();
+        repo_a.add_message(&StoredMessage::new(conversation.id.clone(), "user".to_string(), "Bonjour".to_string())).await.unwrap();
+
+        let report = sync_directory(&repo_a, &settings_a, &dir).await.unwrap();
+        assert_eq!(report.exported, 1);
+        let report = sync_directory(&repo_b, &settings_b, &dir).await.unwrap();
+        assert_eq!(report.imported, 1);
+        assert_eq!(contents(&repo_b, &conversation.id).await, vec!["Bonjour"]);
+
+        // Un message ajouté sur chaque appareil : les deux sont conservés
+        repo_a.add_message(&StoredMessage::new(conversation.id.clone(), "user".to_string(), "Depuis A".to_string())).await.unwrap();
+        repo_b.add_message(&StoredMessage::new(conversation.id.clone(), "user".to_string(), "Depuis B".to_string())).await.unwrap();
+        sync_directory(&repo_a, &settings_a, &dir).await.unwrap();
+        sync_directory(&repo_b, &settings_b, &dir).await.unwrap();
+        sync_directory(&repo_a, &settings_a, &dir).await.unwrap();
+        for repo in [&repo_a, &repo_b] {
+            let mut messages = contents(repo, &conversation.id).await;
+            messages.sort();
+            assert_eq!(messages, vec!["Bonjour", "Depuis A", "Depuis B"]);
+        }
+
+        // Rien de nouveau : la passe suivante ne change rien
+        assert_eq!(sync_directory(&repo_b, &settings_b, &dir).await.unwrap(), SyncReport::default());
+
+        // Un message supprimé sur A n'est pas réintroduit par B
+        let first = repo_a.get_messages(&conversation.id).await.unwrap().into_iter()
+            .find(|m| m.content == "Bonjour")
+            .unwrap();
+        repo_a.delete_message(first.id.unwrap()).await.unwrap();
+        sync_directory(&repo_a, &settings_a, &dir).await.unwrap();
+        sync_directory(&repo_b, &settings_b, &dir).await.unwrap();
+        sync_directory(&repo_a, &settings_a, &dir).await.unwrap();
+        for repo in [&repo_a, &repo_b] {
+            let mut messages = contents(repo, &conversation.id).await;
+            messages.sort();
+            assert_eq!(messages, vec!["Depuis A", "Depuis B"]);
+        }
+        assert_eq!(sync_directory(&repo_b, &settings_b, &dir).await.unwrap(), SyncReport::default());
+        assert_eq!(sync_directory(&repo_a, &settings_a, &dir).await.unwrap(), SyncReport::default());
+
+        // Une suppression sur B est transmise à A
+        repo_b.delete_conversation(&conversation.id).await.unwrap();
+        assert_eq!(sync_directory(&repo_b, &settings_b, &dir).await.unwrap().deleted, 1);
+        assert_eq!(sync_directory(&repo_a, &settings_a, &dir).await.unwrap().deleted, 1);
+        assert!(repo_a.get_conversation(&conversation.id).await.unwrap().is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+/// Journal d'audit des outils - événements de sécurité liés aux appels d'outils
+///
+/// Chaque signalement d'injection de prompt (résultat d'outil, extrait de
+/// document ou de base de connaissances) y est conservé avec la décision prise :
+/// contenu encadré, accepté ou écarté par l'utilisateur.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use ts_rs::TS;
+
+/// Entrée du journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ToolAuditEntry {
+    #[ts(type = "number")]
+    pub id: i64,
+    /// Session, exécution de tâche ou client MCP à l'origine de l'appel
+    pub scope: String,
+    /// Origine du contenu (`tool:fetch_url`, `document:notes.pdf`, `knowledge_base:/docs`)
+    pub source: String,
+    /// Nature de l'événement (`prompt_injection`)
+    pub event: String,
+    /// `tagged`, `accepted` ou `rejected`
+    pub decision: String,
+    /// Détails de l'événement (règles et extraits)
+    pub details: serde_json::Value,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[ts(type = "number")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Persistance du journal d'audit
+pub struct ToolAuditRepository {
+    pool: SqlitePool,
+}
+
+impl ToolAuditRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn record(
+        &self,
+        scope: &str,
+        source: &str,
+        event: &str,
+        decision: &str,
+        details: &serde_json::Value,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO tool_audit (scope, source, event, decision, details, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(scope)
+        .bind(source)
+        .bind(event)
+        .bind(decision)
+        .bind(details.to_string())
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to record tool audit entry")?;
+        Ok(())
+    }
+
+    /// Entrées les plus récentes d'abord
+    pub async fn list(&self, limit: usize) -> Result<Vec<ToolAuditEntry>> {
+        let rows = sqlx::query(
+            "SELECT id, scope, source, event, decision, details, created_at FROM tool_audit ORDER BY id DESC LIMIT ?",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list tool audit entries")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ToolAuditEntry {
+                id: row.get("id"),
+                scope: row.get("scope"),
+                source: row.get("source"),
+                event: row.get("event"),
+                decision: row.get("decision"),
+                details: serde_json::from_str(row.get::<&str, _>("details")).unwrap_or_default(),
+                created_at: DateTime::from_timestamp(row.get("created_at"), 0).unwrap_or_else(Utc::now),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+
+    async fn setup_test_db() -> ToolAuditRepository {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        ToolAuditRepository::new(db.pool().clone())
+    }
+
+    #[tokio::test]
+    async fn test_record_and_list() {
+        let repo = setup_test_db().await;
+        let details = serde_json::json!({ "findings": [{ "rule": "role_markers", "excerpt": "[INST]" }] });
+        repo.record("session-1", "tool:fetch_url", "prompt_injection", "tagged", &details).await.unwrap();
+        repo.record("mcp:abc", "tool:fetch_url", "prompt_injection", "rejected", &details).await.unwrap();
+
+        let entries = repo.list(10).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].scope, "mcp:abc");
+        assert_eq!(entries[0].decision, "rejected");
+        assert_eq!(entries[1].details, details);
+        assert_eq!(repo.list(1).await.unwrap().len(), 1);
+    }
+}
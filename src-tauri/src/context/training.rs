@@ -0,0 +1,200 @@
+/// Fine-tuning datasets exported from conversations
+///
+/// Each selected conversation becomes one JSONL line in the OpenAI chat format
+/// (`{"messages": [{"role", "content"}]}`) or the ShareGPT format
+/// (`{"conversations": [{"from", "value"}]}`). Tool calls and tool results are
+/// left out: they only make sense with the tool definitions of this app.
+
+use super::models::{Conversation, StoredMessage};
+use super::repository::ConversationRepository;
+use crate::llm::GuardrailPolicy;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use ts_rs::TS;
+
+/// Line format of the dataset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum TrainingFormat {
+    #[default]
+    OpenAI,
+    ShareGPT,
+}
+
+/// Conversations to export (every conversation by default)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct TrainingFilter {
+    /// Only these conversations (empty = all)
+    pub conversation_ids: Vec<String>,
+    pub workspace_id: Option<String>,
+    pub agent_id: Option<String>,
+    /// Conversations updated since this date
+    pub since: Option<DateTime<Utc>>,
+    /// Conversations created before this date
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl TrainingFilter {
+    pub fn matches(&self, conversation: &Conversation) -> bool {
+        (self.conversation_ids.is_empty() || self.conversation_ids.contains(&conversation.id))
+            && self.workspace_id.as_ref().is_none_or(|id| conversation.workspace_id.as_ref() == Some(id))
+            && self.agent_id.as_ref().is_none_or(|id| conversation.agent_id.as_ref() == Some(id))
+            && self.since.is_none_or(|since| conversation.updated_at >= since)
+            && self.until.is_none_or(|until| conversation.created_at < until)
+    }
+}
+
+/// Transformations applied to the exported messages
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct TrainingOptions {
+    pub strip_system_prompts: bool,
+    /// Mask email addresses and API keys
+    pub anonymize: bool,
+    /// Skip conversations identical to one already exported
+    pub dedupe: bool,
+}
+
+/// What an export wrote
+#[derive(Debug, Clone, Default, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct TrainingExport {
+    #[ts(type = "number")]
+    pub examples: usize,
+    /// Conversations without a user message and an answer
+    #[ts(type = "number")]
+    pub skipped_empty: usize,
+    #[ts(type = "number")]
+    pub skipped_duplicates: usize,
+}
+
+/// Role/content pairs kept for training
+fn training_messages(messages: &[StoredMessage], options: &TrainingOptions) -> Vec<(String, String)> {
+    let anonymizer = GuardrailPolicy { redact_pii: true, ..Default::default() };
+    messages
+        .iter()
+        .filter(|m| matches!(m.role.as_str(), "system" | "user" | "assistant"))
+        // Assistant messages calling a tool
+        .filter(|m| m.tool_name.is_none())
+        .filter(|m| !(options.strip_system_prompts && m.role == "system"))
+        .map(|m| {
+            let content = if options.anonymize { anonymizer.apply(&m.content).text } else { m.content.clone() };
+            (m.role.clone(), content)
+        })
+        .collect()
+}
+
+/// One dataset line
+fn training_record(messages: &[(String, String)], format: TrainingFormat) -> serde_json::Value {
+    match format {
+        TrainingFormat::OpenAI => serde_json::json!({
+            "messages": messages
+                .iter()
+                .map(|(role, content)| serde_json::json!({ "role": role, "content": content }))
+                .collect::<Vec<_>>(),
+        }),
+        TrainingFormat::ShareGPT => serde_json::json!({
+            "conversations": messages
+                .iter()
+                .map(|(role, content)| {
+                    let from = match role.as_str() {
+                        "user" => "human",
+                        "assistant" => "gpt",
+                        other => other,
+                    };
+                    serde_json::json!({ "from": from, "value": content })
+                })
+                .collect::<Vec<_>>(),
+        }),
+    }
+}
+
+/// Build the JSONL dataset of the conversations matching `filter`
+pub async fn export_training_data(
+    repository: &ConversationRepository,
+    filter: &TrainingFilter,
+    format: TrainingFormat,
+    options: &TrainingOptions,
+) -> Result<(String, TrainingExport)> {
+    let mut conversations: Vec<Conversation> = repository
+        .list_conversations(i32::MAX, 0)
+        .await?
+        .into_iter()
+        .filter(|c| filter.matches(c))
+        .collect();
+    conversations.sort_by_key(|c| c.created_at);
+
+    let mut dataset = String::new();
+    let mut report = TrainingExport::default();
+    let mut seen = HashSet::new();
+    for conversation in &conversations {
+        let messages = training_messages(&repository.get_messages(&conversation.id).await?, options);
+        let has_exchange = messages.iter().any(|(role, _)| role == "user")
+            && messages.iter().any(|(role, _)| role == "assistant");
+        if !has_exchange {
+            report.skipped_empty += 1;
+            continue;
+        }
+        let line = training_record(&messages, format).to_string();
+        if options.dedupe && !seen.insert(line.clone()) {
+            report.skipped_duplicates += 1;
+            continue;
+        }
+        dataset.push_str(&line);
+        dataset.push('\n');
+        report.examples += 1;
+    }
+
+    Ok((dataset, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+
+    #[tokio::test]
+    async fn test_export_training_data() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = ConversationRepository::new(db.pool().clone());
+
+        for _ in 0..2 {
+            let conv = repo.create_conversation("Support", "test-model").await.unwrap();
+            for (role, content) in [
+                ("system", "You are helpful."),
+                ("user", "Write to alice@example.com"),
+                ("assistant", "Done."),
+            ] {
+                repo.add_message(&StoredMessage::new(conv.id.clone(), role.to_string(), content.to_string()))
+                    .await
+                    .unwrap();
+            }
+        }
+        repo.create_conversation("Empty", "test-model").await.unwrap();
+
+        let options = TrainingOptions { strip_system_prompts: true, anonymize: true, dedupe: true };
+        let (dataset, report) = export_training_data(&repo, &TrainingFilter::default(), TrainingFormat::ShareGPT, &options)
+            .await
+            .unwrap();
+        assert_eq!(report, TrainingExport { examples: 1, skipped_empty: 1, skipped_duplicates: 1 });
+        let record: serde_json::Value = serde_json::from_str(dataset.lines().next().unwrap()).unwrap();
+        assert_eq!(record["conversations"][0]["from"], "human");
+        assert_eq!(record["conversations"][0]["value"], "Write to [email]");
+        assert_eq!(record["conversations"][1]["from"], "gpt");
+
+        let (dataset, report) =
+            export_training_data(&repo, &TrainingFilter::default(), TrainingFormat::OpenAI, &TrainingOptions::default())
+                .await
+                .unwrap();
+        assert_eq!(report.examples, 2);
+        let record: serde_json::Value = serde_json::from_str(dataset.lines().next().unwrap()).unwrap();
+        assert_eq!(record["messages"][0]["role"], "system");
+    }
+}
@@ -0,0 +1,201 @@
+/// User profiles - one SQLite database per person using the app
+///
+/// Each profile has its own conversations, agents and settings (the settings
+/// live in the database) while the models directory stays shared. The default
+/// profile keeps the historical `conversations.db`; the others are stored in
+/// `profiles/<name>/`. The list and the active profile are kept in
+/// `profiles.json` next to them, read at startup to pick the database.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+/// Profile using the database of the app before profiles existed
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Longest profile name (also used as a directory name)
+const MAX_NAME_LEN: usize = 32;
+
+const REGISTRY_FILE: &str = "profiles.json";
+
+/// A profile, as listed to the UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct UserProfile {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// Profile the app is running with
+    #[serde(default)]
+    pub active: bool,
+}
+
+/// Content of `profiles.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Registry {
+    active: Option<String>,
+    profiles: Vec<UserProfile>,
+}
+
+/// Profiles stored in the data directory of the app
+#[derive(Debug, Clone)]
+pub struct ProfileStore {
+    directory: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self { directory: directory.into() }
+    }
+
+    /// Data directory of the application
+    pub fn default_location() -> Option<Self> {
+        directories::ProjectDirs::from("com", "agents-rs", "AgentsRS")
+            .map(|dirs| Self::new(dirs.data_dir()))
+    }
+
+    /// All profiles, the default one first
+    pub fn list(&self) -> Result<Vec<UserProfile>> {
+        let registry = self.read()?;
+        let active = registry.active.as_deref().unwrap_or(DEFAULT_PROFILE);
+        let mut profiles = registry.profiles;
+        if !profiles.iter().any(|p| p.name == DEFAULT_PROFILE) {
+            profiles.insert(0, UserProfile {
+                name: DEFAULT_PROFILE.to_string(),
+                created_at: DateTime::UNIX_EPOCH,
+                active: false,
+            });
+        }
+        for profile in &mut profiles {
+            profile.active = profile.name == active;
+        }
+        Ok(profiles)
+    }
+
+    /// Add a profile; its database is created the first time it is used
+    pub fn create(&self, name: &str) -> Result<UserProfile> {
+        validate_name(name)?;
+        let mut registry = self.read()?;
+        if name == DEFAULT_PROFILE || registry.profiles.iter().any(|p| p.name == name) {
+            bail!("Profile {} already exists", name);
+        }
+
+        std::fs::create_dir_all(self.profile_directory(name))
+            .with_context(|| format!("Failed to create the directory of profile {}", name))?;
+        let profile = UserProfile { name: name.to_string(), created_at: Utc::now(), active: false };
+        registry.profiles.push(profile.clone());
+        self.write(&registry)?;
+        Ok(profile)
+    }
+
+    /// Profile selected for the next startup (the default one if none was chosen)
+    pub fn active(&self) -> Result<String> {
+        Ok(self.read()?.active.unwrap_or_else(|| DEFAULT_PROFILE.to_string()))
+    }
+
+    /// Fails for an invalid name or a profile that was never created
+    pub fn ensure_exists(&self, name: &str) -> Result<()> {
+        if name != DEFAULT_PROFILE {
+            validate_name(name)?;
+        }
+        if !self.list()?.iter().any(|p| p.name == name) {
+            bail!("Unknown profile: {}", name);
+        }
+        Ok(())
+    }
+
+    pub fn set_active(&self, name: &str) -> Result<()> {
+        self.ensure_exists(name)?;
+        let mut registry = self.read()?;
+        registry.active = Some(name.to_string());
+        self.write(&registry)
+    }
+
+    /// Database of a profile, None for the default one (historical location)
+    pub fn database_url(&self, name: &str) -> Option<String> {
+        (name != DEFAULT_PROFILE).then(|| {
+            format!("sqlite://{}", self.profile_directory(name).join("conversations.db").display())
+        })
+    }
+
+    fn profile_directory(&self, name: &str) -> PathBuf {
+        self.directory.join("profiles").join(name)
+    }
+
+    fn registry_path(&self) -> PathBuf {
+        self.directory.join(REGISTRY_FILE)
+    }
+
+    fn read(&self) -> Result<Registry> {
+        let path = self.registry_path();
+        if !path.exists() {
+            return Ok(Registry::default());
+        }
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid profile list: {}", path.display()))
+    }
+
+    fn write(&self, registry: &Registry) -> Result<()> {
+        std::fs::create_dir_all(&self.directory).context("Failed to create the data directory")?;
+        write_atomic(&self.registry_path(), &serde_json::to_string_pretty(registry)?)
+    }
+}
+
+/// Letters, digits, `-` and `_`, so the name is a valid directory on every platform
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        bail!("Profile names have 1 to {} characters", MAX_NAME_LEN);
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        bail!("Profile names only contain letters, digits, '-' and '_'");
+    }
+    Ok(())
+}
+
+/// Write through a temporary file, so a crash never leaves a truncated list
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let temporary = path.with_extension("json.tmp");
+    std::fs::write(&temporary, content)
+        .with_context(|| format!("Failed to write {}", temporary.display()))?;
+    std::fs::rename(&temporary, path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let dir = std::env::temp_dir().join(format!("agents-rs-profiles-{}", uuid::Uuid::new_v4()));
+        let store = ProfileStore::new(&dir);
+
+        // Before any profile is created, only the default one exists and is active
+        let profiles = store.list().unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles[0].active && profiles[0].name == DEFAULT_PROFILE);
+        assert_eq!(store.database_url(DEFAULT_PROFILE), None);
+
+        store.create("alice").unwrap();
+        assert!(store.create("alice").is_err());
+        assert!(store.create(DEFAULT_PROFILE).is_err());
+        assert!(store.create("../bob").is_err());
+        assert!(store.create("").is_err());
+        assert!(store.set_active("bob").is_err());
+        // Names asked on the command line are checked the same way
+        assert!(store.ensure_exists("../alice").is_err());
+        assert!(store.ensure_exists("bob").is_err());
+        store.ensure_exists("alice").unwrap();
+        store.ensure_exists(DEFAULT_PROFILE).unwrap();
+
+        store.set_active("alice").unwrap();
+        assert_eq!(store.active().unwrap(), "alice");
+        let names: Vec<(String, bool)> = store.list().unwrap().into_iter().map(|p| (p.name, p.active)).collect();
+        assert_eq!(names, vec![("default".to_string(), false), ("alice".to_string(), true)]);
+        let url = store.database_url("alice").unwrap();
+        assert!(url.ends_with("conversations.db") && url.contains("alice"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,206 @@
+/// Project workspaces - a directory sessions and tasks are scoped to
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use std::path::{Path, PathBuf};
+use tracing::info;
+use ts_rs::TS;
+
+/// Maximum number of top-level entries listed in the workspace context
+const MAX_CONTEXT_ENTRIES: usize = 50;
+
+/// A project directory with its default agent and document index
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    /// Directory file tools are limited to in this workspace's sessions
+    pub root_path: String,
+    /// Agent used for sessions created without an explicit agent
+    pub default_agent_id: Option<String>,
+    /// Knowledge-base folder whose excerpts are added to the workspace's sessions
+    pub index_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Workspace {
+    pub fn new(name: String, root_path: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            root_path,
+            default_agent_id: None,
+            index_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        Path::new(&self.root_path)
+    }
+
+    /// System prompt describing the workspace: name, root and top-level entries
+    pub async fn context_prompt(&self) -> String {
+        let mut entries = Vec::new();
+        if let Ok(mut dir) = tokio::fs::read_dir(self.root()).await {
+            while let Ok(Some(entry)) = dir.next_entry().await {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with('.') {
+                    continue;
+                }
+                let is_dir = entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false);
+                entries.push(if is_dir { format!("{}/", name) } else { name });
+            }
+        }
+        entries.sort();
+
+        let mut prompt = format!(
+            "You are working in the project \"{}\", located at {}. \
+             File paths are relative to this directory and tools cannot access files outside of it.",
+            self.name, self.root_path
+        );
+        if !entries.is_empty() {
+            let more = entries.len().saturating_sub(MAX_CONTEXT_ENTRIES);
+            entries.truncate(MAX_CONTEXT_ENTRIES);
+            prompt.push_str("\n\nTop-level entries:\n");
+            prompt.push_str(&entries.join("\n"));
+            if more > 0 {
+                prompt.push_str(&format!("\n... and {} more", more));
+            }
+        }
+        prompt
+    }
+}
+
+/// Check that a workspace root is an existing directory, returns its absolute path
+pub fn validate_root(root_path: &str) -> Result<PathBuf> {
+    let root = Path::new(root_path);
+    if !root.is_dir() {
+        anyhow::bail!("Workspace root is not a directory: {}", root_path);
+    }
+    root.canonicalize().context("Failed to resolve workspace root")
+}
+
+/// Persistence of workspaces
+pub struct WorkspaceRepository {
+    pool: SqlitePool,
+}
+
+impl WorkspaceRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert or update a workspace
+    pub async fn save(&self, workspace: &Workspace) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO workspaces (id, name, root_path, default_agent_id, index_id, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                root_path = excluded.root_path,
+                default_agent_id = excluded.default_agent_id,
+                index_id = excluded.index_id,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&workspace.id)
+        .bind(&workspace.name)
+        .bind(&workspace.root_path)
+        .bind(&workspace.default_agent_id)
+        .bind(&workspace.index_id)
+        .bind(workspace.created_at.timestamp())
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save workspace")?;
+
+        info!("Saved workspace: {} ({})", workspace.name, workspace.id);
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Workspace>> {
+        let row = sqlx::query("SELECT * FROM workspaces WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch workspace")?;
+
+        Ok(row.map(|row| Self::from_row(&row)))
+    }
+
+    pub async fn list(&self) -> Result<Vec<Workspace>> {
+        let rows = sqlx::query("SELECT * FROM workspaces ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list workspaces")?;
+
+        Ok(rows.iter().map(Self::from_row).collect())
+    }
+
+    /// Delete a workspace (its conversations are kept, outside of any workspace)
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM workspaces WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete workspace")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn from_row(row: &SqliteRow) -> Workspace {
+        let created_at: i64 = row.get("created_at");
+        let updated_at: i64 = row.get("updated_at");
+
+        Workspace {
+            id: row.get("id"),
+            name: row.get("name"),
+            root_path: row.get("root_path"),
+            default_agent_id: row.get("default_agent_id"),
+            index_id: row.get("index_id"),
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_else(Utc::now),
+            updated_at: DateTime::from_timestamp(updated_at, 0).unwrap_or_else(Utc::now),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+
+    #[tokio::test]
+    async fn test_workspace_crud_and_context() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = WorkspaceRepository::new(db.pool().clone());
+
+        let root = std::env::temp_dir().join(format!("agents-rs-workspace-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("README.md"), "# Demo").unwrap();
+
+        let workspace = Workspace::new("Demo".to_string(), root.to_string_lossy().to_string());
+        repo.save(&workspace).await.unwrap();
+        assert_eq!(repo.list().await.unwrap().len(), 1);
+
+        let loaded = repo.get(&workspace.id).await.unwrap().unwrap();
+        let prompt = loaded.context_prompt().await;
+        assert!(prompt.contains("\"Demo\""));
+        assert!(prompt.contains("README.md\nsrc/"));
+
+        assert!(repo.delete(&workspace.id).await.unwrap());
+        assert!(repo.get(&workspace.id).await.unwrap().is_none());
+        assert!(validate_root(&root.join("README.md").to_string_lossy()).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,226 @@
+/// Erreurs structurées renvoyées par les commandes Tauri
+///
+/// Sérialisées sous la forme `{ "kind": "model_not_found", ... }` pour que le
+/// frontend puisse distinguer les cas et proposer une action adaptée.
+
+use crate::llm::gguf::{UnsupportedGgufVersion, SUPPORTED_VERSIONS};
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Erreur commune à toutes les commandes
+#[derive(Debug, Clone, thiserror::Error, Serialize, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export)]
+pub enum AppError {
+    #[error("Model not found: {model}")]
+    ModelNotFound { model: String },
+
+    #[error("No model is loaded")]
+    NoModelLoaded,
+
+    #[error("Failed to load model: {message}")]
+    ModelLoadFailed { message: String },
+
+    /// Fichier de modèle illisible (téléchargement interrompu, fichier abîmé)
+    #[error("Model file is corrupt: {message}")]
+    ModelCorrupt {
+        model: String,
+        message: String,
+        /// Action proposée à l'utilisateur
+        suggestion: String,
+    },
+
+    /// Format GGUF plus récent que la version de llama.cpp intégrée
+    #[error("Model {model} uses GGUF version {gguf_version}, this build supports up to version {max_supported_version}")]
+    BackendUpgradeRequired {
+        model: String,
+        gguf_version: u32,
+        max_supported_version: u32,
+        /// Action proposée à l'utilisateur
+        suggestion: String,
+    },
+
+    #[error("Generation failed: {message}")]
+    GenerationFailed {
+        message: String,
+        /// Texte déjà généré avant l'échec
+        partial_text: Option<String>,
+        /// Message assistant incomplet enregistré avec ce texte (`continue_generation`)
+        #[ts(type = "number | null")]
+        message_id: Option<i64>,
+    },
+
+    /// Prompt trop long pour la fenêtre de contexte, détecté avant le décodage
+    #[error("Prompt ({prompt_tokens} tokens) does not fit in the context window ({n_ctx} tokens)")]
+    ContextOverflow {
+        #[ts(type = "number")]
+        prompt_tokens: usize,
+        #[ts(type = "number")]
+        n_ctx: usize,
+    },
+
+    #[error("Session not found: {session_id}")]
+    SessionNotFound { session_id: String },
+
+    #[error("Database error: {message}")]
+    DatabaseError { message: String },
+
+    #[error("The database is encrypted and locked")]
+    DatabaseLocked,
+
+    #[error("Network error: {message}")]
+    NetworkError { status: Option<u16>, message: String },
+
+    /// Mode hors ligne activé, sans réponse en cache pour la requête
+    #[error("Offline mode is enabled")]
+    Offline,
+
+    #[error("Missing credentials for {provider}")]
+    MissingCredentials { provider: String },
+
+    #[error("Invalid input: {message}")]
+    InvalidInput { message: String },
+
+    #[error("{message}")]
+    Internal { message: String },
+}
+
+/// Résultat des commandes Tauri
+pub type AppResult<T> = std::result::Result<T, AppError>;
+
+impl AppError {
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        AppError::InvalidInput { message: message.into() }
+    }
+
+    /// Fichier de modèle refusé : format trop récent pour llama.cpp, sinon fichier abîmé
+    pub fn model_corrupt(model: impl Into<String>, error: anyhow::Error) -> Self {
+        if let Some(unsupported) = error.downcast_ref::<UnsupportedGgufVersion>().filter(|e| e.is_newer()) {
+            return AppError::BackendUpgradeRequired {
+                model: model.into(),
+                gguf_version: unsupported.version,
+                max_supported_version: *SUPPORTED_VERSIONS.end(),
+                suggestion: "Update the application to load this model".to_string(),
+            };
+        }
+        AppError::ModelCorrupt {
+            model: model.into(),
+            message: format!("{:#}", error),
+            suggestion: "Delete the file and download the model again".to_string(),
+        }
+    }
+
+    /// Erreur de chargement de modèle (conserve une erreur typée sous-jacente)
+    pub fn model_load(error: anyhow::Error) -> Self {
+        match Self::find_typed(&error) {
+            Some(typed) => typed,
+            None => AppError::ModelLoadFailed { message: format!("{:#}", error) },
+        }
+    }
+
+    /// Erreur de génération (conserve une erreur typée sous-jacente)
+    pub fn generation(error: anyhow::Error) -> Self {
+        match Self::find_typed(&error) {
+            Some(typed) => typed,
+            None => AppError::GenerationFailed {
+                message: format!("{:#}", error),
+                partial_text: None,
+                message_id: None,
+            },
+        }
+    }
+
+    /// Recherche une erreur connue dans la chaîne d'une erreur anyhow
+    fn find_typed(error: &anyhow::Error) -> Option<Self> {
+        for cause in error.chain() {
+            if let Some(app_error) = cause.downcast_ref::<AppError>() {
+                return Some(app_error.clone());
+            }
+            if let Some(http) = cause.downcast_ref::<HttpStatusError>() {
+                return Some(AppError::NetworkError {
+                    status: Some(http.status.as_u16()),
+                    message: format!("{:#}", error),
+                });
+            }
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                return Some(AppError::NetworkError {
+                    status: e.status().map(|s| s.as_u16()),
+                    message: format!("{:#}", error),
+                });
+            }
+            if cause.downcast_ref::<sqlx::Error>().is_some() {
+                return Some(AppError::DatabaseError { message: format!("{:#}", error) });
+            }
+        }
+        None
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::find_typed(&error).unwrap_or_else(|| AppError::Internal {
+            message: format!("{:#}", error),
+        })
+    }
+}
+
+/// Réponse HTTP en erreur d'une API distante (HuggingFace, fournisseurs LLM)
+#[derive(Debug, thiserror::Error)]
+#[error("{context}: HTTP {status} - {body}")]
+pub struct HttpStatusError {
+    pub context: String,
+    pub status: reqwest::StatusCode,
+    pub body: String,
+}
+
+impl HttpStatusError {
+    pub fn new(context: impl Into<String>, status: reqwest::StatusCode, body: impl Into<String>) -> Self {
+        Self {
+            context: context.into(),
+            status,
+            body: body.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_error_serialization() {
+        let error = AppError::GenerationFailed {
+            message: "decode failed".to_string(),
+            partial_text: Some("Hello".to_string()),
+            message_id: Some(42),
+        };
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["kind"], "generation_failed");
+        assert_eq!(json["partial_text"], "Hello");
+        assert_eq!(json["message_id"], 42);
+    }
+
+    #[test]
+    fn test_typed_error_survives_context() {
+        let error: anyhow::Error = Err::<(), _>(AppError::NoModelLoaded)
+            .context("Error while generating")
+            .unwrap_err();
+        assert!(matches!(AppError::generation(error), AppError::NoModelLoaded));
+
+        let overflow: anyhow::Error = AppError::ContextOverflow { prompt_tokens: 5000, n_ctx: 4096 }.into();
+        let json = serde_json::to_value(AppError::generation(overflow)).unwrap();
+        assert_eq!(json["kind"], "context_overflow");
+        assert_eq!(json["prompt_tokens"], 5000);
+
+        let http: anyhow::Error = HttpStatusError::new("Hugging Face API error", reqwest::StatusCode::NOT_FOUND, "").into();
+        assert!(matches!(AppError::from(http), AppError::NetworkError { status: Some(404), .. }));
+
+        let newer: anyhow::Error = crate::llm::gguf::UnsupportedGgufVersion { version: 4 }.into();
+        let json = serde_json::to_value(AppError::model_corrupt("tiny.gguf", newer)).unwrap();
+        assert_eq!(json["kind"], "backend_upgrade_required");
+        assert_eq!(json["gguf_version"], 4);
+        let older: anyhow::Error = crate::llm::gguf::UnsupportedGgufVersion { version: 1 }.into();
+        assert!(matches!(AppError::model_corrupt("old.gguf", older), AppError::ModelCorrupt { .. }));
+    }
+}
@@ -0,0 +1,12 @@
+/// Evaluation harness for prompts, agents and models
+///
+/// A suite is a list of prompts with checks on the answer (substring, regex,
+/// JSON output and fields). Running a suite against a model stores the outcome
+/// and latency of every case in the `eval_runs` / `eval_results` tables, with
+/// the aggregate score used to compare models, agents and sampling settings.
+
+pub mod models;
+pub mod repository;
+
+pub use models::{EvalCase, EvalCaseResult, EvalCheck, EvalProgress, EvalRun, EvalSuite};
+pub use repository::EvalRepository;
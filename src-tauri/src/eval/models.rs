@@ -0,0 +1,267 @@
+/// Evaluation suites, their checks and run results
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ts_rs::TS;
+
+/// Condition a model output must satisfy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EvalCheck {
+    Contains {
+        text: String,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    NotContains {
+        text: String,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    Regex {
+        pattern: String,
+    },
+    /// The output is JSON, optionally matching a JSON Schema
+    Json {
+        #[serde(default)]
+        schema: Option<Value>,
+    },
+    /// The JSON output has `value` at `pointer` (`/answer/0`)
+    JsonField {
+        pointer: String,
+        value: Value,
+    },
+}
+
+impl EvalCheck {
+    /// Why `output` fails this check, `None` when it passes
+    pub fn failure(&self, output: &str) -> Option<String> {
+        match self {
+            Self::Contains { text, case_sensitive } => {
+                (!contains(output, text, *case_sensitive)).then(|| format!("missing '{}'", text))
+            }
+            Self::NotContains { text, case_sensitive } => {
+                contains(output, text, *case_sensitive).then(|| format!("unexpected '{}'", text))
+            }
+            Self::Regex { pattern } => match regex::Regex::new(pattern) {
+                Ok(regex) => (!regex.is_match(output)).then(|| format!("does not match {}", pattern)),
+                Err(e) => Some(format!("invalid pattern {}: {}", pattern, e)),
+            },
+            Self::Json { schema } => match parse_json(output) {
+                None => Some("not valid JSON".to_string()),
+                Some(json) => schema.as_ref().and_then(|schema| {
+                    crate::mcp::schema::validate_arguments("output", schema, &json)
+                        .err()
+                        .map(|e| e.to_string())
+                }),
+            },
+            Self::JsonField { pointer, value } => match parse_json(output) {
+                None => Some("not valid JSON".to_string()),
+                Some(json) => match json.pointer(pointer) {
+                    Some(found) if found == value => None,
+                    Some(found) => Some(format!("{} is {}, expected {}", pointer, found, value)),
+                    None => Some(format!("{} is missing", pointer)),
+                },
+            },
+        }
+    }
+}
+
+fn contains(output: &str, text: &str, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        output.contains(text)
+    } else {
+        output.to_lowercase().contains(&text.to_lowercase())
+    }
+}
+
+/// JSON of the output, possibly wrapped in prose or a code block
+fn parse_json(output: &str) -> Option<Value> {
+    if let Ok(json) = serde_json::from_str(output.trim()) {
+        return Some(json);
+    }
+    let start = output.find(['{', '['])?;
+    let end = output.rfind(['}', ']'])?;
+    (start < end).then(|| serde_json::from_str(&output[start..=end]).ok()).flatten()
+}
+
+/// One prompt of a suite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EvalCase {
+    pub name: String,
+    pub prompt: String,
+    /// Replaces the agent's system prompt for this case
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Every check must pass (no check = the model only has to answer)
+    #[serde(default)]
+    pub checks: Vec<EvalCheck>,
+}
+
+/// Named set of cases run against models to compare them
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EvalSuite {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub cases: Vec<EvalCase>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl EvalSuite {
+    pub fn new(name: String, description: String, cases: Vec<EvalCase>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            description,
+            cases,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Outcome of one case
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EvalCaseResult {
+    pub case_name: String,
+    pub passed: bool,
+    #[ts(type = "number")]
+    pub latency_ms: u64,
+    #[ts(type = "number")]
+    pub tokens_generated: usize,
+    pub output: String,
+    /// Failed checks, or the generation error
+    pub failures: Vec<String>,
+}
+
+impl EvalCaseResult {
+    /// Run the checks of `case` on `output`
+    pub fn evaluate(case: &EvalCase, output: String, latency_ms: u64, tokens_generated: usize) -> Self {
+        let failures: Vec<String> = case.checks.iter().filter_map(|check| check.failure(&output)).collect();
+        Self {
+            case_name: case.name.clone(),
+            passed: failures.is_empty(),
+            latency_ms,
+            tokens_generated,
+            output,
+            failures,
+        }
+    }
+
+    /// Case whose generation failed
+    pub fn error(case: &EvalCase, error: String, latency_ms: u64) -> Self {
+        Self {
+            case_name: case.name.clone(),
+            passed: false,
+            latency_ms,
+            tokens_generated: 0,
+            output: String::new(),
+            failures: vec![error],
+        }
+    }
+}
+
+/// A suite run against one model, with its aggregate scores
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EvalRun {
+    pub id: String,
+    pub suite_id: String,
+    pub model: String,
+    pub agent_id: Option<String>,
+    #[ts(type = "number")]
+    pub passed: usize,
+    #[ts(type = "number")]
+    pub total: usize,
+    /// Share of passed cases (0-1)
+    pub score: f64,
+    #[ts(type = "number")]
+    pub avg_latency_ms: u64,
+    /// Per-case results (not loaded when listing runs)
+    pub results: Vec<EvalCaseResult>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl EvalRun {
+    pub fn new(suite_id: String, model: String, agent_id: Option<String>, results: Vec<EvalCaseResult>) -> Self {
+        let total = results.len();
+        let passed = results.iter().filter(|r| r.passed).count();
+        let latency: u64 = results.iter().map(|r| r.latency_ms).sum();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            suite_id,
+            model,
+            agent_id,
+            passed,
+            total,
+            score: if total == 0 { 0.0 } else { passed as f64 / total as f64 },
+            avg_latency_ms: latency.checked_div(total as u64).unwrap_or(0),
+            results,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Progress sent to the UI while a suite runs
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalProgress {
+    pub suite_id: String,
+    pub model: String,
+    pub case_name: String,
+    /// Cases completed for this model
+    pub completed: usize,
+    pub total: usize,
+    pub passed: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval_checks() {
+        let output = "Sure! ```json\n{\"city\": \"Paris\", \"tags\": [\"capital\"]}\n```";
+        let check = |json: Value| serde_json::from_value::<EvalCheck>(json).unwrap();
+
+        assert_eq!(check(serde_json::json!({"type": "contains", "text": "paris"})).failure(output), None);
+        assert!(check(serde_json::json!({"type": "contains", "text": "paris", "case_sensitive": true}))
+            .failure(output)
+            .is_some());
+        assert!(check(serde_json::json!({"type": "not_contains", "text": "Sure"})).failure(output).is_some());
+        assert_eq!(check(serde_json::json!({"type": "regex", "pattern": "\"tags\":\\s*\\["})).failure(output), None);
+        assert_eq!(check(serde_json::json!({"type": "json_field", "pointer": "/tags/0", "value": "capital"})).failure(output), None);
+
+        let schema = serde_json::json!({"type": "object", "required": ["country"]});
+        let failure = check(serde_json::json!({"type": "json", "schema": schema})).failure(output).unwrap();
+        assert!(failure.contains("country"));
+        assert!(check(serde_json::json!({"type": "json"})).failure("no json here").is_some());
+
+        let case = EvalCase {
+            name: "capital".to_string(),
+            prompt: "Capital of France as JSON".to_string(),
+            system_prompt: None,
+            checks: vec![check(serde_json::json!({"type": "contains", "text": "Paris"}))],
+        };
+        let run = EvalRun::new(
+            "suite".to_string(),
+            "model.gguf".to_string(),
+            None,
+            vec![
+                EvalCaseResult::evaluate(&case, output.to_string(), 100, 12),
+                EvalCaseResult::evaluate(&case, "Lyon".to_string(), 300, 1),
+            ],
+        );
+        assert_eq!((run.passed, run.total, run.avg_latency_ms), (1, 2, 200));
+        assert_eq!(run.score, 0.5);
+        assert_eq!(run.results[1].failures, vec!["missing 'Paris'"]);
+    }
+}
@@ -0,0 +1,241 @@
+/// Persistence of evaluation suites and run results
+
+use super::models::{EvalCaseResult, EvalRun, EvalSuite};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use tracing::info;
+
+pub struct EvalRepository {
+    pool: SqlitePool,
+}
+
+impl EvalRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert or update a suite
+    pub async fn save_suite(&self, suite: &EvalSuite) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO eval_suites (id, name, description, cases, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                description = excluded.description,
+                cases = excluded.cases,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&suite.id)
+        .bind(&suite.name)
+        .bind(&suite.description)
+        .bind(serde_json::to_string(&suite.cases)?)
+        .bind(suite.created_at.timestamp())
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save eval suite")?;
+
+        info!("Saved eval suite: {} ({})", suite.name, suite.id);
+        Ok(())
+    }
+
+    pub async fn get_suite(&self, id: &str) -> Result<Option<EvalSuite>> {
+        let row = sqlx::query("SELECT * FROM eval_suites WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch eval suite")?;
+
+        row.map(|row| Self::suite_from_row(&row)).transpose()
+    }
+
+    pub async fn list_suites(&self) -> Result<Vec<EvalSuite>> {
+        let rows = sqlx::query("SELECT * FROM eval_suites ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list eval suites")?;
+
+        rows.iter().map(Self::suite_from_row).collect()
+    }
+
+    /// Delete a suite and its runs
+    pub async fn delete_suite(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM eval_suites WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete eval suite")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Store a run and its per-case results
+    pub async fn save_run(&self, run: &EvalRun) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO eval_runs (id, suite_id, model, agent_id, passed, total, avg_latency_ms, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&run.id)
+        .bind(&run.suite_id)
+        .bind(&run.model)
+        .bind(&run.agent_id)
+        .bind(run.passed as i64)
+        .bind(run.total as i64)
+        .bind(run.avg_latency_ms as i64)
+        .bind(run.created_at.timestamp())
+        .execute(&mut *tx)
+        .await
+        .context("Failed to save eval run")?;
+
+        for (position, result) in run.results.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO eval_results (run_id, position, case_name, passed, latency_ms, tokens_generated, output, failures)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&run.id)
+            .bind(position as i64)
+            .bind(&result.case_name)
+            .bind(result.passed)
+            .bind(result.latency_ms as i64)
+            .bind(result.tokens_generated as i64)
+            .bind(&result.output)
+            .bind(serde_json::to_string(&result.failures)?)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to save eval result")?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// A run with its per-case results
+    pub async fn get_run(&self, id: &str) -> Result<Option<EvalRun>> {
+        let row = sqlx::query("SELECT * FROM eval_runs WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch eval run")?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let mut run = Self::run_from_row(&row);
+        let rows = sqlx::query("SELECT * FROM eval_results WHERE run_id = ? ORDER BY position")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch eval results")?;
+        run.results = rows.iter().map(Self::result_from_row).collect::<Result<_>>()?;
+
+        Ok(Some(run))
+    }
+
+    /// Runs of a suite (without their results), most recent first
+    pub async fn list_runs(&self, suite_id: &str) -> Result<Vec<EvalRun>> {
+        let rows = sqlx::query("SELECT * FROM eval_runs WHERE suite_id = ? ORDER BY created_at DESC")
+            .bind(suite_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list eval runs")?;
+
+        Ok(rows.iter().map(Self::run_from_row).collect())
+    }
+
+    fn suite_from_row(row: &SqliteRow) -> Result<EvalSuite> {
+        let cases: String = row.get("cases");
+        let created_at: i64 = row.get("created_at");
+        let updated_at: i64 = row.get("updated_at");
+
+        Ok(EvalSuite {
+            id: row.get("id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            cases: serde_json::from_str(&cases).context("Invalid eval cases")?,
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_else(Utc::now),
+            updated_at: DateTime::from_timestamp(updated_at, 0).unwrap_or_else(Utc::now),
+        })
+    }
+
+    fn run_from_row(row: &SqliteRow) -> EvalRun {
+        let passed = row.get::<i64, _>("passed") as usize;
+        let total = row.get::<i64, _>("total") as usize;
+        let created_at: i64 = row.get("created_at");
+
+        EvalRun {
+            id: row.get("id"),
+            suite_id: row.get("suite_id"),
+            model: row.get("model"),
+            agent_id: row.get("agent_id"),
+            passed,
+            total,
+            score: if total == 0 { 0.0 } else { passed as f64 / total as f64 },
+            avg_latency_ms: row.get::<i64, _>("avg_latency_ms") as u64,
+            results: vec![],
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_else(Utc::now),
+        }
+    }
+
+    fn result_from_row(row: &SqliteRow) -> Result<EvalCaseResult> {
+        let failures: String = row.get("failures");
+
+        Ok(EvalCaseResult {
+            case_name: row.get("case_name"),
+            passed: row.get("passed"),
+            latency_ms: row.get::<i64, _>("latency_ms") as u64,
+            tokens_generated: row.get::<i64, _>("tokens_generated") as usize,
+            output: row.get("output"),
+            failures: serde_json::from_str(&failures).context("Invalid eval failures")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+    use crate::eval::EvalCase;
+
+    #[tokio::test]
+    async fn test_eval_suites_and_runs() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = EvalRepository::new(db.pool().clone());
+
+        let case = EvalCase {
+            name: "greeting".to_string(),
+            prompt: "Say hello".to_string(),
+            system_prompt: None,
+            checks: vec![],
+        };
+        let suite = EvalSuite::new("Smoke".to_string(), String::new(), vec![case.clone()]);
+        repo.save_suite(&suite).await.unwrap();
+        assert_eq!(repo.get_suite(&suite.id).await.unwrap().unwrap().cases, vec![case.clone()]);
+
+        let run = EvalRun::new(
+            suite.id.clone(),
+            "model.gguf".to_string(),
+            None,
+            vec![EvalCaseResult::evaluate(&case, "Hello!".to_string(), 42, 3)],
+        );
+        repo.save_run(&run).await.unwrap();
+
+        let runs = repo.list_runs(&suite.id).await.unwrap();
+        assert_eq!((runs.len(), runs[0].passed, runs[0].score), (1, 1, 1.0));
+        assert!(runs[0].results.is_empty());
+        assert_eq!(repo.get_run(&run.id).await.unwrap().unwrap().results, run.results);
+
+        // Runs are deleted with their suite
+        assert!(repo.delete_suite(&suite.id).await.unwrap());
+        assert!(repo.get_run(&run.id).await.unwrap().is_none());
+    }
+}
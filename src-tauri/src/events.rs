@@ -0,0 +1,637 @@
+/// Événements émis vers l'interface
+///
+/// Chaque événement a sa structure sérialisée et un nom fixe (`AppEvent::NAME`).
+/// Tous passent par `emit_event`, et le schéma JSON de leur contenu est publié par
+/// la commande `list_event_schemas` pour générer les types TypeScript.
+///
+/// Les schémas s'en tiennent au sous-ensemble validé par `mcp::schema` : un test
+/// vérifie qu'un exemple de chaque événement les respecte.
+
+use crate::context::KbIndexProgress;
+use crate::error::AppError;
+use crate::eval::EvalProgress;
+use crate::llm::{GuardrailTrigger, ModelsChanged, QueueUpdate, SwitchStatus, SystemMetrics};
+use crate::mcp::{ApprovalRequest, ToolOutputChunk};
+use crate::tasks::{ScheduleEvent, TaskEvent};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tracing::debug;
+use ts_rs::TS;
+
+/// Contenu d'un événement
+pub trait AppEvent: Serialize + Clone {
+    /// Nom écouté par le frontend (`listen("llm-token", ...)`)
+    const NAME: &'static str;
+    const DESCRIPTION: &'static str;
+
+    /// Schéma JSON du contenu
+    fn schema() -> Value;
+}
+
+/// Émet un événement vers toutes les fenêtres ; un échec est seulement journalisé
+pub fn emit_event<E: AppEvent>(app: &AppHandle, event: &E) {
+    if let Err(e) = app.emit(E::NAME, event) {
+        debug!("Événement {} non émis: {}", E::NAME, e);
+    }
+}
+
+/// Destination des événements d'un échange : les fenêtres de l'application, ou
+/// une fonction recevant le nom et le contenu de chaque événement (ligne de commande)
+#[derive(Clone)]
+pub enum EventSink {
+    App(AppHandle),
+    Callback(EventCallback),
+}
+
+/// Fonction recevant le nom et le contenu d'un événement
+pub type EventCallback = Arc<dyn Fn(&str, Value) + Send + Sync>;
+
+impl EventSink {
+    pub fn emit<E: AppEvent>(&self, event: &E) {
+        match self {
+            EventSink::App(app) => emit_event(app, event),
+            EventSink::Callback(callback) => match serde_json::to_value(event) {
+                Ok(value) => callback(E::NAME, value),
+                Err(e) => debug!("Événement {} non émis: {}", E::NAME, e),
+            },
+        }
+    }
+}
+
+impl From<AppHandle> for EventSink {
+    fn from(app: AppHandle) -> Self {
+        EventSink::App(app)
+    }
+}
+
+/// Description d'un événement pour la génération des types du frontend
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct EventSchema {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub schema: Value,
+}
+
+impl EventSchema {
+    fn of<E: AppEvent>() -> Self {
+        Self { name: E::NAME, description: E::DESCRIPTION, schema: E::schema() }
+    }
+}
+
+/// Tous les événements émis par l'application
+pub fn event_schemas() -> Vec<EventSchema> {
+    vec![
+        EventSchema::of::<DownloadProgress>(),
+        EventSchema::of::<LlmToken>(),
+        EventSchema::of::<LlmReasoning>(),
+        EventSchema::of::<ContextTruncated>(),
+        EventSchema::of::<ContextOverflow>(),
+        EventSchema::of::<GuardrailTriggered>(),
+        EventSchema::of::<SessionTitle>(),
+        EventSchema::of::<StartupRestored>(),
+        EventSchema::of::<ReplayProgress>(),
+        EventSchema::of::<SwitchStatus>(),
+        EventSchema::of::<QueueUpdate>(),
+        EventSchema::of::<ApprovalRequest>(),
+        EventSchema::of::<ToolOutputChunk>(),
+        EventSchema::of::<ScheduleEvent>(),
+        EventSchema::of::<TaskEvent>(),
+        EventSchema::of::<EvalProgress>(),
+        EventSchema::of::<ModelsChanged>(),
+        EventSchema::of::<SystemMetrics>(),
+        EventSchema::of::<KbIndexProgress>(),
+    ]
+}
+
+/// Objet sans autre propriété que celles listées
+fn object(properties: Value, required: &[&str]) -> Value {
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false
+    })
+}
+
+fn nullable(kind: &str) -> Value {
+    json!({"type": [kind, "null"]})
+}
+
+/// Erreur structurée (`AppError`), sérialisée avec son `kind`
+fn app_error() -> Value {
+    json!({"type": ["object", "null"], "properties": {"kind": {"type": "string"}}, "required": ["kind"]})
+}
+
+// ==================== Événements des commandes ====================
+
+/// Progression d'un téléchargement de modèle (Hub ou URL)
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub filename: String,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    /// Pourcentage (0 si la taille est inconnue)
+    pub progress: u32,
+}
+
+impl DownloadProgress {
+    pub fn new(filename: &str, downloaded: u64, total: Option<u64>) -> Self {
+        let progress = total
+            .filter(|&total| total > 0)
+            .map(|total| (downloaded as f64 / total as f64 * 100.0) as u32)
+            .unwrap_or(0);
+        Self { repo_id: None, url: None, filename: filename.to_string(), downloaded, total, progress }
+    }
+}
+
+impl AppEvent for DownloadProgress {
+    const NAME: &'static str = "download-progress";
+    const DESCRIPTION: &'static str = "Progression d'un téléchargement de modèle";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "repo_id": {"type": "string"},
+                "url": {"type": "string"},
+                "filename": {"type": "string"},
+                "downloaded": {"type": "integer", "minimum": 0},
+                "total": nullable("integer"),
+                "progress": {"type": "integer", "minimum": 0, "maximum": 100}
+            }),
+            &["filename", "downloaded", "progress"],
+        )
+    }
+}
+
+/// Morceau de réponse généré en streaming
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmToken {
+    pub session_id: String,
+    pub chunk: String,
+}
+
+impl AppEvent for LlmToken {
+    const NAME: &'static str = "llm-token";
+    const DESCRIPTION: &'static str = "Morceau de la réponse en cours de génération";
+
+    fn schema() -> Value {
+        object(json!({"session_id": {"type": "string"}, "chunk": {"type": "string"}}), &["session_id", "chunk"])
+    }
+}
+
+/// Étape du raisonnement diffusé en streaming
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningPhase {
+    Start,
+    Delta,
+    End,
+}
+
+/// Raisonnement (`<think>`) généré en streaming ; `chunk` seulement en phase `delta`
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmReasoning {
+    pub session_id: String,
+    pub phase: ReasoningPhase,
+    pub chunk: Option<String>,
+}
+
+impl AppEvent for LlmReasoning {
+    const NAME: &'static str = "llm-reasoning";
+    const DESCRIPTION: &'static str = "Raisonnement du modèle en cours de génération (début, morceau, fin)";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "session_id": {"type": "string"},
+                "phase": {"type": "string", "enum": ["start", "delta", "end"]},
+                "chunk": nullable("string")
+            }),
+            &["session_id", "phase"],
+        )
+    }
+}
+
+/// Début de la conversation retiré du contexte par le décalage de contexte
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextTruncated {
+    pub session_id: String,
+    pub truncated_tokens: usize,
+}
+
+impl AppEvent for ContextTruncated {
+    const NAME: &'static str = "context-truncated";
+    const DESCRIPTION: &'static str = "Tokens les plus anciens retirés du contexte pendant la génération";
+
+    fn schema() -> Value {
+        object(
+            json!({"session_id": {"type": "string"}, "truncated_tokens": {"type": "integer", "minimum": 0}}),
+            &["session_id", "truncated_tokens"],
+        )
+    }
+}
+
+/// Prompt trop long pour la fenêtre de contexte
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextOverflow {
+    pub session_id: String,
+    pub prompt_tokens: usize,
+    pub n_ctx: usize,
+    pub suggestion: String,
+}
+
+impl AppEvent for ContextOverflow {
+    const NAME: &'static str = "context-overflow";
+    const DESCRIPTION: &'static str = "Prompt plus long que la fenêtre de contexte, avec une action proposée";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "session_id": {"type": "string"},
+                "prompt_tokens": {"type": "integer", "minimum": 0},
+                "n_ctx": {"type": "integer", "minimum": 0},
+                "suggestion": {"type": "string"}
+            }),
+            &["session_id", "prompt_tokens", "n_ctx", "suggestion"],
+        )
+    }
+}
+
+/// Réponse modifiée par les garde-fous
+#[derive(Debug, Clone, Serialize)]
+pub struct GuardrailTriggered {
+    pub session_id: String,
+    pub triggers: Vec<GuardrailTrigger>,
+}
+
+impl AppEvent for GuardrailTriggered {
+    const NAME: &'static str = "guardrail-triggered";
+    const DESCRIPTION: &'static str = "Règles des garde-fous appliquées à une réponse";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "session_id": {"type": "string"},
+                "triggers": {
+                    "type": "array",
+                    "items": object(
+                        json!({
+                            "rule": {"type": "string", "enum": ["keyword", "pattern", "email", "secret_key", "max_length"]},
+                            "count": {"type": "integer", "minimum": 1}
+                        }),
+                        &["rule", "count"],
+                    )
+                }
+            }),
+            &["session_id", "triggers"],
+        )
+    }
+}
+
+/// Titre généré pour une session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionTitle {
+    pub session_id: String,
+    pub title: String,
+}
+
+impl AppEvent for SessionTitle {
+    const NAME: &'static str = "session-title";
+    const DESCRIPTION: &'static str = "Titre généré à partir du premier échange d'une session";
+
+    fn schema() -> Value {
+        object(json!({"session_id": {"type": "string"}, "title": {"type": "string"}}), &["session_id", "title"])
+    }
+}
+
+/// Session et modèle rétablis au démarrage
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupRestored {
+    pub session_id: Option<String>,
+    pub model: Option<String>,
+    pub model_error: Option<AppError>,
+}
+
+impl AppEvent for StartupRestored {
+    const NAME: &'static str = "startup-restored";
+    const DESCRIPTION: &'static str = "Dernière session et dernier modèle rétablis au démarrage";
+
+    fn schema() -> Value {
+        object(
+            json!({"session_id": nullable("string"), "model": nullable("string"), "model_error": app_error()}),
+            &[],
+        )
+    }
+}
+
+/// Tour rejoué par `replay_session`
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayProgress {
+    pub session_id: String,
+    pub source_session_id: String,
+    pub turn: usize,
+    pub turns: usize,
+}
+
+impl AppEvent for ReplayProgress {
+    const NAME: &'static str = "replay-progress";
+    const DESCRIPTION: &'static str = "Progression du rejeu d'une conversation avec un autre modèle";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "session_id": {"type": "string"},
+                "source_session_id": {"type": "string"},
+                "turn": {"type": "integer", "minimum": 0},
+                "turns": {"type": "integer", "minimum": 0}
+            }),
+            &["session_id", "source_session_id", "turn", "turns"],
+        )
+    }
+}
+
+// ==================== Événements des autres modules ====================
+
+impl AppEvent for SwitchStatus {
+    const NAME: &'static str = "model-switch-progress";
+    const DESCRIPTION: &'static str = "Étape d'un changement de modèle";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "task_id": {"type": "string"},
+                "model_name": {"type": "string"},
+                "stage": {
+                    "type": "string",
+                    "enum": ["queued", "unloading", "loading", "warming_up", "completed", "failed"]
+                },
+                "error": app_error(),
+                "started_at": {"type": "string"},
+                "updated_at": {"type": "string"}
+            }),
+            &["task_id", "model_name", "stage", "started_at", "updated_at"],
+        )
+    }
+}
+
+impl AppEvent for QueueUpdate {
+    const NAME: &'static str = "generation-queue";
+    const DESCRIPTION: &'static str = "Position d'une requête dans la file des générations (0 = en cours)";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "request_id": {"type": "integer", "minimum": 0},
+                "session_id": nullable("string"),
+                "position": {"type": "integer", "minimum": 0}
+            }),
+            &["request_id", "position"],
+        )
+    }
+}
+
+impl AppEvent for ApprovalRequest {
+    const NAME: &'static str = "tool-approval-request";
+    const DESCRIPTION: &'static str = "Appel d'outil en attente de confirmation (`approve_tool_call`)";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "call_id": {"type": "string"},
+                "scope": {"type": "string"},
+                "tool_name": {"type": "string"},
+                "arguments": {}
+            }),
+            &["call_id", "scope", "tool_name"],
+        )
+    }
+}
+
+impl AppEvent for ToolOutputChunk {
+    const NAME: &'static str = "tool-output";
+    const DESCRIPTION: &'static str = "Sortie partielle d'un outil pendant son exécution";
+
+    fn schema() -> Value {
+        object(
+            json!({"scope": {"type": "string"}, "tool_name": {"type": "string"}, "text": {"type": "string"}}),
+            &["scope", "tool_name", "text"],
+        )
+    }
+}
+
+impl AppEvent for ScheduleEvent {
+    const NAME: &'static str = "schedule-run";
+    const DESCRIPTION: &'static str = "Exécution d'une tâche planifiée terminée";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "schedule_id": {"type": "string"},
+                "conversation_id": nullable("string"),
+                "error": nullable("string")
+            }),
+            &["schedule_id"],
+        )
+    }
+}
+
+impl AppEvent for TaskEvent {
+    const NAME: &'static str = "task-progress";
+    const DESCRIPTION: &'static str = "Début, étape ou fin d'une tâche autonome (selon `type`)";
+
+    fn schema() -> Value {
+        // Union des variantes : `goal` au début, `step` à chaque étape, le reste à la fin
+        object(
+            json!({
+                "type": {"type": "string", "enum": ["started", "step", "finished"]},
+                "run_id": {"type": "string"},
+                "goal": {"type": "string"},
+                "step": {"type": "object"},
+                "status": {"type": "string"},
+                "result": nullable("string"),
+                "error": nullable("string")
+            }),
+            &["type", "run_id"],
+        )
+    }
+}
+
+impl AppEvent for EvalProgress {
+    const NAME: &'static str = "eval-progress";
+    const DESCRIPTION: &'static str = "Cas d'une suite d'évaluation terminé";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "suite_id": {"type": "string"},
+                "model": {"type": "string"},
+                "case_name": {"type": "string"},
+                "completed": {"type": "integer", "minimum": 0},
+                "total": {"type": "integer", "minimum": 0},
+                "passed": {"type": "boolean"}
+            }),
+            &["suite_id", "model", "case_name", "completed", "total", "passed"],
+        )
+    }
+}
+
+impl AppEvent for ModelsChanged {
+    const NAME: &'static str = "models-changed";
+    const DESCRIPTION: &'static str = "Fichiers GGUF ajoutés ou supprimés du dossier des modèles";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "added": {"type": "array", "items": {"type": "string"}},
+                "removed": {"type": "array", "items": {"type": "string"}}
+            }),
+            &["added", "removed"],
+        )
+    }
+}
+
+impl AppEvent for SystemMetrics {
+    const NAME: &'static str = "system-metrics";
+    const DESCRIPTION: &'static str = "Mémoire, VRAM et CPU, émis périodiquement si activé";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "process_rss_bytes": nullable("integer"),
+                "total_memory_bytes": nullable("integer"),
+                "available_memory_bytes": nullable("integer"),
+                "gpus": {
+                    "type": "array",
+                    "items": object(
+                        json!({
+                            "index": {"type": "integer", "minimum": 0},
+                            "name": {"type": "string"},
+                            "used_bytes": {"type": "integer"},
+                            "total_bytes": {"type": "integer"}
+                        }),
+                        &["index", "name", "used_bytes", "total_bytes"],
+                    )
+                },
+                "cpu_usage_percent": nullable("number"),
+                "load_average": nullable("number"),
+                "logical_cores": {"type": "integer", "minimum": 0}
+            }),
+            &["gpus", "logical_cores"],
+        )
+    }
+}
+
+impl AppEvent for KbIndexProgress {
+    const NAME: &'static str = "kb-index-progress";
+    const DESCRIPTION: &'static str = "Avancement de l'indexation d'un dossier de la base de connaissances";
+
+    fn schema() -> Value {
+        object(
+            json!({
+                "folder_id": {"type": "string"},
+                "path": nullable("string"),
+                "indexed": {"type": "integer", "minimum": 0},
+                "total": {"type": "integer", "minimum": 0},
+                "removed": {"type": "integer", "minimum": 0},
+                "done": {"type": "boolean"},
+                "error": nullable("string")
+            }),
+            &["folder_id", "indexed", "total", "removed", "done"],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{GpuMemory, GuardrailRule, SwitchStage};
+    use crate::mcp::schema::validate_arguments;
+    use crate::tasks::{TaskStatus, TaskStep};
+    use chrono::Utc;
+
+    fn check<E: AppEvent>(event: E) {
+        let payload = serde_json::to_value(&event).unwrap();
+        if let Err(e) = validate_arguments(E::NAME, &E::schema(), &payload) {
+            panic!("{}: {}", e, payload);
+        }
+    }
+
+    #[test]
+    fn test_events_match_their_schema() {
+        let session_id = "s1".to_string();
+        check(DownloadProgress { repo_id: Some("org/model".to_string()), ..DownloadProgress::new("m.gguf", 50, Some(200)) });
+        check(DownloadProgress::new("m.gguf", 50, None));
+        check(LlmToken { session_id: session_id.clone(), chunk: "Hi".to_string() });
+        check(LlmReasoning { session_id: session_id.clone(), phase: ReasoningPhase::Delta, chunk: Some("hmm".to_string()) });
+        check(ContextTruncated { session_id: session_id.clone(), truncated_tokens: 12 });
+        check(ContextOverflow { session_id: session_id.clone(), prompt_tokens: 9000, n_ctx: 4096, suggestion: "Summarize".to_string() });
+        check(GuardrailTriggered {
+            session_id: session_id.clone(),
+            triggers: vec![GuardrailTrigger { rule: GuardrailRule::SecretKey, count: 2 }],
+        });
+        check(SessionTitle { session_id: session_id.clone(), title: "Rust".to_string() });
+        check(StartupRestored { session_id: None, model: None, model_error: Some(AppError::NoModelLoaded) });
+        check(ReplayProgress { session_id: session_id.clone(), source_session_id: "s0".to_string(), turn: 1, turns: 3 });
+        check(SwitchStatus {
+            task_id: "t1".to_string(),
+            model_name: "m.gguf".to_string(),
+            stage: SwitchStage::WarmingUp,
+            error: None,
+            started_at: Utc::now(),
+            updated_at: Utc::now(),
+        });
+        check(QueueUpdate { request_id: 3, session_id: Some(session_id.clone()), position: 1 });
+        check(ApprovalRequest {
+            call_id: "c1".to_string(),
+            scope: session_id.clone(),
+            tool_name: "run_code".to_string(),
+            arguments: json!({"code": "print(1)"}),
+            reason: None,
+        });
+        check(ToolOutputChunk { scope: session_id.clone(), tool_name: "run_code".to_string(), text: "1\n".to_string() });
+        check(ScheduleEvent { schedule_id: "daily".to_string(), conversation_id: None, error: Some("No model".to_string()) });
+        check(TaskEvent::Started { run_id: "r1".to_string(), goal: "Say hi".to_string() });
+        check(TaskEvent::Step {
+            run_id: "r1".to_string(),
+            step: TaskStep { index: 1, thought: None, action: None, observation: None, tokens: 5 },
+        });
+        check(TaskEvent::Finished { run_id: "r1".to_string(), status: TaskStatus::Completed, result: Some("hi".to_string()), error: None });
+        check(EvalProgress {
+            suite_id: "e1".to_string(),
+            model: "m.gguf".to_string(),
+            case_name: "greeting".to_string(),
+            completed: 1,
+            total: 2,
+            passed: true,
+        });
+        check(ModelsChanged { added: vec!["m.gguf".to_string()], removed: vec![] });
+        check(SystemMetrics {
+            gpus: vec![GpuMemory { index: 0, name: "GPU".to_string(), used_bytes: 1, total_bytes: 2 }],
+            cpu_usage_percent: Some(12.5),
+            logical_cores: 8,
+            ..Default::default()
+        });
+        check(KbIndexProgress {
+            folder_id: "kb1".to_string(),
+            path: Some("notes/todo.md".to_string()),
+            indexed: 1,
+            total: 3,
+            removed: 0,
+            done: false,
+            error: None,
+        });
+
+        let schemas = event_schemas();
+        let mut names: Vec<&str> = schemas.iter().map(|s| s.name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), schemas.len());
+    }
+}
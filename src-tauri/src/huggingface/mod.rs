@@ -1,7 +1,11 @@
+pub mod cache;
 pub mod client;
 pub mod models;
+pub mod offline;
 
-pub use client::HuggingFaceClient;
+pub use client::{ConnectionSettings, HuggingFaceClient, RateLimitConfig};
+pub use offline::ResponseCache;
 pub use models::{
-    GGUFFile, GGUFModelInfo, GGUFModelMetadata, Model, ModelFile, ModelInfo as HFModelInfo, ModelSearchParams,
+    FileRevision, GGUFFile, GGUFModelInfo, GGUFModelMetadata, Model, ModelFile, ModelInfo as HFModelInfo, ModelSearchParams,
+    ModelSuggestion,
 };
@@ -1,8 +1,10 @@
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
+use ts_rs::TS;
 
 /// Gated status - can be either boolean or string ("manual", "auto")
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, TS)]
+#[ts(export, untagged)]
 pub enum GatedStatus {
     Boolean(bool),
     String(String),
@@ -51,14 +53,17 @@ impl Default for GatedStatus {
 }
 
 /// Represents a model on Hugging Face
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct Model {
     #[serde(rename = "modelId")]
     pub model_id: String,
     #[serde(skip)]
     _id: Option<String>, // Ignore the duplicate 'id' field
     pub author: Option<String>,
+    #[ts(type = "number | null")]
     pub downloads: Option<u64>,
+    #[ts(type = "number | null")]
     pub likes: Option<u64>,
     pub pipeline_tag: Option<String>,
     #[serde(default)]
@@ -72,21 +77,26 @@ pub struct Model {
 }
 
 /// File information within a model repository
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct ModelFile {
     #[serde(rename = "rfilename")]
     pub filename: String,
     #[serde(default)]
+    #[ts(type = "number | null")]
     pub size: Option<u64>,
     #[serde(default)]
     pub lfs: Option<ModelFileLfs>,
 }
 
 /// LFS (Large File Storage) information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct ModelFileLfs {
     pub oid: String,
+    #[ts(type = "number")]
     pub size: u64,
+    #[ts(type = "number | null")]
     pub pointer_size: Option<u64>,
 }
 
@@ -108,8 +118,38 @@ pub struct TreeLfs {
     pub pointer_size: Option<u64>,
 }
 
+/// Repository state at a revision, with the hashes of its files (`blobs=true`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct RevisionInfo {
+    /// Commit the revision resolves to
+    pub sha: String,
+    #[serde(default)]
+    pub siblings: Vec<RevisionFile>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RevisionFile {
+    pub rfilename: String,
+    pub lfs: Option<RevisionLfs>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RevisionLfs {
+    #[serde(alias = "oid")]
+    pub sha256: Option<String>,
+}
+
+/// Commit and content hash of a repository file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileRevision {
+    pub commit: String,
+    /// LFS hash of the file (None for files stored in git)
+    pub sha256: Option<String>,
+}
+
 /// Detailed model information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, rename = "HFModelInfo")]
 pub struct ModelInfo {
     #[serde(rename = "modelId")]
     pub model_id: String,
@@ -128,15 +168,18 @@ pub struct ModelInfo {
     pub pipeline_tag: Option<String>,
     #[serde(default)]
     pub siblings: Vec<ModelFile>,
+    #[ts(type = "number | null")]
     pub downloads: Option<u64>,
+    #[ts(type = "number | null")]
     pub likes: Option<u64>,
     pub library_name: Option<String>,
 }
 
 /// GGUF file information with quantization details
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
 pub struct GGUFFile {
     pub filename: String,
+    #[ts(type = "number")]
     pub size: u64,
     pub quantization: Option<String>,
 }
@@ -166,11 +209,14 @@ impl GGUFFile {
 }
 
 /// GGUF model information with filtered files
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct GGUFModelInfo {
     pub repo_id: String,
     pub gguf_files: Vec<GGUFFile>,
+    #[ts(type = "number")]
     pub downloads: u64,
+    #[ts(type = "number")]
     pub likes: u64,
     pub author: String,
     pub task: Option<String>,
@@ -179,10 +225,13 @@ pub struct GGUFModelInfo {
 }
 
 /// GGUF model metadata (without files, for search results)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct GGUFModelMetadata {
     pub repo_id: String,
+    #[ts(type = "number")]
     pub downloads: u64,
+    #[ts(type = "number")]
     pub likes: u64,
     pub author: String,
     pub task: Option<String>,
@@ -204,6 +253,24 @@ impl From<GGUFModelInfo> for GGUFModelMetadata {
     }
 }
 
+/// Compact search result for type-ahead suggestions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ModelSuggestion {
+    pub repo_id: String,
+    #[ts(type = "number")]
+    pub downloads: u64,
+}
+
+impl From<Model> for ModelSuggestion {
+    fn from(model: Model) -> Self {
+        Self {
+            repo_id: model.model_id,
+            downloads: model.downloads.unwrap_or(0),
+        }
+    }
+}
+
 /// Parameters for searching models
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct ModelSearchParams {
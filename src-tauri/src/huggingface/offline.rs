@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Last successful API responses, kept on disk for offline use
+///
+/// Each response is stored in its own JSON file, named after a hash of the
+/// request, so a corrupt or outdated entry only loses that request.
+#[derive(Debug)]
+pub struct ResponseCache {
+    directory: PathBuf,
+}
+
+/// Cached response with the time it was fetched
+#[derive(Serialize, Deserialize)]
+struct CachedResponse<T> {
+    saved_at: i64,
+    value: T,
+}
+
+impl ResponseCache {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self { directory: directory.into() }
+    }
+
+    /// Cache directory of the application
+    pub fn default_directory() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "agents-rs", "AgentsRS")
+            .map(|dirs| dirs.cache_dir().join("huggingface"))
+    }
+
+    /// Cached response of a request, if any
+    pub fn load<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let json = std::fs::read_to_string(self.path(key)).ok()?;
+        match serde_json::from_str::<CachedResponse<T>>(&json) {
+            Ok(cached) => Some(cached.value),
+            Err(e) => {
+                warn!("Ignoring unreadable cached response for {}: {}", key, e);
+                None
+            }
+        }
+    }
+
+    /// Save the response of a request, replacing the previous one
+    pub fn store<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        std::fs::create_dir_all(&self.directory)
+            .context("Failed to create the response cache directory")?;
+        let cached = CachedResponse { saved_at: Utc::now().timestamp(), value };
+        let json = serde_json::to_string(&cached)?;
+
+        // Written next to the entry then renamed, so a crash never leaves half a file
+        let path = self.path(key);
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, json).context("Failed to write cached response")?;
+        std::fs::rename(&temporary, &path).context("Failed to write cached response")?;
+        Ok(())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{:016x}.json", fnv1a(key)))
+    }
+}
+
+/// Stable 64-bit FNV-1a hash (the std hasher may change between Rust releases)
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_cache_roundtrip() {
+        let directory = std::env::temp_dir().join(format!("agents-rs-hf-cache-{}", uuid::Uuid::new_v4()));
+        let cache = ResponseCache::new(&directory);
+
+        assert_eq!(cache.load::<Vec<String>>("search:qwen"), None);
+        cache.store("search:qwen", &vec!["Qwen/Qwen2-7B".to_string()]).unwrap();
+        cache.store("search:qwen", &vec!["Qwen/Qwen2.5-7B".to_string()]).unwrap();
+        assert_eq!(cache.load::<Vec<String>>("search:qwen"), Some(vec!["Qwen/Qwen2.5-7B".to_string()]));
+        assert_eq!(cache.load::<Vec<String>>("search:llama"), None);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -3,143 +3,131 @@ pub mod context;
 pub mod mcp;
 pub mod huggingface;
 pub mod commands;
+pub mod error;
+pub mod tasks;
+pub mod eval;
+pub mod events;
+pub mod logging;
+pub mod configuration;
+pub mod state;
 
-use llm::{LLMEngine, LLMConfig, ModelManager};
-use huggingface::HuggingFaceClient;
-use context::{Database, SettingsRepository, ContextManager, ConversationRepository, get_default_database_path};
+pub use state::{AppConfig, AppState, SharedState};
 
 use tauri::Manager;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{info, error};
-use tracing_subscriber;
+use tracing::{info, error, warn};
 
 // Import all commands from the commands module
 use commands::*;
 
-/// État global de l'application
-pub struct AppState {
-    pub llm_engine: Arc<RwLock<LLMEngine>>,
-    pub model_manager: Arc<ModelManager>,
-    pub hf_client: Arc<RwLock<HuggingFaceClient>>,
-    pub database: Arc<Database>,
-    pub settings_repo: Arc<SettingsRepository>,
-    pub context_manager: Arc<RwLock<ContextManager>>,
-}
+/// Intervalle entre deux purges de la corbeille des modèles
+const MODEL_TRASH_PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(6 * 3600);
+
+/// Intervalle entre deux passes d'indexation sémantique des messages
+const MESSAGE_EMBEDDING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Délai avant de relire l'intervalle des métriques système quand elles sont désactivées
+const SYSTEM_METRICS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialiser le logging
-    tracing_subscriber::fmt()
-        .with_env_filter("info,agents_rs=debug")
-        .init();
+    // Processus enfant de vérification d'un modèle (voir llm::gguf)
+    if let Some(code) = llm::gguf::run_probe_from_args() {
+        std::process::exit(code);
+    }
+    
+    // Initialiser le logging (console et fichiers tournants)
+    let log_handle = logging::init(logging::default_log_directory());
 
     info!("Démarrage de agents-rs");
+    
+    // Les modèles sont ouverts une première fois dans un processus enfant
+    llm::gguf::enable_child_probe();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .setup(|app| {
+        .setup(move |app| {
             // Initialiser les composants backend
-            let model_manager = Arc::new(ModelManager::new().map_err(|e| {
-                error!("Failed to initialize model manager: {}", e);
-                e
-            })?);
-            
-            let llm_config = LLMConfig::default();
-            let llm_engine = match LLMEngine::new(llm_config) {
-                Ok(engine) => Arc::new(RwLock::new(engine)),
-                Err(e) => {
-                    error!("Erreur lors de l'initialisation du moteur LLM: {}", e);
-                    return Err(e.into());
-                }
-            };
-
-            // Initialize HuggingFace client
-            let hf_client = Arc::new(RwLock::new(
-                HuggingFaceClient::new().map_err(|e| {
-                    error!("Failed to initialize HuggingFace client: {}", e);
-                    e
-                })?
-            ));
-            
-            // Initialize Database and Settings
-            // Create a new runtime for async initialization
-            let runtime = tokio::runtime::Runtime::new().map_err(|e| {
-                error!("Failed to create Tokio runtime: {}", e);
-                e
-            })?;
-            
-            let (database, settings_repo, context_manager) = runtime.block_on(async {
-                // Get database path
-                let db_url = match get_default_database_path() {
-                    Ok(url) => {
-                        info!("Database URL: {}", url);
-                        url
-                    },
-                    Err(e) => {
-                        error!("Failed to get database path, using in-memory: {}", e);
-                        "sqlite::memory:".to_string()
-                    }
-                };
-                
-                // Create database
-                let db = match Database::new(&db_url).await {
-                    Ok(db) => db,
-                    Err(e) => {
-                        error!("Failed to create database, falling back to in-memory: {}", e);
-                        Database::new("sqlite::memory:").await
-                            .expect("Failed to create in-memory database")
-                    }
-                };
-                
-                // Run migrations
-                if let Err(e) = db.migrate().await {
-                    error!("Database migration failed: {}", e);
-                }
-                
-                let pool = db.pool().clone();
-                let settings = SettingsRepository::new(pool.clone());
-                
-                // Get current model or use default
-                let current_model = settings.get_current_model().await
-                    .unwrap_or(None)
-                    .unwrap_or_else(|| "No model loaded".to_string());
-                
-                // Create ConversationRepository and ContextManager
-                let conv_repo = ConversationRepository::new(pool);
-                let ctx_manager = ContextManager::new(conv_repo, current_model);
-                
-                (Arc::new(db), Arc::new(settings), Arc::new(RwLock::new(ctx_manager)))
-            });
-            
-            let app_state = Arc::new(AppState {
-                llm_engine,
-                model_manager,
-                hf_client,
-                database,
-                settings_repo,
-                context_manager,
-            });
+            let config = AppConfig::new().with_log_handle(log_handle.clone());
+            let app_state = Arc::new(tauri::async_runtime::block_on(AppState::initialize(config))?);
             
-            app.manage(app_state);
+            let tasks = start(app.handle(), &app_state);
+            app.manage(SharedState::new(app_state, tasks));
             
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             initialize_llm,
             switch_model,
+            get_switch_status,
             send_message,
+            send_message_stream,
             generate_response,
+            generate_batch,
+            set_context_shift,
+            set_session_context_size,
+            get_context_breakdown,
+            continue_generation,
+            continue_message,
+            set_logprobs,
+            set_vision_projector,
+            get_sampling_config,
+            update_sampling_config,
+            get_postprocess_config,
+            set_postprocess_config,
+            get_stream_pacing,
+            set_stream_pacing,
+            get_guardrail_policy,
+            set_guardrail_policy,
+            compare_models,
+            replay_session,
+            save_eval_suite,
+            list_eval_suites,
+            delete_eval_suite,
+            run_eval_suite,
+            list_eval_runs,
+            get_eval_run,
+            set_prompt_cache_settings,
+            get_prompt_cache_stats,
+            clear_prompt_cache,
             list_models,
+            import_model,
+            list_imported_models,
+            favorite_model,
             delete_model,
+            list_trashed_models,
+            restore_model,
+            empty_model_trash,
+            get_model_trash_days,
+            set_model_trash_days,
+            get_system_metrics,
+            get_system_metrics_interval,
+            set_system_metrics_interval,
+            get_backend_info,
+            get_setup_status,
+            get_hardware_summary,
+            setup_recommended_model,
+            set_setup_completed,
             get_models_directory,
             get_gpu_info,
             detect_gpu,
             update_gpu_settings,
+            update_batch_settings,
+            get_thread_settings,
+            update_thread_settings,
             hf_search_models,
+            hf_autocomplete_models,
             hf_get_model_info,
             hf_download_model,
+            check_model_updates,
+            download_model_from_url,
             hf_set_token,
+            hf_get_offline_mode,
+            hf_set_offline_mode,
+            hf_get_rate_limit,
+            hf_set_rate_limit,
+            hf_get_connection_settings,
+            hf_set_connection_settings,
             hf_discover_gguf_models,
             hf_get_gguf_files,
             get_current_model,
@@ -149,7 +137,334 @@ pub fn run() {
             list_sessions,
             delete_session,
             rename_session,
+            get_session_stats,
+            set_session_language,
+            get_session_status,
+            get_startup_settings,
+            create_preset,
+            list_presets,
+            update_preset,
+            delete_preset,
+            create_session_from_preset,
+            get_session_tools,
+            export_profile,
+            import_profile,
+            set_startup_settings,
+            save_draft,
+            get_draft,
+            get_scratchpad,
+            semantic_search_conversations,
+            rate_message,
+            get_session_feedback,
+            export_feedback_dataset,
+            export_training_data,
+            set_provider_credentials,
+            set_session_backend,
+            get_session_backend,
+            set_tool_secret,
+            delete_tool_secret,
+            list_tool_secrets,
+            approve_tool_call,
+            list_pending_tool_approvals,
+            get_tool_policies,
+            set_tool_policy,
+            get_injection_settings,
+            set_injection_settings,
+            get_tool_audit,
+            list_api_connectors,
+            save_api_connector,
+            delete_api_connector,
+            create_api_token,
+            list_api_tokens,
+            revoke_api_token,
+            get_container_settings,
+            set_container_settings,
+            create_pipeline,
+            list_pipelines,
+            update_pipeline,
+            delete_pipeline,
+            run_pipeline,
+            get_model_capabilities,
+            probe_model_capabilities,
+            backup_database,
+            restore_database,
+            compact_database,
+            get_encryption_status,
+            encrypt_database,
+            unlock_database,
+            decrypt_database,
+            get_retention_policy,
+            set_retention_policy,
+            preview_prune,
+            create_agent,
+            list_agents,
+            get_agent,
+            update_agent,
+            delete_agent,
+            run_task,
+            list_task_runs,
+            get_task_run,
+            delete_task_run,
+            create_schedule,
+            list_schedules,
+            pause_schedule,
+            resume_schedule,
+            delete_schedule,
+            run_schedule_now,
+            get_memory_enabled,
+            set_memory_enabled,
+            list_memories,
+            add_memory,
+            update_memory,
+            delete_memory,
+            get_attachment_data,
+            get_attachment_text,
+            list_documents,
+            delete_document,
+            reindex_document,
+            get_retrieval_settings,
+            set_retrieval_settings,
+            add_knowledge_folder,
+            list_knowledge_folders,
+            remove_knowledge_folder,
+            reindex_knowledge_folder,
+            set_knowledge_reranker,
+            create_workspace,
+            list_workspaces,
+            delete_workspace,
+            set_log_level,
+            get_recent_logs,
+            get_effective_config,
+            list_profiles,
+            create_profile,
+            switch_profile,
+            share_session,
+            import_shared_session,
+            get_sync_settings,
+            set_sync_settings,
+            sync_now,
+            list_event_schemas,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| match event {
+            // Mode économie : moins de threads quand la fenêtre n'a pas le focus
+            tauri::RunEvent::WindowEvent { event: tauri::WindowEvent::Focused(focused), .. } => {
+                if let Some(state) = app_handle.try_state::<SharedState>() {
+                    state.get().llm_engine.set_focused(focused);
+                }
+            }
+            // Fermeture de la dernière fenêtre : finaliser l'état avant de quitter
+            tauri::RunEvent::Exit => {
+                if let Some(state) = app_handle.try_state::<SharedState>() {
+                    let state = state.get();
+                    tauri::async_runtime::block_on(shutdown(&state));
+                }
+            }
+            _ => {}
+        });
+}
+
+/// Branche un état sur l'application : événements vers l'interface et tâches de fond
+///
+/// Les tâches renvoyées sont arrêtées quand l'état est remplacé (`switch_state`).
+fn start(app: &tauri::AppHandle, app_state: &Arc<AppState>) -> Vec<tauri::async_runtime::JoinHandle<()>> {
+    let mut tasks = Vec::new();
+    
+    // File d'attente des générations (un seul moteur local chargé à la fois)
+    let mut queue_updates = app_state.generation_scheduler.subscribe();
+    let app_handle = app.clone();
+    tasks.push(tauri::async_runtime::spawn(async move {
+        while let Ok(update) = queue_updates.recv().await {
+            events::emit_event(&app_handle, &update);
+        }
+    }));
+    
+    // Demandes de confirmation des outils sensibles
+    let mut approval_requests = app_state.tool_approvals.subscribe();
+    let app_handle = app.clone();
+    tasks.push(tauri::async_runtime::spawn(async move {
+        while let Ok(request) = approval_requests.recv().await {
+            events::emit_event(&app_handle, &request);
+        }
+    }));
+    // Sortie des outils longs (run_code...) affichée pendant leur exécution
+    let tool_registry = Arc::clone(&app_state.tool_registry);
+    let app_handle = app.clone();
+    tasks.push(tauri::async_runtime::spawn(async move {
+        use tokio::sync::broadcast::error::RecvError;
+        let mut tool_output = tool_registry.read().await.subscribe_output();
+        loop {
+            match tool_output.recv().await {
+                Ok(chunk) => {
+                    events::emit_event(&app_handle, &chunk);
+                }
+                // Une sortie trop rapide perd des morceaux, le résultat final reste complet
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }));
+    
+    // Tâches planifiées : résultats enregistrés dans des conversations dédiées
+    let mut schedule_events = app_state.schedule_runner.subscribe();
+    let app_handle = app.clone();
+    tasks.push(tauri::async_runtime::spawn(async move {
+        while let Ok(event) = schedule_events.recv().await {
+            events::emit_event(&app_handle, &event);
+        }
+    }));
+    tasks.push(tauri::async_runtime::spawn(app_state.schedule_runner.clone().run_periodic()));
+    
+    // Serveur MCP local si un port est configuré (outils et conversations de l'application,
+    // embeddings au format OpenAI)
+    if let Some(port) = mcp::MCPServer::port_from_env().or(app_state.file_config.mcp_port) {
+        let server = mcp::MCPServer::new(port)
+            .with_tool_registry(Arc::clone(&app_state.tool_registry))
+            .with_conversations(Arc::clone(&app_state.context_manager))
+            .with_embeddings(app_state.llm_engine.clone(), app_state.generation_scheduler.clone())
+            .with_api_tokens((*app_state.api_token_repo).clone());
+        tasks.push(tauri::async_runtime::spawn(async move {
+            if let Err(e) = server.start().await {
+                error!("Serveur MCP arrêté: {:#}", e);
+            }
+        }));
+    }
+    
+    // Élagage périodique selon la politique de rétention
+    tasks.push(tauri::async_runtime::spawn(context::retention::run_periodic(
+        context::ConversationRepository::new(app_state.database.pool().clone()),
+        Arc::clone(&app_state.settings_repo),
+        Arc::clone(&app_state.context_manager),
+    )));
+    
+    // Synchronisation avec le dossier partagé, si configuré
+    tasks.push(tauri::async_runtime::spawn(context::sync::run_periodic(
+        context::ConversationRepository::new(app_state.database.pool().clone())
+            .with_cipher(app_state.content_cipher.clone()),
+        Arc::clone(&app_state.settings_repo),
+        Arc::clone(&app_state.context_manager),
+    )));
+    
+    // Fichiers GGUF ajoutés ou supprimés hors de l'application
+    let model_manager = Arc::clone(&app_state.model_manager);
+    let app_handle = app.clone();
+    tasks.push(tauri::async_runtime::spawn(async move {
+        let watched = llm::watcher::watch_models(model_manager, |changed| {
+            events::emit_event(&app_handle, &changed);
+        });
+        if let Err(e) = watched.await {
+            error!("Surveillance du dossier des modèles impossible: {:#}", e);
+        }
+    }));
+    
+    // Dossiers de la base de connaissances réindexés à chaque modification
+    if let Some(requests) = app_state.knowledge_indexer.take_receiver() {
+        let repo = context::KnowledgeRepository::new(app_state.database.pool().clone());
+        let engine = app_state.llm_engine.clone();
+        let scheduler = app_state.generation_scheduler.clone();
+        let app_handle = app.clone();
+        tasks.push(tauri::async_runtime::spawn(async move {
+            let watched = context::knowledge::run(repo, engine, scheduler, requests, |progress| {
+                events::emit_event(&app_handle, &progress);
+            });
+            if let Err(e) = watched.await {
+                error!("Surveillance de la base de connaissances impossible: {:#}", e);
+            }
+        }));
+    }
+    
+    // Corbeille des modèles vidée selon la durée de conservation
+    let trash_state = Arc::clone(app_state);
+    tasks.push(tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(MODEL_TRASH_PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = commands::model::purge_model_trash(&trash_state).await {
+                error!("Purge de la corbeille des modèles impossible: {}", e);
+            }
+        }
+    }));
+    
+    // Indexation des messages pour la recherche sémantique (par lots, avec le modèle chargé)
+    let embedding_state = Arc::clone(app_state);
+    tasks.push(tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(MESSAGE_EMBEDDING_INTERVAL);
+        loop {
+            interval.tick().await;
+            // Lots successifs jusqu'à rattraper l'historique
+            loop {
+                match commands::session::index_message_embeddings(&embedding_state).await {
+                    Ok(0) => break,
+                    Ok(_) => tokio::task::yield_now().await,
+                    Err(e) => {
+                        warn!("Indexation sémantique des messages impossible: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    }));
+    
+    // Métriques système (RAM, VRAM, CPU) émises périodiquement si activées
+    let metrics_state = Arc::clone(app_state);
+    let app_handle = app.clone();
+    tasks.push(tauri::async_runtime::spawn(async move {
+        loop {
+            // Intervalle relu à chaque tour : un changement de réglage s'applique sans redémarrer
+            let secs = metrics_state.settings_repo.get_system_metrics_interval().await.unwrap_or(0);
+            if secs == 0 {
+                tokio::time::sleep(SYSTEM_METRICS_POLL_INTERVAL).await;
+                continue;
+            }
+            if let Ok(metrics) = tokio::task::spawn_blocking(llm::system::collect_metrics).await {
+                events::emit_event(&app_handle, &metrics);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(secs)).await;
+        }
+    }));
+    
+    // Dernière session et dernier modèle, selon les réglages de démarrage
+    tasks.push(tauri::async_runtime::spawn(commands::session::restore_startup(
+        app.clone(),
+        Arc::clone(app_state),
+    )));
+    
+    tasks
+}
+
+/// Remplace l'état de l'application (changement de profil) : les tâches de
+/// l'ancien état sont arrêtées et il est fermé avant de brancher le nouveau
+pub async fn switch_state(app: &tauri::AppHandle, shared: &SharedState, state: Arc<AppState>) {
+    shared.stop_tasks().await;
+    let previous = shared.replace(Arc::clone(&state), Vec::new());
+    shutdown(&previous).await;
+    let tasks = start(app, &state);
+    shared.replace(state, tasks);
+}
+
+/// Durée maximale de l'arrêt avant de quitter malgré tout
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Arrêt propre : téléchargements en pause, générations annulées, modèle déchargé, base fermée
+pub async fn shutdown(state: &AppState) {
+    info!("Arrêt de l'application");
+    
+    let steps = async {
+        // Les fichiers partiels sont repris au prochain téléchargement
+        state.hf_client.read().await.pause_downloads();
+        
+        if let Err(e) = state.llm_engine.shutdown().await {
+            warn!("Échec du déchargement du modèle: {:#}", e);
+        }
+        
+        // Attend la fin des écritures en cours
+        state.database.close().await;
+    };
+    
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, steps).await.is_err() {
+        warn!("Arrêt incomplet après {}s", SHUTDOWN_TIMEOUT.as_secs());
+    } else {
+        info!("Arrêt terminé");
+    }
 }
@@ -0,0 +1,129 @@
+/// Generation backends - common interface for local and remote models
+
+use super::engine::LLMResponse;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Role of a message sent to a backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+impl ChatRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatRole::System => "system",
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+            ChatRole::Tool => "tool",
+        }
+    }
+}
+
+/// A single message of the conversation passed to a backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+    /// Encoded images (PNG, JPEG...) shown to vision models with this message
+    #[serde(skip)]
+    pub images: Vec<Vec<u8>>,
+}
+
+impl ChatMessage {
+    pub fn new(role: ChatRole, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+            images: Vec::new(),
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::System, content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::User, content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::Assistant, content)
+    }
+
+    pub fn with_images(mut self, images: Vec<Vec<u8>>) -> Self {
+        self.images = images;
+        self
+    }
+}
+
+/// Backend selected for a session
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "provider", rename_all = "snake_case")]
+pub enum BackendKind {
+    /// Local llama.cpp engine
+    #[default]
+    Local,
+    /// Any OpenAI-compatible chat completions API
+    #[serde(rename = "openai")]
+    OpenAI { model: String },
+    /// Anthropic Messages API
+    Anthropic { model: String },
+    /// Deterministic canned replies (`mock-backend` feature and tests)
+    #[cfg(any(test, feature = "mock-backend"))]
+    Mock,
+}
+
+impl BackendKind {
+    /// Provider identifier used for settings keys ("local", "openai", "anthropic")
+    pub fn provider(&self) -> &'static str {
+        match self {
+            BackendKind::Local => "local",
+            BackendKind::OpenAI { .. } => "openai",
+            BackendKind::Anthropic { .. } => "anthropic",
+            #[cfg(any(test, feature = "mock-backend"))]
+            BackendKind::Mock => "mock",
+        }
+    }
+
+    pub fn is_local(&self) -> bool {
+        matches!(self, BackendKind::Local)
+    }
+}
+
+/// Common interface implemented by every generation backend
+#[async_trait::async_trait]
+pub trait LLMBackend: Send + Sync {
+    /// Human readable backend name (used in logs)
+    fn name(&self) -> String;
+
+    /// Whether the backend can serve requests right now
+    async fn is_ready(&self) -> bool;
+
+    /// Generate the assistant reply for a full conversation
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<LLMResponse>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_kind_serialization() {
+        let kind = BackendKind::OpenAI { model: "gpt-4o-mini".to_string() };
+        let json = serde_json::to_value(&kind).unwrap();
+        assert_eq!(json["provider"], "openai");
+        assert_eq!(json["model"], "gpt-4o-mini");
+
+        let local: BackendKind = serde_json::from_str(r#"{"provider":"local"}"#).unwrap();
+        assert!(local.is_local());
+    }
+}
@@ -0,0 +1,79 @@
+/// Build information of the embedded llama.cpp backend
+///
+/// Lets the UI show which llama.cpp bindings and accelerations this build ships
+/// and tell an outdated application (newer GGUF format) from a corrupt model.
+
+use super::gguf::SUPPORTED_VERSIONS;
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Version of the llama-cpp-2 bindings (keep in sync with Cargo.toml); each
+/// release pins a llama.cpp revision
+pub const LLAMA_CPP_BINDINGS_VERSION: &str = "0.1.122";
+
+/// Range of GGUF versions the backend reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub struct GgufVersionRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+/// llama.cpp bindings, compiled accelerations and runtime support
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct BackendInfo {
+    pub bindings_version: String,
+    /// Accelerations compiled in (`cuda`, `metal`, `vulkan`), empty for CPU only
+    pub features: Vec<String>,
+    pub gguf_versions: GgufVersionRange,
+    /// Devices llama.cpp can split a model across
+    #[ts(type = "number")]
+    pub max_devices: usize,
+    pub mmap_supported: bool,
+    pub mlock_supported: bool,
+}
+
+/// Accelerations enabled by the Cargo features of this build
+pub fn compiled_features() -> Vec<String> {
+    [
+        ("cuda", cfg!(feature = "cuda")),
+        ("metal", cfg!(feature = "metal")),
+        ("vulkan", cfg!(feature = "vulkan")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name.to_string())
+    .collect()
+}
+
+pub fn gguf_versions() -> GgufVersionRange {
+    GgufVersionRange {
+        min: *SUPPORTED_VERSIONS.start(),
+        max: *SUPPORTED_VERSIONS.end(),
+    }
+}
+
+/// Information about the backend (queries llama.cpp for the runtime support)
+pub fn backend_info() -> BackendInfo {
+    BackendInfo {
+        bindings_version: LLAMA_CPP_BINDINGS_VERSION.to_string(),
+        features: compiled_features(),
+        gguf_versions: gguf_versions(),
+        max_devices: llama_cpp_2::max_devices(),
+        mmap_supported: llama_cpp_2::mmap_supported(),
+        mlock_supported: llama_cpp_2::mlock_supported(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info() {
+        assert_eq!(gguf_versions(), GgufVersionRange { min: 2, max: 3 });
+        // Default build: CPU only
+        assert_eq!(compiled_features().is_empty(), !cfg!(any(feature = "cuda", feature = "metal", feature = "vulkan")));
+    }
+}
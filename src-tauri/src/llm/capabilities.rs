@@ -0,0 +1,138 @@
+/// Model capability detection (system prompt, tool calls, FIM, vision)
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+/// GGUF metadata keys holding the fill-in-the-middle special tokens
+pub const FIM_METADATA_KEYS: &[&str] = &[
+    "tokenizer.ggml.fim_pre_token_id",
+    "tokenizer.ggml.prefix_token_id",
+];
+
+/// Prompt used to check whether the model follows the tool-call format
+pub const TOOL_CALL_PROBE_SYSTEM: &str = "You can call tools. To call a tool, answer only with \
+<tool_call>{\"name\": \"<tool name>\", \"arguments\": {...}}</tool_call>.\n\
+Available tools:\n- echo: returns the given text. Arguments: {\"text\": string}";
+pub const TOOL_CALL_PROBE_USER: &str = "Use the echo tool with the text \"ping\".";
+
+/// Number of tokens generated by the tool-call probe
+pub const TOOL_CALL_PROBE_MAX_TOKENS: usize = 48;
+
+/// Feature that depends on the loaded model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    SystemPrompt,
+    ToolCalls,
+    Fim,
+    Vision,
+}
+
+/// Capability vector of a model, cached per model file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ModelCapabilities {
+    /// The chat template handles a system role
+    pub system_prompt: bool,
+    /// The model answered the probe with a well formed tool call
+    pub tool_calls: bool,
+    /// Fill-in-the-middle tokens are present in the vocabulary
+    pub fim: bool,
+    /// A vision projector (mmproj) is available next to the model
+    pub vision: bool,
+    /// The GGUF file embeds a chat template
+    pub has_chat_template: bool,
+    /// Unix timestamp of the probe
+    #[ts(type = "number")]
+    pub probed_at: i64,
+}
+
+impl ModelCapabilities {
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::SystemPrompt => self.system_prompt,
+            Capability::ToolCalls => self.tool_calls,
+            Capability::Fim => self.fim,
+            Capability::Vision => self.vision,
+        }
+    }
+
+    /// Fails with a readable error when the model lacks a capability
+    pub fn require(&self, capability: Capability) -> Result<()> {
+        if self.supports(capability) {
+            Ok(())
+        } else {
+            anyhow::bail!("The loaded model does not support {:?}", capability)
+        }
+    }
+}
+
+/// Whether a chat template renders system messages
+pub fn template_supports_system(template: Option<&str>) -> bool {
+    match template {
+        Some(template) => template.contains("system"),
+        // Without template the engine falls back to ChatML, which has a system role
+        None => true,
+    }
+}
+
+/// Whether generated text contains a parseable `<tool_call>` (or bare JSON) call to `tool`
+pub fn looks_like_tool_call(text: &str, tool: &str) -> bool {
+    let body = match (text.find("<tool_call>"), text.find("</tool_call>")) {
+        (Some(start), Some(end)) if end > start => &text[start + "<tool_call>".len()..end],
+        _ => match (text.find('{'), text.rfind('}')) {
+            (Some(start), Some(end)) if end > start => &text[start..=end],
+            _ => return false,
+        },
+    };
+
+    serde_json::from_str::<serde_json::Value>(body.trim())
+        .map(|call| call["name"] == tool && call.get("arguments").is_some())
+        .unwrap_or(false)
+}
+
+/// Looks for a vision projector (`mmproj*.gguf`) in the directory of the model
+pub fn find_vision_projector(model_path: &Path) -> Option<PathBuf> {
+    let dir = model_path.parent()?;
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| {
+                    let name = name.to_lowercase();
+                    name.contains("mmproj") && name.ends_with(".gguf")
+                })
+                .unwrap_or(false)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_like_tool_call() {
+        assert!(looks_like_tool_call(
+            r#"<tool_call>{"name": "echo", "arguments": {"text": "ping"}}</tool_call>"#,
+            "echo"
+        ));
+        assert!(looks_like_tool_call(
+            r#"Sure: {"name": "echo", "arguments": {"text": "ping"}}"#,
+            "echo"
+        ));
+        assert!(!looks_like_tool_call("ping", "echo"));
+        assert!(!looks_like_tool_call(r#"{"name": "other", "arguments": {}}"#, "echo"));
+    }
+
+    #[test]
+    fn test_require_capability() {
+        let caps = ModelCapabilities { system_prompt: true, ..Default::default() };
+        assert!(caps.require(Capability::SystemPrompt).is_ok());
+        assert!(caps.require(Capability::Vision).is_err());
+    }
+}
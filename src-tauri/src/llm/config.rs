@@ -1,38 +1,260 @@
 /// Configuration du moteur LLM
 
+use super::postprocess::PostProcessConfig;
+use super::stop::StopCondition;
+use super::threads;
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Full engine configuration: model settings and sampling settings
+///
+/// Both halves are flattened, so the serialized form keeps a single level of fields.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LLMConfig {
+    #[serde(flatten)]
+    pub model: ModelConfig,
+    #[serde(flatten)]
+    pub sampling: SamplingConfig,
+    /// Cleanup of the generated text (reasoning blocks, template tokens, markdown)
+    #[serde(default)]
+    pub postprocess: PostProcessConfig,
+    /// Streamed chunks are grouped into batches of this many milliseconds before
+    /// reaching the caller (0 forwards each token as soon as it is generated)
+    #[serde(default)]
+    pub stream_pacing_ms: u64,
+}
+
+/// Settings read when the model and its context are created (changes need a reload,
+/// except `context_shift` and the prompt cache limits)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelConfig {
     pub model_path: String,
-    pub max_tokens: usize,
     pub context_size: usize,
     pub n_ctx: usize, // Alias for context_size for compatibility
     pub n_threads: usize,
-    pub temperature: f32,
-    pub top_p: f32,
-    pub top_k: i32,
-    pub repeat_penalty: f32,
+    /// Threads used to evaluate the prompt (0 = same as `n_threads`)
+    #[serde(default)]
+    pub n_threads_batch: usize,
+    /// Use fewer threads while the window is not focused
+    #[serde(default)]
+    pub efficiency_mode: bool,
     pub use_gpu: bool,
     pub n_gpu_layers: u32,
     pub main_gpu: i32,
+    /// Drop the oldest tokens instead of failing when the context window is full
+    pub context_shift: bool,
+    /// Maximum number of cached prompt states (0 disables the prompt cache)
+    pub prompt_cache_entries: usize,
+    /// Maximum memory used by cached prompt states, in MB
+    pub prompt_cache_size_mb: usize,
+    /// Vision projector (mmproj GGUF) paired with the model (None = look next to the model)
+    #[serde(default)]
+    pub mmproj_path: Option<String>,
+    /// Tokens submitted per decode call while evaluating the prompt (0 = auto)
+    #[serde(default)]
+    pub n_batch: u32,
+    /// Tokens computed at once by the backend inside a batch (0 = auto)
+    #[serde(default)]
+    pub n_ubatch: u32,
+    #[serde(default)]
+    pub flash_attention: FlashAttention,
+}
+
+/// Flash attention policy of the contexts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashAttention {
+    /// Enabled by llama.cpp when the backend supports it
+    #[default]
+    Auto,
+    Enabled,
+    Disabled,
 }
 
-impl Default for LLMConfig {
+impl FlashAttention {
+    /// Matching llama.cpp `llama_flash_attn_type` value
+    pub fn policy(&self) -> i32 {
+        match self {
+            FlashAttention::Auto => -1,
+            FlashAttention::Disabled => 0,
+            FlashAttention::Enabled => 1,
+        }
+    }
+}
+
+impl ModelConfig {
+    /// Batch sizes `(n_batch, n_ubatch)` of a context: the configured ones, or defaults
+    /// suited to the hardware. Large batches only pay off when layers run on a GPU.
+    pub fn batch_sizes(&self, gpu_offload: bool) -> (u32, u32) {
+        let (auto_batch, auto_ubatch) = if gpu_offload { (2048, 512) } else { (512, 512) };
+        let n_batch = if self.n_batch > 0 { self.n_batch } else { auto_batch };
+        let n_batch = n_batch.min(self.n_ctx.max(1) as u32);
+        let n_ubatch = if self.n_ubatch > 0 { self.n_ubatch } else { auto_ubatch };
+        (n_batch, n_ubatch.min(n_batch))
+    }
+
+    /// Threads used to evaluate the prompt
+    pub fn threads_batch(&self) -> usize {
+        if self.n_threads_batch > 0 { self.n_threads_batch } else { self.n_threads }
+    }
+
+    /// Reduce the threads for efficiency mode
+    pub fn limit_threads(&mut self) {
+        self.n_threads_batch = threads::efficiency_threads(self.threads_batch());
+        self.n_threads = threads::efficiency_threads(self.n_threads);
+    }
+}
+
+impl Default for ModelConfig {
     fn default() -> Self {
         Self {
             model_path: "models/Qwen3-1.7B-IQ4_XS.gguf".to_string(),
             context_size: 2048,
             n_ctx: 2048,
-            n_threads: 4,
+            n_threads: threads::physical_cores(),
+            n_threads_batch: 0,
+            efficiency_mode: false,
+            use_gpu: false,
+            n_gpu_layers: 0, // 0 means CPU only, set to u32::MAX for all layers
+            main_gpu: 0,
+            context_shift: true,
+            prompt_cache_entries: 8,
+            prompt_cache_size_mb: 512,
+            mmproj_path: None,
+            n_batch: 0,
+            n_ubatch: 0,
+            flash_attention: FlashAttention::Auto,
+        }
+    }
+}
+
+/// Settings read for each generation, applied live without reloading the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SamplingConfig {
+    #[ts(type = "number")]
+    pub max_tokens: usize,
+    pub temperature: f32,
+    pub top_p: f32,
+    pub top_k: i32,
+    pub repeat_penalty: f32,
+    /// Report token log probabilities with this many alternatives (None = disabled)
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub logprobs: Option<usize>,
+    /// End the reply early when one of these is met
+    #[serde(default)]
+    pub stop_conditions: Vec<StopCondition>,
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
             max_tokens: 512,
             temperature: 0.8,
             top_p: 0.9,
             top_k: 40,
             repeat_penalty: 1.1,
-            use_gpu: false,
-            n_gpu_layers: 0, // 0 means CPU only, set to u32::MAX for all layers
-            main_gpu: 0,
+            logprobs: None,
+            stop_conditions: Vec::new(),
         }
     }
 }
+
+impl SamplingConfig {
+    /// Check that the values are usable by the samplers
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_tokens == 0 {
+            return Err("max_tokens must be greater than 0".to_string());
+        }
+        if !(0.0..=2.0).contains(&self.temperature) {
+            return Err("temperature must be between 0 and 2".to_string());
+        }
+        if !(self.top_p > 0.0 && self.top_p <= 1.0) {
+            return Err("top_p must be in (0, 1]".to_string());
+        }
+        if self.top_k < 1 {
+            return Err("top_k must be at least 1".to_string());
+        }
+        if self.repeat_penalty <= 0.0 {
+            return Err("repeat_penalty must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Sampling overrides applied to a single generation (agent profiles)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SamplingParams {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<i32>,
+    pub repeat_penalty: Option<f32>,
+    #[ts(type = "number | null")]
+    pub max_tokens: Option<usize>,
+    /// Number of alternatives reported per token (enables log probabilities)
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub logprobs: Option<usize>,
+    /// Replace the configured stop conditions
+    #[serde(default)]
+    pub stop_conditions: Option<Vec<StopCondition>>,
+}
+
+impl SamplingParams {
+    /// Override the configured values with the ones set here
+    pub fn apply_to(&self, config: &mut SamplingConfig) {
+        if let Some(temperature) = self.temperature {
+            config.temperature = temperature;
+        }
+        if let Some(top_p) = self.top_p {
+            config.top_p = top_p;
+        }
+        if let Some(top_k) = self.top_k {
+            config.top_k = top_k;
+        }
+        if let Some(repeat_penalty) = self.repeat_penalty {
+            config.repeat_penalty = repeat_penalty;
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            config.max_tokens = max_tokens;
+        }
+        if let Some(logprobs) = self.logprobs {
+            config.logprobs = Some(logprobs);
+        }
+        if let Some(stop_conditions) = &self.stop_conditions {
+            config.stop_conditions = stop_conditions.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_serializes_flat() {
+        let config = LLMConfig::default();
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["temperature"], serde_json::json!(0.8f32));
+        assert_eq!(json["n_ctx"], 2048);
+
+        let parsed: LLMConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.sampling, config.sampling);
+        assert_eq!(parsed.model, config.model);
+
+        let mut model = ModelConfig::default();
+        assert_eq!(model.batch_sizes(true), (2048, 512));
+        assert_eq!(model.batch_sizes(false), (512, 512));
+        model.n_batch = 4096;
+        model.n_ubatch = 8192;
+        // Never larger than the context, nor the micro-batch larger than the batch
+        assert_eq!(model.batch_sizes(false), (2048, 2048));
+
+        let invalid = SamplingConfig { top_p: 0.0, ..Default::default() };
+        assert!(invalid.validate().is_err());
+        assert!(SamplingConfig::default().validate().is_ok());
+    }
+}
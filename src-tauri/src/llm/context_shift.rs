@@ -0,0 +1,68 @@
+/// Context shifting - keeps long conversations inside the context window
+///
+/// Mirrors the llama.cpp `main` example: the first `n_keep` tokens (system prompt)
+/// are always preserved and the oldest tokens after them are dropped.
+
+/// Share of the context window kept free for generation when a prompt is truncated
+pub const GENERATION_RESERVE_DIVISOR: usize = 4;
+
+/// ChatML system turn at the start of a prompt, preserved when the context is shifted
+pub fn system_prefix(prompt: &str) -> Option<&str> {
+    const END: &str = "<|im_end|>\n";
+    if !prompt.starts_with("<|im_start|>system") {
+        return None;
+    }
+    prompt.find(END).map(|end| &prompt[..end + END.len()])
+}
+
+/// Maximum prompt length leaving room for generation in a window of `n_ctx` tokens
+pub fn prompt_budget(n_ctx: usize) -> usize {
+    n_ctx - n_ctx / GENERATION_RESERVE_DIVISOR
+}
+
+/// Drops the oldest tokens after the first `n_keep` so that at most `limit` remain
+/// Returns the number of dropped tokens
+pub fn truncate_prompt<T>(tokens: &mut Vec<T>, n_keep: usize, limit: usize) -> usize {
+    if tokens.len() <= limit {
+        return 0;
+    }
+    let n_keep = n_keep.min(limit);
+    let n_drop = tokens.len() - limit;
+    tokens.drain(n_keep..n_keep + n_drop);
+    n_drop
+}
+
+/// Number of tokens to discard from the KV cache when it is full
+/// (half of the non-kept tokens, like llama.cpp)
+pub fn shift_amount(n_past: usize, n_keep: usize) -> usize {
+    (n_past.saturating_sub(n_keep) / 2).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_prompt_keeps_system_prefix() {
+        let mut tokens: Vec<u32> = (0..10).collect();
+        let dropped = truncate_prompt(&mut tokens, 2, 6);
+        assert_eq!(dropped, 4);
+        assert_eq!(tokens, vec![0, 1, 6, 7, 8, 9]);
+
+        let mut short: Vec<u32> = (0..4).collect();
+        assert_eq!(truncate_prompt(&mut short, 2, 6), 0);
+    }
+
+    #[test]
+    fn test_system_prefix() {
+        let prompt = "<|im_start|>system\nBe brief<|im_end|>\n<|im_start|>user\nHi<|im_end|>\n";
+        assert_eq!(system_prefix(prompt), Some("<|im_start|>system\nBe brief<|im_end|>\n"));
+        assert_eq!(system_prefix("<|im_start|>user\nHi<|im_end|>\n"), None);
+    }
+
+    #[test]
+    fn test_shift_amount() {
+        assert_eq!(shift_amount(2048, 48), 1000);
+        assert_eq!(shift_amount(10, 10), 1);
+    }
+}
@@ -0,0 +1,140 @@
+/// Context window usage - what the next prompt of a session is made of
+///
+/// Components are listed in prompt order with their token counts. The
+/// truncation follows `context_shift`: the first system turn is kept and the
+/// oldest tokens after it are dropped until the prompt fits in its budget.
+
+use super::backend::ChatRole;
+use super::context_shift;
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Part of the prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextComponentKind {
+    SystemPrompt,
+    /// Long-term memories recalled for the last user message
+    Memories,
+    Message,
+    /// Document excerpts (RAG chunks) included in a message
+    Documents,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct ContextComponent {
+    pub kind: ContextComponentKind,
+    /// Message the component belongs to (messages and their documents)
+    pub message_id: Option<String>,
+    pub role: Option<ChatRole>,
+    #[ts(type = "number")]
+    pub tokens: usize,
+    /// Tokens of this component dropped when the prompt is truncated
+    #[ts(type = "number")]
+    pub truncated_tokens: usize,
+}
+
+impl ContextComponent {
+    pub fn new(kind: ContextComponentKind, tokens: usize) -> Self {
+        Self { kind, message_id: None, role: None, tokens, truncated_tokens: 0 }
+    }
+
+    pub fn for_message(mut self, message_id: String, role: ChatRole) -> Self {
+        self.message_id = Some(message_id);
+        self.role = Some(role);
+        self
+    }
+}
+
+/// Token usage of the context window
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct ContextBreakdown {
+    #[ts(type = "number")]
+    pub n_ctx: usize,
+    /// Tokens kept free for the reply
+    #[ts(type = "number")]
+    pub reserved_output_tokens: usize,
+    pub components: Vec<ContextComponent>,
+    #[ts(type = "number")]
+    pub prompt_tokens: usize,
+    /// Oldest tokens dropped from the prompt (0 = everything fits)
+    #[ts(type = "number")]
+    pub truncated_tokens: usize,
+    /// Counts approximated from the text length (no model loaded)
+    pub estimated: bool,
+}
+
+impl ContextBreakdown {
+    /// Mark what the engine would drop to fit `components` in `n_ctx` tokens
+    pub fn new(n_ctx: usize, mut components: Vec<ContextComponent>, estimated: bool) -> Self {
+        let budget = context_shift::prompt_budget(n_ctx);
+        let prompt_tokens: usize = components.iter().map(|c| c.tokens).sum();
+        let truncated_tokens = prompt_tokens.saturating_sub(budget);
+
+        // Only the leading system turn is preserved
+        let kept = usize::from(components.first().is_some_and(|c| c.kind == ContextComponentKind::SystemPrompt));
+        let mut remaining = truncated_tokens;
+        for component in components.iter_mut().skip(kept) {
+            if remaining == 0 {
+                break;
+            }
+            component.truncated_tokens = component.tokens.min(remaining);
+            remaining -= component.truncated_tokens;
+        }
+
+        Self {
+            n_ctx,
+            reserved_output_tokens: n_ctx - budget,
+            components,
+            prompt_tokens,
+            truncated_tokens,
+            estimated,
+        }
+    }
+}
+
+/// ChatML turn of a message, as formatted by the engine
+pub fn chat_turn(role: ChatRole, content: &str) -> String {
+    format!("<|im_start|>{}\n{}<|im_end|>\n", role.as_str(), content)
+}
+
+/// Rough token count of a text without a tokenizer (about 4 characters per token)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_breakdown() {
+        let message = |id: &str, tokens| {
+            ContextComponent::new(ContextComponentKind::Message, tokens).for_message(id.to_string(), ChatRole::User)
+        };
+        let components = vec![
+            ContextComponent::new(ContextComponentKind::SystemPrompt, 100),
+            ContextComponent::new(ContextComponentKind::Memories, 50),
+            message("1", 400),
+            message("2", 300),
+        ];
+
+        // Budget of 2048 - 512 tokens: everything fits
+        let breakdown = ContextBreakdown::new(2048, components.clone(), false);
+        assert_eq!((breakdown.prompt_tokens, breakdown.truncated_tokens), (850, 0));
+        assert_eq!(breakdown.reserved_output_tokens, 512);
+        assert!(breakdown.components.iter().all(|c| c.truncated_tokens == 0));
+
+        // Budget of 513 tokens: the system prompt is kept, memories and most of the first message are dropped
+        let breakdown = ContextBreakdown::new(683, components, false);
+        assert_eq!(breakdown.truncated_tokens, 850 - 513);
+        let truncated: Vec<usize> = breakdown.components.iter().map(|c| c.truncated_tokens).collect();
+        assert_eq!(truncated, vec![0, 50, 287, 0]);
+
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+        assert_eq!(estimate_tokens("abcdefghi"), 3);
+    }
+}
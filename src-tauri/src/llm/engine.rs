@@ -1,48 +1,129 @@
 /// LLM Engine Module
 /// Native llama.cpp integration for standalone all-in-one application
 
-use super::config::LLMConfig;
+use super::backend::{ChatMessage, ChatRole, LLMBackend};
+use super::capabilities::{self, ModelCapabilities};
+use super::context_shift;
+use super::gguf;
+use super::logprobs::{self, TokenAlternative, TokenLogprob};
+use super::postprocess;
+use super::prompt_cache::{PromptCache, PromptCacheStats, MIN_PREFIX_TOKENS};
+use super::stop;
+use super::config::{LLMConfig, ModelConfig, SamplingConfig};
+use crate::error::AppError;
 use anyhow::{Context, Result};
 use llama_cpp_2::{
+    context::{params::LlamaContextParams, LlamaContext},
     llama_backend::LlamaBackend,
     llama_batch::LlamaBatch,
     model::{AddBos, LlamaModel, params::LlamaModelParams},
+    mtmd::{MtmdBitmap, MtmdContext, MtmdContextParams, MtmdInputText},
     sampling::LlamaSampler,
+    token::LlamaToken,
 };
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
+
+/// Maximum number of prompts processed in a single batched decode
+pub const MAX_BATCH_PROMPTS: usize = 16;
+
+/// Batch size used to evaluate prompts containing images
+const VISION_BATCH_SIZE: i32 = 512;
+
+/// Header opening the assistant turn at the end of every ChatML prompt
+const ASSISTANT_HEADER: &str = "<|im_start|>assistant\n";
+
+/// Maximum context of the reranker, whatever its trained context
+const RERANK_MAX_CTX: u32 = 4096;
 
 /// LLM model response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct LLMResponse {
     pub text: String,
     pub tool_calls: Vec<ToolCall>,
+    #[ts(type = "number")]
     pub tokens_generated: usize,
+    /// False when generation stopped at `max_tokens` before the end of the reply (EOS)
     pub done: bool,
+    /// Tokens dropped from the context window by context shifting
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub truncated_tokens: usize,
+    /// Per-token log probabilities, when requested with `LLMConfig::logprobs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub logprobs: Option<Vec<TokenLogprob>>,
+    /// Reasoning block captured by the post-processing (`<think>...</think>`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub reasoning: Option<String>,
 }
 
 /// Tool call detected in response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
 pub struct ToolCall {
     pub name: String,
     pub arguments: serde_json::Value,
 }
 
+/// Raw result of the decode loop
+struct Completion {
+    text: String,
+    tokens_generated: usize,
+    /// EOS reached (or generation cancelled) before `max_tokens`
+    done: bool,
+    truncated_tokens: usize,
+    logprobs: Option<Vec<TokenLogprob>>,
+}
+
+/// Receives the generated text piece by piece (streaming); an error stops the generation
+type OnPiece<'a> = Option<&'a mut (dyn FnMut(String) -> Result<()> + Send)>;
+
 /// Wrapper for LlamaModel to make it Send + Sync
 /// SAFETY: We ensure single-threaded access via Mutex
 struct ModelWrapper(LlamaModel);
 unsafe impl Send for ModelWrapper {}
 unsafe impl Sync for ModelWrapper {}
 
+/// Wrapper for the vision projector context to make it Send + Sync
+struct VisionWrapper(MtmdContext);
+unsafe impl Send for VisionWrapper {}
+unsafe impl Sync for VisionWrapper {}
+
 /// Main LLM engine with native llama.cpp integration
 pub struct LLMEngine {
     pub config: LLMConfig,
     backend: Arc<LlamaBackend>,
     model: Arc<Mutex<Option<ModelWrapper>>>,
-    conversation_history: Arc<Mutex<String>>,
+    /// Vision projector loaded with the model, if any
+    vision: Arc<Mutex<Option<VisionWrapper>>>,
+    /// Cross-encoder rescoring retrieved passages, with its path
+    reranker: Arc<Mutex<Option<(ModelWrapper, String)>>>,
+    pub(super) capabilities: Arc<Mutex<Option<ModelCapabilities>>>,
+    pub(super) prompt_cache: Arc<std::sync::Mutex<PromptCache>>,
+    /// Set to stop the running generation (the text generated so far is returned)
+    pub(super) cancel: Arc<AtomicBool>,
+}
+
+/// llama.cpp backend shared by the engines of the process
+///
+/// llama.cpp can only be initialized once at a time: a second engine (profile
+/// switch, tests) reuses the backend of the first while it is alive.
+fn shared_backend() -> Result<Arc<LlamaBackend>> {
+    static BACKEND: std::sync::Mutex<std::sync::Weak<LlamaBackend>> = std::sync::Mutex::new(std::sync::Weak::new());
+    let mut shared = BACKEND.lock().unwrap();
+    if let Some(backend) = shared.upgrade() {
+        return Ok(backend);
+    }
+    let backend = Arc::new(LlamaBackend::init().context("Failed to initialize llama.cpp backend")?);
+    *shared = Arc::downgrade(&backend);
+    Ok(backend)
 }
 
 impl LLMEngine {
@@ -50,15 +131,19 @@ impl LLMEngine {
     pub fn new(config: LLMConfig) -> Result<Self> {
         info!("Initializing native llama.cpp LLM engine...");
         
-        // Initialize llama.cpp backend
-        let backend = LlamaBackend::init()
-            .context("Failed to initialize llama.cpp backend")?;
+        let backend = shared_backend()?;
         
+        let prompt_cache = PromptCache::new(config.model.prompt_cache_entries, config.model.prompt_cache_size_mb);
+
         Ok(Self {
             config,
-            backend: Arc::new(backend),
+            backend,
             model: Arc::new(Mutex::new(None)),
-            conversation_history: Arc::new(Mutex::new(String::new())),
+            vision: Arc::new(Mutex::new(None)),
+            reranker: Arc::new(Mutex::new(None)),
+            capabilities: Arc::new(Mutex::new(None)),
+            prompt_cache: Arc::new(std::sync::Mutex::new(prompt_cache)),
+            cancel: Arc::new(AtomicBool::new(false)),
         })
     }
 
@@ -73,7 +158,7 @@ impl LLMEngine {
         }
         
         // Check if model file exists
-        let model_path = std::path::Path::new(&self.config.model_path);
+        let model_path = std::path::Path::new(&self.config.model.model_path);
         if !model_path.exists() {
             anyhow::bail!(
                 "Model file not found: {}",
@@ -81,19 +166,24 @@ impl LLMEngine {
             );
         }
 
+        // llama.cpp aborts the process on some malformed files: check them first
+        let model_name = model_path.display().to_string();
+        gguf::validate(model_path).map_err(|e| AppError::model_corrupt(&model_name, e))?;
+        gguf::probe_in_child(model_path).map_err(|e| AppError::model_corrupt(&model_name, e))?;
+        
         info!("Loading model from: {}", model_path.display());
         
         // Configure model parameters with GPU settings
         let mut model_params = LlamaModelParams::default();
         
-        if self.config.use_gpu {
+        if self.config.model.use_gpu {
             info!("GPU acceleration enabled");
-            info!("GPU layers: {}", if self.config.n_gpu_layers == u32::MAX { "all".to_string() } else { self.config.n_gpu_layers.to_string() });
-            info!("Main GPU: {}", self.config.main_gpu);
+            info!("GPU layers: {}", if self.config.model.n_gpu_layers == u32::MAX { "all".to_string() } else { self.config.model.n_gpu_layers.to_string() });
+            info!("Main GPU: {}", self.config.model.main_gpu);
             
             model_params = model_params
-                .with_n_gpu_layers(self.config.n_gpu_layers)
-                .with_main_gpu(self.config.main_gpu);
+                .with_n_gpu_layers(self.config.model.n_gpu_layers)
+                .with_main_gpu(self.config.model.main_gpu);
         } else {
             info!("GPU acceleration disabled - using CPU only");
             model_params = model_params.with_n_gpu_layers(0);
@@ -102,21 +192,74 @@ impl LLMEngine {
         // Load the model with GPU parameters
         let model = LlamaModel::load_from_file(
             &self.backend,
-            &self.config.model_path,
+            &self.config.model.model_path,
             &model_params,
         )
         .context("Failed to load GGUF model")?;
         
         info!("Model loaded successfully!");
-        info!("Context size: {} tokens", self.config.n_ctx);
-        info!("Threads: {}", self.config.n_threads);
+        info!("Context size: {} tokens", self.config.model.n_ctx);
+        info!("Threads: {}", self.config.model.n_threads);
+        let (n_batch, n_ubatch) = self.config.model.batch_sizes(self.gpu_offload());
+        info!("Batch: {} tokens, micro-batch: {}, flash attention: {:?}", n_batch, n_ubatch, self.config.model.flash_attention);
         info!("GPU info: {}", self.gpu_info());
         
+        *self.vision.lock().await = self.load_vision_projector(&model)?.map(VisionWrapper);
         *model_lock = Some(ModelWrapper(model));
         
         Ok(())
     }
 
+    /// Load the vision projector configured in `mmproj_path`, or found next to the model
+    ///
+    /// A configured projector that fails to load is an error, an auto-detected one
+    /// only disables image support.
+    fn load_vision_projector(&self, model: &LlamaModel) -> Result<Option<MtmdContext>> {
+        let (path, explicit) = match &self.config.model.mmproj_path {
+            Some(path) => (std::path::PathBuf::from(path), true),
+            None => match capabilities::find_vision_projector(std::path::Path::new(&self.config.model.model_path)) {
+                Some(path) => (path, false),
+                None => return Ok(None),
+            },
+        };
+
+        if !path.exists() {
+            anyhow::bail!("Vision projector not found: {}", path.display());
+        }
+
+        let params = MtmdContextParams {
+            use_gpu: self.config.model.use_gpu,
+            n_threads: self.config.model.n_threads as i32,
+            ..MtmdContextParams::default()
+        };
+        let result = MtmdContext::init_from_file(&path.to_string_lossy(), model, &params)
+            .map_err(|e| anyhow::anyhow!("Failed to load vision projector {}: {}", path.display(), e))
+            .and_then(|ctx| {
+                if ctx.support_vision() {
+                    Ok(ctx)
+                } else {
+                    anyhow::bail!("{} is not a vision projector", path.display())
+                }
+            });
+
+        match result {
+            Ok(ctx) => {
+                info!("Vision projector loaded from: {}", path.display());
+                Ok(Some(ctx))
+            }
+            Err(e) if explicit => Err(e),
+            Err(e) => {
+                warn!("{} - images are disabled", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Whether the loaded model can read images
+    pub async fn has_vision(&self) -> bool {
+        self.vision.lock().await.is_some()
+    }
+
     /// Detect GPU availability and return recommended configuration
     pub fn detect_gpu_config() -> (bool, String) {
         // Check for NVIDIA GPU (CUDA)
@@ -140,13 +283,41 @@ impl LLMEngine {
         (false, "No GPU acceleration available - using CPU".to_string())
     }
 
+    /// Whether model layers are offloaded to a GPU
+    fn gpu_offload(&self) -> bool {
+        Self::gpu_offload_for(&self.config.model)
+    }
+
+    /// Whether a given configuration offloads model layers to a GPU
+    pub fn gpu_offload_for(config: &ModelConfig) -> bool {
+        config.use_gpu && config.n_gpu_layers > 0 && Self::detect_gpu_config().0
+    }
+
+    /// Parameters of a context of `n_ctx` tokens with the configured threads,
+    /// batch sizes and flash attention
+    fn context_params(&self, n_ctx: usize) -> LlamaContextParams {
+        let (n_batch, n_ubatch) = self.config.model.batch_sizes(self.gpu_offload());
+        LlamaContextParams::default()
+            .with_n_ctx(NonZeroU32::new(n_ctx as u32))
+            .with_n_threads(self.config.model.n_threads as i32)
+            .with_n_threads_batch(self.config.model.threads_batch() as i32)
+            .with_n_batch(n_batch)
+            .with_n_ubatch(n_ubatch)
+            .with_flash_attention_policy(self.config.model.flash_attention.policy())
+    }
+
     /// Get GPU information and recommendations
     pub fn gpu_info(&self) -> String {
+        Self::gpu_info_for(&self.config.model)
+    }
+
+    /// Get GPU information for a given configuration
+    pub fn gpu_info_for(config: &ModelConfig) -> String {
         let (has_gpu, info) = Self::detect_gpu_config();
         
-        if self.config.use_gpu && has_gpu {
+        if config.use_gpu && has_gpu {
             format!("GPU: Enabled - {}", info)
-        } else if self.config.use_gpu && !has_gpu {
+        } else if config.use_gpu && !has_gpu {
             format!("GPU: Requested but not available - {}", info)
         } else {
             format!("GPU: Disabled - {}", info)
@@ -158,228 +329,932 @@ impl LLMEngine {
         self.model.lock().await.is_some()
     }
 
-    /// Clear conversation history to start a fresh conversation
-    pub async fn clear_conversation(&self) {
-        let mut history = self.conversation_history.lock().await;
-        history.clear();
-        info!("Conversation history cleared");
+    /// Context size the loaded model was trained with (GGUF `context_length`)
+    pub async fn trained_context(&self) -> Option<usize> {
+        self.model
+            .lock()
+            .await
+            .as_ref()
+            .map(|model| model.0.n_ctx_train() as usize)
+            .filter(|n| *n > 0)
+    }
+
+    /// Generate the assistant reply for a full conversation
+    ///
+    /// The engine is stateless: the caller (the `ContextManager` for sessions)
+    /// owns the history and passes every message on each call.
+    pub async fn generate(&self, messages: &[ChatMessage]) -> Result<LLMResponse> {
+        self.respond(messages, None).await
+    }
+
+    /// Generate a streaming response (callback receives chunks)
+    ///
+    /// Same decode loop as `generate` (sampling, context shifting, prompt cache,
+    /// images), the pieces being forwarded as they are produced.
+    pub async fn generate_stream<F>(
+        &self,
+        messages: &[ChatMessage],
+        mut callback: F,
+    ) -> Result<LLMResponse>
+    where
+        F: FnMut(String) -> Result<()> + Send,
+    {
+        self.respond(messages, Some(&mut callback)).await
+    }
+
+    async fn respond(&self, messages: &[ChatMessage], on_piece: OnPiece<'_>) -> Result<LLMResponse> {
+        if !self.is_loaded().await {
+            return Err(AppError::NoModelLoaded.into());
+        }
+
+        info!("Generating response for {} messages", messages.len());
+
+        let has_images = messages.iter().any(|m| !m.images.is_empty());
+        let completion = if has_images && self.has_vision().await {
+            self.run_vision_completion(messages, self.config.sampling.max_tokens, on_piece).await
+        } else {
+            if has_images {
+                warn!("The loaded model has no vision projector, images are ignored");
+            }
+            let prompt = self.build_prompt(messages).await;
+            self.run_completion(&prompt, self.config.sampling.max_tokens, on_piece).await
+        };
+        let completion = completion.map_err(|e| self.with_processed_partial(e, ""))?;
+
+        let processed = postprocess::process(&completion.text, &self.config.postprocess);
+        Ok(LLMResponse {
+            text: processed.text,
+            tool_calls: Self::parse_tool_calls(&completion.text),
+            tokens_generated: completion.tokens_generated,
+            done: completion.done,
+            truncated_tokens: completion.truncated_tokens,
+            logprobs: completion.logprobs,
+            reasoning: processed.reasoning,
+        })
+    }
+
+    /// Resume an assistant reply: the prompt ends inside its turn, so the model
+    /// continues `partial` instead of starting a new answer. The returned text is
+    /// the whole reply, `partial` included.
+    pub async fn continue_reply(&self, messages: &[ChatMessage], partial: &str) -> Result<LLMResponse> {
+        if !self.is_loaded().await {
+            return Err(AppError::NoModelLoaded.into());
+        }
+
+        info!("Continuing a reply of {} characters", partial.len());
+
+        let prompt = format!("{}{}", self.build_prompt(messages).await, partial);
+        let completion = self
+            .run_completion(&prompt, self.config.sampling.max_tokens, None)
+            .await
+            .map_err(|e| self.with_processed_partial(e, partial))?;
+
+        let processed = postprocess::process(&format!("{}{}", partial, completion.text), &self.config.postprocess);
+        Ok(LLMResponse {
+            text: processed.text,
+            tool_calls: Self::parse_tool_calls(&completion.text),
+            tokens_generated: completion.tokens_generated,
+            done: completion.done,
+            truncated_tokens: completion.truncated_tokens,
+            logprobs: completion.logprobs,
+            reasoning: processed.reasoning,
+        })
+    }
+
+    /// Post-processing of the text generated before a failure: code fences are
+    /// left open so that the reply can be continued
+    fn process_partial(&self, text: &str) -> String {
+        let config = postprocess::PostProcessConfig { normalize_markdown: false, ..self.config.postprocess.clone() };
+        postprocess::process(text, &config).text
+    }
+
+    /// Post-process the partial text of a generation error, after `prefix` (continued reply)
+    fn with_processed_partial(&self, error: anyhow::Error, prefix: &str) -> anyhow::Error {
+        match error.downcast::<AppError>() {
+            Ok(AppError::GenerationFailed { message, partial_text, message_id }) => AppError::GenerationFailed {
+                message,
+                partial_text: partial_text.map(|text| self.process_partial(&format!("{}{}", prefix, text))),
+                message_id,
+            }
+            .into(),
+            Ok(other) => other.into(),
+            Err(error) => error,
+        }
     }
 
-    /// Get current conversation history
-    pub async fn get_conversation_history(&self) -> String {
-        self.conversation_history.lock().await.clone()
+    /// Format the conversation for the loaded model
+    async fn build_prompt(&self, messages: &[ChatMessage]) -> String {
+        // Models whose template ignores the system role get it folded into the first user turn
+        let supports_system = self
+            .capabilities()
+            .await
+            .map(|caps| caps.system_prompt)
+            .unwrap_or(true);
+        if supports_system {
+            Self::format_chat_prompt(messages)
+        } else {
+            Self::format_chat_prompt(&fold_system_messages(messages))
+        }
     }
 
-    /// Generate a response from a prompt
-    pub async fn generate(&self, prompt: &str) -> Result<LLMResponse> {
+    /// Format a full conversation with the ChatML template used by Qwen models
+    pub fn format_chat_prompt(messages: &[ChatMessage]) -> String {
+        let mut prompt = String::new();
+        for message in messages {
+            prompt.push_str("<|im_start|>");
+            prompt.push_str(message.role.as_str());
+            prompt.push('\n');
+            prompt.push_str(&message.content);
+            prompt.push_str("<|im_end|>\n");
+        }
+        prompt.push_str(ASSISTANT_HEADER);
+        prompt
+    }
+
+    /// Capabilities of the loaded model, if they were probed or restored from cache
+    pub async fn capabilities(&self) -> Option<ModelCapabilities> {
+        self.capabilities.lock().await.clone()
+    }
+
+    /// Set the capabilities of the loaded model (e.g. from the per-model cache)
+    pub async fn set_capabilities(&self, capabilities: ModelCapabilities) {
+        *self.capabilities.lock().await = Some(capabilities);
+    }
+
+    /// Probe the loaded model with metadata checks and one short test generation
+    pub async fn probe_capabilities(&self) -> Result<ModelCapabilities> {
         if !self.is_loaded().await {
-            anyhow::bail!("No model is loaded. Call load_model() first.");
+            return Err(AppError::NoModelLoaded.into());
         }
 
-        info!("Generating response for prompt ({}...)", &prompt[..50.min(prompt.len())]);
+        info!("Probing model capabilities...");
+
+        let (template, fim) = {
+            let model_lock = self.model.lock().await;
+            let model = &model_lock
+                .as_ref()
+                .context("Model not loaded despite is_loaded check")?
+                .0;
+            let template = model.meta_val_str("tokenizer.chat_template").ok();
+            let fim = capabilities::FIM_METADATA_KEYS
+                .iter()
+                .any(|key| model.meta_val_str(key).is_ok());
+            (template, fim)
+        };
+
+        let probe = Self::format_chat_prompt(&[
+            ChatMessage::system(capabilities::TOOL_CALL_PROBE_SYSTEM),
+            ChatMessage::user(capabilities::TOOL_CALL_PROBE_USER),
+        ]);
+        let tool_calls = match self
+            .run_completion(&probe, capabilities::TOOL_CALL_PROBE_MAX_TOKENS, None)
+            .await
+        {
+            Ok(completion) => capabilities::looks_like_tool_call(&completion.text, "echo"),
+            Err(e) => {
+                warn!("Tool-call probe failed: {}", e);
+                false
+            }
+        };
+
+        let result = ModelCapabilities {
+            system_prompt: capabilities::template_supports_system(template.as_deref()),
+            tool_calls,
+            fim,
+            vision: self.has_vision().await,
+            has_chat_template: template.is_some(),
+            probed_at: chrono::Utc::now().timestamp(),
+        };
+
+        info!("Model capabilities: {:?}", result);
+        self.set_capabilities(result.clone()).await;
+        Ok(result)
+    }
+
+    /// Create the sampler chain with configured parameters
+    fn build_sampler(&self) -> LlamaSampler {
+        // This uses proper sampling (temperature, top_k, top_p, penalties) instead of greedy sampling
+        // Order matters: penalties -> top_k -> top_p -> temperature -> distribution
+        // See: https://github.com/ggerganov/llama.cpp/blob/master/examples/main/README.md#sampling
+        LlamaSampler::chain_simple([
+            LlamaSampler::penalties(
+                64,  // penalty_last_n: consider last 64 tokens for repeat detection
+                self.config.sampling.repeat_penalty,  // penalty_repeat: from config (default 1.1)
+                0.0, // penalty_freq: frequency penalty (0 = disabled for now)
+                0.0, // penalty_present: presence penalty (0 = disabled for now)
+            ),
+            LlamaSampler::top_k(self.config.sampling.top_k),  // Keep only top K tokens (default 40)
+            LlamaSampler::top_p(self.config.sampling.top_p, 1),  // Nucleus sampling with top_p (default 0.9), min_keep=1
+            LlamaSampler::temp(self.config.sampling.temperature),  // Apply temperature (default 0.7)
+            LlamaSampler::dist(0),  // Sample from distribution (seed=0 for deterministic per session)
+        ])
+    }
+
+    /// Generate independent responses for several prompts in one batched decode
+    ///
+    /// Each prompt is a single user turn and gets its own sequence id in the batch,
+    /// so the prompts are evaluated together instead of one generation per prompt.
+    pub async fn generate_batch(&self, prompts: &[String]) -> Result<Vec<LLMResponse>> {
+        if !self.is_loaded().await {
+            return Err(AppError::NoModelLoaded.into());
+        }
+        if prompts.is_empty() {
+            return Ok(vec![]);
+        }
+        if prompts.len() > MAX_BATCH_PROMPTS {
+            anyhow::bail!("Too many prompts in batch: {} (max {})", prompts.len(), MAX_BATCH_PROMPTS);
+        }
+
+        info!("Generating batch of {} prompts", prompts.len());
 
         let model_lock = self.model.lock().await;
         let model = &model_lock
             .as_ref()
             .context("Model not loaded despite is_loaded check")?
             .0;
-        
-        // Add the new user message to conversation history with proper format
-        let mut history = self.conversation_history.lock().await;
-        if !history.is_empty() {
-            history.push_str("\n");
-        }
-        // Use Qwen3 chat format: <|im_start|>user\n{message}<|im_end|>
-        history.push_str("<|im_start|>user\n");
-        history.push_str(prompt);
-        history.push_str("<|im_end|>\n<|im_start|>assistant\n");
-        
+
+        let sequences = prompts
+            .iter()
+            .map(|prompt| {
+                let formatted = Self::format_chat_prompt(&[ChatMessage::user(prompt.as_str())]);
+                model
+                    .str_to_token(&formatted, AddBos::Always)
+                    .context("Failed to tokenize batch prompt")
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let n_seq = sequences.len();
+        let prompt_tokens: usize = sequences.iter().map(Vec::len).sum();
+        let max_tokens = self.config.sampling.max_tokens;
+
+        // The KV cache is shared by every sequence of the batch
+        let n_ctx = prompt_tokens + n_seq * max_tokens;
+        let ctx_params = self.context_params(n_ctx)
+            .with_n_batch(prompt_tokens.max(n_seq) as u32)
+            .with_n_seq_max(n_seq as u32);
+
+        let mut ctx = model
+            .new_context(&self.backend, ctx_params)
+            .context("Failed to create batch context")?;
+
+        let mut batch = LlamaBatch::new(prompt_tokens.max(n_seq), n_seq as i32);
+
+        // Position of the logits of each sequence in the current batch
+        let mut logits_index = vec![0i32; n_seq];
+        for (seq_id, tokens) in sequences.iter().enumerate() {
+            for (i, token) in tokens.iter().enumerate() {
+                let is_last = i == tokens.len() - 1;
+                batch
+                    .add(*token, i as i32, &[seq_id as i32], is_last)
+                    .context("Failed to add token to batch")?;
+            }
+            logits_index[seq_id] = batch.n_tokens() - 1;
+        }
+
+        ctx
+            .decode(&mut batch)
+            .context("Failed to decode prompt batch")?;
+
+        let mut samplers: Vec<LlamaSampler> = (0..n_seq).map(|_| self.build_sampler()).collect();
+        let mut texts = vec![String::new(); n_seq];
+        let mut generated = vec![0usize; n_seq];
+        let mut positions: Vec<i32> = sequences.iter().map(|t| t.len() as i32).collect();
+        let mut active = vec![true; n_seq];
+        let mut cancelled = false;
+
+        for _ in 0..max_tokens {
+            if self.cancel.load(Ordering::Relaxed) {
+                info!("Batch generation cancelled");
+                cancelled = true;
+                break;
+            }
+            batch.clear();
+
+            for seq_id in 0..n_seq {
+                if !active[seq_id] {
+                    continue;
+                }
+
+                let next_token = samplers[seq_id].sample(&ctx, logits_index[seq_id]);
+                if model.is_eog_token(next_token) {
+                    active[seq_id] = false;
+                    continue;
+                }
+
+                if let Ok(piece) = model.token_to_str(next_token, llama_cpp_2::model::Special::Tokenize) {
+                    texts[seq_id].push_str(&piece);
+                    generated[seq_id] += 1;
+                } else {
+                    warn!("Failed to decode token {}. Continuing generation...", next_token.0);
+                }
+                samplers[seq_id].accept(next_token);
+
+                batch
+                    .add(next_token, positions[seq_id], &[seq_id as i32], true)
+                    .context("Failed to add generated token to batch")?;
+                logits_index[seq_id] = batch.n_tokens() - 1;
+                positions[seq_id] += 1;
+            }
+
+            if batch.n_tokens() == 0 {
+                break;
+            }
+
+            ctx
+                .decode(&mut batch)
+                .context("Failed to decode generated tokens")?;
+        }
+
+        info!("Batch generation done: {} tokens", generated.iter().sum::<usize>());
+
+        Ok(texts
+            .into_iter()
+            .zip(generated)
+            .zip(active)
+            .map(|((text, tokens_generated), active)| {
+                let processed = postprocess::process(&text, &self.config.postprocess);
+                LLMResponse {
+                    tool_calls: Self::parse_tool_calls(&text),
+                    text: processed.text,
+                    tokens_generated,
+                    done: cancelled || !active,
+                    truncated_tokens: 0,
+                    logprobs: None,
+                    reasoning: processed.reasoning,
+                }
+            })
+            .collect())
+    }
+
+    /// Compute one embedding per text with the loaded model
+    ///
+    /// Token embeddings are mean pooled and L2 normalized, so the cosine similarity
+    /// of two embeddings is their dot product. A text is decoded in one micro-batch
+    /// so that pooling sees all its tokens: texts longer than that are truncated.
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if !self.is_loaded().await {
+            return Err(AppError::NoModelLoaded.into());
+        }
+
+        let model_lock = self.model.lock().await;
+        let model = &model_lock
+            .as_ref()
+            .context("Model not loaded despite is_loaded check")?
+            .0;
+
+        let ctx_params = self.context_params(self.config.model.n_ctx)
+            .with_embeddings(true)
+            .with_pooling_type(llama_cpp_2::context::params::LlamaPoolingType::Mean);
+        let mut ctx = model
+            .new_context(&self.backend, ctx_params)
+            .context("Failed to create embedding context")?;
+        let max_tokens = ctx.n_ubatch().min(ctx.n_batch()) as usize;
+
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for text in texts {
+            let mut tokens = model
+                .str_to_token(text, AddBos::Always)
+                .context("Failed to tokenize text to embed")?;
+            tokens.truncate(max_tokens);
+
+            let mut batch = LlamaBatch::new(tokens.len().max(1), 1);
+            batch
+                .add_sequence(&tokens, 0, false)
+                .context("Failed to add tokens to batch")?;
+
+            ctx.clear_kv_cache();
+            ctx.decode(&mut batch).context("Failed to decode text to embed")?;
+
+            let embedding = ctx
+                .embeddings_seq_ith(0)
+                .context("Failed to read embedding")?;
+            let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt().max(f32::EPSILON);
+            embeddings.push(embedding.iter().map(|v| v / norm).collect());
+        }
+
+        debug!("Computed {} embeddings", embeddings.len());
+        Ok(embeddings)
+    }
+
+    /// Number of tokens of each text with the tokenizer of the loaded model
+    pub async fn count_tokens(&self, texts: &[String]) -> Result<Vec<usize>> {
+        if !self.is_loaded().await {
+            return Err(AppError::NoModelLoaded.into());
+        }
+
+        let model_lock = self.model.lock().await;
+        let model = &model_lock
+            .as_ref()
+            .context("Model not loaded despite is_loaded check")?
+            .0;
+
+        texts
+            .iter()
+            .map(|text| {
+                model
+                    .str_to_token(text, AddBos::Never)
+                    .map(|tokens| tokens.len())
+                    .context("Failed to tokenize text")
+            })
+            .collect()
+    }
+
+    /// Load a reranker (cross-encoder GGUF) next to the chat model
+    ///
+    /// Nothing happens if this file is already the loaded reranker; another one
+    /// replaces it once loaded (the current one is kept if the new one fails).
+    pub async fn load_reranker(&self, path: &str) -> Result<()> {
+        let mut reranker = self.reranker.lock().await;
+        if reranker.as_ref().is_some_and(|(_, loaded)| loaded == path) {
+            return Ok(());
+        }
+
+        let model_path = std::path::Path::new(path);
+        if !model_path.exists() {
+            anyhow::bail!("Reranker model not found: {}", model_path.display());
+        }
+        gguf::validate(model_path).map_err(|e| AppError::model_corrupt(path, e))?;
+        gguf::probe_in_child(model_path).map_err(|e| AppError::model_corrupt(path, e))?;
+
+        let n_gpu_layers = if self.config.model.use_gpu { self.config.model.n_gpu_layers } else { 0 };
+        let model_params = LlamaModelParams::default()
+            .with_n_gpu_layers(n_gpu_layers)
+            .with_main_gpu(self.config.model.main_gpu);
+        let model = LlamaModel::load_from_file(&self.backend, path, &model_params)
+            .context("Failed to load reranker model")?;
+        info!("Reranker loaded from: {}", path);
+        *reranker = Some((ModelWrapper(model), path.to_string()));
+        Ok(())
+    }
+
+    /// Release the reranker
+    pub async fn unload_reranker(&self) {
+        if self.reranker.lock().await.take().is_some() {
+            info!("Reranker unloaded");
+        }
+    }
+
+    /// Relevance score of each passage for the query, computed by the reranker
+    ///
+    /// Each pair is encoded as `[BOS] query [EOS] [SEP] passage [EOS]` and rank
+    /// pooled; higher scores are more relevant. A cross-encoder is not causal, so
+    /// the whole pair must fit in one micro-batch: pairs longer than the context are
+    /// truncated. A passage that fails to score gets `f32::NEG_INFINITY`.
+    pub async fn rerank(&self, query: &str, passages: &[String]) -> Result<Vec<f32>> {
+        let reranker_lock = self.reranker.lock().await;
+        let (ModelWrapper(model), _) = reranker_lock
+            .as_ref()
+            .context("No reranker loaded")?;
+
+        let n_ctx = model.n_ctx_train().clamp(1, RERANK_MAX_CTX);
+        let ctx_params = self.context_params(n_ctx as usize)
+            .with_n_batch(n_ctx)
+            .with_n_ubatch(n_ctx)
+            .with_embeddings(true)
+            .with_pooling_type(llama_cpp_2::context::params::LlamaPoolingType::Rank);
+        let mut ctx = model
+            .new_context(&self.backend, ctx_params)
+            .context("Failed to create reranking context")?;
+        let max_tokens = ctx.n_ubatch().min(ctx.n_batch()) as usize;
+
+        let mut query_tokens = model
+            .str_to_token(query, AddBos::Always)
+            .context("Failed to tokenize query")?;
+        query_tokens.push(model.token_eos());
+        query_tokens.push(model.token_sep());
+
+        let mut score_passage = |passage: &str| -> Result<f32> {
+            let mut tokens = query_tokens.clone();
+            tokens.extend(
+                model
+                    .str_to_token(passage, AddBos::Never)
+                    .context("Failed to tokenize passage")?,
+            );
+            tokens.truncate(max_tokens.saturating_sub(1));
+            tokens.push(model.token_eos());
+
+            let mut batch = LlamaBatch::new(tokens.len(), 1);
+            batch
+                .add_sequence(&tokens, 0, false)
+                .context("Failed to add tokens to batch")?;
+
+            ctx.clear_kv_cache();
+            ctx.decode(&mut batch).context("Failed to decode passage to rerank")?;
+
+            ctx.embeddings_seq_ith(0)
+                .context("Failed to read reranking score")?
+                .first()
+                .copied()
+                .context("Empty reranking score")
+        };
+
+        let mut scores = Vec::with_capacity(passages.len());
+        let mut failed = 0;
+        for (i, passage) in passages.iter().enumerate() {
+            match score_passage(passage) {
+                Ok(score) => scores.push(score),
+                Err(e) => {
+                    warn!("Failed to rerank passage {}: {:#}", i, e);
+                    failed += 1;
+                    scores.push(f32::NEG_INFINITY);
+                }
+            }
+        }
+        if failed > 0 && failed == passages.len() {
+            anyhow::bail!("Reranker failed on every passage");
+        }
+
+        debug!("Reranked {} passages ({} failed)", scores.len(), failed);
+        Ok(scores)
+    }
+
+    /// Load into `ctx` the cached KV state sharing the longest token prefix with
+    /// `tokens` (system prompt and history), leaving at least the last token to decode.
+    /// Only prefixes covering the `n_keep` system prompt tokens, and at least
+    /// `MIN_PREFIX_TOKENS`, are restored. Returns the number of tokens already in the context.
+    fn restore_cached_prefix(&self, ctx: &mut LlamaContext<'_>, tokens: &[LlamaToken], n_keep: usize) -> Result<usize> {
+        // The prefix must be followed by at least one token to sample from
+        let Some((_, prefix)) = tokens.split_last() else {
+            return Ok(0);
+        };
+        let scope = PromptCache::scope(&self.config.model.model_path, self.config.model.n_ctx);
+        let prefix: Vec<i32> = prefix.iter().map(|token| token.0).collect();
+        let cached = {
+            let mut cache = self.prompt_cache.lock().unwrap();
+            if !cache.is_enabled() {
+                return Ok(0);
+            }
+            cache.longest_prefix(scope, &prefix, n_keep.max(MIN_PREFIX_TOKENS))
+        };
+        let Some((entry, shared)) = cached else {
+            return Ok(0);
+        };
+
+        // SAFETY: the state was produced by a context of the same model and size
+        let read = unsafe { ctx.set_state_data(&entry.state) };
+        if read == 0 {
+            warn!("Failed to restore cached prompt state, evaluating whole prompt");
+            return Ok(0);
+        }
+        // Drop the cached tokens past the shared prefix
+        if shared < entry.tokens.len() && !ctx.clear_kv_cache_seq(Some(0), Some(shared as u32), None).unwrap_or(false) {
+            warn!("Failed to trim cached prompt state, evaluating whole prompt");
+            ctx.clear_kv_cache();
+            return Ok(0);
+        }
+
+        info!("Prompt cache hit: {} of {} prompt tokens reused", shared, tokens.len());
+        Ok(shared)
+    }
+
+    /// Cache the KV state of a decoded prompt, for the next prompt extending it
+    fn cache_prompt_state(&self, ctx: &LlamaContext<'_>, tokens: &[LlamaToken]) {
+        if !self.prompt_cache.lock().unwrap().is_enabled() {
+            return;
+        }
+
+        let mut state = vec![0u8; ctx.get_state_size()];
+        // SAFETY: the buffer is sized with get_state_size()
+        let written = unsafe { ctx.copy_state_data(state.as_mut_ptr()) };
+        state.truncate(written);
+        let scope = PromptCache::scope(&self.config.model.model_path, self.config.model.n_ctx);
+        self.prompt_cache
+            .lock()
+            .unwrap()
+            .insert(scope, tokens.iter().map(|token| token.0).collect(), state);
+        debug!("Prompt state cached ({} tokens)", tokens.len());
+    }
+
+    /// Decode `tokens[start..]` at their positions, in chunks of at most `n_batch` tokens
+    /// (llama.cpp rejects larger decode calls). Only the last token can get logits, and it
+    /// ends up last in `batch`.
+    fn decode_tokens(
+        ctx: &mut LlamaContext<'_>,
+        batch: &mut LlamaBatch,
+        tokens: &[LlamaToken],
+        start: usize,
+        logits_last: bool,
+    ) -> Result<()> {
+        let n_batch = (ctx.n_batch() as usize).max(1);
+        let mut position = start;
+        while position < tokens.len() {
+            let end = (position + n_batch).min(tokens.len());
+            batch.clear();
+            for (i, token) in tokens.iter().enumerate().take(end).skip(position) {
+                let logits = logits_last && i == tokens.len() - 1;
+                batch
+                    .add(*token, i as i32, &[0], logits)
+                    .context("Failed to add token to batch")?;
+            }
+            ctx.decode(batch).context("Failed to decode tokens")?;
+            position = end;
+        }
+        Ok(())
+    }
+
+    /// Update the prompt cache limits (0 disables the cache)
+    pub fn configure_prompt_cache(&mut self, max_entries: usize, max_size_mb: usize) {
+        self.config.model.prompt_cache_entries = max_entries;
+        self.config.model.prompt_cache_size_mb = max_size_mb;
+        self.prompt_cache.lock().unwrap().configure(max_entries, max_size_mb);
+    }
+
+    /// Drop every cached prompt prefix
+    pub fn clear_prompt_cache(&self) {
+        self.prompt_cache.lock().unwrap().clear();
+        info!("Prompt cache cleared");
+    }
+
+    /// Current prompt cache usage
+    pub fn prompt_cache_stats(&self) -> PromptCacheStats {
+        self.prompt_cache.lock().unwrap().stats()
+    }
+
+    /// Make a tokenized prompt fit in the context window, before anything is decoded
+    ///
+    /// Returns the number of tokens of the system prompt (never discarded) and the
+    /// number of dropped tokens. Without context shifting, an oversized prompt is a
+    /// `ContextOverflow` error instead of an opaque decode failure.
+    fn fit_prompt(&self, model: &LlamaModel, prompt: &str, tokens: &mut Vec<LlamaToken>) -> Result<(usize, usize)> {
+        let n_keep = match context_shift::system_prefix(prompt) {
+            Some(prefix) => {
+                let prefix_tokens = model
+                    .str_to_token(prefix, AddBos::Always)
+                    .context("Failed to tokenize system prompt")?;
+                // Only trust the prefix if it tokenizes identically inside the full prompt
+                if tokens.starts_with(&prefix_tokens) { prefix_tokens.len() } else { 0 }
+            }
+            None => 0,
+        };
+
+        let n_ctx = self.config.model.n_ctx;
+        let budget = context_shift::prompt_budget(n_ctx);
+        if tokens.len() <= budget {
+            return Ok((n_keep, 0));
+        }
+        if !self.config.model.context_shift {
+            return Err(AppError::ContextOverflow { prompt_tokens: tokens.len(), n_ctx }.into());
+        }
+        let truncated_tokens = context_shift::truncate_prompt(tokens, n_keep, budget);
+        warn!("Prompt truncated: {} oldest tokens dropped", truncated_tokens);
+        Ok((n_keep, truncated_tokens))
+    }
+
+    /// Run the decode loop on an already formatted prompt
+    /// When context shifting is enabled, the oldest tokens (after the system prompt)
+    /// are dropped instead of failing once the context window is full
+    async fn run_completion(&self, prompt: &str, max_tokens: usize, on_piece: OnPiece<'_>) -> Result<Completion> {
+        let model_lock = self.model.lock().await;
+        let model = &model_lock
+            .as_ref()
+            .context("Model not loaded despite is_loaded check")?
+            .0;
+
         // Create context parameters for this generation
-        let ctx_params = llama_cpp_2::context::params::LlamaContextParams::default()
-            .with_n_ctx(NonZeroU32::new(self.config.n_ctx as u32))
-            .with_n_threads(self.config.n_threads as i32);
-        
-        // Create a new context with the full conversation history
+        let ctx_params = self.context_params(self.config.model.n_ctx);
+
+        // Create a new context with the full conversation
         let mut ctx = model
             .new_context(&self.backend, ctx_params)
             .context("Failed to create context")?;
-        
-        // Tokenize the FULL conversation history (not just the current prompt)
-        let tokens = model
-            .str_to_token(&history, AddBos::Always)
+
+        // Tokenize the FULL conversation (not just the current prompt)
+        let mut tokens = model
+            .str_to_token(prompt, AddBos::Always)
             .context("Failed to tokenize conversation history")?;
-        
+
         info!("Conversation history tokenized: {} tokens", tokens.len());
-        
+
+        let (n_keep, truncated_tokens) = self.fit_prompt(model, prompt, &mut tokens)?;
+
         // Create batch for processing
-        let mut batch = LlamaBatch::new(self.config.n_ctx as usize, 1);
-        
-        // Add prompt tokens to batch
-        for (i, token) in tokens.iter().enumerate() {
-            let is_last = i == tokens.len() - 1;
+        let mut batch = LlamaBatch::new(self.config.model.n_ctx, 1);
+
+        // Resume from the cached state of the longest already decoded prefix
+        let start = self.restore_cached_prefix(&mut ctx, &tokens, n_keep)?;
+
+        // Decode the remaining prompt tokens
+        Self::decode_tokens(&mut ctx, &mut batch, &tokens, start, true)
+            .context("Failed to decode prompt batch")?;
+        self.cache_prompt_state(&ctx, &tokens);
+
+        let mut completion = self.decode_loop(model, &mut ctx, &mut batch, tokens.len(), Some(n_keep), max_tokens, on_piece)?;
+        completion.truncated_tokens += truncated_tokens;
+        Ok(completion)
+    }
+
+    /// Evaluate a conversation containing images through the vision projector, then decode
+    ///
+    /// Each image is replaced by its embeddings at the media marker placed before
+    /// the text of its message. The KV cache can't be shifted once it holds images.
+    async fn run_vision_completion(&self, messages: &[ChatMessage], max_tokens: usize, on_piece: OnPiece<'_>) -> Result<Completion> {
+        let prompt = self.build_prompt(&with_media_markers(messages)).await;
+        let text = prompt.strip_suffix(ASSISTANT_HEADER).unwrap_or(&prompt);
+
+        // Same lock order as load_model and unload_model: model, then projector
+        let model_lock = self.model.lock().await;
+        let model = &model_lock
+            .as_ref()
+            .context("Model not loaded despite is_loaded check")?
+            .0;
+        let vision_lock = self.vision.lock().await;
+        let vision = &vision_lock
+            .as_ref()
+            .context("Vision projector not loaded")?
+            .0;
+
+        let bitmaps = messages
+            .iter()
+            .flat_map(|m| m.images.iter())
+            .map(|image| MtmdBitmap::from_buffer(vision, image))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| AppError::invalid_input(format!("Unsupported image: {}", e)))?;
+        let bitmap_refs: Vec<&MtmdBitmap> = bitmaps.iter().collect();
+
+        let chunks = vision
+            .tokenize(
+                MtmdInputText { text: text.to_string(), add_special: true, parse_special: true },
+                &bitmap_refs,
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to tokenize prompt with images: {}", e))?;
+
+        let header = model
+            .str_to_token(ASSISTANT_HEADER, AddBos::Never)
+            .context("Failed to tokenize assistant header")?;
+        let n_ctx = self.config.model.n_ctx;
+        let prompt_tokens = chunks.total_tokens() + header.len();
+        // Image chunks can't be truncated: the prompt fits or the request fails
+        if prompt_tokens > context_shift::prompt_budget(n_ctx) {
+            return Err(AppError::ContextOverflow { prompt_tokens, n_ctx }.into());
+        }
+
+        let ctx_params = self.context_params(n_ctx);
+        let mut ctx = model
+            .new_context(&self.backend, ctx_params)
+            .context("Failed to create context")?;
+
+        info!("Evaluating prompt with {} images ({} tokens)", bitmaps.len(), prompt_tokens);
+        let n_past = chunks
+            .eval_chunks(vision, &ctx, 0, 0, VISION_BATCH_SIZE, false)
+            .map_err(|e| anyhow::anyhow!("Failed to evaluate images: {}", e))?;
+
+        // The assistant header is decoded as a regular batch so its logits can be sampled
+        let mut batch = LlamaBatch::new(n_ctx, 1);
+        for (i, token) in header.iter().enumerate() {
             batch
-                .add(*token, i as i32, &[0], is_last)
+                .add(*token, n_past + i as i32, &[0], i == header.len() - 1)
                 .context("Failed to add token to batch")?;
         }
-        
-        // Decode the prompt batch
         ctx
             .decode(&mut batch)
             .context("Failed to decode prompt batch")?;
-        
-        // Generate tokens
+
+        self.decode_loop(model, &mut ctx, &mut batch, n_past as usize + header.len(), None, max_tokens, on_piece)
+    }
+
+    /// Sample up to `max_tokens` tokens once the prompt has been decoded into `ctx`
+    ///
+    /// `n_keep` is the number of tokens preserved when the context is shifted,
+    /// `None` when the KV cache must not be shifted. Each decoded piece is passed
+    /// to `on_piece` when streaming.
+    #[allow(clippy::too_many_arguments)]
+    fn decode_loop(
+        &self,
+        model: &LlamaModel,
+        ctx: &mut LlamaContext<'_>,
+        batch: &mut LlamaBatch,
+        mut n_past: usize,
+        n_keep: Option<usize>,
+        max_tokens: usize,
+        mut on_piece: OnPiece<'_>,
+    ) -> Result<Completion> {
+        let n_ctx = self.config.model.n_ctx;
+        let mut truncated_tokens = 0;
         let mut generated_text = String::new();
         let mut tokens_generated = 0;
-        let max_tokens = self.config.max_tokens as usize;
-        
-        // Create sampler chain with configured parameters
-        // This uses proper sampling (temperature, top_k, top_p, penalties) instead of greedy sampling
-        // Order matters: penalties -> top_k -> top_p -> temperature -> distribution
-        // See: https://github.com/ggerganov/llama.cpp/blob/master/examples/main/README.md#sampling
-        let mut sampler = LlamaSampler::chain_simple([
-            LlamaSampler::penalties(
-                64,  // penalty_last_n: consider last 64 tokens for repeat detection
-                self.config.repeat_penalty,  // penalty_repeat: from config (default 1.1)
-                0.0, // penalty_freq: frequency penalty (0 = disabled for now)
-                0.0, // penalty_present: presence penalty (0 = disabled for now)
-            ),
-            LlamaSampler::top_k(self.config.top_k),  // Keep only top K tokens (default 40)
-            LlamaSampler::top_p(self.config.top_p, 1),  // Nucleus sampling with top_p (default 0.9), min_keep=1
-            LlamaSampler::temp(self.config.temperature),  // Apply temperature (default 0.7)
-            LlamaSampler::dist(0),  // Sample from distribution (seed=0 for deterministic per session)
-        ]);
-        
-        for i in 0..max_tokens {
+
+        let mut sampler = self.build_sampler();
+        let mut logprobs = self.config.sampling.logprobs.map(|_| Vec::new());
+        let mut done = false;
+
+        for _ in 0..max_tokens {
+            if self.cancel.load(Ordering::Relaxed) {
+                info!("Generation cancelled after {} tokens", tokens_generated);
+                done = true;
+                break;
+            }
+
             // Sample next token using the configured sampler chain
-            let next_token = sampler.sample(&ctx, batch.n_tokens() - 1);
-            
+            let next_token = sampler.sample(ctx, batch.n_tokens() - 1);
+
+            if let (Some(logprobs), Some(top_n)) = (logprobs.as_mut(), self.config.sampling.logprobs) {
+                let logits = ctx.get_logits_ith(batch.n_tokens() - 1);
+                logprobs.push(Self::token_logprob(model, logits, next_token, top_n));
+            }
+
             // Check for EOS token
             if model.is_eog_token(next_token) {
                 info!("Generated {} tokens (EOS reached)", tokens_generated);
+                done = true;
                 break;
             }
-            
+
             // Decode token to text (skip if it fails, but continue with generation)
+            let streamed = generated_text.len();
             if let Ok(piece) = model.token_to_str(next_token, llama_cpp_2::model::Special::Tokenize) {
                 generated_text.push_str(&piece);
                 tokens_generated += 1;
             } else {
                 warn!("Failed to decode token {}. Continuing generation...", next_token.0);
             }
-            
+
+            let stop = stop::stop_position(&self.config.sampling.stop_conditions, &generated_text);
+            if let Some(end) = stop {
+                // Text already streamed can't be taken back
+                generated_text.truncate(if on_piece.is_some() { end.max(streamed) } else { end });
+            }
+            if let Some(on_piece) = on_piece.as_mut() {
+                if generated_text.len() > streamed {
+                    on_piece(generated_text[streamed..].to_string())?;
+                }
+            }
+            if stop.is_some() {
+                info!("Generated {} tokens (stop condition met)", tokens_generated);
+                done = true;
+                break;
+            }
+
             // Accept the token for repeat penalty tracking
             sampler.accept(next_token);
-            
+
+            // KV cache full: discard the oldest half of the non-kept tokens
+            if n_past >= n_ctx {
+                let Some(n_keep) = n_keep.filter(|_| self.config.model.context_shift) else {
+                    return Err(AppError::GenerationFailed {
+                        message: format!("Context window full ({} tokens)", n_ctx),
+                        partial_text: Some(generated_text),
+                        message_id: None,
+                    }
+                    .into());
+                };
+                let n_discard = context_shift::shift_amount(n_past, n_keep);
+                ctx.clear_kv_cache_seq(Some(0), Some(n_keep as u32), Some((n_keep + n_discard) as u32))
+                    .context("Failed to discard tokens from KV cache")?;
+                ctx.kv_cache_seq_add(0, Some((n_keep + n_discard) as u32), None, -(n_discard as i32))
+                    .context("Failed to shift KV cache")?;
+                n_past -= n_discard;
+                truncated_tokens += n_discard;
+                info!("Context shifted: {} tokens discarded", n_discard);
+            }
+
             // Prepare next batch with the new token
             batch.clear();
-            let new_pos = tokens.len() as i32 + i as i32;
             batch
-                .add(next_token, new_pos, &[0], true)
+                .add(next_token, n_past as i32, &[0], true)
                 .context("Failed to add generated token to batch")?;
-            
-            // Decode the new token
-            ctx
-                .decode(&mut batch)
-                .context("Failed to decode generated token")?;
+
+            // Decode the new token (keep what was generated so far if it fails)
+            if let Err(e) = ctx.decode(batch) {
+                return Err(AppError::GenerationFailed {
+                    message: format!("Failed to decode generated token: {}", e),
+                    partial_text: Some(generated_text),
+                    message_id: None,
+                }
+                .into());
+            }
+            n_past += 1;
         }
-        
-        info!("Generated {} tokens", tokens_generated);
-        
-        // Add the assistant's response to conversation history with proper format
-        history.push_str(&generated_text);
-        history.push_str("<|im_end|>");
-        drop(history); // Release the lock
-        
-        Ok(LLMResponse {
-            text: generated_text.trim().to_string(),
-            tool_calls: Self::parse_tool_calls(&generated_text),
+
+        if !done {
+            info!("Generated {} tokens (max_tokens reached)", tokens_generated);
+        }
+
+        Ok(Completion {
+            text: generated_text,
             tokens_generated,
-            done: true,
+            done,
+            truncated_tokens,
+            logprobs,
         })
     }
 
-    /// Generate a streaming response (callback receives chunks)
-    pub async fn generate_stream<F>(
-        &self,
-        prompt: &str,
-        mut callback: F,
-    ) -> Result<LLMResponse>
-    where
-        F: FnMut(String) -> Result<()>,
-    {
-        if !self.is_loaded().await {
-            anyhow::bail!("No model is loaded. Call load_model() first.");
-        }
-
-        info!("Generating streaming response for prompt ({}...)", &prompt[..50.min(prompt.len())]);
+    /// Log probability of a sampled token and its best alternatives, as text
+    fn token_logprob(model: &LlamaModel, logits: &[f32], token: LlamaToken, top_n: usize) -> TokenLogprob {
+        let to_text = |id: usize| {
+            model
+                .token_to_str(LlamaToken::new(id as i32), llama_cpp_2::model::Special::Tokenize)
+                .unwrap_or_default()
+        };
+        let (logprob, top) = logprobs::token_logprobs(logits, token.0 as usize, top_n);
 
-        let model_lock = self.model.lock().await;
-        let model = &model_lock
-            .as_ref()
-            .context("Model not loaded despite is_loaded check")?
-            .0;
-        
-        // Create context for this generation
-        let ctx_params = llama_cpp_2::context::params::LlamaContextParams::default()
-            .with_n_ctx(NonZeroU32::new(self.config.n_ctx as u32))
-            .with_n_threads(self.config.n_threads as i32);
-        
-        let mut ctx = model.new_context(&self.backend, ctx_params)?;
-        
-        // Tokenize prompt
-        let tokens = model
-            .str_to_token(prompt, AddBos::Always)
-            .context("Failed to tokenize prompt")?;
-        
-        let mut batch = LlamaBatch::new(self.config.n_ctx as usize, 1);
-        
-        // Process prompt
-        for (i, token) in tokens.iter().enumerate() {
-            batch
-                .add(*token, i as i32, &[0], i == tokens.len() - 1)
-                .context("Failed to add token")?;
-        }
-        
-        ctx.decode(&mut batch)?;
-        
-        // Generate with streaming
-        let mut generated_text = String::new();
-        let mut tokens_generated = 0;
-        let max_tokens = self.config.max_tokens as usize;
-        
-        for i in 0..max_tokens {
-            let candidates = ctx.candidates_ith(batch.n_tokens() - 1);
-            let next_token = candidates
+        TokenLogprob {
+            token: to_text(token.0 as usize),
+            logprob,
+            top: top
                 .into_iter()
-                .max_by(|a, b| a.logit().partial_cmp(&b.logit()).unwrap())
-                .map(|d| d.id())
-                .context("No candidates")?;
-            
-            if model.is_eog_token(next_token) {
-                break;
-            }
-            
-            let piece = model.token_to_str(next_token, llama_cpp_2::model::Special::Tokenize)?;
-            
-            // Stream the chunk
-            callback(piece.clone())?;
-            
-            generated_text.push_str(&piece);
-            tokens_generated += 1;
-            
-            batch.clear();
-            batch.add(next_token, tokens.len() as i32 + i as i32, &[0], true)?;
-            ctx.decode(&mut batch)?;
+                .map(|(id, logprob)| TokenAlternative { token: to_text(id), logprob })
+                .collect(),
         }
-        
-        let tool_calls = Self::parse_tool_calls(&generated_text);
-        
-        Ok(LLMResponse {
-            text: generated_text,
-            tool_calls,
-            tokens_generated,
-            done: true,
-        })
     }
 
     /// Parse tool calls from response text (placeholder for future implementation)
@@ -392,7 +1267,11 @@ impl LLMEngine {
     pub async fn unload_model(&self) -> Result<()> {
         info!("Unloading model");
         let mut model_lock = self.model.lock().await;
+        // The projector references the model, release it first
+        *self.vision.lock().await = None;
         *model_lock = None;
+        *self.capabilities.lock().await = None;
+        self.clear_prompt_cache();
         info!("Model unloaded successfully");
         Ok(())
     }
@@ -402,11 +1281,18 @@ impl LLMEngine {
         &self.config
     }
 
-    /// Update configuration (requires reload)
+    /// Update the configuration; only model settings need a reload
     pub fn set_config(&mut self, config: LLMConfig) {
-        warn!("Configuration changed. Model must be reloaded.");
+        if self.config.model != config.model {
+            warn!("Model settings changed. Model must be reloaded.");
+        }
         self.config = config;
     }
+
+    /// Update the sampling settings, used from the next generation on
+    pub fn set_sampling_config(&mut self, sampling: SamplingConfig) {
+        self.config.sampling = sampling;
+    }
 }
 
 impl Drop for LLMEngine {
@@ -414,3 +1300,58 @@ impl Drop for LLMEngine {
         info!("LLMEngine dropping - cleanup will occur automatically");
     }
 }
+
+#[async_trait::async_trait]
+impl LLMBackend for LLMEngine {
+    fn name(&self) -> String {
+        format!("local:{}", self.config.model.model_path)
+    }
+
+    async fn is_ready(&self) -> bool {
+        self.is_loaded().await
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<LLMResponse> {
+        self.generate(messages).await
+    }
+}
+
+/// Put one media marker per image before the text of its message
+fn with_media_markers(messages: &[ChatMessage]) -> Vec<ChatMessage> {
+    let marker = llama_cpp_2::mtmd::mtmd_default_marker();
+    messages
+        .iter()
+        .map(|m| {
+            let mut message = m.clone();
+            if !m.images.is_empty() {
+                let markers = vec![marker; m.images.len()].join("\n");
+                message.content = format!("{}\n{}", markers, m.content);
+            }
+            message
+        })
+        .collect()
+}
+
+/// Merge system messages into the first user message
+fn fold_system_messages(messages: &[ChatMessage]) -> Vec<ChatMessage> {
+    let system: Vec<&str> = messages
+        .iter()
+        .filter(|m| m.role == ChatRole::System)
+        .map(|m| m.content.as_str())
+        .collect();
+    let mut folded: Vec<ChatMessage> = messages
+        .iter()
+        .filter(|m| m.role != ChatRole::System)
+        .cloned()
+        .collect();
+
+    if !system.is_empty() {
+        match folded.iter_mut().find(|m| m.role == ChatRole::User) {
+            Some(first_user) => {
+                first_user.content = format!("{}\n\n{}", system.join("\n\n"), first_user.content);
+            }
+            None => folded.insert(0, ChatMessage::user(system.join("\n\n"))),
+        }
+    }
+    folded
+}
@@ -0,0 +1,397 @@
+/// GGUF validation before a model is handed to llama.cpp
+///
+/// llama.cpp aborts the whole process on some malformed files. The header, the
+/// metadata and the tensor table are parsed here first, and the tensor data must
+/// fit in the file (which catches truncated downloads). In the application, the file
+/// is then opened by llama.cpp in a child process (vocabulary only): a crash there
+/// is reported as a corrupt model instead of taking the application down.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{debug, info};
+
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+
+/// GGUF versions llama.cpp can read
+pub const SUPPORTED_VERSIONS: std::ops::RangeInclusive<u32> = 2..=3;
+
+/// Alignment of the tensor data when `general.alignment` is absent
+const DEFAULT_ALIGNMENT: u64 = 32;
+
+/// Maximum number of dimensions of a tensor (GGML_MAX_DIMS)
+const MAX_DIMS: u32 = 4;
+
+/// Command line argument running the llama.cpp probe in a child process
+pub const PROBE_ARG: &str = "--probe-gguf";
+
+/// Time allowed to the child process to open the model
+const PROBE_TIMEOUT: Duration = Duration::from_secs(120);
+
+static CHILD_PROBE: AtomicBool = AtomicBool::new(false);
+
+/// Summary of a valid GGUF file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GgufHeader {
+    pub version: u32,
+    pub tensor_count: u64,
+    pub metadata_count: u64,
+}
+
+/// GGUF version this build of llama.cpp cannot read
+///
+/// A newer version means the file is fine but the application needs a more
+/// recent llama.cpp, unlike the other validation errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("unsupported GGUF version {version} (supported: {}-{})", SUPPORTED_VERSIONS.start(), SUPPORTED_VERSIONS.end())]
+pub struct UnsupportedGgufVersion {
+    pub version: u32,
+}
+
+impl UnsupportedGgufVersion {
+    /// Whether the file uses a format newer than this build supports
+    pub fn is_newer(&self) -> bool {
+        self.version > *SUPPORTED_VERSIONS.end()
+    }
+}
+
+/// Sequential reader that refuses to go past the end of the file
+struct HeaderReader<R> {
+    inner: R,
+    position: u64,
+    length: u64,
+}
+
+impl<R: Read> HeaderReader<R> {
+    fn ensure_available(&self, bytes: u64) -> Result<()> {
+        match self.position.checked_add(bytes) {
+            Some(end) if end <= self.length => Ok(()),
+            _ => anyhow::bail!("unexpected end of file at byte {} (truncated download?)", self.position),
+        }
+    }
+
+    fn read_exact<const N: usize>(&mut self) -> Result<[u8; N]> {
+        self.ensure_available(N as u64)?;
+        let mut buffer = [0u8; N];
+        self.inner.read_exact(&mut buffer).context("Failed to read the model file")?;
+        self.position += N as u64;
+        Ok(buffer)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.read_exact()?))
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.read_exact()?))
+    }
+
+    fn skip(&mut self, bytes: u64) -> Result<()> {
+        self.ensure_available(bytes)?;
+        let skipped = std::io::copy(&mut (&mut self.inner).take(bytes), &mut std::io::sink())
+            .context("Failed to read the model file")?;
+        if skipped != bytes {
+            anyhow::bail!("unexpected end of file at byte {}", self.position + skipped);
+        }
+        self.position += bytes;
+        Ok(())
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let length = self.read_u64()?;
+        self.ensure_available(length)?;
+        let mut bytes = vec![0u8; length as usize];
+        self.inner.read_exact(&mut bytes).context("Failed to read the model file")?;
+        self.position += length;
+        String::from_utf8(bytes).map_err(|_| anyhow::anyhow!("invalid UTF-8 string at byte {}", self.position))
+    }
+
+    fn skip_string(&mut self) -> Result<()> {
+        let length = self.read_u64()?;
+        self.skip(length)
+    }
+
+    /// Skip a metadata value of the given type
+    fn skip_value(&mut self, value_type: u32) -> Result<()> {
+        match value_type {
+            8 => self.skip_string(),
+            9 => {
+                let element_type = self.read_u32()?;
+                let count = self.read_u64()?;
+                match scalar_size(element_type) {
+                    Some(size) => {
+                        let bytes = count
+                            .checked_mul(size)
+                            .ok_or_else(|| anyhow::anyhow!("invalid array length {}", count))?;
+                        self.skip(bytes)
+                    }
+                    None => {
+                        // Every string or nested array takes at least 8 bytes
+                        self.ensure_available(count.saturating_mul(8))?;
+                        for _ in 0..count {
+                            self.skip_value(element_type)?;
+                        }
+                        Ok(())
+                    }
+                }
+            }
+            other => match scalar_size(other) {
+                Some(size) => self.skip(size),
+                None => anyhow::bail!("unknown metadata type {} at byte {}", other, self.position),
+            },
+        }
+    }
+}
+
+/// Size of a fixed-size metadata value
+fn scalar_size(value_type: u32) -> Option<u64> {
+    match value_type {
+        0 | 1 | 7 => Some(1),  // u8, i8, bool
+        2 | 3 => Some(2),      // u16, i16
+        4..=6 => Some(4),      // u32, i32, f32
+        10..=12 => Some(8),    // u64, i64, f64
+        _ => None,
+    }
+}
+
+/// Elements and bytes per block of the common ggml tensor types
+///
+/// Tensors of other types are only checked to start inside the file.
+fn tensor_type_layout(tensor_type: u32) -> Option<(u64, u64)> {
+    match tensor_type {
+        0 => Some((1, 4)),       // F32
+        1 => Some((1, 2)),       // F16
+        2 => Some((32, 18)),     // Q4_0
+        3 => Some((32, 20)),     // Q4_1
+        6 => Some((32, 22)),     // Q5_0
+        7 => Some((32, 24)),     // Q5_1
+        8 => Some((32, 34)),     // Q8_0
+        10 => Some((256, 84)),   // Q2_K
+        11 => Some((256, 110)),  // Q3_K
+        12 => Some((256, 144)),  // Q4_K
+        13 => Some((256, 176)),  // Q5_K
+        14 => Some((256, 210)),  // Q6_K
+        30 => Some((1, 2)),      // BF16
+        _ => None,
+    }
+}
+
+/// Check the structure of a GGUF file without loading it
+pub fn validate(path: &Path) -> Result<GgufHeader> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let length = file.metadata().context("Failed to read the model file size")?.len();
+    let mut reader = HeaderReader {
+        inner: BufReader::new(file),
+        position: 0,
+        length,
+    };
+
+    if &reader.read_exact::<4>()? != GGUF_MAGIC {
+        anyhow::bail!("not a GGUF file (wrong magic number)");
+    }
+    let version = reader.read_u32()?;
+    if !SUPPORTED_VERSIONS.contains(&version) {
+        return Err(UnsupportedGgufVersion { version }.into());
+    }
+    let tensor_count = reader.read_u64()?;
+    let metadata_count = reader.read_u64()?;
+
+    let mut alignment = DEFAULT_ALIGNMENT;
+    for _ in 0..metadata_count {
+        let key = reader.read_string()?;
+        let value_type = reader.read_u32()?;
+        if key == "general.alignment" && value_type == 4 {
+            alignment = u64::from(reader.read_u32()?);
+            if alignment == 0 || !alignment.is_power_of_two() {
+                anyhow::bail!("invalid tensor alignment {}", alignment);
+            }
+        } else {
+            reader.skip_value(value_type)?;
+        }
+    }
+
+    // Tensor table: the end of the last tensor bounds the expected file size
+    let mut data_size = 0u64;
+    for _ in 0..tensor_count {
+        let name = reader.read_string()?;
+        let dims = reader.read_u32()?;
+        if dims > MAX_DIMS {
+            anyhow::bail!("tensor {} has {} dimensions", name, dims);
+        }
+        let mut elements = 1u64;
+        for _ in 0..dims {
+            elements = elements
+                .checked_mul(reader.read_u64()?)
+                .ok_or_else(|| anyhow::anyhow!("tensor {} is too large", name))?;
+        }
+        let tensor_type = reader.read_u32()?;
+        let offset = reader.read_u64()?;
+
+        let size = match tensor_type_layout(tensor_type) {
+            Some((block_elements, block_bytes)) => elements.div_ceil(block_elements).saturating_mul(block_bytes),
+            None => 1,
+        };
+        data_size = data_size.max(offset.saturating_add(size));
+    }
+
+    let data_start = reader.position.div_ceil(alignment) * alignment;
+    let expected = data_start.saturating_add(data_size);
+    if tensor_count > 0 && expected > length {
+        anyhow::bail!(
+            "file is truncated: {} bytes expected, {} bytes found",
+            expected,
+            length
+        );
+    }
+
+    debug!("Valid GGUF v{}: {} tensors, {} metadata entries", version, tensor_count, metadata_count);
+    Ok(GgufHeader {
+        version,
+        tensor_count,
+        metadata_count,
+    })
+}
+
+/// Open models in a child process before loading them (set once by the application)
+pub fn enable_child_probe() {
+    CHILD_PROBE.store(true, Ordering::Relaxed);
+}
+
+/// Open the model with llama.cpp in a child process of the application
+///
+/// Does nothing unless `enable_child_probe` was called: test binaries and examples
+/// cannot be re-executed as a probe. Blocking: it runs on the engine thread, the
+/// child's stderr is read by a helper thread so the wait can time out.
+pub fn probe_in_child(path: &Path) -> Result<()> {
+    if !CHILD_PROBE.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    let executable = std::env::current_exe().context("Failed to locate the application executable")?;
+    info!("Checking {} in a child process", path.display());
+    let mut child = std::process::Command::new(executable)
+        .arg(PROBE_ARG)
+        .arg(path)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .context("Failed to start the model check")?;
+
+    // stderr reaches its end when the child exits
+    let mut stderr = child.stderr.take().context("Model check has no stderr")?;
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stderr.read_to_end(&mut output);
+        let _ = sender.send(output);
+    });
+
+    let stderr = match receiver.recv_timeout(PROBE_TIMEOUT) {
+        Ok(stderr) => stderr,
+        Err(_) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("llama.cpp did not open the model within {}s", PROBE_TIMEOUT.as_secs());
+        }
+    };
+    let status = child.wait().context("Model check failed")?;
+    if status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&stderr);
+    let last_lines: Vec<&str> = stderr.lines().rev().take(5).collect();
+    let details: Vec<&str> = last_lines.into_iter().rev().collect();
+    anyhow::bail!("llama.cpp failed to open the model ({}): {}", status, details.join(" | "))
+}
+
+/// Entry point of the child process: opens the vocabulary of the model given on the command line
+///
+/// Returns the exit code when the process was started as a probe, None otherwise.
+pub fn run_probe_from_args() -> Option<i32> {
+    let mut args = std::env::args_os().skip(1);
+    if args.next()? != PROBE_ARG {
+        return None;
+    }
+    let path = args.next()?;
+
+    let result = llama_cpp_2::llama_backend::LlamaBackend::init()
+        .map_err(anyhow::Error::from)
+        .and_then(|backend| {
+            let params = llama_cpp_2::model::params::LlamaModelParams::default().with_vocab_only(true);
+            llama_cpp_2::model::LlamaModel::load_from_file(&backend, &path, &params)
+                .map(|_| ())
+                .map_err(anyhow::Error::from)
+        });
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("{:#}", e);
+            Some(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_string(buffer: &mut Vec<u8>, value: &str) {
+        buffer.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        buffer.extend_from_slice(value.as_bytes());
+    }
+
+    /// Minimal GGUF file: one string metadata entry and one F32 tensor of 4 elements
+    fn sample_gguf() -> Vec<u8> {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(GGUF_MAGIC);
+        buffer.extend_from_slice(&3u32.to_le_bytes());
+        buffer.extend_from_slice(&1u64.to_le_bytes());
+        buffer.extend_from_slice(&1u64.to_le_bytes());
+
+        write_string(&mut buffer, "general.name");
+        buffer.extend_from_slice(&8u32.to_le_bytes());
+        write_string(&mut buffer, "tiny");
+
+        write_string(&mut buffer, "weights");
+        buffer.extend_from_slice(&1u32.to_le_bytes());
+        buffer.extend_from_slice(&4u64.to_le_bytes());
+        buffer.extend_from_slice(&0u32.to_le_bytes());
+        buffer.extend_from_slice(&0u64.to_le_bytes());
+
+        buffer.resize(buffer.len().div_ceil(32) * 32, 0);
+        buffer.extend_from_slice(&[0u8; 16]);
+        buffer
+    }
+
+    #[test]
+    fn test_validate_gguf() {
+        let path = std::env::temp_dir().join(format!("agents-rs-{}.gguf", uuid::Uuid::new_v4()));
+        let data = sample_gguf();
+
+        std::fs::write(&path, &data).unwrap();
+        let header = validate(&path).unwrap();
+        assert_eq!(header.tensor_count, 1);
+
+        std::fs::write(&path, &data[..data.len() - 4]).unwrap();
+        assert!(validate(&path).unwrap_err().to_string().contains("truncated"));
+
+        std::fs::write(&path, &data[..30]).unwrap();
+        assert!(validate(&path).is_err());
+
+        let mut newer = data.clone();
+        newer[4..8].copy_from_slice(&4u32.to_le_bytes());
+        std::fs::write(&path, &newer).unwrap();
+        let error = validate(&path).unwrap_err();
+        assert!(error.downcast_ref::<UnsupportedGgufVersion>().is_some_and(|e| e.is_newer()));
+
+        std::fs::write(&path, b"GGML and some bytes to read").unwrap();
+        assert!(validate(&path).unwrap_err().to_string().contains("magic"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
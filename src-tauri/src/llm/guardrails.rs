@@ -0,0 +1,211 @@
+/// Guardrails - filters applied to a reply between generation and persistence
+///
+/// A policy blocks keywords and regex patterns, redacts personal data (email
+/// addresses, API keys) and caps the reply length. The global policy applies to
+/// every conversation, an agent can carry its own. Every rule that changed the
+/// text is reported so the UI can tell the user.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+const BLOCKED: &str = "[blocked]";
+const REDACTED_EMAIL: &str = "[email]";
+const REDACTED_KEY: &str = "[redacted key]";
+
+const EMAIL_PATTERN: &str = r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}";
+
+/// Well-known secret formats: OpenAI/Anthropic, AWS, GitHub, Hugging Face, Slack, PEM private keys
+const KEY_PATTERN: &str = concat!(
+    r"\bsk-(?:ant-)?[A-Za-z0-9_-]{20,}",
+    r"|\bAKIA[0-9A-Z]{16}\b",
+    r"|\bgh[pousr]_[A-Za-z0-9]{36,}",
+    r"|\bhf_[A-Za-z0-9]{30,}",
+    r"|\bxox[abprs]-[A-Za-z0-9-]{10,}",
+    r"|-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+);
+
+/// Filters of a guardrail policy (everything disabled by default)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct GuardrailPolicy {
+    /// Words or phrases replaced in replies (case insensitive)
+    pub blocked_keywords: Vec<String>,
+    /// Regular expressions replaced in replies
+    pub blocked_patterns: Vec<String>,
+    /// Mask email addresses and API keys
+    pub redact_pii: bool,
+    /// Maximum reply length, in characters
+    #[ts(type = "number | null")]
+    pub max_chars: Option<usize>,
+}
+
+/// Rule that modified a reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardrailRule {
+    Keyword,
+    Pattern,
+    Email,
+    SecretKey,
+    MaxLength,
+}
+
+/// A rule and the number of times it applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuardrailTrigger {
+    pub rule: GuardrailRule,
+    pub count: usize,
+}
+
+/// Reply after the guardrails
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuardrailOutcome {
+    pub text: String,
+    pub triggers: Vec<GuardrailTrigger>,
+}
+
+impl GuardrailOutcome {
+    pub fn is_modified(&self) -> bool {
+        !self.triggers.is_empty()
+    }
+}
+
+impl GuardrailPolicy {
+    pub fn is_enabled(&self) -> bool {
+        !self.blocked_keywords.is_empty()
+            || !self.blocked_patterns.is_empty()
+            || self.redact_pii
+            || self.max_chars.is_some()
+    }
+
+    /// Check the patterns compile and the limits make sense
+    pub fn validate(&self) -> Result<(), String> {
+        for pattern in &self.blocked_patterns {
+            Regex::new(pattern).map_err(|e| format!("Invalid pattern {:?}: {}", pattern, e))?;
+        }
+        if self.blocked_keywords.iter().any(|k| k.trim().is_empty()) {
+            return Err("Blocked keywords must not be empty".to_string());
+        }
+        if self.max_chars == Some(0) {
+            return Err("The maximum length must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// Apply the policy to a reply
+    pub fn apply(&self, text: &str) -> GuardrailOutcome {
+        let mut outcome = GuardrailOutcome { text: text.to_string(), triggers: Vec::new() };
+
+        for keyword in &self.blocked_keywords {
+            let pattern = RegexBuilder::new(&regex::escape(keyword.trim()))
+                .case_insensitive(true)
+                .build();
+            if let Ok(pattern) = pattern {
+                replace(&mut outcome, &pattern, BLOCKED, GuardrailRule::Keyword);
+            }
+        }
+        // Invalid patterns are rejected by `validate` when the policy is saved
+        for pattern in self.blocked_patterns.iter().filter_map(|p| Regex::new(p).ok()) {
+            replace(&mut outcome, &pattern, BLOCKED, GuardrailRule::Pattern);
+        }
+
+        if self.redact_pii {
+            if let Ok(keys) = Regex::new(KEY_PATTERN) {
+                replace(&mut outcome, &keys, REDACTED_KEY, GuardrailRule::SecretKey);
+            }
+            if let Ok(emails) = Regex::new(EMAIL_PATTERN) {
+                replace(&mut outcome, &emails, REDACTED_EMAIL, GuardrailRule::Email);
+            }
+        }
+
+        if let Some(max_chars) = self.max_chars {
+            if let Some((cut, _)) = outcome.text.char_indices().nth(max_chars) {
+                outcome.text.truncate(cut);
+                outcome.text.push('…');
+                add_trigger(&mut outcome.triggers, GuardrailRule::MaxLength, 1);
+            }
+        }
+
+        outcome
+    }
+
+    /// Apply the policy to a reply and to its reasoning, returning the rules that
+    /// changed either of them
+    pub fn apply_reply(&self, text: &mut String, reasoning: &mut Option<String>) -> Vec<GuardrailTrigger> {
+        let outcome = self.apply(text);
+        *text = outcome.text;
+        let mut triggers = outcome.triggers;
+        if let Some(reasoning) = reasoning.as_mut() {
+            let outcome = self.apply(reasoning);
+            *reasoning = outcome.text;
+            for trigger in outcome.triggers {
+                add_trigger(&mut triggers, trigger.rule, trigger.count);
+            }
+        }
+        triggers
+    }
+}
+
+fn replace(outcome: &mut GuardrailOutcome, pattern: &Regex, replacement: &str, rule: GuardrailRule) {
+    let count = pattern.find_iter(&outcome.text).count();
+    if count == 0 {
+        return;
+    }
+    outcome.text = pattern.replace_all(&outcome.text, regex::NoExpand(replacement)).into_owned();
+    add_trigger(&mut outcome.triggers, rule, count);
+}
+
+fn add_trigger(triggers: &mut Vec<GuardrailTrigger>, rule: GuardrailRule, count: usize) {
+    match triggers.iter_mut().find(|t| t.rule == rule) {
+        Some(trigger) => trigger.count += count,
+        None => triggers.push(GuardrailTrigger { rule, count }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guardrail_policy() {
+        let policy = GuardrailPolicy {
+            blocked_keywords: vec!["Secret Project".to_string()],
+            blocked_patterns: vec![r"\b\d{4}-\d{4}\b".to_string()],
+            redact_pii: true,
+            max_chars: None,
+        };
+        assert!(policy.validate().is_ok());
+
+        let outcome = policy.apply(
+            "The secret project code is 1234-5678, mail bob@example.com with key sk-abcdefghijklmnopqrstuvwxyz",
+        );
+        assert_eq!(
+            outcome.text,
+            "The [blocked] code is [blocked], mail [email] with key [redacted key]"
+        );
+        assert_eq!(outcome.triggers.len(), 4);
+
+        let clean = policy.apply("Nothing to see here");
+        assert!(!clean.is_modified());
+
+        let capped = GuardrailPolicy { max_chars: Some(5), ..Default::default() }.apply("Héllo world");
+        assert_eq!(capped.text, "Héllo…");
+        assert_eq!(capped.triggers, vec![GuardrailTrigger { rule: GuardrailRule::MaxLength, count: 1 }]);
+
+        // The reasoning shown to the user is filtered too
+        let mut text = "Ask bob@example.com".to_string();
+        let mut reasoning = Some("The secret project belongs to bob@example.com".to_string());
+        let triggers = policy.apply_reply(&mut text, &mut reasoning);
+        assert_eq!(text, "Ask [email]");
+        assert_eq!(reasoning.as_deref(), Some("The [blocked] belongs to [email]"));
+        assert_eq!(triggers, vec![
+            GuardrailTrigger { rule: GuardrailRule::Email, count: 2 },
+            GuardrailTrigger { rule: GuardrailRule::Keyword, count: 1 },
+        ]);
+
+        let invalid = GuardrailPolicy { blocked_patterns: vec!["(".to_string()], ..Default::default() };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,605 @@
+/// Engine handle - serialized access to the LLM engine through a job queue
+///
+/// The `LLMEngine` is owned by a dedicated OS thread that processes jobs one at a
+/// time, so llama.cpp decode loops never run on (and starve) the tokio runtime.
+/// Commands only hold a cheap, cloneable handle whose async methods are a facade
+/// over the queue: no lock is kept while a generation is running.
+
+use super::backend::{ChatMessage, LLMBackend};
+use super::capabilities::ModelCapabilities;
+use super::config::{LLMConfig, ModelConfig, SamplingConfig, SamplingParams};
+use super::engine::{LLMEngine, LLMResponse};
+use super::prompt_cache::{PromptCache, PromptCacheStats};
+use anyhow::{Context, Result};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+/// Forward streamed chunks until the channel closes, grouping the chunks received
+/// within `pacing` into one call (fewer UI events when tokens come very fast)
+///
+/// The first chunk after a pause is forwarded at once, so pacing never delays the
+/// start of a reply; the pending text is flushed when the window ends, even if no
+/// new chunk arrives.
+async fn forward_chunks<F>(received: &mut mpsc::UnboundedReceiver<String>, pacing: Duration, callback: &mut F) -> Result<()>
+where
+    F: FnMut(String) -> Result<()>,
+{
+    let mut pending = String::new();
+    let mut last_flush: Option<Instant> = None;
+    loop {
+        let next = match last_flush {
+            Some(flushed) if !pending.is_empty() => {
+                match tokio::time::timeout_at(flushed + pacing, received.recv()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        callback(std::mem::take(&mut pending))?;
+                        last_flush = Some(Instant::now());
+                        continue;
+                    }
+                }
+            }
+            _ => received.recv().await,
+        };
+        let Some(chunk) = next else {
+            break;
+        };
+
+        pending.push_str(&chunk);
+        if last_flush.is_none_or(|flushed| flushed.elapsed() >= pacing) {
+            callback(std::mem::take(&mut pending))?;
+            last_flush = Some(Instant::now());
+        }
+    }
+    if !pending.is_empty() {
+        callback(pending)?;
+    }
+    Ok(())
+}
+
+/// Work queued for the engine worker
+enum EngineJob {
+    Load {
+        config: ModelConfig,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    Unload {
+        reply: oneshot::Sender<Result<()>>,
+    },
+    Generate {
+        messages: Vec<ChatMessage>,
+        sampling: Option<SamplingParams>,
+        n_ctx: Option<usize>,
+        reply: oneshot::Sender<Result<LLMResponse>>,
+    },
+    Continue {
+        messages: Vec<ChatMessage>,
+        partial: String,
+        sampling: Option<SamplingParams>,
+        n_ctx: Option<usize>,
+        reply: oneshot::Sender<Result<LLMResponse>>,
+    },
+    GenerateStream {
+        messages: Vec<ChatMessage>,
+        sampling: Option<SamplingParams>,
+        n_ctx: Option<usize>,
+        chunks: mpsc::UnboundedSender<String>,
+        reply: oneshot::Sender<Result<LLMResponse>>,
+    },
+    GenerateBatch {
+        prompts: Vec<String>,
+        reply: oneshot::Sender<Result<Vec<LLMResponse>>>,
+    },
+    ProbeCapabilities {
+        reply: oneshot::Sender<Result<ModelCapabilities>>,
+    },
+    Embed {
+        texts: Vec<String>,
+        reply: oneshot::Sender<Result<Vec<Vec<f32>>>>,
+    },
+    CountTokens {
+        texts: Vec<String>,
+        reply: oneshot::Sender<Result<Vec<usize>>>,
+    },
+    /// Load the reranker at this path (None unloads it)
+    LoadReranker {
+        path: Option<String>,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    Rerank {
+        query: String,
+        passages: Vec<String>,
+        reply: oneshot::Sender<Result<Vec<f32>>>,
+    },
+}
+
+impl EngineJob {
+    /// Answer the job with an error without running it
+    fn reject(self, message: &str) {
+        let error = || anyhow::anyhow!(message.to_string());
+        match self {
+            EngineJob::Load { reply, .. } | EngineJob::Unload { reply } | EngineJob::LoadReranker { reply, .. } => {
+                let _ = reply.send(Err(error()));
+            }
+            EngineJob::Generate { reply, .. }
+            | EngineJob::Continue { reply, .. }
+            | EngineJob::GenerateStream { reply, .. } => {
+                let _ = reply.send(Err(error()));
+            }
+            EngineJob::GenerateBatch { reply, .. } => {
+                let _ = reply.send(Err(error()));
+            }
+            EngineJob::ProbeCapabilities { reply } => {
+                let _ = reply.send(Err(error()));
+            }
+            EngineJob::Embed { reply, .. } => {
+                let _ = reply.send(Err(error()));
+            }
+            EngineJob::CountTokens { reply, .. } => {
+                let _ = reply.send(Err(error()));
+            }
+            EngineJob::Rerank { reply, .. } => {
+                let _ = reply.send(Err(error()));
+            }
+        }
+    }
+}
+
+/// Cloneable handle to the engine worker
+#[derive(Clone)]
+pub struct EngineHandle {
+    jobs: mpsc::UnboundedSender<EngineJob>,
+    /// Configuration shared with the worker, applied before each job
+    config: Arc<std::sync::RwLock<LLMConfig>>,
+    loaded: Arc<AtomicBool>,
+    /// Trained context size of the loaded model (0 = unknown or no model)
+    trained_context: Arc<AtomicUsize>,
+    capabilities: Arc<Mutex<Option<ModelCapabilities>>>,
+    prompt_cache: Arc<std::sync::Mutex<PromptCache>>,
+    cancel: Arc<AtomicBool>,
+    /// Set at shutdown: queued jobs are rejected, only unloading still runs
+    stopping: Arc<AtomicBool>,
+    /// The window lost the focus (threads are limited in efficiency mode)
+    unfocused: Arc<AtomicBool>,
+}
+
+impl EngineHandle {
+    /// Move the engine to its worker thread and return a handle to it
+    pub fn spawn(engine: LLMEngine) -> Result<Self> {
+        let (jobs, receiver) = mpsc::unbounded_channel();
+        let handle = Self {
+            jobs,
+            config: Arc::new(std::sync::RwLock::new(engine.config.clone())),
+            loaded: Arc::new(AtomicBool::new(false)),
+            trained_context: Arc::new(AtomicUsize::new(0)),
+            capabilities: Arc::clone(&engine.capabilities),
+            prompt_cache: Arc::clone(&engine.prompt_cache),
+            cancel: Arc::clone(&engine.cancel),
+            stopping: Arc::new(AtomicBool::new(false)),
+            unfocused: Arc::new(AtomicBool::new(false)),
+        };
+
+        let config = Arc::clone(&handle.config);
+        let loaded = Arc::clone(&handle.loaded);
+        let trained_context = Arc::clone(&handle.trained_context);
+        let stopping = Arc::clone(&handle.stopping);
+        let unfocused = Arc::clone(&handle.unfocused);
+        std::thread::Builder::new()
+            .name("llm-engine".to_string())
+            .spawn(move || run_worker(engine, receiver, config, loaded, trained_context, stopping, unfocused))
+            .context("Failed to spawn LLM engine worker")?;
+
+        Ok(handle)
+    }
+
+    async fn request<T>(&self, job: impl FnOnce(oneshot::Sender<Result<T>>) -> EngineJob) -> Result<T> {
+        let (reply, response) = oneshot::channel();
+        self.jobs
+            .send(job(reply))
+            .map_err(|_| anyhow::anyhow!("LLM engine worker stopped"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("LLM engine worker dropped the request"))?
+    }
+
+    /// Snapshot of the current configuration
+    pub fn config(&self) -> LLMConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Update the configuration; the worker applies it before its next job
+    /// (settings read at load time, like GPU layers, need a reload)
+    pub fn update_config(&self, update: impl FnOnce(&mut LLMConfig)) {
+        update(&mut self.config.write().unwrap());
+    }
+
+    /// Replace the sampling settings, used from the next generation on
+    pub fn update_sampling_config(&self, sampling: SamplingConfig) {
+        self.update_config(|config| config.sampling = sampling);
+    }
+
+    /// Load a model with the given settings (replacing the loaded one);
+    /// the sampling settings are left untouched
+    pub async fn load_model(&self, config: ModelConfig) -> Result<()> {
+        self.request(|reply| EngineJob::Load { config, reply }).await
+    }
+
+    pub async fn unload_model(&self) -> Result<()> {
+        self.request(|reply| EngineJob::Unload { reply }).await
+    }
+
+    /// Stop the running generation, which returns the text generated so far
+    pub fn cancel_generation(&self) {
+        self.cancel.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop the running generation, reject the queued jobs and unload the model
+    pub async fn shutdown(&self) -> Result<()> {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.cancel_generation();
+        self.unload_model().await
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.load(Ordering::SeqCst)
+    }
+
+    /// Track the window focus; in efficiency mode the next jobs use fewer threads
+    pub fn set_focused(&self, focused: bool) {
+        self.unfocused.store(!focused, Ordering::SeqCst);
+    }
+
+    /// Context size the loaded model was trained with, if known
+    pub fn trained_context(&self) -> Option<usize> {
+        Some(self.trained_context.load(Ordering::SeqCst)).filter(|n| *n > 0)
+    }
+
+    /// Context size actually used for a request of `requested` tokens (None = configured size),
+    /// capped by the trained context of the loaded model
+    pub fn usable_context(&self, requested: Option<usize>) -> usize {
+        let n_ctx = requested.unwrap_or_else(|| self.config().model.n_ctx);
+        match self.trained_context() {
+            Some(trained) => n_ctx.min(trained),
+            None => n_ctx,
+        }
+    }
+
+    pub async fn generate(&self, messages: Vec<ChatMessage>) -> Result<LLMResponse> {
+        self.generate_with(messages, None).await
+    }
+
+    /// Generate with sampling overrides that only apply to this request
+    pub async fn generate_with(
+        &self,
+        messages: Vec<ChatMessage>,
+        sampling: Option<SamplingParams>,
+    ) -> Result<LLMResponse> {
+        self.generate_in_context(messages, sampling, None).await
+    }
+
+    /// Generate in a context of `n_ctx` tokens (a session's own size, None = configured size)
+    ///
+    /// The context is created for each generation, so sessions with different sizes
+    /// do not need a reload; the KV cache is sized with `usable_context`.
+    pub async fn generate_in_context(
+        &self,
+        messages: Vec<ChatMessage>,
+        sampling: Option<SamplingParams>,
+        n_ctx: Option<usize>,
+    ) -> Result<LLMResponse> {
+        let n_ctx = n_ctx.map(|n| self.usable_context(Some(n)));
+        self.request(|reply| EngineJob::Generate { messages, sampling, n_ctx, reply }).await
+    }
+
+    /// Continue the assistant reply `partial` that ends the conversation (see `LLMEngine::continue_reply`)
+    pub async fn continue_reply(
+        &self,
+        messages: Vec<ChatMessage>,
+        partial: String,
+        sampling: Option<SamplingParams>,
+        n_ctx: Option<usize>,
+    ) -> Result<LLMResponse> {
+        let n_ctx = n_ctx.map(|n| self.usable_context(Some(n)));
+        self.request(|reply| EngineJob::Continue { messages, partial, sampling, n_ctx, reply }).await
+    }
+
+    /// Generate a streaming response; `callback` runs on the caller's task for each chunk
+    /// (an error returned by the callback stops forwarding chunks)
+    pub async fn generate_stream<F>(&self, messages: Vec<ChatMessage>, callback: F) -> Result<LLMResponse>
+    where
+        F: FnMut(String) -> Result<()>,
+    {
+        self.generate_stream_with(messages, None, callback).await
+    }
+
+    /// Streaming generation with sampling overrides that only apply to this request
+    pub async fn generate_stream_with<F>(
+        &self,
+        messages: Vec<ChatMessage>,
+        sampling: Option<SamplingParams>,
+        callback: F,
+    ) -> Result<LLMResponse>
+    where
+        F: FnMut(String) -> Result<()>,
+    {
+        self.generate_stream_in_context(messages, sampling, None, callback).await
+    }
+
+    /// Streaming generation in a context of `n_ctx` tokens (see `generate_in_context`)
+    pub async fn generate_stream_in_context<F>(
+        &self,
+        messages: Vec<ChatMessage>,
+        sampling: Option<SamplingParams>,
+        n_ctx: Option<usize>,
+        mut callback: F,
+    ) -> Result<LLMResponse>
+    where
+        F: FnMut(String) -> Result<()>,
+    {
+        let n_ctx = n_ctx.map(|n| self.usable_context(Some(n)));
+        let (chunks, mut received) = mpsc::unbounded_channel();
+        let (reply, response) = oneshot::channel();
+        self.jobs
+            .send(EngineJob::GenerateStream { messages, sampling, n_ctx, chunks, reply })
+            .map_err(|_| anyhow::anyhow!("LLM engine worker stopped"))?;
+
+        let pacing = Duration::from_millis(self.config().stream_pacing_ms);
+        if let Err(e) = forward_chunks(&mut received, pacing, &mut callback).await {
+            warn!("Stream callback failed, dropping remaining chunks: {}", e);
+            received.close();
+        }
+
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("LLM engine worker dropped the request"))?
+    }
+
+    pub async fn generate_batch(&self, prompts: Vec<String>) -> Result<Vec<LLMResponse>> {
+        self.request(|reply| EngineJob::GenerateBatch { prompts, reply }).await
+    }
+
+    /// One normalized embedding per text, computed with the loaded model
+    pub async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        self.request(|reply| EngineJob::Embed { texts, reply }).await
+    }
+
+    /// Token count of each text with the tokenizer of the loaded model
+    pub async fn count_tokens(&self, texts: Vec<String>) -> Result<Vec<usize>> {
+        self.request(|reply| EngineJob::CountTokens { texts, reply }).await
+    }
+
+    /// Load a reranker GGUF (kept until another one is loaded)
+    pub async fn load_reranker(&self, path: String) -> Result<()> {
+        self.request(|reply| EngineJob::LoadReranker { path: Some(path), reply }).await
+    }
+
+    pub async fn unload_reranker(&self) -> Result<()> {
+        self.request(|reply| EngineJob::LoadReranker { path: None, reply }).await
+    }
+
+    /// Relevance score of each passage for the query, with the loaded reranker
+    pub async fn rerank(&self, query: String, passages: Vec<String>) -> Result<Vec<f32>> {
+        self.request(|reply| EngineJob::Rerank { query, passages, reply }).await
+    }
+
+    pub async fn probe_capabilities(&self) -> Result<ModelCapabilities> {
+        self.request(|reply| EngineJob::ProbeCapabilities { reply }).await
+    }
+
+    pub async fn capabilities(&self) -> Option<ModelCapabilities> {
+        self.capabilities.lock().await.clone()
+    }
+
+    pub async fn set_capabilities(&self, capabilities: ModelCapabilities) {
+        *self.capabilities.lock().await = Some(capabilities);
+    }
+
+    pub fn gpu_info(&self) -> String {
+        LLMEngine::gpu_info_for(&self.config().model)
+    }
+
+    /// Update the prompt cache limits (0 disables the cache)
+    pub fn configure_prompt_cache(&self, max_entries: usize, max_size_mb: usize) {
+        self.update_config(|config| {
+            config.model.prompt_cache_entries = max_entries;
+            config.model.prompt_cache_size_mb = max_size_mb;
+        });
+        self.prompt_cache.lock().unwrap().configure(max_entries, max_size_mb);
+    }
+
+    pub fn clear_prompt_cache(&self) {
+        self.prompt_cache.lock().unwrap().clear();
+    }
+
+    pub fn prompt_cache_stats(&self) -> PromptCacheStats {
+        self.prompt_cache.lock().unwrap().stats()
+    }
+}
+
+/// Worker loop: owns the engine and processes jobs in order on its own thread
+///
+/// The engine futures are driven by a current-thread tokio runtime owned by the
+/// worker, so the engine can use tokio I/O and timers (model probe, downloads).
+fn run_worker(
+    mut engine: LLMEngine,
+    mut receiver: mpsc::UnboundedReceiver<EngineJob>,
+    config: Arc<std::sync::RwLock<LLMConfig>>,
+    loaded: Arc<AtomicBool>,
+    trained_context: Arc<AtomicUsize>,
+    stopping: Arc<AtomicBool>,
+    unfocused: Arc<AtomicBool>,
+) {
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            error!("Failed to start LLM engine worker: {}", e);
+            return;
+        }
+    };
+    info!("LLM engine worker started");
+
+    while let Some(job) = receiver.blocking_recv() {
+        if stopping.load(Ordering::SeqCst) {
+            if !matches!(job, EngineJob::Unload { .. }) {
+                job.reject("LLM engine is shutting down");
+                continue;
+            }
+        } else {
+            // A cancellation only applies to the generation running when it was requested
+            engine.cancel.store(false, Ordering::SeqCst);
+        }
+        engine.config = config.read().unwrap().clone();
+        if engine.config.model.efficiency_mode && unfocused.load(Ordering::SeqCst) {
+            engine.config.model.limit_threads();
+        }
+
+        match job {
+            EngineJob::Load { config: new_config, reply } => {
+                let result = runtime.block_on(async {
+                    if engine.is_loaded().await {
+                        engine.unload_model().await?;
+                    }
+                    engine.config.model = new_config.clone();
+                    engine.load_model().await
+                });
+                // The shared configuration only describes a model that actually loaded
+                if result.is_ok() {
+                    config.write().unwrap().model = new_config;
+                }
+                loaded.store(runtime.block_on(engine.is_loaded()), Ordering::SeqCst);
+                trained_context.store(runtime.block_on(engine.trained_context()).unwrap_or(0), Ordering::SeqCst);
+                let _ = reply.send(result);
+            }
+            EngineJob::Unload { reply } => {
+                let result = runtime.block_on(engine.unload_model());
+                loaded.store(false, Ordering::SeqCst);
+                trained_context.store(0, Ordering::SeqCst);
+                let _ = reply.send(result);
+            }
+            EngineJob::Generate { messages, sampling, n_ctx, reply } => {
+                // The shared configuration is restored before the next job
+                if let Some(sampling) = sampling {
+                    sampling.apply_to(&mut engine.config.sampling);
+                }
+                if let Some(n_ctx) = n_ctx {
+                    engine.config.model.n_ctx = n_ctx;
+                    engine.config.model.context_size = n_ctx;
+                }
+                let _ = reply.send(runtime.block_on(engine.generate(&messages)));
+            }
+            EngineJob::Continue { messages, partial, sampling, n_ctx, reply } => {
+                if let Some(sampling) = sampling {
+                    sampling.apply_to(&mut engine.config.sampling);
+                }
+                if let Some(n_ctx) = n_ctx {
+                    engine.config.model.n_ctx = n_ctx;
+                    engine.config.model.context_size = n_ctx;
+                }
+                let _ = reply.send(runtime.block_on(engine.continue_reply(&messages, &partial)));
+            }
+            EngineJob::GenerateStream { messages, sampling, n_ctx, chunks, reply } => {
+                if let Some(sampling) = sampling {
+                    sampling.apply_to(&mut engine.config.sampling);
+                }
+                if let Some(n_ctx) = n_ctx {
+                    engine.config.model.n_ctx = n_ctx;
+                    engine.config.model.context_size = n_ctx;
+                }
+                let result = runtime.block_on(engine.generate_stream(&messages, |chunk| {
+                    chunks
+                        .send(chunk)
+                        .map_err(|_| anyhow::anyhow!("Stream receiver dropped"))
+                }));
+                drop(chunks);
+                let _ = reply.send(result);
+            }
+            EngineJob::GenerateBatch { prompts, reply } => {
+                let _ = reply.send(runtime.block_on(engine.generate_batch(&prompts)));
+            }
+            EngineJob::ProbeCapabilities { reply } => {
+                let _ = reply.send(runtime.block_on(engine.probe_capabilities()));
+            }
+            EngineJob::Embed { texts, reply } => {
+                let _ = reply.send(runtime.block_on(engine.embed(&texts)));
+            }
+            EngineJob::CountTokens { texts, reply } => {
+                let _ = reply.send(runtime.block_on(engine.count_tokens(&texts)));
+            }
+            EngineJob::LoadReranker { path, reply } => {
+                let result = runtime.block_on(async {
+                    match path {
+                        Some(path) => engine.load_reranker(&path).await,
+                        None => {
+                            engine.unload_reranker().await;
+                            Ok(())
+                        }
+                    }
+                });
+                let _ = reply.send(result);
+            }
+            EngineJob::Rerank { query, passages, reply } => {
+                let _ = reply.send(runtime.block_on(engine.rerank(&query, &passages)));
+            }
+        }
+    }
+
+    info!("LLM engine worker stopped");
+}
+
+#[async_trait::async_trait]
+impl LLMBackend for EngineHandle {
+    fn name(&self) -> String {
+        format!("local:{}", self.config().model.model_path)
+    }
+
+    async fn is_ready(&self) -> bool {
+        self.is_loaded()
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<LLMResponse> {
+        self.generate(messages.to_vec()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn forwarded(pacing_ms: u64, send: impl FnOnce(mpsc::UnboundedSender<String>) + Send + 'static) -> Vec<String> {
+        let (chunks, mut received) = mpsc::unbounded_channel();
+        tokio::spawn(async move { send(chunks) });
+        let mut calls = Vec::new();
+        forward_chunks(&mut received, Duration::from_millis(pacing_ms), &mut |chunk| {
+            calls.push(chunk);
+            Ok(())
+        }).await.unwrap();
+        calls
+    }
+
+    #[tokio::test]
+    async fn test_stream_pacing() {
+        let burst = |chunks: mpsc::UnboundedSender<String>| {
+            for chunk in ["a", "b", "c"] {
+                chunks.send(chunk.to_string()).unwrap();
+            }
+        };
+        // Without pacing every chunk is forwarded on its own
+        assert_eq!(forwarded(0, burst).await, vec!["a", "b", "c"]);
+        // The first chunk goes out at once, the burst that follows in one call
+        assert_eq!(forwarded(50, burst).await, vec!["a", "bc"]);
+
+        // Pending text is flushed at the end of the window, without waiting for the next chunk
+        let calls = forwarded(50, |chunks| {
+            tokio::spawn(async move {
+                chunks.send("a".to_string()).unwrap();
+                chunks.send("b".to_string()).unwrap();
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                chunks.send("c".to_string()).unwrap();
+            });
+        }).await;
+        assert_eq!(calls, vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,71 @@
+/// Token log probabilities - opt-in inspection of the model's choices
+///
+/// Log probabilities are computed from the raw logits (before temperature, top-k
+/// or top-p), so they describe the model itself rather than the sampler settings.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Maximum number of alternatives reported per token
+pub const MAX_TOP_LOGPROBS: usize = 20;
+
+/// A candidate token and its log probability
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+pub struct TokenAlternative {
+    pub token: String,
+    pub logprob: f32,
+}
+
+/// Log probability of a generated token and of its best alternatives
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+pub struct TokenLogprob {
+    pub token: String,
+    pub logprob: f32,
+    /// Most likely tokens at this position, best first (may include the chosen one)
+    pub top: Vec<TokenAlternative>,
+}
+
+/// Log probability of `chosen` and the `top_n` most likely token ids
+pub fn token_logprobs(logits: &[f32], chosen: usize, top_n: usize) -> (f32, Vec<(usize, f32)>) {
+    if logits.is_empty() {
+        return (f32::NEG_INFINITY, vec![]);
+    }
+
+    // log-softmax, shifted by the max logit for numerical stability
+    let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let log_sum = logits.iter().map(|l| (l - max).exp()).sum::<f32>().ln() + max;
+    let logprob = |id: usize| logits.get(id).map_or(f32::NEG_INFINITY, |l| l - log_sum);
+
+    let top_n = top_n.min(MAX_TOP_LOGPROBS).min(logits.len());
+    let mut ids: Vec<usize> = (0..logits.len()).collect();
+    let top = if top_n == 0 {
+        vec![]
+    } else {
+        ids.select_nth_unstable_by(top_n - 1, |a, b| logits[*b].total_cmp(&logits[*a]));
+        let mut top: Vec<usize> = ids[..top_n].to_vec();
+        top.sort_by(|a, b| logits[*b].total_cmp(&logits[*a]));
+        top.into_iter().map(|id| (id, logprob(id))).collect()
+    };
+
+    (logprob(chosen), top)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_logprobs() {
+        let logits = [1.0, 3.0, 2.0, 0.5];
+        let (chosen, top) = token_logprobs(&logits, 2, 2);
+
+        let total: f32 = logits.iter().map(|l| l.exp()).sum();
+        assert!((chosen - (2.0f32.exp() / total).ln()).abs() < 1e-5);
+        assert_eq!(top.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(top[0].1 > top[1].1);
+
+        let (_, none) = token_logprobs(&logits, 0, 0);
+        assert!(none.is_empty());
+        assert_eq!(token_logprobs(&logits, 0, 100).1.len(), 4);
+    }
+}
@@ -0,0 +1,117 @@
+/// Deterministic backend for tests without a GGUF model
+///
+/// Built with the `mock-backend` feature (and in unit tests). Replies are
+/// picked by the first rule whose trigger appears in the last user or tool
+/// message, otherwise that message is echoed back, so a run always produces
+/// the same output. Sessions use it through `BackendKind::Mock`.
+
+use super::backend::{ChatMessage, ChatRole, LLMBackend};
+use super::engine::LLMResponse;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Reply returned when the last user or tool message contains `trigger`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MockRule {
+    pub trigger: String,
+    pub reply: String,
+}
+
+/// Backend answering from canned rules
+#[derive(Debug, Clone, Default)]
+pub struct MockBackend {
+    rules: Vec<MockRule>,
+}
+
+impl MockBackend {
+    pub fn new(rules: Vec<MockRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Add a rule, checked after the existing ones
+    pub fn with_rule(mut self, trigger: impl Into<String>, reply: impl Into<String>) -> Self {
+        self.rules.push(MockRule { trigger: trigger.into(), reply: reply.into() });
+        self
+    }
+
+    /// Reply to a conversation
+    pub fn reply(&self, messages: &[ChatMessage]) -> String {
+        let prompt = messages
+            .iter()
+            .rev()
+            .find(|m| matches!(m.role, ChatRole::User | ChatRole::Tool))
+            .map(|m| m.content.as_str())
+            .unwrap_or_default();
+        self.rules
+            .iter()
+            .find(|rule| prompt.contains(&rule.trigger))
+            .map(|rule| rule.reply.clone())
+            .unwrap_or_else(|| format!("Echo: {}", prompt))
+    }
+}
+
+/// One token per word
+fn response(text: String) -> LLMResponse {
+    LLMResponse {
+        tokens_generated: text.split_whitespace().count(),
+        text,
+        tool_calls: vec![],
+        done: true,
+        truncated_tokens: 0,
+        logprobs: None,
+        reasoning: None,
+    }
+}
+
+#[async_trait::async_trait]
+impl LLMBackend for MockBackend {
+    fn name(&self) -> String {
+        "mock".to_string()
+    }
+
+    async fn is_ready(&self) -> bool {
+        true
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<LLMResponse> {
+        Ok(response(self.reply(messages)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+    use crate::mcp::ToolRegistry;
+    use crate::tasks::{TaskRepository, TaskRunner, TaskStatus};
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_mock_backend() {
+        let backend = MockBackend::default().with_rule("weather", "It is sunny.");
+        let messages = vec![ChatMessage::system("Be brief."), ChatMessage::user("What's the weather?")];
+        assert_eq!(backend.chat(&messages).await.unwrap().text, "It is sunny.");
+        assert_eq!(backend.chat(&[ChatMessage::user("hi there")]).await.unwrap().text, "Echo: hi there");
+    }
+
+    #[tokio::test]
+    async fn test_mock_backend_drives_tool_loop() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        // Once the tool result is in the conversation, the model gives its answer
+        let backend = MockBackend::default()
+            .with_rule("Echo: hi", r#"{"final_answer": "The tool said hi"}"#)
+            .with_rule("Goal:", r#"{"tool": "echo", "arguments": {"text": "hi"}}"#);
+        let runner = TaskRunner::new(
+            Arc::new(backend),
+            Arc::new(RwLock::new(ToolRegistry::new())),
+            Arc::new(TaskRepository::new(db.pool().clone())),
+        );
+
+        let run = runner.run("Say hi", None, |_| {}).await.unwrap();
+        assert_eq!(run.status, TaskStatus::Completed);
+        assert_eq!(run.result.as_deref(), Some("The tool said hi"));
+        assert_eq!(run.steps.len(), 2);
+    }
+}
@@ -1,12 +1,51 @@
 /// Module LLM - Gestion du moteur d'inférence local
 
+pub mod backend;
+pub mod backend_info;
+pub mod capabilities;
 pub mod config;
+pub mod context_shift;
+pub mod context_usage;
 pub mod engine;
+pub mod gguf;
+pub mod guardrails;
+pub mod handle;
+pub mod logprobs;
+#[cfg(any(test, feature = "mock-backend"))]
+pub mod mock;
 pub mod model_manager;
+pub mod postprocess;
+pub mod prompt_cache;
+pub mod recommend;
+pub mod remote;
+pub mod scheduler;
+pub mod stop;
+pub mod switch;
+pub mod system;
+pub mod threads;
+pub mod watcher;
 
 #[cfg(test)]
 mod tests;
 
+pub use backend::{BackendKind, ChatMessage, ChatRole, LLMBackend};
+pub use backend_info::BackendInfo;
+pub use capabilities::{Capability, ModelCapabilities};
+pub use context_usage::{ContextBreakdown, ContextComponent, ContextComponentKind};
 pub use engine::{LLMEngine, LLMResponse, ToolCall};
-pub use config::LLMConfig;
-pub use model_manager::{ModelManager, ModelInfo};
+pub use guardrails::{GuardrailOutcome, GuardrailPolicy, GuardrailRule, GuardrailTrigger};
+pub use handle::EngineHandle;
+pub use logprobs::{TokenAlternative, TokenLogprob};
+#[cfg(any(test, feature = "mock-backend"))]
+pub use mock::{MockBackend, MockRule};
+pub use config::{FlashAttention, LLMConfig, ModelConfig, SamplingConfig, SamplingParams};
+pub use model_manager::{sort_models, ImportMode, ModelManager, ModelInfo, ModelSort, TrashedModel};
+pub use postprocess::{PostProcessConfig, ReasoningMode, ReasoningSplitter, StreamPiece};
+pub use recommend::RecommendedModel;
+pub use remote::{AnthropicBackend, OpenAICompatibleBackend, RemoteBackendConfig};
+pub use scheduler::{GenerationPermit, GenerationPriority, GenerationScheduler, QueueUpdate, SessionStatus};
+pub use stop::StopCondition;
+pub use switch::{SwitchStage, SwitchStatus, SwitchTracker};
+pub use system::{GpuMemory, SystemMetrics};
+pub use threads::ThreadSettings;
+pub use watcher::ModelsChanged;
@@ -1,16 +1,93 @@
 /// Model manager for handling model files
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::time::Duration;
 use anyhow::{Result, Context};
-use tracing::{info, error};
+use chrono::{DateTime, Utc};
+use crate::error::AppError;
+use super::gguf::{self, GgufHeader};
+use tracing::{info, error, warn};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Subfolder of the models directory holding the deleted models
+const TRASH_DIR: &str = ".trash";
+
+/// Environment variable overriding the models directory (containers, kiosks)
+pub const MODELS_DIR_ENV: &str = "AGENTS_RS_MODELS_DIR";
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct ModelInfo {
     pub name: String,
     pub file_name: String,
+    #[ts(type = "number")]
     pub size_bytes: u64,
     pub is_loaded: bool,
+    /// Number of times the model was loaded
+    #[serde(default)]
+    pub load_count: u32,
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub favorite: bool,
+}
+
+/// Order of the model list (favorites always come first)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelSort {
+    #[default]
+    Name,
+    /// Most loaded first
+    MostUsed,
+    /// Most recently loaded first
+    RecentlyUsed,
+    /// Largest first
+    Size,
+}
+
+/// Sort models, favorites first then by the given order (name as tie-breaker)
+pub fn sort_models(models: &mut [ModelInfo], sort: ModelSort) {
+    models.sort_by(|a, b| {
+        let order = match sort {
+            ModelSort::Name => std::cmp::Ordering::Equal,
+            ModelSort::MostUsed => b.load_count.cmp(&a.load_count),
+            ModelSort::RecentlyUsed => b.last_used_at.cmp(&a.last_used_at),
+            ModelSort::Size => b.size_bytes.cmp(&a.size_bytes),
+        };
+        b.favorite.cmp(&a.favorite).then(order).then_with(|| a.name.cmp(&b.name))
+    });
+}
+
+/// How a model file outside the models directory is imported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Copy the file into the models directory
+    #[default]
+    Copy,
+    /// Symlink the file, saving disk space (the source must stay in place)
+    Link,
+}
+
+/// A deleted model waiting in the trash
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TrashedModel {
+    /// File name the model is restored to
+    pub file_name: String,
+    #[ts(type = "number")]
+    pub size_bytes: u64,
+    pub trashed_at: DateTime<Utc>,
+}
+
+/// Trashed files are named `<deletion time in ms>_<file name>`
+fn parse_trash_name(trash_name: &str) -> Option<(DateTime<Utc>, &str)> {
+    let (millis, file_name) = trash_name.split_once('_')?;
+    Some((DateTime::from_timestamp_millis(millis.parse().ok()?)?, file_name))
 }
 
 pub struct ModelManager {
@@ -20,8 +97,11 @@ pub struct ModelManager {
 impl ModelManager {
     pub fn new() -> Result<Self> {
         // Determine models directory based on platform
-        let models_dir = get_models_directory()?;
-        
+        Self::with_directory(get_models_directory()?)
+    }
+
+    /// Manage the models of a given directory
+    pub fn with_directory(models_dir: PathBuf) -> Result<Self> {
         info!("ModelManager initialized with directory: {:?}", models_dir);
         info!("Models directory exists: {}", models_dir.exists());
         
@@ -75,6 +155,9 @@ impl ModelManager {
                             file_name,
                             size_bytes,
                             is_loaded: false,
+                            load_count: 0,
+                            last_used_at: None,
+                            favorite: false,
                         });
                     }
                 }
@@ -98,24 +181,191 @@ impl ModelManager {
         &self.models_dir
     }
 
-    /// Delete a model file
+    /// Validate a GGUF file and bring it into the models directory, returns
+    /// its file name and header
+    ///
+    /// Copies go through a `.part` file, so an interrupted import never shows
+    /// up as a truncated model.
+    pub fn import_model(&self, source: &Path, mode: ImportMode) -> Result<(String, GgufHeader)> {
+        let file_name = source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .filter(|n| n.to_lowercase().ends_with(".gguf"))
+            .ok_or_else(|| AppError::invalid_input("Only .gguf files can be imported"))?
+            .to_string();
+        if !source.is_file() {
+            return Err(AppError::invalid_input(format!("File not found: {}", source.display())).into());
+        }
+        let header = gguf::validate(source).map_err(|e| AppError::model_corrupt(&file_name, e))?;
+
+        let target = self.get_model_path(&file_name);
+        if target.exists() || target.is_symlink() {
+            return Err(AppError::invalid_input(format!("A model named {} already exists", file_name)).into());
+        }
+
+        match mode {
+            ImportMode::Copy => {
+                let part = self.models_dir.join(format!("{}.part", file_name));
+                fs::copy(source, &part)
+                    .with_context(|| format!("Failed to copy model file: {:?}", source))?;
+                fs::rename(&part, &target)
+                    .with_context(|| format!("Failed to move imported model: {:?}", part))?;
+            }
+            ImportMode::Link => {
+                let source = source
+                    .canonicalize()
+                    .with_context(|| format!("Failed to resolve model path: {:?}", source))?;
+                symlink_file(&source, &target)
+                    .with_context(|| format!("Failed to link model file: {:?}", source))?;
+            }
+        }
+
+        info!("Imported model {} ({:?})", file_name, mode);
+        Ok((file_name, header))
+    }
+
+    /// Folder of the deleted models
+    pub fn trash_directory(&self) -> PathBuf {
+        self.models_dir.join(TRASH_DIR)
+    }
+
+    /// Delete a model file by moving it to the trash, from which it can be
+    /// restored until the trash is purged
     pub fn delete_model(&self, model_name: &str) -> Result<()> {
         let path = self.get_model_path(model_name);
         
-        if !path.exists() {
-            return Err(anyhow::anyhow!("Model file not found: {}", model_name));
+        if !path.is_file() {
+            return Err(AppError::ModelNotFound { model: model_name.to_string() }.into());
         }
 
-        fs::remove_file(&path)
-            .with_context(|| format!("Failed to delete model file: {:?}", path))?;
+        let trash = self.trash_directory();
+        fs::create_dir_all(&trash)
+            .with_context(|| format!("Failed to create trash directory: {:?}", trash))?;
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or(model_name);
+        let trashed = trash.join(format!("{}_{}", Utc::now().timestamp_millis(), file_name));
+
+        // Same filesystem as the models: a rename, whatever the size of the file
+        fs::rename(&path, &trashed)
+            .with_context(|| format!("Failed to move model file to the trash: {:?}", path))?;
         
-        info!("Deleted model: {}", model_name);
+        info!("Moved model to the trash: {}", model_name);
+        Ok(())
+    }
+
+    /// Deleted models, most recently deleted first
+    pub fn list_trash(&self) -> Result<Vec<TrashedModel>> {
+        let mut models: Vec<TrashedModel> = self
+            .trash_entries()?
+            .into_iter()
+            .map(|(path, trashed_at, file_name)| TrashedModel {
+                size_bytes: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+                file_name,
+                trashed_at,
+            })
+            .collect();
+        models.sort_by_key(|model| std::cmp::Reverse(model.trashed_at));
+        Ok(models)
+    }
+
+    /// Whether a deleted model is still in the trash
+    pub fn is_trashed(&self, model_name: &str) -> bool {
+        self.trash_entries()
+            .map(|entries| entries.iter().any(|(_, _, file_name)| file_name == model_name))
+            .unwrap_or(false)
+    }
+
+    /// Move the last deleted version of a model back to the models directory
+    pub fn restore_model(&self, model_name: &str) -> Result<()> {
+        let target = self.get_model_path(model_name);
+        if target.exists() {
+            return Err(AppError::invalid_input(format!("A model named {} already exists", model_name)).into());
+        }
+
+        let (path, _, _) = self
+            .trash_entries()?
+            .into_iter()
+            .filter(|(_, _, file_name)| file_name == model_name)
+            .max_by_key(|(_, trashed_at, _)| *trashed_at)
+            .ok_or_else(|| AppError::ModelNotFound { model: model_name.to_string() })?;
+
+        fs::rename(&path, &target)
+            .with_context(|| format!("Failed to restore model file: {:?}", path))?;
+
+        info!("Restored model from the trash: {}", model_name);
         Ok(())
     }
+
+    /// Permanently delete the trashed models older than `max_age` (all of them
+    /// with `None`), returns the file names of the deleted models
+    pub fn purge_trash(&self, max_age: Option<Duration>) -> Result<Vec<String>> {
+        let now = Utc::now();
+        let mut purged = Vec::new();
+
+        for (path, trashed_at, file_name) in self.trash_entries()? {
+            let age = (now - trashed_at).to_std().unwrap_or_default();
+            if max_age.is_some_and(|max_age| age < max_age) {
+                continue;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => purged.push(file_name),
+                Err(e) => warn!("Failed to purge trashed model {:?}: {}", path, e),
+            }
+        }
+
+        if !purged.is_empty() {
+            info!("Purged {} models from the trash", purged.len());
+        }
+        Ok(purged)
+    }
+
+    /// Path, deletion time and original file name of each trashed model
+    fn trash_entries(&self) -> Result<Vec<(PathBuf, DateTime<Utc>, String)>> {
+        let trash = self.trash_directory();
+        if !trash.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&trash).with_context(|| format!("Failed to read trash directory: {:?}", trash))? {
+            let path = entry?.path();
+            let parsed = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(parse_trash_name)
+                .map(|(trashed_at, file_name)| (trashed_at, file_name.to_string()));
+            if let (true, Some((trashed_at, file_name))) = (path.is_file(), parsed) {
+                entries.push((path, trashed_at, file_name));
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(unix)]
+fn symlink_file(source: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(source, link)
+}
+
+/// Needs the developer mode or administrator rights on Windows
+#[cfg(windows)]
+fn symlink_file(source: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(source, link)
+}
+
+/// Models directory set with `AGENTS_RS_MODELS_DIR`, if any
+pub fn models_dir_from_env() -> Option<PathBuf> {
+    std::env::var_os(MODELS_DIR_ENV)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
 }
 
 /// Get the appropriate models directory for the current platform
 fn get_models_directory() -> Result<PathBuf> {
+    if let Some(models_dir) = models_dir_from_env() {
+        info!("Models directory from {}: {:?}", MODELS_DIR_ENV, models_dir);
+        return Ok(models_dir);
+    }
+
     // Try to use the models directory in the current working directory first
     let cwd_models = std::env::current_dir()?.join("models");
     info!("Checking CWD models directory: {:?} - exists: {}", cwd_models, cwd_models.exists());
@@ -187,4 +437,82 @@ impl Default for ModelManager {
             }
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_and_restore() {
+        let dir = std::env::temp_dir().join(format!("agents-rs-models-{}", uuid::Uuid::new_v4()));
+        let manager = ModelManager::with_directory(dir.clone()).unwrap();
+        fs::write(dir.join("model.gguf"), b"GGUF").unwrap();
+
+        manager.delete_model("model.gguf").unwrap();
+        assert!(manager.list_models().unwrap().is_empty());
+        let trash = manager.list_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].file_name, "model.gguf");
+        assert_eq!(trash[0].size_bytes, 4);
+
+        // Recently deleted models survive a purge by age
+        assert!(manager.purge_trash(Some(Duration::from_secs(3600))).unwrap().is_empty());
+        manager.restore_model("model.gguf").unwrap();
+        assert_eq!(manager.list_models().unwrap().len(), 1);
+        assert!(manager.restore_model("model.gguf").is_err());
+
+        manager.delete_model("model.gguf").unwrap();
+        assert_eq!(manager.purge_trash(None).unwrap(), vec!["model.gguf"]);
+        assert!(!manager.is_trashed("model.gguf"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_model() {
+        let dir = std::env::temp_dir().join(format!("agents-rs-import-{}", uuid::Uuid::new_v4()));
+        let manager = ModelManager::with_directory(dir.join("models")).unwrap();
+
+        // Minimal GGUF v3: no tensors and no metadata
+        let source = dir.join("tiny.gguf");
+        let mut bytes = b"GGUF".to_vec();
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        fs::write(&source, &bytes).unwrap();
+        fs::write(dir.join("notes.gguf"), b"not a model").unwrap();
+
+        let (file_name, header) = manager.import_model(&source, ImportMode::Copy).unwrap();
+        assert_eq!(file_name, "tiny.gguf");
+        assert_eq!(header.version, 3);
+        assert_eq!(manager.list_models().unwrap().len(), 1);
+
+        assert!(manager.import_model(&source, ImportMode::Copy).is_err());
+        assert!(manager.import_model(&dir.join("notes.gguf"), ImportMode::Link).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_sort_models() {
+        let model = |name: &str, load_count: u32, favorite: bool| ModelInfo {
+            name: name.to_string(),
+            file_name: format!("{}.gguf", name),
+            size_bytes: 0,
+            is_loaded: false,
+            load_count,
+            last_used_at: None,
+            favorite,
+        };
+        let mut models = vec![model("a", 1, false), model("b", 5, false), model("c", 0, true)];
+
+        sort_models(&mut models, ModelSort::MostUsed);
+        let names: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["c", "b", "a"]);
+
+        sort_models(&mut models, ModelSort::Name);
+        let names: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["c", "a", "b"]);
+    }
+}
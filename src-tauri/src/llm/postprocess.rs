@@ -0,0 +1,349 @@
+/// Response post-processing - cleanup applied to the raw text of every local reply
+///
+/// Reasoning models (Qwen3, DeepSeek-R1...) wrap their chain of thought in
+/// `<think>...</think>`; the block is removed from the answer and, depending on
+/// the configuration, returned separately. Leftover template tokens are trimmed
+/// and the markdown is normalized so the UI renders partial outputs correctly.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+const THINK_OPEN: &str = "<think>";
+const THINK_CLOSE: &str = "</think>";
+
+/// Template tokens that sometimes leak into the generated text
+const ARTIFACTS: &[&str] = &["<|im_end|>", "<|im_start|>", "<|endoftext|>", "<|eot_id|>", "</s>"];
+
+/// What to do with reasoning blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningMode {
+    /// Leave the blocks in the answer
+    Keep,
+    /// Remove the blocks and discard them
+    Strip,
+    /// Remove the blocks and return them separately
+    #[default]
+    Capture,
+}
+
+/// Steps of the post-processing pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct PostProcessConfig {
+    pub reasoning: ReasoningMode,
+    /// Remove leaked template tokens and surrounding whitespace
+    pub trim_artifacts: bool,
+    /// Normalize line endings, blank lines and unterminated code fences
+    pub normalize_markdown: bool,
+}
+
+impl Default for PostProcessConfig {
+    fn default() -> Self {
+        Self {
+            reasoning: ReasoningMode::default(),
+            trim_artifacts: true,
+            normalize_markdown: true,
+        }
+    }
+}
+
+/// Answer after post-processing
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessedText {
+    pub text: String,
+    /// Captured reasoning (None when absent or not captured)
+    pub reasoning: Option<String>,
+}
+
+/// Run the pipeline on a raw reply
+pub fn process(raw: &str, config: &PostProcessConfig) -> ProcessedText {
+    let (mut text, reasoning) = match config.reasoning {
+        ReasoningMode::Keep => (raw.to_string(), None),
+        ReasoningMode::Strip => (split_reasoning(raw).0, None),
+        ReasoningMode::Capture => split_reasoning(raw),
+    };
+    if config.trim_artifacts {
+        text = trim_artifacts(&text);
+    }
+    if config.normalize_markdown {
+        text = normalize_markdown(&text);
+    }
+    ProcessedText { text: text.trim().to_string(), reasoning }
+}
+
+/// Separate the reasoning blocks from the answer
+///
+/// Handles the shapes seen in practice: complete blocks, a block left open when
+/// generation stopped mid-thought, and a lone closing tag when the chat template
+/// already opened the block in the prompt.
+pub fn split_reasoning(raw: &str) -> (String, Option<String>) {
+    let mut answer = String::new();
+    let mut thoughts: Vec<&str> = Vec::new();
+    let mut rest = raw;
+
+    // Opening tag consumed by the prompt: everything before the first close is reasoning
+    if let Some(close) = rest.find(THINK_CLOSE) {
+        if !rest[..close].contains(THINK_OPEN) {
+            thoughts.push(&rest[..close]);
+            rest = &rest[close + THINK_CLOSE.len()..];
+        }
+    }
+
+    while let Some(open) = rest.find(THINK_OPEN) {
+        answer.push_str(&rest[..open]);
+        let inner = &rest[open + THINK_OPEN.len()..];
+        match inner.find(THINK_CLOSE) {
+            Some(close) => {
+                thoughts.push(&inner[..close]);
+                rest = &inner[close + THINK_CLOSE.len()..];
+            }
+            None => {
+                thoughts.push(inner);
+                rest = "";
+            }
+        }
+    }
+    answer.push_str(rest);
+
+    let reasoning = thoughts
+        .iter()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    (answer, (!reasoning.is_empty()).then_some(reasoning))
+}
+
+/// Piece of a streamed reply, once reasoning and answer are told apart
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamPiece {
+    ReasoningStart,
+    Reasoning(String),
+    ReasoningEnd,
+    Answer(String),
+}
+
+/// Incremental version of `split_reasoning` for streamed chunks
+///
+/// Tags can be cut across chunks: a chunk ending with the start of a tag is held
+/// back until the next one tells whether the tag is complete. Some models start
+/// already thinking and only close the block, so the beginning of a reply is held
+/// back (up to `LEADING_HOLD` bytes) until a tag tells reasoning and answer apart.
+#[derive(Debug)]
+pub struct ReasoningSplitter {
+    in_reasoning: bool,
+    /// Start of the reply, no tag seen yet
+    leading: bool,
+    pending: String,
+}
+
+/// Text held back at the start of a reply while waiting for a lone closing tag
+const LEADING_HOLD: usize = 2048;
+
+impl Default for ReasoningSplitter {
+    fn default() -> Self {
+        Self { in_reasoning: false, leading: true, pending: String::new() }
+    }
+}
+
+impl ReasoningSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, chunk: &str) -> Vec<StreamPiece> {
+        self.pending.push_str(chunk);
+        let mut pieces = Vec::new();
+
+        if self.leading {
+            let open = self.pending.find(THINK_OPEN);
+            match self.pending.find(THINK_CLOSE) {
+                // Closing tag first: the opening one was implied, what came before is reasoning
+                Some(close) if open.is_none_or(|open| close < open) => {
+                    let before: String = self.pending[..close].to_string();
+                    self.pending.drain(..close + THINK_CLOSE.len());
+                    self.leading = false;
+                    pieces.push(StreamPiece::ReasoningStart);
+                    self.in_reasoning = true;
+                    self.emit(before, &mut pieces);
+                    self.in_reasoning = false;
+                    pieces.push(StreamPiece::ReasoningEnd);
+                }
+                _ if open.is_some() || self.pending.len() >= LEADING_HOLD => self.leading = false,
+                _ => return pieces,
+            }
+        }
+
+        loop {
+            let tag = if self.in_reasoning { THINK_CLOSE } else { THINK_OPEN };
+            if let Some(position) = self.pending.find(tag) {
+                let before: String = self.pending[..position].to_string();
+                self.pending.drain(..position + tag.len());
+                self.emit(before, &mut pieces);
+                self.in_reasoning = !self.in_reasoning;
+                pieces.push(if self.in_reasoning { StreamPiece::ReasoningStart } else { StreamPiece::ReasoningEnd });
+                continue;
+            }
+
+            // Tags are ASCII, so the held back suffix always starts on a char boundary
+            let held = (1..tag.len()).rev().find(|&n| self.pending.ends_with(&tag[..n])).unwrap_or(0);
+            let ready: String = self.pending.drain(..self.pending.len() - held).collect();
+            self.emit(ready, &mut pieces);
+            return pieces;
+        }
+    }
+
+    /// Flush the held back text at the end of the generation
+    pub fn finish(&mut self) -> Vec<StreamPiece> {
+        let mut pieces = Vec::new();
+        self.leading = false;
+        let rest = std::mem::take(&mut self.pending);
+        self.emit(rest, &mut pieces);
+        if self.in_reasoning {
+            self.in_reasoning = false;
+            pieces.push(StreamPiece::ReasoningEnd);
+        }
+        pieces
+    }
+
+    fn emit(&self, text: String, pieces: &mut Vec<StreamPiece>) {
+        if text.is_empty() {
+            return;
+        }
+        pieces.push(if self.in_reasoning { StreamPiece::Reasoning(text) } else { StreamPiece::Answer(text) });
+    }
+}
+
+fn trim_artifacts(text: &str) -> String {
+    let mut text = text.to_string();
+    for artifact in ARTIFACTS {
+        text = text.replace(artifact, "");
+    }
+    text
+}
+
+fn normalize_markdown(text: &str) -> String {
+    let text = text.replace("\r\n", "\n");
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_code = false;
+    let mut blank_run = 0;
+
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        // Code blocks are kept verbatim, prose gets at most one blank line in a row
+        if !in_code && line.trim().is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        lines.push(line);
+    }
+
+    let mut normalized = lines.join("\n");
+    // Generation stopped inside a code block: close it so the rest of the page renders
+    if in_code {
+        normalized.push_str("\n```");
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reasoning_capture() {
+        let config = PostProcessConfig::default();
+
+        let processed = process("<think>\nThe user greets me.\n</think>\n\nHello!<|im_end|>", &config);
+        assert_eq!(processed.text, "Hello!");
+        assert_eq!(processed.reasoning.as_deref(), Some("The user greets me."));
+
+        // Opening tag already in the prompt
+        let processed = process("Short thought</think>Answer", &config);
+        assert_eq!(processed.text, "Answer");
+        assert_eq!(processed.reasoning.as_deref(), Some("Short thought"));
+
+        // Stopped while thinking
+        let processed = process("<think>Still thinking", &config);
+        assert_eq!(processed.text, "");
+        assert_eq!(processed.reasoning.as_deref(), Some("Still thinking"));
+
+        let strip = PostProcessConfig { reasoning: ReasoningMode::Strip, ..Default::default() };
+        assert_eq!(process("<think>x</think>Answer", &strip).reasoning, None);
+        let keep = PostProcessConfig { reasoning: ReasoningMode::Keep, ..Default::default() };
+        assert_eq!(process("<think>x</think>Answer", &keep).text, "<think>x</think>Answer");
+    }
+
+    #[test]
+    fn test_reasoning_splitter() {
+        let mut splitter = ReasoningSplitter::new();
+        let mut pieces = Vec::new();
+        for chunk in ["<thi", "nk>Let me", " think</", "think>", "Hello", "<"] {
+            pieces.extend(splitter.push(chunk));
+        }
+        pieces.extend(splitter.finish());
+
+        assert_eq!(pieces, vec![
+            StreamPiece::ReasoningStart,
+            StreamPiece::Reasoning("Let me".to_string()),
+            StreamPiece::Reasoning(" think".to_string()),
+            StreamPiece::ReasoningEnd,
+            StreamPiece::Answer("Hello".to_string()),
+            StreamPiece::Answer("<".to_string()),
+        ]);
+
+        // Generation stopped while thinking
+        let mut splitter = ReasoningSplitter::new();
+        splitter.push("<think>Hmm");
+        assert_eq!(splitter.finish(), vec![StreamPiece::ReasoningEnd]);
+
+        // No tag at all: the held back start of the reply is the answer
+        let mut splitter = ReasoningSplitter::new();
+        assert!(splitter.push("Hello").is_empty());
+        assert_eq!(splitter.finish(), vec![StreamPiece::Answer("Hello".to_string())]);
+    }
+
+    #[test]
+    fn test_reasoning_splitter_lone_close() {
+        // The model starts already thinking and only closes the block
+        let mut splitter = ReasoningSplitter::new();
+        let mut pieces = Vec::new();
+        for chunk in ["Let me", " think</th", "ink>", "Hello <think>"] {
+            pieces.extend(splitter.push(chunk));
+        }
+        pieces.extend(splitter.finish());
+        assert_eq!(pieces, vec![
+            StreamPiece::ReasoningStart,
+            StreamPiece::Reasoning("Let me think".to_string()),
+            StreamPiece::ReasoningEnd,
+            StreamPiece::Answer("Hello ".to_string()),
+            StreamPiece::ReasoningStart,
+            StreamPiece::ReasoningEnd,
+        ]);
+
+        // Same as the non-streamed post-processing
+        let (answer, reasoning) = split_reasoning("Let me think</think>Hello");
+        assert_eq!((answer.as_str(), reasoning.as_deref()), ("Hello", Some("Let me think")));
+
+        // Past the held back start, text is streamed as the answer
+        let mut splitter = ReasoningSplitter::new();
+        let long = "a".repeat(LEADING_HOLD);
+        assert_eq!(splitter.push(&long), vec![StreamPiece::Answer(long.clone())]);
+    }
+
+    #[test]
+    fn test_normalize_markdown() {
+        let config = PostProcessConfig::default();
+        let processed = process("Intro\r\n\r\n\r\n\r\n```rust\nfn main() {\n\n\n}", &config);
+        assert_eq!(processed.text, "Intro\n\n```rust\nfn main() {\n\n\n}\n```");
+    }
+}
@@ -0,0 +1,240 @@
+/// Prompt cache - reuses the KV state of previously decoded prompts
+///
+/// Each entry holds the tokens of a decoded prompt (system prompt, persona and
+/// history) with its KV state. A new prompt resumes from the entry sharing the
+/// longest token prefix with it, for the same model and context size, so a
+/// conversation only decodes its new turns and sessions with the same persona
+/// skip the system prompt. A match shorter than the minimum useful prefix (BOS
+/// and template header are shared by every prompt) counts as a miss: restoring
+/// a state costs more than decoding a few tokens. Entries are evicted in least-recently-used order once
+/// the entry count or size limit is hit.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use tracing::debug;
+use ts_rs::TS;
+
+/// Shortest shared prefix worth restoring a state for, when the prompt has no
+/// longer system prompt
+pub const MIN_PREFIX_TOKENS: usize = 64;
+
+/// Decoded KV state of a prompt
+#[derive(Clone)]
+pub struct CachedPrefix {
+    /// Tokens contained in the state, in position order
+    pub tokens: Arc<[i32]>,
+    /// Raw llama.cpp context state, shared so that a lookup doesn't copy it
+    pub state: Arc<[u8]>,
+    /// Model and context size the state was produced with
+    scope: u64,
+    last_used: u64,
+}
+
+/// Statistics exposed to the UI
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, TS)]
+#[ts(export)]
+pub struct PromptCacheStats {
+    #[ts(type = "number")]
+    pub entries: usize,
+    #[ts(type = "number")]
+    pub size_bytes: usize,
+    #[ts(type = "number")]
+    pub hits: u64,
+    #[ts(type = "number")]
+    pub misses: u64,
+}
+
+/// LRU cache of prefix states
+#[derive(Default)]
+pub struct PromptCache {
+    entries: HashMap<u64, CachedPrefix>,
+    max_entries: usize,
+    max_bytes: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl PromptCache {
+    pub fn new(max_entries: usize, max_size_mb: usize) -> Self {
+        Self {
+            max_entries,
+            max_bytes: max_size_mb * 1024 * 1024,
+            ..Default::default()
+        }
+    }
+
+    /// Hash identifying the model and context size a state can be restored into
+    pub fn scope(model_path: &str, n_ctx: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        model_path.hash(&mut hasher);
+        n_ctx.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn key(scope: u64, tokens: &[i32]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        scope.hash(&mut hasher);
+        tokens.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_entries > 0 && self.max_bytes > 0
+    }
+
+    /// Update the limits, evicting entries that no longer fit
+    pub fn configure(&mut self, max_entries: usize, max_size_mb: usize) {
+        self.max_entries = max_entries;
+        self.max_bytes = max_size_mb * 1024 * 1024;
+        self.evict();
+    }
+
+    /// Entry sharing the longest token prefix with `tokens`, with the length of that
+    /// prefix; shorter matches than `min_shared` tokens are misses
+    pub fn longest_prefix(&mut self, scope: u64, tokens: &[i32], min_shared: usize) -> Option<(CachedPrefix, usize)> {
+        self.clock += 1;
+        let best = self
+            .entries
+            .values_mut()
+            .filter(|entry| entry.scope == scope)
+            .map(|entry| {
+                let shared = entry.tokens.iter().zip(tokens).take_while(|(a, b)| a == b).count();
+                (entry, shared)
+            })
+            .filter(|(_, shared)| *shared >= min_shared.max(1))
+            .max_by_key(|(_, shared)| *shared);
+        match best {
+            Some((entry, shared)) => {
+                entry.last_used = self.clock;
+                self.hits += 1;
+                Some((entry.clone(), shared))
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store the state of a decoded prompt, replacing the entries it extends
+    pub fn insert(&mut self, scope: u64, tokens: Vec<i32>, state: Vec<u8>) {
+        if !self.is_enabled() || state.len() > self.max_bytes {
+            return;
+        }
+        self.entries
+            .retain(|_, entry| entry.scope != scope || !tokens.starts_with(&entry.tokens));
+        self.clock += 1;
+        let key = Self::key(scope, &tokens);
+        let entry = CachedPrefix { tokens: tokens.into(), state: state.into(), scope, last_used: self.clock };
+        self.entries.insert(key, entry);
+        self.evict();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.entries.values().map(|e| e.state.len()).sum()
+    }
+
+    pub fn stats(&self) -> PromptCacheStats {
+        PromptCacheStats {
+            entries: self.entries.len(),
+            size_bytes: self.size_bytes(),
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+
+    /// Drop least recently used entries until both limits are respected
+    fn evict(&mut self) {
+        while !self.entries.is_empty()
+            && (self.entries.len() > self.max_entries || self.size_bytes() > self.max_bytes)
+        {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key);
+            if let Some(key) = oldest {
+                debug!("Evicting prompt cache entry {:x}", key);
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = PromptCache::new(2, 1);
+        cache.insert(0, vec![1], vec![0; 16]);
+        cache.insert(0, vec![2], vec![0; 16]);
+        assert!(cache.longest_prefix(0, &[1], 1).is_some());
+
+        // Entry [2] is the least recently used one
+        cache.insert(0, vec![3], vec![0; 16]);
+        assert!(cache.longest_prefix(0, &[2], 1).is_none());
+        assert!(cache.longest_prefix(0, &[1], 1).is_some());
+        assert!(cache.longest_prefix(0, &[3], 1).is_some());
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    #[test]
+    fn test_size_limit_and_scope() {
+        let mut cache = PromptCache::new(8, 1);
+        cache.insert(0, vec![1], vec![0; 2 * 1024 * 1024]);
+        assert_eq!(cache.stats().entries, 0);
+
+        let a = PromptCache::scope("model.gguf", 2048);
+        assert_eq!(a, PromptCache::scope("model.gguf", 2048));
+        assert_ne!(a, PromptCache::scope("model.gguf", 4096));
+
+        cache.insert(a, vec![1, 2, 3], vec![0; 16]);
+        assert!(cache.longest_prefix(PromptCache::scope("model.gguf", 4096), &[1, 2, 3], 1).is_none());
+    }
+
+    #[test]
+    fn test_longest_prefix_includes_history() {
+        let mut cache = PromptCache::new(8, 1);
+        // System prompt then a first turn, and another session with the same system prompt
+        cache.insert(0, vec![1, 2, 10, 11], vec![0; 16]);
+        cache.insert(0, vec![1, 2, 20], vec![0; 16]);
+
+        let (entry, shared) = cache.longest_prefix(0, &[1, 2, 10, 11, 12, 13], 1).unwrap();
+        assert_eq!(&*entry.tokens, &[1, 2, 10, 11]);
+        assert_eq!(shared, 4);
+
+        // A diverging history still reuses the shared system prompt
+        let (_, shared) = cache.longest_prefix(0, &[1, 2, 30], 1).unwrap();
+        assert_eq!(shared, 2);
+        assert!(cache.longest_prefix(0, &[5], 1).is_none());
+
+        // The next turn replaces the entry it extends
+        cache.insert(0, vec![1, 2, 10, 11, 12, 13], vec![0; 16]);
+        assert_eq!(cache.stats().entries, 2);
+        assert_eq!(cache.stats().hits, 2);
+        assert_eq!(cache.stats().misses, 1);
+    }
+
+    #[test]
+    fn test_short_prefix_is_a_miss() {
+        let mut cache = PromptCache::new(8, 1);
+        // Only BOS and the template header in common
+        cache.insert(0, vec![1, 2, 10, 11], vec![0; 16]);
+
+        assert!(cache.longest_prefix(0, &[1, 2, 30, 31], 3).is_none());
+        let (entry, shared) = cache.longest_prefix(0, &[1, 2, 10, 31], 3).unwrap();
+        assert_eq!(shared, 3);
+        assert_eq!(entry.state.len(), 16);
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 1);
+    }
+}
@@ -0,0 +1,94 @@
+/// Starter model recommendation for the first-run setup
+///
+/// Picks the largest model of a small curated catalog that fits the memory the
+/// engine will use: free VRAM when layers are offloaded to a discrete GPU,
+/// available RAM otherwise (Apple Silicon shares it with the GPU).
+
+use super::system::SystemMetrics;
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Memory needed on top of the weights (KV cache, compute buffers), in percent
+const OVERHEAD_PERCENT: u64 = 30;
+
+const GB: u64 = 1024 * 1024 * 1024;
+
+/// Downloadable GGUF model of the catalog
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct RecommendedModel {
+    pub repo_id: String,
+    pub filename: String,
+    pub parameters: String,
+    /// Approximate size of the file
+    #[ts(type = "number")]
+    pub size_bytes: u64,
+}
+
+/// Q4_K_M instruct models, smallest first
+fn catalog() -> Vec<RecommendedModel> {
+    let model = |repo_id: &str, filename: &str, parameters: &str, size_bytes: u64| RecommendedModel {
+        repo_id: repo_id.to_string(),
+        filename: filename.to_string(),
+        parameters: parameters.to_string(),
+        size_bytes,
+    };
+    vec![
+        model("Qwen/Qwen2.5-0.5B-Instruct-GGUF", "qwen2.5-0.5b-instruct-q4_k_m.gguf", "0.5B", GB / 2),
+        model("Qwen/Qwen2.5-1.5B-Instruct-GGUF", "qwen2.5-1.5b-instruct-q4_k_m.gguf", "1.5B", 11 * GB / 10),
+        model("Qwen/Qwen2.5-3B-Instruct-GGUF", "qwen2.5-3b-instruct-q4_k_m.gguf", "3B", 21 * GB / 10),
+        model("bartowski/Qwen2.5-7B-Instruct-GGUF", "Qwen2.5-7B-Instruct-Q4_K_M.gguf", "7B", 47 * GB / 10),
+        model("bartowski/Qwen2.5-14B-Instruct-GGUF", "Qwen2.5-14B-Instruct-Q4_K_M.gguf", "14B", 9 * GB),
+    ]
+}
+
+/// Memory available to the model weights, `None` when unknown
+pub fn memory_budget(metrics: &SystemMetrics, gpu_offload: bool) -> Option<u64> {
+    let vram = metrics
+        .gpus
+        .iter()
+        .map(|gpu| gpu.total_bytes.saturating_sub(gpu.used_bytes))
+        .max();
+    match vram {
+        Some(vram) if gpu_offload => Some(vram),
+        _ => metrics.available_memory_bytes,
+    }
+}
+
+/// Largest catalog model fitting `budget` (the 1.5B model when unknown)
+pub fn recommend_model(budget: Option<u64>) -> RecommendedModel {
+    let catalog = catalog();
+    let Some(budget) = budget else {
+        return catalog[1].clone();
+    };
+    catalog
+        .iter()
+        .rev()
+        .find(|model| model.size_bytes * (100 + OVERHEAD_PERCENT) / 100 <= budget)
+        // Even the smallest model is better than none: it may swap a little
+        .unwrap_or(&catalog[0])
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::GpuMemory;
+
+    #[test]
+    fn test_recommend_model() {
+        assert_eq!(recommend_model(None).parameters, "1.5B");
+        assert_eq!(recommend_model(Some(GB / 4)).parameters, "0.5B");
+        assert_eq!(recommend_model(Some(4 * GB)).parameters, "3B");
+        assert_eq!(recommend_model(Some(64 * GB)).parameters, "14B");
+
+        let metrics = SystemMetrics {
+            available_memory_bytes: Some(16 * GB),
+            gpus: vec![GpuMemory { index: 0, name: "GPU".to_string(), used_bytes: GB, total_bytes: 8 * GB }],
+            ..Default::default()
+        };
+        assert_eq!(memory_budget(&metrics, true), Some(7 * GB));
+        assert_eq!(memory_budget(&metrics, false), Some(16 * GB));
+        assert_eq!(recommend_model(memory_budget(&metrics, true)).parameters, "7B");
+    }
+}
@@ -0,0 +1,268 @@
+/// Remote generation backends (OpenAI-compatible and Anthropic APIs)
+
+use super::backend::{ChatMessage, ChatRole, LLMBackend};
+use super::engine::LLMResponse;
+use crate::error::HttpStatusError;
+use anyhow::{anyhow, Context, Result};
+use reqwest::Client;
+use serde_json::json;
+use tracing::{debug, info};
+
+pub const OPENAI_DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+pub const ANTHROPIC_DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+const ANTHROPIC_API_VERSION: &str = "2023-06-01";
+
+/// Connection settings shared by remote backends
+#[derive(Debug, Clone)]
+pub struct RemoteBackendConfig {
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+    pub max_tokens: usize,
+    pub temperature: f32,
+}
+
+fn build_http_client() -> Result<Client> {
+    Client::builder()
+        .user_agent("agents-rs/0.1.0")
+        .build()
+        .context("Failed to create HTTP client")
+}
+
+/// Client for OpenAI-compatible chat completion APIs (OpenAI, LM Studio, vLLM, ...)
+pub struct OpenAICompatibleBackend {
+    client: Client,
+    config: RemoteBackendConfig,
+}
+
+impl OpenAICompatibleBackend {
+    pub fn new(config: RemoteBackendConfig) -> Result<Self> {
+        Ok(Self {
+            client: build_http_client()?,
+            config,
+        })
+    }
+
+    fn request_body(&self, messages: &[ChatMessage]) -> serde_json::Value {
+        let messages: Vec<serde_json::Value> = messages
+            .iter()
+            .map(|m| {
+                // Tool results without a tool_call_id are rejected by the API
+                let role = match m.role {
+                    ChatRole::Tool => "user",
+                    other => other.as_str(),
+                };
+                json!({ "role": role, "content": m.content })
+            })
+            .collect();
+
+        json!({
+            "model": self.config.model,
+            "messages": messages,
+            "max_tokens": self.config.max_tokens,
+            "temperature": self.config.temperature,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl LLMBackend for OpenAICompatibleBackend {
+    fn name(&self) -> String {
+        format!("openai:{}", self.config.model)
+    }
+
+    async fn is_ready(&self) -> bool {
+        !self.config.api_key.is_empty()
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<LLMResponse> {
+        let url = format!("{}/chat/completions", self.config.base_url.trim_end_matches('/'));
+        info!("Sending {} messages to {}", messages.len(), url);
+
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.config.api_key)
+            .json(&self.request_body(messages))
+            .send()
+            .await
+            .context("Failed to send request to OpenAI-compatible API")?;
+
+        let status = response.status();
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to read OpenAI-compatible API response")?;
+
+        if !status.is_success() {
+            return Err(HttpStatusError::new("OpenAI-compatible API error", status, body.to_string()).into());
+        }
+        debug!("OpenAI-compatible response: {}", body);
+
+        let text = body["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing message content in API response"))?
+            .to_string();
+        let tokens_generated = body["usage"]["completion_tokens"].as_u64().unwrap_or(0) as usize;
+        let done = body["choices"][0]["finish_reason"] != "length";
+
+        // Reasoning models served behind this API (DeepSeek-R1...) return their thoughts apart
+        let reasoning = body["choices"][0]["message"]["reasoning_content"]
+            .as_str()
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty());
+
+        Ok(LLMResponse {
+            text: text.trim().to_string(),
+            tool_calls: vec![],
+            tokens_generated,
+            done,
+            truncated_tokens: 0,
+            logprobs: None,
+            reasoning,
+        })
+    }
+}
+
+/// Client for the Anthropic Messages API
+pub struct AnthropicBackend {
+    client: Client,
+    config: RemoteBackendConfig,
+}
+
+impl AnthropicBackend {
+    pub fn new(config: RemoteBackendConfig) -> Result<Self> {
+        Ok(Self {
+            client: build_http_client()?,
+            config,
+        })
+    }
+
+    fn request_body(&self, messages: &[ChatMessage]) -> serde_json::Value {
+        // System prompts are a top-level field, not a message role
+        let system: Vec<&str> = messages
+            .iter()
+            .filter(|m| m.role == ChatRole::System)
+            .map(|m| m.content.as_str())
+            .collect();
+
+        let messages: Vec<serde_json::Value> = messages
+            .iter()
+            .filter(|m| m.role != ChatRole::System)
+            .map(|m| {
+                let role = match m.role {
+                    ChatRole::Assistant => "assistant",
+                    _ => "user",
+                };
+                json!({ "role": role, "content": m.content })
+            })
+            .collect();
+
+        let mut body = json!({
+            "model": self.config.model,
+            "messages": messages,
+            "max_tokens": self.config.max_tokens,
+            "temperature": self.config.temperature,
+        });
+        if !system.is_empty() {
+            body["system"] = json!(system.join("\n\n"));
+        }
+        body
+    }
+}
+
+#[async_trait::async_trait]
+impl LLMBackend for AnthropicBackend {
+    fn name(&self) -> String {
+        format!("anthropic:{}", self.config.model)
+    }
+
+    async fn is_ready(&self) -> bool {
+        !self.config.api_key.is_empty()
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> Result<LLMResponse> {
+        let url = format!("{}/v1/messages", self.config.base_url.trim_end_matches('/'));
+        info!("Sending {} messages to {}", messages.len(), url);
+
+        let response = self
+            .client
+            .post(&url)
+            .header("x-api-key", &self.config.api_key)
+            .header("anthropic-version", ANTHROPIC_API_VERSION)
+            .json(&self.request_body(messages))
+            .send()
+            .await
+            .context("Failed to send request to Anthropic API")?;
+
+        let status = response.status();
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to read Anthropic API response")?;
+
+        if !status.is_success() {
+            return Err(HttpStatusError::new("Anthropic API error", status, body.to_string()).into());
+        }
+        debug!("Anthropic response: {}", body);
+
+        let text: String = body["content"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Missing content in Anthropic response"))?
+            .iter()
+            .filter(|block| block["type"] == "text")
+            .filter_map(|block| block["text"].as_str())
+            .collect();
+        let tokens_generated = body["usage"]["output_tokens"].as_u64().unwrap_or(0) as usize;
+        let done = body["stop_reason"] != "max_tokens";
+
+        Ok(LLMResponse {
+            text: text.trim().to_string(),
+            tool_calls: vec![],
+            tokens_generated,
+            done,
+            truncated_tokens: 0,
+            logprobs: None,
+            reasoning: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> RemoteBackendConfig {
+        RemoteBackendConfig {
+            base_url: "http://localhost".to_string(),
+            api_key: "key".to_string(),
+            model: "test-model".to_string(),
+            max_tokens: 128,
+            temperature: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_anthropic_moves_system_prompt() {
+        let backend = AnthropicBackend::new(config()).unwrap();
+        let body = backend.request_body(&[
+            ChatMessage::system("Be brief"),
+            ChatMessage::user("Hello"),
+        ]);
+        assert_eq!(body["system"], "Be brief");
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(body["messages"][0]["role"], "user");
+    }
+
+    #[test]
+    fn test_openai_request_body() {
+        let backend = OpenAICompatibleBackend::new(config()).unwrap();
+        let body = backend.request_body(&[
+            ChatMessage::system("Be brief"),
+            ChatMessage::user("Hello"),
+        ]);
+        assert_eq!(body["model"], "test-model");
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][1]["content"], "Hello");
+    }
+}
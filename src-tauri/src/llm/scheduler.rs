@@ -0,0 +1,373 @@
+/// Generation scheduler - queues generation requests for the local engines
+///
+/// Requests are started by priority (then arrival order). Two requests of the same
+/// session never run at the same time, while different sessions can interleave when
+/// more than one engine slot is available (several models loaded). Requests served
+/// by a remote backend only take their session's turn: they run in parallel with
+/// the local engine and with each other.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, oneshot};
+use tracing::debug;
+use ts_rs::TS;
+
+use super::handle::EngineHandle;
+
+/// Priority of a generation request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GenerationPriority {
+    /// Background work (titles, summaries, batches)
+    Low,
+    #[default]
+    Normal,
+    /// Interactive request the user is waiting for
+    High,
+}
+
+/// Queue position of a request, sent to the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueUpdate {
+    pub request_id: u64,
+    pub session_id: Option<String>,
+    /// Number of requests started before this one (0 = running)
+    pub position: usize,
+}
+
+/// What a session is doing, for `get_session_status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SessionStatus {
+    Idle,
+    /// Waiting behind `position - 1` other requests
+    Queued {
+        #[ts(type = "number")]
+        position: usize,
+    },
+    Generating,
+}
+
+struct Pending {
+    request_id: u64,
+    session_id: Option<String>,
+    priority: GenerationPriority,
+    /// Needs a local engine slot (false for remote backends)
+    engine: bool,
+    permit: oneshot::Sender<GenerationPermit>,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    queue: Vec<Pending>,
+    active_sessions: HashSet<String>,
+    running: usize,
+    slots: usize,
+    next_id: u64,
+}
+
+struct Inner {
+    state: Mutex<SchedulerState>,
+    events: broadcast::Sender<QueueUpdate>,
+}
+
+/// Queue shared by all commands using the local engines
+#[derive(Clone)]
+pub struct GenerationScheduler {
+    inner: Arc<Inner>,
+}
+
+/// Right to run a generation; the next request starts when it is dropped
+pub struct GenerationPermit {
+    inner: Arc<Inner>,
+    request_id: u64,
+    session_id: Option<String>,
+    engine: bool,
+    armed: bool,
+}
+
+impl GenerationPermit {
+    pub fn request_id(&self) -> u64 {
+        self.request_id
+    }
+}
+
+impl Drop for GenerationPermit {
+    fn drop(&mut self) {
+        if self.armed {
+            let mut state = self.inner.state.lock().unwrap();
+            release(&mut state, self.session_id.as_deref(), self.engine);
+            dispatch(&self.inner, &mut state);
+        }
+    }
+}
+
+impl GenerationScheduler {
+    /// Create a scheduler running at most `slots` generations at once
+    pub fn new(slots: usize) -> Self {
+        let (events, _) = broadcast::channel(64);
+        Self {
+            inner: Arc::new(Inner {
+                state: Mutex::new(SchedulerState {
+                    slots: slots.max(1),
+                    ..Default::default()
+                }),
+                events,
+            }),
+        }
+    }
+
+    /// Update the number of concurrent generations (one per loaded model)
+    pub fn set_slots(&self, slots: usize) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.slots = slots.max(1);
+        dispatch(&self.inner, &mut state);
+    }
+
+    /// Queue position updates
+    pub fn subscribe(&self) -> broadcast::Receiver<QueueUpdate> {
+        self.inner.events.subscribe()
+    }
+
+    /// Number of requests waiting to start
+    pub fn queued(&self) -> usize {
+        self.inner.state.lock().unwrap().queue.len()
+    }
+
+    /// Current activity of a session
+    pub fn session_status(&self, session_id: &str) -> SessionStatus {
+        let state = self.inner.state.lock().unwrap();
+        if state.active_sessions.contains(session_id) {
+            return SessionStatus::Generating;
+        }
+        state
+            .queue
+            .iter()
+            .position(|pending| pending.session_id.as_deref() == Some(session_id))
+            .map_or(SessionStatus::Idle, |index| SessionStatus::Queued { position: index + 1 })
+    }
+
+    /// Wait for a slot; requests of the same session (if any) are serialized
+    pub async fn acquire(
+        &self,
+        session_id: Option<&str>,
+        priority: GenerationPriority,
+    ) -> Result<GenerationPermit> {
+        self.enqueue(session_id, priority, true).await
+    }
+
+    /// Wait for the session's turn only, for generations running on a remote backend
+    pub async fn acquire_session(
+        &self,
+        session_id: &str,
+        priority: GenerationPriority,
+    ) -> Result<GenerationPermit> {
+        self.enqueue(Some(session_id), priority, false).await
+    }
+
+    /// Embed texts once an engine slot is free
+    ///
+    /// Embeddings run on the engine thread like generations: queued with the rest,
+    /// a background indexing pass no longer holds up the reply the user is waiting for.
+    pub async fn embed(
+        &self,
+        engine: &EngineHandle,
+        texts: Vec<String>,
+        priority: GenerationPriority,
+    ) -> Result<Vec<Vec<f32>>> {
+        let _permit = self.acquire(None, priority).await?;
+        engine.embed(texts).await
+    }
+
+    async fn enqueue(
+        &self,
+        session_id: Option<&str>,
+        priority: GenerationPriority,
+        engine: bool,
+    ) -> Result<GenerationPermit> {
+        let (permit, ready) = oneshot::channel();
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            state.next_id += 1;
+            let request_id = state.next_id;
+            debug!("Queueing generation request {} ({:?})", request_id, priority);
+            state.queue.push(Pending {
+                request_id,
+                session_id: session_id.map(str::to_string),
+                priority,
+                engine,
+                permit,
+            });
+            dispatch(&self.inner, &mut state);
+        }
+
+        ready
+            .await
+            .map_err(|_| anyhow::anyhow!("Generation scheduler dropped the request"))
+    }
+}
+
+fn release(state: &mut SchedulerState, session_id: Option<&str>, engine: bool) {
+    if engine {
+        state.running -= 1;
+    }
+    if let Some(session_id) = session_id {
+        state.active_sessions.remove(session_id);
+    }
+}
+
+/// Start as many queued requests as possible and publish the new positions
+fn dispatch(inner: &Arc<Inner>, state: &mut SchedulerState) {
+    // Highest priority first, arrival order within a priority (the sort is stable)
+    state.queue.sort_by_key(|pending| std::cmp::Reverse(pending.priority));
+
+    let mut index = 0;
+    while index < state.queue.len() {
+        let candidate = &state.queue[index];
+        let blocked = candidate
+            .session_id
+            .as_ref()
+            .is_some_and(|session| state.active_sessions.contains(session))
+            || (candidate.engine && state.running >= state.slots);
+        if blocked {
+            index += 1;
+            continue;
+        }
+
+        let pending = state.queue.remove(index);
+        if pending.engine {
+            state.running += 1;
+        }
+        if let Some(session_id) = &pending.session_id {
+            state.active_sessions.insert(session_id.clone());
+        }
+
+        let permit = GenerationPermit {
+            inner: Arc::clone(inner),
+            request_id: pending.request_id,
+            session_id: pending.session_id.clone(),
+            engine: pending.engine,
+            armed: true,
+        };
+        match pending.permit.send(permit) {
+            Ok(()) => {
+                let _ = inner.events.send(QueueUpdate {
+                    request_id: pending.request_id,
+                    session_id: pending.session_id,
+                    position: 0,
+                });
+            }
+            Err(mut permit) => {
+                // The caller gave up while waiting: free the slot without re-entering the lock
+                permit.armed = false;
+                release(state, pending.session_id.as_deref(), pending.engine);
+            }
+        }
+    }
+
+    for (position, pending) in state.queue.iter().enumerate() {
+        let _ = inner.events.send(QueueUpdate {
+            request_id: pending.request_id,
+            session_id: pending.session_id.clone(),
+            position: position + 1,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    const WAIT: Duration = Duration::from_millis(50);
+
+    #[tokio::test]
+    async fn test_priority_order() {
+        let scheduler = GenerationScheduler::new(1);
+        let running = scheduler.acquire(Some("a"), GenerationPriority::Normal).await.unwrap();
+
+        let low = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(Some("b"), GenerationPriority::Low).await.unwrap() }
+        });
+        tokio::time::sleep(WAIT).await;
+        let high = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(Some("c"), GenerationPriority::High).await.unwrap() }
+        });
+        tokio::time::sleep(WAIT).await;
+        assert_eq!(scheduler.queued(), 2);
+
+        // The high priority request starts first even though it arrived last
+        drop(running);
+        let high = high.await.unwrap();
+        assert_eq!(scheduler.queued(), 1);
+        drop(high);
+        assert!(low.await.unwrap().request_id() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_same_session_is_serialized() {
+        let scheduler = GenerationScheduler::new(2);
+        let first = scheduler.acquire(Some("a"), GenerationPriority::Normal).await.unwrap();
+
+        // Another session uses the second slot, the same session waits
+        let other = timeout(WAIT, scheduler.acquire(Some("b"), GenerationPriority::Normal)).await;
+        assert!(other.is_ok());
+        let same = timeout(WAIT, scheduler.acquire(Some("a"), GenerationPriority::High)).await;
+        assert!(same.is_err());
+
+        drop(first);
+        let same = timeout(WAIT, scheduler.acquire(Some("a"), GenerationPriority::High)).await;
+        assert!(same.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_remote_generations_skip_the_engine_queue() {
+        let scheduler = GenerationScheduler::new(1);
+        let local = scheduler.acquire(Some("a"), GenerationPriority::Normal).await.unwrap();
+        assert_eq!(scheduler.session_status("a"), SessionStatus::Generating);
+
+        // The engine is busy, a remote session still starts right away
+        let remote = timeout(WAIT, scheduler.acquire_session("b", GenerationPriority::Normal)).await;
+        assert!(remote.is_ok());
+        assert_eq!(scheduler.session_status("b"), SessionStatus::Generating);
+
+        let waiting = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(Some("c"), GenerationPriority::Normal).await.unwrap() }
+        });
+        tokio::time::sleep(WAIT).await;
+        assert_eq!(scheduler.session_status("c"), SessionStatus::Queued { position: 1 });
+
+        drop(local);
+        let started = waiting.await.unwrap();
+        assert_eq!(scheduler.session_status("a"), SessionStatus::Idle);
+        drop(started);
+        assert_eq!(scheduler.session_status("c"), SessionStatus::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_queue_positions_are_published() {
+        let scheduler = GenerationScheduler::new(1);
+        let mut events = scheduler.subscribe();
+        let running = scheduler.acquire(None, GenerationPriority::Normal).await.unwrap();
+        assert_eq!(events.recv().await.unwrap().position, 0);
+
+        let waiting = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(Some("a"), GenerationPriority::Normal).await.unwrap() }
+        });
+        let update = events.recv().await.unwrap();
+        assert_eq!(update.position, 1);
+        assert_eq!(update.session_id.as_deref(), Some("a"));
+
+        drop(running);
+        waiting.await.unwrap();
+        assert_eq!(events.recv().await.unwrap().position, 0);
+    }
+}
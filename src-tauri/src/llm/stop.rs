@@ -0,0 +1,92 @@
+/// Stop conditions - early exit of a generation once the reply is complete
+///
+/// Agents declare what a finished answer looks like (a closed code block, a
+/// `FINAL ANSWER:` line...). The engine checks the text after every token and
+/// stops there instead of sampling a rambling continuation until `max_tokens`.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+const FENCE: &str = "```";
+
+/// When to end a reply early; the text that triggered it is kept
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StopCondition {
+    /// Stop right after this text
+    Sequence { text: String },
+    /// Stop at the end of the line containing this marker (e.g. `FINAL ANSWER:`)
+    AfterLine { marker: String },
+    /// Stop once the first fenced code block is closed
+    CodeBlockClosed,
+}
+
+impl StopCondition {
+    /// Length of `text` to keep if the condition is met
+    fn position(&self, text: &str) -> Option<usize> {
+        match self {
+            StopCondition::Sequence { text: sequence } if !sequence.is_empty() => {
+                text.find(sequence.as_str()).map(|start| start + sequence.len())
+            }
+            StopCondition::AfterLine { marker } if !marker.is_empty() => {
+                let start = text.find(marker.as_str())? + marker.len();
+                // The line is complete once the next newline is generated
+                text[start..].find('\n').map(|end| start + end)
+            }
+            StopCondition::CodeBlockClosed => closed_code_block(text),
+            _ => None,
+        }
+    }
+}
+
+/// End of the fence closing the first code block, once its line is complete
+fn closed_code_block(text: &str) -> Option<usize> {
+    let mut open = false;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let fence = line.trim_start();
+        if fence.starts_with(FENCE) {
+            if open {
+                if !line.ends_with('\n') {
+                    // `` ``` `` may still be the start of a longer line
+                    return None;
+                }
+                return Some(offset + line.trim_end().len());
+            }
+            open = true;
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Length of `text` to keep when one of the conditions is met (the earliest wins)
+pub fn stop_position(conditions: &[StopCondition], text: &str) -> Option<usize> {
+    conditions.iter().filter_map(|condition| condition.position(text)).min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stop_conditions() {
+        let code = [StopCondition::CodeBlockClosed];
+        assert_eq!(stop_position(&code, "Voici :\n```rust\nfn main() {}\n"), None);
+        assert_eq!(stop_position(&code, "Voici :\n```rust\nfn main() {}\n```"), None);
+        let text = "Voici :\n```rust\nfn main() {}\n```\nExplications";
+        assert_eq!(&text[..stop_position(&code, text).unwrap()], "Voici :\n```rust\nfn main() {}\n```");
+
+        let answer = [StopCondition::AfterLine { marker: "FINAL ANSWER:".to_string() }];
+        assert_eq!(stop_position(&answer, "Reasoning...\nFINAL ANSWER: 42"), None);
+        let text = "Reasoning...\nFINAL ANSWER: 42\nBut wait";
+        assert_eq!(&text[..stop_position(&answer, text).unwrap()], "Reasoning...\nFINAL ANSWER: 42");
+
+        // The earliest condition wins
+        let both = [StopCondition::Sequence { text: "END".to_string() }, StopCondition::CodeBlockClosed];
+        let text = "a END ```\nb\n```\n";
+        assert_eq!(stop_position(&both, text), Some(5));
+        assert_eq!(stop_position(&[StopCondition::Sequence { text: String::new() }], text), None);
+    }
+}
@@ -0,0 +1,130 @@
+/// Model switch progress - staged status of the model switches running in the background
+
+use crate::error::AppError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+/// Finished switches kept for `get` before being forgotten
+const MAX_FINISHED_SWITCHES: usize = 20;
+
+/// Step of a model switch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchStage {
+    /// Waiting for the engine
+    Queued,
+    /// Releasing the previous model
+    Unloading,
+    /// Reading the new model
+    Loading,
+    /// Detecting the capabilities of the new model
+    WarmingUp,
+    Completed,
+    Failed,
+}
+
+impl SwitchStage {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, SwitchStage::Completed | SwitchStage::Failed)
+    }
+}
+
+/// Progress of one model switch, as sent to the frontend
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct SwitchStatus {
+    pub task_id: String,
+    pub model_name: String,
+    pub stage: SwitchStage,
+    pub error: Option<AppError>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Status of the recent switches, shared by the commands and the background tasks
+#[derive(Clone, Default)]
+pub struct SwitchTracker {
+    statuses: Arc<Mutex<HashMap<String, SwitchStatus>>>,
+}
+
+impl SwitchTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new switch in the `Queued` stage
+    pub fn start(&self, model_name: &str) -> SwitchStatus {
+        let now = Utc::now();
+        let status = SwitchStatus {
+            task_id: uuid::Uuid::new_v4().to_string(),
+            model_name: model_name.to_string(),
+            stage: SwitchStage::Queued,
+            error: None,
+            started_at: now,
+            updated_at: now,
+        };
+
+        let mut statuses = self.statuses.lock().unwrap();
+        Self::prune(&mut statuses);
+        statuses.insert(status.task_id.clone(), status.clone());
+        status
+    }
+
+    /// Move a switch to another stage, returns its new status
+    pub fn update(&self, task_id: &str, stage: SwitchStage, error: Option<AppError>) -> Option<SwitchStatus> {
+        let mut statuses = self.statuses.lock().unwrap();
+        let status = statuses.get_mut(task_id)?;
+        status.stage = stage;
+        status.error = error;
+        status.updated_at = Utc::now();
+        Some(status.clone())
+    }
+
+    pub fn get(&self, task_id: &str) -> Option<SwitchStatus> {
+        self.statuses.lock().unwrap().get(task_id).cloned()
+    }
+
+    /// Forget the oldest finished switches
+    fn prune(statuses: &mut HashMap<String, SwitchStatus>) {
+        let mut finished: Vec<(DateTime<Utc>, String)> = statuses
+            .values()
+            .filter(|s| s.stage.is_finished())
+            .map(|s| (s.updated_at, s.task_id.clone()))
+            .collect();
+        if finished.len() < MAX_FINISHED_SWITCHES {
+            return;
+        }
+        finished.sort();
+        for (_, task_id) in finished.iter().take(finished.len() + 1 - MAX_FINISHED_SWITCHES) {
+            statuses.remove(task_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_switch_tracker() {
+        let tracker = SwitchTracker::new();
+        let status = tracker.start("model.gguf");
+        assert_eq!(status.stage, SwitchStage::Queued);
+
+        tracker.update(&status.task_id, SwitchStage::Loading, None).unwrap();
+        assert_eq!(tracker.get(&status.task_id).unwrap().stage, SwitchStage::Loading);
+        assert!(tracker.update("unknown", SwitchStage::Loading, None).is_none());
+
+        tracker.update(&status.task_id, SwitchStage::Completed, None).unwrap();
+        for _ in 0..MAX_FINISHED_SWITCHES {
+            let other = tracker.start("other.gguf");
+            tracker.update(&other.task_id, SwitchStage::Failed, Some(AppError::NoModelLoaded)).unwrap();
+        }
+        // The oldest finished switch is forgotten first
+        assert!(tracker.get(&status.task_id).is_none());
+    }
+}
@@ -0,0 +1,249 @@
+/// System resource monitoring (RAM, VRAM, CPU)
+///
+/// Lets the UI warn before loading a model that doesn't fit in memory. Values
+/// the platform doesn't report are `None`: memory and CPU come from /proc on
+/// Linux and `sysctl`/`vm_stat`/`ps` on macOS, VRAM from `nvidia-smi` when an
+/// NVIDIA driver is installed.
+
+use serde::Serialize;
+use std::process::Command;
+use std::time::Duration;
+use ts_rs::TS;
+
+/// Interval between the two CPU counter samples
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const CPU_SAMPLE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Memory of one GPU
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct GpuMemory {
+    pub index: u32,
+    pub name: String,
+    #[ts(type = "number")]
+    pub used_bytes: u64,
+    #[ts(type = "number")]
+    pub total_bytes: u64,
+}
+
+/// Snapshot of the resources used by the app and available on the machine
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+pub struct SystemMetrics {
+    /// Resident memory of this process
+    #[ts(type = "number | null")]
+    pub process_rss_bytes: Option<u64>,
+    #[ts(type = "number | null")]
+    pub total_memory_bytes: Option<u64>,
+    /// Memory that can be allocated without swapping
+    #[ts(type = "number | null")]
+    pub available_memory_bytes: Option<u64>,
+    pub gpus: Vec<GpuMemory>,
+    /// CPU usage over the sampling interval, all cores (0-100)
+    pub cpu_usage_percent: Option<f32>,
+    /// 1-minute load average
+    pub load_average: Option<f64>,
+    #[ts(type = "number")]
+    pub logical_cores: usize,
+}
+
+impl SystemMetrics {
+    /// Whether `bytes` more can be allocated without swapping (unknown = true)
+    pub fn fits_in_memory(&self, bytes: u64) -> bool {
+        self.available_memory_bytes.is_none_or(|available| bytes <= available)
+    }
+}
+
+/// Sample the current metrics (blocks for the CPU sampling interval)
+pub fn collect_metrics() -> SystemMetrics {
+    let (total_memory_bytes, available_memory_bytes) = memory();
+    SystemMetrics {
+        process_rss_bytes: process_rss(),
+        total_memory_bytes,
+        available_memory_bytes,
+        gpus: nvidia_gpus(),
+        cpu_usage_percent: cpu_usage(),
+        load_average: load_average(),
+        logical_cores: super::threads::logical_cores(),
+    }
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// GPUs reported by `nvidia-smi` (empty without an NVIDIA driver)
+fn nvidia_gpus() -> Vec<GpuMemory> {
+    command_output(
+        "nvidia-smi",
+        &["--query-gpu=index,name,memory.used,memory.total", "--format=csv,noheader,nounits"],
+    )
+    .map(|output| parse_nvidia_smi(&output))
+    .unwrap_or_default()
+}
+
+/// Parse `index, name, used MiB, total MiB` lines
+fn parse_nvidia_smi(output: &str) -> Vec<GpuMemory> {
+    const MIB: u64 = 1024 * 1024;
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            match fields.as_slice() {
+                [index, name, used, total] => Some(GpuMemory {
+                    index: index.parse().ok()?,
+                    name: name.to_string(),
+                    used_bytes: used.parse::<u64>().ok()? * MIB,
+                    total_bytes: total.parse::<u64>().ok()? * MIB,
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Value in kB of a `Name:   1234 kB` line of a /proc file
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn proc_kb(content: &str, name: &str) -> Option<u64> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+        .and_then(|value| value.split_whitespace().next()?.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+/// Busy and total jiffies of the aggregated `cpu` line of /proc/stat
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_cpu_times(stat: &str) -> Option<(u64, u64)> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let times: Vec<u64> = line.split_whitespace().skip(1).filter_map(|t| t.parse().ok()).collect();
+    // idle + iowait
+    let idle = times.get(3)? + times.get(4).copied().unwrap_or(0);
+    let total: u64 = times.iter().sum();
+    Some((total - idle, total))
+}
+
+#[cfg(target_os = "linux")]
+fn process_rss() -> Option<u64> {
+    proc_kb(&std::fs::read_to_string("/proc/self/status").ok()?, "VmRSS")
+}
+
+#[cfg(target_os = "linux")]
+fn memory() -> (Option<u64>, Option<u64>) {
+    match std::fs::read_to_string("/proc/meminfo") {
+        Ok(meminfo) => (proc_kb(&meminfo, "MemTotal"), proc_kb(&meminfo, "MemAvailable")),
+        Err(_) => (None, None),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn cpu_usage() -> Option<f32> {
+    let sample = || parse_cpu_times(&std::fs::read_to_string("/proc/stat").ok()?);
+    let (busy_before, total_before) = sample()?;
+    std::thread::sleep(CPU_SAMPLE_INTERVAL);
+    let (busy_after, total_after) = sample()?;
+    let total = total_after.checked_sub(total_before).filter(|t| *t > 0)?;
+    Some(busy_after.saturating_sub(busy_before) as f32 * 100.0 / total as f32)
+}
+
+#[cfg(target_os = "linux")]
+fn load_average() -> Option<f64> {
+    std::fs::read_to_string("/proc/loadavg").ok()?.split_whitespace().next()?.parse().ok()
+}
+
+#[cfg(target_os = "macos")]
+fn process_rss() -> Option<u64> {
+    let pid = std::process::id().to_string();
+    let kb: u64 = command_output("ps", &["-o", "rss=", "-p", &pid])?.trim().parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(target_os = "macos")]
+fn memory() -> (Option<u64>, Option<u64>) {
+    let total = command_output("sysctl", &["-n", "hw.memsize"]).and_then(|v| v.trim().parse().ok());
+    // Free and inactive pages can be reclaimed without swapping
+    let available = command_output("vm_stat", &[]).and_then(|vm_stat| {
+        let page_size: u64 = vm_stat
+            .lines()
+            .next()?
+            .split("page size of ")
+            .nth(1)?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()?;
+        let pages = |name: &str| {
+            vm_stat
+                .lines()
+                .find_map(|line| line.strip_prefix(name))
+                .and_then(|value| value.trim().trim_end_matches('.').parse::<u64>().ok())
+        };
+        Some((pages("Pages free:")? + pages("Pages inactive:")?) * page_size)
+    });
+    (total, available)
+}
+
+#[cfg(target_os = "macos")]
+fn cpu_usage() -> Option<f32> {
+    // Instant %CPU of every process, divided by the number of cores
+    let output = command_output("ps", &["-A", "-o", "%cpu="])?;
+    let total: f32 = output.lines().filter_map(|line| line.trim().parse::<f32>().ok()).sum();
+    Some((total / super::threads::logical_cores() as f32).min(100.0))
+}
+
+#[cfg(target_os = "macos")]
+fn load_average() -> Option<f64> {
+    // "{ 1.52 1.38 1.30 }"
+    command_output("sysctl", &["-n", "vm.loadavg"])?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn process_rss() -> Option<u64> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn memory() -> (Option<u64>, Option<u64>) {
+    (None, None)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn cpu_usage() -> Option<f32> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn load_average() -> Option<f64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metrics() {
+        let meminfo = "MemTotal:       16309248 kB\nMemFree:         1022340 kB\nMemAvailable:    8154624 kB\n";
+        assert_eq!(proc_kb(meminfo, "MemTotal"), Some(16309248 * 1024));
+        assert_eq!(proc_kb(meminfo, "MemAvailable"), Some(8154624 * 1024));
+        assert_eq!(proc_kb(meminfo, "SwapTotal"), None);
+
+        let stat = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 50 0 25 400 25 0 0 0 0 0\n";
+        assert_eq!(parse_cpu_times(stat), Some((150, 1000)));
+
+        let gpus = parse_nvidia_smi("0, NVIDIA GeForce RTX 4090, 1024, 24564\nNo devices were found\n");
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].name, "NVIDIA GeForce RTX 4090");
+        assert_eq!(gpus[0].used_bytes, 1024 * 1024 * 1024);
+
+        let metrics = SystemMetrics { available_memory_bytes: Some(1000), ..Default::default() };
+        assert!(metrics.fits_in_memory(1000));
+        assert!(!metrics.fits_in_memory(1001));
+        assert!(SystemMetrics::default().fits_in_memory(u64::MAX));
+    }
+}
@@ -3,7 +3,7 @@
 
 #[cfg(test)]
 mod model_tests {
-    use crate::llm::{LLMEngine, LLMConfig};
+    use crate::llm::{ChatMessage, LLMEngine, LLMConfig, ModelConfig, SamplingConfig};
 
     #[tokio::test]
     async fn test_model_loading() {
@@ -13,18 +13,30 @@ mod model_tests {
         model_path.push("models/Qwen3-1.7B-IQ4_XS.gguf");
         
         let config = LLMConfig {
-            model_path: model_path.to_string_lossy().to_string(),
-            n_ctx: 2048,
-            n_threads: 4,
-            temperature: 0.7,
-            top_p: 0.9,
-            top_k: 40,
-            repeat_penalty: 1.1,
-            max_tokens: 512,
-            context_size: 2048,
-            use_gpu: false,
-            n_gpu_layers: 0,
-            main_gpu: 0,
+            model: ModelConfig {
+                model_path: model_path.to_string_lossy().to_string(),
+                n_ctx: 2048,
+                n_threads: 4,
+                context_size: 2048,
+                use_gpu: false,
+                n_gpu_layers: 0,
+                main_gpu: 0,
+                context_shift: true,
+                prompt_cache_entries: 8,
+                prompt_cache_size_mb: 512,
+                mmproj_path: None,
+                ..Default::default()
+            },
+            sampling: SamplingConfig {
+                temperature: 0.7,
+                top_p: 0.9,
+                top_k: 40,
+                repeat_penalty: 1.1,
+                max_tokens: 512,
+                logprobs: None,
+                stop_conditions: Vec::new(),
+            },
+            ..Default::default()
         };
 
         let engine = LLMEngine::new(config).expect("Failed to create LLM engine");
@@ -52,25 +64,37 @@ mod model_tests {
         model_path.push("models/Qwen3-1.7B-IQ4_XS.gguf");
         
         let config = LLMConfig {
-            model_path: model_path.to_string_lossy().to_string(),
-            n_ctx: 2048,
-            n_threads: 4,
-            temperature: 0.7,
-            top_p: 0.9,
-            top_k: 40,
-            repeat_penalty: 1.1,
-            max_tokens: 512,
-            context_size: 2048,
-            use_gpu: false,
-            n_gpu_layers: 0,
-            main_gpu: 0,
+            model: ModelConfig {
+                model_path: model_path.to_string_lossy().to_string(),
+                n_ctx: 2048,
+                n_threads: 4,
+                context_size: 2048,
+                use_gpu: false,
+                n_gpu_layers: 0,
+                main_gpu: 0,
+                context_shift: true,
+                prompt_cache_entries: 8,
+                prompt_cache_size_mb: 512,
+                mmproj_path: None,
+                ..Default::default()
+            },
+            sampling: SamplingConfig {
+                temperature: 0.7,
+                top_p: 0.9,
+                top_k: 40,
+                repeat_penalty: 1.1,
+                max_tokens: 512,
+                logprobs: None,
+                stop_conditions: Vec::new(),
+            },
+            ..Default::default()
         };
 
         let engine = LLMEngine::new(config).expect("Failed to create engine");
         let _ = engine.load_model().await;
 
         if engine.is_loaded().await {
-            let response = engine.generate("Hello, how are you?").await;
+            let response = engine.generate(&[ChatMessage::user("Hello, how are you?")]).await;
             
             match response {
                 Ok(resp) => {
@@ -83,4 +107,57 @@ mod model_tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_generate_batch_with_model() {
+        let mut model_path = std::env::current_dir().expect("Failed to get current dir");
+        model_path.pop(); // Remove src-tauri from path
+        model_path.push("models/Qwen3-1.7B-IQ4_XS.gguf");
+        
+        let config = LLMConfig {
+            model: ModelConfig {
+                model_path: model_path.to_string_lossy().to_string(),
+                n_ctx: 2048,
+                n_threads: 4,
+                context_size: 2048,
+                use_gpu: false,
+                n_gpu_layers: 0,
+                main_gpu: 0,
+                context_shift: true,
+                prompt_cache_entries: 8,
+                prompt_cache_size_mb: 512,
+                mmproj_path: None,
+                ..Default::default()
+            },
+            sampling: SamplingConfig {
+                temperature: 0.7,
+                top_p: 0.9,
+                top_k: 40,
+                repeat_penalty: 1.1,
+                max_tokens: 32,
+                logprobs: None,
+                stop_conditions: Vec::new(),
+            },
+            ..Default::default()
+        };
+
+        let engine = LLMEngine::new(config).expect("Failed to create engine");
+        let _ = engine.load_model().await;
+
+        if engine.is_loaded().await {
+            let prompts = vec![
+                "Give a title for a chat about Rust lifetimes".to_string(),
+                "Summarize in one word: the sky is blue".to_string(),
+            ];
+            match engine.generate_batch(&prompts).await {
+                Ok(responses) => {
+                    println!("✅ Batch responses: {:?}", responses.iter().map(|r| &r.text).collect::<Vec<_>>());
+                    assert_eq!(responses.len(), prompts.len());
+                }
+                Err(e) => {
+                    println!("⚠️  Batch generation failed: {}", e);
+                }
+            }
+        }
+    }
 }
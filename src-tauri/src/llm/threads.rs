@@ -0,0 +1,118 @@
+/// CPU thread detection for the inference contexts
+///
+/// llama.cpp runs best with one thread per physical core: hyper-threads share the
+/// execution units and mostly add contention.
+
+use super::config::ModelConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use ts_rs::TS;
+
+/// Thread counts chosen by the user (None = auto-detected)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ThreadSettings {
+    /// Threads used while generating tokens
+    #[ts(type = "number | null")]
+    pub n_threads: Option<usize>,
+    /// Threads used while evaluating the prompt
+    #[ts(type = "number | null")]
+    pub n_threads_batch: Option<usize>,
+    /// Use fewer threads while the window is not focused
+    #[serde(default)]
+    pub efficiency_mode: bool,
+}
+
+impl ThreadSettings {
+    /// Resolve the automatic values and store them in the model settings
+    pub fn apply_to(&self, config: &mut ModelConfig) {
+        config.n_threads = self.n_threads.unwrap_or_else(physical_cores);
+        config.n_threads_batch = self.n_threads_batch.unwrap_or(0);
+        config.efficiency_mode = self.efficiency_mode;
+    }
+}
+
+/// Number of logical CPUs, 1 when unknown
+pub fn logical_cores() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Number of physical CPU cores, estimated from the logical count when the
+/// platform doesn't report it (detected once)
+pub fn physical_cores() -> usize {
+    static PHYSICAL_CORES: OnceLock<usize> = OnceLock::new();
+    *PHYSICAL_CORES.get_or_init(|| {
+        detect_physical_cores().unwrap_or_else(|| {
+            let logical = logical_cores();
+            // Most desktop CPUs with more than 4 logical CPUs run 2 threads per core
+            if logical > 4 { logical / 2 } else { logical }
+        })
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn detect_physical_cores() -> Option<usize> {
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+    parse_cpuinfo(&cpuinfo)
+}
+
+#[cfg(target_os = "macos")]
+fn detect_physical_cores() -> Option<usize> {
+    let output = std::process::Command::new("sysctl")
+        .args(["-n", "hw.physicalcpu"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok().filter(|n| *n > 0)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn detect_physical_cores() -> Option<usize> {
+    None
+}
+
+/// Count the distinct (physical id, core id) pairs of /proc/cpuinfo
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_cpuinfo(cpuinfo: &str) -> Option<usize> {
+    let mut cores = HashSet::new();
+    for processor in cpuinfo.split("\n\n") {
+        let field = |name: &str| {
+            processor
+                .lines()
+                .find(|line| line.split(':').next().is_some_and(|key| key.trim() == name))
+                .and_then(|line| line.split(':').nth(1))
+                .map(|value| value.trim().to_string())
+        };
+        if let Some(core_id) = field("core id") {
+            cores.insert((field("physical id").unwrap_or_default(), core_id));
+        }
+    }
+    Some(cores.len()).filter(|n| *n > 0)
+}
+
+/// Threads left to the engine in efficiency mode
+pub fn efficiency_threads(n_threads: usize) -> usize {
+    (n_threads / 4).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpuinfo() {
+        // 1 socket, 2 cores with 2 threads each
+        let cpuinfo = "processor\t: 0\nphysical id\t: 0\ncore id\t\t: 0\n\n\
+                       processor\t: 1\nphysical id\t: 0\ncore id\t\t: 1\n\n\
+                       processor\t: 2\nphysical id\t: 0\ncore id\t\t: 0\n\n\
+                       processor\t: 3\nphysical id\t: 0\ncore id\t\t: 1\n";
+        assert_eq!(parse_cpuinfo(cpuinfo), Some(2));
+
+        // Some ARM kernels don't report core ids
+        assert_eq!(parse_cpuinfo("processor\t: 0\nBogoMIPS\t: 48.00\n"), None);
+
+        assert!(physical_cores() >= 1);
+        assert_eq!(efficiency_threads(8), 2);
+        assert_eq!(efficiency_threads(2), 1);
+    }
+}
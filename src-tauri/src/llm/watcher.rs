@@ -0,0 +1,102 @@
+/// Watcher of the models directory
+///
+/// Files added or removed outside the application (file manager, `cp`, another
+/// download tool) are reported so that the model list stays up to date.
+
+use super::model_manager::ModelManager;
+use anyhow::{Context, Result};
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Quiet period before the directory is scanned again: a copy or a download
+/// produces a burst of events
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+/// GGUF files added and removed since the previous change
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ModelsChanged {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl ModelsChanged {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Difference between two listings of the models directory
+fn diff(previous: &BTreeSet<String>, current: &BTreeSet<String>) -> ModelsChanged {
+    ModelsChanged {
+        added: current.difference(previous).cloned().collect(),
+        removed: previous.difference(current).cloned().collect(),
+    }
+}
+
+fn model_files(model_manager: &ModelManager) -> BTreeSet<String> {
+    match model_manager.list_models() {
+        Ok(models) => models.into_iter().map(|model| model.file_name).collect(),
+        Err(e) => {
+            warn!("Failed to list models: {:#}", e);
+            BTreeSet::new()
+        }
+    }
+}
+
+/// Watch the models directory until the application exits, calling
+/// `on_change` each time its GGUF files change
+pub async fn watch_models(model_manager: Arc<ModelManager>, on_change: impl Fn(ModelsChanged)) -> Result<()> {
+    let directory = model_manager.models_directory().to_path_buf();
+    let (events_tx, mut events) = mpsc::unbounded_channel();
+
+    // notify calls the handler from its own thread
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+        Ok(event) => {
+            let _ = events_tx.send(event);
+        }
+        Err(e) => warn!("Models directory watcher error: {}", e),
+    })
+    .context("Failed to create the models directory watcher")?;
+    watcher
+        .watch(&directory, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {:?}", directory))?;
+    info!("Watching models directory: {:?}", directory);
+
+    let mut known = model_files(&model_manager);
+    while let Some(event) = events.recv().await {
+        debug!("Models directory event: {:?}", event.kind);
+        // Wait for the burst to end, the scan then sees the final state
+        while let Ok(Some(_)) = tokio::time::timeout(SETTLE_DELAY, events.recv()).await {}
+
+        let current = model_files(&model_manager);
+        let changed = diff(&known, &current);
+        if !changed.is_empty() {
+            info!("Models changed: {} added, {} removed", changed.added.len(), changed.removed.len());
+            on_change(changed);
+        }
+        known = current;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let previous: BTreeSet<String> = ["a.gguf", "b.gguf"].iter().map(|s| s.to_string()).collect();
+        let current: BTreeSet<String> = ["b.gguf", "c.gguf"].iter().map(|s| s.to_string()).collect();
+
+        let changed = diff(&previous, &current);
+        assert_eq!(changed.added, vec!["c.gguf"]);
+        assert_eq!(changed.removed, vec!["a.gguf"]);
+        assert!(diff(&current, &current).is_empty());
+    }
+}
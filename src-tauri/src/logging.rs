@@ -0,0 +1,228 @@
+/// Journalisation : console et fichiers tournants dans le dossier de données
+///
+/// Les logs sont écrits dans `logs/agents-rs.log`. Au-delà de `MAX_FILE_SIZE`, le
+/// fichier devient `agents-rs.log.1`, les plus anciens sont décalés et le dernier
+/// est supprimé. Le niveau se modifie à chaud, sans redémarrer l'application.
+
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+/// Niveau par défaut (surchargé par RUST_LOG ou le réglage enregistré)
+pub const DEFAULT_LOG_LEVEL: &str = "info,agents_rs=debug";
+
+const LOG_FILE_NAME: &str = "agents-rs.log";
+
+/// Taille maximale d'un fichier de log avant rotation
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Nombre de fichiers conservés (fichier courant compris)
+const MAX_FILES: usize = 5;
+
+/// Nombre maximal de lignes renvoyées par `recent_lines`
+pub const MAX_RECENT_LINES: usize = 5000;
+
+/// Chemin du fichier de rang `index` (0 = fichier courant)
+fn log_path(dir: &Path, index: usize) -> PathBuf {
+    match index {
+        0 => dir.join(LOG_FILE_NAME),
+        _ => dir.join(format!("{}.{}", LOG_FILE_NAME, index)),
+    }
+}
+
+/// Fichier de log avec rotation par taille
+pub struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir).context("Impossible de créer le dossier des logs")?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path(dir, 0))
+            .context("Impossible d'ouvrir le fichier de log")?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file,
+            size,
+            max_size: MAX_FILE_SIZE,
+        })
+    }
+
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for index in (1..MAX_FILES).rev() {
+            let from = log_path(&self.dir, index - 1);
+            if from.exists() {
+                let to = log_path(&self.dir, index);
+                // Windows refuse de renommer vers un fichier existant
+                let _ = std::fs::remove_file(&to);
+                std::fs::rename(&from, &to)?;
+            }
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(log_path(&self.dir, 0))?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Dernières lignes des logs, de la plus ancienne à la plus récente
+pub fn read_recent_lines(dir: &Path, lines: usize) -> Result<Vec<String>> {
+    let mut recent: Vec<String> = Vec::new();
+    for index in 0..MAX_FILES {
+        if recent.len() >= lines {
+            break;
+        }
+        let path = log_path(dir, index);
+        if !path.exists() {
+            break;
+        }
+        let content = std::fs::read(&path)
+            .with_context(|| format!("Impossible de lire {}", path.display()))?;
+        let mut older: Vec<String> = String::from_utf8_lossy(&content).lines().map(String::from).collect();
+        older.append(&mut recent);
+        recent = older;
+    }
+
+    let skip = recent.len().saturating_sub(lines);
+    Ok(recent.split_off(skip))
+}
+
+/// Dossier des logs dans le dossier de données de l'application
+pub fn default_log_directory() -> Option<PathBuf> {
+    directories::ProjectDirs::from("com", "agents-rs", "AgentsRS")
+        .map(|dirs| dirs.data_dir().join("logs"))
+}
+
+/// Accès au niveau de log et aux fichiers après l'initialisation
+#[derive(Clone)]
+pub struct LogHandle {
+    filter: reload::Handle<EnvFilter, Registry>,
+    directory: Option<PathBuf>,
+}
+
+impl LogHandle {
+    /// Change le niveau (`debug`, `warn`...) ou les directives (`info,agents_rs=trace`)
+    pub fn set_level(&self, directives: &str) -> Result<()> {
+        let filter = EnvFilter::try_new(directives)
+            .with_context(|| format!("Niveau de log invalide: {}", directives))?;
+        self.filter.reload(filter).context("Impossible de changer le niveau de log")
+    }
+
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    /// Dernières lignes écrites dans les fichiers de log (vide sans fichier)
+    pub fn recent_lines(&self, lines: usize) -> Result<Vec<String>> {
+        match &self.directory {
+            Some(dir) => read_recent_lines(dir, lines.min(MAX_RECENT_LINES)),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+/// Installe le subscriber global : console et, si possible, fichiers dans `directory`
+pub fn init(directory: Option<PathBuf>) -> LogHandle {
+    install(directory, DEFAULT_LOG_LEVEL, BoxMakeWriter::new(io::stdout))
+}
+
+/// Variante de `init` pour la ligne de commande : la console passe sur la sortie
+/// d'erreur (la sortie standard reste aux résultats) avec `default_level` si
+/// RUST_LOG n'est pas défini
+pub fn init_stderr(directory: Option<PathBuf>, default_level: &str) -> LogHandle {
+    install(directory, default_level, BoxMakeWriter::new(io::stderr))
+}
+
+fn install(directory: Option<PathBuf>, default_level: &str, console: BoxMakeWriter) -> LogHandle {
+    let level = std::env::var("RUST_LOG").unwrap_or_else(|_| default_level.to_string());
+    let filter = EnvFilter::try_new(&level).unwrap_or_else(|_| EnvFilter::new(default_level));
+    let (filter, filter_handle) = reload::Layer::new(filter);
+
+    // Le subscriber n'existe pas encore : l'erreur éventuelle est journalisée après l'installation
+    let (file, file_error) = match directory.as_deref().map(RotatingFile::open).transpose() {
+        Ok(file) => (file, None),
+        Err(e) => (None, Some(e)),
+    };
+    let file_enabled = file.is_some();
+    let file_layer = file.map(|file| {
+        fmt::layer()
+            .with_ansi(false)
+            .with_writer(Mutex::new(file))
+    });
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(console))
+        .with(file_layer)
+        .init();
+
+    if let Some(e) = file_error {
+        warn!("Logs écrits uniquement dans la console: {:#}", e);
+    }
+
+    LogHandle {
+        filter: filter_handle,
+        directory: directory.filter(|_| file_enabled),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_and_recent_lines() {
+        let dir = std::env::temp_dir().join(format!("agents-rs-logs-{}", uuid::Uuid::new_v4()));
+        let mut file = RotatingFile::open(&dir).unwrap().with_max_size(20);
+
+        for i in 0..10 {
+            file.write_all(format!("line {}\n", i).as_bytes()).unwrap();
+        }
+        assert!(log_path(&dir, 1).exists());
+        assert!(!log_path(&dir, MAX_FILES).exists());
+
+        let recent = read_recent_lines(&dir, 3).unwrap();
+        assert_eq!(recent, vec!["line 7", "line 8", "line 9"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+/// Confirmation des outils sensibles avant leur exécution
+///
+/// L'exécution d'un outil marqué `requires_approval` est suspendue : une demande
+/// est diffusée (événement "tool-approval-request" côté interface) et l'appel ne
+/// reprend qu'après la réponse de l'utilisateur. Un outil peut être autorisé
+/// durablement pour une portée (session, exécution de tâche).
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot};
+use tracing::{info, warn};
+use ts_rs::TS;
+
+/// Délai après lequel une demande sans réponse est refusée
+pub const APPROVAL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Appel d'outil en attente de confirmation
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ApprovalRequest {
+    pub call_id: String,
+    /// Session (ou exécution de tâche) à l'origine de l'appel
+    pub scope: String,
+    pub tool_name: String,
+    pub arguments: serde_json::Value,
+    /// Motif de la demande quand ce n'est pas l'appel lui-même (résultat suspect)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+struct PendingApproval {
+    request: ApprovalRequest,
+    reply: oneshot::Sender<bool>,
+}
+
+#[derive(Default)]
+struct ApprovalState {
+    pending: HashMap<String, PendingApproval>,
+    /// Couples (portée, outil) autorisés sans confirmation
+    always_allowed: HashSet<(String, String)>,
+}
+
+/// Demandes de confirmation partagées entre le registre d'outils et les commandes
+#[derive(Clone)]
+pub struct ToolApprovals {
+    state: Arc<Mutex<ApprovalState>>,
+    requests: broadcast::Sender<ApprovalRequest>,
+    timeout: Duration,
+}
+
+impl ToolApprovals {
+    pub fn new() -> Self {
+        let (requests, _) = broadcast::channel(16);
+        Self {
+            state: Arc::new(Mutex::new(ApprovalState::default())),
+            requests,
+            timeout: APPROVAL_TIMEOUT,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Flux des nouvelles demandes de confirmation
+    pub fn subscribe(&self) -> broadcast::Receiver<ApprovalRequest> {
+        self.requests.subscribe()
+    }
+
+    /// Demandes encore sans réponse (après un rechargement de l'interface)
+    pub fn pending(&self) -> Vec<ApprovalRequest> {
+        let state = self.state.lock().unwrap();
+        state.pending.values().map(|p| p.request.clone()).collect()
+    }
+
+    /// Attend la décision de l'utilisateur pour un appel (refusé après le délai)
+    pub async fn request(&self, scope: &str, tool_name: &str, arguments: &serde_json::Value) -> bool {
+        self.request_with_reason(scope, tool_name, arguments, None).await
+    }
+
+    /// Demande motivée (ex. injection de prompt) : jamais couverte par une autorisation permanente
+    pub async fn request_with_reason(
+        &self,
+        scope: &str,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+        reason: Option<String>,
+    ) -> bool {
+        let request = ApprovalRequest {
+            call_id: uuid::Uuid::new_v4().to_string(),
+            scope: scope.to_string(),
+            tool_name: tool_name.to_string(),
+            arguments: arguments.clone(),
+            reason,
+        };
+
+        let receiver = {
+            let mut state = self.state.lock().unwrap();
+            let allowed = state.always_allowed.contains(&(scope.to_string(), tool_name.to_string()));
+            if allowed && request.reason.is_none() {
+                return true;
+            }
+            let (reply, receiver) = oneshot::channel();
+            state.pending.insert(request.call_id.clone(), PendingApproval { request: request.clone(), reply });
+            receiver
+        };
+
+        info!("Confirmation demandée pour l'outil {} ({})", tool_name, request.call_id);
+        let _ = self.requests.send(request.clone());
+
+        match tokio::time::timeout(self.timeout, receiver).await {
+            Ok(Ok(approved)) => approved,
+            _ => {
+                warn!("Pas de réponse pour l'appel {}, outil refusé", request.call_id);
+                self.state.lock().unwrap().pending.remove(&request.call_id);
+                false
+            }
+        }
+    }
+
+    /// Répond à une demande ; `always_allow` autorise l'outil pour le reste de la portée
+    pub fn respond(&self, call_id: &str, approved: bool, always_allow: bool) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let pending = state
+            .pending
+            .remove(call_id)
+            .ok_or_else(|| anyhow::anyhow!("Aucune demande en attente: {}", call_id))?;
+
+        if approved && always_allow && pending.request.reason.is_none() {
+            state
+                .always_allowed
+                .insert((pending.request.scope.clone(), pending.request.tool_name.clone()));
+        }
+
+        let _ = pending.reply.send(approved);
+        Ok(())
+    }
+
+    /// Oublie les autorisations permanentes d'une portée (session supprimée, tâche terminée)
+    pub fn clear_scope(&self, scope: &str) {
+        self.state.lock().unwrap().always_allowed.retain(|(s, _)| s != scope);
+    }
+}
+
+impl Default for ToolApprovals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_approve_and_always_allow() {
+        let approvals = ToolApprovals::new();
+        let mut requests = approvals.subscribe();
+
+        let responder = approvals.clone();
+        tokio::spawn(async move {
+            let request = requests.recv().await.unwrap();
+            responder.respond(&request.call_id, true, true).unwrap();
+        });
+
+        let arguments = serde_json::json!({"path": "notes.txt"});
+        assert!(approvals.request("session-1", "file_writer", &arguments).await);
+        // Autorisé pour la session, plus de demande
+        assert!(approvals.request("session-1", "file_writer", &arguments).await);
+        assert!(approvals.pending().is_empty());
+
+        approvals.clear_scope("session-1");
+        let approvals = approvals.with_timeout(Duration::from_millis(50));
+        assert!(!approvals.request("session-1", "file_writer", &arguments).await);
+        assert!(approvals.pending().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_denied_call() {
+        let approvals = ToolApprovals::new();
+        let mut requests = approvals.subscribe();
+
+        let responder = approvals.clone();
+        tokio::spawn(async move {
+            let request = requests.recv().await.unwrap();
+            responder.respond(&request.call_id, false, true).unwrap();
+        });
+
+        assert!(!approvals.request("session-1", "file_writer", &serde_json::json!({})).await);
+        assert!(approvals.respond("unknown", true, false).is_err());
+    }
+}
@@ -0,0 +1,384 @@
+/// Outil run_code : exécution de courts programmes Python ou JavaScript
+///
+/// Le code est écrit dans un dossier temporaire et exécuté dans un sous-processus
+/// avec un environnement vide, une durée maximale et, sous Unix, des limites de
+/// temps CPU et de mémoire (`ulimit`), ou dans un conteneur éphémère lorsque
+/// l'exécution en conteneur est activée (voir `container`). Le code est
+/// arbitraire : l'outil demande une confirmation.
+
+use super::container::ContainerRunner;
+use super::tools::{OutputSink, Tool, ToolContext, ToolHandler};
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+/// Durée maximale par défaut, et plafond accepté, en secondes
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const MAX_TIMEOUT_SECS: u64 = 60;
+
+/// Mémoire maximale du processus, en Mo
+pub(super) const MEMORY_LIMIT_MB: u64 = 512;
+
+/// Taille maximale de chaque flux de sortie renvoyé au modèle
+const MAX_STREAM_CHARS: usize = 10_000;
+
+/// Langages pris en charge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeLanguage {
+    Python,
+    JavaScript,
+}
+
+impl CodeLanguage {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "python" | "py" => Some(CodeLanguage::Python),
+            "javascript" | "js" | "node" => Some(CodeLanguage::JavaScript),
+            _ => None,
+        }
+    }
+
+    pub(super) fn file_name(&self) -> &'static str {
+        match self {
+            CodeLanguage::Python => "main.py",
+            CodeLanguage::JavaScript => "main.js",
+        }
+    }
+
+    fn interpreter(&self) -> &'static str {
+        match self {
+            CodeLanguage::Python if cfg!(windows) => "python",
+            CodeLanguage::Python => "python3",
+            CodeLanguage::JavaScript => "node",
+        }
+    }
+
+    /// Arguments exécutant le script dans un conteneur (limité par le conteneur)
+    pub(super) fn container_command(&self, script: &str) -> Vec<String> {
+        match self {
+            CodeLanguage::Python => vec!["python3".to_string(), script.to_string()],
+            CodeLanguage::JavaScript => vec![
+                "node".to_string(),
+                format!("--max-old-space-size={}", MEMORY_LIMIT_MB),
+                script.to_string(),
+            ],
+        }
+    }
+
+    /// Commande exécutant le script avec les limites de ressources
+    fn command(&self, script: &Path, timeout_secs: u64) -> Command {
+        let script = script.to_string_lossy().to_string();
+        // V8 réserve beaucoup de mémoire virtuelle : Node est limité par son propre tas
+        let memory_flag = format!("--max-old-space-size={}", MEMORY_LIMIT_MB);
+        let args: Vec<&str> = match self {
+            CodeLanguage::Python => vec![&script],
+            CodeLanguage::JavaScript => vec![&memory_flag, &script],
+        };
+
+        if cfg!(unix) {
+            let virtual_limit = match self {
+                CodeLanguage::Python => format!("ulimit -v {} && ", MEMORY_LIMIT_MB * 1024),
+                CodeLanguage::JavaScript => String::new(),
+            };
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(format!("ulimit -t {} && {}exec \"$0\" \"$@\"", timeout_secs, virtual_limit))
+                .arg(self.interpreter())
+                .args(args);
+            command
+        } else {
+            let mut command = Command::new(self.interpreter());
+            command.args(args);
+            command
+        }
+    }
+}
+
+/// Résultat d'une exécution
+#[derive(Debug)]
+pub struct CodeOutput {
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+}
+
+impl CodeOutput {
+    /// Présentation pour le modèle
+    pub fn to_text(&self) -> String {
+        let status = match (self.timed_out, self.exit_code) {
+            (true, _) => "interrompu (durée maximale atteinte)".to_string(),
+            (false, Some(code)) => format!("code de sortie {}", code),
+            (false, None) => "interrompu par un signal (limite de ressources ?)".to_string(),
+        };
+        format!(
+            "Statut: {}\n--- stdout ---\n{}\n--- stderr ---\n{}",
+            status,
+            truncate_stream(&self.stdout),
+            truncate_stream(&self.stderr)
+        )
+    }
+}
+
+fn truncate_stream(output: &str) -> String {
+    if output.chars().count() <= MAX_STREAM_CHARS {
+        return output.trim_end().to_string();
+    }
+    let truncated: String = output.chars().take(MAX_STREAM_CHARS).collect();
+    format!("{}\n[... sortie tronquée]", truncated)
+}
+
+/// Exécute un programme dans un dossier temporaire supprimé ensuite
+pub async fn run_code(language: CodeLanguage, code: &str, timeout: Duration) -> Result<CodeOutput> {
+    run_code_streaming(language, code, timeout, &|_| {}).await
+}
+
+/// Comme `run_code`, en transmettant chaque ligne de stdout et stderr dès qu'elle est écrite
+pub async fn run_code_streaming(
+    language: CodeLanguage,
+    code: &str,
+    timeout: Duration,
+    on_output: OutputSink<'_>,
+) -> Result<CodeOutput> {
+    run_code_sandboxed(language, code, timeout, None, on_output).await
+}
+
+/// Conteneur utilisé par `run_code_sandboxed` et espace de travail monté en lecture seule
+pub struct ContainerTarget<'a> {
+    pub runner: &'a ContainerRunner,
+    pub workspace: Option<&'a Path>,
+}
+
+/// Comme `run_code_streaming`, dans un conteneur éphémère si `container` est fourni
+pub async fn run_code_sandboxed(
+    language: CodeLanguage,
+    code: &str,
+    timeout: Duration,
+    container: Option<ContainerTarget<'_>>,
+    on_output: OutputSink<'_>,
+) -> Result<CodeOutput> {
+    let dir = std::env::temp_dir().join(format!("agents-rs-run-{}", uuid::Uuid::new_v4()));
+    tokio::fs::create_dir_all(&dir).await
+        .context("Impossible de créer le dossier d'exécution")?;
+    let result = run_in(&dir, language, code, timeout, container.as_ref(), on_output).await;
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    result
+}
+
+async fn run_in(
+    dir: &Path,
+    language: CodeLanguage,
+    code: &str,
+    timeout: Duration,
+    container: Option<&ContainerTarget<'_>>,
+    on_output: OutputSink<'_>,
+) -> Result<CodeOutput> {
+    let script = dir.join(language.file_name());
+    tokio::fs::write(&script, code).await
+        .context("Impossible d'écrire le programme")?;
+
+    let container_name = format!("agents-rs-run-{}", uuid::Uuid::new_v4());
+    let (mut command, program) = match container {
+        // Le client du moteur garde l'environnement (DOCKER_HOST...), le conteneur part d'un environnement vide
+        Some(target) => (
+            target.runner.command(language, dir, target.workspace, &container_name),
+            target.runner.runtime(),
+        ),
+        None => {
+            let mut command = language.command(&script, timeout.as_secs().max(1));
+            command
+                .current_dir(dir)
+                .env_clear()
+                .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+                .env("HOME", dir)
+                .env("TMPDIR", dir);
+            if cfg!(windows) {
+                if let Some(root) = std::env::var_os("SystemRoot") {
+                    command.env("SystemRoot", root);
+                }
+            }
+            (command, language.interpreter())
+        }
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Impossible de lancer {} (est-il installé ?)", program))?;
+    let guard = container.map(|target| target.runner.guard(&container_name));
+    let stdout = child.stdout.take().context("Sortie standard indisponible")?;
+    let stderr = child.stderr.take().context("Sortie d'erreur indisponible")?;
+
+    let execution = async {
+        let (stdout, stderr) = tokio::try_join!(read_lines(stdout, on_output), read_lines(stderr, on_output))?;
+        let status = child.wait().await?;
+        Ok::<_, std::io::Error>((status, stdout, stderr))
+    };
+
+    // À l'expiration, le processus est tué en libérant `child` (kill_on_drop)
+    // et le conteneur en libérant `guard`
+    let result = tokio::time::timeout(timeout, execution).await;
+    if let (Ok(_), Some(guard)) = (&result, guard) {
+        guard.finish();
+    }
+    match result {
+        Ok(output) => {
+            let (status, stdout, stderr) = output.context("Échec de l'exécution du programme")?;
+            Ok(CodeOutput {
+                exit_code: status.code(),
+                stdout,
+                stderr,
+                timed_out: false,
+            })
+        }
+        Err(_) => Ok(CodeOutput {
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            timed_out: true,
+        }),
+    }
+}
+
+/// Lit un flux ligne par ligne en transmettant chaque ligne dès sa réception
+async fn read_lines(stream: impl AsyncRead + Unpin, on_output: OutputSink<'_>) -> std::io::Result<String> {
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    let mut output = String::new();
+    while reader.read_until(b'\n', &mut line).await? > 0 {
+        let text = String::from_utf8_lossy(&line);
+        on_output(&text);
+        output.push_str(&text);
+        line.clear();
+    }
+    Ok(output)
+}
+
+/// Handler de l'outil run_code
+pub struct RunCodeHandler {
+    /// Conteneur éphémère, `None` pour le bac à sable natif
+    container: Option<ContainerRunner>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for RunCodeHandler {
+    async fn execute(&self, arguments: serde_json::Value, context: &ToolContext) -> Result<String> {
+        self.execute_streaming(arguments, context, &|_| {}).await
+    }
+
+    async fn execute_streaming(
+        &self,
+        arguments: serde_json::Value,
+        context: &ToolContext,
+        on_output: OutputSink<'_>,
+    ) -> Result<String> {
+        let language = arguments
+            .get("language")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Paramètre 'language' manquant"))?;
+        let language = CodeLanguage::parse(language)
+            .ok_or_else(|| anyhow::anyhow!("Langage non pris en charge: {} (python ou javascript)", language))?;
+        let code = arguments
+            .get("code")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Paramètre 'code' manquant"))?;
+        let timeout = arguments
+            .get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_TIMEOUT_SECS)
+            .clamp(1, MAX_TIMEOUT_SECS);
+
+        let container = self.container.as_ref().map(|runner| ContainerTarget {
+            runner,
+            workspace: context.root.as_deref(),
+        });
+        let output = run_code_sandboxed(language, code, Duration::from_secs(timeout), container, on_output).await?;
+        Ok(output.to_text())
+    }
+}
+
+/// Outil run_code, exécuté dans `container` s'il est fourni
+pub fn create_run_code_tool(container: Option<ContainerRunner>) -> Tool {
+    Tool {
+        name: "run_code".to_string(),
+        description: "Exécute un court programme Python ou JavaScript (Node) et renvoie sa sortie".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "language": {
+                    "type": "string",
+                    "enum": ["python", "javascript"],
+                    "description": "Langage du programme"
+                },
+                "code": {
+                    "type": "string",
+                    "description": "Code source à exécuter"
+                },
+                "timeout_secs": {
+                    "type": "integer",
+                    "description": "Durée maximale en secondes (10 par défaut, 60 au plus)"
+                }
+            },
+            "required": ["language", "code"]
+        }),
+        // Exécute du code arbitraire : confirmation obligatoire
+        requires_approval: true,
+        handler: Some(Arc::new(RunCodeHandler { container })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn python_available() -> bool {
+        std::process::Command::new(CodeLanguage::Python.interpreter())
+            .arg("--version")
+            .output()
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_run_python() {
+        if !python_available() {
+            return;
+        }
+
+        let lines = std::sync::Mutex::new(Vec::new());
+        let output = run_code_streaming(
+            CodeLanguage::Python,
+            "import sys\nprint('bonjour')\nprint('oups', file=sys.stderr)\nsys.exit(3)",
+            Duration::from_secs(10),
+            &|line| lines.lock().unwrap().push(line.trim_end().to_string()),
+        )
+        .await
+        .unwrap();
+        let mut lines = lines.into_inner().unwrap();
+        lines.sort();
+        assert_eq!(lines, vec!["bonjour", "oups"]);
+        assert_eq!(output.exit_code, Some(3));
+        assert_eq!(output.stdout.trim(), "bonjour");
+        assert_eq!(output.stderr.trim(), "oups");
+
+        let output = run_code(CodeLanguage::Python, "while True: pass", Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert!(output.timed_out || output.exit_code.is_none());
+    }
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!(CodeLanguage::parse("JS"), Some(CodeLanguage::JavaScript));
+        assert_eq!(CodeLanguage::parse("python"), Some(CodeLanguage::Python));
+        assert_eq!(CodeLanguage::parse("ruby"), None);
+    }
+}
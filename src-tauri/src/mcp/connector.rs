@@ -0,0 +1,383 @@
+/// Connecteurs d'API : outils HTTP déclarés en JSON, sans code Rust
+///
+/// Un connecteur décrit une requête (méthode, URL de base, chemin, paramètres de
+/// requête, en-têtes, corps). Les modèles `{{nom}}` sont remplacés par les
+/// arguments de l'appel et `{{secret.nom}}` par les secrets de l'outil (trousseau),
+/// ce qui évite d'écrire un jeton dans la configuration. Le résultat peut être
+/// réduit à une partie de la réponse JSON (`$.data.items[0].name`).
+
+use super::tools::{input_schema, Tool, ToolContext, ToolHandler, ToolParameter};
+use anyhow::{Context, Result};
+use regex::Regex;
+use reqwest::{Client, Method};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use ts_rs::TS;
+
+/// Durée maximale d'une requête
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Taille maximale de la réponse renvoyée au modèle
+const MAX_RESPONSE_CHARS: usize = 20_000;
+
+/// Préfixe des modèles remplacés par un secret de l'outil
+const SECRET_PREFIX: &str = "secret.";
+
+pub(super) fn placeholder() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z0-9_.-]+)\s*\}\}").unwrap())
+}
+
+/// Définition d'un connecteur
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ApiConnector {
+    /// Nom de l'outil
+    pub name: String,
+    pub description: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    pub base_url: String,
+    /// Chemin ajouté à l'URL de base (modèles encodés)
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Corps JSON ; une chaîne réduite à `{{nom}}` reçoit la valeur de l'argument telle quelle
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+    #[serde(default)]
+    pub parameters: Vec<ToolParameter>,
+    /// Partie de la réponse JSON renvoyée au modèle (toute la réponse sinon)
+    #[serde(default)]
+    pub result_path: Option<String>,
+    /// Confirmation avant chaque appel (par défaut pour toute méthode autre que GET)
+    #[serde(default)]
+    pub requires_approval: Option<bool>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+impl ApiConnector {
+    /// Vérifie la définition avant de l'enregistrer
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty()
+            || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!("Nom de connecteur invalide: {:?} (lettres, chiffres, _ et -)", self.name));
+        }
+        self.method()?;
+        let url = reqwest::Url::parse(&self.base_url)
+            .map_err(|e| format!("URL de base invalide: {}", e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Schéma non pris en charge: {}", url.scheme()));
+        }
+
+        let mut templates: Vec<&str> = vec![&self.path];
+        templates.extend(self.query.values().map(String::as_str));
+        templates.extend(self.headers.values().map(String::as_str));
+        let body = self.body.as_ref().map(|b| b.to_string()).unwrap_or_default();
+        templates.push(&body);
+        for template in templates {
+            for name in placeholder().captures_iter(template).map(|c| c[1].to_string()) {
+                if !name.starts_with(SECRET_PREFIX) && !self.parameters.iter().any(|p| p.name == name) {
+                    return Err(format!("Paramètre non déclaré dans un modèle: {}", name));
+                }
+            }
+        }
+
+        if self.result_path.as_deref().is_some_and(|p| p.trim().is_empty()) {
+            return Err("Le chemin du résultat est vide".to_string());
+        }
+        Ok(())
+    }
+
+    fn method(&self) -> Result<Method, String> {
+        match self.method.to_uppercase().as_str() {
+            "GET" => Ok(Method::GET),
+            "POST" => Ok(Method::POST),
+            "PUT" => Ok(Method::PUT),
+            "PATCH" => Ok(Method::PATCH),
+            "DELETE" => Ok(Method::DELETE),
+            other => Err(format!("Méthode HTTP non prise en charge: {}", other)),
+        }
+    }
+
+    /// Outil exécutant ce connecteur
+    pub fn to_tool(&self) -> Tool {
+        let requires_approval = self
+            .requires_approval
+            .unwrap_or_else(|| !self.method.eq_ignore_ascii_case("GET"));
+        Tool {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            input_schema: input_schema(&self.parameters),
+            requires_approval,
+            handler: Some(Arc::new(ApiConnectorHandler {
+                connector: self.clone(),
+                client: Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+            })),
+        }
+    }
+}
+
+/// Valeur d'un modèle : argument de l'appel ou secret de l'outil
+fn lookup(name: &str, arguments: &serde_json::Value, context: &ToolContext) -> Result<String> {
+    if let Some(key) = name.strip_prefix(SECRET_PREFIX) {
+        return Ok(context.secrets.require(key)?.to_string());
+    }
+    match arguments.get(name) {
+        Some(serde_json::Value::String(value)) => Ok(value.clone()),
+        Some(serde_json::Value::Null) | None => anyhow::bail!("Paramètre '{}' manquant", name),
+        Some(value) => Ok(value.to_string()),
+    }
+}
+
+/// Remplace les modèles d'une chaîne ; `encode` encode les valeurs pour une URL
+fn render(
+    template: &str,
+    arguments: &serde_json::Value,
+    context: &ToolContext,
+    encode: bool,
+) -> Result<String> {
+    let mut rendered = String::new();
+    let mut last = 0;
+    for captures in placeholder().captures_iter(template) {
+        let whole = captures.get(0).unwrap();
+        let value = lookup(&captures[1], arguments, context)?;
+        rendered.push_str(&template[last..whole.start()]);
+        rendered.push_str(&if encode { percent_encode(&value) } else { value });
+        last = whole.end();
+    }
+    rendered.push_str(&template[last..]);
+    Ok(rendered)
+}
+
+/// Remplace les modèles d'un corps JSON
+fn render_json(
+    template: &serde_json::Value,
+    arguments: &serde_json::Value,
+    context: &ToolContext,
+) -> Result<serde_json::Value> {
+    Ok(match template {
+        serde_json::Value::String(text) => {
+            let whole = placeholder()
+                .captures(text)
+                .filter(|c| c.get(0).unwrap().as_str() == text);
+            match whole {
+                // Argument seul : sa valeur JSON est gardée (nombre, booléen, objet)
+                Some(captures) if !captures[1].starts_with(SECRET_PREFIX) => arguments
+                    .get(&captures[1])
+                    .cloned()
+                    .filter(|v| !v.is_null())
+                    .ok_or_else(|| anyhow::anyhow!("Paramètre '{}' manquant", &captures[1]))?,
+                _ => serde_json::Value::String(render(text, arguments, context, false)?),
+            }
+        }
+        serde_json::Value::Array(items) => serde_json::Value::Array(
+            items
+                .iter()
+                .map(|item| render_json(item, arguments, context))
+                .collect::<Result<_>>()?,
+        ),
+        serde_json::Value::Object(fields) => serde_json::Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), render_json(value, arguments, context)?)))
+                .collect::<Result<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Extrait une partie d'un document JSON : `$.data.items[0].name` ou `data.items.0.name`
+pub fn extract<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let path = path.trim().trim_start_matches('$').replace('[', ".").replace(']', "");
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |current, segment| match current {
+            serde_json::Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            serde_json::Value::Object(fields) => fields.get(segment.trim_matches(|c| c == '"' || c == '\'')),
+            _ => None,
+        })
+}
+
+fn truncate_response(text: String) -> String {
+    if text.chars().count() <= MAX_RESPONSE_CHARS {
+        return text;
+    }
+    let truncated: String = text.chars().take(MAX_RESPONSE_CHARS).collect();
+    format!("{}\n[... réponse tronquée]", truncated)
+}
+
+/// Handler d'un connecteur
+struct ApiConnectorHandler {
+    connector: ApiConnector,
+    client: Client,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ApiConnectorHandler {
+    async fn execute(&self, arguments: serde_json::Value, context: &ToolContext) -> Result<String> {
+        let connector = &self.connector;
+        let method = connector.method().map_err(anyhow::Error::msg)?;
+        let url = format!(
+            "{}{}",
+            connector.base_url.trim_end_matches('/'),
+            render(&connector.path, &arguments, context, true)?
+        );
+
+        let query = connector
+            .query
+            .iter()
+            .map(|(key, template)| Ok((key.clone(), render(template, &arguments, context, false)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut request = self.client.request(method, &url).query(&query);
+        for (name, template) in &connector.headers {
+            request = request.header(name, render(template, &arguments, context, false)?);
+        }
+        if let Some(body) = &connector.body {
+            request = request.json(&render_json(body, &arguments, context)?);
+        }
+
+        let response = request.send().await
+            .with_context(|| format!("Échec de la requête {} {}", connector.method, url))?;
+        let status = response.status();
+        let text = response.text().await.context("Réponse illisible")?;
+        if !status.is_success() {
+            anyhow::bail!("{} {} a répondu {}: {}", connector.method, url, status, truncate_response(text));
+        }
+
+        let Some(path) = &connector.result_path else {
+            return Ok(truncate_response(text));
+        };
+        let json: serde_json::Value = serde_json::from_str(&text)
+            .context("La réponse n'est pas du JSON (chemin du résultat configuré)")?;
+        let result = extract(&json, path)
+            .ok_or_else(|| anyhow::anyhow!("Chemin introuvable dans la réponse: {}", path))?;
+        Ok(truncate_response(match result {
+            serde_json::Value::String(text) => text.clone(),
+            other => serde_json::to_string_pretty(other)?,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::ToolSecrets;
+    use axum::{extract::Path, http::HeaderMap, routing::get, Json, Router};
+    use std::collections::HashMap;
+
+    fn connector(base_url: &str) -> ApiConnector {
+        serde_json::from_value(serde_json::json!({
+            "name": "ticket",
+            "description": "Fetch a ticket",
+            "base_url": base_url,
+            "path": "/tickets/{{id}}",
+            "headers": {"Authorization": "Bearer {{secret.token}}"},
+            "parameters": [{"name": "id", "required": true}],
+            "result_path": "$.fields.title"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_templates_and_extraction() {
+        let context = ToolContext {
+            secrets: ToolSecrets::new(HashMap::from([("token".to_string(), "t0k".to_string())])),
+            root: None,
+            scope: String::new(),
+        };
+        let arguments = serde_json::json!({"id": "a b/1", "count": 3});
+        assert_eq!(render("/tickets/{{ id }}", &arguments, &context, true).unwrap(), "/tickets/a%20b%2F1");
+        assert_eq!(render("Bearer {{secret.token}}", &arguments, &context, false).unwrap(), "Bearer t0k");
+        assert!(render("{{missing}}", &arguments, &context, false).is_err());
+
+        let body = serde_json::json!({"count": "{{count}}", "label": "#{{id}}"});
+        assert_eq!(
+            render_json(&body, &arguments, &context).unwrap(),
+            serde_json::json!({"count": 3, "label": "#a b/1"})
+        );
+
+        let document = serde_json::json!({"data": {"items": [{"name": "first"}]}});
+        assert_eq!(extract(&document, "$.data.items[0].name"), Some(&serde_json::json!("first")));
+        assert_eq!(extract(&document, "data.items.0"), Some(&serde_json::json!({"name": "first"})));
+        assert_eq!(extract(&document, "$.data.missing"), None);
+    }
+
+    #[test]
+    fn test_validate_connector() {
+        let valid = connector("https://tracker.example.com/api");
+        assert!(valid.validate().is_ok());
+        assert!(!valid.to_tool().requires_approval);
+
+        let undeclared = ApiConnector { path: "/tickets/{{other}}".to_string(), ..valid.clone() };
+        assert!(undeclared.validate().is_err());
+        assert!(ApiConnector { base_url: "file:///etc".to_string(), ..valid.clone() }.validate().is_err());
+        assert!(ApiConnector { name: "bad name".to_string(), ..valid.clone() }.validate().is_err());
+
+        let post = ApiConnector { method: "post".to_string(), ..valid };
+        assert!(post.validate().is_ok());
+        assert!(post.to_tool().requires_approval);
+    }
+
+    #[test]
+    fn test_connector_cannot_shadow_builtin() {
+        let mut registry = crate::mcp::ToolRegistry::new();
+        let ticket = connector("https://tracker.example.com/api");
+        registry.register_connector(&ticket).unwrap();
+        // Enregistrer à nouveau remplace le connecteur
+        registry.register_connector(&ticket).unwrap();
+
+        let echo = ApiConnector { name: "echo".to_string(), ..ticket };
+        assert!(registry.register_connector(&echo).is_err());
+        assert!(!registry.unregister_connector("echo"));
+        assert!(registry.list_tools().iter().any(|t| t.name == "echo"));
+        assert!(registry.unregister_connector("ticket"));
+    }
+
+    #[tokio::test]
+    async fn test_connector_request() {
+        let app = Router::new().route(
+            "/tickets/:id",
+            get(|Path(id): Path<String>, headers: HeaderMap| async move {
+                let authorized = headers.get("authorization").and_then(|v| v.to_str().ok()) == Some("Bearer t0k");
+                Json(serde_json::json!({"fields": {"title": format!("Ticket {} ({})", id, authorized)}}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let tool = connector(&format!("http://{}", address)).to_tool();
+        let context = ToolContext {
+            secrets: ToolSecrets::new(HashMap::from([("token".to_string(), "t0k".to_string())])),
+            root: None,
+            scope: String::new(),
+        };
+        let result = tool
+            .handler
+            .unwrap()
+            .execute(serde_json::json!({"id": 42}), &context)
+            .await
+            .unwrap();
+        assert_eq!(result, "Ticket 42 (true)");
+    }
+}
@@ -0,0 +1,221 @@
+/// Exécution des outils dans un conteneur éphémère (Docker ou Podman)
+///
+/// Quand un moteur de conteneurs est disponible, run_code s'exécute dans un
+/// conteneur supprimé après usage : sans réseau, sans capacités, système de
+/// fichiers en lecture seule, le programme monté en lecture seule et l'espace de
+/// travail de l'outil visible sous `/workspace` en lecture seule. Sinon l'outil
+/// revient au bac à sable natif (sous-processus limité).
+
+use super::code::{CodeLanguage, MEMORY_LIMIT_MB};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+use tracing::{info, warn};
+use ts_rs::TS;
+
+/// Moteurs essayés, dans l'ordre, en détection automatique
+const RUNTIMES: [&str; 2] = ["docker", "podman"];
+
+/// Dossier du programme et de l'espace de travail dans le conteneur
+const CODE_DIR: &str = "/code";
+const WORKSPACE_DIR: &str = "/workspace";
+
+/// Nombre maximal de processus dans le conteneur
+const PIDS_LIMIT: u32 = 64;
+
+/// Réglages de l'exécution en conteneur
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ContainerSettings {
+    pub enabled: bool,
+    /// Exécutable du moteur (`docker`, `podman`...) ; détecté si absent
+    #[serde(default)]
+    pub runtime: Option<String>,
+    #[serde(default = "default_python_image")]
+    pub python_image: String,
+    #[serde(default = "default_node_image")]
+    pub node_image: String,
+}
+
+fn default_python_image() -> String {
+    "python:3.12-slim".to_string()
+}
+
+fn default_node_image() -> String {
+    "node:20-slim".to_string()
+}
+
+impl Default for ContainerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            runtime: None,
+            python_image: default_python_image(),
+            node_image: default_node_image(),
+        }
+    }
+}
+
+/// Lance des programmes dans des conteneurs éphémères
+#[derive(Debug, Clone)]
+pub struct ContainerRunner {
+    runtime: String,
+    settings: ContainerSettings,
+}
+
+impl ContainerRunner {
+    /// Moteur utilisable pour ces réglages, `None` (avec un avertissement) sinon
+    pub async fn detect(settings: &ContainerSettings) -> Option<Self> {
+        if !settings.enabled {
+            return None;
+        }
+        let candidates: Vec<&str> = match settings.runtime.as_deref() {
+            Some(runtime) => vec![runtime],
+            None => RUNTIMES.to_vec(),
+        };
+        for runtime in candidates {
+            if runtime_available(runtime).await {
+                info!("Exécution des outils en conteneur via {}", runtime);
+                return Some(Self { runtime: runtime.to_string(), settings: settings.clone() });
+            }
+        }
+        warn!("Aucun moteur de conteneurs disponible : run_code utilise le bac à sable natif");
+        None
+    }
+
+    pub fn runtime(&self) -> &str {
+        &self.runtime
+    }
+
+    pub fn image(&self, language: CodeLanguage) -> &str {
+        match language {
+            CodeLanguage::Python => &self.settings.python_image,
+            CodeLanguage::JavaScript => &self.settings.node_image,
+        }
+    }
+
+    /// Commande `run` exécutant le script de `dir` ; `name` permet d'arrêter
+    /// le conteneur à l'expiration (`--rm` le supprime ensuite)
+    pub fn command(
+        &self,
+        language: CodeLanguage,
+        dir: &Path,
+        workspace: Option<&Path>,
+        name: &str,
+    ) -> Command {
+        let mut command = Command::new(&self.runtime);
+        command
+            .args(["run", "--rm", "--name", name])
+            .args(["--network", "none", "--cap-drop", "ALL"])
+            .args(["--security-opt", "no-new-privileges"])
+            .args(["--read-only", "--tmpfs", "/tmp"])
+            .arg(format!("--memory={}m", MEMORY_LIMIT_MB))
+            .arg(format!("--pids-limit={}", PIDS_LIMIT))
+            .args(["--cpus", "1"])
+            .args(["--env", "HOME=/tmp", "--workdir", CODE_DIR])
+            .arg("--volume")
+            .arg(format!("{}:{}:ro", dir.display(), CODE_DIR));
+        if let Some(workspace) = workspace {
+            command
+                .arg("--volume")
+                .arg(format!("{}:{}:ro", workspace.display(), WORKSPACE_DIR));
+        }
+        command
+            .arg(self.image(language))
+            .args(language.container_command(&format!("{}/{}", CODE_DIR, language.file_name())));
+        command
+    }
+
+    /// Arrête un conteneur resté actif (expiration)
+    pub async fn kill(&self, name: &str) {
+        let killed = Command::new(&self.runtime)
+            .args(["kill", name])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await;
+        if let Err(e) = killed {
+            warn!("Impossible d'arrêter le conteneur {}: {}", name, e);
+        }
+    }
+
+    /// Garde qui arrête le conteneur `name` si l'exécution est abandonnée
+    pub fn guard(&self, name: &str) -> ContainerGuard {
+        ContainerGuard { runner: self.clone(), name: name.to_string(), finished: false }
+    }
+}
+
+/// Arrête le conteneur quand elle est libérée avant `finish`
+///
+/// Tuer le client `run` ne suffit pas : le conteneur lui survit. La garde couvre
+/// l'expiration de run_code comme l'annulation de l'appel par la durée maximale
+/// de l'outil, qui abandonne l'exécution sans passer par l'expiration.
+pub struct ContainerGuard {
+    runner: ContainerRunner,
+    name: String,
+    finished: bool,
+}
+
+impl ContainerGuard {
+    /// Le conteneur s'est arrêté de lui-même
+    pub fn finish(mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for ContainerGuard {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let (runner, name) = (self.runner.clone(), std::mem::take(&mut self.name));
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move { runner.kill(&name).await });
+            }
+            Err(_) => warn!("Conteneur {} laissé actif: aucun runtime pour l'arrêter", name),
+        }
+    }
+}
+
+async fn runtime_available(runtime: &str) -> bool {
+    Command::new(runtime)
+        .arg("version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_container_command() {
+        let settings = ContainerSettings { enabled: true, ..Default::default() };
+        let runner = ContainerRunner { runtime: "podman".to_string(), settings };
+        let command = runner.command(
+            CodeLanguage::Python,
+            Path::new("/tmp/run"),
+            Some(Path::new("/home/user/project")),
+            "agents-rs-test",
+        );
+        let args: Vec<String> = command
+            .as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(command.as_std().get_program(), "podman");
+        assert!(args.windows(2).any(|pair| pair == ["--network", "none"]));
+        assert!(args.contains(&"/tmp/run:/code:ro".to_string()));
+        assert!(args.contains(&"/home/user/project:/workspace:ro".to_string()));
+        assert_eq!(args[args.len() - 3..], ["python:3.12-slim", "python3", "/code/main.py"]);
+
+        // Désactivé : aucune détection, bac à sable natif
+        assert!(ContainerRunner::detect(&ContainerSettings::default()).await.is_none());
+    }
+}
@@ -0,0 +1,213 @@
+/// Conversations exposées aux clients MCP : outils et ressources
+///
+/// `create_conversation` et `append_message` écrivent dans l'historique de
+/// l'application, chaque conversation est lisible comme ressource
+/// `conversation://{id}`. Tout passe par le gestionnaire de contexte partagé :
+/// l'interface voit les messages ajoutés et le chiffrement s'applique.
+
+use super::protocol::{ResourceContents, ResourceDescription, ResourceTemplate};
+use super::tools::{Tool, ToolContext, ToolHandler};
+use crate::context::{ContextManager, Message, MessageRole};
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Schéma des URI de conversation
+pub const CONVERSATION_SCHEME: &str = "conversation://";
+
+const MIME_TYPE: &str = "application/json";
+
+pub fn conversation_uri(id: &str) -> String {
+    format!("{}{}", CONVERSATION_SCHEME, id)
+}
+
+/// Une ressource par conversation (les plus récentes)
+pub async fn list_conversation_resources(manager: &ContextManager) -> Result<Vec<ResourceDescription>> {
+    Ok(manager
+        .list_sessions()
+        .await?
+        .into_iter()
+        .map(|session| ResourceDescription {
+            uri: conversation_uri(&session.id),
+            name: session.title,
+            description: None,
+            mime_type: MIME_TYPE.to_string(),
+        })
+        .collect())
+}
+
+pub fn conversation_template() -> ResourceTemplate {
+    ResourceTemplate {
+        uri_template: format!("{}{{id}}", CONVERSATION_SCHEME),
+        name: "Conversation".to_string(),
+        description: "Conversation et ses messages, en JSON".to_string(),
+        mime_type: MIME_TYPE.to_string(),
+    }
+}
+
+/// Contenu d'une ressource `conversation://{id}`
+pub async fn read_conversation_resource(manager: &ContextManager, uri: &str) -> Result<ResourceContents> {
+    let id = uri
+        .strip_prefix(CONVERSATION_SCHEME)
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("URI de ressource inconnue: {}", uri))?;
+    let session = manager.get_session(id).await?;
+    Ok(ResourceContents {
+        uri: uri.to_string(),
+        mime_type: MIME_TYPE.to_string(),
+        text: serde_json::to_string_pretty(&session)?,
+    })
+}
+
+fn string_arg<'a>(arguments: &'a serde_json::Value, name: &str) -> Result<&'a str> {
+    arguments
+        .get(name)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Paramètre '{}' manquant", name))
+}
+
+/// Handler de l'outil create_conversation
+pub struct CreateConversationHandler {
+    manager: Arc<RwLock<ContextManager>>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for CreateConversationHandler {
+    async fn execute(&self, arguments: serde_json::Value, _context: &ToolContext) -> Result<String> {
+        let title = string_arg(&arguments, "title")?;
+        let manager = self.manager.read().await;
+        // La conversation ne devient pas la session active de l'interface
+        let id = manager.create_detached_session(title.to_string(), None).await?;
+        if let Some(system) = arguments.get("system").and_then(|v| v.as_str()) {
+            manager.add_message(&id, Message::system(system.to_string())).await?;
+        }
+        Ok(serde_json::json!({ "id": id, "uri": conversation_uri(&id) }).to_string())
+    }
+}
+
+/// Handler de l'outil append_message
+pub struct AppendMessageHandler {
+    manager: Arc<RwLock<ContextManager>>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for AppendMessageHandler {
+    async fn execute(&self, arguments: serde_json::Value, _context: &ToolContext) -> Result<String> {
+        let id = string_arg(&arguments, "conversation_id")?;
+        let role: MessageRole = serde_json::from_value(serde_json::json!(string_arg(&arguments, "role")?))
+            .context("Rôle invalide (system, user, assistant ou tool)")?;
+        let content = string_arg(&arguments, "content")?;
+
+        let manager = self.manager.read().await;
+        // Vérifie que la conversation existe avant d'écrire
+        manager.get_session(id).await?;
+        manager.add_message(id, Message::new(role, content.to_string())).await?;
+        Ok(format!("Message ajouté à la conversation {}", id))
+    }
+}
+
+/// Outils d'écriture dans l'historique des conversations
+pub fn conversation_tools(manager: Arc<RwLock<ContextManager>>) -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "create_conversation".to_string(),
+            description: "Crée une conversation et renvoie son identifiant".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "Titre de la conversation"
+                    },
+                    "system": {
+                        "type": "string",
+                        "description": "Prompt système (optionnel)"
+                    }
+                },
+                "required": ["title"]
+            }),
+            // Écrit dans l'historique de l'utilisateur : confirmation obligatoire
+            requires_approval: true,
+            handler: Some(Arc::new(CreateConversationHandler { manager: Arc::clone(&manager) })),
+        },
+        Tool {
+            name: "append_message".to_string(),
+            description: "Ajoute un message à une conversation existante".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "conversation_id": {
+                        "type": "string",
+                        "description": "Identifiant de la conversation"
+                    },
+                    "role": {
+                        "type": "string",
+                        "enum": ["system", "user", "assistant", "tool"],
+                        "description": "Auteur du message"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "Texte du message"
+                    }
+                },
+                "required": ["conversation_id", "role", "content"]
+            }),
+            requires_approval: true,
+            handler: Some(Arc::new(AppendMessageHandler { manager })),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{ConversationRepository, Database};
+    use crate::mcp::{ToolApprovals, ToolRegistry};
+
+    #[tokio::test]
+    async fn test_conversation_tools_and_resource() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let manager = Arc::new(RwLock::new(ContextManager::new(
+            ConversationRepository::new(db.pool().clone()),
+            "test-model".to_string(),
+        )));
+        // Les outils d'écriture demandent une confirmation, accordée ici
+        let approvals = ToolApprovals::new();
+        let mut requests = approvals.subscribe();
+        let mut registry = ToolRegistry::new().with_approvals(approvals.clone());
+        tokio::spawn(async move {
+            while let Ok(request) = requests.recv().await {
+                approvals.respond(&request.call_id, true, false).unwrap();
+            }
+        });
+        for tool in conversation_tools(Arc::clone(&manager)) {
+            assert!(tool.requires_approval);
+            registry.register_tool(tool).unwrap();
+        }
+
+        let created = registry
+            .execute_tool("create_conversation", serde_json::json!({"title": "Orchestration"}))
+            .await
+            .unwrap();
+        let created: serde_json::Value = serde_json::from_str(&created).unwrap();
+        let id = created["id"].as_str().unwrap();
+
+        let append = serde_json::json!({"conversation_id": id, "role": "user", "content": "Bonjour"});
+        registry.execute_tool("append_message", append).await.unwrap();
+        let invalid = serde_json::json!({"conversation_id": id, "role": "robot", "content": "Bip"});
+        assert!(registry.execute_tool("append_message", invalid).await.is_err());
+        let unknown = serde_json::json!({"conversation_id": "missing", "role": "user", "content": "?"});
+        assert!(registry.execute_tool("append_message", unknown).await.is_err());
+
+        let manager = manager.read().await;
+        let resources = list_conversation_resources(&manager).await.unwrap();
+        assert_eq!(resources[0].uri, created["uri"].as_str().unwrap());
+
+        let contents = read_conversation_resource(&manager, &resources[0].uri).await.unwrap();
+        let session: serde_json::Value = serde_json::from_str(&contents.text).unwrap();
+        assert_eq!(session["title"], "Orchestration");
+        assert_eq!(session["messages"][0]["content"], "Bonjour");
+        assert!(read_conversation_resource(&manager, "file:///etc/passwd").await.is_err());
+    }
+}
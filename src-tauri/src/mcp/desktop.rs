@@ -0,0 +1,287 @@
+/// Outils d'accès au bureau : presse-papiers et capture d'écran
+///
+/// Les outils s'appuient sur les utilitaires du système (pbcopy/pbpaste et
+/// screencapture sur macOS, PowerShell sur Windows, wl-clipboard, xclip, grim,
+/// gnome-screenshot... sur Linux). Le premier utilitaire disponible est utilisé.
+/// Ils exposent ce que l'utilisateur a à l'écran : tous demandent une confirmation.
+
+use super::tools::{Tool, ToolContext, ToolHandler};
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::debug;
+
+/// Taille maximale du texte lu dans le presse-papiers
+const MAX_CLIPBOARD_CHARS: usize = 100_000;
+
+/// Un utilitaire candidat : programme et arguments
+type Candidate = (&'static str, Vec<String>);
+
+/// Exécute le premier utilitaire installé et renvoie sa sortie standard
+///
+/// Un utilitaire absent est ignoré ; un utilitaire présent qui échoue arrête la
+/// recherche avec son message d'erreur.
+async fn run_first(candidates: Vec<Candidate>, input: Option<&str>) -> Result<Vec<u8>> {
+    let names: Vec<&str> = candidates.iter().map(|(program, _)| *program).collect();
+
+    for (program, args) in &candidates {
+        let child = Command::new(program)
+            .args(args)
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("{} introuvable, essai suivant", program);
+                continue;
+            }
+            Err(e) => return Err(e).with_context(|| format!("Échec du lancement de {}", program)),
+        };
+
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input.as_bytes()).await
+                .with_context(|| format!("Échec de l'écriture vers {}", program))?;
+        }
+
+        let output = child.wait_with_output().await
+            .with_context(|| format!("Échec de l'exécution de {}", program))?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "{} a échoué: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        return Ok(output.stdout);
+    }
+
+    anyhow::bail!("Aucun utilitaire disponible (essayés: {})", names.join(", "))
+}
+
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+fn is_wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+fn read_clipboard_candidates() -> Vec<Candidate> {
+    if cfg!(target_os = "macos") {
+        vec![("pbpaste", vec![])]
+    } else if cfg!(windows) {
+        vec![("powershell", args(&["-NoProfile", "-Command", "Get-Clipboard -Raw"]))]
+    } else {
+        let mut candidates = vec![
+            ("xclip", args(&["-selection", "clipboard", "-o"])),
+            ("xsel", args(&["--clipboard", "--output"])),
+        ];
+        if is_wayland() {
+            candidates.insert(0, ("wl-paste", args(&["--no-newline"])));
+        }
+        candidates
+    }
+}
+
+fn write_clipboard_candidates() -> Vec<Candidate> {
+    if cfg!(target_os = "macos") {
+        vec![("pbcopy", vec![])]
+    } else if cfg!(windows) {
+        vec![(
+            "powershell",
+            args(&["-NoProfile", "-Command", "Set-Clipboard -Value ([Console]::In.ReadToEnd())"]),
+        )]
+    } else {
+        let mut candidates = vec![
+            ("xclip", args(&["-selection", "clipboard", "-i"])),
+            ("xsel", args(&["--clipboard", "--input"])),
+        ];
+        if is_wayland() {
+            candidates.insert(0, ("wl-copy", vec![]));
+        }
+        candidates
+    }
+}
+
+fn screenshot_candidates(path: &str) -> Vec<Candidate> {
+    if cfg!(target_os = "macos") {
+        vec![("screencapture", args(&["-x", path]))]
+    } else if cfg!(windows) {
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms,System.Drawing; \
+             $b = [System.Windows.Forms.SystemInformation]::VirtualScreen; \
+             $bmp = New-Object System.Drawing.Bitmap $b.Width, $b.Height; \
+             $g = [System.Drawing.Graphics]::FromImage($bmp); \
+             $g.CopyFromScreen($b.Left, $b.Top, 0, 0, $bmp.Size); \
+             $bmp.Save('{}', [System.Drawing.Imaging.ImageFormat]::Png)",
+            path.replace('\'', "''")
+        );
+        vec![("powershell", vec!["-NoProfile".to_string(), "-Command".to_string(), script])]
+    } else {
+        let mut candidates = vec![
+            ("gnome-screenshot", args(&["-f", path])),
+            ("spectacle", args(&["-b", "-n", "-o", path])),
+            ("scrot", args(&["-o", path])),
+            ("import", args(&["-window", "root", path])),
+        ];
+        if is_wayland() {
+            candidates.insert(0, ("grim", args(&[path])));
+        }
+        candidates
+    }
+}
+
+/// Outil read_clipboard : texte du presse-papiers
+pub struct ReadClipboardHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for ReadClipboardHandler {
+    async fn execute(&self, _arguments: serde_json::Value, _context: &ToolContext) -> Result<String> {
+        let output = run_first(read_clipboard_candidates(), None).await
+            .context("Impossible de lire le presse-papiers")?;
+        let text = String::from_utf8_lossy(&output);
+
+        if text.chars().count() > MAX_CLIPBOARD_CHARS {
+            let truncated: String = text.chars().take(MAX_CLIPBOARD_CHARS).collect();
+            return Ok(format!("{}\n[... presse-papiers tronqué]", truncated));
+        }
+        Ok(text.into_owned())
+    }
+}
+
+/// Outil write_clipboard : remplace le contenu du presse-papiers
+pub struct WriteClipboardHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for WriteClipboardHandler {
+    async fn execute(&self, arguments: serde_json::Value, _context: &ToolContext) -> Result<String> {
+        let text = arguments
+            .get("text")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Paramètre 'text' manquant"))?;
+
+        run_first(write_clipboard_candidates(), Some(text)).await
+            .context("Impossible d'écrire dans le presse-papiers")?;
+        Ok(format!("{} caractères copiés dans le presse-papiers", text.chars().count()))
+    }
+}
+
+/// Outil capture_screenshot : capture de l'écran dans un fichier PNG temporaire
+pub struct CaptureScreenshotHandler {
+    directory: PathBuf,
+}
+
+impl CaptureScreenshotHandler {
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for CaptureScreenshotHandler {
+    async fn execute(&self, _arguments: serde_json::Value, _context: &ToolContext) -> Result<String> {
+        tokio::fs::create_dir_all(&self.directory).await
+            .context("Impossible de créer le dossier des captures")?;
+        let path = self.directory.join(format!(
+            "screenshot-{}.png",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S%3f")
+        ));
+        let path_str = path.to_string_lossy().to_string();
+
+        run_first(screenshot_candidates(&path_str), None).await
+            .context("Impossible de capturer l'écran")?;
+        if !path.exists() {
+            anyhow::bail!("La capture n'a pas produit de fichier");
+        }
+        Ok(path_str)
+    }
+}
+
+/// Dossier temporaire des captures d'écran
+pub fn default_screenshot_directory() -> PathBuf {
+    std::env::temp_dir().join("agents-rs-screenshots")
+}
+
+pub fn create_read_clipboard_tool() -> Tool {
+    Tool {
+        name: "read_clipboard".to_string(),
+        description: "Lit le texte actuellement copié dans le presse-papiers".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {}
+        }),
+        requires_approval: true,
+        handler: Some(Arc::new(ReadClipboardHandler)),
+    }
+}
+
+pub fn create_write_clipboard_tool() -> Tool {
+    Tool {
+        name: "write_clipboard".to_string(),
+        description: "Copie un texte dans le presse-papiers".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "text": {
+                    "type": "string",
+                    "description": "Texte à copier"
+                }
+            },
+            "required": ["text"]
+        }),
+        requires_approval: true,
+        handler: Some(Arc::new(WriteClipboardHandler)),
+    }
+}
+
+pub fn create_capture_screenshot_tool() -> Tool {
+    Tool {
+        name: "capture_screenshot".to_string(),
+        description: "Capture l'écran dans un fichier PNG et renvoie son chemin".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {}
+        }),
+        requires_approval: true,
+        handler: Some(Arc::new(CaptureScreenshotHandler::new(default_screenshot_directory()))),
+    }
+}
+
+/// Outils du bureau, à enregistrer dans le registre de l'application
+pub fn desktop_tools() -> Vec<Tool> {
+    vec![
+        create_read_clipboard_tool(),
+        create_write_clipboard_tool(),
+        create_capture_screenshot_tool(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desktop_tools_require_approval() {
+        assert!(desktop_tools().iter().all(|tool| tool.requires_approval));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_first_skips_missing_programs() {
+        let candidates = vec![
+            ("agents-rs-missing-program", vec![]),
+            ("cat", vec![]),
+        ];
+        let output = run_first(candidates, Some("copié")).await.unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "copié");
+
+        assert!(run_first(vec![("agents-rs-missing-program", vec![])], None).await.is_err());
+        assert!(run_first(vec![("false", vec![])], None).await.is_err());
+    }
+}
@@ -0,0 +1,175 @@
+/// OpenAI-compatible `/v1/embeddings` endpoint
+///
+/// Served next to the MCP endpoint so local RAG stacks (LlamaIndex, LangChain...)
+/// can use the loaded model as their embedding model by pointing their OpenAI
+/// client at `http://127.0.0.1:<port>/v1`. Only float vectors are returned.
+
+use crate::llm::{EngineHandle, GenerationPriority, GenerationScheduler};
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Longest batch accepted in one request
+const MAX_INPUTS: usize = 2048;
+
+/// `input` is a single string or a list of strings
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum EmbeddingInput {
+    Single(String),
+    Batch(Vec<String>),
+}
+
+impl EmbeddingInput {
+    fn into_texts(self) -> Vec<String> {
+        match self {
+            EmbeddingInput::Single(text) => vec![text],
+            EmbeddingInput::Batch(texts) => texts,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmbeddingRequest {
+    pub input: EmbeddingInput,
+    /// Ignored: the loaded model is always used
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub encoding_format: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EmbeddingData {
+    pub object: &'static str,
+    pub index: usize,
+    pub embedding: Vec<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EmbeddingUsage {
+    pub prompt_tokens: usize,
+    pub total_tokens: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EmbeddingResponse {
+    pub object: &'static str,
+    pub data: Vec<EmbeddingData>,
+    pub model: String,
+    pub usage: EmbeddingUsage,
+}
+
+impl EmbeddingResponse {
+    pub fn new(model: String, embeddings: Vec<Vec<f32>>, prompt_tokens: usize) -> Self {
+        let data = embeddings
+            .into_iter()
+            .enumerate()
+            .map(|(index, embedding)| EmbeddingData { object: "embedding", index, embedding })
+            .collect();
+        Self {
+            object: "list",
+            data,
+            model,
+            usage: EmbeddingUsage { prompt_tokens, total_tokens: prompt_tokens },
+        }
+    }
+}
+
+/// Error body in the OpenAI format
+fn error(status: StatusCode, message: impl Into<String>) -> axum::response::Response {
+    let kind = if status.is_client_error() { "invalid_request_error" } else { "server_error" };
+    let body = serde_json::json!({ "error": { "message": message.into(), "type": kind } });
+    (status, Json(body)).into_response()
+}
+
+/// Texts to embed, or the reason the request is rejected
+fn validate(request: EmbeddingRequest) -> Result<Vec<String>, String> {
+    if let Some(format) = request.encoding_format.as_deref().filter(|f| *f != "float") {
+        return Err(format!("Unsupported encoding_format: {} (only float)", format));
+    }
+    let texts = request.input.into_texts();
+    if texts.is_empty() {
+        return Err("input must not be empty".to_string());
+    }
+    if texts.len() > MAX_INPUTS {
+        return Err(format!("input has more than {} items", MAX_INPUTS));
+    }
+    if texts.iter().any(|text| text.is_empty()) {
+        return Err("input items must not be empty strings".to_string());
+    }
+    Ok(texts)
+}
+
+/// Name reported as `model`: the file name of the loaded model
+fn model_name(engine: &EngineHandle) -> String {
+    let path = engine.config().model.model_path;
+    Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or(path)
+}
+
+pub async fn handle_embeddings(
+    engine: &EngineHandle,
+    scheduler: &GenerationScheduler,
+    request: EmbeddingRequest,
+) -> axum::response::Response {
+    let texts = match validate(request) {
+        Ok(texts) => texts,
+        Err(message) => return error(StatusCode::BAD_REQUEST, message),
+    };
+    if !engine.is_loaded() {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "No model loaded in agents-rs");
+    }
+
+    debug!("Embedding {} texts for an HTTP client", texts.len());
+    // Queued with the application's own generations, which share the engine
+    let _permit = match scheduler.acquire(None, GenerationPriority::Normal).await {
+        Ok(permit) => permit,
+        Err(e) => return error(StatusCode::SERVICE_UNAVAILABLE, format!("{:#}", e)),
+    };
+    let prompt_tokens = match engine.count_tokens(texts.clone()).await {
+        Ok(counts) => counts.iter().sum(),
+        Err(e) => {
+            warn!("Token count for /v1/embeddings failed: {:#}", e);
+            0
+        }
+    };
+    match engine.embed(texts).await {
+        Ok(embeddings) => Json(EmbeddingResponse::new(model_name(engine), embeddings, prompt_tokens)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(json: serde_json::Value) -> EmbeddingRequest {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_embedding_request() {
+        let single = request(serde_json::json!({"input": "Bonjour", "model": "text-embedding-3-small"}));
+        assert_eq!(validate(single).unwrap(), vec!["Bonjour"]);
+
+        let batch = request(serde_json::json!({"input": ["a", "b"], "encoding_format": "float"}));
+        assert_eq!(validate(batch).unwrap(), vec!["a", "b"]);
+
+        assert!(validate(request(serde_json::json!({"input": []}))).is_err());
+        assert!(validate(request(serde_json::json!({"input": ["a", ""]}))).is_err());
+        assert!(validate(request(serde_json::json!({"input": "a", "encoding_format": "base64"}))).is_err());
+    }
+
+    #[test]
+    fn test_embedding_response() {
+        let response = EmbeddingResponse::new("nomic.gguf".to_string(), vec![vec![0.5, 0.5], vec![1.0, 0.0]], 7);
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["object"], "list");
+        assert_eq!(json["data"][1], serde_json::json!({"object": "embedding", "index": 1, "embedding": [1.0, 0.0]}));
+        assert_eq!(json["usage"], serde_json::json!({"prompt_tokens": 7, "total_tokens": 7}));
+    }
+}
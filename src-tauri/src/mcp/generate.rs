@@ -0,0 +1,93 @@
+/// Outil generate_text : génération avec le modèle local
+///
+/// Les clients MCP connectés au serveur local peuvent ainsi utiliser le modèle
+/// chargé dans l'application via `tools/call`. Les requêtes passent par la file
+/// des générations, comme celles de l'interface.
+
+use super::tools::{Tool, ToolContext, ToolHandler};
+use crate::llm::{ChatMessage, GenerationPriority, GenerationScheduler, LLMBackend};
+use anyhow::Result;
+use std::sync::Arc;
+
+/// Handler de l'outil generate_text
+pub struct GenerateTextHandler {
+    backend: Arc<dyn LLMBackend>,
+    scheduler: Option<GenerationScheduler>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for GenerateTextHandler {
+    async fn execute(&self, arguments: serde_json::Value, _context: &ToolContext) -> Result<String> {
+        let prompt = arguments
+            .get("prompt")
+            .and_then(|v| v.as_str())
+            .filter(|p| !p.trim().is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Paramètre 'prompt' manquant"))?;
+
+        let mut messages = Vec::new();
+        if let Some(system) = arguments.get("system").and_then(|v| v.as_str()) {
+            messages.push(ChatMessage::system(system));
+        }
+        messages.push(ChatMessage::user(prompt));
+
+        if !self.backend.is_ready().await {
+            anyhow::bail!("Aucun modèle chargé");
+        }
+        let _permit = match &self.scheduler {
+            Some(scheduler) => Some(scheduler.acquire(None, GenerationPriority::Normal).await?),
+            None => None,
+        };
+        let response = self.backend.chat(&messages).await?;
+        Ok(response.text)
+    }
+}
+
+pub fn create_generate_text_tool(
+    backend: Arc<dyn LLMBackend>,
+    scheduler: Option<GenerationScheduler>,
+) -> Tool {
+    Tool {
+        name: "generate_text".to_string(),
+        description: "Génère une réponse avec le modèle chargé localement".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "prompt": {
+                    "type": "string",
+                    "description": "Message envoyé au modèle"
+                },
+                "system": {
+                    "type": "string",
+                    "description": "Prompt système (optionnel)"
+                }
+            },
+            "required": ["prompt"]
+        }),
+        requires_approval: false,
+        handler: Some(Arc::new(GenerateTextHandler { backend, scheduler })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::ToolRegistry;
+    use crate::tasks::testing::ScriptedBackend;
+
+    #[tokio::test]
+    async fn test_generate_text() {
+        let mut registry = ToolRegistry::new();
+        let scheduler = GenerationScheduler::new(1);
+        registry
+            .register_tool(create_generate_text_tool(ScriptedBackend::new(&["Bonjour !"]), Some(scheduler)))
+            .unwrap();
+
+        let result = registry
+            .execute_tool("generate_text", serde_json::json!({"prompt": "Salut", "system": "Sois bref"}))
+            .await
+            .unwrap();
+        assert_eq!(result, "Bonjour !");
+
+        assert!(registry.execute_tool("generate_text", serde_json::json!({})).await.is_err());
+    }
+}
@@ -0,0 +1,296 @@
+/// Outils Git : état, diff, historique et commit d'un dépôt local
+///
+/// Les outils appellent le binaire `git` du système dans le dossier du dépôt
+/// (sans pager ni invite interactive). Seul `git_commit` modifie le dépôt : il
+/// demande une confirmation.
+
+use super::tools::{Tool, ToolContext, ToolHandler};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::process::Command;
+
+/// Taille maximale de la sortie renvoyée au modèle
+const MAX_OUTPUT_CHARS: usize = 20_000;
+
+/// Nombre de commits par défaut (et maximum) de `git_log`
+const DEFAULT_LOG_LIMIT: u64 = 10;
+const MAX_LOG_LIMIT: u64 = 100;
+
+/// Exécute git dans le dépôt et renvoie sa sortie standard
+async fn run_git(repository: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("--no-pager")
+        // Un dépôt non fiable ne doit pas pouvoir lancer de programme via sa configuration
+        .args(["-c", "core.fsmonitor=false", "-c", "core.hooksPath=/dev/null"])
+        .args(args)
+        .current_dir(repository)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("Impossible de lancer git (est-il installé ?)")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} a échoué: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(truncate_output(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+fn truncate_output(output: String) -> String {
+    if output.chars().count() <= MAX_OUTPUT_CHARS {
+        return output;
+    }
+    let truncated: String = output.chars().take(MAX_OUTPUT_CHARS).collect();
+    format!("{}\n[... sortie tronquée]", truncated)
+}
+
+/// Dossier du dépôt passé en argument (la racine de l'espace de travail par défaut)
+fn repository_arg(arguments: &serde_json::Value, context: &ToolContext) -> Result<PathBuf> {
+    let path = match arguments.get("repository").and_then(|v| v.as_str()) {
+        Some(path) => context.resolve_path(path)?,
+        None => context
+            .root
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Paramètre 'repository' manquant"))?,
+    };
+    if !path.is_dir() {
+        anyhow::bail!("Dossier introuvable: {}", path.display());
+    }
+    Ok(path)
+}
+
+/// Chemins optionnels passés en argument
+fn paths_arg(arguments: &serde_json::Value, key: &str) -> Vec<String> {
+    arguments
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(|paths| paths.iter().filter_map(|p| p.as_str()).map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Outil git_status
+pub struct GitStatusHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for GitStatusHandler {
+    async fn execute(&self, arguments: serde_json::Value, context: &ToolContext) -> Result<String> {
+        let repository = repository_arg(&arguments, context)?;
+        let status = run_git(&repository, &["status", "--short", "--branch"]).await?;
+        Ok(status)
+    }
+}
+
+/// Outil git_diff : modifications non indexées, ou indexées avec `staged`
+pub struct GitDiffHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for GitDiffHandler {
+    async fn execute(&self, arguments: serde_json::Value, context: &ToolContext) -> Result<String> {
+        let repository = repository_arg(&arguments, context)?;
+        let staged = arguments.get("staged").and_then(|v| v.as_bool()).unwrap_or(false);
+        let paths = paths_arg(&arguments, "paths");
+
+        let mut args = vec!["diff"];
+        if staged {
+            args.push("--cached");
+        }
+        args.push("--");
+        args.extend(paths.iter().map(String::as_str));
+
+        let diff = run_git(&repository, &args).await?;
+        if diff.is_empty() {
+            return Ok("Aucune modification".to_string());
+        }
+        Ok(diff)
+    }
+}
+
+/// Outil git_log : derniers commits
+pub struct GitLogHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for GitLogHandler {
+    async fn execute(&self, arguments: serde_json::Value, context: &ToolContext) -> Result<String> {
+        let repository = repository_arg(&arguments, context)?;
+        let limit = arguments
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_LOG_LIMIT)
+            .clamp(1, MAX_LOG_LIMIT);
+
+        let count = format!("-{}", limit);
+        run_git(
+            &repository,
+            &["log", &count, "--date=short", "--pretty=format:%h %ad %an: %s"],
+        )
+        .await
+    }
+}
+
+/// Outil git_commit : indexe les fichiers donnés (ou toutes les modifications) et commite
+pub struct GitCommitHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for GitCommitHandler {
+    async fn execute(&self, arguments: serde_json::Value, context: &ToolContext) -> Result<String> {
+        let repository = repository_arg(&arguments, context)?;
+        let message = arguments
+            .get("message")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Paramètre 'message' manquant"))?;
+        let files = paths_arg(&arguments, "files");
+
+        if files.is_empty() {
+            run_git(&repository, &["add", "--all"]).await?;
+        } else {
+            let mut args = vec!["add", "--"];
+            args.extend(files.iter().map(String::as_str));
+            run_git(&repository, &args).await?;
+        }
+
+        run_git(&repository, &["commit", "-m", message]).await?;
+        run_git(&repository, &["log", "-1", "--pretty=format:Commit %h: %s"]).await
+    }
+}
+
+fn repository_property() -> serde_json::Value {
+    serde_json::json!({
+        "type": "string",
+        "description": "Chemin du dépôt Git (racine de l'espace de travail par défaut)"
+    })
+}
+
+pub fn create_git_status_tool() -> Tool {
+    Tool {
+        name: "git_status".to_string(),
+        description: "Affiche la branche et les fichiers modifiés d'un dépôt Git".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "repository": repository_property()
+            }
+        }),
+        requires_approval: false,
+        handler: Some(Arc::new(GitStatusHandler)),
+    }
+}
+
+pub fn create_git_diff_tool() -> Tool {
+    Tool {
+        name: "git_diff".to_string(),
+        description: "Affiche les modifications d'un dépôt Git".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "repository": repository_property(),
+                "staged": {
+                    "type": "boolean",
+                    "description": "Modifications indexées plutôt que non indexées"
+                },
+                "paths": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Limiter le diff à ces chemins"
+                }
+            }
+        }),
+        requires_approval: false,
+        handler: Some(Arc::new(GitDiffHandler)),
+    }
+}
+
+pub fn create_git_log_tool() -> Tool {
+    Tool {
+        name: "git_log".to_string(),
+        description: "Liste les derniers commits d'un dépôt Git".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "repository": repository_property(),
+                "limit": {
+                    "type": "integer",
+                    "description": "Nombre de commits (10 par défaut, 100 au plus)"
+                }
+            }
+        }),
+        requires_approval: false,
+        handler: Some(Arc::new(GitLogHandler)),
+    }
+}
+
+pub fn create_git_commit_tool() -> Tool {
+    Tool {
+        name: "git_commit".to_string(),
+        description: "Crée un commit avec les fichiers donnés (toutes les modifications par défaut)".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "repository": repository_property(),
+                "message": {
+                    "type": "string",
+                    "description": "Message du commit"
+                },
+                "files": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Fichiers à inclure"
+                }
+            },
+            "required": ["message"]
+        }),
+        // Modifie l'historique du dépôt : confirmation obligatoire
+        requires_approval: true,
+        handler: Some(Arc::new(GitCommitHandler)),
+    }
+}
+
+/// Outils Git, à enregistrer dans le registre de l'application
+pub fn git_tools() -> Vec<Tool> {
+    vec![
+        create_git_status_tool(),
+        create_git_diff_tool(),
+        create_git_log_tool(),
+        create_git_commit_tool(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_status_diff_commit_log() {
+        let dir = std::env::temp_dir().join(format!("agents-rs-git-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        run_git(&dir, &["init", "--quiet"]).await.unwrap();
+        run_git(&dir, &["config", "user.name", "Test"]).await.unwrap();
+        run_git(&dir, &["config", "user.email", "test@example.com"]).await.unwrap();
+        std::fs::write(dir.join("notes.txt"), "bonjour\n").unwrap();
+
+        let context = ToolContext::default();
+        let repository = serde_json::json!({"repository": dir.to_string_lossy()});
+
+        let status = GitStatusHandler.execute(repository.clone(), &context).await.unwrap();
+        assert!(status.contains("?? notes.txt"));
+
+        let commit = serde_json::json!({"repository": dir.to_string_lossy(), "message": "Add notes"});
+        let result = GitCommitHandler.execute(commit, &context).await.unwrap();
+        assert!(result.ends_with("Add notes"));
+
+        std::fs::write(dir.join("notes.txt"), "bonsoir\n").unwrap();
+        let diff = GitDiffHandler.execute(repository.clone(), &context).await.unwrap();
+        assert!(diff.contains("+bonsoir"));
+
+        let log = GitLogHandler.execute(repository, &context).await.unwrap();
+        assert!(log.contains("Test: Add notes"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -3,7 +3,30 @@
 pub mod server;
 pub mod protocol;
 pub mod tools;
+pub mod secrets;
+pub mod approval;
+pub mod policy;
+pub mod schema;
+pub mod code;
+pub mod container;
+pub mod connector;
+pub mod conversations;
+pub mod desktop;
+pub mod embeddings;
+pub mod generate;
+pub mod injection;
+pub mod pipeline;
+pub mod git;
+pub mod scratchpad;
 
 pub use server::MCPServer;
-pub use protocol::{JsonRpcRequest, JsonRpcResponse, ServerInfo};
-pub use tools::{Tool, ToolHandler, ToolRegistry};
+pub use protocol::{JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, ServerInfo};
+pub use tools::{OutputSink, Tool, ToolContext, ToolHandler, ToolOutputChunk, ToolParameter, ToolRegistry};
+pub use secrets::{SecretStore, ToolSecrets};
+pub use approval::{ApprovalRequest, ToolApprovals};
+pub use injection::{InjectionFinding, InjectionRule, InjectionSettings};
+pub use policy::{ToolPolicy, ToolPolicyError};
+pub use schema::{SchemaViolation, ToolValidationError};
+pub use connector::ApiConnector;
+pub use container::{ContainerRunner, ContainerSettings};
+pub use pipeline::{PipelineStep, ToolPipeline};
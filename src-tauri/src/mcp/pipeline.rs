@@ -0,0 +1,260 @@
+/// Pipelines d'outils : enchaînements nommés exposés comme un seul outil
+///
+/// Chaque étape appelle un outil du registre (avec ses limites et sa confirmation).
+/// Ses arguments sont un modèle JSON où `{{input.nom}}` désigne un argument du
+/// pipeline, `{{previous}}` le résultat de l'étape précédente et `{{steps.id}}`
+/// celui d'une étape identifiée. Le résultat du pipeline est celui de sa dernière
+/// étape.
+
+use super::connector::placeholder;
+use super::tools::{input_schema, Tool, ToolParameter};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+/// Profondeur maximale des pipelines appelant d'autres pipelines
+pub const MAX_PIPELINE_DEPTH: usize = 4;
+
+const INPUT_PREFIX: &str = "input.";
+const STEP_PREFIX: &str = "steps.";
+const PREVIOUS: &str = "previous";
+
+/// Appel d'outil d'un pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PipelineStep {
+    /// Identifiant pour `{{steps.id}}` (facultatif)
+    #[serde(default)]
+    pub id: Option<String>,
+    pub tool: String,
+    /// Modèle des arguments de l'outil
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+/// Pipeline nommé
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ToolPipeline {
+    pub id: String,
+    /// Nom de l'outil synthétique
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub parameters: Vec<ToolParameter>,
+    pub steps: Vec<PipelineStep>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ToolPipeline {
+    pub fn new(name: String, description: String, steps: Vec<PipelineStep>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            description,
+            parameters: vec![],
+            steps,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Vérifie le nom, les étapes et les références des modèles
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty()
+            || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!("Nom de pipeline invalide: {:?} (lettres, chiffres, _ et -)", self.name));
+        }
+        if self.steps.is_empty() {
+            return Err("Le pipeline n'a aucune étape".to_string());
+        }
+
+        let mut known_steps: Vec<&str> = Vec::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            if step.tool == self.name {
+                return Err(format!("Étape {}: le pipeline ne peut pas s'appeler lui-même", index + 1));
+            }
+            for name in placeholders(&step.arguments) {
+                let valid = match name.as_str() {
+                    PREVIOUS => index > 0,
+                    name => match (name.strip_prefix(INPUT_PREFIX), name.strip_prefix(STEP_PREFIX)) {
+                        (Some(input), _) => self.parameters.iter().any(|p| p.name == input),
+                        (_, Some(step)) => known_steps.contains(&step),
+                        _ => false,
+                    },
+                };
+                if !valid {
+                    return Err(format!("Étape {}: référence inconnue {{{{{}}}}}", index + 1, name));
+                }
+            }
+            if let Some(id) = step.id.as_deref() {
+                if known_steps.contains(&id) {
+                    return Err(format!("Identifiant d'étape en double: {}", id));
+                }
+                known_steps.push(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Outil synthétique : exécuté par le registre, étape par étape
+    pub fn to_tool(&self) -> Tool {
+        Tool {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            input_schema: input_schema(&self.parameters),
+            // Chaque étape demande sa propre confirmation si nécessaire
+            requires_approval: false,
+            handler: None,
+        }
+    }
+}
+
+fn placeholders(template: &serde_json::Value) -> Vec<String> {
+    match template {
+        serde_json::Value::String(text) => placeholder()
+            .captures_iter(text)
+            .map(|c| c[1].to_string())
+            .collect(),
+        serde_json::Value::Array(items) => items.iter().flat_map(placeholders).collect(),
+        serde_json::Value::Object(fields) => fields.values().flat_map(placeholders).collect(),
+        _ => vec![],
+    }
+}
+
+/// Résultats disponibles pour les étapes suivantes
+#[derive(Debug)]
+pub struct PipelineState<'a> {
+    input: &'a serde_json::Value,
+    previous: Option<String>,
+    steps: HashMap<String, String>,
+}
+
+impl<'a> PipelineState<'a> {
+    pub fn new(input: &'a serde_json::Value) -> Self {
+        Self { input, previous: None, steps: HashMap::new() }
+    }
+
+    /// Enregistre le résultat d'une étape
+    pub fn record(&mut self, step: &PipelineStep, output: String) {
+        if let Some(id) = &step.id {
+            self.steps.insert(id.clone(), output.clone());
+        }
+        self.previous = Some(output);
+    }
+
+    /// Résultat de la dernière étape exécutée
+    pub fn into_output(self) -> String {
+        self.previous.unwrap_or_default()
+    }
+
+    fn lookup(&self, name: &str) -> Result<serde_json::Value> {
+        let value = if name == PREVIOUS {
+            self.previous.clone().map(serde_json::Value::String)
+        } else if let Some(input) = name.strip_prefix(INPUT_PREFIX) {
+            self.input.get(input).filter(|v| !v.is_null()).cloned()
+        } else if let Some(step) = name.strip_prefix(STEP_PREFIX) {
+            self.steps.get(step).cloned().map(serde_json::Value::String)
+        } else {
+            None
+        };
+        value.ok_or_else(|| anyhow::anyhow!("Valeur manquante pour {{{{{}}}}}", name))
+    }
+
+    /// Arguments d'une étape ; une chaîne réduite à un modèle garde la valeur JSON
+    pub fn render(&self, template: &serde_json::Value) -> Result<serde_json::Value> {
+        Ok(match template {
+            serde_json::Value::String(text) => {
+                let whole = placeholder()
+                    .captures(text)
+                    .filter(|c| c.get(0).unwrap().as_str() == text);
+                match whole {
+                    Some(captures) => self.lookup(&captures[1])?,
+                    None => {
+                        let mut rendered = String::new();
+                        let mut last = 0;
+                        for captures in placeholder().captures_iter(text) {
+                            let whole = captures.get(0).unwrap();
+                            rendered.push_str(&text[last..whole.start()]);
+                            match self.lookup(&captures[1])? {
+                                serde_json::Value::String(value) => rendered.push_str(&value),
+                                value => rendered.push_str(&value.to_string()),
+                            }
+                            last = whole.end();
+                        }
+                        rendered.push_str(&text[last..]);
+                        serde_json::Value::String(rendered)
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => serde_json::Value::Array(
+                items.iter().map(|item| self.render(item)).collect::<Result<_>>()?,
+            ),
+            serde_json::Value::Object(fields) => serde_json::Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), self.render(value)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(id: Option<&str>, tool: &str, arguments: serde_json::Value) -> PipelineStep {
+        PipelineStep { id: id.map(str::to_string), tool: tool.to_string(), arguments }
+    }
+
+    #[test]
+    fn test_validate_pipeline() {
+        let mut pipeline = ToolPipeline::new(
+            "digest".to_string(),
+            "Fetch and summarize a page".to_string(),
+            vec![
+                step(Some("page"), "fetch_url", serde_json::json!({"url": "{{input.url}}"})),
+                step(None, "generate_text", serde_json::json!({"prompt": "Summarize: {{previous}}"})),
+                step(None, "file_writer", serde_json::json!({"path": "out.md", "content": "{{steps.page}}"})),
+            ],
+        );
+        assert!(pipeline.validate().is_err());
+
+        pipeline.parameters.push(ToolParameter {
+            name: "url".to_string(),
+            description: String::new(),
+            required: true,
+        });
+        assert!(pipeline.validate().is_ok());
+
+        let mut first_previous = pipeline.clone();
+        first_previous.steps[0].arguments = serde_json::json!({"url": "{{previous}}"});
+        assert!(first_previous.validate().is_err());
+
+        let mut recursive = pipeline.clone();
+        recursive.steps[1].tool = "digest".to_string();
+        assert!(recursive.validate().is_err());
+    }
+
+    #[test]
+    fn test_render_arguments() {
+        let input = serde_json::json!({"url": "https://example.com", "limit": 3});
+        let mut state = PipelineState::new(&input);
+        let first = step(Some("page"), "fetch_url", serde_json::Value::Null);
+        state.record(&first, "<html>".to_string());
+
+        let rendered = state
+            .render(&serde_json::json!({"limit": "{{input.limit}}", "text": "{{input.url}}: {{steps.page}}"}))
+            .unwrap();
+        assert_eq!(rendered, serde_json::json!({"limit": 3, "text": "https://example.com: <html>"}));
+        assert!(state.render(&serde_json::json!("{{input.missing}}")).is_err());
+        assert_eq!(state.into_output(), "<html>");
+    }
+}
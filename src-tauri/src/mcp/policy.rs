@@ -0,0 +1,105 @@
+/// Politiques d'exécution des outils : nombre d'appels par minute, durée maximale
+/// d'un appel et taille maximale du résultat
+///
+/// Un agent qui boucle ne peut ainsi ni marteler le système de fichiers ou le réseau,
+/// ni bloquer une tâche sur un appel sans fin. Les dépassements sont des erreurs
+/// typées (`ToolPolicyError`) que l'appelant peut distinguer d'un échec de l'outil.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+/// Fenêtre du limiteur d'appels
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Limites d'un outil (aucune par défaut)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct ToolPolicy {
+    pub max_calls_per_minute: Option<u32>,
+    /// Durée maximale d'un appel, en secondes
+    #[ts(type = "number | null")]
+    pub timeout_secs: Option<u64>,
+    /// Taille maximale du résultat, en octets
+    #[ts(type = "number | null")]
+    pub max_output_bytes: Option<usize>,
+}
+
+impl ToolPolicy {
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_secs.map(Duration::from_secs)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_calls_per_minute == Some(0) || self.timeout_secs == Some(0) || self.max_output_bytes == Some(0) {
+            return Err("Les limites d'un outil doivent être supérieures à 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Dépassement d'une limite
+#[derive(Debug, Clone, PartialEq, thiserror::Error, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ToolPolicyError {
+    #[error("Tool {tool} exceeded {max_calls_per_minute} calls per minute")]
+    RateLimited { tool: String, max_calls_per_minute: u32 },
+
+    #[error("Tool {tool} timed out after {timeout_secs}s")]
+    Timeout { tool: String, timeout_secs: u64 },
+
+    #[error("Tool {tool} returned {size} bytes (limit {max_output_bytes})")]
+    OutputTooLarge { tool: String, size: usize, max_output_bytes: usize },
+
+    #[error("Tool {tool} is not allowed here")]
+    NotAllowed { tool: String },
+
+    #[error("Tool {tool} requires approval and no one can approve it here")]
+    ApprovalUnavailable { tool: String },
+}
+
+/// Appels récents de chaque outil (fenêtre glissante d'une minute)
+#[derive(Debug, Default)]
+pub struct CallLimiter {
+    calls: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl CallLimiter {
+    /// Enregistre un appel s'il reste de la place dans la fenêtre
+    pub fn try_acquire(&self, tool: &str, max_calls_per_minute: u32, now: Instant) -> bool {
+        let mut calls = self.calls.lock().unwrap();
+        let recent = calls.entry(tool.to_string()).or_default();
+        while recent.front().is_some_and(|&call| now.duration_since(call) >= RATE_WINDOW) {
+            recent.pop_front();
+        }
+        if recent.len() >= max_calls_per_minute as usize {
+            return false;
+        }
+        recent.push_back(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_limiter_window() {
+        let limiter = CallLimiter::default();
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire("fetch", 2, start));
+        assert!(limiter.try_acquire("fetch", 2, start + Duration::from_secs(1)));
+        assert!(!limiter.try_acquire("fetch", 2, start + Duration::from_secs(2)));
+        // Les autres outils ont leur propre fenêtre
+        assert!(limiter.try_acquire("echo", 2, start + Duration::from_secs(2)));
+
+        // Le premier appel sort de la fenêtre
+        assert!(limiter.try_acquire("fetch", 2, start + Duration::from_secs(60)));
+        assert!(ToolPolicy { timeout_secs: Some(0), ..Default::default() }.validate().is_err());
+    }
+}
@@ -3,6 +3,12 @@ use serde::{Deserialize, Serialize};
 /// MCP protocol version
 pub const MCP_VERSION: &str = "2024-11-05";
 
+/// Notification sent when tools are registered or removed
+pub const TOOLS_LIST_CHANGED: &str = "notifications/tools/list_changed";
+
+/// Notification carrying the partial output of a running tool call
+pub const PROGRESS: &str = "notifications/progress";
+
 /// Structure for JSON-RPC requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
@@ -23,6 +29,25 @@ pub struct JsonRpcResponse {
     pub id: Option<serde_json::Value>,
 }
 
+/// Structure for JSON-RPC notifications (server-initiated, no id and no response)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<serde_json::Value>,
+}
+
+impl JsonRpcNotification {
+    pub fn new(method: &str, params: Option<serde_json::Value>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+        }
+    }
+}
+
 /// JSON-RPC error
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcError {
@@ -45,6 +70,9 @@ pub struct ServerInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerCapabilities {
     pub tools: bool,
+    /// Clients listening on the events channel are told when the tool list changes
+    #[serde(default)]
+    pub tools_list_changed: bool,
     pub resources: bool,
     pub prompts: bool,
     pub logging: bool,
@@ -54,6 +82,7 @@ impl Default for ServerCapabilities {
     fn default() -> Self {
         Self {
             tools: true,
+            tools_list_changed: true,
             resources: false,
             prompts: false,
             logging: true,
@@ -74,4 +103,50 @@ pub struct ToolDescription {
 pub struct CallToolParams {
     pub name: String,
     pub arguments: serde_json::Value,
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<RequestMeta>,
+}
+
+/// Request metadata
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequestMeta {
+    /// Set by clients that want progress notifications for the call
+    #[serde(rename = "progressToken", default, skip_serializing_if = "Option::is_none")]
+    pub progress_token: Option<serde_json::Value>,
+}
+
+/// MCP resource description (`resources/list`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceDescription {
+    pub uri: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub mime_type: String,
+}
+
+/// Parameterized resource (`resources/templates/list`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTemplate {
+    pub uri_template: String,
+    pub name: String,
+    pub description: String,
+    pub mime_type: String,
+}
+
+/// Parameters for resource read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadResourceParams {
+    pub uri: String,
+}
+
+/// Text content of a resource (`resources/read`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceContents {
+    pub uri: String,
+    pub mime_type: String,
+    pub text: String,
 }
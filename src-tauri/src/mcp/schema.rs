@@ -0,0 +1,201 @@
+/// Validation des arguments d'outil par le schéma `input_schema`
+///
+/// Le registre vérifie les arguments avant de les transmettre au handler : un
+/// appel invalide échoue avec la liste des contraintes violées
+/// (`ToolValidationError`) plutôt qu'avec un message vague venu de l'outil.
+///
+/// Seul le sous-ensemble de JSON Schema utilisé par les outils est pris en
+/// charge : `type`, `enum`, `const`, `properties`, `required`,
+/// `additionalProperties`, `items`, `minItems`/`maxItems`,
+/// `minLength`/`maxLength`, `pattern` et `minimum`/`maximum`. Les autres mots-clés
+/// sont ignorés.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Contrainte violée, repérée par son chemin dans les arguments (`/path/0`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub message: String,
+}
+
+/// Arguments refusés avant l'exécution de l'outil
+#[derive(Debug, Clone, PartialEq, thiserror::Error, Serialize)]
+#[error("Invalid arguments for tool {tool}: {}", format_violations(.violations))]
+pub struct ToolValidationError {
+    pub tool: String,
+    pub violations: Vec<SchemaViolation>,
+}
+
+fn format_violations(violations: &[SchemaViolation]) -> String {
+    violations
+        .iter()
+        .map(|v| if v.path.is_empty() { v.message.clone() } else { format!("{}: {}", v.path, v.message) })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Vérifie `tool` ; aucune violation si le schéma est absent ou vide
+pub fn validate_arguments(tool: &str, schema: &Value, arguments: &Value) -> Result<(), ToolValidationError> {
+    let mut violations = Vec::new();
+    validate(schema, arguments, "", &mut violations);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(ToolValidationError { tool: tool.to_string(), violations })
+    }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn validate(schema: &Value, value: &Value, path: &str, violations: &mut Vec<SchemaViolation>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+    let mut violation = |message: String| violations.push(SchemaViolation { path: path.to_string(), message });
+
+    let expected: Vec<&str> = match schema.get("type") {
+        Some(Value::String(expected)) => vec![expected],
+        Some(Value::Array(expected)) => expected.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    if !expected.is_empty() && !expected.iter().any(|expected| type_matches(expected, value)) {
+        violation(format!("expected {}, got {}", expected.join(" or "), type_name(value)));
+        // Les autres contraintes ne s'appliquent pas à une valeur du mauvais type
+        return;
+    }
+
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            violation(format!("must be one of {}", allowed.join(", ")));
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if constant != value {
+            violation(format!("must be {}", constant));
+        }
+    }
+
+    match value {
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64).filter(|&min| length < min) {
+                violation(format!("must be at least {} characters long", min));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64).filter(|&max| length > max) {
+                violation(format!("must be at most {} characters long", max));
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                // Un motif invalide dans le schéma n'est pas la faute de l'appelant
+                if regex::Regex::new(pattern).is_ok_and(|regex| !regex.is_match(text)) {
+                    violation(format!("must match pattern {}", pattern));
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64).filter(|&min| number < min) {
+                violation(format!("must be >= {}", min));
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64).filter(|&max| number > max) {
+                violation(format!("must be <= {}", max));
+            }
+        }
+        Value::Array(items) => {
+            let count = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64).filter(|&min| count < min) {
+                violation(format!("must contain at least {} items", min));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64).filter(|&max| count > max) {
+                violation(format!("must contain at most {} items", max));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate(item_schema, item, &format!("{}/{}", path, index), violations);
+                }
+            }
+        }
+        Value::Object(fields) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if fields.get(name).is_none_or(Value::is_null) {
+                        violation(format!("missing required property '{}'", name));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, field) in fields {
+                match properties.and_then(|p| p.get(name)) {
+                    Some(field_schema) => validate(field_schema, field, &format!("{}/{}", path, name), violations),
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        violations.push(SchemaViolation {
+                            path: path.to_string(),
+                            message: format!("unexpected property '{}'", name),
+                        });
+                    }
+                    None => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_arguments() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "language": {"type": "string", "enum": ["python", "javascript"]},
+                "timeout_secs": {"type": "integer", "minimum": 1},
+                "paths": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["language"],
+            "additionalProperties": false
+        });
+
+        let valid = serde_json::json!({"language": "python", "timeout_secs": 5, "paths": ["a.txt"]});
+        assert!(validate_arguments("run_code", &schema, &valid).is_ok());
+
+        let invalid = serde_json::json!({"timeout_secs": 0.5, "paths": ["a.txt", 3], "extra": true});
+        let error = validate_arguments("run_code", &schema, &invalid).unwrap_err();
+        let paths: Vec<&str> = error.violations.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(error.violations.len(), 4);
+        assert!(paths.contains(&"/timeout_secs"));
+        assert!(paths.contains(&"/paths/1"));
+        assert!(error.to_string().contains("missing required property 'language'"));
+
+        let wrong_enum = serde_json::json!({"language": "ruby"});
+        assert!(validate_arguments("run_code", &schema, &wrong_enum).is_err());
+        // Pas de schéma : tout est accepté
+        assert!(validate_arguments("echo", &serde_json::json!({}), &invalid).is_ok());
+    }
+}
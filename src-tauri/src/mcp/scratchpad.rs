@@ -0,0 +1,174 @@
+/// Bloc-notes de session : variables clé-valeur propres à une portée d'appel
+///
+/// `scratchpad_set` et `scratchpad_get` permettent à l'agent de garder des résultats
+/// intermédiaires d'un tour à l'autre sans les écrire dans la conversation visible.
+/// Les valeurs sont rangées dans la table `session_state` sous la portée de l'appel
+/// (conversation, exécution de tâche, client MCP) et suivent le chiffrement des
+/// conversations. Le bloc-notes d'une conversation disparaît avec elle ; celui des
+/// autres portées est effacé quand elles se terminent (`ToolRegistry::end_scope`).
+
+use super::tools::{Tool, ToolContext, ToolHandler, DEFAULT_APPROVAL_SCOPE};
+use crate::context::ConversationRepository;
+use anyhow::Result;
+use std::sync::Arc;
+
+/// Nombre maximal de clés par session
+pub const MAX_KEYS: usize = 64;
+
+/// Taille maximale d'une valeur, en octets
+pub const MAX_VALUE_BYTES: usize = 16 * 1024;
+
+fn string_arg<'a>(arguments: &'a serde_json::Value, name: &str) -> Result<&'a str> {
+    arguments
+        .get(name)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Paramètre '{}' manquant", name))
+}
+
+/// Portée de l'appel : la portée par défaut, partagée par tous les appels
+/// anonymes, n'a pas de bloc-notes
+fn scope(context: &ToolContext) -> Result<&str> {
+    let scope = context.scope.as_str();
+    if scope.is_empty() || scope == DEFAULT_APPROVAL_SCOPE {
+        anyhow::bail!("Le bloc-notes n'est disponible que dans une conversation, une tâche ou une session MCP");
+    }
+    Ok(scope)
+}
+
+/// Handler de l'outil scratchpad_set
+pub struct ScratchpadSetHandler {
+    repo: Arc<ConversationRepository>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ScratchpadSetHandler {
+    async fn execute(&self, arguments: serde_json::Value, context: &ToolContext) -> Result<String> {
+        let key = string_arg(&arguments, "key")?.trim();
+        if key.is_empty() {
+            anyhow::bail!("La clé ne peut pas être vide");
+        }
+        // Une valeur absente ou nulle efface la clé
+        let value = arguments.get("value").and_then(|v| v.as_str());
+        if value.is_some_and(|v| v.len() > MAX_VALUE_BYTES) {
+            anyhow::bail!("Valeur trop longue (maximum {} octets)", MAX_VALUE_BYTES);
+        }
+
+        let scope = scope(context)?;
+        if value.is_some() {
+            let state = self.repo.get_session_state(scope).await?;
+            if !state.contains_key(key) && state.len() >= MAX_KEYS {
+                anyhow::bail!("Bloc-notes plein ({} clés) : effacez une clé avant d'en ajouter", MAX_KEYS);
+            }
+        }
+        self.repo.set_session_value(scope, key, value).await?;
+        Ok(match value {
+            Some(_) => format!("{} enregistré", key),
+            None => format!("{} effacé", key),
+        })
+    }
+
+    async fn end_scope(&self, scope: &str) -> Result<()> {
+        // Les conversations gardent leur bloc-notes tant qu'elles existent
+        if self.repo.get_conversation(scope).await?.is_none() {
+            self.repo.clear_session_state(scope).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Handler de l'outil scratchpad_get
+pub struct ScratchpadGetHandler {
+    repo: Arc<ConversationRepository>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ScratchpadGetHandler {
+    async fn execute(&self, arguments: serde_json::Value, context: &ToolContext) -> Result<String> {
+        let scope = scope(context)?;
+        match arguments.get("key").and_then(|v| v.as_str()) {
+            Some(key) => Ok(self.repo.get_session_value(scope, key.trim()).await?.unwrap_or_default()),
+            None => Ok(serde_json::to_string(&self.repo.get_session_state(scope).await?)?),
+        }
+    }
+}
+
+/// Outils du bloc-notes de session
+pub fn scratchpad_tools(repo: Arc<ConversationRepository>) -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "scratchpad_set".to_string(),
+            description: "Enregistre une valeur dans le bloc-notes de la conversation (invisible dans le chat)".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Nom de la variable"
+                    },
+                    "value": {
+                        "type": ["string", "null"],
+                        "description": "Valeur à enregistrer (absente ou nulle pour effacer la variable)"
+                    }
+                },
+                "required": ["key"]
+            }),
+            requires_approval: false,
+            handler: Some(Arc::new(ScratchpadSetHandler { repo: Arc::clone(&repo) })),
+        },
+        Tool {
+            name: "scratchpad_get".to_string(),
+            description: "Lit une valeur du bloc-notes de la conversation, ou toutes les valeurs en JSON".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Nom de la variable (optionnel)"
+                    }
+                }
+            }),
+            requires_approval: false,
+            handler: Some(Arc::new(ScratchpadGetHandler { repo })),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Database;
+    use crate::mcp::ToolRegistry;
+
+    #[tokio::test]
+    async fn test_scratchpad_tools() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = Arc::new(ConversationRepository::new(db.pool().clone()));
+        let conv = repo.create_conversation("Recherche", "test-model").await.unwrap();
+        let mut registry = ToolRegistry::new();
+        for tool in scratchpad_tools(Arc::clone(&repo)) {
+            registry.register_tool(tool).unwrap();
+        }
+
+        let set = serde_json::json!({"key": "sources", "value": "a.md, b.md"});
+        registry.execute_tool_in("scratchpad_set", set, &conv.id).await.unwrap();
+        let get = serde_json::json!({"key": "sources"});
+        assert_eq!(registry.execute_tool_in("scratchpad_get", get, &conv.id).await.unwrap(), "a.md, b.md");
+        let all = registry.execute_tool_in("scratchpad_get", serde_json::json!({}), &conv.id).await.unwrap();
+        assert_eq!(all, r#"{"sources":"a.md, b.md"}"#);
+
+        // Effacement, puis appel hors d'une conversation
+        let clear = serde_json::json!({"key": "sources", "value": null});
+        registry.execute_tool_in("scratchpad_set", clear, &conv.id).await.unwrap();
+        assert_eq!(registry.execute_tool_in("scratchpad_get", serde_json::json!({}), &conv.id).await.unwrap(), "{}");
+        assert!(registry.execute_tool("scratchpad_get", serde_json::json!({})).await.is_err());
+        // Hors conversation, le bloc-notes disparaît avec la portée
+        let set = serde_json::json!({"key": "k", "value": "v"});
+        registry.execute_tool_in("scratchpad_set", set, "mcp:client").await.unwrap();
+        registry.end_scope("mcp:client").await;
+        registry.end_scope(&conv.id).await;
+        assert!(repo.get_session_state("mcp:client").await.unwrap().is_empty());
+        // Rien n'apparaît dans la conversation visible
+        assert!(repo.get_messages(&conv.id).await.unwrap().is_empty());
+    }
+}
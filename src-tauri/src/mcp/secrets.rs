@@ -0,0 +1,244 @@
+/// Per-tool secrets (API keys, tokens) stored in the OS keyring
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use tracing::{debug, info};
+
+/// Keyring service under which every tool secret is stored
+pub const KEYRING_SERVICE: &str = "agents-rs";
+
+/// Placeholder written instead of a secret value
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Low level storage used by the secret store
+pub trait SecretBackend: Send + Sync {
+    fn get(&self, account: &str) -> Result<Option<String>>;
+    fn set(&self, account: &str, value: &str) -> Result<()>;
+    fn delete(&self, account: &str) -> Result<()>;
+}
+
+/// Backend using the platform keyring (Keychain, Credential Manager, kernel keyring)
+pub struct KeyringBackend {
+    service: String,
+}
+
+impl KeyringBackend {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self { service: service.into() }
+    }
+
+    fn entry(&self, account: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(&self.service, account).context("Failed to open keyring entry")
+    }
+}
+
+impl SecretBackend for KeyringBackend {
+    fn get(&self, account: &str) -> Result<Option<String>> {
+        match self.entry(account)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e).context("Failed to read secret from keyring"),
+        }
+    }
+
+    fn set(&self, account: &str, value: &str) -> Result<()> {
+        self.entry(account)?
+            .set_password(value)
+            .context("Failed to write secret to keyring")
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        match self.entry(account)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e).context("Failed to delete secret from keyring"),
+        }
+    }
+}
+
+/// Volatile backend, used when no keyring is available and in tests
+#[derive(Default)]
+pub struct MemoryBackend {
+    values: Mutex<HashMap<String, String>>,
+}
+
+impl SecretBackend for MemoryBackend {
+    fn get(&self, account: &str) -> Result<Option<String>> {
+        Ok(self.values.lock().unwrap().get(account).cloned())
+    }
+
+    fn set(&self, account: &str, value: &str) -> Result<()> {
+        self.values.lock().unwrap().insert(account.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        self.values.lock().unwrap().remove(account);
+        Ok(())
+    }
+}
+
+/// Secrets resolved for one tool execution
+///
+/// `Debug` never prints the values, so the struct can be logged safely.
+#[derive(Clone, Default)]
+pub struct ToolSecrets {
+    values: HashMap<String, String>,
+}
+
+impl ToolSecrets {
+    pub fn new(values: HashMap<String, String>) -> Self {
+        Self { values }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Returns the secret or an error naming the missing key
+    pub fn require(&self, key: &str) -> Result<&str> {
+        self.get(key)
+            .ok_or_else(|| anyhow::anyhow!("Missing secret '{}' for this tool", key))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Replaces every secret value found in `text` with a placeholder
+    pub fn redact(&self, text: &str) -> String {
+        let mut redacted = text.to_string();
+        for value in self.values.values().filter(|v| !v.is_empty()) {
+            redacted = redacted.replace(value.as_str(), REDACTED);
+        }
+        redacted
+    }
+}
+
+impl std::fmt::Debug for ToolSecrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut keys: Vec<&String> = self.values.keys().collect();
+        keys.sort();
+        f.debug_struct("ToolSecrets").field("keys", &keys).finish()
+    }
+}
+
+/// Store of the secrets configured for each tool
+///
+/// Values only live in the backend; the list of key names per tool is kept in
+/// an index entry so the secrets of a tool can be enumerated without the keyring
+/// supporting listing.
+pub struct SecretStore {
+    backend: Box<dyn SecretBackend>,
+}
+
+impl SecretStore {
+    pub fn new(backend: Box<dyn SecretBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Store backed by the OS keyring
+    pub fn keyring() -> Self {
+        Self::new(Box::new(KeyringBackend::new(KEYRING_SERVICE)))
+    }
+
+    /// Store that only keeps secrets in memory
+    pub fn in_memory() -> Self {
+        Self::new(Box::new(MemoryBackend::default()))
+    }
+
+    fn account(tool: &str, key: &str) -> String {
+        format!("tool.{}.{}", tool, key)
+    }
+
+    fn index_account(tool: &str) -> String {
+        format!("tool.{}.__keys", tool)
+    }
+
+    fn read_index(&self, tool: &str) -> Result<BTreeSet<String>> {
+        match self.backend.get(&Self::index_account(tool))? {
+            Some(raw) => serde_json::from_str(&raw).context("Corrupted secret index"),
+            None => Ok(BTreeSet::new()),
+        }
+    }
+
+    fn write_index(&self, tool: &str, keys: &BTreeSet<String>) -> Result<()> {
+        if keys.is_empty() {
+            return self.backend.delete(&Self::index_account(tool));
+        }
+        self.backend.set(&Self::index_account(tool), &serde_json::to_string(keys)?)
+    }
+
+    /// Stores (or replaces) a secret for a tool
+    pub fn set_secret(&self, tool: &str, key: &str, value: &str) -> Result<()> {
+        if key.is_empty() || key.starts_with("__") {
+            return Err(anyhow::anyhow!("Invalid secret name: {}", key));
+        }
+        self.backend.set(&Self::account(tool, key), value)?;
+
+        let mut keys = self.read_index(tool)?;
+        keys.insert(key.to_string());
+        self.write_index(tool, &keys)?;
+
+        info!("Secret '{}' stored for tool {}", key, tool);
+        Ok(())
+    }
+
+    /// Removes a secret of a tool
+    pub fn delete_secret(&self, tool: &str, key: &str) -> Result<()> {
+        self.backend.delete(&Self::account(tool, key))?;
+
+        let mut keys = self.read_index(tool)?;
+        keys.remove(key);
+        self.write_index(tool, &keys)?;
+
+        info!("Secret '{}' removed for tool {}", key, tool);
+        Ok(())
+    }
+
+    /// Lists the names (never the values) of the secrets of a tool
+    pub fn list_secret_names(&self, tool: &str) -> Result<Vec<String>> {
+        Ok(self.read_index(tool)?.into_iter().collect())
+    }
+
+    /// Loads every secret of a tool for an execution
+    pub fn secrets_for(&self, tool: &str) -> Result<ToolSecrets> {
+        let mut values = HashMap::new();
+        for key in self.read_index(tool)? {
+            if let Some(value) = self.backend.get(&Self::account(tool, &key))? {
+                values.insert(key, value);
+            }
+        }
+        debug!("Loaded {} secret(s) for tool {}", values.len(), tool);
+        Ok(ToolSecrets::new(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_store_roundtrip() {
+        let store = SecretStore::in_memory();
+        store.set_secret("web_search", "api_key", "sk-123").unwrap();
+        store.set_secret("web_search", "region", "eu").unwrap();
+
+        assert_eq!(store.list_secret_names("web_search").unwrap(), vec!["api_key", "region"]);
+        let secrets = store.secrets_for("web_search").unwrap();
+        assert_eq!(secrets.get("api_key"), Some("sk-123"));
+        assert!(store.secrets_for("send_email").unwrap().is_empty());
+
+        store.delete_secret("web_search", "api_key").unwrap();
+        assert_eq!(store.list_secret_names("web_search").unwrap(), vec!["region"]);
+    }
+
+    #[test]
+    fn test_secrets_never_printed() {
+        let secrets = ToolSecrets::new(HashMap::from([
+            ("api_key".to_string(), "sk-123".to_string()),
+        ]));
+        assert!(!format!("{:?}", secrets).contains("sk-123"));
+        assert_eq!(secrets.redact("token sk-123 rejected"), "token [REDACTED] rejected");
+    }
+}
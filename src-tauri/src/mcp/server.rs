@@ -1,25 +1,55 @@
 /// MCP (Model Context Protocol) Server
 
+use super::conversations;
+use super::embeddings::{self, EmbeddingRequest};
+use super::policy::ToolPolicyError;
+use super::schema::ToolValidationError;
 use super::protocol::*;
 use super::tools::ToolRegistry;
+use crate::context::{ApiAccess, ApiTokenRepository, ContextManager};
+use crate::llm::{EngineHandle, GenerationScheduler};
 use anyhow::Result;
 use axum::{
     extract::State,
-    http::StatusCode,
-    response::IntoResponse,
-    routing::{get, post},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, post},
     Json, Router,
 };
+use futures::stream::{self, Stream};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{info, error};
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, info, error, warn};
 
 /// Shared state of the MCP server
+#[derive(Clone)]
 pub struct MCPServerState {
     tool_registry: Arc<RwLock<ToolRegistry>>,
     server_info: ServerInfo,
+    /// Progress notifications of running tool calls, with the scope of the client that made them
+    progress: broadcast::Sender<(String, JsonRpcNotification)>,
+    /// Conversations served as `conversation://{id}` resources
+    conversations: Option<Arc<RwLock<ContextManager>>>,
+    /// Engine answering `/v1/embeddings` and the queue it shares with the application
+    embeddings: Option<(EngineHandle, GenerationScheduler)>,
+    /// Scoped tokens required once at least one is issued
+    api_tokens: Option<ApiTokenRepository>,
 }
 
+/// Header carrying the session of an MCP client, assigned at `initialize`
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Tools a chat-only token may call
+const CHAT_TOOLS: &[&str] = &["generate_text"];
+
+/// Environment variable setting the port of the MCP server (containers, kiosks)
+pub const MCP_PORT_ENV: &str = "AGENTS_RS_MCP_PORT";
+
 /// Main MCP server
 pub struct MCPServer {
     state: Arc<MCPServerState>,
@@ -41,17 +71,71 @@ impl MCPServer {
         let state = Arc::new(MCPServerState {
             tool_registry: Arc::new(RwLock::new(ToolRegistry::new())),
             server_info,
+            progress: broadcast::channel(256).0,
+            conversations: None,
+            embeddings: None,
+            api_tokens: None,
         });
 
         Self { state, port }
     }
 
+    /// Port set with `AGENTS_RS_MCP_PORT`, if any (an invalid value is ignored)
+    pub fn port_from_env() -> Option<u16> {
+        let value = std::env::var(MCP_PORT_ENV).ok()?;
+        match value.trim().parse() {
+            Ok(port) => Some(port),
+            Err(_) => {
+                warn!("Invalid {} ignored: {}", MCP_PORT_ENV, value);
+                None
+            }
+        }
+    }
+
+    /// Serves the tools of an existing registry (the application's) instead of a new one
+    pub fn with_tool_registry(mut self, tool_registry: Arc<RwLock<ToolRegistry>>) -> Self {
+        let mut state = (*self.state).clone();
+        state.tool_registry = tool_registry;
+        self.state = Arc::new(state);
+        self
+    }
+
+    /// Serves the application's conversations as resources
+    pub fn with_conversations(mut self, manager: Arc<RwLock<ContextManager>>) -> Self {
+        let mut state = (*self.state).clone();
+        state.conversations = Some(manager);
+        state.server_info.capabilities.resources = true;
+        self.state = Arc::new(state);
+        self
+    }
+
+    /// Also serves an OpenAI-compatible `/v1/embeddings` endpoint backed by the loaded model
+    pub fn with_embeddings(mut self, engine: EngineHandle, scheduler: GenerationScheduler) -> Self {
+        let mut state = (*self.state).clone();
+        state.embeddings = Some((engine, scheduler));
+        self.state = Arc::new(state);
+        self
+    }
+
+    /// Checks the `Authorization: Bearer` token of each request against its route
+    pub fn with_api_tokens(mut self, tokens: ApiTokenRepository) -> Self {
+        let mut state = (*self.state).clone();
+        state.api_tokens = Some(tokens);
+        self.state = Arc::new(state);
+        self
+    }
+
     /// Starts the MCP server
     pub async fn start(&self) -> Result<()> {
-        let app = Router::new()
+        let mut app = Router::new()
             .route("/", get(health_check))
             .route("/mcp", post(handle_mcp_request))
-            .with_state(Arc::clone(&self.state));
+            .route("/mcp", delete(handle_end_session))
+            .route("/mcp/events", get(handle_events));
+        if self.state.embeddings.is_some() {
+            app = app.route("/v1/embeddings", post(handle_embeddings));
+        }
+        let app = app.with_state(Arc::clone(&self.state));
 
         let addr = format!("127.0.0.1:{}", self.port);
         info!("MCP server listening on http://{}", addr);
@@ -68,6 +152,21 @@ impl MCPServer {
     }
 }
 
+/// Handler for OpenAI-compatible embedding requests
+async fn handle_embeddings(
+    State(state): State<Arc<MCPServerState>>,
+    headers: HeaderMap,
+    Json(request): Json<EmbeddingRequest>,
+) -> Response {
+    if let Err(status) = authorize(&state, &headers, ApiAccess::Chat).await {
+        return status.into_response();
+    }
+    match &state.embeddings {
+        Some((engine, scheduler)) => embeddings::handle_embeddings(engine, scheduler, request).await,
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
 /// Handler for health check
 async fn health_check() -> impl IntoResponse {
     Json(serde_json::json!({
@@ -76,17 +175,168 @@ async fn health_check() -> impl IntoResponse {
     }))
 }
 
+/// Server-initiated notifications, streamed as Server-Sent Events
+///
+/// Each event carries a JSON-RPC notification; clients re-fetch `tools/list`
+/// when they receive `notifications/tools/list_changed`, and receive the
+/// partial output of the calls they made with a `progressToken`. Progress only
+/// goes to the stream opened with the same `mcp-session-id` as the call.
+async fn handle_events(
+    State(state): State<Arc<MCPServerState>>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(status) = authorize(&state, &headers, ApiAccess::Session).await {
+        return status.into_response();
+    }
+    let changes = state.tool_registry.read().await.subscribe_changes();
+    let progress = state.progress.subscribe();
+    let scope = client_session(&headers).map(|session| client_scope(&session));
+    debug!("MCP client subscribed to notifications");
+    let events = stream::select(notifications(changes), progress_notifications(progress, scope));
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+fn notifications(changes: broadcast::Receiver<()>) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(changes, |mut changes| async move {
+        match changes.recv().await {
+            // Missed changes collapse into a single notification
+            Ok(()) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                let notification = JsonRpcNotification::new(TOOLS_LIST_CHANGED, None);
+                Some((Ok(to_event(&notification)), changes))
+            }
+            Err(broadcast::error::RecvError::Closed) => None,
+        }
+    })
+}
+
+/// Progress of the calls made in `scope` (none without a session)
+fn progress_notifications(
+    progress: broadcast::Receiver<(String, JsonRpcNotification)>,
+    scope: Option<String>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold((progress, scope), |(mut progress, scope)| async move {
+        loop {
+            match progress.recv().await {
+                Ok((from, notification)) if scope.as_ref() == Some(&from) => {
+                    return Some((Ok(to_event(&notification)), (progress, scope)));
+                }
+                Ok(_) => continue,
+                // Output missed by a slow client is not replayed
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+fn to_event(notification: &JsonRpcNotification) -> Event {
+    Event::default()
+        .event("message")
+        .json_data(notification)
+        .unwrap_or_default()
+}
+
+/// Session of the client that sent a request, if it sent one
+fn client_session(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(SESSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// Token sent as `Authorization: Bearer <token>`
+fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// Rejects a request whose token is missing (401) or does not grant `access` (403)
+async fn authorize(state: &MCPServerState, headers: &HeaderMap, access: ApiAccess) -> Result<(), StatusCode> {
+    let Some(tokens) = &state.api_tokens else {
+        return Ok(());
+    };
+    let checked = match tokens.is_required().await {
+        Ok(false) => return Ok(()),
+        Ok(true) => match bearer(headers) {
+            Some(token) => tokens.authenticate(token).await,
+            None => return Err(StatusCode::UNAUTHORIZED),
+        },
+        Err(e) => Err(e),
+    };
+    match checked {
+        Ok(Some(scope)) if scope.allows(access) => Ok(()),
+        Ok(Some(scope)) => {
+            warn!("MCP request refused: {:?} token used for {:?}", scope, access);
+            Err(StatusCode::FORBIDDEN)
+        }
+        Ok(None) => Err(StatusCode::UNAUTHORIZED),
+        Err(e) => {
+            error!("API token check failed: {:#}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// What a JSON-RPC request needs from the client's token
+fn request_access(request: &JsonRpcRequest) -> ApiAccess {
+    match request.method.as_str() {
+        "tools/call" => {
+            let tool = request.params.as_ref().and_then(|p| p.get("name")).and_then(|n| n.as_str());
+            if tool.is_some_and(|name| CHAT_TOOLS.contains(&name)) {
+                ApiAccess::Chat
+            } else {
+                ApiAccess::Admin
+            }
+        }
+        method if method.starts_with("resources/") => ApiAccess::Read,
+        _ => ApiAccess::Session,
+    }
+}
+
+/// Approval scope of an MCP client: "always allow" only covers its own session
+fn client_scope(session: &str) -> String {
+    format!("mcp:{}", session)
+}
+
 /// Main handler for MCP requests
 async fn handle_mcp_request(
     State(state): State<Arc<MCPServerState>>,
+    headers: HeaderMap,
     Json(request): Json<JsonRpcRequest>,
-) -> impl IntoResponse {
+) -> Response {
     info!("MCP request received: {}", request.method);
+    if let Err(status) = authorize(&state, &headers, request_access(&request)).await {
+        return status.into_response();
+    }
 
+    let mut response_headers = HeaderMap::new();
     let response = match request.method.as_str() {
-        "initialize" => handle_initialize(&state, request).await,
+        "initialize" => {
+            // Each client gets its own session, sent back on every later request
+            let session = uuid::Uuid::new_v4().to_string();
+            if let Ok(value) = HeaderValue::from_str(&session) {
+                response_headers.insert(SESSION_HEADER, value);
+            }
+            handle_initialize(&state, request).await
+        }
         "tools/list" => handle_list_tools(&state, request).await,
-        "tools/call" => handle_call_tool(&state, request).await,
+        "tools/call" => match client_session(&headers) {
+            Some(session) => handle_call_tool(&state, request, &client_scope(&session)).await,
+            // Without a session, permanent approvals only last for this call
+            None => {
+                let scope = client_scope(&uuid::Uuid::new_v4().to_string());
+                let response = handle_call_tool(&state, request, &scope).await;
+                end_scope(&state, &scope).await;
+                response
+            }
+        },
+        "resources/list" => handle_list_resources(&state, request).await,
+        "resources/templates/list" => handle_list_resource_templates(&state, request),
+        "resources/read" => handle_read_resource(&state, request).await,
         _ => JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
             result: None,
@@ -99,7 +349,30 @@ async fn handle_mcp_request(
         },
     };
 
-    (StatusCode::OK, Json(response))
+    (StatusCode::OK, response_headers, Json(response)).into_response()
+}
+
+/// End of an MCP session (`DELETE /mcp`): its permanent approvals are forgotten
+async fn handle_end_session(
+    State(state): State<Arc<MCPServerState>>,
+    headers: HeaderMap,
+) -> StatusCode {
+    if let Err(status) = authorize(&state, &headers, ApiAccess::Session).await {
+        return status;
+    }
+    match client_session(&headers) {
+        Some(session) => {
+            debug!("MCP session {} ended", session);
+            end_scope(&state, &client_scope(&session)).await;
+            StatusCode::NO_CONTENT
+        }
+        None => StatusCode::BAD_REQUEST,
+    }
+}
+
+async fn end_scope(state: &MCPServerState, scope: &str) {
+    let registry = state.tool_registry.read().await.snapshot();
+    registry.end_scope(scope).await;
 }
 
 /// Handles initialization request
@@ -146,6 +419,7 @@ async fn handle_list_tools(
 async fn handle_call_tool(
     state: &MCPServerState,
     request: JsonRpcRequest,
+    scope: &str,
 ) -> JsonRpcResponse {
     let params: CallToolParams = match request.params {
         Some(ref p) => match serde_json::from_value(p.clone()) {
@@ -178,9 +452,34 @@ async fn handle_call_tool(
         }
     };
 
-    let registry = state.tool_registry.read().await;
-    
-    match registry.execute_tool(&params.name, params.arguments).await {
+    // The registry lock is released before the call, which may wait for an approval
+    let registry = state.tool_registry.read().await.snapshot();
+    let progress_token = params.meta.and_then(|meta| meta.progress_token);
+
+    let result = match progress_token {
+        // Each chunk of output is sent as a progress notification on the events stream
+        Some(token) => {
+            let sent = AtomicU64::new(0);
+            let on_output = |text: &str| {
+                let progress = sent.fetch_add(1, Ordering::Relaxed) + 1;
+                let notification = JsonRpcNotification::new(
+                    PROGRESS,
+                    Some(serde_json::json!({
+                        "progressToken": token,
+                        "progress": progress,
+                        "message": text,
+                    })),
+                );
+                let _ = state.progress.send((scope.to_string(), notification));
+            };
+            registry
+                .execute_tool_streaming(&params.name, params.arguments, scope, None, &on_output)
+                .await
+        }
+        None => registry.execute_tool_in(&params.name, params.arguments, scope).await,
+    };
+
+    match result {
         Ok(result) => JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
             result: Some(serde_json::json!({
@@ -194,13 +493,23 @@ async fn handle_call_tool(
         },
         Err(e) => {
             error!("Tool execution error for {}: {}", params.name, e);
+            // Exceeded limits and invalid arguments are reported with their details
+            // so clients can back off or fix the call
+            let (code, data) = match e.downcast_ref::<ToolValidationError>() {
+                Some(validation) => (-32602, serde_json::to_value(validation).ok()),
+                None => (
+                    -32000,
+                    e.downcast_ref::<ToolPolicyError>()
+                        .and_then(|policy| serde_json::to_value(policy).ok()),
+                ),
+            };
             JsonRpcResponse {
                 jsonrpc: "2.0".to_string(),
                 result: None,
                 error: Some(JsonRpcError {
-                    code: -32000,
+                    code,
                     message: format!("Tool execution error: {}", e),
-                    data: None,
+                    data,
                 }),
                 id: request.id,
             }
@@ -208,9 +517,73 @@ async fn handle_call_tool(
     }
 }
 
+fn error_response(id: Option<serde_json::Value>, code: i32, message: String) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        result: None,
+        error: Some(JsonRpcError { code, message, data: None }),
+        id,
+    }
+}
+
+fn result_response(id: Option<serde_json::Value>, result: serde_json::Value) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        result: Some(result),
+        error: None,
+        id,
+    }
+}
+
+/// Handles list resources request (one resource per conversation)
+async fn handle_list_resources(
+    state: &MCPServerState,
+    request: JsonRpcRequest,
+) -> JsonRpcResponse {
+    let resources = match &state.conversations {
+        Some(manager) => match conversations::list_conversation_resources(&*manager.read().await).await {
+            Ok(resources) => resources,
+            Err(e) => return error_response(request.id, -32603, format!("Failed to list resources: {:#}", e)),
+        },
+        None => Vec::new(),
+    };
+    result_response(request.id, serde_json::json!({ "resources": resources }))
+}
+
+fn handle_list_resource_templates(
+    state: &MCPServerState,
+    request: JsonRpcRequest,
+) -> JsonRpcResponse {
+    let templates: Vec<ResourceTemplate> = match state.conversations {
+        Some(_) => vec![conversations::conversation_template()],
+        None => Vec::new(),
+    };
+    result_response(request.id, serde_json::json!({ "resourceTemplates": templates }))
+}
+
+/// Handles read resource request
+async fn handle_read_resource(
+    state: &MCPServerState,
+    request: JsonRpcRequest,
+) -> JsonRpcResponse {
+    let params: ReadResourceParams = match request.params.clone().map(serde_json::from_value) {
+        Some(Ok(params)) => params,
+        _ => return error_response(request.id, -32602, "Invalid parameters".to_string()),
+    };
+    let Some(manager) = &state.conversations else {
+        return error_response(request.id, -32002, format!("Resource not found: {}", params.uri));
+    };
+
+    match conversations::read_conversation_resource(&*manager.read().await, &params.uri).await {
+        Ok(contents) => result_response(request.id, serde_json::json!({ "contents": [contents] })),
+        Err(e) => error_response(request.id, -32002, format!("Resource not found: {}: {:#}", params.uri, e)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mcp::tools::{OutputSink, Tool, ToolContext, ToolHandler};
 
     #[test]
     fn test_server_creation() {
@@ -218,6 +591,210 @@ mod tests {
         assert_eq!(server.port, 3000);
     }
 
+    #[tokio::test]
+    async fn test_tools_list_changed_notification() {
+        use futures::StreamExt;
+
+        let server = MCPServer::new(3000);
+        let registry = server.tool_registry();
+        let changes = registry.read().await.subscribe_changes();
+        let mut events = Box::pin(notifications(changes));
+
+        registry.write().await.register_tool(crate::mcp::tools::create_file_reader_tool()).unwrap();
+        assert!(events.next().await.unwrap().is_ok());
+
+        let json = serde_json::to_value(JsonRpcNotification::new(TOOLS_LIST_CHANGED, None)).unwrap();
+        assert_eq!(json, serde_json::json!({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"}));
+    }
+
+    struct CountdownHandler;
+
+    #[async_trait::async_trait]
+    impl ToolHandler for CountdownHandler {
+        async fn execute(&self, arguments: serde_json::Value, context: &ToolContext) -> Result<String> {
+            self.execute_streaming(arguments, context, &|_| {}).await
+        }
+
+        async fn execute_streaming(
+            &self,
+            _arguments: serde_json::Value,
+            _context: &ToolContext,
+            on_output: OutputSink<'_>,
+        ) -> Result<String> {
+            on_output("2\n");
+            on_output("1\n");
+            Ok("2\n1\n".to_string())
+        }
+    }
+
+    fn call_request(name: &str, meta: serde_json::Value) -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/call".to_string(),
+            params: Some(serde_json::json!({"name": name, "arguments": {}, "_meta": meta})),
+            id: Some(serde_json::json!(1)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_progress_notifications() {
+        use futures::StreamExt;
+
+        let server = MCPServer::new(3000);
+        server.tool_registry().write().await.register_tool(Tool {
+            name: "countdown".to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({}),
+            requires_approval: false,
+            handler: Some(Arc::new(CountdownHandler)),
+        }).unwrap();
+        let mut progress = server.state.progress.subscribe();
+        let mut own = Box::pin(progress_notifications(server.state.progress.subscribe(), Some("mcp:test".to_string())));
+        let mut other = Box::pin(progress_notifications(server.state.progress.subscribe(), Some("mcp:other".to_string())));
+
+        let response = handle_call_tool(&server.state, call_request("countdown", serde_json::json!({})), "mcp:test").await;
+        assert!(response.error.is_none());
+        // Without a progress token, only the final result is sent
+        assert!(progress.try_recv().is_err());
+
+        let meta = serde_json::json!({"progressToken": "call-1"});
+        let response = handle_call_tool(&server.state, call_request("countdown", meta), "mcp:test").await;
+        assert!(response.error.is_none());
+
+        let (scope, first) = progress.try_recv().unwrap();
+        assert_eq!(scope, "mcp:test");
+        assert_eq!(first.method, PROGRESS);
+        assert_eq!(
+            first.params,
+            Some(serde_json::json!({"progressToken": "call-1", "progress": 1, "message": "2\n"}))
+        );
+        assert_eq!(progress.try_recv().unwrap().1.params.unwrap()["progress"], 2);
+        assert!(progress.try_recv().is_err());
+
+        // Only the stream of the calling client receives the progress
+        assert!(own.next().await.unwrap().is_ok());
+        assert!(own.next().await.unwrap().is_ok());
+        drop(server);
+        let waited = tokio::time::timeout(std::time::Duration::from_millis(50), other.next()).await;
+        assert!(!matches!(waited, Ok(Some(_))));
+    }
+
+    #[tokio::test]
+    async fn test_client_approval_scopes() {
+        use crate::mcp::approval::ToolApprovals;
+
+        let approvals = ToolApprovals::new();
+        let registry = Arc::new(RwLock::new(ToolRegistry::new().with_approvals(approvals.clone())));
+        registry.write().await.register_tool(Tool {
+            name: "countdown".to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({}),
+            requires_approval: true,
+            handler: Some(Arc::new(CountdownHandler)),
+        }).unwrap();
+        let server = MCPServer::new(3000).with_tool_registry(Arc::clone(&registry));
+        let mut requests = approvals.subscribe();
+
+        let call = tokio::spawn({
+            let state = Arc::clone(&server.state);
+            async move { handle_call_tool(&state, call_request("countdown", serde_json::json!({})), "mcp:a").await }
+        });
+        let request = requests.recv().await.unwrap();
+        assert_eq!(request.scope, "mcp:a");
+        // The registry stays writable while the user decides
+        let written = tokio::time::timeout(std::time::Duration::from_secs(1), registry.write()).await;
+        assert!(written.is_ok());
+        drop(written);
+        approvals.respond(&request.call_id, true, true).unwrap();
+        assert!(call.await.unwrap().error.is_none());
+
+        // "Always allow" covers the same client only
+        let response = handle_call_tool(&server.state, call_request("countdown", serde_json::json!({})), "mcp:a").await;
+        assert!(response.error.is_none());
+        let other = tokio::spawn({
+            let state = Arc::clone(&server.state);
+            async move { handle_call_tool(&state, call_request("countdown", serde_json::json!({})), "mcp:b").await }
+        });
+        let request = requests.recv().await.unwrap();
+        assert_eq!(request.scope, "mcp:b");
+        approvals.respond(&request.call_id, false, false).unwrap();
+        assert!(other.await.unwrap().error.is_some());
+
+        // Ending the session forgets its approvals
+        end_scope(&server.state, "mcp:a").await;
+        assert!(approvals.pending().is_empty());
+        let again = tokio::spawn({
+            let state = Arc::clone(&server.state);
+            async move { handle_call_tool(&state, call_request("countdown", serde_json::json!({})), "mcp:a").await }
+        });
+        let request = requests.recv().await.unwrap();
+        approvals.respond(&request.call_id, false, false).unwrap();
+        assert!(again.await.unwrap().error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_api_token_scopes() {
+        use crate::context::ApiScope;
+        use axum::http::header::AUTHORIZATION;
+
+        let db = crate::context::Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let tokens = ApiTokenRepository::new(db.pool().clone());
+        let server = MCPServer::new(3000).with_api_tokens(tokens.clone());
+        let state = &server.state;
+
+        // Open until a first token is issued
+        assert!(authorize(state, &HeaderMap::new(), ApiAccess::Admin).await.is_ok());
+        let (_, reader) = tokens.create("reader", ApiScope::ReadOnly).await.unwrap();
+        let (_, chat) = tokens.create("chat", ApiScope::Chat).await.unwrap();
+        let headers = |token: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+            headers
+        };
+        assert_eq!(authorize(state, &HeaderMap::new(), ApiAccess::Session).await, Err(StatusCode::UNAUTHORIZED));
+        assert_eq!(authorize(state, &headers("ars_wrong"), ApiAccess::Session).await, Err(StatusCode::UNAUTHORIZED));
+        assert!(authorize(state, &headers(&reader), ApiAccess::Read).await.is_ok());
+        assert_eq!(authorize(state, &headers(&reader), ApiAccess::Chat).await, Err(StatusCode::FORBIDDEN));
+
+        // Only the chat tools are open to a chat token
+        let generate = call_request("generate_text", serde_json::json!({}));
+        let write = call_request("file_writer", serde_json::json!({}));
+        assert!(authorize(state, &headers(&chat), request_access(&generate)).await.is_ok());
+        assert_eq!(authorize(state, &headers(&chat), request_access(&write)).await, Err(StatusCode::FORBIDDEN));
+    }
+
+    #[tokio::test]
+    async fn test_conversation_resources() {
+        let db = crate::context::Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let manager = Arc::new(RwLock::new(ContextManager::new(
+            crate::context::ConversationRepository::new(db.pool().clone()),
+            "test-model".to_string(),
+        )));
+        let id = manager.read().await.create_session("Notes".to_string()).await.unwrap();
+
+        let server = MCPServer::new(3000).with_conversations(manager);
+        assert!(server.state.server_info.capabilities.resources);
+
+        let request = |method: &str, params: Option<serde_json::Value>| JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+            id: Some(serde_json::json!(1)),
+        };
+        let listed = handle_list_resources(&server.state, request("resources/list", None)).await;
+        let uri = listed.result.unwrap()["resources"][0]["uri"].clone();
+        assert_eq!(uri, format!("conversation://{}", id));
+
+        let read = handle_read_resource(&server.state, request("resources/read", Some(serde_json::json!({"uri": uri})))).await;
+        assert_eq!(read.result.unwrap()["contents"][0]["mimeType"], "application/json");
+
+        let missing = serde_json::json!({"uri": "conversation://missing"});
+        let read = handle_read_resource(&server.state, request("resources/read", Some(missing))).await;
+        assert_eq!(read.error.unwrap().code, -32002);
+    }
+
     #[test]
     fn test_json_rpc_request() {
         let request = JsonRpcRequest {
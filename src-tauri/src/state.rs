@@ -0,0 +1,537 @@
+/// État de l'application et sa construction
+///
+/// L'état est construit de la même façon par la fenêtre Tauri, la CLI
+/// (`agent-cli`) et les tests, les emplacements de la base et des modèles
+/// pouvant être choisis ; les événements vers l'interface et les tâches de fond
+/// sont branchés ensuite par `run()`.
+
+use crate::llm::{EngineHandle, GenerationScheduler, LLMEngine, LLMConfig, ModelManager, SwitchTracker};
+use crate::llm::model_manager::models_dir_from_env;
+use crate::huggingface::{HuggingFaceClient, ResponseCache};
+use crate::mcp::{self, ContainerRunner, SecretStore, ToolApprovals, ToolRegistry};
+use crate::tasks::{ScheduleRepository, ScheduleRunner, TaskRepository};
+use crate::eval::EvalRepository;
+use crate::context::knowledge::KnowledgeIndexer;
+use crate::context::{AgentRepository, ApiTokenRepository, KnowledgeRepository, PipelineRepository, PresetRepository, ToolAuditRepository, MemoryRepository, WorkspaceRepository, StatsRepository, DownloadedModelRepository, ImportedModelRepository, ModelUsageRepository, CipherState, Database, SettingsRepository, ContextManager, ConversationRepository, SharedCipher, ProfileStore, DEFAULT_PROFILE, get_default_database_path};
+use crate::context::database::DB_PATH_ENV;
+use crate::configuration::{default_config_path, FileConfig};
+use crate::logging;
+
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, error, warn};
+
+/// État global de l'application
+pub struct AppState {
+    pub llm_engine: EngineHandle,
+    pub generation_scheduler: GenerationScheduler,
+    pub model_manager: Arc<ModelManager>,
+    /// Changements de modèle en cours ou récents
+    pub model_switches: SwitchTracker,
+    pub hf_client: Arc<RwLock<HuggingFaceClient>>,
+    pub database: Arc<Database>,
+    pub content_cipher: SharedCipher,
+    pub settings_repo: Arc<SettingsRepository>,
+    pub agent_repo: Arc<AgentRepository>,
+    /// Modèles de sessions (prompt, modèle, échantillonnage, outils)
+    pub preset_repo: Arc<PresetRepository>,
+    pub memory_repo: Arc<MemoryRepository>,
+    pub workspace_repo: Arc<WorkspaceRepository>,
+    /// Dossiers de la base de connaissances et leurs extraits indexés
+    pub knowledge_repo: Arc<KnowledgeRepository>,
+    /// Réindexation en tâche de fond des dossiers de la base de connaissances
+    pub knowledge_indexer: KnowledgeIndexer,
+    /// Temps et énergie estimée des générations
+    pub stats_repo: Arc<StatsRepository>,
+    /// Dépôt et révision HuggingFace des modèles téléchargés
+    pub downloads_repo: Arc<DownloadedModelRepository>,
+    /// Fichiers GGUF locaux copiés ou liés dans le dossier des modèles
+    pub imports_repo: Arc<ImportedModelRepository>,
+    /// Chargements et favoris des modèles locaux
+    pub model_usage_repo: Arc<ModelUsageRepository>,
+    pub task_repo: Arc<TaskRepository>,
+    pub schedule_repo: Arc<ScheduleRepository>,
+    pub schedule_runner: ScheduleRunner,
+    /// Suites d'évaluation et résultats de leurs exécutions
+    pub eval_repo: Arc<EvalRepository>,
+    pub context_manager: Arc<RwLock<ContextManager>>,
+    pub secret_store: Arc<SecretStore>,
+    pub tool_registry: Arc<RwLock<ToolRegistry>>,
+    pub tool_approvals: ToolApprovals,
+    /// Signalements d'injection de prompt et décisions de l'utilisateur
+    pub tool_audit_repo: Arc<ToolAuditRepository>,
+    /// Jetons d'accès au serveur local et leur portée
+    pub api_token_repo: Arc<ApiTokenRepository>,
+    /// Enchaînements d'outils exposés comme outils synthétiques
+    pub pipeline_repo: Arc<PipelineRepository>,
+    pub log_handle: Option<logging::LogHandle>,
+    /// Fichier `agents-rs.toml` lu au démarrage et son contenu
+    pub config_file: Option<PathBuf>,
+    pub file_config: FileConfig,
+    /// Profil d'utilisateur dont la base est ouverte
+    pub profile: String,
+    pub profile_store: Option<ProfileStore>,
+}
+
+/// Emplacements et journalisation de l'état construit par `AppState::initialize`
+pub struct AppConfig {
+    /// URL SQLite (`sqlite://...` ou `sqlite::memory:`), base de l'application si absente
+    pub database_url: Option<String>,
+    /// Dossier des modèles, celui de l'application si absent
+    pub models_dir: Option<PathBuf>,
+    /// Fichier `agents-rs.toml` (None pour n'en lire aucun) ; `database_url` et
+    /// `models_dir` l'emportent sur ses valeurs
+    pub config_file: Option<PathBuf>,
+    /// Profil d'utilisateur (le profil actif si absent), dont la base est ouverte
+    /// sauf si `database_url` ou `AGENTS_RS_DB_PATH` en désignent une autre
+    pub profile: Option<String>,
+    pub profile_store: Option<ProfileStore>,
+    /// Journalisation installée par l'appelant (aucune dans les tests)
+    pub log_handle: Option<logging::LogHandle>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AppConfig {
+    /// Emplacements par défaut de l'application, sans journalisation
+    pub fn new() -> Self {
+        Self {
+            database_url: None,
+            models_dir: None,
+            config_file: default_config_path(),
+            profile: None,
+            profile_store: ProfileStore::default_location(),
+            log_handle: None,
+        }
+    }
+
+    /// Niveau de log et logs récents pilotés par l'application
+    pub fn with_log_handle(mut self, log_handle: logging::LogHandle) -> Self {
+        self.log_handle = Some(log_handle);
+        self
+    }
+
+    pub fn with_database_url(mut self, database_url: impl Into<String>) -> Self {
+        self.database_url = Some(database_url.into());
+        self
+    }
+
+    pub fn with_models_dir(mut self, models_dir: impl Into<PathBuf>) -> Self {
+        self.models_dir = Some(models_dir.into());
+        self
+    }
+
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Dossier des profils, ou aucun (profil par défaut seulement)
+    pub fn with_profile_store(mut self, profile_store: Option<ProfileStore>) -> Self {
+        self.profile_store = profile_store;
+        self
+    }
+
+    /// Lit un autre fichier de configuration, ou aucun
+    pub fn with_config_file(mut self, config_file: Option<PathBuf>) -> Self {
+        self.config_file = config_file;
+        self
+    }
+}
+
+impl AppState {
+    /// Construit l'état de l'application : moteur, client HuggingFace, base,
+    /// dépôts et outils, avec les réglages enregistrés
+    ///
+    /// S'exécute dans le runtime de l'appelant (Tauri, CLI ou test).
+    pub async fn initialize(config: AppConfig) -> Result<AppState> {
+        let AppConfig { database_url, models_dir, config_file, profile, profile_store, log_handle } = config;
+        
+        // Valeurs par défaut du fichier de configuration, sous les réglages enregistrés
+        let file_config = match config_file.as_deref().map(FileConfig::load).transpose() {
+            Ok(file_config) => file_config.unwrap_or_default(),
+            Err(e) => {
+                warn!("Fichier de configuration ignoré: {:#}", e);
+                FileConfig::default()
+            }
+        };
+        
+        // Initialiser les composants backend
+        let models_dir = models_dir
+            .or_else(models_dir_from_env)
+            .or_else(|| file_config.models_dir.clone());
+        let model_manager = match models_dir {
+            Some(models_dir) => ModelManager::with_directory(models_dir),
+            None => ModelManager::new(),
+        };
+        let model_manager = Arc::new(model_manager.map_err(|e| {
+            error!("Failed to initialize model manager: {}", e);
+            e
+        })?);
+        
+        let llm_config = LLMConfig::default();
+        let llm_engine = match LLMEngine::new(llm_config).and_then(EngineHandle::spawn) {
+            Ok(handle) => handle,
+            Err(e) => {
+                error!("Erreur lors de l'initialisation du moteur LLM: {}", e);
+                return Err(e);
+            }
+        };
+
+        // Initialize HuggingFace client
+        let mut hf_client = HuggingFaceClient::from_env().map_err(|e| {
+            error!("Failed to initialize HuggingFace client: {}", e);
+            e
+        })?;
+        // Dernières réponses conservées pour le mode hors ligne
+        if let Some(directory) = ResponseCache::default_directory() {
+            hf_client = hf_client.with_response_cache(ResponseCache::new(directory));
+        }
+        let hf_client = Arc::new(RwLock::new(hf_client));
+        
+        // Initialize Database and Settings
+        let content_cipher = SharedCipher::default();
+        // Profil choisi au dernier changement, sauf demande explicite
+        let profile = match (profile, &profile_store) {
+            // Un profil demandé explicitement doit exister (son nom devient un dossier)
+            (Some(profile), Some(store)) => {
+                store.ensure_exists(&profile)?;
+                profile
+            }
+            (Some(profile), None) => profile,
+            (None, Some(store)) => store.active().unwrap_or_else(|e| {
+                warn!("Profil actif illisible, profil par défaut: {:#}", e);
+                DEFAULT_PROFILE.to_string()
+            }),
+            (None, None) => DEFAULT_PROFILE.to_string(),
+        };
+        info!("Profil: {}", profile);
+        let profile_database = profile_store.as_ref()
+            .and_then(|store| store.database_url(&profile))
+            .filter(|_| std::env::var_os(DB_PATH_ENV).is_none());
+        let database = match database_url.or(profile_database) {
+            // Une base demandée explicitement doit s'ouvrir
+            Some(db_url) => {
+                info!("Database URL: {}", db_url);
+                Database::new(&db_url).await?
+            }
+            None => open_default_database().await,
+        };
+        
+        // Run migrations
+        if let Err(e) = database.migrate().await {
+            error!("Database migration failed: {}", e);
+        }
+        
+        let pool = database.pool().clone();
+        let settings_repo = SettingsRepository::new(pool.clone());
+        
+        // Get current model or use default
+        let current_model = settings_repo.get_current_model().await
+            .unwrap_or(None)
+            .or_else(|| file_config.default_model.clone())
+            .unwrap_or_else(|| "No model loaded".to_string());
+        
+        // Threads choisis par l'utilisateur (un par cœur physique par défaut)
+        if let Ok(threads) = settings_repo.get_thread_settings().await {
+            llm_engine.update_config(|config| threads.apply_to(&mut config.model));
+        }
+        info!("Threads du moteur: {}", llm_engine.config().model.n_threads);
+        
+        // Nettoyage des réponses (blocs de raisonnement, balises du template, markdown)
+        if let Ok(postprocess) = settings_repo.get_postprocess_config().await {
+            llm_engine.update_config(|config| config.postprocess = postprocess);
+        }
+        if let Ok(pacing_ms) = settings_repo.get_stream_pacing_ms().await {
+            llm_engine.update_config(|config| config.stream_pacing_ms = pacing_ms);
+        }
+        
+        // Mode hors ligne : HuggingFace n'est plus interrogé
+        if let Ok(true) = settings_repo.get_hf_offline().await {
+            info!("Mode hors ligne activé");
+            hf_client.read().await.set_offline(true);
+        }
+        
+        // Miroir et proxy pour HuggingFace
+        if let Ok(mut connection) = settings_repo.get_hf_connection().await {
+            if connection.proxy.is_none() {
+                connection.proxy = file_config.proxy.clone();
+            }
+            if connection != Default::default() {
+                if let Err(e) = hf_client.write().await.set_connection(connection) {
+                    warn!("Connexion HuggingFace enregistrée ignorée: {:#}", e);
+                }
+            }
+        }
+        if let Ok(Some(rate_limit)) = settings_repo.get_hf_rate_limit().await {
+            hf_client.read().await.set_rate_limit(rate_limit);
+        }
+        
+        // Niveau de log choisi par l'utilisateur (RUST_LOG reste prioritaire)
+        if std::env::var_os("RUST_LOG").is_none() {
+            let level = settings_repo.get_log_level().await.ok().flatten()
+                .or_else(|| file_config.log_level.clone());
+            if let (Some(level), Some(log_handle)) = (level, &log_handle) {
+                if let Err(e) = log_handle.set_level(&level) {
+                    warn!("Niveau de log enregistré ignoré: {:#}", e);
+                }
+            }
+        }
+        
+        // Base chiffrée : le contenu reste verrouillé jusqu'à la saisie de la phrase de passe
+        if let Ok(Some(_)) = settings_repo.get_encryption_params().await {
+            info!("Database is encrypted, waiting for passphrase");
+            *content_cipher.write().unwrap() = CipherState::Locked;
+        }
+        
+        // Create ConversationRepository and ContextManager
+        let conv_repo = ConversationRepository::new(pool.clone()).with_cipher(content_cipher.clone());
+        let context_manager = Arc::new(RwLock::new(ContextManager::new(conv_repo, current_model)));
+        
+        let agent_repo = Arc::new(AgentRepository::new(pool.clone()));
+        let memory_repo = Arc::new(MemoryRepository::new(pool.clone()).with_cipher(content_cipher.clone()));
+        let workspace_repo = Arc::new(WorkspaceRepository::new(pool.clone()));
+        let task_repo = Arc::new(TaskRepository::new(pool.clone()).with_cipher(content_cipher.clone()));
+        let schedule_repo = Arc::new(ScheduleRepository::new(pool.clone()));
+        
+        // File d'attente des générations (un seul moteur local chargé à la fois)
+        let generation_scheduler = GenerationScheduler::new(1);
+        
+        // Secrets des outils dans le trousseau du système
+        let secret_store = Arc::new(SecretStore::keyring());
+        // Les outils sensibles attendent la confirmation de l'utilisateur
+        let tool_approvals = ToolApprovals::new();
+        let tool_audit_repo = Arc::new(ToolAuditRepository::new(pool.clone()));
+        let api_token_repo = Arc::new(ApiTokenRepository::new(pool.clone()));
+        let mut registry = ToolRegistry::new()
+            .with_secret_store(Arc::clone(&secret_store))
+            .with_approvals(tool_approvals.clone())
+            .with_audit(Arc::clone(&tool_audit_repo));
+        // Presse-papiers, capture d'écran, Git, exécution de code, génération et historique
+        // (les actions sensibles sont soumises à confirmation)
+        // run_code dans un conteneur éphémère si activé et disponible
+        let container_settings = settings_repo.get_container_settings().await.unwrap_or_default();
+        let container_runner = ContainerRunner::detect(&container_settings).await;
+        let builtin_tools = mcp::desktop::desktop_tools()
+            .into_iter()
+            .chain(mcp::git::git_tools())
+            .chain([mcp::code::create_run_code_tool(container_runner)])
+            // Le modèle local, pour les clients MCP externes
+            .chain([mcp::generate::create_generate_text_tool(
+                Arc::new(llm_engine.clone()),
+                Some(generation_scheduler.clone()),
+            )])
+            .chain(mcp::conversations::conversation_tools(Arc::clone(&context_manager)))
+            // Bloc-notes de session, hors de la conversation visible
+            .chain(mcp::scratchpad::scratchpad_tools(Arc::new(
+                ConversationRepository::new(pool.clone()).with_cipher(content_cipher.clone()),
+            )));
+        for tool in builtin_tools {
+            registry.register_tool(tool)?;
+        }
+        // Connecteurs d'API déclarés par l'utilisateur
+        if let Ok(connectors) = settings_repo.get_api_connectors().await {
+            for connector in connectors.iter().filter(|c| c.validate().is_ok()) {
+                if let Err(e) = registry.register_connector(connector) {
+                    warn!("Connecteur ignoré: {:#}", e);
+                }
+            }
+        }
+        // Pipelines d'outils déclarés par l'utilisateur
+        let pipeline_repo = Arc::new(PipelineRepository::new(pool.clone()));
+        if let Ok(pipelines) = pipeline_repo.list().await {
+            for pipeline in pipelines.into_iter().filter(|p| p.validate().is_ok()) {
+                if let Err(e) = registry.register_pipeline(pipeline) {
+                    warn!("Pipeline ignoré: {:#}", e);
+                }
+            }
+        }
+        // Limites d'exécution des outils enregistrées par l'utilisateur
+        if let Ok(policies) = settings_repo.get_tool_policies().await {
+            for (name, policy) in policies {
+                registry.set_policy(&name, Some(policy));
+            }
+        }
+        if let Ok(injection) = settings_repo.get_injection_settings().await {
+            registry.set_injection_settings(injection);
+        }
+        let tool_registry = Arc::new(RwLock::new(registry));
+        
+        // Tâches planifiées : résultats enregistrés dans des conversations dédiées
+        let schedule_runner = ScheduleRunner::new(
+            Arc::new(llm_engine.clone()),
+            Arc::clone(&schedule_repo),
+            Arc::clone(&agent_repo),
+            Arc::clone(&task_repo),
+            Arc::clone(&tool_registry),
+            Arc::clone(&context_manager),
+        )
+        .with_scheduler(generation_scheduler.clone());
+        
+        Ok(AppState {
+            llm_engine,
+            generation_scheduler,
+            model_manager,
+            model_switches: SwitchTracker::new(),
+            hf_client,
+            content_cipher,
+            settings_repo: Arc::new(settings_repo),
+            agent_repo,
+            preset_repo: Arc::new(PresetRepository::new(pool.clone())),
+            memory_repo,
+            workspace_repo,
+            knowledge_repo: Arc::new(KnowledgeRepository::new(pool.clone())),
+            knowledge_indexer: KnowledgeIndexer::new(),
+            stats_repo: Arc::new(StatsRepository::new(pool.clone())),
+            downloads_repo: Arc::new(DownloadedModelRepository::new(pool.clone())),
+            imports_repo: Arc::new(ImportedModelRepository::new(pool.clone())),
+            model_usage_repo: Arc::new(ModelUsageRepository::new(pool.clone())),
+            task_repo,
+            schedule_repo,
+            schedule_runner,
+            eval_repo: Arc::new(EvalRepository::new(pool)),
+            context_manager,
+            secret_store,
+            tool_registry,
+            tool_approvals,
+            tool_audit_repo,
+            api_token_repo,
+            pipeline_repo,
+            database: Arc::new(database),
+            log_handle,
+            config_file,
+            file_config,
+            profile,
+            profile_store,
+        })
+    }
+}
+
+/// État lu par les commandes Tauri, remplacé au changement de profil
+///
+/// Chaque commande prend l'état courant à son début : une commande en cours
+/// pendant un remplacement termine avec l'ancien état.
+pub struct SharedState {
+    current: std::sync::RwLock<Arc<AppState>>,
+    /// Tâches de fond branchées sur l'état courant, arrêtées avec lui
+    tasks: std::sync::Mutex<Vec<tauri::async_runtime::JoinHandle<()>>>,
+}
+
+impl SharedState {
+    pub fn new(state: Arc<AppState>, tasks: Vec<tauri::async_runtime::JoinHandle<()>>) -> Self {
+        Self {
+            current: std::sync::RwLock::new(state),
+            tasks: std::sync::Mutex::new(tasks),
+        }
+    }
+
+    /// État courant
+    pub fn get(&self) -> Arc<AppState> {
+        Arc::clone(&self.current.read().unwrap())
+    }
+
+    /// Arrête les tâches de fond de l'état courant
+    pub async fn stop_tasks(&self) {
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        for task in tasks {
+            task.abort();
+            // Attendre l'arrêt libère leurs ressources (port du serveur MCP...)
+            let _ = task.await;
+        }
+    }
+
+    /// Remplace l'état courant, renvoie l'ancien
+    pub fn replace(&self, state: Arc<AppState>, tasks: Vec<tauri::async_runtime::JoinHandle<()>>) -> Arc<AppState> {
+        *self.tasks.lock().unwrap() = tasks;
+        std::mem::replace(&mut *self.current.write().unwrap(), state)
+    }
+}
+
+/// Base de l'application, en mémoire si elle ne peut pas être ouverte
+async fn open_default_database() -> Database {
+    let db_url = match get_default_database_path() {
+        Ok(url) => {
+            info!("Database URL: {}", url);
+            url
+        },
+        Err(e) => {
+            error!("Failed to get database path, using in-memory: {}", e);
+            "sqlite::memory:".to_string()
+        }
+    };
+    
+    match Database::new(&db_url).await {
+        Ok(db) => db,
+        Err(e) => {
+            error!("Failed to create database, falling back to in-memory: {}", e);
+            Database::new("sqlite::memory:").await
+                .expect("Failed to create in-memory database")
+        }
+    }
+}
+
+/// État de test : base en mémoire et dossier de modèles temporaire, supprimé avec lui
+#[cfg(test)]
+pub(crate) struct TestState {
+    state: Arc<AppState>,
+    models_dir: PathBuf,
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TestState {
+    type Target = Arc<AppState>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.state
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestState {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.models_dir);
+    }
+}
+
+/// Construit un état sans Tauri pour les tests
+#[cfg(test)]
+pub(crate) async fn test_state() -> TestState {
+    let models_dir = std::env::temp_dir().join(format!("agents-rs-models-{}", uuid::Uuid::new_v4()));
+    let config = AppConfig::new()
+        .with_database_url("sqlite::memory:")
+        .with_models_dir(models_dir.clone())
+        .with_config_file(None)
+        .with_profile_store(None);
+    let state = Arc::new(AppState::initialize(config).await.unwrap());
+    TestState { state, models_dir }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_initialize_without_tauri() {
+        let state = test_state().await;
+        assert_eq!(state.profile, DEFAULT_PROFILE);
+        assert!(state.log_handle.is_none());
+        assert!(!state.llm_engine.is_loaded());
+
+        let session_id = state.context_manager.read().await
+            .create_session("Test".to_string()).await.unwrap();
+        let sessions = state.context_manager.read().await.list_sessions().await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, session_id);
+        assert!(state.tool_registry.read().await.list_tools().iter().any(|tool| tool.name == "scratchpad_set"));
+
+        // Un second état (changement de profil) partage le moteur llama.cpp
+        let other = test_state().await;
+        assert!(other.context_manager.read().await.list_sessions().await.unwrap().is_empty());
+    }
+}
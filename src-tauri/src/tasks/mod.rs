@@ -0,0 +1,21 @@
+/// Autonomous tasks - multi-step runs driven by the model
+///
+/// A `TaskRunner` pursues a goal in a plan / act / observe loop: the model picks
+/// a tool from the `ToolRegistry`, the tool output is fed back to it, until it
+/// gives a final answer or a limit is reached. Every run is traced in the
+/// `task_runs` table. Schedules trigger prompts or task runs from cron expressions.
+
+pub mod models;
+pub mod repository;
+pub mod runner;
+pub mod schedule_runner;
+pub mod schedules;
+
+#[cfg(test)]
+pub(crate) mod testing;
+
+pub use models::{TaskAction, TaskEvent, TaskLimits, TaskRun, TaskStatus, TaskStep};
+pub use repository::TaskRepository;
+pub use runner::TaskRunner;
+pub use schedule_runner::{ScheduleEvent, ScheduleRunner};
+pub use schedules::{ScheduleRepository, ScheduledJob, TaskSchedule};
@@ -0,0 +1,148 @@
+/// Task run types
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Limits of a single run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TaskLimits {
+    /// Maximum number of model calls
+    pub max_iterations: u32,
+    /// Maximum number of tokens generated over the whole run
+    pub max_tokens: usize,
+}
+
+impl Default for TaskLimits {
+    fn default() -> Self {
+        Self {
+            max_iterations: 10,
+            max_tokens: 8192,
+        }
+    }
+}
+
+/// State of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Running,
+    /// The model gave a final answer
+    Completed,
+    /// The model failed (tool errors are fed back to the model instead)
+    Failed,
+    IterationLimitReached,
+    BudgetExceeded,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Running => "running",
+            TaskStatus::Completed => "completed",
+            TaskStatus::Failed => "failed",
+            TaskStatus::IterationLimitReached => "iteration_limit_reached",
+            TaskStatus::BudgetExceeded => "budget_exceeded",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "running" => Some(TaskStatus::Running),
+            "completed" => Some(TaskStatus::Completed),
+            "failed" => Some(TaskStatus::Failed),
+            "iteration_limit_reached" => Some(TaskStatus::IterationLimitReached),
+            "budget_exceeded" => Some(TaskStatus::BudgetExceeded),
+            _ => None,
+        }
+    }
+}
+
+/// Action chosen by the model at a step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskAction {
+    Tool {
+        name: String,
+        arguments: serde_json::Value,
+    },
+    Final {
+        answer: String,
+    },
+}
+
+/// One step of the trace
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TaskStep {
+    /// Step number (starting at 1)
+    pub index: u32,
+    /// Reasoning given by the model before acting
+    pub thought: Option<String>,
+    /// None when the model response could not be parsed
+    pub action: Option<TaskAction>,
+    /// Tool output or error fed back to the model
+    pub observation: Option<String>,
+    #[ts(type = "number")]
+    pub tokens: usize,
+}
+
+/// A task run and its trace
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TaskRun {
+    pub id: String,
+    pub goal: String,
+    pub agent_id: Option<String>,
+    pub status: TaskStatus,
+    pub steps: Vec<TaskStep>,
+    /// Final answer of the model
+    pub result: Option<String>,
+    pub error: Option<String>,
+    pub iterations: u32,
+    #[ts(type = "number")]
+    pub tokens_used: usize,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TaskRun {
+    pub fn new(goal: String, agent_id: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            goal,
+            agent_id,
+            status: TaskStatus::Running,
+            steps: vec![],
+            result: None,
+            error: None,
+            iterations: 0,
+            tokens_used: 0,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Progress sent to the UI while a task runs
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskEvent {
+    Started {
+        run_id: String,
+        goal: String,
+    },
+    Step {
+        run_id: String,
+        step: TaskStep,
+    },
+    Finished {
+        run_id: String,
+        status: TaskStatus,
+        result: Option<String>,
+        error: Option<String>,
+    },
+}
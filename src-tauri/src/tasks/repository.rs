@@ -0,0 +1,232 @@
+/// Persistence of task runs and their traces
+///
+/// The goal, final answer and trace of a run follow the conversation encryption.
+/// While the database is locked they are masked, so runs can still be listed.
+
+use super::models::{TaskRun, TaskStatus};
+use crate::context::{CipherState, ContentCipher, SharedCipher};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use tracing::info;
+
+/// Shown in place of the encrypted fields of a run while the database is locked
+const LOCKED: &str = "[locked]";
+
+pub struct TaskRepository {
+    pool: SqlitePool,
+    cipher: SharedCipher,
+}
+
+impl TaskRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool, cipher: SharedCipher::default() }
+    }
+
+    /// Share the encryption state with the rest of the application
+    pub fn with_cipher(mut self, cipher: SharedCipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    fn cipher(&self) -> CipherState {
+        self.cipher.read().unwrap().clone()
+    }
+
+    /// Insert or update a run (saved after every step so an interrupted run keeps its trace)
+    pub async fn save(&self, run: &TaskRun) -> Result<()> {
+        let _writing = self.cipher.lock_writes().await;
+        let cipher = self.cipher();
+        let goal = cipher.seal(&run.goal)?;
+        let result = run.result.as_deref().map(|r| cipher.seal(r)).transpose()?;
+        let trace = cipher.seal(&serde_json::to_string(&run.steps)?)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO task_runs (id, goal, agent_id, status, result, error, trace, iterations, tokens_used, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                status = excluded.status,
+                result = excluded.result,
+                error = excluded.error,
+                trace = excluded.trace,
+                iterations = excluded.iterations,
+                tokens_used = excluded.tokens_used,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&run.id)
+        .bind(goal)
+        .bind(&run.agent_id)
+        .bind(run.status.as_str())
+        .bind(result)
+        .bind(&run.error)
+        .bind(trace)
+        .bind(run.iterations as i64)
+        .bind(run.tokens_used as i64)
+        .bind(run.created_at.timestamp())
+        .bind(run.updated_at.timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save task run")?;
+
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<TaskRun>> {
+        let row = sqlx::query("SELECT * FROM task_runs WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch task run")?;
+
+        row.map(|row| Self::from_row(&row, &self.cipher())).transpose()
+    }
+
+    /// Most recent runs first
+    pub async fn list(&self, limit: i64) -> Result<Vec<TaskRun>> {
+        let rows = sqlx::query("SELECT * FROM task_runs ORDER BY created_at DESC LIMIT ?")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list task runs")?;
+
+        let cipher = self.cipher();
+        rows.iter().map(|row| Self::from_row(row, &cipher)).collect()
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM task_runs WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete task run")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Rewrite the goal, result and trace of every run from one encryption state
+    /// to another, inside the caller's transaction
+    pub async fn reencrypt(conn: &mut SqliteConnection, from: &CipherState, to: &CipherState) -> Result<usize> {
+        let rows: Vec<(String, String, Option<String>, String)> = sqlx::query_as("SELECT id, goal, result, trace FROM task_runs")
+            .fetch_all(&mut *conn)
+            .await
+            .context("Failed to fetch task runs")?;
+
+        for (id, goal, result, trace) in &rows {
+            let result = result.as_deref().map(|r| to.seal(&from.open(r)?)).transpose()?;
+            sqlx::query("UPDATE task_runs SET goal = ?, result = ?, trace = ? WHERE id = ?")
+                .bind(to.seal(&from.open(goal)?)?)
+                .bind(result)
+                .bind(to.seal(&from.open(trace)?)?)
+                .bind(id)
+                .execute(&mut *conn)
+                .await
+                .context("Failed to update task run")?;
+        }
+
+        info!("Rewrote {} task runs", rows.len());
+        Ok(rows.len())
+    }
+
+    fn from_row(row: &SqliteRow, cipher: &CipherState) -> Result<TaskRun> {
+        let status: String = row.get("status");
+        let goal: String = row.get("goal");
+        let result: Option<String> = row.get("result");
+        let trace: String = row.get("trace");
+        // Encrypted fields can't be read before unlocking: mask them
+        let open = |value: &str| match cipher {
+            CipherState::Locked if ContentCipher::is_encrypted(value) => Ok(None),
+            _ => cipher.open(value).map(Some),
+        };
+        let steps = match open(&trace)? {
+            Some(trace) => serde_json::from_str(&trace).context("Invalid task trace")?,
+            None => vec![],
+        };
+        let iterations: i64 = row.get("iterations");
+        let tokens_used: i64 = row.get("tokens_used");
+        let created_at: i64 = row.get("created_at");
+        let updated_at: i64 = row.get("updated_at");
+
+        Ok(TaskRun {
+            id: row.get("id"),
+            goal: open(&goal)?.unwrap_or_else(|| LOCKED.to_string()),
+            agent_id: row.get("agent_id"),
+            status: TaskStatus::parse(&status)
+                .ok_or_else(|| anyhow::anyhow!("Unknown task status: {}", status))?,
+            steps,
+            result: result.as_deref().map(open).transpose()?.map(|r| r.unwrap_or_else(|| LOCKED.to_string())),
+            error: row.get("error"),
+            iterations: iterations as u32,
+            tokens_used: tokens_used as usize,
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_else(Utc::now),
+            updated_at: DateTime::from_timestamp(updated_at, 0).unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{ContentCipher, Database};
+
+    #[tokio::test]
+    async fn test_encrypted_runs() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let repo = TaskRepository::new(db.pool().clone());
+
+        let mut run = TaskRun::new("Summarize the changelog".to_string(), None);
+        run.result = Some("Nothing new".to_string());
+        repo.save(&run).await.unwrap();
+
+        let (cipher, _) = ContentCipher::create("secret").unwrap();
+        let unlocked = CipherState::Unlocked(cipher);
+        let mut tx = db.pool().begin().await.unwrap();
+        assert_eq!(TaskRepository::reencrypt(&mut tx, &CipherState::Disabled, &unlocked).await.unwrap(), 1);
+        tx.commit().await.unwrap();
+
+        let (goal, result, trace): (String, String, String) = sqlx::query_as("SELECT goal, result, trace FROM task_runs")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert!(ContentCipher::is_encrypted(&goal));
+        assert!(ContentCipher::is_encrypted(&result));
+        assert!(ContentCipher::is_encrypted(&trace));
+        assert!(repo.get(&run.id).await.is_err());
+
+        let shared = SharedCipher::default();
+        *shared.write().unwrap() = unlocked;
+        let repo = TaskRepository::new(db.pool().clone()).with_cipher(shared);
+        let loaded = repo.get(&run.id).await.unwrap().unwrap();
+        assert_eq!(loaded.goal, "Summarize the changelog");
+        assert_eq!(loaded.result.as_deref(), Some("Nothing new"));
+        assert!(loaded.steps.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_runs_while_locked() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let (cipher, _) = ContentCipher::create("secret").unwrap();
+        let shared = SharedCipher::default();
+        *shared.write().unwrap() = CipherState::Unlocked(cipher);
+        let repo = TaskRepository::new(db.pool().clone()).with_cipher(shared.clone());
+
+        let mut run = TaskRun::new("Summarize the changelog".to_string(), None);
+        run.result = Some("Nothing new".to_string());
+        run.status = TaskStatus::Completed;
+        repo.save(&run).await.unwrap();
+
+        *shared.write().unwrap() = CipherState::Locked;
+        let runs = repo.list(10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].id, run.id);
+        assert_eq!(runs[0].status, TaskStatus::Completed);
+        assert_eq!(runs[0].goal, LOCKED);
+        assert_eq!(runs[0].result.as_deref(), Some(LOCKED));
+        assert!(runs[0].steps.is_empty());
+        assert!(repo.save(&run).await.is_err());
+    }
+}
@@ -0,0 +1,393 @@
+/// Plan / act / observe loop
+
+use super::models::{TaskAction, TaskEvent, TaskLimits, TaskRun, TaskStatus, TaskStep};
+use super::repository::TaskRepository;
+use crate::context::{AgentProfile, Workspace};
+use crate::llm::{ChatMessage, ChatRole, GenerationPriority, GenerationScheduler, LLMBackend};
+use crate::mcp::{Tool, ToolRegistry};
+use anyhow::Result;
+use chrono::Utc;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// Tool outputs are cut to this many characters before being fed back to the model
+const MAX_OBSERVATION_CHARS: usize = 4000;
+
+/// Runs goals step by step with the tools of the registry
+pub struct TaskRunner {
+    backend: Arc<dyn LLMBackend>,
+    tools: Arc<RwLock<ToolRegistry>>,
+    repository: Arc<TaskRepository>,
+    scheduler: Option<GenerationScheduler>,
+    limits: TaskLimits,
+    workspace: Option<Workspace>,
+}
+
+impl TaskRunner {
+    pub fn new(
+        backend: Arc<dyn LLMBackend>,
+        tools: Arc<RwLock<ToolRegistry>>,
+        repository: Arc<TaskRepository>,
+    ) -> Self {
+        Self {
+            backend,
+            tools,
+            repository,
+            scheduler: None,
+            limits: TaskLimits::default(),
+            workspace: None,
+        }
+    }
+
+    /// Queue every model call behind the interactive generations
+    pub fn with_scheduler(mut self, scheduler: GenerationScheduler) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    pub fn with_limits(mut self, limits: TaskLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Run inside a workspace: its context is given to the model and file tools are limited to its root
+    pub fn with_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// Pursue a goal until a final answer or a limit, reporting each step to `on_event`
+    ///
+    /// With an agent, its system prompt is used and only its allowed tools are offered.
+    pub async fn run<F>(&self, goal: &str, agent: Option<&AgentProfile>, mut on_event: F) -> Result<TaskRun>
+    where
+        F: FnMut(&TaskEvent),
+    {
+        let tools = self.available_tools(agent).await;
+        let mut instructions = system_prompt(agent, &tools);
+        if let Some(workspace) = &self.workspace {
+            instructions.push_str("\n\n");
+            instructions.push_str(&workspace.context_prompt().await);
+        }
+        let mut messages = vec![
+            ChatMessage::system(instructions),
+            ChatMessage::user(format!("Goal: {}", goal)),
+        ];
+
+        let mut run = TaskRun::new(goal.to_string(), agent.map(|a| a.id.clone()));
+        self.repository.save(&run).await?;
+        info!("Task {} started: {}", run.id, goal);
+        on_event(&TaskEvent::Started {
+            run_id: run.id.clone(),
+            goal: run.goal.clone(),
+        });
+
+        run.status = loop {
+            if run.iterations >= self.limits.max_iterations {
+                break TaskStatus::IterationLimitReached;
+            }
+            if run.tokens_used >= self.limits.max_tokens {
+                break TaskStatus::BudgetExceeded;
+            }
+
+            let response = {
+                let _permit = match &self.scheduler {
+                    Some(scheduler) => Some(scheduler.acquire(None, GenerationPriority::Normal).await?),
+                    None => None,
+                };
+                self.backend.chat(&messages).await
+            };
+            let response = match response {
+                Ok(response) => response,
+                Err(e) => {
+                    run.error = Some(format!("{:#}", e));
+                    break TaskStatus::Failed;
+                }
+            };
+
+            run.iterations += 1;
+            run.tokens_used += response.tokens_generated;
+            messages.push(ChatMessage::assistant(response.text.clone()));
+
+            let mut step = TaskStep {
+                index: run.iterations,
+                thought: None,
+                action: None,
+                observation: None,
+                tokens: response.tokens_generated,
+            };
+
+            let finished = match parse_action(&response.text) {
+                Ok((thought, action)) => {
+                    step.thought = thought;
+                    let finished = match &action {
+                        TaskAction::Final { answer } => {
+                            run.result = Some(answer.clone());
+                            true
+                        }
+                        TaskAction::Tool { name, arguments } => {
+                            let observation = self.execute_tool(&tools, name, arguments.clone(), &run.id).await;
+                            messages.push(ChatMessage::new(ChatRole::Tool, observation.clone()));
+                            step.observation = Some(observation);
+                            false
+                        }
+                    };
+                    step.action = Some(action);
+                    finished
+                }
+                Err(e) => {
+                    let observation = format!(
+                        "Invalid response: {}. Reply with a single JSON object as described.",
+                        e
+                    );
+                    messages.push(ChatMessage::user(observation.clone()));
+                    step.observation = Some(observation);
+                    false
+                }
+            };
+
+            run.steps.push(step.clone());
+            run.updated_at = Utc::now();
+            self.repository.save(&run).await?;
+            on_event(&TaskEvent::Step {
+                run_id: run.id.clone(),
+                step,
+            });
+
+            if finished {
+                break TaskStatus::Completed;
+            }
+        };
+
+        // "Always allow" answers and the scratchpad only last for this run
+        let tools = self.tools.read().await.snapshot();
+        tools.end_scope(&run.id).await;
+
+        run.updated_at = Utc::now();
+        self.repository.save(&run).await?;
+        info!(
+            "Task {} finished: {} after {} iterations ({} tokens)",
+            run.id,
+            run.status.as_str(),
+            run.iterations,
+            run.tokens_used
+        );
+        on_event(&TaskEvent::Finished {
+            run_id: run.id.clone(),
+            status: run.status,
+            result: run.result.clone(),
+            error: run.error.clone(),
+        });
+
+        Ok(run)
+    }
+
+    /// Tools offered to the model (all of them without an agent)
+    async fn available_tools(&self, agent: Option<&AgentProfile>) -> Vec<Tool> {
+        let mut tools: Vec<Tool> = self
+            .tools
+            .read()
+            .await
+            .list_tools()
+            .into_iter()
+            .filter(|tool| agent.is_none_or(|a| a.allows_tool(&tool.name)))
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+
+    /// Execute a tool call, errors are returned as the observation so the model can recover
+    ///
+    /// Tools requiring approval are confirmed per run (`run_id` is the approval scope).
+    async fn execute_tool(&self, tools: &[Tool], name: &str, arguments: serde_json::Value, run_id: &str) -> String {
+        if !tools.iter().any(|tool| tool.name == name) {
+            return format!("Error: unknown or unavailable tool '{}'", name);
+        }
+
+        let root = self.workspace.as_ref().map(Workspace::root);
+        // The registry lock is released before the call, which may wait for an approval
+        let registry = self.tools.read().await.snapshot();
+        let output = match registry.execute_tool_in_root(name, arguments, run_id, root).await {
+            Ok(output) => output,
+            Err(e) => {
+                warn!("Task tool {} failed: {:#}", name, e);
+                format!("Error: {:#}", e)
+            }
+        };
+
+        truncate_observation(output)
+    }
+}
+
+fn truncate_observation(output: String) -> String {
+    match output.char_indices().nth(MAX_OBSERVATION_CHARS) {
+        Some((cut, _)) => format!("{}\n[output truncated]", &output[..cut]),
+        None => output,
+    }
+}
+
+/// Instructions describing the available tools and the expected response format
+fn system_prompt(agent: Option<&AgentProfile>, tools: &[Tool]) -> String {
+    let mut prompt = String::new();
+    if let Some(agent) = agent.filter(|a| !a.system_prompt.is_empty()) {
+        prompt.push_str(&agent.system_prompt);
+        prompt.push_str("\n\n");
+    }
+
+    prompt.push_str(
+        "You complete the user's goal step by step. At each step, reply with a single JSON object and nothing else.\n\
+         To call a tool: {\"thought\": \"...\", \"tool\": \"<name>\", \"arguments\": {...}}\n\
+         When the goal is reached: {\"thought\": \"...\", \"final_answer\": \"...\"}\n\
+         The result of each tool call is sent back to you before the next step.\n\n",
+    );
+
+    if tools.is_empty() {
+        prompt.push_str("No tools are available: give your final answer directly.");
+    } else {
+        prompt.push_str("Available tools:\n");
+        for tool in tools {
+            prompt.push_str(&format!("- {}: {} (arguments schema: {})\n", tool.name, tool.description, tool.input_schema));
+        }
+    }
+
+    prompt
+}
+
+#[derive(Deserialize)]
+struct RawAction {
+    thought: Option<String>,
+    tool: Option<String>,
+    #[serde(default)]
+    arguments: serde_json::Value,
+    final_answer: Option<String>,
+}
+
+/// Extract the JSON action from a model response (tolerates surrounding text or code fences)
+fn parse_action(text: &str) -> Result<(Option<String>, TaskAction)> {
+    let (start, end) = match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => (start, end),
+        _ => anyhow::bail!("no JSON object found"),
+    };
+
+    let raw: RawAction = serde_json::from_str(&text[start..=end])
+        .map_err(|e| anyhow::anyhow!("malformed JSON ({})", e))?;
+
+    let action = match (raw.final_answer, raw.tool) {
+        (Some(answer), _) => TaskAction::Final { answer },
+        (None, Some(name)) => TaskAction::Tool {
+            name,
+            arguments: if raw.arguments.is_null() { serde_json::json!({}) } else { raw.arguments },
+        },
+        (None, None) => anyhow::bail!("expected either \"tool\" or \"final_answer\""),
+    };
+
+    Ok((raw.thought, action))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{AgentRepository, Database};
+    use crate::tasks::testing::ScriptedBackend;
+
+    async fn setup_runner(backend: Arc<ScriptedBackend>) -> (TaskRunner, Database) {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let runner = TaskRunner::new(
+            backend,
+            Arc::new(RwLock::new(ToolRegistry::new())),
+            Arc::new(TaskRepository::new(db.pool().clone())),
+        );
+        (runner, db)
+    }
+
+    #[test]
+    fn test_parse_action() {
+        let (thought, action) = parse_action(
+            "```json\n{\"thought\": \"say it\", \"tool\": \"echo\", \"arguments\": {\"text\": \"hi\"}}\n```",
+        )
+        .unwrap();
+        assert_eq!(thought.as_deref(), Some("say it"));
+        assert_eq!(
+            action,
+            TaskAction::Tool { name: "echo".to_string(), arguments: serde_json::json!({"text": "hi"}) }
+        );
+
+        let (_, action) = parse_action(r#"{"final_answer": "done"}"#).unwrap();
+        assert_eq!(action, TaskAction::Final { answer: "done".to_string() });
+
+        assert!(parse_action("I will use the echo tool").is_err());
+        assert!(parse_action(r#"{"thought": "hmm"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_uses_tools_and_records_trace() {
+        let backend = ScriptedBackend::new(&[
+            r#"{"thought": "echo first", "tool": "echo", "arguments": {"text": "hi"}}"#,
+            "not json",
+            r#"{"final_answer": "The tool said hi"}"#,
+        ]);
+        let (runner, _db) = setup_runner(backend).await;
+
+        let mut events = vec![];
+        let run = runner.run("Say hi", None, |event| events.push(event.clone())).await.unwrap();
+
+        assert_eq!(run.status, TaskStatus::Completed);
+        assert_eq!(run.result.as_deref(), Some("The tool said hi"));
+        assert_eq!(run.steps[0].observation.as_deref(), Some("Echo: hi"));
+        assert!(run.steps[1].action.is_none());
+        assert_eq!(run.tokens_used, 30);
+        // Started + 3 steps + Finished
+        assert_eq!(events.len(), 5);
+
+        let stored = runner.repository.get(&run.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, TaskStatus::Completed);
+        assert_eq!(stored.steps.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_limits_and_agent_tools() {
+        let call = r#"{"tool": "echo", "arguments": {"text": "again"}}"#;
+        let (runner, _db) = setup_runner(ScriptedBackend::new(&[call, call, call])).await;
+        let runner = runner.with_limits(TaskLimits { max_iterations: 2, max_tokens: 1000 });
+
+        let run = runner.run("Loop", None, |_| {}).await.unwrap();
+        assert_eq!(run.status, TaskStatus::IterationLimitReached);
+        assert_eq!(run.iterations, 2);
+
+        // An agent without the echo tool cannot call it
+        let (runner, db) = setup_runner(ScriptedBackend::new(&[call])).await;
+        let runner = runner.with_limits(TaskLimits { max_iterations: 5, max_tokens: 10 });
+        let agent = AgentProfile::new("Restricted".to_string(), String::new());
+        AgentRepository::new(db.pool().clone()).save(&agent).await.unwrap();
+
+        let run = runner.run("Loop", Some(&agent), |_| {}).await.unwrap();
+        assert_eq!(run.status, TaskStatus::BudgetExceeded);
+        assert!(run.steps[0].observation.as_deref().unwrap().contains("unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_scratchpad_lasts_for_the_run() {
+        let backend = ScriptedBackend::new(&[
+            r#"{"tool": "scratchpad_set", "arguments": {"key": "plan", "value": "1. search"}}"#,
+            r#"{"tool": "scratchpad_get", "arguments": {"key": "plan"}}"#,
+            r#"{"final_answer": "done"}"#,
+        ]);
+        let (runner, db) = setup_runner(backend).await;
+        let conversations = Arc::new(crate::context::ConversationRepository::new(db.pool().clone()));
+        {
+            let mut tools = runner.tools.write().await;
+            for tool in crate::mcp::scratchpad::scratchpad_tools(Arc::clone(&conversations)) {
+                tools.register_tool(tool).unwrap();
+            }
+        }
+
+        let run = runner.run("Plan", None, |_| {}).await.unwrap();
+        assert_eq!(run.status, TaskStatus::Completed);
+        assert_eq!(run.steps[1].observation.as_deref(), Some("1. search"));
+        // Cleared once the run is over
+        assert!(conversations.get_session_state(&run.id).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+/// Background execution of due schedules
+
+use super::models::TaskLimits;
+use super::repository::TaskRepository;
+use super::runner::TaskRunner;
+use super::schedules::{next_occurrence, ScheduleRepository, ScheduledJob, TaskSchedule};
+use crate::context::{AgentProfile, AgentRepository, ContextManager, Message, MessageRole};
+use crate::llm::{ChatMessage, GenerationPriority, GenerationScheduler, LLMBackend};
+use crate::mcp::ToolRegistry;
+use anyhow::Result;
+use chrono::{Local, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{error, info};
+
+/// Interval between two checks for due schedules
+pub const SCHEDULE_TICK: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Sent after each scheduled run
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleEvent {
+    pub schedule_id: String,
+    pub conversation_id: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Runs schedules and saves their results into dedicated conversations
+#[derive(Clone)]
+pub struct ScheduleRunner {
+    backend: Arc<dyn LLMBackend>,
+    schedules: Arc<ScheduleRepository>,
+    agents: Arc<AgentRepository>,
+    tasks: Arc<TaskRepository>,
+    tools: Arc<RwLock<ToolRegistry>>,
+    context_manager: Arc<RwLock<ContextManager>>,
+    scheduler: Option<GenerationScheduler>,
+    events: broadcast::Sender<ScheduleEvent>,
+}
+
+impl ScheduleRunner {
+    pub fn new(
+        backend: Arc<dyn LLMBackend>,
+        schedules: Arc<ScheduleRepository>,
+        agents: Arc<AgentRepository>,
+        tasks: Arc<TaskRepository>,
+        tools: Arc<RwLock<ToolRegistry>>,
+        context_manager: Arc<RwLock<ContextManager>>,
+    ) -> Self {
+        let (events, _) = broadcast::channel(16);
+        Self {
+            backend,
+            schedules,
+            agents,
+            tasks,
+            tools,
+            context_manager,
+            scheduler: None,
+            events,
+        }
+    }
+
+    /// Queue scheduled generations behind the interactive ones
+    pub fn with_scheduler(mut self, scheduler: GenerationScheduler) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ScheduleEvent> {
+        self.events.subscribe()
+    }
+
+    /// Run every due schedule once, returns the number of runs
+    ///
+    /// Occurrences missed while the app was closed are caught up by a single run.
+    pub async fn run_due(&self) -> Result<usize> {
+        let due = self.schedules.due(Utc::now()).await?;
+        let count = due.len();
+        for schedule in due {
+            self.run_schedule(schedule).await?;
+        }
+        Ok(count)
+    }
+
+    /// Run a schedule now and plan its next occurrence
+    pub async fn run_schedule(&self, mut schedule: TaskSchedule) -> Result<TaskSchedule> {
+        info!("Running schedule {} ({})", schedule.name, schedule.id);
+
+        let result = self.execute(&mut schedule).await;
+        if let Err(e) = &result {
+            error!("Schedule {} failed: {:#}", schedule.name, e);
+        }
+
+        let now = Utc::now();
+        schedule.last_run_at = Some(now);
+        schedule.last_error = result.err().map(|e| format!("{:#}", e));
+        schedule.next_run_at = next_occurrence(&schedule.cron, &Local, now).ok();
+        self.schedules.save(&schedule).await?;
+
+        let _ = self.events.send(ScheduleEvent {
+            schedule_id: schedule.id.clone(),
+            conversation_id: schedule.conversation_id.clone(),
+            error: schedule.last_error.clone(),
+        });
+        Ok(schedule)
+    }
+
+    async fn execute(&self, schedule: &mut TaskSchedule) -> Result<()> {
+        let agent = match &schedule.agent_id {
+            Some(id) => self.agents.get(id).await?,
+            None => None,
+        };
+        let conversation_id = self.ensure_conversation(schedule, agent.as_ref()).await?;
+
+        let (request, answer) = match &schedule.job {
+            ScheduledJob::Prompt { prompt } => {
+                let context_manager = self.context_manager.read().await;
+                context_manager
+                    .add_message(&conversation_id, Message::new(MessageRole::User, prompt.clone()))
+                    .await?;
+                let session = context_manager.get_session(&conversation_id).await?;
+                drop(context_manager);
+
+                let messages: Vec<ChatMessage> = session.messages.iter().map(ChatMessage::from).collect();
+                let _permit = match &self.scheduler {
+                    Some(scheduler) => Some(scheduler.acquire(Some(&conversation_id), GenerationPriority::Low).await?),
+                    None => None,
+                };
+                (None, self.backend.chat(&messages).await?.text)
+            }
+            ScheduledJob::Task { goal, max_iterations } => {
+                let mut limits = TaskLimits::default();
+                if let Some(max_iterations) = max_iterations {
+                    limits.max_iterations = *max_iterations;
+                }
+                let mut runner = TaskRunner::new(
+                    Arc::clone(&self.backend),
+                    Arc::clone(&self.tools),
+                    Arc::clone(&self.tasks),
+                )
+                .with_limits(limits);
+                if let Some(scheduler) = &self.scheduler {
+                    runner = runner.with_scheduler(scheduler.clone());
+                }
+
+                let run = runner.run(goal, agent.as_ref(), |_| {}).await?;
+                let answer = run.result.unwrap_or_else(|| {
+                    format!(
+                        "Task ended without an answer ({}){}",
+                        run.status.as_str(),
+                        run.error.map(|e| format!(": {}", e)).unwrap_or_default()
+                    )
+                });
+                (Some(goal.clone()), answer)
+            }
+        };
+
+        let context_manager = self.context_manager.read().await;
+        if let Some(request) = request {
+            context_manager
+                .add_message(&conversation_id, Message::new(MessageRole::User, request))
+                .await?;
+        }
+        context_manager
+            .add_message(&conversation_id, Message::new(MessageRole::Assistant, answer))
+            .await?;
+        Ok(())
+    }
+
+    /// Conversation receiving the results, recreated if it was deleted
+    async fn ensure_conversation(&self, schedule: &mut TaskSchedule, agent: Option<&AgentProfile>) -> Result<String> {
+        let context_manager = self.context_manager.read().await;
+        if let Some(id) = &schedule.conversation_id {
+            if context_manager.get_session(id).await.is_ok() {
+                return Ok(id.clone());
+            }
+        }
+
+        let id = context_manager.create_detached_session(schedule.name.clone(), agent).await?;
+        schedule.conversation_id = Some(id.clone());
+        Ok(id)
+    }
+
+    /// Background task: run due schedules at a regular interval
+    pub async fn run_periodic(self) {
+        let mut interval = tokio::time::interval(SCHEDULE_TICK);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.run_due().await {
+                error!("Scheduled tasks check failed: {:#}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{ConversationRepository, Database};
+    use crate::tasks::testing::ScriptedBackend;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_next_occurrence() {
+        let after = Utc.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap();
+        let next = next_occurrence("0 8 * * *", &Utc, after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2026, 1, 2, 8, 0, 0).unwrap());
+
+        assert!(next_occurrence("every morning", &Utc, after).is_err());
+        assert!(TaskSchedule::new("Bad".to_string(), "61 * * * *".to_string(), ScheduledJob::Prompt {
+            prompt: String::new(),
+        })
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_due_schedule_writes_to_its_conversation() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        let pool = db.pool().clone();
+        let schedules = Arc::new(ScheduleRepository::new(pool.clone()));
+        let context_manager = Arc::new(RwLock::new(ContextManager::new(
+            ConversationRepository::new(pool.clone()),
+            "model".to_string(),
+        )));
+        let runner = ScheduleRunner::new(
+            ScriptedBackend::new(&["Morning summary", "Second summary"]),
+            Arc::clone(&schedules),
+            Arc::new(AgentRepository::new(pool.clone())),
+            Arc::new(TaskRepository::new(pool)),
+            Arc::new(RwLock::new(ToolRegistry::new())),
+            Arc::clone(&context_manager),
+        );
+
+        let mut schedule = TaskSchedule::new(
+            "Daily summary".to_string(),
+            "0 8 * * *".to_string(),
+            ScheduledJob::Prompt { prompt: "Summarize".to_string() },
+        )
+        .unwrap();
+        schedule.next_run_at = Some(Utc::now() - Duration::minutes(1));
+        schedules.save(&schedule).await.unwrap();
+
+        assert_eq!(runner.run_due().await.unwrap(), 1);
+        assert_eq!(runner.run_due().await.unwrap(), 0);
+
+        let stored = schedules.get(&schedule.id).await.unwrap().unwrap();
+        assert!(stored.last_error.is_none());
+        assert!(stored.next_run_at.unwrap() > Utc::now());
+
+        // The next run reuses the same conversation
+        let conversation_id = stored.conversation_id.clone().unwrap();
+        runner.run_schedule(stored).await.unwrap();
+        let session = context_manager.read().await.get_session(&conversation_id).await.unwrap();
+        assert_eq!(session.messages.len(), 4);
+        assert_eq!(session.messages[1].content, "Morning summary");
+
+        // Paused schedules are never due
+        let mut paused = schedules.get(&schedule.id).await.unwrap().unwrap();
+        paused.enabled = false;
+        paused.next_run_at = Some(Utc::now() - Duration::minutes(1));
+        schedules.save(&paused).await.unwrap();
+        assert_eq!(runner.run_due().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,192 @@
+/// Scheduled tasks - prompts or task runs triggered by a cron expression
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use tracing::info;
+use ts_rs::TS;
+
+/// Work done when a schedule fires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduledJob {
+    /// Send a prompt in the schedule's conversation
+    Prompt { prompt: String },
+    /// Run a goal with the task runner, its final answer is saved in the conversation
+    Task {
+        goal: String,
+        max_iterations: Option<u32>,
+    },
+}
+
+/// A recurring job
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TaskSchedule {
+    pub id: String,
+    pub name: String,
+    /// Standard 5-field cron expression, evaluated in local time (e.g. "0 8 * * *")
+    pub cron: String,
+    pub job: ScheduledJob,
+    pub agent_id: Option<String>,
+    /// Dedicated conversation receiving the results (created on the first run)
+    pub conversation_id: Option<String>,
+    pub enabled: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TaskSchedule {
+    /// Create an enabled schedule, fails if the cron expression is invalid
+    pub fn new(name: String, cron: String, job: ScheduledJob) -> Result<Self> {
+        let now = Utc::now();
+        let next_run_at = next_occurrence(&cron, &Local, now)?;
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            cron,
+            job,
+            agent_id: None,
+            conversation_id: None,
+            enabled: true,
+            last_run_at: None,
+            last_error: None,
+            next_run_at: Some(next_run_at),
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+/// Next time strictly after `after` matching the expression, evaluated in `timezone`
+pub fn next_occurrence<Tz: TimeZone>(cron: &str, timezone: &Tz, after: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let pattern = Cron::new(cron)
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid cron expression '{}': {}", cron, e))?;
+    let next = pattern
+        .find_next_occurrence(&after.with_timezone(timezone), false)
+        .map_err(|e| anyhow::anyhow!("No upcoming occurrence for '{}': {}", cron, e))?;
+    Ok(next.with_timezone(&Utc))
+}
+
+/// Persistence of schedules
+pub struct ScheduleRepository {
+    pool: SqlitePool,
+}
+
+impl ScheduleRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert or update a schedule
+    pub async fn save(&self, schedule: &TaskSchedule) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO schedules (id, name, cron, job, agent_id, conversation_id, enabled, last_run_at, last_error, next_run_at, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                cron = excluded.cron,
+                job = excluded.job,
+                agent_id = excluded.agent_id,
+                conversation_id = excluded.conversation_id,
+                enabled = excluded.enabled,
+                last_run_at = excluded.last_run_at,
+                last_error = excluded.last_error,
+                next_run_at = excluded.next_run_at,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&schedule.id)
+        .bind(&schedule.name)
+        .bind(&schedule.cron)
+        .bind(serde_json::to_string(&schedule.job)?)
+        .bind(&schedule.agent_id)
+        .bind(&schedule.conversation_id)
+        .bind(schedule.enabled)
+        .bind(schedule.last_run_at.map(|t| t.timestamp()))
+        .bind(&schedule.last_error)
+        .bind(schedule.next_run_at.map(|t| t.timestamp()))
+        .bind(schedule.created_at.timestamp())
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save schedule")?;
+
+        info!("Saved schedule: {} ({})", schedule.name, schedule.id);
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<TaskSchedule>> {
+        let row = sqlx::query("SELECT * FROM schedules WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch schedule")?;
+
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    pub async fn list(&self) -> Result<Vec<TaskSchedule>> {
+        let rows = sqlx::query("SELECT * FROM schedules ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list schedules")?;
+
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// Enabled schedules whose next run is at or before `now`
+    pub async fn due(&self, now: DateTime<Utc>) -> Result<Vec<TaskSchedule>> {
+        let rows = sqlx::query(
+            "SELECT * FROM schedules WHERE enabled = 1 AND next_run_at IS NOT NULL AND next_run_at <= ? ORDER BY next_run_at",
+        )
+        .bind(now.timestamp())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch due schedules")?;
+
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM schedules WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete schedule")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<TaskSchedule> {
+        let job: String = row.get("job");
+        let last_run_at: Option<i64> = row.get("last_run_at");
+        let next_run_at: Option<i64> = row.get("next_run_at");
+        let created_at: i64 = row.get("created_at");
+        let updated_at: i64 = row.get("updated_at");
+
+        Ok(TaskSchedule {
+            id: row.get("id"),
+            name: row.get("name"),
+            cron: row.get("cron"),
+            job: serde_json::from_str(&job).context("Invalid scheduled job")?,
+            agent_id: row.get("agent_id"),
+            conversation_id: row.get("conversation_id"),
+            enabled: row.get("enabled"),
+            last_run_at: last_run_at.and_then(|t| DateTime::from_timestamp(t, 0)),
+            last_error: row.get("last_error"),
+            next_run_at: next_run_at.and_then(|t| DateTime::from_timestamp(t, 0)),
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_else(Utc::now),
+            updated_at: DateTime::from_timestamp(updated_at, 0).unwrap_or_else(Utc::now),
+        })
+    }
+}
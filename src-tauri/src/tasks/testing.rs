@@ -0,0 +1,48 @@
+/// Test helpers shared by the task modules
+
+use crate::llm::{ChatMessage, LLMBackend, LLMResponse};
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Backend replaying canned responses
+pub struct ScriptedBackend {
+    responses: Mutex<VecDeque<String>>,
+}
+
+impl ScriptedBackend {
+    pub fn new(responses: &[&str]) -> Arc<Self> {
+        Arc::new(Self {
+            responses: Mutex::new(responses.iter().map(|r| r.to_string()).collect()),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl LLMBackend for ScriptedBackend {
+    fn name(&self) -> String {
+        "scripted".to_string()
+    }
+
+    async fn is_ready(&self) -> bool {
+        true
+    }
+
+    async fn chat(&self, _messages: &[ChatMessage]) -> Result<LLMResponse> {
+        let text = self
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| anyhow::anyhow!("no more responses"))?;
+        Ok(LLMResponse {
+            text,
+            tool_calls: vec![],
+            tokens_generated: 10,
+            done: true,
+            truncated_tokens: 0,
+            logprobs: None,
+            reasoning: None,
+        })
+    }
+}
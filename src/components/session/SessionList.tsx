@@ -8,7 +8,9 @@ import {
   MessageSquare
 } from 'lucide-react';
 import { cn } from '@/lib/utils';
-import { useState } from 'react';
+import { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import type { StartupRestoredEvent } from '@/types';
 import {
   AlertDialog,
   AlertDialogAction,
@@ -27,8 +29,26 @@ export function SessionList() {
     selectSession, 
     deleteSession, 
     createSession,
+    applyGeneratedTitle,
     isLoading 
   } = useSessionStore();
+
+  useEffect(() => {
+    // Title generated from the first exchange of a new conversation
+    const unlisten = listen<{ session_id: string; title: string }>('session-title', (event) => {
+      applyGeneratedTitle(event.payload.session_id, event.payload.title);
+    });
+    // Last active session reopened at startup (when enabled in the settings)
+    const unlistenStartup = listen<StartupRestoredEvent>('startup-restored', (event) => {
+      if (event.payload.session_id) {
+        selectSession(event.payload.session_id);
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+      unlistenStartup.then((fn) => fn());
+    };
+  }, []);
   
   const [deleteDialogOpen, setDeleteDialogOpen] = useState(false);
   const [sessionToDelete, setSessionToDelete] = useState<string | null>(null);
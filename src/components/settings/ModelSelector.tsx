@@ -1,9 +1,20 @@
 import { useEffect, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { Button } from '@/components/ui/button';
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from '@/components/ui/card';
 import { Loader2, Check } from 'lucide-react';
 import { useLLMStore } from '@/stores/llm';
+import type { SwitchStage, SwitchStatus } from '@/types';
+
+const STAGE_LABELS: Record<SwitchStage, string> = {
+  queued: 'Waiting...',
+  unloading: 'Unloading previous model...',
+  loading: 'Loading model...',
+  warming_up: 'Warming up...',
+  completed: 'Done',
+  failed: 'Failed',
+};
 
 interface ModelInfo {
   name: string;
@@ -16,11 +27,20 @@ export function ModelSelector() {
   const [models, setModels] = useState<ModelInfo[]>([]);
   const [loading, setLoading] = useState(true);
   const [switching, setSwitching] = useState(false);
+  const [switchStage, setSwitchStage] = useState<SwitchStage | null>(null);
   const [error, setError] = useState<string | null>(null);
   const { setModelName } = useLLMStore();
 
   useEffect(() => {
     loadModels();
+
+    // Models added or removed outside the app
+    const unlisten = listen('models-changed', () => {
+      loadModels();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   const loadModels = async () => {
@@ -41,8 +61,41 @@ export function ModelSelector() {
     try {
       setSwitching(true);
       setError(null);
-      const result = await invoke<string>('switch_model', { modelName: fileName });
-      console.log(result);
+      setSwitchStage('queued');
+
+      // The command returns right away, progress arrives as events
+      let taskId: string | null = null;
+      const pending: SwitchStatus[] = [];
+      let finish: (status: SwitchStatus) => void = () => {};
+      const finished = new Promise<SwitchStatus>((resolve) => {
+        finish = resolve;
+      });
+      const handle = (status: SwitchStatus) => {
+        setSwitchStage(status.stage);
+        if (status.stage === 'completed' || status.stage === 'failed') {
+          finish(status);
+        }
+      };
+      const unlisten = await listen<SwitchStatus>('model-switch-progress', (event) => {
+        if (taskId === null) {
+          pending.push(event.payload);
+        } else if (event.payload.task_id === taskId) {
+          handle(event.payload);
+        }
+      });
+
+      let status: SwitchStatus;
+      try {
+        taskId = await invoke<string>('switch_model', { modelName: fileName });
+        pending.filter((s) => s.task_id === taskId).forEach(handle);
+        status = await finished;
+      } finally {
+        unlisten();
+      }
+
+      if (status.stage === 'failed') {
+        throw new Error(status.error?.message ?? status.error?.kind ?? 'Failed to switch model');
+      }
       
       // Update the LLM store with the new model name
       const displayName = fileName.replace('.gguf', '');
@@ -55,6 +108,7 @@ export function ModelSelector() {
       console.error('Error switching model:', err);
     } finally {
       setSwitching(false);
+      setSwitchStage(null);
     }
   };
 
@@ -85,6 +139,13 @@ export function ModelSelector() {
         <CardDescription>Choose which LLM model to use</CardDescription>
       </CardHeader>
       <CardContent className="space-y-3">
+        {switchStage && (
+          <div className="flex items-center gap-2 text-sm text-muted-foreground">
+            <Loader2 className="h-4 w-4 animate-spin" />
+            {STAGE_LABELS[switchStage]}
+          </div>
+        )}
+
         {error && (
           <div className="rounded-md bg-destructive/10 p-3 text-sm text-destructive">
             {error}
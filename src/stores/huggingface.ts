@@ -1,7 +1,7 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import type { HFModel, HFModelInfo, HFSearchParams, GGUFModelMetadata, GGUFFile } from '@/types';
+import type { HFModel, HFModelInfo, HFModelSuggestion, HFModelUpdate, HFSearchParams, GGUFModelMetadata, GGUFFile } from '@/types';
 
 interface HuggingFaceState {
   // Search state
@@ -9,6 +9,9 @@ interface HuggingFaceState {
   isSearching: boolean;
   searchError: string | null;
 
+  // Type-ahead suggestions
+  suggestions: HFModelSuggestion[];
+
   // GGUF discovery state
   ggufModels: GGUFModelMetadata[];
   isDiscoveringGGUF: boolean;
@@ -29,26 +32,40 @@ interface HuggingFaceState {
   downloadProgress: number;
   downloadError: string | null;
 
+  // Updates of the models downloaded from the Hub
+  modelUpdates: HFModelUpdate[];
+
   // Token state
   hasToken: boolean;
 
+  // Offline mode (searches and model info served from the disk cache)
+  isOffline: boolean;
+
   // Actions
   searchModels: (params: HFSearchParams) => Promise<void>;
+  autocompleteModels: (prefix: string) => Promise<void>;
   discoverGGUFModels: (params?: HFSearchParams) => Promise<void>;
   getGGUFFiles: (repoId: string) => Promise<void>;
   getModelInfo: (repoId: string) => Promise<void>;
   downloadModel: (repoId: string, filename: string, revision?: string) => Promise<string>;
+  downloadModelFromUrl: (url: string, filename: string) => Promise<string>;
+  checkModelUpdates: () => Promise<HFModelUpdate[]>;
+  updateModel: (update: HFModelUpdate) => Promise<string>;
   setToken: (token: string) => Promise<void>;
+  loadOfflineMode: () => Promise<void>;
+  setOfflineMode: (enabled: boolean) => Promise<void>;
   clearSearch: () => void;
   clearSelectedModel: () => void;
 }
 
-export const useHuggingFaceStore = create<HuggingFaceState>((set) => ({
+export const useHuggingFaceStore = create<HuggingFaceState>((set, get) => ({
   // Initial state
   searchResults: [],
   isSearching: false,
   searchError: null,
 
+  suggestions: [],
+
   ggufModels: [],
   isDiscoveringGGUF: false,
   ggufDiscoveryError: null,
@@ -65,8 +82,12 @@ export const useHuggingFaceStore = create<HuggingFaceState>((set) => ({
   downloadProgress: 0,
   downloadError: null,
 
+  modelUpdates: [],
+
   hasToken: false,
 
+  isOffline: false,
+
   // Search models
   searchModels: async (params: HFSearchParams) => {
     set({ isSearching: true, searchError: null });
@@ -87,6 +108,19 @@ export const useHuggingFaceStore = create<HuggingFaceState>((set) => ({
     }
   },
 
+  // Type-ahead suggestions (the backend debounces and answers null for superseded queries)
+  autocompleteModels: async (prefix: string) => {
+    try {
+      const suggestions = await invoke<HFModelSuggestion[] | null>('hf_autocomplete_models', { prefix });
+      if (suggestions !== null) {
+        set({ suggestions });
+      }
+    } catch (error) {
+      console.error('[HF Store] Autocomplete failed:', error);
+      set({ suggestions: [] });
+    }
+  },
+
   // Discover GGUF models
   discoverGGUFModels: async (params?: HFSearchParams) => {
     set({ isDiscoveringGGUF: true, ggufDiscoveryError: null });
@@ -180,6 +214,53 @@ export const useHuggingFaceStore = create<HuggingFaceState>((set) => ({
     }
   },
 
+  // Download a GGUF file from a direct link (outside the Hub)
+  downloadModelFromUrl: async (url: string, filename: string) => {
+    set({ isDownloading: true, downloadError: null, downloadProgress: 0 });
+
+    const unlisten = await listen<{
+      url?: string;
+      filename: string;
+      progress: number;
+    }>('download-progress', (event) => {
+      if (event.payload.url === url && event.payload.filename === filename) {
+        set({ downloadProgress: event.payload.progress });
+      }
+    });
+
+    try {
+      const path = await invoke<string>('download_model_from_url', { url, filename });
+      set({ isDownloading: false, downloadProgress: 100 });
+      return path;
+    } catch (error) {
+      const errorMessage = error instanceof Error ? error.message : String(error);
+      set({ downloadError: errorMessage, isDownloading: false, downloadProgress: 0 });
+      throw error;
+    } finally {
+      unlisten();
+    }
+  },
+
+  // Compare the downloaded models to the latest revision of their repository
+  checkModelUpdates: async () => {
+    const modelUpdates = await invoke<HFModelUpdate[]>('check_model_updates');
+    set({ modelUpdates });
+    return modelUpdates;
+  },
+
+  // Download the latest revision of a model (replaces the file once complete)
+  updateModel: async (update: HFModelUpdate) => {
+    const path = await get().downloadModel(update.repo_id, update.filename);
+    set((state) => ({
+      modelUpdates: state.modelUpdates.map((u) =>
+        u.filename === update.filename
+          ? { ...u, revision: u.latest_revision, update_available: false }
+          : u
+      ),
+    }));
+    return path;
+  },
+
   // Set authentication token
   setToken: async (token: string) => {
     try {
@@ -191,9 +272,20 @@ export const useHuggingFaceStore = create<HuggingFaceState>((set) => ({
     }
   },
 
+  // Offline mode
+  loadOfflineMode: async () => {
+    const isOffline = await invoke<boolean>('hf_get_offline_mode');
+    set({ isOffline });
+  },
+
+  setOfflineMode: async (enabled: boolean) => {
+    await invoke('hf_set_offline_mode', { enabled });
+    set({ isOffline: enabled });
+  },
+
   // Clear search results
   clearSearch: () => {
-    set({ searchResults: [], searchError: null });
+    set({ searchResults: [], searchError: null, suggestions: [] });
   },
 
   // Clear selected model
@@ -1,12 +1,17 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useSessionStore } from './session';
-import type { Message } from '../types';
+import type { Message, ReasoningEvent, TokenEvent } from '../types';
 
 interface MessageState {
   // State
   messages: Message[];
   isGenerating: boolean;
+  // Reply being streamed (answer and reasoning shown separately)
+  streamingContent: string;
+  streamingReasoning: string;
+  isReasoning: boolean;
   error: string | null;
 
   // Actions
@@ -22,6 +27,9 @@ interface MessageState {
 export const useMessageStore = create<MessageState>((set) => ({
   messages: [],
   isGenerating: false,
+  streamingContent: '',
+  streamingReasoning: '',
+  isReasoning: false,
   error: null,
 
   loadMessages: async (sessionId: string) => {
@@ -50,21 +58,39 @@ export const useMessageStore = create<MessageState>((set) => ({
       id: `temp-${Date.now()}`,
       role: 'user',
       content,
-      timestamp: new Date().toISOString()
+      timestamp: new Date().toISOString(),
+      metadata: {}
     };
 
     set(state => ({ 
       messages: [...state.messages, tempMessage],
       error: null, 
-      isGenerating: true 
+      isGenerating: true,
+      streamingContent: '',
+      streamingReasoning: '',
+      isReasoning: false
     }));
 
+    // Chunks of the reply: the reasoning panel is open while the model thinks
+    const unlistenToken = await listen<TokenEvent>('llm-token', (event) => {
+      if (event.payload.session_id !== activeSessionId) return;
+      set(state => ({ streamingContent: state.streamingContent + event.payload.chunk }));
+    });
+    const unlistenReasoning = await listen<ReasoningEvent>('llm-reasoning', (event) => {
+      const { session_id, phase, chunk } = event.payload;
+      if (session_id !== activeSessionId) return;
+      set(state => ({
+        isReasoning: phase !== 'end',
+        streamingReasoning: state.streamingReasoning + (chunk ?? '')
+      }));
+    });
+
     try {
       // Le backend retourne les deux messages avec leurs vrais IDs
       const response = await invoke<{
         user_message: Message;
         assistant_message: Message;
-      }>('send_message', {
+      }>('send_message_stream', {
         sessionId: activeSessionId,
         content: content
       });
@@ -76,7 +102,10 @@ export const useMessageStore = create<MessageState>((set) => ({
           response.user_message,
           response.assistant_message
         ],
-        isGenerating: false 
+        isGenerating: false,
+        streamingContent: '',
+        streamingReasoning: '',
+        isReasoning: false
       }));
 
     } catch (error) {
@@ -86,8 +115,14 @@ export const useMessageStore = create<MessageState>((set) => ({
       set(state => ({
         messages: state.messages.filter(m => m.id !== tempMessage.id),
         error: error instanceof Error ? error.message : 'Failed to send message',
-        isGenerating: false
+        isGenerating: false,
+        streamingContent: '',
+        streamingReasoning: '',
+        isReasoning: false
       }));
+    } finally {
+      unlistenToken();
+      unlistenReasoning();
     }
   },
 
@@ -1,6 +1,6 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
-import type { SessionSummary, ConversationSession } from '../types';
+import type { SessionSummary, ConversationSession, Language, MessageMatch, SessionPreset, SessionStatus } from '../types';
 
 interface SessionState {
   // State
@@ -15,6 +15,14 @@ interface SessionState {
   selectSession: (id: string) => Promise<void>;
   deleteSession: (id: string) => Promise<void>;
   renameSession: (id: string, newTitle: string) => Promise<void>;
+  setSessionLanguage: (id: string, language: Language | null) => Promise<void>;
+  applyGeneratedTitle: (id: string, title: string) => void;
+  searchSessions: (query: string, k?: number) => Promise<MessageMatch[]>;
+  getSessionStatus: (id: string) => Promise<SessionStatus>;
+  listPresets: () => Promise<SessionPreset[]>;
+  createSessionFromPreset: (presetId: string) => Promise<string | null>;
+  saveDraft: (id: string, text: string) => Promise<void>;
+  getDraft: (id: string) => Promise<string | null>;
   clearError: () => void;
   
   // Getters
@@ -159,6 +167,88 @@ export const useSessionStore = create<SessionState>((set, get) => ({
     }
   },
 
+  setSessionLanguage: async (id: string, language: Language | null) => {
+    try {
+      await invoke('set_session_language', { sessionId: id, language });
+    } catch (error) {
+      console.error('Failed to set session language:', error);
+      set({ error: error instanceof Error ? error.message : 'Failed to set session language' });
+    }
+  },
+
+  applyGeneratedTitle: (id: string, title: string) => {
+    set(state => ({
+      sessions: state.sessions.map(s => s.id === id ? { ...s, title } : s)
+    }));
+  },
+
+  searchSessions: async (query: string, k?: number) => {
+    try {
+      return await invoke<MessageMatch[]>('semantic_search_conversations', { query, k });
+    } catch (error) {
+      console.error('Failed to search sessions:', error);
+      set({ error: error instanceof Error ? error.message : 'Failed to search sessions' });
+      return [];
+    }
+  },
+
+  listPresets: async () => {
+    try {
+      return await invoke<SessionPreset[]>('list_presets');
+    } catch (error) {
+      console.error('Failed to list presets:', error);
+      return [];
+    }
+  },
+
+  createSessionFromPreset: async (presetId: string) => {
+    set({ isLoading: true, error: null });
+    try {
+      const newSession = await invoke<ConversationSession>('create_session_from_preset', { presetId });
+
+      set(state => ({
+        sessions: [newSession, ...state.sessions],
+        activeSessionId: newSession.id,
+        isLoading: false
+      }));
+
+      return newSession.id;
+    } catch (error) {
+      console.error('Failed to create session from preset:', error);
+      set({
+        error: error instanceof Error ? error.message : 'Failed to create session from preset',
+        isLoading: false
+      });
+      return null;
+    }
+  },
+
+  getSessionStatus: async (id: string) => {
+    try {
+      return await invoke<SessionStatus>('get_session_status', { sessionId: id });
+    } catch (error) {
+      console.error('Failed to get session status:', error);
+      return { state: 'idle' };
+    }
+  },
+
+  saveDraft: async (id: string, text: string) => {
+    try {
+      await invoke('save_draft', { sessionId: id, text });
+    } catch (error) {
+      console.error('Failed to save draft:', error);
+    }
+  },
+
+  getDraft: async (id: string) => {
+    try {
+      return await invoke<string | null>('get_draft', { sessionId: id });
+    } catch (error) {
+      console.error('Failed to load draft:', error);
+      return null;
+    }
+  },
+
   clearError: () => set({ error: null }),
 
   getActiveSession: () => {
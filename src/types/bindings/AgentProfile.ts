@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GuardrailPolicy } from "./GuardrailPolicy";
+import type { SamplingParams } from "./SamplingParams";
+
+/**
+ * A reusable agent configuration a conversation can be started from
+ */
+export type AgentProfile = { id: string, name: string, system_prompt: string, 
+/**
+ * Model loaded when a conversation with this agent starts (None = keep the current one)
+ */
+default_model: string | null, 
+/**
+ * Names of the tools this agent may call (empty = no tools)
+ */
+allowed_tools: Array<string>, 
+/**
+ * Sampling overrides used for this agent's generations
+ */
+sampling: SamplingParams, 
+/**
+ * Guardrails replacing the global policy for this agent's replies
+ */
+guardrails: GuardrailPolicy | null, created_at: string, updated_at: string, };
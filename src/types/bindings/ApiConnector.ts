@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonValue } from "./serde_json/JsonValue";
+import type { ToolParameter } from "./ToolParameter";
+
+/**
+ * Définition d'un connecteur
+ */
+export type ApiConnector = { 
+/**
+ * Nom de l'outil
+ */
+name: string, description: string, method: string, base_url: string, 
+/**
+ * Chemin ajouté à l'URL de base (modèles encodés)
+ */
+path: string, query: { [key in string]?: string }, headers: { [key in string]?: string }, 
+/**
+ * Corps JSON ; une chaîne réduite à `{{nom}}` reçoit la valeur de l'argument telle quelle
+ */
+body: JsonValue | null, parameters: Array<ToolParameter>, 
+/**
+ * Partie de la réponse JSON renvoyée au modèle (toute la réponse sinon)
+ */
+result_path: string | null, 
+/**
+ * Confirmation avant chaque appel (par défaut pour toute méthode autre que GET)
+ */
+requires_approval: boolean | null, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Erreur commune à toutes les commandes
+ */
+export type AppError = { "kind": "model_not_found", model: string, } | { "kind": "no_model_loaded" } | { "kind": "model_load_failed", message: string, } | { "kind": "model_corrupt", model: string, message: string, 
+/**
+ * Action proposée à l'utilisateur
+ */
+suggestion: string, } | { "kind": "backend_upgrade_required", model: string, gguf_version: number, max_supported_version: number, 
+/**
+ * Action proposée à l'utilisateur
+ */
+suggestion: string, } | { "kind": "generation_failed", message: string, 
+/**
+ * Texte déjà généré avant l'échec
+ */
+partial_text: string | null, 
+/**
+ * Message assistant incomplet enregistré avec ce texte (`continue_generation`)
+ */
+message_id: number | null, } | { "kind": "context_overflow", prompt_tokens: number, n_ctx: number, } | { "kind": "session_not_found", session_id: string, } | { "kind": "database_error", message: string, } | { "kind": "database_locked" } | { "kind": "network_error", status: number | null, message: string, } | { "kind": "offline" } | { "kind": "missing_credentials", provider: string, } | { "kind": "invalid_input", message: string, } | { "kind": "internal", message: string, };
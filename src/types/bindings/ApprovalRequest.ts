@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonValue } from "./serde_json/JsonValue";
+
+/**
+ * Appel d'outil en attente de confirmation
+ */
+export type ApprovalRequest = { call_id: string, 
+/**
+ * Session (ou exécution de tâche) à l'origine de l'appel
+ */
+scope: string, tool_name: string, arguments: JsonValue, 
+/**
+ * Motif de la demande quand ce n'est pas l'appel lui-même (résultat suspect)
+ */
+reason: string | null, };
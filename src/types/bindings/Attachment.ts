@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AttachmentKind } from "./AttachmentKind";
+
+/**
+ * A file attached to a message, stored as a blob in the `attachments` table
+ */
+export type Attachment = { id: string, kind: AttachmentKind, file_name: string, mime_type: string, 
+/**
+ * Size of the content in bytes
+ */
+size: number, created_at: number, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Fichier joint à un message par l'interface
+ */
+export type AttachmentUpload = { file_name: string, mime_type: string, 
+/**
+ * Contenu encodé en base64
+ */
+data: string, };
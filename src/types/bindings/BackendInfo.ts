@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GgufVersionRange } from "./GgufVersionRange";
+
+/**
+ * llama.cpp bindings, compiled accelerations and runtime support
+ */
+export type BackendInfo = { bindings_version: string, 
+/**
+ * Accelerations compiled in (`cuda`, `metal`, `vulkan`), empty for CPU only
+ */
+features: Array<string>, gguf_versions: GgufVersionRange, 
+/**
+ * Devices llama.cpp can split a model across
+ */
+max_devices: number, mmap_supported: boolean, mlock_supported: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Backend selected for a session
+ */
+export type BackendKind = { "provider": "local" } | { "provider": "openai", model: string, } | { "provider": "anthropic", model: string, } | { "provider": "mock" };
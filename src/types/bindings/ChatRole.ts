@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Role of a message sent to a backend
+ */
+export type ChatRole = "system" | "user" | "assistant" | "tool";
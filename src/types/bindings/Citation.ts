@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A document, or a chunk of it, shown to the model under the marker `[source]`
+ */
+export type Citation = { source: number, attachment_id: string, document: string, 
+/**
+ * Index of the chunk (None when the whole document was included)
+ */
+chunk: number | null, 
+/**
+ * Character offsets of the source text in the document
+ */
+start: number, end: number, 
+/**
+ * Page of the PDF the chunk comes from (1-based)
+ */
+page?: number, };
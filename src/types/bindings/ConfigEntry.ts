@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConfigSource } from "./ConfigSource";
+
+/**
+ * Valeur retenue et la couche dont elle provient
+ */
+export type ConfigEntry<T> = { value: T | null, source: ConfigSource, };
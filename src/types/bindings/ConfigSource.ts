@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Origine d'une valeur de la configuration effective
+ */
+export type ConfigSource = "default" | "file" | "database" | "environment";
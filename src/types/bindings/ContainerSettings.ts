@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Réglages de l'exécution en conteneur
+ */
+export type ContainerSettings = { enabled: boolean, 
+/**
+ * Exécutable du moteur (`docker`, `podman`...) ; détecté si absent
+ */
+runtime: string | null, python_image: string, node_image: string, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContextComponent } from "./ContextComponent";
+
+/**
+ * Token usage of the context window
+ */
+export type ContextBreakdown = { n_ctx: number, 
+/**
+ * Tokens kept free for the reply
+ */
+reserved_output_tokens: number, components: Array<ContextComponent>, prompt_tokens: number, 
+/**
+ * Oldest tokens dropped from the prompt (0 = everything fits)
+ */
+truncated_tokens: number, 
+/**
+ * Counts approximated from the text length (no model loaded)
+ */
+estimated: boolean, };
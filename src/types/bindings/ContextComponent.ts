@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChatRole } from "./ChatRole";
+import type { ContextComponentKind } from "./ContextComponentKind";
+
+export type ContextComponent = { kind: ContextComponentKind, 
+/**
+ * Message the component belongs to (messages and their documents)
+ */
+message_id: string | null, role: ChatRole | null, tokens: number, 
+/**
+ * Tokens of this component dropped when the prompt is truncated
+ */
+truncated_tokens: number, };
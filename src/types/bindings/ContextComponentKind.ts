@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Part of the prompt
+ */
+export type ContextComponentKind = "system_prompt" | "memories" | "message" | "documents";
@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonValue } from "./serde_json/JsonValue";
+import type { Language } from "./Language";
+import type { Message } from "./Message";
+
+/**
+ * Session de conversation complète avec tous les messages
+ */
+export type ConversationSession = { id: string, title: string, created_at: string, updated_at: string, messages: Array<Message>, metadata: { [key in string]?: JsonValue }, 
+/**
+ * Profil d'agent associé à la conversation
+ */
+agent_id: string | null, 
+/**
+ * Espace de travail auquel la conversation appartient
+ */
+workspace_id: string | null, 
+/**
+ * Langue de la conversation (prompts système et génération du titre)
+ */
+language: Language | null, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Current model and the context size usable with it
+ */
+export type CurrentModel = { name: string | null, 
+/**
+ * Context size the model was trained with (None = no model loaded)
+ */
+trained_context: number | null, 
+/**
+ * Context size used for generations (of the session, when one is given)
+ */
+usable_context: number, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DocumentStatus } from "./DocumentStatus";
+
+/**
+ * A document attachment of the knowledge base, with its index status
+ */
+export type DocumentInfo = { attachment_id: string, file_name: string, mime_type: string, conversation_id: string, conversation_title: string, 
+/**
+ * Stored size in bytes
+ */
+size: number, 
+/**
+ * Length of the extracted text, in characters
+ */
+chars: number, status: DocumentStatus, chunks: number, 
+/**
+ * Tokens of the indexed chunks (None if they could not be counted)
+ */
+tokens: number | null, 
+/**
+ * File name of the model that computed the embeddings
+ */
+embedding_model: string | null, indexed_at: number | null, created_at: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where a document of the library stands with the retrieval index
+ */
+export type DocumentStatus = "inline" | "indexed" | "missing_embeddings" | "not_indexed";
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConfigEntry } from "./ConfigEntry";
+
+/**
+ * Configuration en vigueur, avec l'origine de chaque valeur
+ */
+export type EffectiveConfig = { 
+/**
+ * Fichier lu au démarrage (qu'il existe ou non)
+ */
+config_file: string | null, models_dir: ConfigEntry<string>, default_model: ConfigEntry<string>, mcp_port: ConfigEntry<number>, proxy: ConfigEntry<string>, log_level: ConfigEntry<string>, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EvalCheck } from "./EvalCheck";
+
+/**
+ * One prompt of a suite
+ */
+export type EvalCase = { name: string, prompt: string, 
+/**
+ * Replaces the agent's system prompt for this case
+ */
+system_prompt: string | null, 
+/**
+ * Every check must pass (no check = the model only has to answer)
+ */
+checks: Array<EvalCheck>, };
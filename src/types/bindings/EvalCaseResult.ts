@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Outcome of one case
+ */
+export type EvalCaseResult = { case_name: string, passed: boolean, latency_ms: number, tokens_generated: number, output: string, 
+/**
+ * Failed checks, or the generation error
+ */
+failures: Array<string>, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonValue } from "./serde_json/JsonValue";
+
+/**
+ * Condition a model output must satisfy
+ */
+export type EvalCheck = { "type": "contains", text: string, case_sensitive: boolean, } | { "type": "not_contains", text: string, case_sensitive: boolean, } | { "type": "regex", pattern: string, } | { "type": "json", schema: JsonValue | null, } | { "type": "json_field", pointer: string, value: JsonValue, };
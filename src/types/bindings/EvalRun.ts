@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EvalCaseResult } from "./EvalCaseResult";
+
+/**
+ * A suite run against one model, with its aggregate scores
+ */
+export type EvalRun = { id: string, suite_id: string, model: string, agent_id: string | null, passed: number, total: number, 
+/**
+ * Share of passed cases (0-1)
+ */
+score: number, avg_latency_ms: number, 
+/**
+ * Per-case results (not loaded when listing runs)
+ */
+results: Array<EvalCaseResult>, created_at: string, };
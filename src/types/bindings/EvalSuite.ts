@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EvalCase } from "./EvalCase";
+
+/**
+ * Named set of cases run against models to compare them
+ */
+export type EvalSuite = { id: string, name: string, description: string, cases: Array<EvalCase>, created_at: string, updated_at: string, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonValue } from "./serde_json/JsonValue";
+
+/**
+ * Description d'un événement pour la génération des types du frontend
+ */
+export type EventSchema = { name: string, description: string, schema: JsonValue, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * GGUF file information with quantization details
+ */
+export type GGUFFile = { filename: string, size: number, quantization: string | null, };
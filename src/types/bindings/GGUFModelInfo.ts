@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GGUFFile } from "./GGUFFile";
+
+/**
+ * GGUF model information with filtered files
+ */
+export type GGUFModelInfo = { repo_id: string, gguf_files: Array<GGUFFile>, downloads: number, likes: number, author: string, task: string | null, tags: Array<string>, last_modified: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * GGUF model metadata (without files, for search results)
+ */
+export type GGUFModelMetadata = { repo_id: string, downloads: number, likes: number, author: string, task: string | null, tags: Array<string>, last_modified: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Gated status - can be either boolean or string ("manual", "auto")
+ */
+export type GatedStatus = boolean | string;
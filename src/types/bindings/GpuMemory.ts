@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Memory of one GPU
+ */
+export type GpuMemory = { index: number, name: string, used_bytes: number, total_bytes: number, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Filters of a guardrail policy (everything disabled by default)
+ */
+export type GuardrailPolicy = { 
+/**
+ * Words or phrases replaced in replies (case insensitive)
+ */
+blocked_keywords: Array<string>, 
+/**
+ * Regular expressions replaced in replies
+ */
+blocked_patterns: Array<string>, 
+/**
+ * Mask email addresses and API keys
+ */
+redact_pii: boolean, 
+/**
+ * Maximum reply length, in characters
+ */
+max_chars: number | null, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GatedStatus } from "./GatedStatus";
+import type { ModelFile } from "./ModelFile";
+
+/**
+ * Detailed model information
+ */
+export type HFModelInfo = { modelId: string, author: string | null, sha: string, lastModified: string, private: boolean, disabled: boolean | null, gated: GatedStatus | null, tags: Array<string>, pipeline_tag: string | null, siblings: Array<ModelFile>, downloads: number | null, likes: number | null, library_name: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Matériel de la machine, pour interpréter les statistiques
+ */
+export type HardwareInfo = { physical_cores: number, logical_cores: number, gpu: string, };
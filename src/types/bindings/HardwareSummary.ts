@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GpuMemory } from "./GpuMemory";
+import type { RecommendedModel } from "./RecommendedModel";
+
+/**
+ * Matériel détecté et modèle adapté
+ */
+export type HardwareSummary = { physical_cores: number, logical_cores: number, total_memory_bytes: number | null, available_memory_bytes: number | null, gpus: Array<GpuMemory>, 
+/**
+ * Accélération GPU disponible dans cette version (CUDA, Metal)
+ */
+gpu_acceleration: boolean, gpu_info: string, recommended_model: RecommendedModel, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How a model file outside the models directory is imported
+ */
+export type ImportMode = "copy" | "link";
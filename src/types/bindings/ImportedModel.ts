@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A model file imported from elsewhere on the disk
+ */
+export type ImportedModel = { 
+/**
+ * File name in the models directory
+ */
+filename: string, source_path: string, 
+/**
+ * Symlinked rather than copied: the model disappears with its source
+ */
+linked: boolean, size_bytes: number, gguf_version: number, tensor_count: number, imported_at: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Réglages de la détection (`get_injection_settings`)
+ */
+export type InjectionSettings = { enabled: boolean, 
+/**
+ * Demande à l'utilisateur de confirmer un résultat d'outil signalé avant de l'utiliser
+ */
+require_confirmation: boolean, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ApiScope } from "./ApiScope";
+
+/**
+ * Jeton émis et sa valeur, à copier immédiatement
+ */
+export type IssuedApiToken = { value: string, id: string, name: string, scope: ApiScope, created_at: number, last_used_at: number | null, };
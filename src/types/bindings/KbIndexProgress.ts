@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Payload of the `kb-index-progress` event
+ */
+export type KbIndexProgress = { folder_id: string, 
+/**
+ * File being indexed (None on the final event)
+ */
+path: string | null, indexed: number, total: number, removed: number, done: boolean, error: string | null, };
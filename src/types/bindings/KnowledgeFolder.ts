@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A directory indexed for retrieval
+ */
+export type KnowledgeFolder = { id: string, path: string, 
+/**
+ * Reranker GGUF (file name in the models directory) rescoring the retrieved chunks
+ */
+reranker_model: string | null, files: number, chunks: number, last_indexed_at: number | null, created_at: number, };
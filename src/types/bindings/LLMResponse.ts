@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TokenLogprob } from "./TokenLogprob";
+import type { ToolCall } from "./ToolCall";
+
+/**
+ * LLM model response
+ */
+export type LLMResponse = { text: string, tool_calls: Array<ToolCall>, tokens_generated: number, 
+/**
+ * False when generation stopped at `max_tokens` before the end of the reply (EOS)
+ */
+done: boolean, 
+/**
+ * Tokens dropped from the context window by context shifting
+ */
+truncated_tokens: number, 
+/**
+ * Per-token log probabilities, when requested with `LLMConfig::logprobs`
+ */
+logprobs?: Array<TokenLogprob>, 
+/**
+ * Reasoning block captured by the post-processing (`<think>...</think>`)
+ */
+reasoning?: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Languages with localized prompts
+ */
+export type Language = "en" | "fr" | "es" | "de" | "it" | "pt";
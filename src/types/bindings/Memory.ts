@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A durable fact about the user or their projects
+ */
+export type Memory = { id: string, content: string, source_conversation_id: string | null, created_at: string, updated_at: string, };
@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Attachment } from "./Attachment";
+import type { JsonValue } from "./serde_json/JsonValue";
+import type { MessageRole } from "./MessageRole";
+
+/**
+ * Message dans une conversation
+ */
+export type Message = { id: string, role: MessageRole, content: string, timestamp: string, metadata: { [key in string]?: JsonValue }, 
+/**
+ * Outil appelé (message assistant) ou ayant produit le résultat (message tool)
+ */
+tool_name?: string, 
+/**
+ * Identifiant reliant le résultat d'un outil à son appel
+ */
+tool_call_id?: string, 
+/**
+ * Fichiers joints au message (images pour les modèles de vision)
+ */
+attachments?: Array<Attachment>, };
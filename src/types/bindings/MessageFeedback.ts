@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FeedbackRating } from "./FeedbackRating";
+
+/**
+ * Rating of one assistant message
+ */
+export type MessageFeedback = { message_id: number, rating: FeedbackRating, comment: string | null, created_at: string, };
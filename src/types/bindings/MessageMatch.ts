@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Message retrouvé par une recherche sémantique
+ */
+export type MessageMatch = { conversation_id: string, conversation_title: string, message_id: number, role: string, content: string, created_at: string, 
+/**
+ * Similarité cosinus avec la requête
+ */
+score: number, };
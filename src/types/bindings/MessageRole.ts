@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Rôle d'un message dans la conversation
+ */
+export type MessageRole = "system" | "user" | "assistant" | "tool";
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GatedStatus } from "./GatedStatus";
+
+/**
+ * Represents a model on Hugging Face
+ */
+export type Model = { modelId: string, author: string | null, downloads: number | null, likes: number | null, pipeline_tag: string | null, tags: Array<string>, private: boolean | null, gated: GatedStatus | null, lastModified: string | null, library_name: string | null, };
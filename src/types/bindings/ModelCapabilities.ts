@@ -0,0 +1,30 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Capability vector of a model, cached per model file
+ */
+export type ModelCapabilities = { 
+/**
+ * The chat template handles a system role
+ */
+system_prompt: boolean, 
+/**
+ * The model answered the probe with a well formed tool call
+ */
+tool_calls: boolean, 
+/**
+ * Fill-in-the-middle tokens are present in the vocabulary
+ */
+fim: boolean, 
+/**
+ * A vision projector (mmproj) is available next to the model
+ */
+vision: boolean, 
+/**
+ * The GGUF file embeds a chat template
+ */
+has_chat_template: boolean, 
+/**
+ * Unix timestamp of the probe
+ */
+probed_at: number, };
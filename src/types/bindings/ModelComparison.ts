@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ModelRun } from "./ModelRun";
+
+export type ModelComparison = { prompt: string, model_a: ModelRun, model_b: ModelRun, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ModelFileLfs } from "./ModelFileLfs";
+
+/**
+ * File information within a model repository
+ */
+export type ModelFile = { rfilename: string, size: number | null, lfs: ModelFileLfs | null, };
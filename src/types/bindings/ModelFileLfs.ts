@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * LFS (Large File Storage) information
+ */
+export type ModelFileLfs = { oid: string, size: number, pointer_size: number | null, };
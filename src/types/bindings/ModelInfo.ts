@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ModelInfo = { name: string, file_name: string, size_bytes: number, is_loaded: boolean, 
+/**
+ * Number of times the model was loaded
+ */
+load_count: number, last_used_at: string | null, favorite: boolean, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Réponse d'un modèle et ses temps d'exécution
+ */
+export type ModelRun = { model: string, text: string, tokens_generated: number, 
+/**
+ * Temps de chargement (0 si le modèle était déjà chargé)
+ */
+load_ms: number, generation_ms: number, tokens_per_second: number, };
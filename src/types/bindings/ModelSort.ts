@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Order of the model list (favorites always come first)
+ */
+export type ModelSort = "name" | "most_used" | "recently_used" | "size";
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Compact search result for type-ahead suggestions
+ */
+export type ModelSuggestion = { repo_id: string, downloads: number, };
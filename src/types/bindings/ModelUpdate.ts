@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Mise à jour disponible pour un modèle téléchargé depuis le Hub
+ */
+export type ModelUpdate = { filename: string, repo_id: string, revision: string, latest_revision: string, update_available: boolean, };
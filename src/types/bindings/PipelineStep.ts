@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonValue } from "./serde_json/JsonValue";
+
+/**
+ * Appel d'outil d'un pipeline
+ */
+export type PipelineStep = { 
+/**
+ * Identifiant pour `{{steps.id}}` (facultatif)
+ */
+id: string | null, tool: string, 
+/**
+ * Modèle des arguments de l'outil
+ */
+arguments: JsonValue, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ReasoningMode } from "./ReasoningMode";
+
+/**
+ * Steps of the post-processing pipeline
+ */
+export type PostProcessConfig = { reasoning: ReasoningMode, 
+/**
+ * Remove leaked template tokens and surrounding whitespace
+ */
+trim_artifacts: boolean, 
+/**
+ * Normalize line endings, blank lines and unterminated code fences
+ */
+normalize_markdown: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Statistics exposed to the UI
+ */
+export type PromptCacheStats = { entries: number, size_bytes: number, hits: number, misses: number, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Résultat (ou aperçu) d'un élagage
+ */
+export type PruneReport = { 
+/**
+ * Conversations supprimées au-delà de `max_conversations`
+ */
+deleted_conversations: Array<string>, 
+/**
+ * Messages plus anciens que `max_message_age_days`
+ */
+expired_messages: number, 
+/**
+ * Messages au-delà de `max_messages_per_conversation`
+ */
+trimmed_messages: number, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Request rate and retries of the Hugging Face client
+ */
+export type RateLimitConfig = { 
+/**
+ * Sustained request rate
+ */
+requests_per_second: number, 
+/**
+ * Requests that can be sent at once before the rate applies
+ */
+burst: number, 
+/**
+ * Retries of a rate-limited or unavailable request (0 = never retry)
+ */
+max_retries: number, 
+/**
+ * Delay before the first retry, doubled at each attempt
+ */
+initial_backoff_ms: number, max_backoff_ms: number, };
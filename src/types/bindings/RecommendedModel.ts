@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Downloadable GGUF model of the catalog
+ */
+export type RecommendedModel = { repo_id: string, filename: string, parameters: string, 
+/**
+ * Approximate size of the file
+ */
+size_bytes: number, };
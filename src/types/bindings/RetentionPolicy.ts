@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Limites de rétention (None = pas de limite)
+ */
+export type RetentionPolicy = { 
+/**
+ * Nombre de conversations conservées (les plus récemment modifiées)
+ */
+max_conversations: number | null, 
+/**
+ * Âge maximal des messages, en jours
+ */
+max_message_age_days: number | null, 
+/**
+ * Nombre de messages conservés par conversation (les plus récents)
+ */
+max_messages_per_conversation: number | null, };
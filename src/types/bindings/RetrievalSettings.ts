@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Weighting of the two rankings (`get_retrieval_settings`)
+ */
+export type RetrievalSettings = { 
+/**
+ * Share of the lexical ranking, from 0 (vector search only) to 1 (BM25 only)
+ */
+lexical_weight: number, };
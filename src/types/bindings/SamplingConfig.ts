@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { StopCondition } from "./StopCondition";
+
+/**
+ * Settings read for each generation, applied live without reloading the model
+ */
+export type SamplingConfig = { max_tokens: number, temperature: number, top_p: number, top_k: number, repeat_penalty: number, 
+/**
+ * Report token log probabilities with this many alternatives (None = disabled)
+ */
+logprobs: number | null, 
+/**
+ * End the reply early when one of these is met
+ */
+stop_conditions: Array<StopCondition>, };
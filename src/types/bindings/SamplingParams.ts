@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { StopCondition } from "./StopCondition";
+
+/**
+ * Sampling overrides applied to a single generation (agent profiles)
+ */
+export type SamplingParams = { temperature: number | null, top_p: number | null, top_k: number | null, repeat_penalty: number | null, max_tokens: number | null, 
+/**
+ * Number of alternatives reported per token (enables log probabilities)
+ */
+logprobs: number | null, 
+/**
+ * Replace the configured stop conditions
+ */
+stop_conditions: Array<StopCondition> | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Work done when a schedule fires
+ */
+export type ScheduledJob = { "type": "prompt", prompt: string, } | { "type": "task", goal: string, max_iterations: number | null, };
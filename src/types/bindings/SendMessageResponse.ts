@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Citation } from "./Citation";
+import type { Message } from "./Message";
+import type { TokenLogprob } from "./TokenLogprob";
+
+export type SendMessageResponse = { user_message: Message, assistant_message: Message, 
+/**
+ * Log probabilities of the reply tokens (when enabled with `set_logprobs`)
+ */
+logprobs?: Array<TokenLogprob>, 
+/**
+ * Document sources cited by the reply (`[n]` markers)
+ */
+citations: Array<Citation>, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SamplingParams } from "./SamplingParams";
+
+/**
+ * Template a conversation can be created from
+ */
+export type SessionPreset = { id: string, name: string, 
+/**
+ * Title of the created sessions; `{name}`, `{date}` and `{time}` are replaced
+ */
+title_pattern: string, system_prompt: string, 
+/**
+ * Model loaded when a session is created (None = keep the current one)
+ */
+model: string | null, 
+/**
+ * Sampling overrides of the created sessions
+ */
+sampling: SamplingParams, 
+/**
+ * Names of the tools attached to the created sessions
+ */
+tools: Array<string>, created_at: string, updated_at: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Session créée par `replay_session`
+ */
+export type SessionReplay = { session_id: string, source_session_id: string, model: string, 
+/**
+ * Tours utilisateur rejoués
+ */
+turns: number, tokens_generated: number, generation_ms: number, };
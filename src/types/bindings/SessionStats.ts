@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Totals of a session
+ */
+export type SessionStats = { session_id: string, generations: number, tokens_generated: number, inference_ms: number, 
+/**
+ * Tokens per second over the local generations
+ */
+tokens_per_second: number, estimated_energy_wh: number, 
+/**
+ * Models used in the session, most used first
+ */
+models: Array<string>, };
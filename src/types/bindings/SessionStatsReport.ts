@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HardwareInfo } from "./HardwareInfo";
+
+export type SessionStatsReport = { hardware: HardwareInfo, session_id: string, generations: number, tokens_generated: number, inference_ms: number, 
+/**
+ * Tokens per second over the local generations
+ */
+tokens_per_second: number, estimated_energy_wh: number, 
+/**
+ * Models used in the session, most used first
+ */
+models: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What a session is doing, for `get_session_status`
+ */
+export type SessionStatus = { "state": "idle" } | { "state": "queued", position: number, } | { "state": "generating" };
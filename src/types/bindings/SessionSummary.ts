@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Résumé d'une session (sans les messages) pour l'affichage dans la liste
+ */
+export type SessionSummary = { id: string, title: string, created_at: string, updated_at: string, };
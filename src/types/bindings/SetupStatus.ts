@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Avancement de l'assistant de premier lancement
+ */
+export type SetupStatus = { completed: boolean, 
+/**
+ * Modèles déjà présents dans le dossier des modèles
+ */
+installed_models: number, current_model: string | null, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Comportement au démarrage de l'application
+ */
+export type StartupSettings = { 
+/**
+ * Rouvrir la dernière session active
+ */
+restore_last_session: boolean, 
+/**
+ * Charger le dernier modèle utilisé
+ */
+load_last_model: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * When to end a reply early; the text that triggered it is kept
+ */
+export type StopCondition = { "kind": "sequence", text: string, } | { "kind": "after_line", marker: string, } | { "kind": "code_block_closed" };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Step of a model switch
+ */
+export type SwitchStage = "queued" | "unloading" | "loading" | "warming_up" | "completed" | "failed";
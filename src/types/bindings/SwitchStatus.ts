@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AppError } from "./AppError";
+import type { SwitchStage } from "./SwitchStage";
+
+/**
+ * Progress of one model switch, as sent to the frontend
+ */
+export type SwitchStatus = { task_id: string, model_name: string, stage: SwitchStage, error: AppError | null, started_at: string, updated_at: string, };
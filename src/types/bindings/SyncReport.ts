@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Résultat d'une passe de synchronisation
+ */
+export type SyncReport = { 
+/**
+ * Conversations copiées vers le dossier pour la première fois
+ */
+exported: number, 
+/**
+ * Conversations créées depuis le dossier
+ */
+imported: number, 
+/**
+ * Conversations modifiées des deux côtés puis fusionnées
+ */
+merged: number, 
+/**
+ * Suppressions appliquées ici ou transmises aux autres appareils
+ */
+deleted: number, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Réglages de la synchronisation
+ */
+export type SyncSettings = { 
+/**
+ * Dossier partagé (synchronisation désactivée si absent)
+ */
+directory: string | null, interval_secs: number, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GpuMemory } from "./GpuMemory";
+
+/**
+ * Snapshot of the resources used by the app and available on the machine
+ */
+export type SystemMetrics = { 
+/**
+ * Resident memory of this process
+ */
+process_rss_bytes: number | null, total_memory_bytes: number | null, 
+/**
+ * Memory that can be allocated without swapping
+ */
+available_memory_bytes: number | null, gpus: Array<GpuMemory>, 
+/**
+ * CPU usage over the sampling interval, all cores (0-100)
+ */
+cpu_usage_percent: number | null, 
+/**
+ * 1-minute load average
+ */
+load_average: number | null, logical_cores: number, };
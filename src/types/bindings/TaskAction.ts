@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonValue } from "./serde_json/JsonValue";
+
+/**
+ * Action chosen by the model at a step
+ */
+export type TaskAction = { "type": "tool", name: string, arguments: JsonValue, } | { "type": "final", answer: string, };
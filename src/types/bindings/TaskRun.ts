@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TaskStatus } from "./TaskStatus";
+import type { TaskStep } from "./TaskStep";
+
+/**
+ * A task run and its trace
+ */
+export type TaskRun = { id: string, goal: string, agent_id: string | null, status: TaskStatus, steps: Array<TaskStep>, 
+/**
+ * Final answer of the model
+ */
+result: string | null, error: string | null, iterations: number, tokens_used: number, created_at: string, updated_at: string, };
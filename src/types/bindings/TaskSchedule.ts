@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ScheduledJob } from "./ScheduledJob";
+
+/**
+ * A recurring job
+ */
+export type TaskSchedule = { id: string, name: string, 
+/**
+ * Standard 5-field cron expression, evaluated in local time (e.g. "0 8 * * *")
+ */
+cron: string, job: ScheduledJob, agent_id: string | null, 
+/**
+ * Dedicated conversation receiving the results (created on the first run)
+ */
+conversation_id: string | null, enabled: boolean, last_run_at: string | null, last_error: string | null, next_run_at: string | null, created_at: string, updated_at: string, };
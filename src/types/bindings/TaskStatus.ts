@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * State of a run
+ */
+export type TaskStatus = "running" | "completed" | "failed" | "iteration_limit_reached" | "budget_exceeded";
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TaskAction } from "./TaskAction";
+
+/**
+ * One step of the trace
+ */
+export type TaskStep = { 
+/**
+ * Step number (starting at 1)
+ */
+index: number, 
+/**
+ * Reasoning given by the model before acting
+ */
+thought: string | null, 
+/**
+ * None when the model response could not be parsed
+ */
+action: TaskAction | null, 
+/**
+ * Tool output or error fed back to the model
+ */
+observation: string | null, tokens: number, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ThreadSettings } from "./ThreadSettings";
+
+/**
+ * Thread settings and the detected CPU
+ */
+export type ThreadInfo = { settings: ThreadSettings, physical_cores: number, logical_cores: number, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Thread counts chosen by the user (None = auto-detected)
+ */
+export type ThreadSettings = { 
+/**
+ * Threads used while generating tokens
+ */
+n_threads: number | null, 
+/**
+ * Threads used while evaluating the prompt
+ */
+n_threads_batch: number | null, 
+/**
+ * Use fewer threads while the window is not focused
+ */
+efficiency_mode: boolean, };
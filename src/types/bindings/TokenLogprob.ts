@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TokenAlternative } from "./TokenAlternative";
+
+/**
+ * Log probability of a generated token and of its best alternatives
+ */
+export type TokenLogprob = { token: string, logprob: number, 
+/**
+ * Most likely tokens at this position, best first (may include the chosen one)
+ */
+top: Array<TokenAlternative>, };
@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonValue } from "./serde_json/JsonValue";
+
+/**
+ * Entrée du journal
+ */
+export type ToolAuditEntry = { id: number, 
+/**
+ * Session, exécution de tâche ou client MCP à l'origine de l'appel
+ */
+scope: string, 
+/**
+ * Origine du contenu (`tool:fetch_url`, `document:notes.pdf`, `knowledge_base:/docs`)
+ */
+source: string, 
+/**
+ * Nature de l'événement (`prompt_injection`)
+ */
+event: string, 
+/**
+ * `tagged`, `accepted` ou `rejected`
+ */
+decision: string, 
+/**
+ * Détails de l'événement (règles et extraits)
+ */
+details: JsonValue, created_at: number, };
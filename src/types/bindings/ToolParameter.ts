@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Paramètre d'un outil déclaré sans code (connecteur d'API, pipeline)
+ */
+export type ToolParameter = { name: string, description: string, required: boolean, };
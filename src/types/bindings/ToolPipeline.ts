@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PipelineStep } from "./PipelineStep";
+import type { ToolParameter } from "./ToolParameter";
+
+/**
+ * Pipeline nommé
+ */
+export type ToolPipeline = { id: string, 
+/**
+ * Nom de l'outil synthétique
+ */
+name: string, description: string, parameters: Array<ToolParameter>, steps: Array<PipelineStep>, created_at: string, updated_at: string, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Limites d'un outil (aucune par défaut)
+ */
+export type ToolPolicy = { max_calls_per_minute: number | null, 
+/**
+ * Durée maximale d'un appel, en secondes
+ */
+timeout_secs: number | null, 
+/**
+ * Taille maximale du résultat, en octets
+ */
+max_output_bytes: number | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What an export wrote
+ */
+export type TrainingExport = { examples: number, 
+/**
+ * Conversations without a user message and an answer
+ */
+skipped_empty: number, skipped_duplicates: number, };
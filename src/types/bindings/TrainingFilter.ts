@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Conversations to export (every conversation by default)
+ */
+export type TrainingFilter = { 
+/**
+ * Only these conversations (empty = all)
+ */
+conversation_ids: Array<string>, workspace_id: string | null, agent_id: string | null, 
+/**
+ * Conversations updated since this date
+ */
+since: string | null, 
+/**
+ * Conversations created before this date
+ */
+until: string | null, };
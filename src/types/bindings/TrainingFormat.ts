@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Line format of the dataset
+ */
+export type TrainingFormat = "openai" | "sharegpt";
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Transformations applied to the exported messages
+ */
+export type TrainingOptions = { strip_system_prompts: boolean, 
+/**
+ * Mask email addresses and API keys
+ */
+anonymize: boolean, 
+/**
+ * Skip conversations identical to one already exported
+ */
+dedupe: boolean, };
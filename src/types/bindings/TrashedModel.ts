@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A deleted model waiting in the trash
+ */
+export type TrashedModel = { 
+/**
+ * File name the model is restored to
+ */
+file_name: string, size_bytes: number, trashed_at: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A profile, as listed to the UI
+ */
+export type UserProfile = { name: string, created_at: string, 
+/**
+ * Profile the app is running with
+ */
+active: boolean, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A project directory with its default agent and document index
+ */
+export type Workspace = { id: string, name: string, 
+/**
+ * Directory file tools are limited to in this workspace's sessions
+ */
+root_path: string, 
+/**
+ * Agent used for sessions created without an explicit agent
+ */
+default_agent_id: string | null, 
+/**
+ * Knowledge-base folder whose excerpts are added to the workspace's sessions
+ */
+index_id: string | null, created_at: string, updated_at: string, };
@@ -0,0 +1,4 @@
+// Configuration in effect (`get_effective_config`): agents-rs.toml layered under the settings saved in the app
+export type { EffectiveConfig } from './bindings/EffectiveConfig';
+export type { ConfigEntry } from './bindings/ConfigEntry';
+export type { ConfigSource } from './bindings/ConfigSource';
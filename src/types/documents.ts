@@ -0,0 +1,6 @@
+// Document library (`list_documents`, `delete_document`, `reindex_document`): document attachments and their index status
+export type { DocumentInfo } from './bindings/DocumentInfo';
+export type { DocumentStatus } from './bindings/DocumentStatus';
+
+// Hybrid retrieval weighting (`get_retrieval_settings`, `set_retrieval_settings`)
+export type { RetrievalSettings } from './bindings/RetrievalSettings';
@@ -0,0 +1,61 @@
+// Evaluation suites (`save_eval_suite`, `run_eval_suite`)
+
+// Condition a model answer must satisfy
+export type EvalCheck =
+  | { type: 'contains'; text: string; case_sensitive?: boolean }
+  | { type: 'not_contains'; text: string; case_sensitive?: boolean }
+  | { type: 'regex'; pattern: string }
+  // The answer is JSON, optionally matching a JSON Schema
+  | { type: 'json'; schema?: Record<string, any> | null }
+  // The JSON answer has `value` at `pointer` (e.g. `/answer/0`)
+  | { type: 'json_field'; pointer: string; value: any };
+
+export interface EvalCase {
+  name: string;
+  prompt: string;
+  system_prompt?: string | null;
+  checks: EvalCheck[];
+}
+
+export interface EvalSuite {
+  id: string;
+  name: string;
+  description: string;
+  cases: EvalCase[];
+  created_at: string;
+  updated_at: string;
+}
+
+export interface EvalCaseResult {
+  case_name: string;
+  passed: boolean;
+  latency_ms: number;
+  tokens_generated: number;
+  output: string;
+  // Failed checks, or the generation error
+  failures: string[];
+}
+
+// One suite run against one model; `results` is empty in `list_eval_runs`
+export interface EvalRun {
+  id: string;
+  suite_id: string;
+  model: string;
+  agent_id: string | null;
+  passed: number;
+  total: number;
+  score: number;
+  avg_latency_ms: number;
+  results: EvalCaseResult[];
+  created_at: string;
+}
+
+// Payload of the `eval-progress` event
+export interface EvalProgress {
+  suite_id: string;
+  model: string;
+  case_name: string;
+  completed: number;
+  total: number;
+  passed: boolean;
+}
@@ -0,0 +1,4 @@
+// Events emitted by the backend (`list_event_schemas`)
+
+// Name passed to `listen()`, with the JSON Schema of the payload
+export type { EventSchema } from './bindings/EventSchema';
@@ -38,6 +38,8 @@ export interface HFModelInfo {
   library_name?: string;
 }
 
+export type { ModelSuggestion as HFModelSuggestion } from './bindings/ModelSuggestion';
+
 export interface HFSearchParams {
   search?: string;
   author?: string;
@@ -49,3 +51,12 @@ export interface HFSearchParams {
   limit?: number;
   full?: boolean;
 }
+
+export type { RateLimitConfig as HFRateLimitConfig } from './bindings/RateLimitConfig';
+
+export interface HFConnectionSettings {
+  endpoint?: string | null;
+  proxy?: string | null;
+}
+
+export type { ModelUpdate as HFModelUpdate } from './bindings/ModelUpdate';
@@ -4,5 +4,14 @@ export type * from './llm';
 export type * from './tool';
 export type * from './huggingface';
 export type * from './gguf';
+export type * from './eval';
+export type * from './events';
+export type * from './config';
+export type * from './profiles';
+export type * from './sharing';
+export type * from './sync';
+export type * from './tokens';
+export type * from './documents';
+export type * from './knowledge';
 export * from './paths';
 
@@ -0,0 +1,3 @@
+// Knowledge-base folders (`add_knowledge_folder`, `list_knowledge_folders`, `remove_knowledge_folder`, `reindex_knowledge_folder`, `set_knowledge_reranker`) and the `kb-index-progress` event
+export type { KnowledgeFolder } from './bindings/KnowledgeFolder';
+export type { KbIndexProgress } from './bindings/KbIndexProgress';
@@ -1,3 +1,5 @@
+import type { SwitchStage } from './bindings/SwitchStage';
+
 // LLM configuration types
 export interface LLMConfig {
   model_path?: string;
@@ -8,10 +10,81 @@ export interface LLMConfig {
   max_tokens?: number;
 }
 
+// Sampling settings, updated live with `update_sampling_config`
+export type { SamplingConfig } from './bindings/SamplingConfig';
+
+// Ends a reply early (per agent through its sampling overrides)
+export type { StopCondition } from './bindings/StopCondition';
+
+// What to do with `<think>...</think>` blocks of reasoning models
+export type { ReasoningMode } from './bindings/ReasoningMode';
+
+// Cleanup of local replies, updated with `set_postprocess_config`
+export type { PostProcessConfig } from './bindings/PostProcessConfig';
+
+// Reply filters, global (`set_guardrail_policy`) or per agent
+export type { GuardrailPolicy } from './bindings/GuardrailPolicy';
+
+export type GuardrailRule = 'keyword' | 'pattern' | 'email' | 'secret_key' | 'max_length';
+
+// Payload of the `guardrail-triggered` event
+export interface GuardrailTriggeredEvent {
+  session_id: string;
+  triggers: { rule: GuardrailRule; count: number }[];
+}
+
 // Model types
-export interface ModelInfo {
-  name: string;
-  file_name: string;
-  size_bytes: number;
-  is_loaded: boolean;
+export type { ModelInfo } from './bindings/ModelInfo';
+export type { LLMResponse } from './bindings/LLMResponse';
+export type { ToolCall } from './bindings/ToolCall';
+export type { AppError } from './bindings/AppError';
+
+// Order of list_models (favorites always come first)
+export type { ModelSort } from './bindings/ModelSort';
+
+// Local GGUF file brought in with import_model
+export type { ImportMode } from './bindings/ImportMode';
+export type { ImportedModel } from './bindings/ImportedModel';
+
+// Deleted model waiting in the trash (restore_model)
+export type { TrashedModel } from './bindings/TrashedModel';
+
+// Embedded llama.cpp backend (`get_backend_info`). Models in a newer GGUF
+// format fail with a `backend_upgrade_required` error
+export interface BackendInfo {
+  bindings_version: string;
+  features: ('cuda' | 'metal' | 'vulkan')[];
+  gguf_versions: { min: number; max: number };
+  max_devices: number;
+  mmap_supported: boolean;
+  mlock_supported: boolean;
+}
+
+// Memory of one NVIDIA GPU (reported by nvidia-smi)
+export type { GpuMemory } from './bindings/GpuMemory';
+
+// RAM/VRAM/CPU usage (`get_system_metrics`, emitted on "system-metrics").
+// null = not reported on this platform
+export type { SystemMetrics } from './bindings/SystemMetrics';
+
+// Starter model picked for the available memory
+export type { RecommendedModel } from './bindings/RecommendedModel';
+
+// First-run setup (`get_hardware_summary`, `get_setup_status`)
+export type { HardwareSummary } from './bindings/HardwareSummary';
+export type { SetupStatus } from './bindings/SetupStatus';
+
+// Model switch progress (emitted on "model-switch-progress")
+export type { SwitchStage } from './bindings/SwitchStage';
+
+export interface SwitchStatus {
+  task_id: string;
+  model_name: string;
+  stage: SwitchStage;
+  error: { kind: string; message?: string } | null;
+  started_at: string;
+  updated_at: string;
 }
+
+// Result of `import_profile` (settings, agents and session presets written)
+export type { ProfileImport } from './bindings/ProfileImport';
@@ -1,30 +1,106 @@
-// Message types
-export interface Message {
-  id: string;
-  role: 'user' | 'assistant' | 'system' | 'tool';
-  content: string;
-  timestamp: string; // ISO string from backend
-  metadata?: Record<string, any>;
+// Message types, generated from the Rust structs (src-tauri, `cargo test`)
+// Message metadata: `reasoning`: captured <think> block of the reply, `incomplete`: partial reply kept after a generation failure, `truncated`: reply stopped by max_tokens, `citations`: document sources cited by the reply (Citation[])
+import type { StopCondition } from './llm';
+export type { Message } from './bindings/Message';
+export type { MessageRole } from './bindings/MessageRole';
+export type { Attachment } from './bindings/Attachment';
+export type { AttachmentKind } from './bindings/AttachmentKind';
+export type { SessionSummary } from './bindings/SessionSummary';
+export type { ConversationSession } from './bindings/ConversationSession';
+export type { Language } from './bindings/Language';
+export type { SendMessageResponse } from './bindings/SendMessageResponse';
+export type { Citation } from './bindings/Citation';
+export type { TokenLogprob } from './bindings/TokenLogprob';
+export type { TokenAlternative } from './bindings/TokenAlternative';
+
+// Chunk of a streamed reply (`llm-token` event)
+export interface TokenEvent {
+  session_id: string;
+  chunk: string;
 }
 
-// Session summary for listing (without messages)
-export interface SessionSummary {
-  id: string;
-  title: string;
-  created_at: string; 
-  updated_at: string; 
+// Reasoning of a streamed reply (`llm-reasoning` event): start opens the thinking panel, end collapses it
+export interface ReasoningEvent {
+  session_id: string;
+  phase: 'start' | 'delta' | 'end';
+  chunk: string | null;
+}
+
+// Prompt too long for the model (`context-overflow` event), sent with the `context_overflow` error
+export interface ContextOverflowEvent {
+  session_id: string;
+  prompt_tokens: number;
+  n_ctx: number;
+  suggestion: string;
+}
+
+// Token usage of the next prompt of a session (`get_context_breakdown`), in prompt order
+export type { ContextComponentKind } from './bindings/ContextComponentKind';
+export type { ContextComponent } from './bindings/ContextComponent';
+export type { ContextBreakdown } from './bindings/ContextBreakdown';
+
+// File sent with a message, base64 encoded
+export type { AttachmentUpload } from './bindings/AttachmentUpload';
+
+// Past message found by semantic search
+export type { MessageMatch } from './bindings/MessageMatch';
+
+// Rating of an assistant message (`rate_message`, keyed by the numeric message id)
+export type { FeedbackRating } from './bindings/FeedbackRating';
+export type { MessageFeedback } from './bindings/MessageFeedback';
+
+// Fine-tuning dataset export (`export_training_data`)
+export type { TrainingFormat } from './bindings/TrainingFormat';
+
+// Conversations to export; empty fields select everything
+export interface TrainingFilter {
+  conversation_ids?: string[];
+  workspace_id?: string | null;
+  agent_id?: string | null;
+  since?: string | null;
+  until?: string | null;
 }
 
-// Full conversation session with all messages
-export interface ConversationSession {
+export type { TrainingOptions } from './bindings/TrainingOptions';
+export type { TrainingExport } from './bindings/TrainingExport';
+
+// Session created by `replay_session` (progress: `replay-progress` events)
+export type { SessionReplay } from './bindings/SessionReplay';
+
+// Template for one-click sessions (`create_session_from_preset`)
+export interface SessionPreset {
   id: string;
-  title: string;
-  created_at: string; 
-  updated_at: string; 
-  messages: Message[];
-  metadata?: Record<string, any>;
+  name: string;
+  // `{name}`, `{date}` and `{time}` are replaced
+  title_pattern: string;
+  system_prompt: string;
+  model: string | null;
+  sampling: {
+    temperature?: number | null;
+    top_p?: number | null;
+    top_k?: number | null;
+    repeat_penalty?: number | null;
+    max_tokens?: number | null;
+    stop_conditions?: StopCondition[] | null;
+  };
+  tools: string[];
+  created_at: string;
+  updated_at: string;
 }
 
+// Startup behavior, from `get_startup_settings`
+export type { StartupSettings } from './bindings/StartupSettings';
+
+// Payload of the `startup-restored` event (null when disabled or unavailable)
+export interface StartupRestoredEvent {
+  session_id: string | null;
+  model: string | null;
+  model_error: { kind: string; message?: string } | null;
+}
+
+// Activity of a session, from `get_session_status`
+export type { SessionStatus } from './bindings/SessionStatus';
+
 // Legacy Session type for compatibility
 export interface Session {
   id: string;
@@ -0,0 +1,2 @@
+// User profiles (`list_profiles`, `create_profile`, `switch_profile`): one database per profile, models shared
+export type { UserProfile } from './bindings/UserProfile';
@@ -0,0 +1,2 @@
+// Conversation sharing (`share_session`, `import_shared_session`): self-contained HTML page or encrypted .agentchat file
+export type { ShareFormat } from './bindings/ShareFormat';
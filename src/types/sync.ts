@@ -0,0 +1,3 @@
+// Folder sync (`get_sync_settings`, `set_sync_settings`, `sync_now`): conversations mirrored to a shared directory
+export type { SyncSettings } from './bindings/SyncSettings';
+export type { SyncReport } from './bindings/SyncReport';
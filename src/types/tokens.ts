@@ -0,0 +1,4 @@
+// Local server tokens (`create_api_token`, `list_api_tokens`, `revoke_api_token`): chat-only, read-only or admin
+export type { ApiScope } from './bindings/ApiScope';
+export type { ApiToken } from './bindings/ApiToken';
+export type { IssuedApiToken } from './bindings/IssuedApiToken';
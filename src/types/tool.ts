@@ -1,6 +1,61 @@
+import type { ToolParameter } from './bindings/ToolParameter';
+
 // Tool types
 export interface Tool {
   name: string;
   description: string;
   parameters?: Record<string, any>;
 }
+
+// Payload of the "tool-approval-request" event
+export interface ToolApprovalRequest {
+  call_id: string;
+  scope: string;
+  tool_name: string;
+  arguments: Record<string, any>;
+  // Set when a suspicious result is awaiting confirmation, e.g. "prompt_injection: ignore_instructions"
+  reason?: string;
+}
+
+// Prompt-injection screening of tool results and documents (`set_injection_settings`)
+export type { InjectionSettings } from './bindings/InjectionSettings';
+
+// Execution limits of a tool (`set_tool_policy`), null = unlimited
+export type { ToolPolicy } from './bindings/ToolPolicy';
+
+// Payload of the "tool-output" event: partial output of a running tool
+export interface ToolOutputChunk {
+  scope: string;
+  tool_name: string;
+  text: string;
+}
+
+// Parameter of a declared tool (API connector, pipeline)
+export type { ToolParameter } from './bindings/ToolParameter';
+
+// Declarative HTTP tool (`save_api_connector`). Templates: `{{param}}` and `{{secret.key}}`
+export interface ApiConnector {
+  name: string;
+  description: string;
+  method: 'GET' | 'POST' | 'PUT' | 'PATCH' | 'DELETE';
+  base_url: string;
+  path: string;
+  query: Record<string, string>;
+  headers: Record<string, string>;
+  body: any | null;
+  parameters: ToolParameter[];
+  // JSON path of the result, e.g. "$.data.items[0].name"
+  result_path: string | null;
+  // null = confirmation for every method but GET
+  requires_approval: boolean | null;
+}
+
+// Ephemeral container execution of run_code (`set_container_settings` returns the
+// runtime in use, null when falling back to the native sandbox)
+export type { ContainerSettings } from './bindings/ContainerSettings';
+
+// Tool pipeline step. Argument templates: `{{input.name}}`, `{{previous}}`, `{{steps.id}}`
+export type { PipelineStep } from './bindings/PipelineStep';
+
+// Named chain of tool calls exposed as a single tool (`run_pipeline` from a chat)
+export type { ToolPipeline } from './bindings/ToolPipeline';